@@ -0,0 +1,45 @@
+// Parser for the ##lxe[...] lines written by `lxe build --ci-output`
+
+class LxeServiceMessages implements Serializable {
+    static final def LINE = ~/^##lxe\[(\w+)((?:\s+\w+='(?:[^'|]|\|.)*')*)\s*\]$/
+    static final def ATTRIBUTE = ~/(\w+)='((?:[^'|]|\|.)*)'/
+
+    /** Parse one log line; returns [name: ..., attributes: [...]] or null */
+    static Map parse(String line) {
+        def match = LINE.matcher(line.trim())
+        if (!match.matches()) {
+            return null
+        }
+        def attributes = [:]
+        def attrMatch = ATTRIBUTE.matcher(match.group(2))
+        while (attrMatch.find()) {
+            attributes[attrMatch.group(1)] = unescape(attrMatch.group(2))
+        }
+        return [name: match.group(1), attributes: attributes]
+    }
+
+    static String unescape(String value) {
+        def out = new StringBuilder()
+        for (int i = 0; i < value.length(); i++) {
+            def c = value.charAt(i)
+            if (c == '|' && i + 1 < value.length()) {
+                def next = value.charAt(++i)
+                switch (next) {
+                    case 'n': out.append('\n'); break
+                    case 'r': out.append('\r'); break
+                    default: out.append(next) // ' | [ ]
+                }
+            } else {
+                out.append(c)
+            }
+        }
+        return out.toString()
+    }
+
+    /** All messages in a build log */
+    static List<Map> parseLog(String log) {
+        return log.readLines().collect { parse(it) }.findAll { it != null }
+    }
+}
+
+return LxeServiceMessages
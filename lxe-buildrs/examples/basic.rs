@@ -0,0 +1,24 @@
+//! Build the package for an lxe.toml
+//!
+//! ```bash
+//! cargo run -p lxe-build-rs --example basic -- path/to/lxe.toml
+//! ```
+
+use lxe_build_rs::BuildPlan;
+
+fn main() -> anyhow::Result<()> {
+    let config = std::env::args().nth(1).unwrap_or_else(|| "lxe.toml".to_string());
+    
+    let plan = BuildPlan::from_config(&config)?;
+    println!("Packaging {} v{}...", plan.config().package.name, plan.config().package.version);
+    
+    let result = plan.run()?;
+    println!(
+        "Built {} ({:.1} MB) in {:.1}s",
+        result.output_path.display(),
+        result.size_bytes as f64 / 1024.0 / 1024.0,
+        result.duration.as_secs_f64(),
+    );
+    
+    Ok(())
+}
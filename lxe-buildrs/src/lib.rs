@@ -0,0 +1,169 @@
+//! LXE Packaging for Rust Build Tooling
+//!
+//! Builds `.lxe` packages from an `lxe.toml` using `lxe-common` directly, so
+//! a `cargo xtask dist` task works without the `lxe` CLI installed. Only the
+//! runtime binary is needed: set `[runtime] path` in lxe.toml, or install it
+//! once with `lxe runtime download`.
+//!
+//! ```no_run
+//! let result = lxe_build_rs::BuildPlan::from_config("lxe.toml")?.run()?;
+//! println!("{} ({} bytes)", result.output_path.display(), result.size_bytes);
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context, Result};
+use lxe_common::builder::{self, BuildHints};
+use lxe_common::config::LxeConfig;
+use lxe_common::hooks::{self, BuildHook};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// A package build described by an `lxe.toml`
+/// 
+/// Relative paths in the config resolve against the config file's directory.
+pub struct BuildPlan {
+    config: LxeConfig,
+    base_dir: PathBuf,
+    run_script: bool,
+}
+
+/// Outcome of a successful build
+#[derive(Debug, Clone)]
+pub struct BuildResult {
+    pub output_path: PathBuf,
+    pub size_bytes: u64,
+    pub duration: Duration,
+}
+
+impl BuildPlan {
+    /// Load the plan from an `lxe.toml`
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let config = LxeConfig::from_file(path)?;
+        let base_dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => std::env::current_dir()?,
+        };
+        
+        Ok(Self { config, base_dir, run_script: true })
+    }
+    
+    /// Skip the `[build] script` (e.g., when the task already produced the input)
+    pub fn skip_script(mut self) -> Self {
+        self.run_script = false;
+        self
+    }
+    
+    pub fn config(&self) -> &LxeConfig {
+        &self.config
+    }
+    
+    /// Where the package will be written
+    pub fn output_path(&self) -> PathBuf {
+        self.config.output_path(&self.base_dir)
+    }
+    
+    /// Run the build script (if any) and assemble the package, with the same
+    /// steps as `lxe build`: `[build.hooks]`, SVG icon rendering and
+    /// `generate_metainfo` included
+    /// 
+    /// Unlike `lxe build`, a configured signing key that doesn't exist is an
+    /// error rather than a warning, so CI never ships an unsigned package by
+    /// accident.
+    pub fn run(&self) -> Result<BuildResult> {
+        let config = &self.config;
+        let result = (|| {
+            hooks::run(BuildHook::PreBuild, config, &self.base_dir, &[], false)?;
+            let result = self.build()?;
+            let output = result.output_path.to_string_lossy();
+            hooks::run(BuildHook::PostBuild, config, &self.base_dir, &[("LXE_OUTPUT_PATH", &output)], false)?;
+            Ok(result)
+        })();
+        
+        if let Err(ref e) = result {
+            let message = format!("{:#}", e);
+            hooks::run(BuildHook::OnBuildFailed, config, &self.base_dir, &[("LXE_ERROR", &message)], false).ok();
+        }
+        result
+    }
+    
+    /// The build steps between the hooks
+    fn build(&self) -> Result<BuildResult> {
+        let started = Instant::now();
+        let config = &self.config;
+        
+        if let Some(script) = config.build.script.as_ref().filter(|_| self.run_script) {
+            config.validate_script(&self.base_dir)?;
+            let status = Command::new("sh")
+                .arg("-c")
+                .arg(script)
+                .current_dir(config.script_dir(&self.base_dir))
+                .status()
+                .context("Failed to run build script")?;
+            
+            if !status.success() {
+                anyhow::bail!("Build script failed with exit code: {:?}", status.code());
+            }
+        }
+        config.validate(&self.base_dir)?;
+        
+        let input_path = config.input_path(&self.base_dir);
+        let exec_path = input_path.join(&config.package.executable);
+        if !exec_path.exists() {
+            anyhow::bail!(
+                "Executable not found: {}\n\
+                 Make sure '{}' exists in '{}'",
+                exec_path.display(),
+                config.package.executable,
+                input_path.display()
+            );
+        }
+        
+        let svg_icon = builder::render_svg_icon(config, &input_path)?;
+        let tar_data = if config.build.strip_symbols {
+            let stripped = lxe_common::strip::strip_elf_files(&input_path, &Default::default())?;
+            builder::create_tar_archive_with(&input_path, &stripped.overrides, config.build.reproducible)?
+        } else {
+            builder::create_tar_archive_with(&input_path, &Default::default(), config.build.reproducible)?
+        };
+        let compressed = builder::compress(&tar_data, &config.build.compressor())?;
+        let checksum = lxe_common::payload::calculate_checksum(&compressed, config.build.checksum_algorithm);
+        
+        let mut metadata = builder::package_metadata(
+            config,
+            &self.base_dir,
+            tar_data.len() as u64,
+            &checksum,
+            &BuildHints::default(),
+        );
+        if svg_icon.package_icon != config.package.icon {
+            metadata["icon"] = serde_json::json!(svg_icon.package_icon);
+        }
+        
+        if let Some(key_path) = config.key_path(&self.base_dir) {
+            if !key_path.exists() {
+                anyhow::bail!("Signing key not found: {}", key_path.display());
+            }
+            let key = lxe_common::signing::KeyBackend::from_key_file(&key_path)?;
+            builder::sign_metadata(&mut metadata, &key, &checksum)?;
+        }
+        
+        let runtime = builder::find_runtime_binary(config.runtime_path(&self.base_dir).as_deref())?;
+        let output_path = self.output_path();
+        let metadata_json = serde_json::to_vec(&metadata)?;
+        let size_bytes = builder::write_package(&output_path, &runtime, &metadata_json, &checksum, &compressed)?;
+        builder::write_metainfo(config, &metadata, &output_path)?;
+        
+        Ok(BuildResult {
+            output_path,
+            size_bytes,
+            duration: started.elapsed(),
+        })
+    }
+}
+
+/// Build the package described by `config_path` (shorthand for [`BuildPlan`])
+pub fn build_lxe_package(config_path: impl AsRef<Path>) -> Result<BuildResult> {
+    BuildPlan::from_config(config_path)?.run()
+}
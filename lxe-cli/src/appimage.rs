@@ -0,0 +1,327 @@
+//! AppImage Migration (`lxe migrate-appimage`)
+//!
+//! Converts a catalog of AppImages to LXE packages. Each AppImage is
+//! unpacked with its own `--appimage-extract` (so the AppImage's runtime is
+//! executed; only migrate AppImages you trust), and the package is
+//! described from what it ships:
+//!
+//! - the `.desktop` file at the top of `squashfs-root/` for the name,
+//!   categories, icon and terminal flag
+//! - AppStream metainfo (`usr/share/metainfo/*.xml`) for the ID, summary
+//!   and version, when present
+//!
+//! `AppRun` stays the entry point, so the environment the AppImage sets up
+//! for itself is preserved. The generated `lxe.toml` is built by a child
+//! `lxe build`, like workspace members.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Directory `--appimage-extract` unpacks into
+const EXTRACT_DIR: &str = "squashfs-root";
+
+/// Where AppStream metadata lives inside an AppImage
+const METAINFO_DIRS: &[&str] = &["usr/share/metainfo", "usr/share/appdata"];
+
+/// Package fields read from an extracted AppImage
+#[derive(Debug, Default, PartialEq)]
+pub struct AppImageInfo {
+    pub name: String,
+    pub app_id: String,
+    pub version: String,
+    /// Entry point, relative to the extracted root
+    pub executable: String,
+    pub icon: Option<String>,
+    pub description: Option<String>,
+    pub categories: Vec<String>,
+    pub terminal: bool,
+}
+
+/// `*.AppImage` files in `dir`, sorted by name
+pub fn find_appimages(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut found: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| path.extension().is_some_and(|e| e.eq_ignore_ascii_case("appimage")))
+        .collect();
+    found.sort();
+    Ok(found)
+}
+
+/// Convert one AppImage and return the package path
+///
+/// Works in a temporary directory, which is removed afterwards.
+pub fn convert(appimage: &Path, output_dir: &Path, key: Option<&Path>, lxe_exe: &Path) -> Result<PathBuf> {
+    let work = tempfile::Builder::new().prefix("lxe-appimage-").tempdir()?;
+    let root = extract(appimage, work.path())?;
+
+    let stem = appimage.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let info = read_info(&root, &stem)?;
+    let output = output_dir.join(format!("{}.lxe", stem));
+    fs::write(work.path().join("lxe.toml"), generate_config(&info, &output, key)?)?;
+
+    let result = Command::new(lxe_exe)
+        .args(["build", "--silent"])
+        .current_dir(work.path())
+        .stdout(Stdio::null())
+        .output()
+        .context("Failed to run lxe build")?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let reason = stderr.lines()
+            .find_map(|line| line.strip_prefix("Error: "))
+            .or_else(|| stderr.lines().last())
+            .unwrap_or("build failed");
+        anyhow::bail!("{}", reason);
+    }
+    Ok(output)
+}
+
+/// Unpack `appimage` into `work_dir/squashfs-root`
+///
+/// A copy is extracted so the original doesn't need to be executable.
+fn extract(appimage: &Path, work_dir: &Path) -> Result<PathBuf> {
+    let copy = work_dir.join("source.AppImage");
+    fs::copy(appimage, &copy)
+        .with_context(|| format!("Failed to copy {}", appimage.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&copy, fs::Permissions::from_mode(0o755))?;
+    }
+
+    let status = Command::new(&copy)
+        .arg("--appimage-extract")
+        .current_dir(work_dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("Failed to run the AppImage (is it an AppImage for this architecture?)")?;
+    let root = work_dir.join(EXTRACT_DIR);
+    if !status.success() || !root.is_dir() {
+        anyhow::bail!("--appimage-extract failed (exit code {:?})", status.code());
+    }
+    fs::remove_file(&copy)?;
+    Ok(root)
+}
+
+/// Describe the package from the desktop entry and AppStream metainfo
+fn read_info(root: &Path, file_stem: &str) -> Result<AppImageInfo> {
+    let desktop_path = fs::read_dir(root)?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| path.extension().is_some_and(|e| e == "desktop"))
+        .ok_or_else(|| anyhow::anyhow!("No .desktop file at the top of the AppImage"))?;
+    let desktop = parse_desktop_entry(&fs::read_to_string(&desktop_path)?);
+    let metainfo = METAINFO_DIRS.iter()
+        .filter_map(|dir| fs::read_dir(root.join(dir)).ok())
+        .flat_map(|entries| entries.flatten())
+        .find(|entry| entry.path().extension().is_some_and(|e| e == "xml"))
+        .and_then(|entry| fs::read_to_string(entry.path()).ok())
+        .unwrap_or_default();
+
+    let name = desktop.get("Name").cloned()
+        .or_else(|| xml_text(&metainfo, "name"))
+        .unwrap_or_else(|| file_stem.to_string());
+    let desktop_id = desktop_path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+    let app_id = xml_text(&metainfo, "id")
+        .map(|id| id.trim_end_matches(".desktop").to_string())
+        .into_iter()
+        .chain([desktop_id])
+        .find(|id| id.matches('.').count() >= 2)
+        .unwrap_or_else(|| format!("io.appimage.{}", sanitize_id_segment(&name)));
+    let version = xml_attr(&metainfo, "release", "version")
+        .or_else(|| desktop.get("X-AppImage-Version").cloned())
+        .unwrap_or_else(|| "1.0.0".to_string());
+
+    // AppRun sets up the environment; Exec is only a fallback
+    let executable = if root.join("AppRun").exists() {
+        "AppRun".to_string()
+    } else {
+        let exec = desktop.get("Exec").and_then(|e| e.split_whitespace().next())
+            .ok_or_else(|| anyhow::anyhow!("No AppRun and no Exec= in the desktop entry"))?;
+        ["usr/bin", ""].iter()
+            .map(|dir| Path::new(dir).join(exec))
+            .find(|path| root.join(path).is_file())
+            .ok_or_else(|| anyhow::anyhow!("Executable '{}' not found in the AppImage", exec))?
+            .to_string_lossy()
+            .to_string()
+    };
+
+    let icon = desktop.get("Icon").and_then(|icon| {
+        [format!("{}.png", icon), format!("{}.svg", icon), icon.clone()]
+            .into_iter()
+            .find(|file| root.join(file).is_file())
+    });
+
+    Ok(AppImageInfo {
+        name,
+        app_id,
+        version,
+        executable,
+        icon,
+        description: xml_text(&metainfo, "summary").or_else(|| desktop.get("Comment").cloned()),
+        categories: desktop.get("Categories")
+            .map(|c| c.split(';').filter(|c| !c.is_empty()).map(String::from).collect())
+            .unwrap_or_default(),
+        terminal: desktop.get("Terminal").is_some_and(|t| t == "true"),
+    })
+}
+
+/// `lxe.toml` packaging `squashfs-root` as-is
+fn generate_config(info: &AppImageInfo, output: &Path, key: Option<&Path>) -> Result<String> {
+    let mut package = toml::Table::new();
+    package.insert("name".into(), info.name.clone().into());
+    package.insert("id".into(), info.app_id.clone().into());
+    package.insert("version".into(), info.version.clone().into());
+    package.insert("executable".into(), info.executable.clone().into());
+    if let Some(ref icon) = info.icon {
+        package.insert("icon".into(), icon.clone().into());
+    }
+    if let Some(ref description) = info.description {
+        package.insert("description".into(), description.clone().into());
+    }
+    package.insert("categories".into(), info.categories.clone().into());
+    package.insert("terminal".into(), info.terminal.into());
+
+    let mut build = toml::Table::new();
+    build.insert("input".into(), format!("./{}", EXTRACT_DIR).into());
+    build.insert("output".into(), output.to_string_lossy().to_string().into());
+
+    let mut config = toml::Table::new();
+    config.insert("package".into(), package.into());
+    config.insert("build".into(), build.into());
+    if let Some(key) = key {
+        let mut security = toml::Table::new();
+        security.insert("key".into(), key.to_string_lossy().to_string().into());
+        config.insert("security".into(), security.into());
+    }
+    Ok(format!("# Generated by lxe migrate-appimage\n\n{}", toml::to_string(&config)?))
+}
+
+/// Unlocalized keys of the `[Desktop Entry]` section
+fn parse_desktop_entry(content: &str) -> HashMap<String, String> {
+    let mut in_main = false;
+    let mut entries = HashMap::new();
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_main = line == "[Desktop Entry]";
+        } else if let (true, Some((key, value))) = (in_main, line.split_once('=')) {
+            if !key.contains('[') {
+                entries.entry(key.trim().to_string()).or_insert_with(|| value.trim().to_string());
+            }
+        }
+    }
+    entries
+}
+
+/// Text of the first unlocalized `<tag>` element (inner markup stripped)
+fn xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        let end_of_open = after.find('>')?;
+        let attributes = &after[..end_of_open];
+        let body = &after[end_of_open + 1..];
+        // `<name>` must not match `<names>`, and translations carry xml:lang
+        let is_tag = attributes.is_empty() || attributes.starts_with(char::is_whitespace);
+        if is_tag && !attributes.contains("xml:lang") {
+            let text = &body[..body.find(&close)?];
+            let text = strip_tags(text);
+            return (!text.is_empty()).then_some(text);
+        }
+        rest = body;
+    }
+    None
+}
+
+/// Value of `attribute` on the first `<tag>` element
+fn xml_attr(xml: &str, tag: &str, attribute: &str) -> Option<String> {
+    let start = xml.find(&format!("<{} ", tag))?;
+    let element = &xml[start..start + xml[start..].find('>')?];
+    let needle = format!("{}=", attribute);
+    let value = &element[element.find(&needle)? + needle.len()..];
+    let quote = value.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let value = &value[1..];
+    Some(value[..value.find(quote)?].to_string())
+}
+
+/// Text without markup, with whitespace collapsed
+fn strip_tags(text: &str) -> String {
+    let mut plain = String::new();
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => {
+                in_tag = false;
+                plain.push(' ');
+            }
+            c if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Lowercase letters, digits and '-' (for generated app IDs)
+fn sanitize_id_segment(name: &str) -> String {
+    let segment = name.to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if segment.is_empty() { "app".to_string() } else { segment }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_info() {
+        let root = std::env::temp_dir().join(format!("lxe-appimage-{}", std::process::id()));
+        fs::create_dir_all(root.join("usr/share/metainfo")).unwrap();
+        fs::write(root.join("AppRun"), "#!/bin/sh\n").unwrap();
+        fs::write(root.join("editor.png"), b"png").unwrap();
+        fs::write(
+            root.join("editor.desktop"),
+            "[Desktop Entry]\nName=Editor\nName[de]=Bearbeiter\nExec=editor %F\nIcon=editor\n\
+             Categories=Development;TextEditor;\nComment=Edits text\n\n[Desktop Action New]\nName=New\n",
+        ).unwrap();
+        fs::write(
+            root.join("usr/share/metainfo/org.example.Editor.metainfo.xml"),
+            "<component type=\"desktop-application\">\n  <id>org.example.Editor.desktop</id>\n\
+             <summary xml:lang=\"de\">Bearbeitet Text</summary>\n  <summary>A <em>fast</em> editor</summary>\n\
+             <releases>\n    <release version=\"2.4.1\" date=\"2024-01-01\"/>\n  </releases>\n</component>\n",
+        ).unwrap();
+
+        let info = read_info(&root, "Editor-x86_64").unwrap();
+        assert_eq!(info, AppImageInfo {
+            name: "Editor".into(),
+            app_id: "org.example.Editor".into(),
+            version: "2.4.1".into(),
+            executable: "AppRun".into(),
+            icon: Some("editor.png".into()),
+            description: Some("A fast editor".into()),
+            categories: vec!["Development".into(), "TextEditor".into()],
+            terminal: false,
+        });
+
+        assert_eq!(sanitize_id_segment("Foo \"Quoted\" 2"), "foo-quoted-2");
+
+        let config = generate_config(&info, Path::new("/out/Editor.lxe"), None).unwrap();
+        let parsed: lxe_common::config::LxeConfig = toml::from_str(&config).unwrap();
+        assert_eq!(parsed.package.id, "org.example.Editor");
+        assert_eq!(parsed.build.output.as_deref(), Some("/out/Editor.lxe"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,105 @@
+//! lxe-progress-reader - Progress bar for headless installs
+//!
+//! Listens on a Unix socket and renders the events a silent install sends
+//! to it (see `lxe_common::progress`):
+//!
+//! ```bash
+//! lxe-progress-reader /tmp/install.sock &
+//! ./MyApp.lxe --silent --progress-socket /tmp/install.sock
+//! ```
+//!
+//! Unlike a plain pipe, the reader owns the socket: it creates and listens
+//! on it and the runtime connects, so the reader must be started first. That
+//! way no event is lost to a reader that attaches after the install began,
+//! and a runtime with no reader just installs without reporting.
+//!
+//! Exits with status 1 if the install reports an error or disconnects
+//! before completing.
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use lxe_common::progress::ProgressEvent;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixListener;
+use std::path::{Path, PathBuf};
+
+/// Show the progress of `lxe-runtime --silent --progress-socket <SOCKET>`
+#[derive(Parser, Debug)]
+#[command(name = "lxe-progress-reader", version)]
+struct Args {
+    /// Socket to create and listen on
+    socket: PathBuf,
+}
+
+fn main() {
+    let args = Args::parse();
+    match run(&args) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("❌ {:#}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Removes the socket once this reader is done with it. Only created after a
+/// successful bind, so a path someone else is using is never deleted.
+struct BoundSocket<'a>(&'a Path);
+
+impl Drop for BoundSocket<'_> {
+    fn drop(&mut self) {
+        std::fs::remove_file(self.0).ok();
+    }
+}
+
+/// Render one install's events; returns whether it completed
+fn run(args: &Args) -> Result<bool> {
+    // A stale socket from an earlier run would make bind fail
+    if std::fs::symlink_metadata(&args.socket).is_ok_and(|m| m.file_type().is_socket()) {
+        std::fs::remove_file(&args.socket).ok();
+    }
+    let listener = UnixListener::bind(&args.socket)
+        .with_context(|| format!("Failed to listen on {}", args.socket.display()))?;
+    let _bound = BoundSocket(&args.socket);
+    let (stream, _) = listener.accept().context("Failed to accept the installer's connection")?;
+
+    let bar = ProgressBar::new(0);
+    bar.set_style(ProgressStyle::with_template(
+        "{msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({elapsed})"
+    ).unwrap().progress_chars("=> "));
+
+    let mut name = String::new();
+    for line in BufReader::new(stream).lines() {
+        let line = line.context("Failed to read from the installer")?;
+        let event: ProgressEvent = match serde_json::from_str(&line) {
+            Ok(event) => event,
+            // Newer runtimes may send events this reader doesn't know
+            Err(_) => continue,
+        };
+        match event {
+            ProgressEvent::Start { name: package, total_bytes } => {
+                bar.set_length(total_bytes);
+                bar.set_message(format!("📦 {}", package));
+                name = package;
+            }
+            ProgressEvent::Progress { files, bytes } => {
+                bar.set_position(bytes);
+                bar.set_message(format!("📦 {} ({} files)", name, files));
+            }
+            ProgressEvent::Complete => {
+                bar.finish_with_message("✅ Installed");
+                return Ok(true);
+            }
+            ProgressEvent::Error { message } => {
+                bar.abandon_with_message("❌ Failed");
+                eprintln!("{}", message);
+                return Ok(false);
+            }
+        }
+    }
+    bar.abandon_with_message("❌ Installer disconnected");
+    Ok(false)
+}
@@ -0,0 +1,701 @@
+//! Build Outputs: Versioned Paths and Intermediate Artifacts
+//!
+//! Release pipelines collect every version in one directory, so file names
+//! for `lxe build --output-dir` come from a template (`[build] output_template`)
+//! instead of being fixed:
+//!
+//! ```toml
+//! [build]
+//! output_template = "{id}-{version}-{arch}.lxe"
+//! ```
+//!
+//! Variables: `{id}`, `{version}`, `{arch}`, `{name}` and `{date}` (UTC, YYYYMMDD).
+//!
+//! An existing output file is overwritten unless `--conflict` (or `conflict`
+//! in the global config) says to fail, pick a free `-N` name or keep a
+//! `.bak` copy.
+//!
+//! The tar archive and compressed payload are written to
+//! `<target-dir>/lxe-build-<app_id>-<ts>/` rather than kept in memory, so
+//! huge packages don't run out of RAM.
+//!
+//! After each expensive phase (archive, compression, signing) the build
+//! records a checkpoint in `.lxe-build-state.json` next to `lxe.toml`. An
+//! interrupted build resumes from the last checkpoint whose files still
+//! match their checksums; `lxe build --clean` starts over.
+//!
+//! With `[build] max_memory_mb`, compression gets a smaller window when the
+//! level's estimated memory (plus what the process already uses) wouldn't
+//! fit the cap or the memory available on the machine.
+
+use anyhow::{Context, Result};
+use lxe_common::config::{Compressor, ConflictAction};
+use lxe_common::metadata::LxeMetadata;
+use lxe_common::payload::{checksum_file, ChecksumAlgorithm};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const DEFAULT_TEMPLATE: &str = "{id}-{version}-{arch}.lxe";
+
+/// Variables that differ between builds of the same app
+const VARYING: &[&str] = &["{version}", "{date}"];
+
+pub struct OutputPathBuilder;
+
+impl OutputPathBuilder {
+    /// File name for `metadata` from `template`
+    pub fn from_template(template: &str, metadata: &LxeMetadata) -> PathBuf {
+        PathBuf::from(Self::substitute(template, metadata, &today()))
+    }
+
+    fn substitute(template: &str, metadata: &LxeMetadata, date: &str) -> String {
+        // Values must not introduce directories
+        let clean = |value: &str| value.replace(['/', '\0'], "-");
+        template
+            .replace("{id}", &clean(&metadata.app_id))
+            .replace("{version}", &clean(&metadata.version))
+            .replace("{arch}", &clean(&metadata.arch))
+            .replace("{name}", &clean(&metadata.name))
+            .replace("{date}", date)
+    }
+}
+
+/// Delete all but the `keep` latest builds of `metadata`'s app in `dir`
+///
+/// Builds are files whose names match `template` with the same app ID, name
+/// and arch, where `{version}` is a version number and `{date}` a date, so
+/// apps whose ID extends this one's (`com.example.App-beta`) don't match.
+/// The highest versions are kept (then the latest dates and modification
+/// times). Returns the deleted files.
+pub fn keep_latest(dir: &Path, template: &str, metadata: &LxeMetadata, keep: usize) -> Result<Vec<PathBuf>> {
+    // Split at the varying variables; the fixed parts are filled in
+    let mut literals = Vec::new();
+    let mut vars = Vec::new();
+    let mut rest = template;
+    while let Some((at, var)) = VARYING.iter()
+        .filter_map(|var| rest.find(var).map(|at| (at, *var)))
+        .min()
+    {
+        literals.push(OutputPathBuilder::substitute(&rest[..at], metadata, ""));
+        vars.push(var);
+        rest = &rest[at + var.len()..];
+    }
+    literals.push(OutputPathBuilder::substitute(rest, metadata, ""));
+
+    let mut builds: Vec<(Option<semver::Version>, Option<String>, SystemTime, PathBuf)> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let values = match_template(&literals, &vars, &name)?;
+            let value = |var: &str| vars.iter().position(|v| *v == var).map(|i| values[i].to_string());
+            let version = value("{version}").and_then(|v| parse_build_version(&v));
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((version, value("{date}"), modified, entry.path()))
+        })
+        .collect();
+    builds.sort_by(|a, b| (&b.0, &b.1, b.2).cmp(&(&a.0, &a.1, a.2)));
+
+    let mut removed = Vec::new();
+    for (_, _, _, path) in builds.into_iter().skip(keep) {
+        fs::remove_file(&path)
+            .with_context(|| format!("Failed to delete {}", path.display()))?;
+        removed.push(path);
+    }
+    Ok(removed)
+}
+
+/// Highest `-N` suffix `ConflictAction::Rename` tries
+const MAX_RENAME_SUFFIX: u32 = 99;
+
+/// Path to write the package to when `path` may already exist
+///
+/// `Backup` keeps the path: the existing file is only moved away by
+/// [`back_up_output`] once the new package was built.
+pub fn resolve_output_conflict(path: &Path, action: ConflictAction) -> Result<PathBuf> {
+    if !path.exists() {
+        return Ok(path.to_path_buf());
+    }
+    match action {
+        ConflictAction::Overwrite => Ok(path.to_path_buf()),
+        ConflictAction::Error => anyhow::bail!(
+            "{} already exists (use --conflict overwrite, rename or backup)",
+            path.display()
+        ),
+        ConflictAction::Rename => {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let extension = path.extension()
+                .map(|ext| format!(".{}", ext.to_string_lossy()))
+                .unwrap_or_default();
+            (1..=MAX_RENAME_SUFFIX)
+                .map(|n| path.with_file_name(format!("{}-{}{}", stem, n, extension)))
+                .find(|candidate| !candidate.exists())
+                .with_context(|| format!(
+                    "{} and {}-1{} to -{} all exist",
+                    path.display(), stem, extension, MAX_RENAME_SUFFIX
+                ))
+        }
+        ConflictAction::Backup => Ok(path.to_path_buf()),
+    }
+}
+
+/// Move `path` to `<name>.bak`, replacing an older backup; returns the
+/// backup, if there was a file to move
+pub fn back_up_output(path: &Path) -> Result<Option<PathBuf>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let backup = with_suffix(path, ".bak");
+    fs::rename(path, &backup).with_context(|| {
+        format!("Failed to move {} to {}", path.display(), backup.display())
+    })?;
+    Ok(Some(backup))
+}
+
+/// Where the package is written before it replaces a backed-up `path`
+pub fn partial_output_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".partial")
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Prefix of artifact directory names
+const ARTIFACTS_PREFIX: &str = "lxe-build-";
+
+/// Artifact directories untouched for this long are removed by `--clean-artifacts`
+pub const STALE_ARTIFACT_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Intermediate files of one build (`lxe build --target-dir`)
+/// 
+/// The directory outlives a failed build, whose saved state resumes from
+/// it; only `finish` removes it.
+pub struct BuildArtifacts {
+    pub tar_path: PathBuf,
+    pub compressed_path: PathBuf,
+    pub dir: PathBuf,
+}
+
+impl BuildArtifacts {
+    /// Create `<target_dir>/lxe-build-<app_id>-<ts>-<random>/`
+    pub fn create(target_dir: &Path, app_id: &str) -> Result<Self> {
+        fs::create_dir_all(target_dir)
+            .with_context(|| format!("Failed to create {}", target_dir.display()))?;
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let name = format!("{}{}-{}-", ARTIFACTS_PREFIX, app_id.replace(['/', '\0'], "-"), secs);
+        let dir = tempfile::Builder::new()
+            .prefix(&name)
+            .tempdir_in(target_dir)
+            .with_context(|| format!("Failed to create {}", target_dir.join(&name).display()))?
+            .keep();
+        
+        Ok(Self {
+            tar_path: dir.join("archive.tar"),
+            compressed_path: dir.join("payload.zst"),
+            dir,
+        })
+    }
+    
+    /// After a successful build: remove the files, or keep them with
+    /// `keep` (`--keep-artifacts`) and return their directory
+    pub fn finish(self, keep: bool) -> Option<PathBuf> {
+        if keep {
+            return Some(self.dir);
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+        None
+    }
+}
+
+/// Remove artifact directories in `target_dir` that haven't been modified for
+/// `max_age` (left behind by crashed or `--keep-artifacts` builds)
+pub fn clean_stale_artifacts(target_dir: &Path, max_age: Duration) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(target_dir) else {
+        return Vec::new();
+    };
+    
+    let now = SystemTime::now();
+    entries.flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(ARTIFACTS_PREFIX))
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|entry| {
+            entry.metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age >= max_age)
+        })
+        .map(|entry| entry.path())
+        .filter(|path| fs::remove_dir_all(path).is_ok())
+        .collect()
+}
+
+/// Checkpoint file of an unfinished build, next to the config
+pub const BUILD_STATE_FILE: &str = ".lxe-build-state.json";
+
+/// Checkpoints of an unfinished build (`.lxe-build-state.json`)
+///
+/// Every phase is only reused if its file still has the recorded SHA256,
+/// and only for the same inputs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildState {
+    /// Config the build used; the state is stale once it is modified
+    pub config_path: PathBuf,
+    
+    /// Fingerprint of the input files (see `input_fingerprint`)
+    pub inputs: String,
+    
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tar_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tar_sha256: Option<String>,
+    
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_sha256: Option<String>,
+    /// Compressor and level the payload was compressed with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compressor: Option<Compressor>,
+    
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signing: Option<SigningState>,
+}
+
+/// Metadata before and after signing, and the key that signed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigningState {
+    pub key_sha256: String,
+    pub unsigned_metadata: serde_json::Value,
+    pub signed_metadata: serde_json::Value,
+}
+
+impl BuildState {
+    pub fn new(config_path: &Path, inputs: String) -> Self {
+        Self { config_path: config_path.to_path_buf(), inputs, ..Default::default() }
+    }
+    
+    /// Write the state (atomically, so an interruption can't corrupt it)
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+    
+    /// Read a saved state; `None` if there is none, it is unreadable, or
+    /// the config was modified after it was saved
+    pub fn load(path: &Path) -> Option<Self> {
+        let state: Self = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+        let saved = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+        let config_modified = fs::metadata(&state.config_path).and_then(|m| m.modified()).ok()?;
+        (config_modified <= saved).then_some(state)
+    }
+    
+    /// Delete the state file (`lxe build --clean`); returns whether it existed
+    pub fn remove(path: &Path) -> Result<bool> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("Failed to delete {}", path.display())),
+        }
+    }
+    
+    /// Move a still-valid archive to `to`; returns its SHA256
+    pub fn restore_tar(&self, inputs: &str, to: &Path) -> Option<String> {
+        if self.inputs != inputs {
+            return None;
+        }
+        restore_file(self.tar_path.as_deref()?, self.tar_sha256.as_deref()?, to)
+    }
+    
+    /// Move a still-valid payload to `to`; returns its SHA256
+    ///
+    /// Only meaningful after the archive was restored.
+    pub fn restore_payload(&self, compressor: Compressor, to: &Path) -> Option<String> {
+        if self.compressor != Some(compressor) {
+            return None;
+        }
+        restore_file(self.payload_path.as_deref()?, self.payload_sha256.as_deref()?, to)
+    }
+    
+    /// Remove the artifact directories of the interrupted build, except `current`
+    pub fn remove_artifacts(&self, current: &Path) {
+        let dirs = [&self.tar_path, &self.payload_path].into_iter().flatten().filter_map(|path| path.parent());
+        for dir in dirs {
+            let is_artifacts = dir.file_name().is_some_and(|name| name.to_string_lossy().starts_with(ARTIFACTS_PREFIX));
+            if is_artifacts && dir != current {
+                let _ = fs::remove_dir_all(dir);
+            }
+        }
+    }
+    
+    /// Signed metadata from a previous build, if the metadata and key are unchanged
+    pub fn signed_metadata(&self, unsigned: &serde_json::Value, key_sha256: &str) -> Option<serde_json::Value> {
+        let signing = self.signing.as_ref()?;
+        (signing.unsigned_metadata == *unsigned && signing.key_sha256 == key_sha256)
+            .then(|| signing.signed_metadata.clone())
+    }
+}
+
+/// Move `from` to `to` if it has the checksum `sha256`
+fn restore_file(from: &Path, sha256: &str, to: &Path) -> Option<String> {
+    let actual = checksum_file(from, ChecksumAlgorithm::Sha256).ok()?;
+    if actual != sha256 {
+        return None;
+    }
+    if from != to {
+        fs::rename(from, to).ok()?;
+        // The interrupted build's artifact directory is empty once both files moved
+        if let Some(old_dir) = from.parent() {
+            let _ = fs::remove_dir(old_dir);
+        }
+    }
+    Some(actual)
+}
+
+/// Fingerprint of everything that goes into the archive: relative path,
+/// size and mtime of each input file, and path and size of each override
+/// (overrides are regenerated on each build, so their mtimes always differ)
+pub fn input_fingerprint(input_dir: &Path, overrides: &BTreeMap<PathBuf, PathBuf>) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for entry in walkdir::WalkDir::new(input_dir).sort_by_file_name() {
+        let entry = entry.with_context(|| format!("Failed to read {}", input_dir.display()))?;
+        let meta = entry.path().symlink_metadata()?;
+        let mtime = meta.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let rel = entry.path().strip_prefix(input_dir).unwrap_or(entry.path());
+        hasher.update(format!("{}\0{}\0{}\n", rel.display(), meta.len(), mtime.as_nanos()));
+    }
+    for (rel, path) in overrides {
+        let len = fs::metadata(path).map(|m| m.len()).unwrap_or_default();
+        hasher.update(format!("override:{}\0{}\n", rel.display(), len));
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Writer adaptor that hashes everything written through it
+pub struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, hasher: Sha256::new() }
+    }
+    
+    /// Hex-encoded SHA256 of all bytes written so far
+    pub fn finalize(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+const MB: u64 = 1024 * 1024;
+
+/// zstd's (window, chain, hash) logs per level for inputs over 256 KB,
+/// from `lib/compress/clevels.h`; index 0 is the base of negative levels
+const ZSTD_LEVEL_LOGS: [(u32, u32, u32); 23] = [
+    (19, 12, 13), (19, 13, 14), (20, 15, 16), (21, 16, 17), (21, 18, 18), (21, 18, 19),
+    (21, 18, 19), (21, 19, 20), (21, 19, 20), (22, 20, 21), (22, 21, 22), (22, 21, 22),
+    (22, 22, 23), (22, 22, 22), (22, 22, 23), (22, 23, 23), (22, 22, 22), (23, 23, 22),
+    (23, 23, 22), (23, 24, 22), (25, 25, 23), (26, 26, 24), (27, 27, 25),
+];
+
+/// Smallest window zstd accepts
+const ZSTD_WINDOW_LOG_MIN: u32 = 10;
+
+/// zstd's logs for `level` (0 is the default level 3)
+fn level_logs(level: i32) -> (u32, u32, u32) {
+    ZSTD_LEVEL_LOGS[if level == 0 { 3 } else { level.clamp(0, 22) as usize }]
+}
+
+/// Rough memory use of a zstd encoder: the window plus its match tables
+///
+/// zstd shrinks the tables along with a smaller window, so `window_log`
+/// caps them too.
+pub fn estimate_compression_memory(level: i32, window_log: Option<u32>) -> u64 {
+    let (default_window, chain_log, hash_log) = level_logs(level);
+    let window = window_log.unwrap_or(default_window).min(default_window);
+    (1u64 << window) + 4 * (1u64 << chain_log.min(window + 1)) + 4 * (1u64 << hash_log.min(window + 1))
+}
+
+/// Largest window that keeps compression at `level` within `budget` bytes,
+/// `None` if the level's own window fits
+pub fn capped_window_log(level: i32, budget: u64) -> Option<u32> {
+    if estimate_compression_memory(level, None) <= budget {
+        return None;
+    }
+    let window = (ZSTD_WINDOW_LOG_MIN..level_logs(level).0)
+        .rev()
+        .find(|&log| estimate_compression_memory(level, Some(log)) <= budget)
+        .unwrap_or(ZSTD_WINDOW_LOG_MIN);
+    Some(window)
+}
+
+/// Bytes compression may use under `max_memory_mb`: the cap minus the
+/// current RSS, but no more than the system has available
+pub fn compression_budget(max_memory_mb: u64) -> u64 {
+    let cap = max_memory_mb.saturating_mul(MB);
+    let available = fs::read_to_string("/proc/meminfo").ok()
+        .and_then(|meminfo| proc_kb_field(&meminfo, "MemAvailable"))
+        .unwrap_or(u64::MAX);
+    cap.saturating_sub(current_rss().unwrap_or(0)).min(available)
+}
+
+/// Resident set size of this process (Linux only)
+pub fn current_rss() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    proc_kb_field(&status, "VmRSS")
+}
+
+/// `Key:   1234 kB` lines of /proc/meminfo and /proc/self/status, in bytes
+fn proc_kb_field(contents: &str, key: &str) -> Option<u64> {
+    contents.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+}
+
+/// Reader adaptor that samples the process RSS while compression reads
+/// its input, remembering the peak
+pub struct RssWatch<R> {
+    inner: R,
+    unchecked: u64,
+    pub peak: u64,
+}
+
+impl<R: Read> RssWatch<R> {
+    /// Sample every this many bytes read
+    const INTERVAL: u64 = 64 * MB;
+    
+    pub fn new(inner: R) -> Self {
+        Self { inner, unchecked: 0, peak: current_rss().unwrap_or(0) }
+    }
+}
+
+impl<R: Read> Read for RssWatch<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.unchecked += n as u64;
+        if self.unchecked >= Self::INTERVAL || n == 0 {
+            self.unchecked = 0;
+            self.peak = self.peak.max(current_rss().unwrap_or(0));
+        }
+        Ok(n)
+    }
+}
+
+/// Values of `vars` if `name` is `literals` with them in between
+fn match_template<'a>(literals: &[String], vars: &[&str], name: &'a str) -> Option<Vec<&'a str>> {
+    let rest = name.strip_prefix(literals.first()?.as_str())?;
+    let Some((var, vars)) = vars.split_first() else {
+        return rest.is_empty().then(Vec::new);
+    };
+    (1..=rest.len())
+        .filter(|&i| rest.is_char_boundary(i))
+        .find_map(|i| {
+            let value = &rest[..i];
+            let valid = match *var {
+                "{date}" => value.len() == 8 && value.bytes().all(|b| b.is_ascii_digit()),
+                _ => parse_build_version(value).is_some(),
+            };
+            if !valid {
+                return None;
+            }
+            let mut values = match_template(&literals[1..], vars, &rest[i..])?;
+            values.insert(0, value);
+            Some(values)
+        })
+}
+
+/// A semver version, or a plain dotted number like `1.2`
+fn parse_build_version(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version).ok().or_else(|| {
+        version.bytes().all(|b| b.is_ascii_digit() || b == b'.')
+            .then(|| lxe_common::requirements::normalize_version(version))
+            .flatten()
+    })
+}
+
+/// Today's UTC date as YYYYMMDD
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    crate::stats::format_install_date(&format!("unix:{}", secs)).replace('-', "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_and_keep_latest() {
+        let mut metadata = LxeMetadata::new("com.example.App", "App", "1.2.0", "app", 0, "");
+        metadata.arch = "x86_64".into();
+        assert_eq!(
+            OutputPathBuilder::from_template(DEFAULT_TEMPLATE, &metadata),
+            PathBuf::from("com.example.App-1.2.0-x86_64.lxe")
+        );
+        assert_eq!(today().len(), 8);
+
+        let dir = std::env::temp_dir().join(format!("lxe-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // 1.10.0 is the oldest file but the highest version
+        for (i, name) in ["com.example.App-1.10.0-x86_64.lxe", "com.example.App-1.0.0-x86_64.lxe",
+                          "com.example.App-1.9.0-x86_64.lxe", "com.example.App-1.2.0-x86_64.lxe",
+                          "com.example.Other-1.0.0-x86_64.lxe", "com.example.App-beta-1.0.0-x86_64.lxe"]
+            .iter()
+            .enumerate()
+        {
+            let path = dir.join(name);
+            fs::write(&path, b"").unwrap();
+            let mtime = UNIX_EPOCH + std::time::Duration::from_secs(1_000_000 + i as u64);
+            fs::File::options().write(true).open(&path).unwrap().set_modified(mtime).unwrap();
+        }
+
+        let removed = keep_latest(&dir, DEFAULT_TEMPLATE, &metadata, 2).unwrap();
+        assert_eq!(removed, vec![dir.join("com.example.App-1.2.0-x86_64.lxe"), dir.join("com.example.App-1.0.0-x86_64.lxe")]);
+        assert!(dir.join("com.example.App-1.10.0-x86_64.lxe").exists());
+        assert!(dir.join("com.example.Other-1.0.0-x86_64.lxe").exists());
+        assert!(dir.join("com.example.App-beta-1.0.0-x86_64.lxe").exists());
+        assert!(match_template(&["a-".into(), "-".into(), ".lxe".into()], &["{version}", "{date}"], "a-1.0.0-rc.1-20240101.lxe").is_some());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_artifacts() {
+        let dir = std::env::temp_dir().join(format!("lxe-artifacts-{}", std::process::id()));
+        let artifacts = BuildArtifacts::create(&dir, "com.example.App").unwrap();
+        let artifact_dir = artifacts.tar_path.parent().unwrap().to_path_buf();
+        assert!(artifact_dir.file_name().unwrap().to_string_lossy().starts_with("lxe-build-com.example.App-"));
+        fs::write(&artifacts.tar_path, b"tar").unwrap();
+
+        // Recent directories survive a cleanup; finishing removes them
+        assert!(clean_stale_artifacts(&dir, STALE_ARTIFACT_AGE).is_empty());
+        assert_eq!(artifacts.finish(false), None);
+        assert!(!artifact_dir.exists());
+
+        // A failed build leaves them for resuming, until the next build is done with them
+        let failed = BuildArtifacts::create(&dir, "com.example.App").unwrap();
+        let failed_dir = failed.dir.clone();
+        drop(failed);
+        assert!(failed_dir.exists());
+        // Builds of the same app started in the same second don't collide
+        let concurrent = BuildArtifacts::create(&dir, "com.example.App").unwrap();
+        assert_ne!(concurrent.dir, failed_dir);
+        concurrent.finish(false);
+        let state = BuildState { payload_path: Some(failed_dir.join("payload.zst")), ..Default::default() };
+        state.remove_artifacts(&failed_dir);
+        assert!(failed_dir.exists());
+        state.remove_artifacts(&dir);
+        assert!(!failed_dir.exists());
+
+        let kept_dir = BuildArtifacts::create(&dir, "com.example.App").unwrap().finish(true).unwrap();
+        assert!(kept_dir.exists());
+        assert_eq!(clean_stale_artifacts(&dir, Duration::ZERO), vec![kept_dir]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_build_state_resume() {
+        let dir = std::env::temp_dir().join(format!("lxe-build-state-{}", std::process::id()));
+        fs::create_dir_all(dir.join("old")).unwrap();
+        let config = dir.join("lxe.toml");
+        fs::write(&config, "").unwrap();
+        let old_tar = dir.join("old/archive.tar");
+        fs::write(&old_tar, b"tar").unwrap();
+        let state_path = dir.join(BUILD_STATE_FILE);
+
+        let mut state = BuildState::new(&config, "inputs".into());
+        state.tar_path = Some(old_tar.clone());
+        state.tar_sha256 = Some(checksum_file(&old_tar, ChecksumAlgorithm::Sha256).unwrap());
+        state.save(&state_path).unwrap();
+
+        // Changed inputs or a modified file are not reused
+        let loaded = BuildState::load(&state_path).unwrap();
+        let new_tar = dir.join("archive.tar");
+        assert_eq!(loaded.restore_tar("other inputs", &new_tar), None);
+        assert_eq!(loaded.restore_payload(Compressor::Zstd { level: 3 }, &dir.join("payload.zst")), None);
+        assert!(loaded.restore_tar("inputs", &new_tar).is_some());
+        assert!(new_tar.exists() && !dir.join("old").exists());
+
+        // Editing the config invalidates the state
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options().write(true).open(&config).unwrap().set_modified(later).unwrap();
+        assert!(BuildState::load(&state_path).is_none());
+        assert!(BuildState::remove(&state_path).unwrap());
+        assert!(!BuildState::remove(&state_path).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_memory_cap() {
+        // Level 19: 8 MB window, 64 MB chain and 16 MB hash table
+        assert_eq!(estimate_compression_memory(19, None), 88 * MB);
+        assert_eq!(capped_window_log(19, 100 * MB), None);
+        let window = capped_window_log(22, 100 * MB).unwrap();
+        assert!(window < 27 && estimate_compression_memory(22, Some(window)) <= 100 * MB);
+        assert_eq!(capped_window_log(3, 0), Some(ZSTD_WINDOW_LOG_MIN));
+
+        let meminfo = "MemTotal:       16284212 kB\nMemAvailable:    8000000 kB\n";
+        assert_eq!(proc_kb_field(meminfo, "MemAvailable"), Some(8_000_000 * 1024));
+        assert_eq!(proc_kb_field(meminfo, "MemFree"), None);
+    }
+
+    #[test]
+    fn test_resolve_output_conflict() {
+        let dir = std::env::temp_dir().join(format!("lxe-conflict-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("app.lxe");
+        assert_eq!(resolve_output_conflict(&path, ConflictAction::Error).unwrap(), path);
+
+        fs::write(&path, b"old").unwrap();
+        assert!(resolve_output_conflict(&path, ConflictAction::Error).is_err());
+        assert_eq!(resolve_output_conflict(&path, ConflictAction::Overwrite).unwrap(), path);
+
+        fs::write(dir.join("app-1.lxe"), b"").unwrap();
+        assert_eq!(resolve_output_conflict(&path, ConflictAction::Rename).unwrap(), dir.join("app-2.lxe"));
+
+        fs::write(dir.join("app.lxe.bak"), b"older").unwrap();
+        assert_eq!(resolve_output_conflict(&path, ConflictAction::Backup).unwrap(), path);
+        assert!(path.exists());
+        assert_eq!(back_up_output(&path).unwrap(), Some(dir.join("app.lxe.bak")));
+        assert!(!path.exists());
+        assert_eq!(fs::read(dir.join("app.lxe.bak")).unwrap(), b"old");
+        assert_eq!(back_up_output(&path).unwrap(), None);
+
+        assert_eq!("backup".parse::<ConflictAction>().unwrap(), ConflictAction::Backup);
+        assert!("skip".parse::<ConflictAction>().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
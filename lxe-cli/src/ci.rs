@@ -0,0 +1,56 @@
+//! CI Service Messages (`lxe build --ci-output`)
+//!
+//! Build logs get machine-parseable lines in the style of TeamCity service
+//! messages, so CI plugins can pick out steps, artifacts and failures:
+//!
+//! ```text
+//! ##lxe[buildStep name='Compressing']
+//! ##lxe[artifact path='app.lxe' size='52428800']
+//! ##lxe[buildStatus status='SUCCESS' text='Package built in 45s']
+//! ```
+//!
+//! Values are quoted with `'` and escaped with `|` (`|'`, `||`, `|n`, `|r`,
+//! `|[`, `|]`) so every message stays on one line.
+
+/// Format a service message
+pub fn message(name: &str, attributes: &[(&str, &str)]) -> String {
+    let mut line = format!("##lxe[{}", name);
+    for (key, value) in attributes {
+        line.push_str(&format!(" {}='{}'", key, escape(value)));
+    }
+    line.push(']');
+    line
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '|' => escaped.push_str("||"),
+            '\'' => escaped.push_str("|'"),
+            '\n' => escaped.push_str("|n"),
+            '\r' => escaped.push_str("|r"),
+            '[' => escaped.push_str("|["),
+            ']' => escaped.push_str("|]"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_escaping() {
+        assert_eq!(
+            message("artifact", &[("path", "app.lxe"), ("size", "42")]),
+            "##lxe[artifact path='app.lxe' size='42']"
+        );
+        assert_eq!(
+            message("buildProblem", &[("description", "can't find [x]\nline 2")]),
+            "##lxe[buildProblem description='can|'t find |[x|]|nline 2']"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+//! Housekeeping (`lxe clean`)
+//!
+//! Finds what LXE leaves behind when things go wrong: manifests of apps whose
+//! directory was deleted by hand, icons extracted to the temp directory by
+//! `payload::extract_icon_to_temp`, `<app_dir>.lxe-staging` directories of
+//! installs that failed before the atomic rename, and `<app_dir>.lxe-old`
+//! directories of previous versions an upgrade didn't get to remove.
+
+use lxe_common::manifest::InstallManifest;
+use lxe_common::paths::lxe::{BACKUP_SUFFIX, STAGING_SUFFIX};
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Staging and backup directories changed more recently than this may
+/// belong to an install that is still running
+const MIN_LEFTOVER_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanKind {
+    OrphanedManifest,
+    TempIcon,
+    StagingDir,
+    BackupDir,
+}
+
+impl fmt::Display for CleanKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OrphanedManifest => "manifest",
+            Self::TempIcon => "icon",
+            Self::StagingDir => "staging",
+            Self::BackupDir => "backup",
+        })
+    }
+}
+
+/// Something `lxe clean` would delete
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CleanItem {
+    pub kind: CleanKind,
+    pub path: PathBuf,
+    pub size_bytes: u64,
+}
+
+impl CleanItem {
+    fn new(kind: CleanKind, path: PathBuf) -> Self {
+        let size_bytes = crate::stats::dir_size(&path);
+        Self { kind, path, size_bytes }
+    }
+
+    pub fn remove(&self) -> std::io::Result<()> {
+        match self.kind {
+            CleanKind::StagingDir | CleanKind::BackupDir => fs::remove_dir_all(&self.path),
+            CleanKind::OrphanedManifest | CleanKind::TempIcon => fs::remove_file(&self.path),
+        }
+    }
+}
+
+/// Everything to clean up for the current user; staging and backup
+/// directories are also looked for system-wide, which only root can delete
+pub fn find_items() -> Vec<CleanItem> {
+    let manifests: Vec<InstallManifest> = InstallManifest::list_installed()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|id| InstallManifest::load(id).ok().flatten())
+        .collect();
+
+    let mut share_dirs: Vec<PathBuf> = lxe_common::paths::user::base_dir().into_iter()
+        .chain([lxe_common::paths::system::base_dir()])
+        .chain(manifests.iter().filter_map(|m| m.prefix.clone()))
+        .map(|base| base.join("share"))
+        .collect();
+    share_dirs.sort();
+    share_dirs.dedup();
+
+    let mut items: Vec<CleanItem> = manifests.iter()
+        .filter(|m| crate::stats::app_dir(m).is_some_and(|dir| !dir.exists()))
+        .map(|m| CleanItem::new(CleanKind::OrphanedManifest, InstallManifest::manifest_path(&m.app_id)))
+        .collect();
+    items.extend(find_temp_icons(&std::env::temp_dir()));
+    for dir in &share_dirs {
+        items.extend(find_install_leftovers(dir, MIN_LEFTOVER_AGE));
+    }
+    items
+}
+
+/// `lxe-icon-*.png` files in `temp_dir`
+fn find_temp_icons(temp_dir: &Path) -> Vec<CleanItem> {
+    sorted_entries(temp_dir)
+        .filter(|path| path.is_file())
+        .filter(|path| path.file_name()
+            .map(|n| n.to_string_lossy())
+            .is_some_and(|n| n.starts_with("lxe-icon-") && n.ends_with(".png")))
+        .map(|path| CleanItem::new(CleanKind::TempIcon, path))
+        .collect()
+}
+
+/// Leftover `<app_dir>.lxe-staging` and `<app_dir>.lxe-old` directories in
+/// `share_dir` that haven't changed for `min_age`
+/// 
+/// A backup is only a leftover next to its app directory; without one, it
+/// is the only copy of the app.
+fn find_install_leftovers(share_dir: &Path, min_age: Duration) -> Vec<CleanItem> {
+    sorted_entries(share_dir)
+        .filter(|path| path.is_dir() && !path.is_symlink())
+        .filter_map(|path| {
+            let name = path.to_string_lossy();
+            if name.ends_with(STAGING_SUFFIX) {
+                Some(CleanItem::new(CleanKind::StagingDir, path))
+            } else if name.strip_suffix(BACKUP_SUFFIX).is_some_and(|app_dir| Path::new(app_dir).is_dir()) {
+                Some(CleanItem::new(CleanKind::BackupDir, path))
+            } else {
+                None
+            }
+        })
+        .filter(|item| unchanged_for(&item.path, min_age))
+        .collect()
+}
+
+/// Whether `path` itself was last changed (including renamed) at least
+/// `min_age` ago
+fn unchanged_for(path: &Path, min_age: Duration) -> bool {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return false;
+    };
+    let changed = SystemTime::UNIX_EPOCH + Duration::from_secs(metadata.ctime().max(0) as u64);
+    changed.elapsed().is_ok_and(|age| age >= min_age)
+}
+
+fn sorted_entries(dir: &Path) -> impl Iterator<Item = PathBuf> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_leftovers() {
+        let dir = std::env::temp_dir().join(format!("lxe-clean-test-{}", std::process::id()));
+        fs::create_dir_all(dir.join("com.example.App.lxe-staging/bin")).unwrap();
+        fs::create_dir_all(dir.join("com.example.Other")).unwrap();
+        fs::write(dir.join("com.example.App.lxe-staging/bin/app"), b"12345").unwrap();
+        fs::write(dir.join("lxe-icon-com.example.App.png"), b"png").unwrap();
+        fs::write(dir.join("lxe-icon-notes.txt"), b"").unwrap();
+        // A backup without its app directory is the only copy left
+        fs::create_dir_all(dir.join("com.example.Other.lxe-old")).unwrap();
+        fs::create_dir_all(dir.join("com.example.Gone.lxe-old")).unwrap();
+
+        // Recent ones may belong to a running install
+        assert!(find_install_leftovers(&dir, MIN_LEFTOVER_AGE).is_empty());
+        let staging = find_install_leftovers(&dir, Duration::ZERO);
+        assert_eq!(staging, [
+            CleanItem {
+                kind: CleanKind::StagingDir,
+                path: dir.join("com.example.App.lxe-staging"),
+                size_bytes: 5,
+            },
+            CleanItem {
+                kind: CleanKind::BackupDir,
+                path: dir.join("com.example.Other.lxe-old"),
+                size_bytes: 0,
+            },
+        ]);
+        let icons = find_temp_icons(&dir);
+        assert_eq!(icons.len(), 1);
+        assert_eq!(icons[0].size_bytes, 3);
+
+        for item in staging.iter().chain(&icons) {
+            item.remove().unwrap();
+        }
+        assert!(find_install_leftovers(&dir, Duration::ZERO).is_empty());
+        assert!(find_temp_icons(&dir).is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
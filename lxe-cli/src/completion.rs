@@ -0,0 +1,176 @@
+//! Shell Completions (`lxe completion`)
+//!
+//! Scripts are generated by `clap_complete` from the CLI definition. For the
+//! commands that take an installed app's ID, the scripts ask `lxe list` at
+//! completion time, so they stay current as apps come and go.
+
+use anyhow::{Context, Result};
+use clap_complete::Shell;
+use std::path::{Path, PathBuf};
+
+/// Subcommands whose `id` argument is an installed app
+const INSTALLED_ID_COMMANDS: &[&str] = &["uninstall", "verify-install"];
+
+/// Stand-in value the generators put where the installed IDs go
+const IDS_PLACEHOLDER: &str = "__LXE_INSTALLED_IDS__";
+
+/// Shell command printing the installed app IDs, one per line
+const LIST_IDS: &str = "lxe list --format csv 2>/dev/null | tail -n +2 | cut -d, -f1";
+
+/// Shells `lxe completion` supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl CompletionShell {
+    /// Shell of a `$SHELL` value (e.g. /usr/bin/zsh)
+    pub fn from_path(shell: &str) -> Option<Self> {
+        match Path::new(shell).file_name()?.to_str()? {
+            "bash" => Some(Self::Bash),
+            "zsh" => Some(Self::Zsh),
+            "fish" => Some(Self::Fish),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Bash => "bash",
+            Self::Zsh => "zsh",
+            Self::Fish => "fish",
+        }
+    }
+
+    fn generator(self) -> Shell {
+        match self {
+            Self::Bash => Shell::Bash,
+            Self::Zsh => Shell::Zsh,
+            Self::Fish => Shell::Fish,
+        }
+    }
+
+    /// Where `lxe completion install` writes the script, under `home`
+    pub fn install_path(self, home: &Path) -> PathBuf {
+        match self {
+            Self::Bash => home.join(".bash_completion.d/lxe"),
+            Self::Zsh => home.join(".zsh/completions/_lxe"),
+            Self::Fish => home.join(".config/fish/completions/lxe.fish"),
+        }
+    }
+
+    /// What the user has to add to their shell config, if anything
+    pub fn setup_hint(self, path: &Path) -> Option<String> {
+        match self {
+            Self::Bash => Some(format!("Add to ~/.bashrc: source {}", path.display())),
+            Self::Zsh => Some(format!(
+                "Add to ~/.zshrc (before compinit): fpath=({} $fpath)",
+                path.parent().unwrap_or(path).display()
+            )),
+            // Loaded automatically
+            Self::Fish => None,
+        }
+    }
+}
+
+/// Write the completion script of `cmd` for `shell`, completing installed
+/// app IDs for installed-app arguments
+pub fn generate(mut cmd: clap::Command, shell: CompletionShell, out: &mut dyn std::io::Write) {
+    cmd = cmd.mut_subcommands(|sub| {
+        if !INSTALLED_ID_COMMANDS.contains(&sub.get_name()) {
+            return sub;
+        }
+        // Only affects the script: `lxe` itself still accepts any ID.
+        // (`mut_args` keeps the positional order, unlike `mut_arg`)
+        sub.mut_args(|arg| match arg.get_id().as_str() {
+            "id" => arg.value_parser(clap::builder::PossibleValuesParser::new([IDS_PLACEHOLDER])),
+            _ => arg,
+        })
+    });
+    let name = cmd.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell.generator(), &mut cmd, name.clone(), &mut script);
+    let script = String::from_utf8_lossy(&script);
+    
+    // Swap the placeholder for a call to a helper listing the IDs
+    let helper = format!("_{}_installed_ids", name);
+    let script = match shell {
+        // Inside the double-quoted `opts`, so it runs on every completion
+        CompletionShell::Bash => format!(
+            "{helper}() {{ {LIST_IDS}; }}\n\n{}",
+            script.replace(IDS_PLACEHOLDER, &format!("$({})", helper))
+        ),
+        // A bare function name is an action `_arguments` calls
+        CompletionShell::Zsh => {
+            let (compdef, rest) = script.split_once('\n').unwrap_or((&script, ""));
+            format!(
+                "{compdef}\n\n{helper}() {{\n    local -a ids\n    ids=(${{(f)\"$({LIST_IDS})\"}})\n    _describe 'installed app' ids\n}}\n{}",
+                rest.replace(&format!("({})", IDS_PLACEHOLDER), &helper)
+            )
+        }
+        // The fish generator leaves out the values of positionals
+        CompletionShell::Fish => {
+            let mut script = script.into_owned();
+            for command in INSTALLED_ID_COMMANDS.iter().filter(|c| cmd.find_subcommand(c).is_some()) {
+                script.push_str(&format!(
+                    "complete -c {name} -n \"__fish_{name}_using_subcommand {command}\" -f -a \"({LIST_IDS})\"\n"
+                ));
+            }
+            script
+        }
+    };
+    out.write_all(script.as_bytes()).ok();
+}
+
+/// Write the script for `shell` to its completion directory; returns the path
+pub fn install(cmd: clap::Command, shell: CompletionShell) -> Result<PathBuf> {
+    let home = dirs::home_dir().context("Could not determine the home directory")?;
+    let path = shell.install_path(&home);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let mut script = Vec::new();
+    generate(cmd, shell, &mut script);
+    std::fs::write(&path, script)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completion_scripts() {
+        assert_eq!(CompletionShell::from_path("/usr/bin/zsh"), Some(CompletionShell::Zsh));
+        assert_eq!(CompletionShell::from_path("fish"), Some(CompletionShell::Fish));
+        assert_eq!(CompletionShell::from_path("/bin/tcsh"), None);
+        assert_eq!(
+            CompletionShell::Fish.install_path(Path::new("/home/me")),
+            PathBuf::from("/home/me/.config/fish/completions/lxe.fish")
+        );
+
+        let cmd = clap::Command::new("lxe")
+            .subcommand(clap::Command::new("uninstall").arg(clap::Arg::new("id")))
+            .subcommand(clap::Command::new("verify-install").arg(clap::Arg::new("id")));
+        let script = |shell| {
+            let mut script = Vec::new();
+            generate(cmd.clone(), shell, &mut script);
+            String::from_utf8(script).unwrap()
+        };
+        let bash = script(CompletionShell::Bash);
+        assert!(bash.starts_with("_lxe_installed_ids() { lxe list --format csv"), "{}", bash);
+        assert!(bash.contains("$(_lxe_installed_ids)"), "{}", bash);
+        let zsh = script(CompletionShell::Zsh);
+        assert!(zsh.starts_with("#compdef lxe\n\n_lxe_installed_ids() {"), "{}", zsh);
+        assert!(zsh.contains(":_lxe_installed_ids'"), "{}", zsh);
+        let fish = script(CompletionShell::Fish);
+        assert!(fish.contains(r#"complete -c lxe -n "__fish_lxe_using_subcommand verify-install" -f -a "(lxe list"#), "{}", fish);
+        for script in [bash, zsh, fish] {
+            assert!(!script.contains(IDS_PLACEHOLDER), "{}", script);
+        }
+    }
+}
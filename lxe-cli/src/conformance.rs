@@ -0,0 +1,372 @@
+//! Package Format Conformance Checks
+//!
+//! Validates `.lxe` files produced by third-party tools against the format:
+//!
+//! ```text
+//! [ELF runtime][LXE_MAGIC][len:u32 LE][metadata JSON][sha256:32][zstd tar][footer]
+//! ```
+//!
+//! Checks read the raw bytes rather than going through the runtime's
+//! parser, so a malformed package produces a report instead of an error.
+
+use lxe_common::builder;
+use lxe_common::desktop::is_mime_type;
+use lxe_common::metadata::{LxeMetadata, LXE_MAGIC};
+use lxe_common::payload::{self, ChecksumAlgorithm, ChecksumHasher, PackageLayout, CHECKSUM_SLOT_SIZE};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path};
+
+/// How thorough the checks are
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConformanceLevel {
+    /// Binary layout, metadata fields, checksum and tar paths
+    Basic,
+    /// Basic plus value validation (semver, app ID, arch, MIME types, icon)
+    Full,
+}
+
+/// Outcome of one check
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub passed: bool,
+    pub detail: String,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Check { name, passed: true, detail: detail.into() }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>) -> Self {
+        Check { name, passed: false, detail: detail.into() }
+    }
+
+    fn from_result(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => Check::pass(name, detail),
+            Err(detail) => Check::fail(name, detail),
+        }
+    }
+}
+
+/// Architectures the runtime is built for
+const KNOWN_ARCHES: &[&str] = &["x86_64", "aarch64"];
+
+/// Icon formats the installer can handle
+const ICON_EXTENSIONS: &[&str] = &["png", "svg", "xpm"];
+
+/// Required metadata fields and their JSON types
+const REQUIRED_FIELDS: &[(&str, JsonType)] = &[
+    ("format_version", JsonType::Number),
+    ("app_id", JsonType::String),
+    ("name", JsonType::String),
+    ("version", JsonType::String),
+    ("arch", JsonType::String),
+    ("install_size", JsonType::Number),
+    ("exec", JsonType::String),
+    ("payload_checksum", JsonType::String),
+];
+
+#[derive(Debug, Clone, Copy)]
+enum JsonType {
+    String,
+    Number,
+}
+
+/// Run all checks for `level`
+///
+/// Later checks are skipped when the layout can't be determined.
+pub fn run(path: &Path, level: ConformanceLevel) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    checks.push(Check::from_result("ELF header at offset 0", check_elf(path)));
+
+    let layout = match payload::read_layout(path) {
+        Ok(layout) => {
+            let detail = if layout.footer_size == 0 {
+                "no footer - header found by legacy scan".to_string()
+            } else {
+                format!("v{} footer, header at offset {}", layout.footer_version, layout.header_offset)
+            };
+            checks.push(Check {
+                name: "Footer present",
+                passed: layout.footer_size > 0,
+                detail,
+            });
+            layout
+        }
+        Err(e) => {
+            checks.push(Check::fail("Footer present", e.to_string()));
+            return checks;
+        }
+    };
+
+    checks.push(Check::from_result("Footer HeaderOffset points to magic", check_magic(path, &layout)));
+
+    let metadata = match read_metadata(path, &layout) {
+        Ok(json) => {
+            checks.push(Check::pass(
+                "Metadata length matches JSON",
+                format!("{} bytes", layout.metadata_len),
+            ));
+            Some(json)
+        }
+        Err(e) => {
+            checks.push(Check::fail("Metadata length matches JSON", e));
+            None
+        }
+    };
+
+    if let Some(ref json) = metadata {
+        checks.push(Check::from_result("Required metadata fields", check_required_fields(json)));
+    }
+
+    checks.push(Check::from_result(
+        "Payload checksum",
+        check_checksum(path, &layout, metadata.as_ref()),
+    ));
+    checks.push(Check::from_result("Tar entries use safe relative paths", check_tar_paths(path, &layout, metadata.as_ref())));
+
+    if level == ConformanceLevel::Full {
+        let json = metadata.unwrap_or_default();
+        let field = |name: &str| json.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+        checks.push(Check::from_result("Version is valid semver", {
+            let version = field("version");
+            semver::Version::parse(&version)
+                .map(|_| version.clone())
+                .map_err(|e| format!("'{}': {}", version, e))
+        }));
+        checks.push(Check::from_result("App ID is reverse-DNS", {
+            let app_id = field("app_id");
+            if is_reverse_dns(&app_id) { Ok(app_id) } else { Err(format!("'{}'", app_id)) }
+        }));
+        checks.push(Check::from_result("Architecture is known", {
+            let arch = field("arch");
+            if KNOWN_ARCHES.contains(&arch.as_str()) {
+                Ok(arch)
+            } else {
+                Err(format!("'{}' (expected one of: {})", arch, KNOWN_ARCHES.join(", ")))
+            }
+        }));
+        checks.push(Check::from_result("MIME types are well-formed", check_mime_types(&json)));
+        checks.push(Check::from_result("Icon has a supported extension", {
+            match json.get("icon").and_then(|v| v.as_str()) {
+                None => Ok("no icon".to_string()),
+                Some(icon) => {
+                    let ext = Path::new(icon).extension().and_then(|e| e.to_str()).unwrap_or_default();
+                    if ICON_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()) {
+                        Ok(icon.to_string())
+                    } else {
+                        Err(format!("'{}' (expected {})", icon, ICON_EXTENSIONS.join(", ")))
+                    }
+                }
+            }
+        }));
+    }
+
+    checks
+}
+
+fn check_elf(path: &Path) -> Result<String, String> {
+    let mut header = [0u8; 4];
+    File::open(path)
+        .and_then(|mut f| f.read_exact(&mut header))
+        .map_err(|e| e.to_string())?;
+    if header == *b"\x7fELF" {
+        Ok("ok".to_string())
+    } else {
+        Err(format!("found {:02x?}", header))
+    }
+}
+
+fn check_magic(path: &Path, layout: &PackageLayout) -> Result<String, String> {
+    let bytes = read_at(path, layout.header_offset, LXE_MAGIC.len()).map_err(|e| e.to_string())?;
+    if bytes == LXE_MAGIC {
+        Ok(format!("offset {}", layout.header_offset))
+    } else {
+        Err(format!("no magic at offset {}", layout.header_offset))
+    }
+}
+
+/// Parse exactly `metadata_len` bytes as JSON
+pub(crate) fn read_metadata(path: &Path, layout: &PackageLayout) -> Result<serde_json::Value, String> {
+    if layout.checksum_offset() + 32 > layout.file_size - layout.footer_size {
+        return Err(format!("length field {} runs past the end of the file", layout.metadata_len));
+    }
+    let bytes = read_at(path, layout.metadata_offset(), layout.metadata_len as usize)
+        .map_err(|e| e.to_string())?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| format!("length field {} does not delimit valid JSON: {}", layout.metadata_len, e))
+}
+
+fn check_required_fields(json: &serde_json::Value) -> Result<String, String> {
+    let problems: Vec<String> = REQUIRED_FIELDS.iter()
+        .filter_map(|(name, kind)| {
+            let ok = match (json.get(name), kind) {
+                (Some(v), JsonType::String) => v.is_string(),
+                (Some(v), JsonType::Number) => v.is_u64(),
+                (None, _) => return Some(format!("{} missing", name)),
+            };
+            (!ok).then(|| format!("{} should be a {:?}", name, kind).to_lowercase())
+        })
+        .collect();
+
+    if problems.is_empty() {
+        Ok(format!("{} fields", REQUIRED_FIELDS.len()))
+    } else {
+        Err(problems.join(", "))
+    }
+}
+
+/// Stored checksum bytes must match the payload and the metadata
+/// 
+/// The payload is hashed with the metadata's `checksum_algorithm` (SHA256
+/// when absent or unreadable); the stored slot is the digest's first 32 bytes.
+pub(crate) fn check_checksum(
+    path: &Path,
+    layout: &PackageLayout,
+    metadata: Option<&serde_json::Value>,
+) -> Result<String, String> {
+    let stored = read_at(path, layout.checksum_offset(), CHECKSUM_SLOT_SIZE).map_err(|e| e.to_string())?;
+
+    let algo: ChecksumAlgorithm = metadata
+        .and_then(|m| m.get("checksum_algorithm"))
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default();
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(layout.payload_offset())).map_err(|e| e.to_string())?;
+    let mut hasher = ChecksumHasher::new(algo);
+    let mut payload = file.take(layout.payload_size());
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = payload.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    let computed = hasher.finalize_hex();
+
+    if hex::encode(&stored) != computed[..CHECKSUM_SLOT_SIZE * 2] {
+        return Err(format!("stored {} but payload hashes to {} ({})", hex::encode(&stored), computed, algo));
+    }
+    let in_metadata = metadata.and_then(|m| m.get("payload_checksum")).and_then(|v| v.as_str());
+    if in_metadata.is_some_and(|m| !m.eq_ignore_ascii_case(&computed)) {
+        return Err("metadata payload_checksum differs from stored checksum".to_string());
+    }
+    Ok(format!("{}... ({})", &computed[..16], algo))
+}
+
+/// `check_checksum` for a package that was already parsed, as an error
+pub(crate) fn verify_payload(path: &Path, metadata: &LxeMetadata) -> anyhow::Result<()> {
+    let layout = payload::read_layout(path)?;
+    let metadata = serde_json::to_value(metadata)?;
+    check_checksum(path, &layout, Some(&metadata))
+        .map(|_| ())
+        .map_err(|e| anyhow::anyhow!("Payload checksum mismatch: {}", e))
+}
+
+/// Every tar entry must be relative and stay inside the install directory
+///
+/// The payload is decoded with the metadata's `compressor` (zstd when absent).
+fn check_tar_paths(
+    path: &Path,
+    layout: &PackageLayout,
+    metadata: Option<&serde_json::Value>,
+) -> Result<String, String> {
+    let compressor = metadata
+        .and_then(|m| m.get("compressor"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("zstd");
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    file.seek(SeekFrom::Start(layout.payload_offset())).map_err(|e| e.to_string())?;
+    let decoder = builder::decoder(file.take(layout.payload_size()), compressor)
+        .map_err(|e| format!("payload is not {}: {:#}", compressor, e))?;
+    let mut archive = tar::Archive::new(decoder);
+
+    let mut count = 0;
+    let mut bad = Vec::new();
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| format!("corrupt tar: {}", e))?;
+        let entry_path = entry.path().map_err(|e| e.to_string())?.to_path_buf();
+        count += 1;
+        if !is_safe_relative(&entry_path) {
+            bad.push(entry_path.display().to_string());
+        }
+    }
+
+    if bad.is_empty() {
+        Ok(format!("{} entries", count))
+    } else {
+        Err(format!("unsafe paths: {}", bad.join(", ")))
+    }
+}
+
+/// Relative path without `..` components
+fn is_safe_relative(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// At least two dot-separated segments of letters, digits, '-' or '_',
+/// none starting with a digit
+pub(crate) fn is_reverse_dns(app_id: &str) -> bool {
+    let segments: Vec<&str> = app_id.split('.').collect();
+    segments.len() >= 2
+        && segments.iter().all(|s| {
+            !s.is_empty()
+                && !s.starts_with(|c: char| c.is_ascii_digit())
+                && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+fn check_mime_types(json: &serde_json::Value) -> Result<String, String> {
+    let Some(types) = json.get("mime_types").and_then(|v| v.as_array()) else {
+        return Ok("none declared".to_string());
+    };
+
+    let bad: Vec<String> = types.iter()
+        .map(|t| t.as_str().unwrap_or_default())
+        .filter(|t| !is_mime_type(t))
+        .map(|t| format!("'{}'", t))
+        .collect();
+
+    if bad.is_empty() {
+        Ok(format!("{} type(s)", types.len()))
+    } else {
+        Err(bad.join(", "))
+    }
+}
+
+fn read_at(path: &Path, offset: u64, len: usize) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0u8; len];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_checks() {
+        assert!(is_reverse_dns("com.example.App"));
+        assert!(is_reverse_dns("org.gnome.text-editor"));
+        assert!(!is_reverse_dns("myapp"));
+        assert!(!is_reverse_dns("com..app"));
+        assert!(!is_reverse_dns("com.1app"));
+
+        assert!(is_mime_type("text/plain"));
+        assert!(is_mime_type("application/vnd.ms-excel"));
+        assert!(!is_mime_type("text"));
+        assert!(!is_mime_type("text/"));
+
+        assert!(is_safe_relative(Path::new("bin/app")));
+        assert!(!is_safe_relative(Path::new("../etc/passwd")));
+        assert!(!is_safe_relative(Path::new("/etc/passwd")));
+    }
+}
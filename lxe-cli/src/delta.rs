@@ -0,0 +1,201 @@
+//! Delta Packages (`lxe delta`)
+//!
+//! Builds a package holding only the entries that differ between two
+//! releases, for upgrading large apps without downloading every file again
+//! (see `lxe_common::delta` for the format). Entries are compared by their
+//! tar header and the SHA256 of their contents.
+
+use anyhow::{Context, Result};
+use lxe_common::builder;
+use lxe_common::delta::{DeltaManifest, TreeEntry, DELTA_MANIFEST};
+use lxe_common::payload::{entry_info, open_payload_reader, EntryInfo, EntryKind, PayloadInfo};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/// A payload entry with the SHA256 of its contents (files only)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryDigest {
+    pub info: EntryInfo,
+    pub sha256: Option<String>,
+}
+
+/// Every entry of the payload, by path
+pub fn entry_digests(info: &PayloadInfo) -> Result<BTreeMap<PathBuf, EntryDigest>> {
+    let decoder = builder::decoder(open_payload_reader(info)?, &info.metadata.compressor)?;
+    let mut archive = tar::Archive::new(decoder);
+
+    let mut digests = BTreeMap::new();
+    for entry in archive.entries().context("Failed to read payload")? {
+        let mut entry = entry.context("Corrupt tar entry in payload")?;
+        let Some(info) = entry_info(&entry)? else {
+            continue;
+        };
+        let sha256 = if info.kind == EntryKind::File {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut entry, &mut hasher)?;
+            Some(hex::encode(hasher.finalize()))
+        } else {
+            None
+        };
+        digests.insert(info.path.clone(), EntryDigest { info, sha256 });
+    }
+    Ok(digests)
+}
+
+/// `delta::tree_checksum` of a release, from its entry digests
+///
+/// Hard links count as copies of their target, as they end up on disk.
+pub fn tree_checksum(digests: &BTreeMap<PathBuf, EntryDigest>) -> String {
+    let entries = digests.iter()
+        .filter_map(|(path, entry)| {
+            let target = entry.info.link_target.as_deref()
+                .map(|target| target.strip_prefix(".").unwrap_or(target));
+            let tree_entry = match entry.info.kind {
+                EntryKind::File => TreeEntry::File(entry.sha256.clone()?),
+                EntryKind::Symlink => TreeEntry::Symlink(entry.info.link_target.clone()?),
+                EntryKind::HardLink => TreeEntry::File(digests.get(target?)?.sha256.clone()?),
+                _ => return None,
+            };
+            Some((path.clone(), tree_entry))
+        })
+        .collect();
+    lxe_common::delta::tree_checksum(&entries)
+}
+
+/// Contents of a delta from one release to the next
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct DeltaPlan {
+    /// Added and changed entries of the new release
+    pub include: BTreeSet<PathBuf>,
+
+    /// Entries of the old release to remove first: those the new release
+    /// dropped, those that changed type and replaced links
+    pub removed: Vec<PathBuf>,
+}
+
+pub fn plan(old: &BTreeMap<PathBuf, EntryDigest>, new: &BTreeMap<PathBuf, EntryDigest>) -> DeltaPlan {
+    let mut plan = DeltaPlan::default();
+    for (path, entry) in new {
+        match old.get(path) {
+            Some(old_entry) if old_entry == entry => {}
+            Some(old_entry) => {
+                if old_entry.info.kind != entry.info.kind {
+                    plan.removed.push(path.clone());
+                }
+                plan.include.insert(path.clone());
+            }
+            None => {
+                plan.include.insert(path.clone());
+            }
+        }
+    }
+
+    // The installed copy of a hard link doesn't share its target's new contents
+    let links: Vec<PathBuf> = new.values()
+        .filter(|entry| entry.info.kind == EntryKind::HardLink)
+        .filter(|entry| entry.info.link_target.as_deref()
+            .is_some_and(|target| plan.include.contains(target.strip_prefix(".").unwrap_or(target))))
+        .map(|entry| entry.info.path.clone())
+        .collect();
+    plan.include.extend(links);
+
+    // Links can't be created over existing ones
+    plan.removed.extend(plan.include.iter()
+        .filter(|path| new[*path].info.kind == EntryKind::Symlink || new[*path].info.kind == EntryKind::HardLink)
+        .filter(|path| old.contains_key(*path) && !plan.removed.contains(path))
+        .cloned()
+        .collect::<Vec<_>>());
+
+    plan.removed.extend(old.keys().filter(|path| !new.contains_key(*path)).cloned());
+    plan
+}
+
+/// Write the delta payload (zstd-compressed tar) for `plan` to `output`,
+/// copying the included entries from `new`
+pub fn write_payload(new: &PayloadInfo, plan: &DeltaPlan, output: &Path, level: i32) -> Result<()> {
+    let file = File::create(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut encoder = zstd::Encoder::new(file, level)
+        .context("Failed to compress with zstd")?;
+    let mut builder = tar::Builder::new(&mut encoder);
+
+    // The manifest comes first, so removals happen before anything is unpacked
+    let manifest = serde_json::to_vec(&DeltaManifest { removed: plan.removed.clone() })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, Path::new(".").join(DELTA_MANIFEST), &manifest[..])?;
+
+    let decoder = builder::decoder(open_payload_reader(new)?, &new.metadata.compressor)?;
+    let mut archive = tar::Archive::new(decoder);
+    for entry in archive.entries().context("Failed to read payload")? {
+        let mut entry = entry.context("Corrupt tar entry in payload")?;
+        let Some(info) = entry_info(&entry)?.filter(|info| plan.include.contains(&info.path)) else {
+            continue;
+        };
+
+        // Fresh headers, so sparse files are stored expanded
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(info.mode);
+        header.set_mtime(entry.header().mtime()?);
+        let name = Path::new(".").join(&info.path);
+        match (info.kind, &info.link_target) {
+            (EntryKind::File, _) => {
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(info.size);
+                builder.append_data(&mut header, &name, &mut entry)?;
+            }
+            (EntryKind::Directory, _) => {
+                header.set_entry_type(tar::EntryType::Directory);
+                header.set_size(0);
+                builder.append_data(&mut header, &name, std::io::empty())?;
+            }
+            (EntryKind::Symlink, Some(target)) | (EntryKind::HardLink, Some(target)) => {
+                header.set_entry_type(if info.kind == EntryKind::Symlink {
+                    tar::EntryType::Symlink
+                } else {
+                    tar::EntryType::Link
+                });
+                header.set_size(0);
+                builder.append_link(&mut header, &name, target)?;
+            }
+            _ => {}
+        }
+    }
+
+    builder.finish().context("Failed to finish tar archive")?;
+    drop(builder);
+    encoder.finish().context("Failed to compress with zstd")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, sha256: &str) -> (PathBuf, EntryDigest) {
+        let info = EntryInfo { path: path.into(), size: 1, mode: 0o644, kind: EntryKind::File, link_target: None };
+        (path.into(), EntryDigest { info, sha256: Some(sha256.into()) })
+    }
+
+    #[test]
+    fn test_plan() {
+        let mut link = file("lib/libfoo.so.1", "");
+        link.1.info.kind = EntryKind::HardLink;
+        link.1.info.link_target = Some("./lib/libfoo.so".into());
+        link.1.sha256 = None;
+
+        let old = BTreeMap::from([file("app", "a1"), file("lib/libfoo.so", "f1"), link.clone(), file("README", "r1")]);
+        let new = BTreeMap::from([file("app", "a1"), file("lib/libfoo.so", "f2"), link, file("NEWS", "n1")]);
+        let plan = plan(&old, &new);
+        assert_eq!(plan.include, BTreeSet::from(["NEWS".into(), "lib/libfoo.so".into(), "lib/libfoo.so.1".into()]));
+        assert_eq!(plan.removed, [PathBuf::from("lib/libfoo.so.1"), PathBuf::from("README")]);
+
+        // The link counts as a copy of its target
+        let with_copy = BTreeMap::from([file("app", "a1"), file("lib/libfoo.so", "f2"), file("lib/libfoo.so.1", "f2"), file("NEWS", "n1")]);
+        assert_eq!(tree_checksum(&new), tree_checksum(&with_copy));
+    }
+}
@@ -1,9 +1,10 @@
 //! Project Detection Module
 //!
 //! Auto-detects project metadata from common configuration files.
-//! Supports: Rust, Node.js, Tauri, Electron, Python, monorepos
+//! Supports: Rust, Node.js, Tauri, Electron, Python, .NET, Flutter, Go, monorepos
 
-use std::path::Path;
+use lxe_common::metadata::LxeMetadata;
+use std::path::{Path, PathBuf};
 use std::fs;
 
 /// Detected project information
@@ -16,6 +17,49 @@ pub struct DetectedProject {
     pub icon: Option<String>,
     pub build_script: Option<String>,
     pub build_input: Option<String>,
+    /// Most specific project type found
+    pub kind: Option<ProjectKind>,
+    confidence: DetectionConfidence,
+}
+
+/// Project types detection recognizes, least specific first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ProjectKind {
+    Python,
+    Node,
+    Rust,
+    Go,
+    Dotnet,
+    Flutter,
+    Electron,
+    Tauri,
+}
+
+impl std::fmt::Display for ProjectKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ProjectKind::Python => "Python",
+            ProjectKind::Node => "Node.js",
+            ProjectKind::Rust => "Rust",
+            ProjectKind::Go => "Go",
+            ProjectKind::Dotnet => ".NET",
+            ProjectKind::Flutter => "Flutter",
+            ProjectKind::Electron => "Electron",
+            ProjectKind::Tauri => "Tauri",
+        })
+    }
+}
+
+/// How much the detected values can be trusted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DetectionConfidence {
+    /// Nothing found; the name was guessed from the folder name
+    #[default]
+    Low,
+    /// Found generic config parsed on a best-effort basis (setup.py, setup.cfg)
+    Medium,
+    /// Found project files of a specific toolchain
+    High,
 }
 
 impl DetectedProject {
@@ -73,6 +117,21 @@ impl DetectedProject {
             detected.merge(info);
         }
         
+        // 8. .NET (*.csproj)
+        if let Some(info) = detect_from_csproj(dir) {
+            detected.merge(info);
+        }
+        
+        // 9. Flutter (pubspec.yaml)
+        if let Some(info) = detect_from_pubspec(dir) {
+            detected.merge(info);
+        }
+        
+        // 10. Go (go.mod with a main package)
+        if let Some(info) = detect_from_go_mod(dir) {
+            detected.merge(info);
+        }
+        
         // Fallback to folder name
         if detected.name.is_none() {
             detected.name = dir.file_name()
@@ -102,6 +161,23 @@ impl DetectedProject {
         if self.icon.is_none() { self.icon = other.icon; }
         if self.build_script.is_none() { self.build_script = other.build_script; }
         if self.build_input.is_none() { self.build_input = other.build_input; }
+        self.kind = self.kind.max(other.kind);
+        self.confidence = self.confidence.max(other.confidence);
+    }
+    
+    /// How the values were found
+    pub fn confidence(&self) -> DetectionConfidence {
+        self.confidence
+    }
+    
+    /// Prefer the values of an already built package over detected ones
+    pub fn apply_package(&mut self, metadata: &LxeMetadata) {
+        self.name = Some(metadata.name.clone());
+        self.version = Some(metadata.version.clone());
+        self.executable = Some(metadata.exec.clone());
+        if metadata.icon.is_some() { self.icon = metadata.icon.clone(); }
+        if metadata.description.is_some() { self.description = metadata.description.clone(); }
+        self.confidence = DetectionConfidence::High;
     }
     
     /// Check if detection found anything meaningful
@@ -182,6 +258,8 @@ fi && \
 cp src-tauri/icons/128x128.png dist/icon.png 2>/dev/null || \
 cp src-tauri/icons/icon.png dist/icon.png 2>/dev/null || echo "No icon""#, name)),
         build_input: Some("./dist".to_string()),
+        kind: Some(ProjectKind::Tauri),
+        confidence: DetectionConfidence::High,
     })
 }
 
@@ -202,6 +280,8 @@ fn detect_from_cargo_toml(dir: &Path) -> Option<DetectedProject> {
         icon: None,
         build_script: None,
         build_input: None,
+        kind: Some(ProjectKind::Rust),
+        confidence: DetectionConfidence::High,
     })
 }
 
@@ -224,6 +304,8 @@ fn detect_from_package_json(dir: &Path) -> Option<DetectedProject> {
         icon: None,
         build_script: None,
         build_input: None,
+        kind: Some(ProjectKind::Node),
+        confidence: DetectionConfidence::High,
     })
 }
 
@@ -287,6 +369,8 @@ fn detect_from_monorepo(dir: &Path) -> Option<DetectedProject> {
                             icon: None,
                             build_script: None,
                             build_input: None,
+                            kind: Some(ProjectKind::Node),
+                            confidence: DetectionConfidence::High,
                         });
                     }
                 }
@@ -339,6 +423,8 @@ fn detect_from_electron_builder(dir: &Path) -> Option<DetectedProject> {
 cp -r release/linux-unpacked dist && \
 cp build/icon.png dist/ 2>/dev/null || echo "No icon""#.to_string()),
                         build_input: Some("./dist".to_string()),
+                        kind: Some(ProjectKind::Electron),
+                        confidence: DetectionConfidence::High,
                     });
                 }
             }
@@ -390,6 +476,8 @@ fn detect_from_tauri_conf(dir: &Path) -> Option<DetectedProject> {
 cp src-tauri/target/release/{} dist/ && \
 cp src-tauri/icons/128x128.png dist/icon.png 2>/dev/null || echo "No icon""#, n)),
                             build_input: Some("./dist".to_string()),
+                            kind: Some(ProjectKind::Tauri),
+                            confidence: DetectionConfidence::High,
                         });
                     }
                 }
@@ -437,6 +525,8 @@ pip install -q -r requirements.txt 2>/dev/null || true && \
 pyinstaller --onefile --name {} --clean main.py --distpath dist && \
 cp icon.png dist/ 2>/dev/null || echo "No icon""#, n)),
         build_input: Some("./dist".to_string()),
+        kind: Some(ProjectKind::Python),
+        confidence: DetectionConfidence::High,
     })
 }
 
@@ -492,6 +582,8 @@ pip install -q -r requirements.txt 2>/dev/null || true && \
 pyinstaller --onefile --name {} --clean main.py --distpath dist && \
 cp icon.png dist/ 2>/dev/null || echo "No icon""#, n)),
             build_input: Some("./dist".to_string()),
+            kind: Some(ProjectKind::Python),
+            confidence: DetectionConfidence::Medium,
         })
     } else {
         None
@@ -548,12 +640,270 @@ pip install -q -r requirements.txt 2>/dev/null || true && \
 pyinstaller --onefile --name {} --clean main.py --distpath dist && \
 cp icon.png dist/ 2>/dev/null || echo "No icon""#, n)),
             build_input: Some("./dist".to_string()),
+            kind: Some(ProjectKind::Python),
+            confidence: DetectionConfidence::Medium,
         })
     } else {
         None
     }
 }
 
+/// Flutter desktop app: the release bundle (binary, lib/, data/) is packaged as is
+fn detect_from_pubspec(dir: &Path) -> Option<DetectedProject> {
+    let content = fs::read_to_string(dir.join("pubspec.yaml")).ok()?;
+    let name = pubspec_field(&content, "name")?;
+    
+    Some(DetectedProject {
+        name: Some(to_title_case(&name)),
+        version: pubspec_field(&content, "version"),
+        description: pubspec_field(&content, "description"),
+        // Relative to the bundle, which is the build input
+        executable: Some(name),
+        icon: None,
+        build_script: Some("flutter build linux --release".to_string()),
+        build_input: Some("build/linux/x64/release/bundle".to_string()),
+        kind: Some(ProjectKind::Flutter),
+        confidence: DetectionConfidence::High,
+    })
+}
+
+/// .NET app: published self-contained, so the runtime ships in the package
+fn detect_from_csproj(dir: &Path) -> Option<DetectedProject> {
+    let path = find_csproj(dir)?;
+    let content = fs::read_to_string(&path).ok()?;
+    let name = csproj_assembly_name(&path, &content)?;
+    
+    // `dotnet publish` needs the project when the root holds a solution
+    let relative = path.strip_prefix(dir).ok()?;
+    let project = if relative.parent() == Some(Path::new("")) {
+        String::new()
+    } else {
+        format!("{} ", relative.display())
+    };
+    
+    Some(DetectedProject {
+        name: Some(to_title_case(&name)),
+        version: csproj_property(&content, "Version").or_else(|| csproj_property(&content, "VersionPrefix")),
+        description: csproj_property(&content, "Description"),
+        executable: Some(name),
+        icon: None,
+        build_script: Some(format!("dotnet publish {}-r linux-x64 --self-contained -c Release -o dist", project)),
+        build_input: Some("./dist".to_string()),
+        kind: Some(ProjectKind::Dotnet),
+        confidence: DetectionConfidence::High,
+    })
+}
+
+/// Go app: builds a static binary into dist/, or runs the Makefile's `build`
+/// target if there is one and the Makefile names its output directory
+fn detect_from_go_mod(dir: &Path) -> Option<DetectedProject> {
+    let name = go_module_name(dir)?;
+    let package = go_main_package(dir, &name)?;
+    
+    let (build, output_dir) = match make_output_dir(dir).filter(|_| has_make_target(dir, "build")) {
+        Some(output_dir) => ("make build".to_string(), output_dir),
+        None => (
+            format!(r#"CGO_ENABLED=0 go build -ldflags="-s -w" -o {} {}"#, shell_quote(&format!("dist/{}", name)), package),
+            "dist".to_string(),
+        ),
+    };
+    let icon = find_asset_icon(dir);
+    let build_script = match icon {
+        Some(ref icon) => format!("{} && cp {} {}", build, shell_quote(icon), shell_quote(&format!("{}/", output_dir))),
+        None => build,
+    };
+    
+    Some(DetectedProject {
+        name: Some(to_title_case(&name)),
+        version: None,
+        description: None,
+        executable: Some(name),
+        // Copied next to the binary by the build script
+        icon: icon.as_deref().and_then(|icon| Path::new(icon).file_name()).map(|f| f.to_string_lossy().to_string()),
+        build_script: Some(build_script),
+        build_input: Some(format!("./{}", output_dir)),
+        kind: Some(ProjectKind::Go),
+        confidence: DetectionConfidence::High,
+    })
+}
+
+/// `.lxe` files in `dir`, most recently modified first
+pub fn existing_packages(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut packages: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "lxe"))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    packages.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    packages.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Read the package name from a Rust project's Cargo.toml
+pub fn cargo_package_name(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    let toml: toml::Value = content.parse().ok()?;
+    
+    toml.get("package")?.get("name")?.as_str().map(String::from)
+}
+
+/// Read the package name from a Flutter project's pubspec.yaml
+pub fn pubspec_name(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("pubspec.yaml")).ok()?;
+    pubspec_field(&content, "name")
+}
+
+/// Binary name of a Go project: the last segment of the go.mod module path
+/// (`github.com/jesseduffield/lazygit` → `lazygit`), skipping a major
+/// version suffix like `/v2`
+pub fn go_module_name(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("go.mod")).ok()?;
+    let module = content.lines()
+        .find_map(|line| line.trim().strip_prefix("module "))?
+        .trim()
+        .trim_matches('"');
+    let mut segments = module.rsplit('/');
+    let last = segments.next()?;
+    let is_major_version = last.len() > 1 && last.starts_with('v') && last[1..].chars().all(|c| c.is_ascii_digit());
+    let name = if is_major_version { segments.next()? } else { last };
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Package path of a Go project's main package: the project root or `cmd/<name>`
+pub fn go_main_package(dir: &Path, name: &str) -> Option<String> {
+    if dir.join("main.go").is_file() {
+        Some(".".to_string())
+    } else if dir.join("cmd").join(name).join("main.go").is_file() {
+        Some(format!("./cmd/{}", name))
+    } else {
+        None
+    }
+}
+
+/// Whether the Makefile defines `target`
+fn has_make_target(dir: &Path, target: &str) -> bool {
+    let Ok(content) = fs::read_to_string(dir.join("Makefile")) else {
+        return false;
+    };
+    content.lines().any(|line| {
+        line.strip_prefix(target).is_some_and(|rest| rest.starts_with(':') && !rest.starts_with(":="))
+    })
+}
+
+/// Makefile variables that conventionally hold the build output directory
+const MAKE_OUTPUT_VARS: &[&str] = &["BUILD_DIR", "BUILDDIR", "OUTPUT_DIR", "OUT_DIR", "OUTDIR", "BIN_DIR", "BINDIR", "DIST_DIR", "DISTDIR"];
+
+/// Output directory the Makefile assigns to one of `MAKE_OUTPUT_VARS`,
+/// relative to `dir`; None if it has none or its value refers to other
+/// variables
+fn make_output_dir(dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(dir.join("Makefile")).ok()?;
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once('=')?;
+        let name = name.trim_end_matches([':', '?', '+']).trim();
+        let value = value.trim().trim_start_matches("./").trim_end_matches('/');
+        let usable = MAKE_OUTPUT_VARS.contains(&name)
+            && !value.is_empty()
+            && !value.contains('$')
+            && !Path::new(value).is_absolute()
+            && !value.split('/').any(|part| part == "..");
+        usable.then(|| value.to_string())
+    })
+}
+
+/// Quote `value` for `sh` if it contains anything but plain path characters
+fn shell_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./".contains(c)) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+/// First `.png` (or else `.svg`) in `assets/` or `resources/`
+fn find_asset_icon(dir: &Path) -> Option<String> {
+    for ext in ["png", "svg"] {
+        for folder in ["assets", "resources"] {
+            let Ok(entries) = fs::read_dir(dir.join(folder)) else {
+                continue;
+            };
+            let mut icons: Vec<String> = entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == ext))
+                .filter_map(|path| path.file_name().map(|f| format!("{}/{}", folder, f.to_string_lossy())))
+                .collect();
+            icons.sort();
+            if let Some(icon) = icons.into_iter().next() {
+                return Some(icon);
+            }
+        }
+    }
+    None
+}
+
+/// Executable name of a .NET project (`<AssemblyName>`, else the project
+/// file name)
+pub fn dotnet_assembly_name(dir: &Path) -> Option<String> {
+    let path = find_csproj(dir)?;
+    let content = fs::read_to_string(&path).ok()?;
+    csproj_assembly_name(&path, &content)
+}
+
+/// The app's .csproj in `dir` or a few levels below, skipping test projects
+/// and build output. An `<OutputType>Exe</OutputType>` project wins over
+/// libraries.
+fn find_csproj(dir: &Path) -> Option<PathBuf> {
+    let mut projects: Vec<PathBuf> = walkdir::WalkDir::new(dir)
+        .max_depth(3)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !entry.file_type().is_dir()
+                || !(name.starts_with('.') || ["test", "tests", "spec", "bin", "obj", "node_modules"].contains(&name.to_lowercase().as_str()))
+        })
+        .flatten()
+        .map(|entry| entry.into_path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "csproj"))
+        .collect();
+    let exe = projects.iter().position(|path| {
+        fs::read_to_string(path).ok()
+            .and_then(|content| csproj_property(&content, "OutputType"))
+            .is_some_and(|kind| kind.eq_ignore_ascii_case("Exe") || kind.eq_ignore_ascii_case("WinExe"))
+    });
+    match exe {
+        Some(i) => Some(projects.swap_remove(i)),
+        None => projects.into_iter().next(),
+    }
+}
+
+fn csproj_assembly_name(path: &Path, content: &str) -> Option<String> {
+    let name = csproj_property(content, "AssemblyName")
+        .or_else(|| path.file_stem().map(|s| s.to_string_lossy().to_string()))?;
+    Some(name.trim_end_matches(".dll").trim_end_matches(".exe").to_string())
+}
+
+/// Text of the first `<Tag>` property of a .csproj
+fn csproj_property(content: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let start = content.find(&open)? + open.len();
+    let end = start + content[start..].find(&format!("</{}>", tag))?;
+    let value = content[start..end].trim();
+    (!value.is_empty() && !value.contains('$')).then(|| value.to_string())
+}
+
+/// Top-level scalar `key` of a pubspec.yaml (nested keys are indented)
+fn pubspec_field(content: &str, key: &str) -> Option<String> {
+    content.lines()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+        .map(|value| value.trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|value| !value.is_empty())
+}
+
 /// Extract string value from Python-style assignment
 fn extract_string_value(line: &str) -> Option<String> {
     // Find the value part after =
@@ -777,4 +1127,130 @@ mod tests {
         });
         assert!(!is_monorepo_root(&app_pkg));
     }
+    
+    #[test]
+    fn test_apply_package() {
+        let mut detected = DetectedProject {
+            name: Some("detected".into()),
+            icon: Some("assets/icon.png".into()),
+            ..Default::default()
+        };
+        let metadata = LxeMetadata::builder("com.example.App", "App", "1.0.0", "bin/app", 0, "")
+            .description("An app")
+            .build()
+            .unwrap();
+        detected.apply_package(&metadata);
+        
+        assert_eq!(detected.name.as_deref(), Some("App"));
+        assert_eq!(detected.executable.as_deref(), Some("bin/app"));
+        assert_eq!(detected.description.as_deref(), Some("An app"));
+        // Packages without an icon keep the detected one
+        assert_eq!(detected.icon.as_deref(), Some("assets/icon.png"));
+    }
+    
+    #[test]
+    fn test_pubspec_name() {
+        let dir = std::env::temp_dir().join(format!("lxe-detect-pubspec-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("pubspec.yaml"), "name: hello_flutter\nversion: 1.2.0\n").unwrap();
+        
+        assert_eq!(pubspec_name(&dir), Some("hello_flutter".to_string()));
+        
+        fs::write(dir.join("pubspec.yaml"),
+            "name: hello_flutter\ndescription: \"Says hello\"\nversion: 1.2.0+3\n\nflutter:\n  name: nested\n").unwrap();
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!(detected.kind, Some(ProjectKind::Flutter));
+        assert_eq!(detected.name.as_deref(), Some("Hello Flutter"));
+        assert_eq!(detected.version.as_deref(), Some("1.2.0+3"));
+        assert_eq!(detected.description.as_deref(), Some("Says hello"));
+        assert_eq!(detected.executable.as_deref(), Some("hello_flutter"));
+        assert_eq!(detected.build_input.as_deref(), Some("build/linux/x64/release/bundle"));
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_detect_go() {
+        let dir = std::env::temp_dir().join(format!("lxe-detect-go-{}", std::process::id()));
+        fs::create_dir_all(dir.join("cmd/glow")).unwrap();
+        fs::write(dir.join("go.mod"), "module github.com/charmbracelet/glow/v2\n\ngo 1.22\n").unwrap();
+        
+        // A library without a main package isn't an app
+        assert!(detect_from_go_mod(&dir).is_none());
+        
+        fs::write(dir.join("cmd/glow/main.go"), "package main\n").unwrap();
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!(detected.kind, Some(ProjectKind::Go));
+        assert_eq!(detected.name.as_deref(), Some("Glow"));
+        assert_eq!(detected.executable.as_deref(), Some("glow"));
+        assert_eq!(detected.build_script.as_deref(),
+            Some(r#"CGO_ENABLED=0 go build -ldflags="-s -w" -o dist/glow ./cmd/glow"#));
+        
+        fs::create_dir_all(dir.join("assets")).unwrap();
+        fs::write(dir.join("assets/logo.svg"), "<svg/>").unwrap();
+        // Without an output directory variable, make's output can't be found
+        fs::write(dir.join("Makefile"), "VERSION := 1\nbuild: deps\n\tgo build -o dist/glow ./cmd/glow\n").unwrap();
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!(detected.build_script.as_deref(),
+            Some(r#"CGO_ENABLED=0 go build -ldflags="-s -w" -o dist/glow ./cmd/glow && cp assets/logo.svg dist/"#));
+        assert_eq!(detected.icon.as_deref(), Some("logo.svg"));
+        
+        fs::write(dir.join("assets/my logo.svg"), "<svg/>").unwrap();
+        fs::remove_file(dir.join("assets/logo.svg")).unwrap();
+        fs::write(dir.join("Makefile"), "BUILD_DIR ?= ./out/bin/\nbuild: deps\n\tgo build -o $(BUILD_DIR)/glow ./cmd/glow\n").unwrap();
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!(detected.build_script.as_deref(), Some("make build && cp 'assets/my logo.svg' out/bin/"));
+        assert_eq!(detected.build_input.as_deref(), Some("./out/bin"));
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_detect_csproj() {
+        let dir = std::env::temp_dir().join(format!("lxe-detect-csproj-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src/Notes")).unwrap();
+        fs::create_dir_all(dir.join("tests/Notes.Tests")).unwrap();
+        fs::write(dir.join("tests/Notes.Tests/Notes.Tests.csproj"),
+            "<Project><PropertyGroup><OutputType>Exe</OutputType></PropertyGroup></Project>").unwrap();
+        fs::write(dir.join("src/Notes/Notes.csproj"), r#"<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup>
+    <OutputType>Exe</OutputType>
+    <AssemblyName>notes-app</AssemblyName>
+    <Version>2.1.0</Version>
+    <Description>Takes notes</Description>
+  </PropertyGroup>
+</Project>"#).unwrap();
+        
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!(detected.kind, Some(ProjectKind::Dotnet));
+        assert_eq!(detected.name.as_deref(), Some("Notes App"));
+        assert_eq!(detected.version.as_deref(), Some("2.1.0"));
+        assert_eq!(detected.description.as_deref(), Some("Takes notes"));
+        assert_eq!(detected.executable.as_deref(), Some("notes-app"));
+        assert_eq!(detected.build_script.as_deref(),
+            Some("dotnet publish src/Notes/Notes.csproj -r linux-x64 --self-contained -c Release -o dist"));
+        
+        // Only test projects: nothing to package
+        fs::remove_dir_all(dir.join("src")).unwrap();
+        assert!(detect_from_csproj(&dir).is_none());
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
+    
+    #[test]
+    fn test_detection_confidence() {
+        let dir = std::env::temp_dir().join(format!("lxe-detect-confidence-{}", std::process::id()));
+        fs::create_dir_all(dir.join("src-tauri")).unwrap();
+        assert_eq!(DetectedProject::detect(&dir).confidence(), DetectionConfidence::Low);
+        
+        fs::write(dir.join("setup.cfg"), "[metadata]\nname = photo-tool\n").unwrap();
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!((detected.kind, detected.confidence()), (Some(ProjectKind::Python), DetectionConfidence::Medium));
+        
+        fs::write(dir.join("src-tauri/Cargo.toml"), "[package]\nname = \"photo-tool\"\n").unwrap();
+        let detected = DetectedProject::detect(&dir);
+        assert_eq!((detected.kind, detected.confidence()), (Some(ProjectKind::Tauri), DetectionConfidence::High));
+        
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
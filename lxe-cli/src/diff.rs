@@ -0,0 +1,102 @@
+//! Package Comparison (`lxe diff`)
+//!
+//! Compares the metadata and payload listings of two packages, e.g. two
+//! releases, to catch files that were added or dropped by accident. Only the
+//! tar headers are compared (path, type, size, mode, link target); file
+//! contents are not.
+
+use lxe_common::metadata::LxeMetadata;
+use lxe_common::payload::EntryInfo;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Metadata fields listed before the others when they changed
+const KEY_FIELDS: &[&str] = &["version", "arch", "payload_checksum"];
+
+/// Difference of one payload path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileChange {
+    Added(EntryInfo),
+    Removed(EntryInfo),
+    Changed { old: EntryInfo, new: EntryInfo },
+}
+
+impl FileChange {
+    pub fn path(&self) -> &std::path::Path {
+        match self {
+            Self::Added(entry) | Self::Removed(entry) => &entry.path,
+            Self::Changed { new, .. } => &new.path,
+        }
+    }
+}
+
+/// Changed, added and removed paths, sorted by path
+pub fn diff_entries(old: &[EntryInfo], new: &[EntryInfo]) -> Vec<FileChange> {
+    let old: BTreeMap<&PathBuf, &EntryInfo> = old.iter().map(|e| (&e.path, e)).collect();
+    let new: BTreeMap<&PathBuf, &EntryInfo> = new.iter().map(|e| (&e.path, e)).collect();
+
+    let mut changes: Vec<FileChange> = old.iter()
+        .filter_map(|(path, old_entry)| match new.get(path) {
+            None => Some(FileChange::Removed((*old_entry).clone())),
+            Some(new_entry) if new_entry != old_entry => Some(FileChange::Changed {
+                old: (*old_entry).clone(),
+                new: (*new_entry).clone(),
+            }),
+            Some(_) => None,
+        })
+        .collect();
+    changes.extend(new.iter()
+        .filter(|(path, _)| !old.contains_key(*path))
+        .map(|(_, entry)| FileChange::Added((*entry).clone())));
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
+
+/// `(field, old value, new value)` of every changed metadata field, with
+/// version, arch and checksum first
+pub fn metadata_changes(old: &LxeMetadata, new: &LxeMetadata) -> Vec<(String, String, String)> {
+    let old: BTreeMap<String, String> = crate::inspect::metadata_rows(old).into_iter().collect();
+    let new: BTreeMap<String, String> = crate::inspect::metadata_rows(new).into_iter().collect();
+
+    let mut fields: Vec<&String> = old.keys().chain(new.keys()).collect();
+    fields.sort_by_key(|field| (KEY_FIELDS.iter().position(|k| k == field).unwrap_or(KEY_FIELDS.len()), *field));
+    fields.dedup();
+
+    let unset = "-".to_string();
+    fields.into_iter()
+        .map(|field| {
+            let old_value = old.get(field).unwrap_or(&unset);
+            let new_value = new.get(field).unwrap_or(&unset);
+            (field.clone(), old_value.clone(), new_value.clone())
+        })
+        .filter(|(_, old_value, new_value)| old_value != new_value)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lxe_common::payload::EntryKind;
+
+    fn entry(path: &str, size: u64, mode: u32) -> EntryInfo {
+        EntryInfo { path: path.into(), size, mode, kind: EntryKind::File, link_target: None }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = [entry("app", 100, 0o755), entry("lib/old.so", 10, 0o644), entry("README", 5, 0o644)];
+        let new = [entry("app", 120, 0o755), entry("lib/new.so", 10, 0o644), entry("README", 5, 0o644)];
+        let changes = diff_entries(&old, &new);
+        assert_eq!(changes, [
+            FileChange::Changed { old: old[0].clone(), new: new[0].clone() },
+            FileChange::Added(new[1].clone()),
+            FileChange::Removed(old[1].clone()),
+        ]);
+
+        let old_meta = LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 0, "aa");
+        let mut new_meta = LxeMetadata::new("com.example.App", "App", "1.1.0", "app", 0, "bb");
+        new_meta.description = Some("An app".into());
+        let fields: Vec<String> = metadata_changes(&old_meta, &new_meta).into_iter().map(|(f, _, _)| f).collect();
+        assert_eq!(fields, ["version", "payload_checksum", "description"]);
+    }
+}
@@ -0,0 +1,232 @@
+//! Environment Diagnostics (`lxe doctor`)
+//!
+//! Unlike `lxe status`, which reports the state of the installation, doctor
+//! looks for known problems and says how to fix each one. Any failed check
+//! makes the command exit with status 1, so it can gate CI or onboarding
+//! scripts. Warnings are for things that only matter to some setups.
+
+use lxe_common::manifest::InstallManifest;
+use lxe_common::paths;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// How to fix it (not set for passed checks)
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Pass, detail: detail.into(), hint: None }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Warn, detail: detail.into(), hint: Some(hint.into()) }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name, status: CheckStatus::Fail, detail: detail.into(), hint: Some(hint.into()) }
+    }
+}
+
+/// Run every check; `runtime_path` is where `lxe runtime download` puts the runtime
+pub fn run_checks(runtime_path: &Path) -> Vec<Check> {
+    let mut applications_dirs: Vec<PathBuf> = paths::user::applications_dir().into_iter().collect();
+    applications_dirs.push(paths::system::applications_dir());
+
+    vec![
+        check_path(),
+        check_desktop_database(),
+        check_polkit_policy(),
+        check_manifests(),
+        check_installed_files(),
+        check_runtime(runtime_path),
+        check_desktop_entries(&applications_dirs),
+    ]
+}
+
+fn check_path() -> Check {
+    const NAME: &str = "~/.local/bin in PATH";
+    if crate::status::bin_dir_in_path() {
+        Check::pass(NAME, "yes")
+    } else {
+        Check::fail(NAME, "no, installed commands can't be run by name",
+            "add `export PATH=\"$HOME/.local/bin:$PATH\"` to your shell profile")
+    }
+}
+
+fn check_desktop_database() -> Check {
+    const NAME: &str = "update-desktop-database";
+    match find_in_path("update-desktop-database") {
+        Some(path) => Check::pass(NAME, path.display().to_string()),
+        None => Check::warn(NAME, "not found, new apps may not show up until the next login",
+            "install desktop-file-utils"),
+    }
+}
+
+fn check_polkit_policy() -> Check {
+    const NAME: &str = "polkit policy";
+    if Path::new(paths::lxe::POLKIT_POLICY_PATH).exists() {
+        Check::pass(NAME, paths::lxe::POLKIT_POLICY_PATH)
+    } else {
+        Check::warn(NAME, "not installed, system-wide installs need root",
+            "sudo ./app.lxe --install-policy (see `lxe help polkit`)")
+    }
+}
+
+fn check_manifests() -> Check {
+    const NAME: &str = "Install manifests";
+    let manifests = match InstallManifest::load_all() {
+        Ok(manifests) => manifests,
+        Err(e) => return Check::fail(NAME, format!("unreadable: {:#}", e),
+            format!("fix or remove the files in {}", InstallManifest::manifests_dir().display())),
+    };
+
+    let orphaned: Vec<String> = manifests.iter()
+        .filter(|m| m.files.iter().any(|f| !Path::new(f).exists()))
+        .map(|m| m.app_id.clone())
+        .collect();
+    if orphaned.is_empty() {
+        Check::pass(NAME, format!("{} app(s), all files present", manifests.len()))
+    } else {
+        Check::warn(NAME, format!("missing files of {}", orphaned.join(", ")),
+            "reinstall the app, or `lxe uninstall <id>` to drop its manifest")
+    }
+}
+
+/// Sizes only, so doctor stays fast; `lxe verify-install` compares contents
+fn check_installed_files() -> Check {
+    const NAME: &str = "Installed files";
+    let Ok(manifests) = InstallManifest::load_all() else {
+        return Check::pass(NAME, "no readable manifests");
+    };
+    let recorded: Vec<&InstallManifest> = manifests.iter().filter(|m| !m.file_records.is_empty()).collect();
+    let damaged: Vec<String> = recorded.iter()
+        .filter(|m| !m.verify_files(false).is_intact())
+        .map(|m| m.app_id.clone())
+        .collect();
+    if damaged.is_empty() {
+        Check::pass(NAME, format!("{} app(s) with recorded checksums unchanged", recorded.len()))
+    } else {
+        Check::fail(NAME, format!("files of {} are missing or changed size", damaged.join(", ")),
+            "`lxe verify-install <id>` lists them; reinstall the app to repair it")
+    }
+}
+
+fn check_runtime(runtime_path: &Path) -> Check {
+    const NAME: &str = "LXE runtime";
+    match std::fs::metadata(runtime_path) {
+        Err(_) => Check::warn(NAME, format!("not installed ({})", runtime_path.display()),
+            "lxe runtime download"),
+        Ok(metadata) if metadata.permissions().mode() & 0o111 == 0 => Check::fail(NAME,
+            format!("{} is not executable", runtime_path.display()),
+            format!("chmod +x {}", runtime_path.display())),
+        Ok(_) => Check::pass(NAME, runtime_path.display().to_string()),
+    }
+}
+
+fn check_desktop_entries(applications_dirs: &[PathBuf]) -> Check {
+    const NAME: &str = "Desktop entries";
+    let mut count = 0;
+    let mut broken = Vec::new();
+    for dir in applications_dirs {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "desktop") {
+                continue;
+            }
+            let Ok(contents) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if !contents.lines().any(|line| line.starts_with("X-LXE-AppId=")) {
+                continue;
+            }
+            count += 1;
+            if desktop_exec(&contents).is_some_and(|exec| !exec_exists(exec)) {
+                broken.push(path.display().to_string());
+            }
+        }
+    }
+
+    if broken.is_empty() {
+        Check::pass(NAME, format!("{} LXE entr{}, all executables present", count, if count == 1 { "y" } else { "ies" }))
+    } else {
+        Check::fail(NAME, format!("missing executable in {}", broken.join(", ")),
+            "reinstall the app, or `lxe uninstall <id>` to remove it")
+    }
+}
+
+/// `Exec=` of the `[Desktop Entry]` section (actions come after it)
+fn desktop_exec(contents: &str) -> Option<&str> {
+    contents.lines()
+        .take_while(|line| !line.starts_with("[Desktop Action"))
+        .find_map(|line| line.strip_prefix("Exec="))
+}
+
+/// Whether the command of an `Exec=` value exists; LXE writes the path
+/// unquoted, so a path with spaces is tried as a whole first
+fn exec_exists(exec: &str) -> bool {
+    let exec = exec.trim();
+    if Path::new(exec).is_file() {
+        return true;
+    }
+    let command = exec.split_whitespace().next().unwrap_or_default();
+    if command.contains('/') {
+        Path::new(command).is_file()
+    } else {
+        find_in_path(command).is_some()
+    }
+}
+
+fn find_in_path(command: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_entries() {
+        let dir = std::env::temp_dir().join(format!("lxe-doctor-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("ok.desktop"),
+            "[Desktop Entry]\nExec=sh --flag\nX-LXE-AppId=a.b\n\n[Desktop Action Uninstall]\nExec=/missing/lxe-runtime\n").unwrap();
+        std::fs::write(dir.join("other.desktop"), "[Desktop Entry]\nExec=/missing/app\n").unwrap();
+
+        let check = check_desktop_entries(std::slice::from_ref(&dir));
+        assert_eq!(check.status, CheckStatus::Pass);
+        assert!(check.detail.starts_with("1 LXE entry"));
+
+        std::fs::write(dir.join("broken.desktop"), "[Desktop Entry]\nExec=/missing/app %U\nX-LXE-AppId=c.d\n").unwrap();
+        let check = check_desktop_entries(std::slice::from_ref(&dir));
+        assert_eq!(check.status, CheckStatus::Fail);
+        assert!(check.detail.contains("broken.desktop"));
+
+        let runtime = dir.join("lxe-runtime");
+        std::fs::write(&runtime, b"").unwrap();
+        assert_eq!(check_runtime(&runtime).status, CheckStatus::Fail);
+        std::fs::set_permissions(&runtime, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(check_runtime(&runtime).status, CheckStatus::Pass);
+        assert_eq!(check_runtime(&dir.join("missing")).status, CheckStatus::Warn);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
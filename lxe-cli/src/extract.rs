@@ -0,0 +1,143 @@
+//! Payload Extraction (`lxe extract`)
+//!
+//! Unpacks a package's files into a directory without installing it: no
+//! desktop entry, symlinks or manifest. `--strip-components` works like GNU
+//! tar's, dropping leading directories (the payload's own `./` doesn't
+//! count).
+//!
+//! Entries are checked like `tar`'s `unpack_in` would (which can't be used
+//! itself, as it ignores the stripped path): nothing is written below a
+//! symlink or outside `output`, and hard links may only point to files
+//! extracted before them.
+
+use anyhow::{Context, Result};
+use lxe_common::builder;
+use lxe_common::payload::{open_payload_reader, PayloadInfo};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Unpack the payload into `output`, calling `on_entry` with each path
+/// before it is written; returns the number of entries unpacked
+pub fn extract(
+    info: &PayloadInfo,
+    output: &Path,
+    strip: usize,
+    mut on_entry: impl FnMut(&Path),
+) -> Result<usize> {
+    fs::create_dir_all(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let output = &output.canonicalize()
+        .with_context(|| format!("Failed to resolve {}", output.display()))?;
+    let decoder = builder::decoder(open_payload_reader(info)?, &info.metadata.compressor)?;
+    let mut archive = tar::Archive::new(decoder);
+
+    let mut files = HashSet::new();
+    let mut count = 0;
+    for entry in archive.entries().context("Failed to read payload")? {
+        let mut entry = entry.context("Corrupt tar entry in payload")?;
+        let path = entry.path()?.into_owned();
+        let Some(relative) = strip_components(&path, strip) else {
+            continue;
+        };
+        on_entry(&relative);
+
+        let target = safe_target(output, &relative)?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() && fs::symlink_metadata(&target).is_ok_and(|meta| meta.file_type().is_symlink()) {
+            anyhow::bail!("Refusing to extract the directory {} over a symlink", relative.display());
+        }
+        if entry_type.is_hard_link() {
+            // Link targets are archive paths, stripped like the entry itself
+            let source = entry.link_name()?
+                .and_then(|source| strip_components(&source, strip))
+                .filter(|source| files.contains(source))
+                .with_context(|| format!("Hard link target of {} is not an extracted file", path.display()))?;
+            fs::hard_link(output.join(source), &target)
+                .with_context(|| format!("Failed to create {}", target.display()))?;
+        } else {
+            // Files and symlinks replace whatever is at `target`, they are
+            // never written through it
+            entry.unpack(&target)
+                .with_context(|| format!("Failed to extract {}", relative.display()))?;
+        }
+        if entry_type.is_file() {
+            files.insert(relative);
+        } else {
+            files.remove(&relative);
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Where `relative` is written in `output` (canonical); its parent
+/// directories are created, and none of them may be a symlink
+fn safe_target(output: &Path, relative: &Path) -> Result<PathBuf> {
+    let target = output.join(relative);
+    let mut dir = output.to_path_buf();
+    if let Some(parent) = relative.parent() {
+        for component in parent.components() {
+            dir.push(component);
+            match fs::symlink_metadata(&dir) {
+                Ok(meta) if meta.file_type().is_symlink() => anyhow::bail!(
+                    "Refusing to extract {} through the symlink {}",
+                    relative.display(),
+                    dir.display()
+                ),
+                Ok(_) => {}
+                Err(_) => fs::create_dir(&dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?,
+            }
+        }
+    }
+    if !dir.canonicalize()?.starts_with(output) {
+        anyhow::bail!("Refusing to extract {} outside {}", relative.display(), output.display());
+    }
+    Ok(target)
+}
+
+/// `path` without its first `strip` directories; `None` for entries that
+/// are stripped entirely or would leave the output directory
+pub fn strip_components(path: &Path, strip: usize) -> Option<PathBuf> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    let stripped: PathBuf = parts.into_iter().skip(strip).collect();
+    (!stripped.as_os_str().is_empty()).then_some(stripped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_components() {
+        assert_eq!(strip_components(Path::new("./app/bin/tool"), 0), Some(PathBuf::from("app/bin/tool")));
+        assert_eq!(strip_components(Path::new("./app/bin/tool"), 1), Some(PathBuf::from("bin/tool")));
+        assert_eq!(strip_components(Path::new("./app"), 1), None);
+        assert_eq!(strip_components(Path::new("."), 0), None);
+        assert_eq!(strip_components(Path::new("../etc/passwd"), 0), None);
+    }
+
+    #[test]
+    fn test_safe_target() {
+        let outside = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let output = output.path().canonicalize().unwrap();
+        std::os::unix::fs::symlink(outside.path(), output.join("link")).unwrap();
+
+        assert_eq!(safe_target(&output, Path::new("a/b/file")).unwrap(), output.join("a/b/file"));
+        assert!(output.join("a/b").is_dir());
+        // The link itself may be replaced, but not written through
+        assert_eq!(safe_target(&output, Path::new("link")).unwrap(), output.join("link"));
+        assert!(safe_target(&output, Path::new("link/file")).is_err());
+        assert!(safe_target(&output, Path::new("link/sub/file")).is_err());
+        assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
+}
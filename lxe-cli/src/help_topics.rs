@@ -0,0 +1,249 @@
+//! Help Topics (`lxe help <topic>`)
+//!
+//! Longer explanations than fit in `--help`. Each paragraph of a topic is a
+//! single line here and is wrapped to the terminal when printed; lines
+//! starting with spaces keep their indentation (bullets wrap under their
+//! text).
+
+/// One `lxe help` topic
+pub struct Topic {
+    pub name: &'static str,
+    pub summary: &'static str,
+    pub text: &'static str,
+}
+
+pub const TOPICS: &[Topic] = &[
+    Topic { name: "format", summary: "Layout of a .lxe file", text: FORMAT },
+    Topic { name: "signing", summary: "Signing packages and verifying them", text: SIGNING },
+    Topic { name: "presets", summary: "Framework presets of lxe init", text: PRESETS },
+    Topic { name: "ci", summary: "Building packages in CI pipelines", text: CI },
+    Topic { name: "runtime", summary: "What the runtime does and how to manage it", text: RUNTIME },
+    Topic { name: "polkit", summary: "System-wide installation", text: POLKIT },
+    Topic { name: "hooks", summary: "Build hooks and install hooks", text: HOOKS },
+    Topic { name: "troubleshooting", summary: "Common problems and their solutions", text: TROUBLESHOOTING },
+];
+
+/// Width help text is wrapped at
+pub const WRAP_WIDTH: usize = 80;
+
+const FORMAT: &str = "\
+A .lxe file is the LXE runtime (an ELF executable) with the package appended:
+
+    [runtime][LXE_MAGIC][metadata_len:u32][metadata JSON][checksum:32][payload][footer]
+
+The footer is read from the end of the file. Version 1 is 16 bytes: the offset of the LXE_MAGIC header followed by LXE_MAGIC again. Version 2 adds extension fields before them, so readers of version 1 still find the header. Version 3, written by lxe build, is 40 bytes starting with LXE_MAGIC, the version and flags, followed by the metadata offset and length and the payload offset and length, so nothing has to be searched for. Packages without a footer are found by scanning for the header.
+
+The metadata JSON holds the app ID, name, version, executable, desktop integration, the payload checksum and the signature. The 32-byte checksum slot holds the start of the payload digest; the full hex digest is payload_checksum in the metadata (sha256, blake3 or sha512, see [build] checksum_algorithm).
+
+The payload is a tar archive of the build input, compressed with zstd or, with [build] compressor = \"lz4\", with LZ4 (recorded as compressor in the metadata).
+
+lxe build also records the offsets of metadata and payload in a 256-byte stub in the runtime's __lxe_meta_stub ELF section, so tools find the metadata from the section headers alone.
+
+Check a package against the specification with: lxe conformance <file>";
+
+const SIGNING: &str = "\
+Packages are signed with Ed25519 (or RSA where policy requires it).
+
+    lxe key generate                        # creates lxe-signing.key
+    lxe key generate --algorithm rsa4096    # RSA instead (PEM)
+    lxe key inspect lxe-signing.key         # algorithm and public key
+
+Point lxe.toml at the key and every build is signed:
+
+    [security]
+    key = \"lxe-signing.key\"
+
+The signature covers the metadata and the payload checksum, so neither can be changed without invalidating it. The public key is stored in the package.
+
+To sign a package that was built without a key: lxe sign <file> --key lxe-signing.key
+
+Verify a package with: lxe verify <file>
+
+Any valid signature proves the package wasn't changed, but only keys in your trust store (~/.config/lxe/trusted-keys.toml) identify the publisher. Several keys can be trusted for the same apps, e.g. while rotating keys:
+
+    lxe key trust <public-key> --app-id 'com.acme.*'
+
+How the installer treats unsigned packages is set by signature_policy in ~/.config/lxe/config.toml: require-signed, warn-unsigned (default), tofu (ask once per app ID), allow-all or trusted-only (signed by a key trusted for the app ID).
+
+Keep the private key out of version control; in CI, write it from a secret variable before lxe build.";
+
+const PRESETS: &str = "\
+lxe init --preset <name> writes an lxe.toml with the build script and input directory of a framework:
+
+  - tauri: copies the release binary and icon from src-tauri/ into dist/
+  - electron: packages release/linux-unpacked from electron-builder
+  - python: builds a single-file executable with PyInstaller
+  - flutter: packages build/linux/x64/release/bundle (executable from pubspec.yaml)
+  - go: builds a static binary into dist/ with go build (executable from go.mod)
+  - dotnet: publishes a self-contained linux-x64 build into dist/ (executable from the .csproj)
+  - rust-xtask: also scaffolds xtask/ so cargo xtask dist builds and packages the app
+
+Without a preset, lxe init detects the project (Cargo.toml, package.json, tauri.conf.json, pyproject.toml, pubspec.yaml, go.mod, *.csproj, ...) and asks for each value; lxe init --detect takes the detected values without asking.";
+
+const CI: &str = "\
+lxe is a static binary, so it runs in any CI image:
+
+    curl -fsSL https://raw.githubusercontent.com/lxe-core/lxe/main/install.sh | bash
+    lxe runtime download
+    lxe build
+
+lxe init --ci gitlab generates a .gitlab-ci.yml with build, sign and verify stages.
+
+Useful flags of lxe build in pipelines:
+
+  - --ci-output prints ##lxe[...] service messages (progress, artifacts, build status) for CI log parsers.
+  - --output-dir <dir> names packages from [build] output_template, e.g. <id>-<version>-<arch>.lxe.
+  - --generate-update-metadata <file> writes the update manifest for the package.
+  - --manifest-only writes metadata and payload separately; lxe assemble combines them on another machine.
+
+The build exits with a non-zero code on failure; [build.hooks] on_build_failed can notify someone.";
+
+const RUNTIME: &str = "\
+The runtime is the installer every package starts with. Running a .lxe executes it: it finds the metadata from the footer, verifies the payload checksum and signature, shows the GTK installer (or installs silently with --silent) and extracts the payload. No FUSE mounts or temporary images are needed.
+
+The installed runtime also uninstalls apps (--uninstall <id>) and lists them (--list).
+
+lxe build prepends the runtime from [runtime] path in lxe.toml, the one next to lxe, or the downloaded one, in that order.
+
+    lxe runtime download         # download the runtime
+    lxe runtime status           # installed version and location
+    lxe runtime list-versions    # versions available on GitHub
+    lxe runtime upgrade          # upgrade, keeping a backup (--rollback)
+
+lxe build prints the runtime version it embeds; lxe build --verify-runtime also runs it with --version and enforces [runtime] min_version.";
+
+const POLKIT: &str = "\
+Packages install into the user's home by default. With --system they install under /usr for all users, which needs root:
+
+    sudo ./app.lxe --install-policy    # once per machine
+    ./app.lxe --system
+
+--install-policy writes the polkit policy to /usr/share/polkit-1/actions/org.lxe.policy. With it installed, a system-wide install asks for the administrator password through polkit instead of requiring the installer itself to run as root.
+
+Administrators can also install into another user's home: sudo ./app.lxe --silent --for-user <name>. The files are then owned by that user.";
+
+const HOOKS: &str = "\
+Build hooks run shell commands around lxe build itself and are set in lxe.toml:
+
+    [build.hooks]
+    pre_build = \"git diff --quiet\"
+    post_build = \"./upload.sh \\\"$LXE_OUTPUT_PATH\\\"\"
+    on_build_failed = \"./notify.sh \\\"$LXE_ERROR\\\"\"
+
+  - pre_build runs before any build step; if it fails, the build is aborted.
+  - post_build runs after the package was created; LXE_OUTPUT_PATH is its path.
+  - on_build_failed runs if any step fails; LXE_ERROR is the error message.
+
+All of them get LXE_APP_ID and LXE_VERSION.
+
+Install hooks (pre_install, post_install, pre_uninstall, post_uninstall) are shell scripts stored in the package metadata. They run on the user's machine, so lxe security-scan lists them verbatim for review before a package is deployed.";
+
+const TROUBLESHOOTING: &str = "\
+LXE runtime not found
+  Run lxe runtime download, put lxe-runtime next to lxe, or set [runtime] path in lxe.toml.
+
+lxe.toml already exists in this directory
+  lxe init never overwrites it; edit the file or delete it first.
+
+A build resumes work from an earlier run
+  Interrupted builds continue from .lxe-build-state.json; lxe build --clean starts over.
+
+The build agent runs out of memory
+  Set [build] max_memory_mb; compression then uses a smaller window.
+
+The package doesn't start or install
+  Check the embedded runtime with lxe build --verify-runtime and the package with lxe conformance <file>.
+
+Signature verification fails
+  The package was changed after signing (lxe patch-meta strips the signature) or signed with another key. Rebuild it, then check with lxe verify <file>.
+
+System-wide install is refused
+  Install the polkit policy once: sudo ./app.lxe --install-policy (see lxe help polkit).
+
+An app is missing from the application menu
+  lxe status reports broken desktop entries and icons of installed apps.
+
+Something else is wrong with the setup
+  lxe doctor checks for common problems and suggests a fix for each.";
+
+pub fn find(name: &str) -> Option<&'static Topic> {
+    TOPICS.iter().find(|t| t.name.eq_ignore_ascii_case(name))
+}
+
+/// Topic closest to a misspelled `name`, if any is close enough
+pub fn closest(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    TOPICS.iter()
+        .map(|t| (edit_distance(&name, t.name), t.name))
+        .filter(|&(distance, topic)| distance <= topic.len() / 3 + 1 || topic.starts_with(&name))
+        .min()
+        .map(|(_, topic)| topic)
+}
+
+/// Levenshtein distance
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Wrap every line of `text` at `width`, continuing under its indentation
+/// (under the text of `- ` bullets)
+pub fn wrap(text: &str, width: usize) -> String {
+    let mut out = String::new();
+    for line in text.lines() {
+        let content = line.trim_start();
+        let mut indent = line.len() - content.len();
+        if content.starts_with("- ") {
+            indent += 2;
+        }
+
+        let mut current = line[..line.len() - content.len()].to_string();
+        let mut at_start = true;
+        for word in content.split(' ') {
+            if !at_start && current.len() + 1 + word.len() > width {
+                out.push_str(current.trim_end());
+                out.push('\n');
+                current = " ".repeat(indent);
+                at_start = true;
+            }
+            if !at_start {
+                current.push(' ');
+            }
+            current.push_str(word);
+            at_start = false;
+        }
+        out.push_str(current.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topics() {
+        assert!(find("Signing").is_some());
+        assert_eq!(closest("singing"), Some("signing"));
+        assert_eq!(closest("trouble"), Some("troubleshooting"));
+        assert_eq!(closest("xyz"), None);
+
+        assert_eq!(wrap("  - one two three", 12), "  - one two\n    three\n");
+        for topic in TOPICS {
+            let wrapped = wrap(topic.text, WRAP_WIDTH);
+            // Only the package layout diagram is wider than the terminal
+            assert!(wrapped.lines().all(|l| l.len() <= WRAP_WIDTH || l.contains("[runtime]")), "{}", topic.name);
+        }
+    }
+}
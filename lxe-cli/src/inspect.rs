@@ -0,0 +1,207 @@
+//! Package Inspection (`lxe inspect`)
+//!
+//! Shows what a built package contains - its metadata and the file tree of
+//! the payload, like `tar -tvf` - without installing it. The package layout
+//! is read without the installer's checks, so tampered or unsupported
+//! packages can be inspected too; the signature is checked and reported
+//! instead of refused.
+
+use lxe_common::metadata::{LxeMetadata, LXE_MAGIC};
+use lxe_common::payload::{self, EntryKind, PayloadInfo};
+use lxe_common::payload_format::MetaStub;
+use serde_json::Value;
+use std::path::Path;
+
+/// Longest metadata value shown in full
+const MAX_VALUE_WIDTH: usize = 64;
+
+/// Result of checking a package's signature
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureStatus {
+    Unsigned,
+    Valid,
+    Invalid(String),
+}
+
+impl SignatureStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unsigned => "unsigned",
+            Self::Valid => "valid",
+            Self::Invalid(_) => "invalid",
+        }
+    }
+}
+
+/// Check the signature the way the installer does (without the trust store)
+pub fn signature_status(metadata: &LxeMetadata) -> SignatureStatus {
+    if metadata.signature.is_none() && metadata.public_key.is_none() {
+        return SignatureStatus::Unsigned;
+    }
+    match lxe_common::payload::check_package_signature(metadata) {
+        Ok(()) => SignatureStatus::Valid,
+        Err(e) => SignatureStatus::Invalid(format!("{:#}", e)),
+    }
+}
+
+/// State of the runtime's metadata stub
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StubStatus {
+    /// No stub section, or one that was never filled in
+    Missing,
+    /// Points at the package's metadata and payload
+    Current,
+    /// Disagrees with the layout found from the footer
+    Stale,
+}
+
+impl StubStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Missing => "none",
+            Self::Current => "current",
+            Self::Stale => "stale",
+        }
+    }
+}
+
+/// Read the metadata stub and compare it with the layout `info` was read from
+pub fn stub_status(file: &Path, info: &PayloadInfo) -> StubStatus {
+    match payload::read_metadata_stub(file) {
+        Ok(stub) => compare_stub(&stub, info.header_offset, info.payload_offset, info.payload_size),
+        Err(_) => StubStatus::Missing,
+    }
+}
+
+fn compare_stub(stub: &MetaStub, header_offset: u64, payload_offset: u64, payload_size: u64) -> StubStatus {
+    let metadata_offset = header_offset + LXE_MAGIC.len() as u64 + 4;
+    let metadata_end = metadata_offset + stub.metadata_len as u64 + payload::CHECKSUM_SLOT_SIZE as u64;
+    if stub.header_offset == header_offset
+        && stub.metadata_offset == metadata_offset
+        && metadata_end == payload_offset
+        && stub.payload_offset == payload_offset
+        && stub.payload_size == payload_size
+    {
+        StubStatus::Current
+    } else {
+        StubStatus::Stale
+    }
+}
+
+/// Every metadata field as a `(name, value)` row; nested objects such as
+/// `installer` and `hooks` become `installer.theme`, ...
+pub fn metadata_rows(metadata: &LxeMetadata) -> Vec<(String, String)> {
+    let mut rows = Vec::new();
+    if let Ok(Value::Object(fields)) = serde_json::to_value(metadata) {
+        for (name, value) in fields {
+            flatten(&name, &value, &mut rows);
+        }
+    }
+    rows
+}
+
+fn flatten(name: &str, value: &Value, rows: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(fields) if !fields.is_empty() => {
+            for (key, value) in fields {
+                // Unset nested fields are noise
+                if !value.is_null() {
+                    flatten(&format!("{}.{}", name, key), value, rows);
+                }
+            }
+        }
+        Value::Array(items) if items.iter().all(|item| !item.is_object()) => {
+            let items: Vec<String> = items.iter().map(display_value).collect();
+            rows.push((name.to_string(), items.join(", ")));
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten(&format!("{}[{}]", name, i), item, rows);
+            }
+        }
+        _ => rows.push((name.to_string(), display_value(value))),
+    }
+}
+
+/// One-line form of a value (scripts and long keys are shortened)
+fn display_value(value: &Value) -> String {
+    let text = match value {
+        Value::Null => return "-".to_string(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+
+    let line_count = text.lines().count();
+    let mut line = text.lines().next().unwrap_or_default().to_string();
+    if line.chars().count() > MAX_VALUE_WIDTH {
+        line = line.chars().take(MAX_VALUE_WIDTH - 3).collect::<String>() + "...";
+    }
+    if line_count > 1 {
+        line = format!("{} ({} lines)", line, line_count);
+    }
+    line
+}
+
+/// `ls -l` style type and permissions, e.g. `drwxr-xr-x`
+pub fn mode_string(kind: EntryKind, mode: u32) -> String {
+    let mut out = String::from(match kind {
+        EntryKind::Directory => 'd',
+        EntryKind::Symlink => 'l',
+        EntryKind::HardLink => 'h',
+        EntryKind::File => '-',
+        EntryKind::Other => '?',
+    });
+    for shift in [6, 3, 0] {
+        let bits = (mode >> shift) & 0o7;
+        out.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        out.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        out.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_formatting() {
+        assert_eq!(mode_string(EntryKind::File, 0o755), "-rwxr-xr-x");
+        assert_eq!(mode_string(EntryKind::Directory, 0o750), "drwxr-x---");
+
+        let mut metadata = LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 0, "ab");
+        metadata.categories = vec!["Utility".into(), "Development".into()];
+        metadata.hooks = Some(lxe_common::metadata::InstallHooks {
+            pre_install: None,
+            post_install: Some("echo one\necho two\n".into()),
+            pre_uninstall: None,
+            post_uninstall: None,
+        });
+        let rows = metadata_rows(&metadata);
+        let row = |name: &str| rows.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str());
+        assert_eq!(row("app_id"), Some("com.example.App"));
+        assert_eq!(row("categories"), Some("Utility, Development"));
+        assert_eq!(row("hooks.post_install"), Some("echo one (2 lines)"));
+        assert_eq!(row("installer.show_launch"), Some("false"));
+        assert_eq!(row("hooks.pre_install"), None);
+
+        assert_eq!(signature_status(&metadata), SignatureStatus::Unsigned);
+        metadata.signature = Some("AAAA".into());
+        assert!(matches!(signature_status(&metadata), SignatureStatus::Invalid(_)));
+    }
+
+    #[test]
+    fn test_compare_stub() {
+        let stub = MetaStub {
+            header_offset: 1000,
+            metadata_offset: 1012,
+            metadata_len: 300,
+            payload_offset: 1344,
+            payload_size: 5000,
+        };
+        assert_eq!(compare_stub(&stub, 1000, 1344, 5000), StubStatus::Current);
+        // Metadata rewritten with a different length
+        assert_eq!(compare_stub(&stub, 1000, 1350, 5000), StubStatus::Stale);
+        assert_eq!(compare_stub(&stub, 1000, 1344, 4000), StubStatus::Stale);
+    }
+}
@@ -0,0 +1,150 @@
+//! Installed App Listing (`lxe list`)
+//!
+//! Turns install manifests into rows that can be filtered, sorted and
+//! rendered as a table, JSON or RFC 4180 CSV.
+
+use crate::stats;
+use lxe_common::manifest::InstallManifest;
+use lxe_common::requirements::normalize_version;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ListFormat {
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortField {
+    Id,
+    Name,
+    Version,
+    Size,
+    Date,
+}
+
+/// One installed app
+#[derive(Debug, Serialize)]
+pub struct ListRow {
+    pub app_id: String,
+    pub name: String,
+    pub version: String,
+    pub size_bytes: u64,
+    pub install_date: String,
+    pub location: &'static str,
+    pub signed: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<String>,
+    /// Installed by an administrator with `--for-user`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_for_user: Option<String>,
+    #[serde(skip)]
+    installed_secs: u64,
+}
+
+impl ListRow {
+    /// Build a row; `base_dir` is the install prefix (e.g. ~/.local) of user
+    /// apps installed without `--install-dir`
+    ///
+    /// With `cache_size`, the directory size is cached in the manifest.
+    pub fn from_manifest(mut manifest: InstallManifest, base_dir: Option<&Path>, cache_size: bool) -> Self {
+        let dir = if manifest.prefix.is_some() || manifest.is_system {
+            stats::install_base(&manifest)
+        } else {
+            base_dir.map(Path::to_path_buf)
+        }
+        .map(|base| base.join("share").join(&manifest.app_id));
+
+        let size_bytes = match dir {
+            Some(ref dir) if cache_size => stats::cached_dir_size(&mut manifest, dir),
+            Some(ref dir) => stats::dir_size(dir),
+            None => 0,
+        };
+
+        ListRow {
+            name: manifest.name.clone().unwrap_or_else(|| manifest.app_id.clone()),
+            install_date: stats::format_install_date(&manifest.installed_at),
+            installed_secs: manifest.installed_at.strip_prefix("unix:")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(0),
+            location: if manifest.is_system { "system" } else { "user" },
+            app_id: manifest.app_id,
+            version: manifest.version,
+            size_bytes,
+            signed: manifest.signed,
+            deprecated: manifest.deprecated,
+            deprecation_message: manifest.deprecation_message,
+            installed_for_user: manifest.installed_for_user,
+        }
+    }
+}
+
+/// Sort rows in place
+pub fn sort(rows: &mut [ListRow], field: SortField, reverse: bool) {
+    rows.sort_by(|a, b| {
+        let ordering = match field {
+            SortField::Id => a.app_id.cmp(&b.app_id),
+            SortField::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortField::Version => compare_versions(&a.version, &b.version),
+            SortField::Size => a.size_bytes.cmp(&b.size_bytes),
+            SortField::Date => a.installed_secs.cmp(&b.installed_secs),
+        };
+        // Stable order for ties
+        ordering.then_with(|| a.app_id.cmp(&b.app_id))
+    });
+    if reverse {
+        rows.reverse();
+    }
+}
+
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    match (normalize_version(a), normalize_version(b)) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Render rows as RFC 4180 CSV (CRLF line endings, quoted where needed)
+pub fn to_csv(rows: &[ListRow]) -> String {
+    let mut out = String::from("app_id,name,version,size_bytes,install_date,location,signed\r\n");
+    for row in rows {
+        let fields = [
+            row.app_id.clone(),
+            row.name.clone(),
+            row.version.clone(),
+            row.size_bytes.to_string(),
+            row.install_date.clone(),
+            row.location.to_string(),
+            row.signed.to_string(),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        out.push_str(&line.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("Tom, Jerry"), "\"Tom, Jerry\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
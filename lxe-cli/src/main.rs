@@ -6,25 +6,50 @@
 //!   lxe key generate       Generate Ed25519 signing keypair
 //!   lxe verify <file.lxe>  Verify package signature
 
+mod appimage;
+mod build;
+mod ci;
+mod clean;
+mod completion;
+mod conformance;
+mod delta;
 mod detect;
+mod diff;
+mod doctor;
+mod extract;
+mod help_topics;
+mod inspect;
+mod list;
+mod network;
+mod releases;
+mod repo;
+mod report;
+mod runtime_verify;
+mod security;
+mod stats;
+mod status;
+mod swap;
+mod transaction;
+mod update_manifest;
+mod updates;
+mod validate;
+mod watch;
+mod workspace;
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use dialoguer::{Input, Confirm};
+use dialoguer::Input;
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{Read, Seek, Write};
 use std::path::PathBuf;
 use std::process::Command;
 use indicatif::{ProgressBar, ProgressStyle};
 
 // Import from lxe-common
+use lxe_common::builder;
 use lxe_common::config::LxeConfig;
-use lxe_common::metadata::SignableMetadata;
 
-// Re-use signing and compression
-use ed25519_dalek::SigningKey;
-use base64::prelude::*;
-use rand::rngs::OsRng;
+// Re-use hashing for checksums
 use sha2::{Sha256, Digest};
 
 /// Magic bytes identifying LXE payload (must match runtime)
@@ -35,6 +60,7 @@ const LXE_MAGIC: &[u8; 8] = b"\x00LXE\xF0\x9F\x93\x01";
 #[command(version = env!("CARGO_PKG_VERSION"))]
 #[command(about = "LXE - The Universal Linux Package Builder")]
 #[command(author = "LXE Project")]
+#[command(disable_help_subcommand = true)]
 struct Cli {
     /// Suppress all output except errors
     #[arg(short, long, global = true)]
@@ -44,6 +70,22 @@ struct Cli {
     #[arg(short, long, global = true)]
     verbose: bool,
 
+    /// Cap download speed in KiB/s (overrides max_download_speed_kbps)
+    #[arg(long, global = true, value_name = "KBPS")]
+    limit_rate: Option<u64>,
+
+    /// Ignore the configured download speed limit
+    #[arg(long, global = true, conflicts_with = "limit_rate")]
+    no_limit: bool,
+
+    /// HTTP(S) proxy for downloads (default: HTTP_PROXY / HTTPS_PROXY)
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// `json` prints one JSON object with the result instead of messages
+    #[arg(long, global = true, value_enum, default_value_t = OutputMode::Human)]
+    output_format: OutputMode,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -59,6 +101,115 @@ enum Commands {
         /// Skip running the build script
         #[arg(long)]
         no_script: bool,
+        
+        /// Run the app under Xvfb to detect its real WM_CLASS
+        #[arg(long)]
+        detect_wmclass: bool,
+        
+        /// Strip debug symbols from ELF binaries (also: build.strip_symbols)
+        #[arg(long)]
+        strip_symbols: bool,
+        
+        /// strip binary to use, e.g. aarch64-linux-gnu-strip
+        #[arg(long, value_name = "PATH")]
+        strip_tool: Option<PathBuf>,
+        
+        /// Also strip shared libraries (.so), which breaks some apps
+        #[arg(long)]
+        strip_shared: bool,
+        
+        /// Optimize the PNG icon with oxipng/optipng (also: build.compress_icons)
+        #[arg(long)]
+        compress_icons: bool,
+        
+        /// Only write <app_id>.lxe.json and <app_id>.tar.zst (see `lxe assemble`)
+        #[arg(long)]
+        manifest_only: bool,
+        
+        /// Write to DIR as <app_id>-<version>-<arch>.lxe (see build.output_template)
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<PathBuf>,
+        
+        /// Replace an existing file of the same version in --output-dir
+        #[arg(long, requires = "output_dir", conflicts_with = "conflict")]
+        overwrite: bool,
+        
+        /// If the output file exists: overwrite, error, rename (-N suffix) or backup (.bak)
+        #[arg(long, value_name = "ACTION")]
+        conflict: Option<lxe_common::config::ConflictAction>,
+        
+        /// Delete all but the N latest versions in --output-dir
+        #[arg(long, value_name = "N", requires = "output_dir")]
+        keep_latest: Option<usize>,
+        
+        /// Add ##lxe[...] service messages for CI log parsers
+        #[arg(long)]
+        ci_output: bool,
+        
+        /// Write an update server manifest for the new package
+        #[arg(long, value_name = "FILE", conflicts_with = "manifest_only")]
+        generate_update_metadata: Option<PathBuf>,
+        
+        /// Add the new package to an existing update server manifest
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["manifest_only", "generate_update_metadata"])]
+        append_to: Option<PathBuf>,
+        
+        /// Base URL the package will be downloadable from (for the download_url)
+        #[arg(long, value_name = "URL")]
+        download_base_url: Option<String>,
+        
+        /// Release notes for the update server manifest
+        #[arg(long, value_name = "TEXT")]
+        changelog: Option<String>,
+        
+        /// Where to write the intermediate tar and payload (default: system temp dir)
+        #[arg(long, value_name = "DIR")]
+        target_dir: Option<PathBuf>,
+        
+        /// Keep the intermediate files after the build
+        #[arg(long)]
+        keep_artifacts: bool,
+        
+        /// Remove artifact directories older than a day from the target dir first
+        #[arg(long)]
+        clean_artifacts: bool,
+        
+        /// Ignore and delete the saved progress of an interrupted build
+        #[arg(long)]
+        clean: bool,
+        
+        /// Run the runtime with --version before embedding it and enforce
+        /// [runtime] min_version
+        #[arg(long)]
+        verify_runtime: bool,
+        
+        /// Rebuild whenever the input directory or lxe.toml changes (with a
+        /// build script: anything in the current directory)
+        #[arg(long)]
+        watch: bool,
+        
+        /// Write a JSON build report (sizes, compression, signing), e.g. MyApp.lxe.report.json
+        #[arg(long, value_name = "FILE", conflicts_with = "manifest_only")]
+        report: Option<PathBuf>,
+    },
+    
+    /// Combine metadata and payload from `lxe build --manifest-only` into a package
+    Assemble {
+        /// Metadata JSON (<app_id>.lxe.json)
+        #[arg(long, value_name = "FILE")]
+        metadata: PathBuf,
+        
+        /// Compressed payload (<app_id>.tar.zst)
+        #[arg(long, value_name = "FILE")]
+        payload: PathBuf,
+        
+        /// Runtime binary (default: the installed runtime)
+        #[arg(long, value_name = "FILE")]
+        runtime: Option<PathBuf>,
+        
+        /// Output path (default: ./<name>.lxe)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
     
     /// Create a template lxe.toml in current directory (interactive)
@@ -67,9 +218,22 @@ enum Commands {
         #[arg(short, long)]
         yes: bool,
         
-        /// Framework preset: tauri, python, electron
+        /// Create lxe.toml from detected values only and print a summary
+        /// (exit code 2 if the values were mostly guessed)
+        #[arg(long, conflicts_with = "preset")]
+        detect: bool,
+        
+        /// Framework preset: tauri, python, electron, flutter, go, dotnet, rust-xtask
         #[arg(short, long)]
         preset: Option<String>,
+        
+        /// Also generate a CI pipeline for the given platform
+        #[arg(long, value_enum)]
+        ci: Option<CiTarget>,
+        
+        /// Take defaults from an existing package (default: newest .lxe in the directory)
+        #[arg(long, value_name = "FILE", conflicts_with = "preset")]
+        from_lxe: Option<PathBuf>,
     },
     
     /// Manage the LXE runtime
@@ -88,12 +252,162 @@ enum Commands {
     Verify {
         /// Path to .lxe file
         file: PathBuf,
+        
+        /// Show the signing key's chain of issuers from the trust store
+        #[arg(long)]
+        show_chain: bool,
+    },
+
+    /// Check that a package follows the LXE format specification
+    Conformance {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Check level
+        #[arg(long, value_enum, default_value_t = conformance::ConformanceLevel::Basic)]
+        level: conformance::ConformanceLevel,
+    },
+
+    /// Check a package for common mistakes before releasing it (exit 1 on failure)
+    Validate {
+        /// Path to .lxe file
+        file: PathBuf,
+    },
+
+    /// Show a package's metadata and files without installing it
+    Inspect {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Print metadata, signature status and files as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Unpack a package's files into a directory without installing it
+    Extract {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Directory to unpack into
+        #[arg(short, long, value_name = "DIR")]
+        output: PathBuf,
+        
+        /// Drop the first N leading directories of each path (like tar)
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        strip_components: usize,
+    },
+
+    /// Compare the metadata and files of two packages
+    Diff {
+        /// The older package
+        old: PathBuf,
+        
+        /// The newer package
+        new: PathBuf,
+        
+        /// Only list files that are new
+        #[arg(long)]
+        added: bool,
+        
+        /// Only list files that are gone
+        #[arg(long)]
+        removed: bool,
+        
+        /// Only list files whose size, type, mode or link target changed
+        #[arg(long)]
+        changed: bool,
+    },
+
+    /// Build a delta package with only the files that changed since an older release
+    Delta {
+        /// The installed (older) release
+        old: PathBuf,
+        
+        /// The release to upgrade to
+        new: PathBuf,
+        
+        /// Where to write the delta package
+        #[arg(short, long)]
+        output: PathBuf,
+        
+        /// zstd compression level
+        #[arg(long, default_value_t = 19)]
+        level: i32,
+    },
+
+    /// Convert every *.AppImage in a directory to an LXE package
+    MigrateAppimage {
+        /// Directory containing the AppImages
+        input_dir: PathBuf,
+        
+        /// Where to write the packages
+        #[arg(short, long, default_value = ".")]
+        output_dir: PathBuf,
+        
+        /// Sign the packages with this private key
+        #[arg(short, long)]
+        key: Option<PathBuf>,
+        
+        /// Convert several AppImages at once
+        #[arg(long)]
+        parallel: bool,
+    },
+
+    /// Screen a package's payload for suspicious content without installing it
+    SecurityScan {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Scan level
+        #[arg(long, value_enum, default_value_t = security::ScanLevel::Basic)]
+        level: security::ScanLevel,
+    },
+
+    /// Compare payload checksum algorithms ([build] checksum_algorithm)
+    Benchmark {
+        /// Hash this file instead of generated data (e.g. a compressed payload)
+        #[arg(long)]
+        file: Option<PathBuf>,
+        
+        /// Size of the generated data in MB
+        #[arg(long, default_value_t = 256)]
+        size: usize,
+    },
+
+    /// Sign an already-built package in place
+    Sign {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Private key file (from lxe key generate)
+        #[arg(long, value_name = "KEY_FILE")]
+        key: PathBuf,
+        
+        /// Replace an existing signature
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Update package metadata without rebuilding (strips the signature)
+    PatchMeta {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Field to change, e.g. --set version=1.0.1 (repeatable)
+        #[arg(long, value_name = "KEY=VALUE")]
+        set: Vec<String>,
     },
 
     /// Uninstall an LXE application
     Uninstall {
         /// App ID to uninstall (e.g., com.example.app)
-        id: String,
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        id: Option<String>,
+
+        /// Uninstall every LXE app (with --system, system-wide ones too)
+        #[arg(long)]
+        all: bool,
 
         /// Skip confirmation prompt
         #[arg(short, long)]
@@ -102,6 +416,215 @@ enum Commands {
         /// Uninstall system-wide installation (requires sudo)
         #[arg(long)]
         system: bool,
+
+        /// Show what would be removed without removing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Also delete the app's config, cache and state directories
+        #[arg(long)]
+        purge: bool,
+
+        /// Afterwards, list directories the app left in config, cache, data and temp locations
+        #[arg(long, conflicts_with = "all")]
+        scan_leftovers: bool,
+
+        /// Delete the leftovers found by --scan-leftovers (after confirmation)
+        #[arg(long, conflicts_with = "all")]
+        remove_leftovers: bool,
+    },
+
+    /// Build multi-package workspaces (lxe-workspace.toml)
+    Workspace {
+        #[command(subcommand)]
+        action: WorkspaceAction,
+    },
+
+    /// Replace an installed app with a new package in place
+    Swap {
+        /// Application ID of the installed app
+        id: String,
+        
+        /// Path to the new .lxe file
+        file: PathBuf,
+        
+        /// Send SIGHUP to running instances instead of stopping them
+        #[arg(long)]
+        keep_running: bool,
+    },
+
+    /// Install/uninstall several apps atomically
+    #[command(args_conflicts_with_subcommands = true)]
+    Transaction {
+        /// Actions file with [[install]] and [[uninstall]] entries
+        actions: Option<PathBuf>,
+        
+        #[command(subcommand)]
+        action: Option<TransactionAction>,
+    },
+
+    /// Show a health report of the whole LXE installation
+    Status {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        
+        /// Refresh every 5 seconds
+        #[arg(long)]
+        watch: bool,
+    },
+
+    /// Check the local setup for known problems (exits 1 if a check fails)
+    Doctor,
+
+    /// Check an installed app's files against the checksums recorded at install time
+    VerifyInstall {
+        /// Application ID
+        id: String,
+    },
+
+    /// Find files in an app's directory that its manifest doesn't list
+    CheckOrphans {
+        /// Application ID
+        id: String,
+        
+        /// Delete the orphaned files (asks first)
+        #[arg(long)]
+        delete: bool,
+        
+        /// Skip confirmation prompt
+        #[arg(short, long, requires = "delete")]
+        yes: bool,
+    },
+
+    /// Remove orphaned manifests, temporary icons and failed install leftovers
+    Clean {
+        /// List what would be deleted without deleting anything
+        #[arg(long)]
+        dry_run: bool,
+        
+        /// Skip confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show disk usage statistics of installed apps
+    Stats {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        
+        /// Only show the N largest apps
+        #[arg(long, value_name = "N")]
+        top: Option<usize>,
+    },
+
+    /// Watch for updates of installed apps and show desktop notifications
+    NotifyUpdates {
+        /// Seconds between checks (default: check_updates_interval from config, or 6 hours)
+        #[arg(long)]
+        interval: Option<u64>,
+        
+        /// Run in the background
+        #[arg(long)]
+        daemon: bool,
+        
+        /// Stop the background process
+        #[arg(long, conflicts_with_all = ["daemon", "interval"])]
+        stop: bool,
+    },
+
+    /// Run an installed application, optionally sandboxed with bubblewrap
+    Run {
+        /// Application ID
+        id: String,
+        
+        /// Sandbox profile (see `lxe sandbox-profiles list`)
+        #[arg(long, value_name = "NAME")]
+        sandbox_preset: Option<String>,
+        
+        /// Arguments passed to the application
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// Manage sandbox profiles for `lxe run`
+    SandboxProfiles {
+        #[command(subcommand)]
+        action: SandboxProfilesAction,
+    },
+
+    /// List installed applications
+    List {
+        /// Only show deprecated apps, with the publisher's message
+        #[arg(long)]
+        show_deprecated: bool,
+        
+        /// List another user's installed apps instead of your own
+        #[arg(long, value_name = "USERNAME")]
+        user: Option<String>,
+        
+        /// Output format
+        #[arg(long, value_enum, default_value_t = list::ListFormat::Table)]
+        format: list::ListFormat,
+        
+        /// Only apps whose ID matches this glob (e.g. "com.example.*")
+        #[arg(long, value_name = "PATTERN")]
+        filter: Option<String>,
+        
+        /// Sort by this column
+        #[arg(long, value_enum, default_value_t = list::SortField::Id)]
+        sort: list::SortField,
+        
+        /// Reverse the sort order
+        #[arg(long)]
+        reverse: bool,
+        
+        /// Only system-wide installs
+        #[arg(long, conflicts_with = "user_only")]
+        system_only: bool,
+        
+        /// Only per-user installs
+        #[arg(long)]
+        user_only: bool,
+        
+        /// Only print the number of matching apps
+        #[arg(long)]
+        count: bool,
+    },
+
+    /// Show when apps were installed, upgraded and uninstalled, newest first
+    History {
+        /// Only events of this app
+        #[arg(long, value_name = "ID")]
+        app_id: Option<String>,
+        
+        /// Only show the N most recent events
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+
+    /// Mark a published package version as deprecated in the registry
+    Deprecate {
+        /// Application ID
+        id: String,
+        
+        /// Version to deprecate (default: all versions)
+        version: Option<String>,
+        
+        /// Message shown to users installing this version
+        #[arg(short, long)]
+        message: String,
+        
+        /// Registry URL (default: registry from ~/.config/lxe/config.toml)
+        #[arg(long)]
+        registry: Option<String>,
+    },
+
+    /// Manage a local package repository (for air-gapped networks)
+    Repo {
+        #[command(subcommand)]
+        action: RepoAction,
     },
 
     /// Update the LXE tool itself
@@ -110,72 +633,536 @@ enum Commands {
         #[arg(long)]
         check: bool,
     },
+    
+    /// Print or install shell completions (bash, zsh, fish)
+    Completion {
+        #[command(subcommand)]
+        action: CompletionAction,
+    },
+    
+    /// Explain a topic (`lxe help list`) or show the help of a command
+    Help {
+        /// Topic, or command (and subcommand) name
+        topic: Vec<String>,
+    },
+}
+
+/// Algorithms `lxe key generate` can create keys for
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+enum KeyAlgorithm {
+    Ed25519,
+    Rsa2048,
+    Rsa4096,
+}
+
+/// Key storage of `lxe key generate`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum KeyBackendKind {
+    File,
+    /// Hardware token (YubiKey PIV, smart card) through a PKCS#11 module
+    Pkcs11,
+}
+
+/// Output of `lxe key generate --batch`
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum KeyTableFormat {
+    Table,
+    /// `filename,public_key_b64,fingerprint` rows
+    Csv,
 }
 
 #[derive(Subcommand)]
 enum KeyAction {
-    /// Generate a new Ed25519 signing keypair
+    /// Generate a new signing keypair
     Generate {
         /// Output path for the key file
         #[arg(short, long, default_value = "lxe-signing.key")]
         output: PathBuf,
+        
+        /// Key algorithm (RSA keys are stored as PEM)
+        #[arg(short, long, value_enum, default_value_t = KeyAlgorithm::Ed25519)]
+        algorithm: KeyAlgorithm,
+        
+        /// Generate COUNT keypairs named <output>-001.key, <output>-002.key, ...
+        #[arg(long, value_name = "COUNT", value_parser = clap::value_parser!(u32).range(1..=999))]
+        batch: Option<u32>,
+        
+        /// How to print the generated keys in batch mode
+        #[arg(long, value_enum, default_value_t = KeyTableFormat::Table, requires = "batch")]
+        format: KeyTableFormat,
+        
+        /// Where the private key lives; with pkcs11 it is generated on the
+        /// token and the output file only points to it (PIN: LXE_PKCS11_PIN)
+        #[arg(long, value_enum, default_value_t = KeyBackendKind::File, conflicts_with = "batch")]
+        backend: KeyBackendKind,
+        
+        /// PKCS#11 module of the token (e.g. /usr/lib/x86_64-linux-gnu/libykcs11.so)
+        #[arg(long, required_if_eq("backend", "pkcs11"))]
+        module: Option<PathBuf>,
+        
+        /// Token slot
+        #[arg(long, default_value_t = 0)]
+        slot: u64,
     },
-}
 
-#[derive(Subcommand)]
-enum RuntimeAction {
-    /// Download the LXE runtime from GitHub
-    Download {
-        /// Force re-download even if runtime exists
+    /// Show what a key file holds and whether it is private
+    Inspect {
+        /// Key file (private key, or public key in any importable format)
+        key: PathBuf,
+    },
+
+    /// Import a trusted public key (PEM, OpenSSH, base64 or hex)
+    Import {
+        /// Key source: file path, http(s) URL, or "-" for stdin
+        #[arg(value_parser = parse_key_source)]
+        key_source: KeySource,
+
+        /// Name to store the key under
         #[arg(short, long)]
-        force: bool,
+        name: String,
+        
+        /// Name of the trusted key that issued this one (shown by `verify --show-chain`)
+        #[arg(long)]
+        issuer: Option<String>,
+        
+        /// Expected fingerprint (SHA256:...); confirms a key from a URL without prompting
+        #[arg(long)]
+        fingerprint: Option<String>,
     },
-    
-    /// Show runtime status and location
-    Status,
-}
 
-// Console helper for output control
-struct Console {
-    silent: bool,
-    verbose: bool,
-}
+    /// Trust a public key for all apps or for app IDs matching --app-id
+    Trust {
+        /// Public key (base64, PEM, OpenSSH or hex)
+        public_key: String,
+        
+        /// App ID glob the key may sign, e.g. "com.acme.*" (run again to add more)
+        #[arg(long)]
+        app_id: Option<String>,
+        
+        /// Name to store a new key under (default: from its fingerprint)
+        #[arg(short, long)]
+        name: Option<String>,
+    },
 
-impl Console {
-    fn new(silent: bool, verbose: bool) -> Self {
-        Self { silent, verbose }
-    }
+    /// List trusted public keys (use --verbose to show full keys)
+    List,
 
-    fn log(&self, msg: impl std::fmt::Display) {
-        if !self.silent {
-            println!("{}", msg);
+    /// Remove a trusted public key by name
+    Remove {
+        /// Name of the key to remove
+        name: String,
+    },
+
+    /// Sign any file, writing a detached <file>.sig
+    SignFile {
+        /// Private key file
+        #[arg(short, long)]
+        key: PathBuf,
+
+        /// File to sign
+        file: PathBuf,
+
+        /// Signature path (default: <file>.sig)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Re-sign every package in a directory signed by the old key with the new one
+    Rotate {
+        /// Key the packages are signed with now (private or public key)
+        #[arg(long, value_name = "FILE")]
+        old_key: PathBuf,
+        
+        /// Private key to sign them with instead
+        #[arg(long, value_name = "FILE")]
+        new_key: PathBuf,
+        
+        /// Directory of .lxe packages
+        #[arg(long, value_name = "DIR")]
+        dir: PathBuf,
+    },
+
+    /// Verify a file against its detached signature
+    VerifyFile {
+        /// Name of a trusted key, or the public key itself (PEM, OpenSSH, base64 or hex)
+        #[arg(long)]
+        public_key: String,
+
+        /// File to verify
+        file: PathBuf,
+
+        /// Signature path (default: <file>.sig)
+        #[arg(long)]
+        sig: Option<PathBuf>,
+    },
+}
+
+/// Where `lxe key import` reads the key from
+#[derive(Debug, Clone)]
+enum KeySource {
+    File(PathBuf),
+    Url(String),
+    Stdin,
+}
+
+fn parse_key_source(s: &str) -> Result<KeySource, String> {
+    if s == "-" {
+        Ok(KeySource::Stdin)
+    } else if s.starts_with("http://") || s.starts_with("https://") {
+        Ok(KeySource::Url(s.to_string()))
+    } else {
+        Ok(KeySource::File(PathBuf::from(s)))
+    }
+}
+
+#[derive(Subcommand)]
+enum CompletionAction {
+    /// Print the bash completion script
+    Bash,
+    
+    /// Print the zsh completion script
+    Zsh,
+    
+    /// Print the fish completion script
+    Fish,
+    
+    /// Write the script for your shell to its completion directory (rerun to refresh app IDs)
+    Install {
+        /// Shell to install for (default: from $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<completion::CompletionShell>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SandboxProfilesAction {
+    /// List built-in and custom profiles
+    List,
+}
+
+#[derive(Subcommand)]
+enum WorkspaceAction {
+    /// Build workspace members in dependency order
+    Build {
+        /// Build every member
+        #[arg(long)]
+        all: bool,
+        
+        /// Members to build (their dependencies are built too)
+        packages: Vec<String>,
+        
+        /// Maximum number of parallel builds (default: CPU count)
+        #[arg(short, long)]
+        jobs: Option<usize>,
+    },
+}
+
+/// Output of every command (`--output-format`)
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputMode {
+    Human,
+    Json,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum TransactionAction {
+    /// Start a transaction and make it the current one
+    Begin {
+        /// Transaction ID
+        id: String,
+    },
+    
+    /// Queue an install or uninstall
+    Add {
+        /// Operation type
+        #[arg(value_enum)]
+        kind: TransactionOp,
+        
+        /// Package file (install) or application ID (uninstall)
+        target: String,
+        
+        /// Transaction ID (default: current)
+        #[arg(long)]
+        id: Option<String>,
+    },
+    
+    /// Prepare and apply all queued operations
+    Commit {
+        /// Transaction ID (default: current)
+        id: Option<String>,
+    },
+    
+    /// Discard a transaction and undo any partial commit
+    Rollback {
+        /// Transaction ID (default: current)
+        id: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum TransactionOp {
+    Install,
+    Uninstall,
+}
+
+#[derive(Subcommand)]
+enum RepoAction {
+    /// Create a repository (repo.toml, index.json, packages/)
+    Init {
+        /// Repository directory
+        dir: PathBuf,
+        
+        /// Repository name (default: directory name)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    
+    /// Add a package to the repository
+    Add {
+        /// Path to .lxe file
+        file: PathBuf,
+        
+        /// Repository directory
+        #[arg(long, default_value = ".")]
+        repo: PathBuf,
+        
+        /// Record the SHA256 of the package in the index
+        #[arg(long)]
+        generate_sha256: bool,
+    },
+    
+    /// Remove a package version from the repository
+    Remove {
+        /// Application ID
+        id: String,
+        
+        /// Version to remove
+        version: String,
+        
+        /// Repository directory
+        #[arg(long, default_value = ".")]
+        repo: PathBuf,
+    },
+    
+    /// Serve the repository over HTTP
+    Serve {
+        /// Port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        
+        /// Repository directory
+        #[arg(long, default_value = ".")]
+        repo: PathBuf,
+    },
+    
+    /// Rebuild index.json from the packages directory
+    Index {
+        /// Repository directory
+        #[arg(long, default_value = ".")]
+        repo: PathBuf,
+        
+        /// Hash every package (otherwise existing hashes are kept)
+        #[arg(long)]
+        generate_sha256: bool,
+    },
+    
+    /// Search the configured repositories ([repo] urls) by app ID or name
+    Search {
+        /// Text to look for in app IDs and names
+        query: String,
+        
+        /// Search this repository instead of the configured ones
+        #[arg(long)]
+        url: Option<String>,
+    },
+}
+
+/// CI platforms `lxe init --ci` can generate a pipeline for
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum CiTarget {
+    /// .gitlab-ci.yml with build, sign and verify stages
+    Gitlab,
+}
+
+impl CiTarget {
+    /// Pipeline file name and contents
+    fn pipeline(self) -> (&'static str, &'static str) {
+        match self {
+            CiTarget::Gitlab => (".gitlab-ci.yml", include_str!("ci_templates/gitlab.yml.template")),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum RuntimeAction {
+    /// Download the LXE runtime from GitHub
+    Download {
+        /// Force re-download even if runtime exists
+        #[arg(short, long)]
+        force: bool,
+        
+        /// Skip SHA256 verification (for mirrors without .sha256 sidecars)
+        #[arg(long)]
+        skip_verify: bool,
+        
+        /// Discard any partial download and start fresh
+        #[arg(long)]
+        no_resume: bool,
+    },
+    
+    /// Show runtime status and location
+    Status,
+    
+    /// List runtime versions available on GitHub
+    ListVersions {
+        /// Maximum number of releases to show
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        
+        /// Include prereleases and drafts
+        #[arg(long)]
+        prerelease: bool,
+    },
+    
+    /// Upgrade the runtime (keeps a backup for --rollback)
+    Upgrade {
+        /// Install a specific version instead of the latest (e.g., 1.2.0)
+        #[arg(long)]
+        version: Option<String>,
+        
+        /// Restore the previous runtime from the backup
+        #[arg(long, conflicts_with = "version")]
+        rollback: bool,
+        
+        /// Skip SHA256 verification
+        #[arg(long)]
+        skip_verify: bool,
+    },
+}
+
+// Console helper for output control
+struct Console {
+    silent: bool,
+    verbose: bool,
+    /// Emit `##lxe[...]` service messages (`lxe build --ci-output`)
+    ci_mode: bool,
+    /// `--output-format json`: no messages, only the final result object
+    json: bool,
+    /// Result fields of the command for the JSON output
+    report: std::sync::Arc<std::sync::Mutex<serde_json::Map<String, serde_json::Value>>>,
+}
+
+impl Console {
+    fn new(silent: bool, verbose: bool) -> Self {
+        Self { silent, verbose, ci_mode: false, json: false, report: Default::default() }
+    }
+
+    /// Whether messages are suppressed
+    fn quiet(&self) -> bool {
+        self.silent || self.json
+    }
+
+    /// Record a result field for `--output-format json`
+    fn report(&self, key: &str, value: impl serde::Serialize) {
+        if self.json {
+            let value = serde_json::to_value(value).unwrap_or_default();
+            self.report.lock().unwrap().insert(key.to_string(), value);
+        }
+    }
+
+
+    /// Print a command's own JSON output (`--json`, `--format json`); with
+    /// `--output-format json` its fields go into the result object instead
+    fn print_json(&self, value: serde_json::Value) -> Result<()> {
+        if !self.json {
+            println!("{}", serde_json::to_string_pretty(&value)?);
+        } else if let serde_json::Value::Object(fields) = value {
+            for (key, value) in fields {
+                self.report(&key, value);
+            }
+        } else {
+            self.report("result", value);
+        }
+        Ok(())
+    }
+
+    /// Print machine-readable text (CSV, scripts); with `--output-format json`
+    /// it becomes the `key` field of the result
+    fn print_raw(&self, key: &str, text: &str) {
+        if self.json {
+            self.report(key, text);
+        } else {
+            print!("{}", text);
+        }
+    }
+
+    /// Ask a yes/no question; nobody can answer it in the middle of JSON
+    /// output, so there the command needs `--yes`
+    fn confirm(&self, question: impl std::fmt::Display) -> Result<bool> {
+        if self.json {
+            anyhow::bail!("Confirmation required; pass --yes with --output-format json");
+        }
+        print!("\n⚠️  {} [y/N] ", question);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        Ok(input.trim().eq_ignore_ascii_case("y"))
+    }
+
+    /// Service message for CI log parsers; printed even when silent, but
+    /// never into JSON output
+    fn ci_message(&self, name: &str, attributes: &[(&str, &str)]) {
+        if self.ci_mode && !self.json {
+            println!("{}", ci::message(name, attributes));
+        }
+    }
+
+    /// Announce a build step with its overall progress in percent
+    fn build_step(&self, name: &str, progress: u8) {
+        self.ci_message("buildStep", &[("name", name)]);
+        self.ci_message("progressMessage", &[("value", &progress.to_string())]);
+    }
+
+    fn log(&self, msg: impl std::fmt::Display) {
+        if !self.quiet() {
+            println!("{}", msg);
         }
     }
 
     fn verbose(&self, msg: impl std::fmt::Display) {
-        if self.verbose && !self.silent {
+        if self.verbose && !self.quiet() {
             println!("  {}", msg);
         }
     }
 
     fn success(&self, msg: impl std::fmt::Display) {
-        if !self.silent {
+        if !self.quiet() {
             println!("✅ {}", msg);
         }
     }
 
     fn warn(&self, msg: impl std::fmt::Display) {
-        if !self.silent {
+        if !self.quiet() {
             eprintln!("⚠️  {}", msg);
         }
     }
 
     fn error(&self, msg: impl std::fmt::Display) {
-        eprintln!("❌ {}", msg); // Always print errors
+        // Always printed, except into JSON output (the result carries the error)
+        if !self.json {
+            eprintln!("❌ {}", msg);
+        }
     }
 
     fn spinner(&self, msg: &str) -> Option<ProgressBar> {
-        if self.silent {
+        // Spinner redraws garble CI logs
+        if self.quiet() || self.ci_mode {
             None
         } else {
             let pb = ProgressBar::new_spinner();
@@ -187,91 +1174,585 @@ impl Console {
             Some(pb)
         }
     }
+
+    /// Byte progress bar showing current speed against `limit_kbps`
+    fn download_bar(&self, limit_kbps: Option<u64>) -> Option<ProgressBar> {
+        if self.quiet() {
+            return None;
+        }
+        let pb = ProgressBar::new(0);
+        pb.set_style(ProgressStyle::with_template(
+            "   [{bar:30.cyan/blue}] {bytes}/{total_bytes} {binary_bytes_per_sec}{msg}"
+        ).unwrap().progress_chars("=> "));
+        if let Some(kbps) = limit_kbps {
+            pb.set_message(format!(" (limit {} KiB/s)", kbps));
+        }
+        Some(pb)
+    }
+}
+
+/// Effective download speed limit in KiB/s from the flags and global config
+fn download_rate_limit(limit_rate: Option<u64>, no_limit: bool) -> Option<u64> {
+    if no_limit {
+        return None;
+    }
+    limit_rate.or_else(|| {
+        lxe_common::config::GlobalConfig::load().ok()
+            .and_then(|config| config.max_download_speed_kbps)
+    })
+    .filter(|&kbps| kbps > 0)
 }
 
 fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let console = Console::new(cli.silent, cli.verbose);
+    use clap::{CommandFactory, FromArgMatches};
+    
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let mut console = Console::new(cli.silent, cli.verbose);
+    if cli.output_format == OutputMode::Human {
+        return run(cli, console);
+    }
+    
+    console.json = true;
+    let report = std::sync::Arc::clone(&console.report);
+    let command = matches.subcommand_name().unwrap_or_default().to_string();
+    let result = run(cli, console);
+    let report = std::mem::take(&mut *report.lock().unwrap());
+    println!("{}", json_result(&command, report, result.as_ref().err()));
+    if result.is_err() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The `--output-format json` object of `command`: status, the reported
+/// fields and the error
+fn json_result(
+    command: &str,
+    report: serde_json::Map<String, serde_json::Value>,
+    error: Option<&anyhow::Error>,
+) -> serde_json::Value {
+    let mut result = serde_json::Map::new();
+    result.insert("status".into(), if error.is_some() { "error" } else { "ok" }.into());
+    result.insert("command".into(), command.into());
+    result.extend(report);
+    result.insert("error".into(), error.map(|e| format!("{:#}", e)).into());
+    result.into()
+}
+
+fn run(cli: Cli, mut console: Console) -> Result<()> {
+    let rate_limit = download_rate_limit(cli.limit_rate, cli.no_limit);
+    let proxy = cli.proxy.as_deref();
     
     match cli.command {
-        Commands::Build { config, no_script } => {
-            cmd_build(config, no_script, &console)
+        Commands::Build {
+            config,
+            no_script,
+            detect_wmclass,
+            strip_symbols,
+            strip_tool,
+            strip_shared,
+            compress_icons,
+            manifest_only,
+            output_dir,
+            overwrite,
+            conflict,
+            keep_latest,
+            ci_output,
+            generate_update_metadata,
+            append_to,
+            download_base_url,
+            changelog,
+            target_dir,
+            keep_artifacts,
+            clean_artifacts,
+            clean,
+            verify_runtime,
+            watch,
+            report,
+        } => {
+            console.ci_mode = ci_output;
+            let options = BuildOptions {
+                no_script,
+                detect_wmclass,
+                strip_symbols,
+                strip_tool,
+                strip_shared,
+                compress_icons,
+                manifest_only,
+                output_dir,
+                overwrite,
+                conflict,
+                keep_latest,
+                update_metadata: generate_update_metadata,
+                append_to,
+                download_base_url,
+                changelog,
+                target_dir: target_dir.unwrap_or_else(std::env::temp_dir),
+                keep_artifacts,
+                clean_artifacts,
+                clean,
+                verify_runtime,
+                report,
+            };
+            if watch {
+                return cmd_build_watch(config, &options, &console);
+            }
+            let result = cmd_build(config, &options, &console);
+            if let Err(ref e) = result {
+                console.ci_message("buildProblem", &[("description", &format!("{:#}", e))]);
+                console.ci_message("buildStatus", &[("status", "FAILURE"), ("text", "Build failed")]);
+            }
+            result
         }
-        Commands::Init { yes, preset } => {
-            cmd_init(yes, preset.as_deref(), &console)
+        Commands::Assemble { metadata, payload, runtime, output } => {
+            cmd_assemble(&metadata, &payload, runtime.as_deref(), output, &console)
+        }
+        Commands::Init { yes, detect, preset, ci, from_lxe } => {
+            cmd_init(yes, detect, preset.as_deref(), ci, from_lxe.as_deref(), &console)
         }
         Commands::Runtime { action } => {
             match action {
-                RuntimeAction::Download { force } => cmd_runtime_download(force, &console),
-                RuntimeAction::Status => cmd_runtime_status(&console),
+                RuntimeAction::Download { force, skip_verify, no_resume } => {
+                    cmd_runtime_download(force, skip_verify, no_resume, rate_limit, proxy, &console)
+                }
+                RuntimeAction::Status => cmd_runtime_status(rate_limit, &console),
+                RuntimeAction::ListVersions { limit, prerelease } => {
+                    cmd_runtime_list_versions(limit, prerelease, proxy, &console)
+                }
+                RuntimeAction::Upgrade { version, rollback, skip_verify } => {
+                    cmd_runtime_upgrade(version.as_deref(), rollback, skip_verify, rate_limit, proxy, &console)
+                }
             }
         }
         Commands::Key { action } => {
             match action {
-                KeyAction::Generate { output } => cmd_key_generate(&output, &console),
+                KeyAction::Generate { output, algorithm, batch: None, backend, module, slot, .. } => {
+                    let backend = match backend {
+                        KeyBackendKind::File => lxe_common::signing::KeyBackend::File(output.clone()),
+                        KeyBackendKind::Pkcs11 => lxe_common::signing::KeyBackend::Pkcs11 {
+                            module: module.unwrap_or_default(),
+                            slot,
+                            pin: std::env::var("LXE_PKCS11_PIN").ok(),
+                            public_key: None,
+                        },
+                    };
+                    cmd_key_generate(&output, algorithm, &backend, &console)
+                }
+                KeyAction::Generate { output, algorithm, batch: Some(count), format, .. } => {
+                    cmd_key_generate_batch(&output, algorithm, count, format, &console)
+                }
+                KeyAction::Inspect { key } => cmd_key_inspect(&key, &console),
+                KeyAction::Import { key_source, name, issuer, fingerprint } => {
+                    cmd_key_import(&key_source, &name, issuer.as_deref(), fingerprint.as_deref(), proxy, &console)
+                }
+                KeyAction::Trust { public_key, app_id, name } => {
+                    cmd_key_trust(&public_key, app_id.as_deref(), name.as_deref(), &console)
+                }
+                KeyAction::List => cmd_key_list(&console),
+                KeyAction::Remove { name } => cmd_key_remove(&name, &console),
+                KeyAction::SignFile { key, file, output } => {
+                    cmd_key_sign_file(&key, &file, output, &console)
+                }
+                KeyAction::Rotate { old_key, new_key, dir } => {
+                    cmd_key_rotate(&old_key, &new_key, &dir, &console)
+                }
+                KeyAction::VerifyFile { public_key, file, sig } => {
+                    cmd_key_verify_file(&public_key, &file, sig, &console)
+                }
+            }
+        }
+        Commands::Verify { file, show_chain } => {
+            cmd_verify(&file, show_chain, &console)
+        }
+        Commands::Conformance { file, level } => {
+            cmd_conformance(&file, level, &console)
+        }
+        Commands::Validate { file } => {
+            cmd_validate(&file, &console)
+        }
+        Commands::MigrateAppimage { input_dir, output_dir, key, parallel } => {
+            cmd_migrate_appimage(&input_dir, &output_dir, key.as_deref(), parallel, &console)
+        }
+        Commands::Inspect { file, json } => {
+            cmd_inspect(&file, json, &console)
+        }
+        Commands::Extract { file, output, strip_components } => {
+            cmd_extract(&file, &output, strip_components, &console)
+        }
+        Commands::Diff { old, new, added, removed, changed } => {
+            cmd_diff(&old, &new, (added, removed, changed), &console)
+        }
+        Commands::Delta { old, new, output, level } => {
+            cmd_delta(&old, &new, &output, level, &console)
+        }
+        Commands::SecurityScan { file, level } => {
+            cmd_security_scan(&file, level, &console)
+        }
+        Commands::Benchmark { file, size } => {
+            cmd_benchmark(file.as_deref(), size, &console)
+        }
+        Commands::Sign { file, key, force } => {
+            cmd_sign(&file, &key, force, &console)
+        }
+        Commands::PatchMeta { file, set } => {
+            cmd_patch_meta(&file, &set, &console)
+        }
+        Commands::Uninstall { id, all, yes, system, dry_run, purge, scan_leftovers, remove_leftovers } => {
+            let options = UninstallOptions {
+                yes,
+                system,
+                dry_run,
+                purge,
+                scan_leftovers: scan_leftovers || remove_leftovers,
+                remove_leftovers,
+            };
+            match id {
+                Some(id) if !all => cmd_uninstall(&id, &options, &console),
+                _ => cmd_uninstall_all(&options, &console),
+            }
+        }
+        Commands::Workspace { action } => {
+            match action {
+                WorkspaceAction::Build { all, packages, jobs } => {
+                    cmd_workspace_build(all, &packages, jobs, &console)
+                }
+            }
+        }
+        Commands::Swap { id, file, keep_running } => {
+            cmd_swap(&id, &file, keep_running, &console)
+        }
+        Commands::Transaction { actions, action } => {
+            match (actions, action) {
+                (_, Some(TransactionAction::Begin { id })) => cmd_transaction_begin(&id, &console),
+                (_, Some(TransactionAction::Add { kind, target, id })) => {
+                    cmd_transaction_add(kind, &target, id.as_deref(), &console)
+                }
+                (_, Some(TransactionAction::Commit { id })) => cmd_transaction_commit(id.as_deref(), &console),
+                (_, Some(TransactionAction::Rollback { id })) => cmd_transaction_rollback(id.as_deref(), &console),
+                (Some(actions), None) => cmd_transaction_run(&actions, &console),
+                (None, None) => anyhow::bail!("Pass an actions file or a subcommand (see: lxe transaction --help)"),
+            }
+        }
+        Commands::Status { format, watch } => {
+            cmd_status(format, watch, proxy, &console)
+        }
+        Commands::Doctor => {
+            cmd_doctor(&console)
+        }
+        Commands::VerifyInstall { id } => {
+            cmd_verify_install(&id, &console)
+        }
+        Commands::CheckOrphans { id, delete, yes } => {
+            cmd_check_orphans(&id, delete, yes, &console)
+        }
+        Commands::Clean { dry_run, yes } => {
+            cmd_clean(dry_run, yes, &console)
+        }
+        Commands::Stats { format, top } => {
+            cmd_stats(format, top, &console)
+        }
+        Commands::NotifyUpdates { interval, daemon, stop } => {
+            cmd_notify_updates(interval, daemon, stop, proxy, &console)
+        }
+        Commands::Run { id, sandbox_preset, args } => {
+            cmd_run(&id, sandbox_preset.as_deref(), &args, &console)
+        }
+        Commands::SandboxProfiles { action } => {
+            match action {
+                SandboxProfilesAction::List => cmd_sandbox_profiles_list(&console),
             }
         }
-        Commands::Verify { file } => {
-            cmd_verify(&file, &console)
+        Commands::List {
+            show_deprecated,
+            user,
+            format,
+            filter,
+            sort,
+            reverse,
+            system_only,
+            user_only,
+            count,
+        } => {
+            let options = ListOptions {
+                show_deprecated,
+                user,
+                format,
+                filter,
+                sort,
+                reverse,
+                system_only,
+                user_only,
+                count,
+            };
+            cmd_list(&options, &console)
+        }
+        Commands::History { app_id, limit } => {
+            cmd_history(app_id.as_deref(), limit, &console)
+        }
+        Commands::Deprecate { id, version, message, registry } => {
+            cmd_deprecate(&id, version.as_deref(), &message, registry.as_deref(), proxy, &console)
         }
-        Commands::Uninstall { id, yes, system } => {
-            cmd_uninstall(&id, yes, system, &console)
+        Commands::Repo { action } => {
+            match action {
+                RepoAction::Init { dir, name } => cmd_repo_init(&dir, name.as_deref(), &console),
+                RepoAction::Add { file, repo, generate_sha256 } => {
+                    cmd_repo_add(&repo, &file, generate_sha256, &console)
+                }
+                RepoAction::Remove { id, version, repo } => cmd_repo_remove(&repo, &id, &version, &console),
+                RepoAction::Serve { port, repo } => cmd_repo_serve(&repo, port, &console),
+                RepoAction::Index { repo, generate_sha256 } => cmd_repo_index(&repo, generate_sha256, &console),
+                RepoAction::Search { query, url } => cmd_repo_search(&query, url, proxy, &console),
+            }
         }
         Commands::SelfUpdate { check } => {
-            cmd_self_update(check, &console)
+            cmd_self_update(check, proxy, &console)
+        }
+        Commands::Completion { action } => cmd_completion(action, &console),
+        Commands::Help { topic } => cmd_help(&topic),
+    }
+}
+
+/// Flags of `lxe build`
+struct BuildOptions {
+    no_script: bool,
+    detect_wmclass: bool,
+    strip_symbols: bool,
+    strip_tool: Option<PathBuf>,
+    strip_shared: bool,
+    compress_icons: bool,
+    manifest_only: bool,
+    output_dir: Option<PathBuf>,
+    overwrite: bool,
+    conflict: Option<lxe_common::config::ConflictAction>,
+    keep_latest: Option<usize>,
+    update_metadata: Option<PathBuf>,
+    append_to: Option<PathBuf>,
+    download_base_url: Option<String>,
+    changelog: Option<String>,
+    target_dir: PathBuf,
+    keep_artifacts: bool,
+    clean_artifacts: bool,
+    clean: bool,
+    verify_runtime: bool,
+    report: Option<PathBuf>,
+}
+
+/// Apply `--conflict` (or the global default) to an existing output file
+///
+/// `--output-dir` collects releases, so there an existing file of the same
+/// version is an error unless `--overwrite` or a conflict action says otherwise.
+/// Returns the path and whether the existing file is to be backed up once
+/// the package is built.
+fn resolve_conflict(path: PathBuf, options: &BuildOptions, console: &Console) -> Result<(PathBuf, bool)> {
+    use lxe_common::config::{ConflictAction, GlobalConfig};
+    
+    if !path.exists() {
+        return Ok((path, false));
+    }
+    let action = match options.conflict.or(options.overwrite.then_some(ConflictAction::Overwrite)) {
+        Some(action) => Some(action),
+        None => GlobalConfig::load()?.conflict,
+    };
+    let action = action
+        .unwrap_or(if options.output_dir.is_some() { ConflictAction::Error } else { ConflictAction::Overwrite });
+    
+    let resolved = build::resolve_output_conflict(&path, action)?;
+    match action {
+        // Already failed above
+        ConflictAction::Error => {}
+        ConflictAction::Overwrite => {
+            console.verbose(format!("{} exists, overwriting it", path.display()));
+        }
+        ConflictAction::Rename => {
+            console.verbose(format!("{} exists, writing {} instead", path.display(), resolved.display()));
+        }
+        ConflictAction::Backup => {
+            console.verbose(format!("{} exists, it will be moved to {}.bak", path.display(), path.display()));
         }
     }
+    Ok((resolved, action == ConflictAction::Backup))
 }
 
-/// Build an LXE package
-fn cmd_build(config_path: Option<PathBuf>, no_script: bool, console: &Console) -> Result<()> {
+/// Build an LXE package, running the `[build.hooks]` around it
+fn cmd_build(config_path: Option<PathBuf>, options: &BuildOptions, console: &Console) -> Result<()> {
+    use lxe_common::hooks::{self, BuildHook};
+    
+    let build_start = std::time::Instant::now();
     console.log("🔧 LXE Builder v2.0.0\n");
     
     // Load configuration
     let base_dir = std::env::current_dir()?;
-    let config = if let Some(path) = config_path {
-        LxeConfig::from_file(&path)?
+    let config = if let Some(ref path) = config_path {
+        LxeConfig::from_file(path)?
     } else {
         LxeConfig::from_current_dir()?
     };
+    let config_file = config_path.unwrap_or_else(|| base_dir.join("lxe.toml"));
     
-    // Validate
-    if !no_script && config.build.script.is_some() {
-        // Will validate after script runs
-    } else {
-        config.validate(&base_dir)?;
+    let result = (|| {
+        if hooks::run(BuildHook::PreBuild, &config, &base_dir, &[], console.json)? {
+            console.log("   ✓ pre_build hook completed\n");
+        }
+        let output = build_package(&config, &config_file, &base_dir, options, console, build_start)?;
+        if hooks::run(BuildHook::PostBuild, &config, &base_dir, &[("LXE_OUTPUT_PATH", &output.to_string_lossy())], console.json)? {
+            console.log("   ✓ post_build hook completed");
+        }
+        Ok(())
+    })();
+    
+    if let Err(ref e) = result {
+        let message = format!("{:#}", e);
+        if let Err(hook_error) = hooks::run(BuildHook::OnBuildFailed, &config, &base_dir, &[("LXE_ERROR", &message)], console.json) {
+            console.warn(format!("{:#}", hook_error));
+        }
     }
+    result
+}
+
+/// `lxe build --watch`: build, then rebuild after every change until interrupted
+///
+/// A failed build is reported and the next change is awaited.
+fn cmd_build_watch(config_path: Option<PathBuf>, options: &BuildOptions, console: &Console) -> Result<()> {
+    use watch::{BuildWatcher, WatchTargets};
     
-    console.log(format!("📦 Package: {} v{}", config.package.name, config.package.version));
-    console.log(format!("   App ID: {}", config.package.id));
+    if console.json {
+        anyhow::bail!("--watch doesn't work with --output-format json");
+    }
     
-    // Run build script if specified
-    if let Some(ref script) = config.build.script {
-        if no_script {
-            console.log("   ⏭️  Skipping build script (--no-script)");
-        } else {
-            console.log(format!("\n🔨 Running build script: {}", script));
-            
-            let status = Command::new("sh")
-                .arg("-c")
-                .arg(script)
-                .current_dir(&base_dir)
-                .status()
-                .context("Failed to run build script")?;
-            
-            if !status.success() {
+    let base_dir = std::env::current_dir()?;
+    let config_file = base_dir.join(config_path.as_deref().unwrap_or(std::path::Path::new("lxe.toml")));
+    let config = LxeConfig::from_file(&config_file)?;
+    let build_dirs: Vec<PathBuf> = [Some(&options.target_dir), options.output_dir.as_ref()].into_iter().flatten().cloned().collect();
+    let mut targets = WatchTargets::new(&config, &config_file, &base_dir, &build_dirs);
+    let mut watcher = BuildWatcher::new(targets.clone())?;
+    
+    for attempt in 1.. {
+        console.log(format!("━━━ Build #{} at {} ━━━", attempt, watch::local_time()));
+        if let Err(e) = cmd_build(config_path.clone(), options, console) {
+            console.error(format!("Build failed: {:#}", e));
+        }
+        watcher.discard_pending();
+        
+        let watched: Vec<String> = targets.dirs.iter().chain(&targets.files)
+            .map(|path| path.strip_prefix(&base_dir).unwrap_or(path).display().to_string())
+            .map(|path| if path.is_empty() { ".".to_string() } else { path })
+            .collect();
+        console.log(format!("\n👀 Watching {} for changes (Ctrl+C to stop)\n", watched.join(", ")));
+        let changed = watcher.wait_for_change()?;
+        let first = changed[0].strip_prefix(&base_dir).unwrap_or(&changed[0]);
+        match changed.len() {
+            1 => console.log(format!("🔄 {} changed", first.display())),
+            n => console.log(format!("🔄 {} and {} more changed", first.display(), n - 1)),
+        }
+        
+        // A changed input or build script moves what needs watching
+        if changed.contains(&config_file) {
+            if let Ok(config) = LxeConfig::from_file(&config_file) {
+                let new_targets = WatchTargets::new(&config, &config_file, &base_dir, &build_dirs);
+                if new_targets != targets {
+                    watcher = BuildWatcher::new(new_targets.clone())?;
+                    targets = new_targets;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The build steps; returns the package (the metadata file with `--manifest-only`)
+fn build_package(
+    config: &LxeConfig,
+    config_file: &std::path::Path,
+    base_dir: &std::path::Path,
+    options: &BuildOptions,
+    console: &Console,
+    build_start: std::time::Instant,
+) -> Result<PathBuf> {
+    // Checkpoints of an interrupted build live next to the config
+    let state_path = config_file.with_file_name(build::BUILD_STATE_FILE);
+    if options.clean && build::BuildState::remove(&state_path)? {
+        console.log("🧹 Discarded the saved build progress (--clean)");
+    }
+    
+    // Validate
+    if !options.no_script && config.build.script.is_some() {
+        // Will validate after script runs
+    } else {
+        config.validate(base_dir)?;
+    }
+    
+    console.log(format!("📦 Package: {} v{}", config.package.name, config.package.version));
+    console.log(format!("   App ID: {}", config.package.id));
+    
+    // Run build script if specified
+    if let Some(ref script) = config.build.script {
+        if options.no_script {
+            console.log("   ⏭️  Skipping build script (--no-script)");
+        } else {
+            config.validate_script(base_dir)?;
+            let script_dir = config.script_dir(base_dir);
+            
+            // Look into script files too (./lxe-build.sh)
+            let script_contents = lxe_common::config::script_file(script)
+                .and_then(|file| fs::read_to_string(script_dir.join(file)).ok());
+            if std::iter::once(script.as_str()).chain(script_contents.as_deref())
+                .any(lxe_common::config::detect_recursive_build)
+            {
+                console.warn(
+                    "The build script seems to run 'lxe build' (or lxe-pack) itself.\n   \
+                     Recursive builds are not supported and may loop forever."
+                );
+            }
+            
+            console.log(format!("\n🔨 Running build script: {}", script));
+            console.build_step("Running build script", 10);
+            
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(script).current_dir(&script_dir);
+            // Keep stdout for the JSON result
+            if console.json {
+                lxe_common::hooks::redirect_stdout_to_stderr(&mut command)?;
+            }
+            let status = command.status()
+                .context("Failed to run build script")?;
+            
+            if !status.success() {
                 anyhow::bail!("Build script failed with exit code: {:?}", status.code());
             }
             
             console.log("   ✓ Build script completed successfully");
             
             // Validate now that input should exist
-            config.validate(&base_dir)?;
+            config.validate(base_dir)?;
         }
     }
     
-    let input_path = config.input_path(&base_dir);
-    let output_path = config.output_path(&base_dir);
+    let input_path = config.input_path(base_dir);
+    
+    // Versioned file name in --output-dir
+    let output_template = config.build.output_template.as_deref().unwrap_or(build::DEFAULT_TEMPLATE);
+    let template_metadata = lxe_common::metadata::LxeMetadata::new(
+        &config.package.id,
+        &config.package.name,
+        &config.package.version,
+        &config.package.executable,
+        0,
+        "",
+    );
+    let output_path = match options.output_dir {
+        Some(ref dir) => {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+            dir.join(build::OutputPathBuilder::from_template(output_template, &template_metadata))
+        }
+        None => config.output_path(base_dir),
+    };
+    let (output_path, back_up_existing) = if options.manifest_only {
+        (output_path, false)
+    } else {
+        resolve_conflict(output_path, options, console)?
+    };
     
     console.log(format!("\n📁 Input: {}", input_path.display()));
     console.log(format!("📄 Output: {}", output_path.display()));
@@ -288,77 +1769,268 @@ fn cmd_build(config_path: Option<PathBuf>, no_script: bool, console: &Console) -
         );
     }
     
+    // Dynamic WM_CLASS / D-Bus activation detection
+    let mut wm_class = config.package.wm_class.clone();
+    let mut dbus_activatable = false;
+    if options.detect_wmclass {
+        let spinner = console.spinner("Detecting WM_CLASS (running app under Xvfb)...");
+        let hints = lxe_common::window_hints::detect(
+            &exec_path,
+            &config.package.id,
+            std::time::Duration::from_secs(5),
+        );
+        if let Some(pb) = spinner {
+            pb.finish_and_clear();
+        }
+        
+        match hints {
+            Ok(hints) => {
+                match (&hints.wm_class, &wm_class) {
+                    (Some(detected), Some(configured)) if detected != configured => {
+                        console.warn(format!(
+                            "Detected WM_CLASS '{}' differs from configured '{}' (keeping configured)",
+                            detected, configured
+                        ));
+                    }
+                    (Some(detected), None) => {
+                        console.log(format!("   🪟 Detected WM_CLASS: {}", detected));
+                        wm_class = Some(detected.clone());
+                    }
+                    (None, _) => console.warn("No window appeared within 5s - WM_CLASS not detected"),
+                    _ => {}
+                }
+                if hints.dbus_activatable {
+                    console.log("   🚌 App is D-Bus activatable");
+                    dbus_activatable = true;
+                }
+            }
+            Err(e) => console.warn(format!("WM_CLASS detection skipped: {}", e)),
+        }
+    }
+    
+    // Strip symbols into a staging dir; the input is left untouched
+    let stripped = if options.strip_symbols || config.build.strip_symbols {
+        let strip_options = lxe_common::strip::StripOptions {
+            tool: options.strip_tool.clone().unwrap_or_else(|| PathBuf::from("strip")),
+            include_shared: options.strip_shared,
+        };
+        console.log(format!("\n✂️  Stripping symbols ({})...", strip_options.tool.display()));
+        console.build_step("Stripping symbols", 30);
+        let stripped = lxe_common::strip::strip_elf_files(&input_path, &strip_options)?;
+        if stripped.overrides.is_empty() {
+            console.log("   No unstripped ELF files found");
+        } else {
+            console.log(format!(
+                "   {} file(s): {} → {} bytes (saved {:.1} MB)",
+                stripped.overrides.len(),
+                stripped.original_size,
+                stripped.stripped_size,
+                stripped.saved_bytes() as f64 / 1024.0 / 1024.0
+            ));
+        }
+        Some(stripped)
+    } else {
+        None
+    };
+    
+    let mut overrides = stripped.as_ref()
+        .map(|s| s.overrides.clone())
+        .unwrap_or_default();
+    
+    // The installer only sizes PNG icons; render SVGs instead of needing librsvg
+    let svg_icon = builder::render_svg_icon(config, &input_path)?;
+    if !svg_icon.rendered.is_empty() {
+        console.log("\n🖼️  Rendering SVG icon...");
+    }
+    for render in &svg_icon.rendered {
+        if render.updated {
+            console.log(format!("   {} ({}x{})", render.path.display(), render.size, render.size));
+        } else {
+            console.verbose(format!("{} is up to date", render.path.display()));
+        }
+    }
+    let package_icon = svg_icon.package_icon;
+    
+    // Optimize the icon the same way
+    let _optimized_icon = match package_icon {
+        Some(ref icon) if options.compress_icons || config.build.compress_icons => {
+            console.log("\n🖼️  Optimizing icon...");
+            console.build_step("Optimizing icon", 35);
+            let optimized = lxe_common::icon::optimize_icon(
+                &input_path.join(icon),
+                config.build.icon_compression_level,
+            )?;
+            match optimized {
+                Some(ref optimized) => {
+                    let (width, height) = optimized.dimensions;
+                    let max = lxe_common::icon::MAX_ICON_SIZE;
+                    if optimized.resized {
+                        console.log(format!("   Scaled {}x{} → {}x{}", width, height, max, max));
+                    } else if width > max || height > max {
+                        console.warn(format!(
+                            "Icon is {}x{}; install ImageMagick to scale it to {}x{}",
+                            width, height, max, max
+                        ));
+                    }
+                    if optimized.optimizer.is_none() {
+                        console.warn("Neither oxipng nor optipng is installed - icon not recompressed");
+                    }
+                    console.log(format!(
+                        "   {} → {} bytes",
+                        optimized.original_size, optimized.optimized_size
+                    ));
+                    overrides.insert(PathBuf::from(icon.trim_start_matches("./")), optimized.path.clone());
+                }
+                None => console.log("   Not a PNG - skipped"),
+            }
+            optimized
+        }
+        _ => None,
+    };
+    
+    if options.clean_artifacts {
+        for removed in build::clean_stale_artifacts(&options.target_dir, build::STALE_ARTIFACT_AGE) {
+            console.verbose(format!("Removed stale artifacts {}", removed.display()));
+        }
+    }
+    
+    let mut report = report::BuildReport::default();
+    report.input.file_count = report::count_files(&input_path);
+    report.compression.compressor = config.build.compressor().to_string();
+    
+    // Intermediate files go to disk so huge packages don't exhaust memory
+    let artifacts = build::BuildArtifacts::create(&options.target_dir, &config.package.id)?;
+    let mut state = build::BuildState::new(config_file, build::input_fingerprint(&input_path, &overrides)?);
+    let previous = build::BuildState::load(&state_path);
+    
     // Create tar archive
     console.log("\n📁 Creating archive...");
-    let tar_data = create_tar_archive(&input_path)?;
-    let uncompressed_mb = tar_data.len() as f64 / 1024.0 / 1024.0;
+    console.build_step("Creating archive", 40);
+    let restored_tar = previous.as_ref().and_then(|p| p.restore_tar(&state.inputs, &artifacts.tar_path));
+    let tar_restored = restored_tar.is_some();
+    let tar_sha256 = match restored_tar {
+        Some(sha256) => {
+            console.log("   ♻️  Reusing the archive of the interrupted build");
+            sha256
+        }
+        None => {
+            let tar_file = fs::File::create(&artifacts.tar_path)
+                .with_context(|| format!("Failed to create {}", artifacts.tar_path.display()))?;
+            let mut writer = build::HashingWriter::new(std::io::BufWriter::new(tar_file));
+            builder::write_tar_archive(&input_path, &overrides, config.build.reproducible, &mut writer)?;
+            writer.finalize()
+        }
+    };
+    state.tar_path = Some(artifacts.tar_path.clone());
+    state.tar_sha256 = Some(tar_sha256);
+    state.save(&state_path)?;
+    let tar_size = fs::metadata(&artifacts.tar_path)?.len();
+    report.input.uncompressed_bytes = tar_size;
+    let uncompressed_mb = tar_size as f64 / 1024.0 / 1024.0;
     console.log(format!("   Uncompressed: {} bytes ({:.1} MB)", 
-             tar_data.len(), uncompressed_mb));
+             tar_size, uncompressed_mb));
     
-    // Warn for large packages
-    if uncompressed_mb > 100.0 {
-        console.log(format!("   ⏳ Large package - compression may take 1-2 minutes..."));
+    // A payload is only reusable together with its archive
+    let restored_payload = previous.as_ref()
+        .filter(|_| tar_restored)
+        .and_then(|p| p.restore_payload(config.build.compressor(), &artifacts.compressed_path));
+    if let Some(previous) = &previous {
+        previous.remove_artifacts(&artifacts.dir);
     }
-    
-    // Compress with zstd (with spinner)
-    let spinner = console.spinner(&format!("Compressing (level {})...", config.build.compression));
-    let compression_start = std::time::Instant::now();
-    let compressed = compress_zstd(&tar_data, config.build.compression)?;
-    let compression_time = compression_start.elapsed();
-    let ratio = tar_data.len() as f64 / compressed.len() as f64;
-    if let Some(pb) = spinner {
-        pb.finish_with_message(format!("Compressed: {} bytes ({:.1}x ratio) in {:.1}s", 
-                                       compressed.len(), ratio, compression_time.as_secs_f64()));
+    let compressed_size = if restored_payload.is_some() {
+        console.log("   ♻️  Reusing the payload of the interrupted build");
+        let compressed_size = fs::metadata(&artifacts.compressed_path)?.len();
+        report.set_compression(compressed_size, None);
+        compressed_size
     } else {
-        console.log(format!("   Compressed: {} bytes ({:.1}x ratio) in {:.1}s", 
-                           compressed.len(), ratio, compression_time.as_secs_f64()));
-    }
+        // Warn for large packages
+        if uncompressed_mb > 100.0 {
+            console.log(format!("   ⏳ Large package - compression may take 1-2 minutes..."));
+        }
+        
+        // A smaller window costs some ratio, the OOM killer costs the build
+        // (LZ4 needs little memory at any level)
+        let compressor = config.build.compressor();
+        let level = compressor.level();
+        let window_log = config.build.max_memory_mb
+            .filter(|_| matches!(compressor, lxe_common::config::Compressor::Zstd { .. }))
+            .and_then(|cap| build::capped_window_log(level, build::compression_budget(cap)));
+        if let Some(window_log) = window_log {
+            console.log(format!(
+                "   🧠 Memory cap: compressing with a {} KB window (~{} MB instead of ~{} MB)",
+                1u64 << (window_log - 10),
+                build::estimate_compression_memory(level, Some(window_log)) / 1024 / 1024,
+                build::estimate_compression_memory(level, None) / 1024 / 1024,
+            ));
+        }
+        
+        // Compress (with spinner)
+        console.build_step("Compressing", 50);
+        let spinner = console.spinner(&format!("Compressing ({})...", compressor));
+        let compression_start = std::time::Instant::now();
+        let tar_file = fs::File::open(&artifacts.tar_path)
+            .with_context(|| format!("Failed to open {}", artifacts.tar_path.display()))?;
+        let mut watch = build::RssWatch::new(tar_file);
+        let compressed_size = builder::compress_stream(&mut watch, &artifacts.compressed_path, &compressor, window_log)?;
+        let compression_time = compression_start.elapsed();
+        report.set_compression(compressed_size, Some(compression_time.as_secs_f64()));
+        let ratio = tar_size as f64 / compressed_size as f64;
+        if let Some(pb) = spinner {
+            pb.finish_with_message(format!("Compressed: {} bytes ({:.1}x ratio) in {:.1}s", 
+                                           compressed_size, ratio, compression_time.as_secs_f64()));
+        } else {
+            console.log(format!("   Compressed: {} bytes ({:.1}x ratio) in {:.1}s", 
+                               compressed_size, ratio, compression_time.as_secs_f64()));
+        }
+        if let Some(cap) = config.build.max_memory_mb {
+            let peak_mb = watch.peak / 1024 / 1024;
+            if peak_mb > cap {
+                console.warn(format!("Memory use reached {} MB during compression (max_memory_mb = {})", peak_mb, cap));
+            }
+        }
+        compressed_size
+    };
     
     // Calculate checksum
-    let checksum = calculate_sha256(&compressed);
-    console.verbose(format!("SHA256: {}", checksum));
+    let checksum = lxe_common::payload::checksum_file(&artifacts.compressed_path, config.build.checksum_algorithm)?;
+    console.verbose(format!("{}: {}", config.build.checksum_algorithm, checksum));
+    let payload_sha256 = match (restored_payload, config.build.checksum_algorithm) {
+        (Some(sha256), _) => sha256,
+        (None, lxe_common::payload::ChecksumAlgorithm::Sha256) => checksum.clone(),
+        (None, _) => lxe_common::payload::checksum_file(&artifacts.compressed_path, Default::default())?,
+    };
+    state.payload_path = Some(artifacts.compressed_path.clone());
+    state.payload_sha256 = Some(payload_sha256);
+    state.compressor = Some(config.build.compressor());
+    state.save(&state_path)?;
     
     // Build metadata JSON
-    let categories: Vec<String> = config.package.categories.clone();
-    let mut metadata = serde_json::json!({
-        "format_version": 1,
-        "app_id": config.package.id,
-        "name": config.package.name,
-        "version": config.package.version,
-        "arch": std::env::consts::ARCH,
-        "install_size": tar_data.len(),
-        "exec": config.package.executable,
-        "icon": config.package.icon,
-        "description": config.package.description,
-        "categories": categories,
-        "terminal": config.package.terminal,
-        "wm_class": config.package.wm_class,
-        "payload_checksum": checksum,
-        // Installer customization
-        "installer": {
-            "welcome_title": config.installer.welcome_title,
-            "welcome_text": config.installer.welcome_text,
-            "finish_title": config.installer.finish_title,
-            "finish_text": config.installer.finish_text,
-            "accent_color": config.installer.accent_color,
-            "theme": config.installer.theme,
-            "show_launch": config.installer.show_launch.unwrap_or(true),
-            // Advanced branding - read license file if specified
-            "license_text": config.installer.license.as_ref().and_then(|p| {
-                let path = base_dir.join(p);
-                std::fs::read_to_string(&path).ok()
-            }),
-            "banner": config.installer.banner,
-            "logo": config.installer.logo,
-            "allow_custom_dir": config.installer.allow_custom_dir.unwrap_or(false),
-        },
-    });
+    let hints = builder::BuildHints { wm_class, dbus_activatable };
+    let mut metadata = builder::package_metadata(config, base_dir, tar_size, &checksum, &hints);
+    if package_icon != config.package.icon {
+        metadata["icon"] = serde_json::json!(package_icon);
+    }
     
     // Sign if key provided
-    if let Some(key_path) = config.key_path(&base_dir) {
+    if let Some(key_path) = config.key_path(base_dir) {
         if key_path.exists() {
             console.log("🔏 Signing package...");
-            sign_metadata(&mut metadata, &key_path, &checksum)?;
+            console.build_step("Signing", 85);
+            let key_sha256 = lxe_common::payload::checksum_file(&key_path, Default::default())?;
+            let unsigned_metadata = metadata.clone();
+            match previous.as_ref().and_then(|p| p.signed_metadata(&metadata, &key_sha256)) {
+                Some(signed) => metadata = signed,
+                None => {
+                    let key = lxe_common::signing::KeyBackend::from_key_file(&key_path)?;
+                    builder::sign_metadata(&mut metadata, &key, &checksum)?
+                }
+            }
+            state.signing = Some(build::SigningState {
+                key_sha256,
+                unsigned_metadata,
+                signed_metadata: metadata.clone(),
+            });
+            state.save(&state_path)?;
             console.log("   ✓ Package signed");
         } else {
             console.warn(format!("Key file not found: {}", key_path.display()));
@@ -367,73 +2039,244 @@ fn cmd_build(config_path: Option<PathBuf>, no_script: bool, console: &Console) -
     
     let metadata_json = serde_json::to_vec(&metadata)?;
     console.verbose(format!("Metadata: {} bytes", metadata_json.len()));
+    let signature_status = if metadata.get("signature").is_some() { "signed" } else { "unsigned" };
+    console.ci_message("signatureStatus", &[("value", signature_status)]);
+    
+    if options.manifest_only {
+        let output_dir = output_path.parent().unwrap_or(base_dir);
+        let (metadata_path, payload_path) = lxe_common::assembly::part_paths(output_dir, &config.package.id);
+        fs::write(&metadata_path, &metadata_json)
+            .with_context(|| format!("Failed to write {}", metadata_path.display()))?;
+        fs::copy(&artifacts.compressed_path, &payload_path)
+            .with_context(|| format!("Failed to write {}", payload_path.display()))?;
+        build::BuildState::remove(&state_path)?;
+        finish_build_artifacts(artifacts, options, console);
+        
+        for (path, size) in [(&metadata_path, metadata_json.len() as u64), (&payload_path, compressed_size)] {
+            console.ci_message("artifact", &[
+                ("path", &path.strip_prefix(base_dir).unwrap_or(path).display().to_string()),
+                ("size", &size.to_string()),
+            ]);
+        }
+        console.ci_message("progressMessage", &[("value", "100")]);
+        console.ci_message("buildStatus", &[
+            ("status", "SUCCESS"),
+            ("text", &format!("Package parts written in {}s", build_start.elapsed().as_secs())),
+        ]);
+        
+        console.report("output_file", &metadata_path);
+        console.report("payload_file", &payload_path);
+        console.report("signed", signature_status == "signed");
+        console.success("Package parts written");
+        console.log(format!("   📄 {}", metadata_path.display()));
+        console.log(format!("   📦 {}", payload_path.display()));
+        console.log(format!(
+            "\n💡 To assemble: lxe assemble --metadata {} --payload {}",
+            metadata_path.display(),
+            payload_path.display()
+        ));
+        return Ok(metadata_path);
+    }
     
     // Get runtime binary
     console.log("🔗 Preparing runtime...");
-    let runtime_data = get_runtime_binary(&config.runtime_path(&base_dir))?;
+    console.build_step("Assembling", 90);
+    let runtime_path = builder::find_runtime_path(config.runtime_path(base_dir).as_deref())?;
+    let runtime_info = if options.verify_runtime {
+        Some(runtime_verify::verify_runtime_binary(&runtime_path, config.runtime.min_version.as_deref())?)
+    } else {
+        None
+    };
+    let runtime_data = fs::read(&runtime_path)
+        .with_context(|| format!("Failed to read runtime: {}", runtime_path.display()))?;
     console.log(format!("   Runtime: {} bytes ({:.1} MB)", 
              runtime_data.len(),
              runtime_data.len() as f64 / 1024.0 / 1024.0));
+    match runtime_info.map_or_else(|| runtime_verify::inspect_runtime(&runtime_data), Ok) {
+        Ok(info) => {
+            let verified = if options.verify_runtime { ", verified" } else { "" };
+            console.log(format!("   Version: {} ({}{})", info.version, info.arch, verified));
+        }
+        Err(e) => console.warn(format!("{:#} (use --verify-runtime to fail the build)", e)),
+    }
     
     // Assemble final package
     console.log("🔨 Assembling package...");
-    let mut output_file = File::create(&output_path)?;
+    // With --conflict backup, the old package is only moved once the new one is complete
+    let write_path = if back_up_existing { build::partial_output_path(&output_path) } else { output_path.clone() };
+    let total_size = builder::write_package_file(&write_path, &runtime_data, &metadata_json, &checksum, &artifacts.compressed_path)
+        .inspect_err(|_| if back_up_existing { fs::remove_file(&write_path).ok(); })?;
+    if back_up_existing {
+        if let Some(backup) = build::back_up_output(&output_path)? {
+            console.verbose(format!("Moved the previous package to {}", backup.display()));
+        }
+        fs::rename(&write_path, &output_path)
+            .with_context(|| format!("Failed to move {} to {}", write_path.display(), output_path.display()))?;
+    }
+    build::BuildState::remove(&state_path)?;
+    finish_build_artifacts(artifacts, options, console);
     
-    // [Runtime Binary]
-    output_file.write_all(&runtime_data)?;
+    // Relative paths work with CI artifact globs
+    console.ci_message("artifact", &[
+        ("path", &output_path.strip_prefix(base_dir).unwrap_or(&output_path).display().to_string()),
+        ("size", &total_size.to_string()),
+    ]);
+    
+    console.report("output_file", &output_path);
+    console.report("size_bytes", total_size);
+    console.report("signed", signature_status == "signed");
+    console.success("Package created successfully!");
+    console.log(format!("   📄 {}", output_path.display()));
+    console.log(format!("   📊 {} bytes ({:.2} MB)", total_size, total_size as f64 / 1024.0 / 1024.0));
     
-    // [Magic Bytes] - header marker
-    output_file.write_all(LXE_MAGIC)?;
+    if signature_status == "signed" {
+        console.log("   🔐 Signed: Yes");
+    } else {
+        console.log("   🔐 Signed: No");
+    }
     
-    // [Metadata Length (u32 LE)]
-    let metadata_len = metadata_json.len() as u32;
-    output_file.write_all(&metadata_len.to_le_bytes())?;
+    if let Some(ref report_path) = options.report {
+        report.output_path = output_path.clone();
+        report.format_version = lxe_common::payload::FOOTER_VERSION_3;
+        report.set_signing(&metadata);
+        report.sections = report::SectionSizes {
+            runtime: runtime_data.len() as u64,
+            metadata: metadata_json.len() as u64,
+            payload: compressed_size,
+            total: total_size,
+        };
+        report.write(report_path)?;
+        console.log(format!("   📈 Build report: {}", report_path.display()));
+    }
     
-    // [Metadata JSON]
-    output_file.write_all(&metadata_json)?;
+    if options.update_metadata.is_some() || options.append_to.is_some() {
+        write_update_metadata(&metadata, &output_path, total_size, options, console)?;
+    }
     
-    // [Checksum (32 bytes)]
-    let checksum_bytes = hex::decode(&checksum)?;
-    output_file.write_all(&checksum_bytes)?;
+    if let Some(metainfo_path) = builder::write_metainfo(config, &metadata, &output_path)? {
+        console.log(format!("   📇 AppStream: {}", metainfo_path.display()));
+    }
     
-    // [Compressed Payload]
-    output_file.write_all(&compressed)?;
+    if let (Some(dir), Some(keep)) = (&options.output_dir, options.keep_latest) {
+        for removed in build::keep_latest(dir, output_template, &template_metadata, keep)? {
+            console.log(format!("   🗑️  Removed old build {}", removed.display()));
+        }
+    }
     
-    // [Footer: HeaderOffset (u64 LE) + Magic]
-    let header_offset = runtime_data.len() as u64;
-    output_file.write_all(&header_offset.to_le_bytes())?;
-    output_file.write_all(LXE_MAGIC)?;
+    console.log(format!("\n💡 To install: ./{}", output_path.file_name().unwrap().to_string_lossy()));
     
-    output_file.flush()?;
+    console.ci_message("progressMessage", &[("value", "100")]);
+    console.ci_message("buildStatus", &[
+        ("status", "SUCCESS"),
+        ("text", &format!("Package built in {}s", build_start.elapsed().as_secs())),
+    ]);
     
-    // Make executable
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let mut perms = fs::metadata(&output_path)?.permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&output_path, perms)?;
+    Ok(output_path)
+}
+
+/// Remove the intermediate files of a successful build, or keep them with
+/// `--keep-artifacts`
+fn finish_build_artifacts(artifacts: build::BuildArtifacts, options: &BuildOptions, console: &Console) {
+    if let Some(dir) = artifacts.finish(options.keep_artifacts) {
+        console.log(format!("   🗃️  Build artifacts kept in {}", dir.display()));
     }
+}
+
+/// Write or extend the update server manifest after a build
+fn write_update_metadata(
+    metadata: &serde_json::Value,
+    package_path: &std::path::Path,
+    file_size: u64,
+    options: &BuildOptions,
+    console: &Console,
+) -> Result<()> {
+    let field = |name: &str| metadata.get(name).and_then(|v| v.as_str()).map(String::from);
+    let app_id = field("app_id").unwrap_or_default();
+    let file_name = package_path.file_name().unwrap_or_default().to_string_lossy();
     
-    let total_size = fs::metadata(&output_path)?.len();
+    let entry = update_manifest::UpdateEntry {
+        version: field("version").unwrap_or_default(),
+        arch: field("arch").unwrap_or_default(),
+        download_url: update_manifest::download_url(options.download_base_url.as_deref(), &file_name),
+        file_size,
+        payload_checksum: field("payload_checksum").unwrap_or_default(),
+        public_key: field("public_key"),
+        signature: field("signature"),
+        min_runtime_version: field("min_runtime_version"),
+        changelog: options.changelog.clone(),
+    };
     
-    console.success("Package created successfully!");
-    console.log(format!("   📄 {}", output_path.display()));
-    console.log(format!("   📊 {} bytes ({:.2} MB)", total_size, total_size as f64 / 1024.0 / 1024.0));
+    let (path, mut manifest) = match (&options.append_to, &options.update_metadata) {
+        (Some(path), _) => {
+            let manifest = update_manifest::UpdateManifest::load(path)?;
+            if manifest.app_id != app_id {
+                anyhow::bail!(
+                    "{} describes {}, not {}",
+                    path.display(), manifest.app_id, app_id
+                );
+            }
+            (path, manifest)
+        }
+        (None, Some(path)) => (path, update_manifest::UpdateManifest::new(&app_id)),
+        (None, None) => return Ok(()),
+    };
     
-    if metadata.get("signature").is_some() {
-        console.log("   🔐 Signed: Yes");
-    } else {
-        console.log("   🔐 Signed: No");
+    if options.download_base_url.is_none() {
+        console.warn("No --download-base-url given - download_url is just the file name");
     }
     
-    console.log(format!("\n💡 To install: ./{}", output_path.file_name().unwrap().to_string_lossy()));
+    manifest.add(entry);
+    manifest.save(path)?;
+    console.log(format!("   📰 Update metadata: {}", path.display()));
+    Ok(())
+}
+
+/// Build a package from the parts written by `lxe build --manifest-only`
+fn cmd_assemble(
+    metadata_path: &std::path::Path,
+    payload_path: &std::path::Path,
+    runtime_path: Option<&std::path::Path>,
+    output: Option<PathBuf>,
+    console: &Console,
+) -> Result<()> {
+    let runtime = match runtime_path {
+        Some(path) => fs::read(path)
+            .with_context(|| format!("Failed to read runtime {}", path.display()))?,
+        None => builder::find_runtime_binary(None)?,
+    };
+    
+    // Named after the app, like `lxe build` does
+    let output = match output {
+        Some(path) => path,
+        None => {
+            let json = fs::read(metadata_path)
+                .with_context(|| format!("Failed to read {}", metadata_path.display()))?;
+            let metadata: lxe_common::metadata::LxeMetadata = serde_json::from_slice(&json)
+                .with_context(|| format!("{} is not LXE metadata", metadata_path.display()))?;
+            PathBuf::from(format!("{}.lxe", metadata.app_id.rsplit('.').next().unwrap_or("app")))
+        }
+    };
+    
+    console.log("🔨 Assembling package...");
+    let (metadata, size) = lxe_common::assembly::assemble(metadata_path, payload_path, &runtime, &output)?;
+    
+    console.success(format!("Assembled {} v{}", metadata.name, metadata.version));
+    console.log(format!("   📄 {}", output.display()));
+    console.log(format!("   📊 {} bytes ({:.2} MB)", size, size as f64 / 1024.0 / 1024.0));
+    console.log(format!("   🔐 Signed: {}", if metadata.is_signed() { "Yes" } else { "No" }));
     
     Ok(())
 }
 
 /// Create template lxe.toml (interactive or with preset)
-fn cmd_init(accept_defaults: bool, preset: Option<&str>, console: &Console) -> Result<()> {
+fn cmd_init(
+    accept_defaults: bool,
+    detect: bool,
+    preset: Option<&str>,
+    ci: Option<CiTarget>,
+    from_lxe: Option<&std::path::Path>,
+    console: &Console,
+) -> Result<()> {
     let config_path = std::env::current_dir()?.join("lxe.toml");
     
     if config_path.exists() {
@@ -446,10 +2289,43 @@ fn cmd_init(accept_defaults: bool, preset: Option<&str>, console: &Console) -> R
             "tauri" => TAURI_TEMPLATE.to_string(),
             "python" => PYTHON_TEMPLATE.to_string(),
             "electron" => ELECTRON_TEMPLATE.to_string(),
+            "flutter" => {
+                let current_dir = std::env::current_dir()?;
+                let executable = detect::pubspec_name(&current_dir).unwrap_or_else(|| {
+                    console.warn("No pubspec.yaml found - using placeholder executable name");
+                    "my_app".to_string()
+                });
+                FLUTTER_TEMPLATE.replace("{executable}", &executable)
+            }
+            "go" => {
+                let current_dir = std::env::current_dir()?;
+                let executable = detect::go_module_name(&current_dir).unwrap_or_else(|| {
+                    console.warn("No go.mod found - using placeholder executable name");
+                    "my-app".to_string()
+                });
+                let package = detect::go_main_package(&current_dir, &executable).unwrap_or_else(|| ".".to_string());
+                GO_TEMPLATE.replace("{executable}", &executable).replace("{package}", &package)
+            }
+            "dotnet" => {
+                let current_dir = std::env::current_dir()?;
+                let executable = detect::dotnet_assembly_name(&current_dir).unwrap_or_else(|| {
+                    console.warn("No .csproj found - using placeholder executable name");
+                    "MyApp".to_string()
+                });
+                DOTNET_TEMPLATE.replace("{executable}", &executable)
+            }
+            "rust-xtask" => {
+                let current_dir = std::env::current_dir()?;
+                let executable = detect::cargo_package_name(&current_dir).unwrap_or_else(|| {
+                    console.warn("No Cargo.toml [package] found - using placeholder executable name");
+                    "my-app".to_string()
+                });
+                RUST_XTASK_TEMPLATE.replace("{executable}", &executable)
+            }
             other => {
                 anyhow::bail!(
                     "Unknown preset: '{}'\n\
-                     Available presets: tauri, python, electron",
+                     Available presets: tauri, python, electron, flutter, go, dotnet, rust-xtask",
                     other
                 );
             }
@@ -457,18 +2333,56 @@ fn cmd_init(accept_defaults: bool, preset: Option<&str>, console: &Console) -> R
         
         fs::write(&config_path, &template)?;
         console.success(format!("Created lxe.toml (preset: {})", preset));
+        if let Some(target) = ci {
+            cmd_init_ci(target, console)?;
+        }
+        
+        if preset == "rust-xtask" {
+            init_xtask(console)?;
+            console.log("\nNext steps:");
+            console.log("  1. Update [package] section with your app details");
+            console.log("  2. Add \"xtask\" to [workspace] members in Cargo.toml");
+            console.log("  3. Run: cargo xtask dist");
+            return Ok(());
+        }
+        
         console.log("\nNext steps:");
         console.log("  1. Update [package] section with your app details");
         console.log("  2. Run: lxe build");
         return Ok(());
     }
     
+    // `--detect` replaces the progress output with a one-line summary
+    let summary_console = console;
+    let quiet = Console::new(true, false);
+    let (accept_defaults, console) = if detect { (true, &quiet) } else { (accept_defaults, console) };
+    if console.json && !accept_defaults {
+        anyhow::bail!("Interactive init doesn't work with --output-format json; pass --yes");
+    }
+    
     // Interactive mode with auto-detection
     console.log("🔧 LXE Package Initializer\n");
     
     // Detect project info
     let current_dir = std::env::current_dir()?;
-    let detected = detect::DetectedProject::detect(&current_dir);
+    let mut detected = detect::DetectedProject::detect(&current_dir);
+    
+    // A package built from a lost lxe.toml knows better than detection
+    let existing = init_source_package(from_lxe, &current_dir, console)?;
+    if let Some(ref metadata) = existing {
+        console.log(format!(
+            "📦 Found existing package: {} v{} — using its metadata as defaults\n",
+            metadata.name, metadata.version
+        ));
+        detected.apply_package(metadata);
+    }
+    let (kind, confidence) = (detected.kind, detected.confidence());
+    
+    if current_dir.join("pubspec.yaml").exists() {
+        console.log("💡 Flutter project detected. Consider: lxe init --preset flutter\n");
+    } else if current_dir.join("go.mod").exists() {
+        console.log("💡 Go project detected. Consider: lxe init --preset go\n");
+    }
     
     // Prompt for each field with detected defaults
     let name: String = if accept_defaults {
@@ -480,7 +2394,10 @@ fn cmd_init(accept_defaults: bool, preset: Option<&str>, console: &Console) -> R
             .interact_text()?
     };
     
-    let default_id = detect::generate_app_id(&name);
+    let default_id = match existing {
+        Some(ref metadata) => metadata.app_id.clone(),
+        None => detect::generate_app_id(&name),
+    };
     let id: String = if accept_defaults {
         default_id
     } else {
@@ -561,6 +2478,14 @@ fn cmd_init(accept_defaults: bool, preset: Option<&str>, console: &Console) -> R
         "# script = \"your build command here\"".to_string()
     };
 
+    let (categories, terminal) = match existing {
+        Some(ref metadata) if !metadata.categories.is_empty() => (
+            metadata.categories.iter().map(|c| format!("{:?}", c)).collect::<Vec<_>>().join(", "),
+            metadata.terminal,
+        ),
+        _ => ("\"Utility\"".to_string(), false),
+    };
+    
     // Generate config content
     let config_content = format!(
         r#"# LXE Package Configuration
@@ -572,8 +2497,8 @@ version = "{version}"
 executable = "{executable}"
 icon = "{icon}"
 description = "{description}"
-categories = ["Utility"]
-terminal = false
+categories = [{categories}]
+terminal = {terminal}
 
 [build]
 input = "{input}"
@@ -586,21 +2511,18 @@ compression = 19
         executable = executable,
         icon = icon,
         description = description,
+        categories = categories,
+        terminal = terminal,
         input = build_input,
         build_script = build_script_line,
     );
     
     // Show preview and confirm (unless -y flag)
     if !accept_defaults {
-        println!("\n📄 About to create lxe.toml:\n");
-        println!("{}", config_content);
+        console.log("\n📄 About to create lxe.toml:\n");
+        console.log(&config_content);
         
-        let confirm = Confirm::new()
-            .with_prompt("Create this file?")
-            .default(true)
-            .interact()?;
-        
-        if !confirm {
+        if !console.confirm("Create this file?")? {
             console.log("Cancelled.");
             // Clean up created script if cancelled
             if fs::exists("lxe-build.sh")? {
@@ -613,6 +2535,21 @@ compression = 19
     fs::write(&config_path, &config_content)?;
     
     console.success("Created lxe.toml");
+    if let Some(target) = ci {
+        cmd_init_ci(target, console)?;
+    }
+    
+    if detect {
+        let kind = kind.map(|k| format!("{} ", k)).unwrap_or_default();
+        summary_console.log(format!("Created lxe.toml for {}app '{}' v{}", kind, name, version));
+        if confidence == detect::DetectionConfidence::Low {
+            summary_console.warn("No project files found - values were guessed from the folder name");
+            summary_console.log("💡 Review lxe.toml, or delete it and run the interactive `lxe init`");
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+    
     console.log("\nNext steps:");
     console.log("  1. Review lxe-build.sh (if applicable)");
     console.log("  2. Run: lxe build");
@@ -620,8 +2557,85 @@ compression = 19
     Ok(())
 }
 
+/// Metadata of the package `lxe init` takes its defaults from
+/// 
+/// An explicit `--from-lxe` must be readable; `.lxe` files found in the
+/// directory are skipped with a warning when they aren't.
+fn init_source_package(
+    from_lxe: Option<&std::path::Path>,
+    dir: &std::path::Path,
+    console: &Console,
+) -> Result<Option<lxe_common::metadata::LxeMetadata>> {
+    if let Some(path) = from_lxe {
+        let info = lxe_common::payload::read_payload_info(path)
+            .with_context(|| format!("Failed to read package {}", path.display()))?;
+        return Ok(Some(info.metadata));
+    }
+    
+    for path in detect::existing_packages(dir) {
+        match lxe_common::payload::read_payload_info(&path) {
+            Ok(info) => return Ok(Some(info.metadata)),
+            Err(e) => console.warn(format!(
+                "Ignoring {}: {:#}",
+                path.file_name().unwrap_or_default().to_string_lossy(),
+                e
+            )),
+        }
+    }
+    Ok(None)
+}
+
+/// Scaffold an `xtask` crate whose `dist` task packages the app via lxe-build-rs
+fn init_xtask(console: &Console) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+    let files = [
+        ("xtask/Cargo.toml", XTASK_CARGO_TEMPLATE),
+        ("xtask/src/main.rs", XTASK_MAIN_TEMPLATE),
+        (".cargo/config.toml", XTASK_ALIAS_TEMPLATE),
+    ];
+    
+    for (file_name, contents) in files {
+        let path = current_dir.join(file_name);
+        if path.exists() {
+            console.warn(format!("{} already exists - not overwriting", file_name));
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, contents)?;
+        console.success(format!("Created {}", file_name));
+    }
+    
+    Ok(())
+}
+
+/// Write the CI pipeline for `target` next to lxe.toml
+fn cmd_init_ci(target: CiTarget, console: &Console) -> Result<()> {
+    let (file_name, contents) = target.pipeline();
+    let path = std::env::current_dir()?.join(file_name);
+    
+    if path.exists() {
+        console.warn(format!("{} already exists - not overwriting", file_name));
+        return Ok(());
+    }
+    
+    fs::write(&path, contents)?;
+    console.success(format!("Created {}", file_name));
+    console.log("   Set LXE_SIGNING_KEY as a protected CI/CD variable to sign on protected branches.");
+    
+    Ok(())
+}
+
 /// Download the LXE runtime from GitHub
-fn cmd_runtime_download(force: bool, console: &Console) -> Result<()> {
+fn cmd_runtime_download(
+    force: bool,
+    skip_verify: bool,
+    no_resume: bool,
+    rate_limit: Option<u64>,
+    proxy: Option<&str>,
+    console: &Console,
+) -> Result<()> {
     let runtime_dir = get_runtime_dir()?;
     let runtime_path = runtime_dir.join("lxe-runtime");
     
@@ -633,7 +2647,19 @@ fn cmd_runtime_download(force: bool, console: &Console) -> Result<()> {
     
     console.log("📦 Downloading LXE runtime...\n");
     
-    // Detect architecture
+    let release_url = runtime_release_url(None)?;
+    install_runtime_from(&release_url, skip_verify, !no_resume, rate_limit, proxy, console)?;
+    
+    console.success(format!("Runtime installed to: {}", runtime_path.display()));
+    console.log("\n🚀 You're ready to build packages with 'lxe build'!");
+    
+    Ok(())
+}
+
+/// Release URL of the runtime tarball for this architecture
+/// 
+/// `tag` selects a specific release (e.g., "v1.2.0"); `None` means latest.
+fn runtime_release_url(tag: Option<&str>) -> Result<String> {
     let arch = std::env::consts::ARCH;
     let arch_name = match arch {
         "x86_64" => "x86_64",
@@ -641,39 +2667,109 @@ fn cmd_runtime_download(force: bool, console: &Console) -> Result<()> {
         _ => anyhow::bail!("Unsupported architecture: {}", arch),
     };
     
-    console.log(format!("   Architecture: {}", arch_name));
+    let asset = releases::runtime_asset_name(arch_name);
+    Ok(match tag {
+        Some(tag) => format!("https://github.com/lxe-core/lxe/releases/download/{}/{}", tag, asset),
+        None => format!("https://github.com/lxe-core/lxe/releases/latest/download/{}", asset),
+    })
+}
+
+/// Download, verify and install the runtime tarball at `release_url`
+/// 
+/// With `resume`, an interrupted earlier download of the same URL is continued.
+fn install_runtime_from(
+    release_url: &str,
+    skip_verify: bool,
+    resume: bool,
+    rate_limit: Option<u64>,
+    proxy: Option<&str>,
+    console: &Console,
+) -> Result<()> {
+    let runtime_dir = get_runtime_dir()?;
+    let runtime_path = runtime_dir.join("lxe-runtime");
     
-    // Get latest release info
-    let release_url = format!(
-        "https://github.com/lxe-core/lxe/releases/latest/download/lxe-runtime-{}-linux.tar.gz",
-        arch_name
-    );
+    let removed = network::clean_stale_parts(&runtime_dir, network::STALE_PART_AGE);
+    if removed > 0 {
+        console.verbose(format!("Removed {} stale partial download(s)", removed));
+    }
     
+    console.log(format!("   Architecture: {}", std::env::consts::ARCH));
     console.log(format!("   Downloading from: {}", release_url));
     
     // Create runtime directory
     fs::create_dir_all(&runtime_dir)?;
     
-    // Download using reqwest (already a dependency via self_update)
-    let response = reqwest::blocking::get(&release_url)
+    let client = network::client_builder(proxy)?.build()?;
+    
+    // Fetch the expected checksum first so a bad download is caught immediately
+    let expected_sha256 = if skip_verify {
+        console.warn("Skipping checksum verification (--skip-verify)");
+        None
+    } else {
+        Some(fetch_sha256_sidecar(&client, &format!("{}.sha256", release_url))?)
+    };
+    
+    // Partial downloads are keyed by URL so a different release never resumes them
+    let url_hash = &calculate_sha256(release_url.as_bytes())[..12];
+    let part_path = runtime_dir.join(format!("download-{}.tar.gz.part", url_hash));
+    if !resume {
+        network::remove_part(&part_path)?;
+    }
+    
+    let progress = console.download_bar(rate_limit);
+    let options = network::DownloadOptions {
+        resume,
+        limit_kbps: rate_limit,
+        progress: progress.as_ref(),
+    };
+    let resumed = network::download_resumable(&client, release_url, &part_path, &options)
         .context("Failed to download runtime")?;
+    if resumed > 0 {
+        console.log(format!("   Resumed at {} bytes", resumed));
+    }
     
-    if !response.status().is_success() {
-        anyhow::bail!("Download failed: HTTP {}", response.status());
+    let actual_sha256 = {
+        let mut reader = HashingReader::new(File::open(&part_path)?);
+        std::io::copy(&mut reader, &mut std::io::sink())?;
+        reader.finalize()
+    };
+    
+    if let Some(expected) = expected_sha256 {
+        if !actual_sha256.eq_ignore_ascii_case(&expected) {
+            network::remove_part(&part_path).ok();
+            anyhow::bail!(
+                "Checksum mismatch for downloaded runtime!\n\
+                 Expected: {}\n\
+                 Actual:   {}\n\n\
+                 The download may be corrupted or tampered with. Please try again.",
+                expected,
+                actual_sha256
+            );
+        }
+        console.log("   ✓ Checksum verified");
     }
     
-    let bytes = response.bytes()?;
+    let tarball_path = runtime_dir.join("lxe-runtime-download.tar.gz");
+    fs::rename(&part_path, &tarball_path)?;
+    network::remove_part(&part_path)?;
+    console.verbose(format!("SHA256: {}", actual_sha256));
     
     // Extract tarball
-    let decoder = flate2::read::GzDecoder::new(&bytes[..]);
-    let mut archive = tar::Archive::new(decoder);
-    archive.unpack(&runtime_dir)?;
+    let unpack_result = File::open(&tarball_path)
+        .map(|file| tar::Archive::new(flate2::read::GzDecoder::new(file)))
+        .and_then(|mut archive| archive.unpack(&runtime_dir));
+    let _ = fs::remove_file(&tarball_path);
+    unpack_result.context("Failed to extract runtime archive")?;
     
     // Find and rename the extracted binary
     for entry in fs::read_dir(&runtime_dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.is_file() && path.file_name().and_then(|s| s.to_str()).map(|s| s.starts_with("lxe-runtime")).unwrap_or(false) {
+        let is_extracted = path.file_name()
+            .and_then(|s| s.to_str())
+            .map(|s| s.starts_with("lxe-runtime") && !s.ends_with(".bak"))
+            .unwrap_or(false);
+        if path.is_file() && is_extracted {
             if path != runtime_path {
                 fs::rename(&path, &runtime_path)?;
             }
@@ -690,14 +2786,13 @@ fn cmd_runtime_download(force: bool, console: &Console) -> Result<()> {
         fs::set_permissions(&runtime_path, perms)?;
     }
     
-    console.success(format!("Runtime installed to: {}", runtime_path.display()));
-    console.log("\n🚀 You're ready to build packages with 'lxe build'!");
-    
+    console.report("installed_path", &runtime_path);
+    console.report("sha256", &actual_sha256);
     Ok(())
 }
 
 /// Show runtime status
-fn cmd_runtime_status(console: &Console) -> Result<()> {
+fn cmd_runtime_status(rate_limit: Option<u64>, console: &Console) -> Result<()> {
     console.log("🔍 LXE Runtime Status\n");
     
     let runtime_dir = get_runtime_dir()?;
@@ -713,23 +2808,208 @@ fn cmd_runtime_status(console: &Console) -> Result<()> {
         console.log("\n   Run 'lxe runtime download' to install.");
     }
     
+    match rate_limit {
+        Some(kbps) => console.log(format!("   Download limit: {} KiB/s", kbps)),
+        None => console.verbose("Download limit: none"),
+    }
+    
     Ok(())
 }
 
-/// Get the runtime installation directory
-fn get_runtime_dir() -> Result<PathBuf> {
-    let dir = dirs::data_local_dir()
-        .ok_or_else(|| anyhow::anyhow!("Cannot find local data directory"))?
-        .join("lxe");
-    Ok(dir)
+/// List runtime versions published on GitHub
+fn cmd_runtime_list_versions(limit: usize, include_prerelease: bool, proxy: Option<&str>, console: &Console) -> Result<()> {
+    console.log("🔍 Fetching runtime versions...\n");
+    
+    let (versions, remaining) = releases::fetch_runtime_versions(limit, include_prerelease, proxy)?;
+    let installed = installed_runtime_version();
+    
+    if versions.is_empty() {
+        console.log("   No runtime releases found.");
+    } else {
+        console.log(format!("   {:<14} {:<12} {:<12} {}", "VERSION", "DATE", "CHANNEL", "ARCH"));
+        for version in &versions {
+            let marker = if installed.as_deref() == Some(version.version()) { " (installed)" } else { "" };
+            let arches = version.architectures();
+            console.log(format!(
+                "   {:<14} {:<12} {:<12} {}{}",
+                version.tag,
+                version.date(),
+                if version.prerelease { "prerelease" } else { "stable" },
+                if arches.is_empty() { "-".to_string() } else { arches.join(", ") },
+                marker,
+            ));
+        }
+    }
+    
+    if releases::rate_limit_low(remaining) {
+        console.warn(format!(
+            "GitHub API rate limit is low ({} requests left this hour)",
+            remaining.unwrap_or(0)
+        ));
+    }
+    
+    Ok(())
 }
 
-// Embedded templates for presets
-const TAURI_TEMPLATE: &str = r#"# LXE Configuration for Tauri 2.x Applications
-#
-# IMPORTANT: Tauri sidecars have specific requirements!
-# This template handles them correctly.
-
+/// Upgrade (or roll back) the installed runtime
+fn cmd_runtime_upgrade(
+    version: Option<&str>,
+    rollback: bool,
+    skip_verify: bool,
+    rate_limit: Option<u64>,
+    proxy: Option<&str>,
+    console: &Console,
+) -> Result<()> {
+    let runtime_dir = get_runtime_dir()?;
+    let runtime_path = runtime_dir.join("lxe-runtime");
+    let backup_path = runtime_dir.join("lxe-runtime.bak");
+    
+    if rollback {
+        if !backup_path.exists() {
+            anyhow::bail!(
+                "No runtime backup found at {}\n\
+                 A backup is created by 'lxe runtime upgrade'.",
+                backup_path.display()
+            );
+        }
+        fs::rename(&backup_path, &runtime_path)
+            .context("Failed to restore runtime backup")?;
+        console.success("Restored previous runtime from backup");
+        refresh_uninstall_actions(&runtime_path, console);
+        console.log("");
+        return cmd_runtime_status(rate_limit, console);
+    }
+    
+    console.log("🔄 Upgrading LXE runtime...\n");
+    
+    let current = installed_runtime_version();
+    console.log(format!("   Current version: {}", current.as_deref().unwrap_or("unknown")));
+    
+    // Resolve the target release
+    let target_tag = match version {
+        Some(v) => format!("v{}", v.trim_start_matches('v')),
+        None => {
+            let (versions, _) = releases::fetch_runtime_versions(1, false, proxy)?;
+            versions.into_iter()
+                .next()
+                .map(|v| v.tag)
+                .ok_or_else(|| anyhow::anyhow!("No stable runtime release found on GitHub"))?
+        }
+    };
+    let target_version = target_tag.trim_start_matches('v');
+    console.log(format!("   Target version:  {}", target_version));
+    
+    if current.as_deref() == Some(target_version) {
+        console.success("Runtime is already up to date.");
+        return Ok(());
+    }
+    
+    // Keep the current runtime so a bad upgrade can be rolled back
+    if runtime_path.exists() {
+        fs::copy(&runtime_path, &backup_path)
+            .context("Failed to back up current runtime")?;
+        console.verbose(format!("Backup: {}", backup_path.display()));
+    }
+    
+    console.log("");
+    let release_url = runtime_release_url(Some(&target_tag))?;
+    if let Err(e) = install_runtime_from(&release_url, skip_verify, true, rate_limit, proxy, console) {
+        if backup_path.exists() {
+            let _ = fs::copy(&backup_path, &runtime_path);
+        }
+        return Err(e.context("Runtime upgrade failed (previous runtime kept)"));
+    }
+    
+    console.success(format!("Runtime upgraded to {}", target_version));
+    console.log("   Run 'lxe runtime upgrade --rollback' to restore the previous version.");
+    
+    refresh_uninstall_actions(&runtime_path, console);
+    
+    console.log("");
+    cmd_runtime_status(rate_limit, console)
+}
+
+/// Point dangling uninstall actions of installed apps at `runtime_path`
+/// 
+/// Installed apps normally use their own copy in ~/.local/bin, so this only
+/// touches entries whose runtime no longer exists.
+fn refresh_uninstall_actions(runtime_path: &std::path::Path, console: &Console) {
+    let Some(applications_dir) = lxe_common::paths::user::applications_dir() else {
+        return;
+    };
+    let Ok(entries) = fs::read_dir(&applications_dir) else {
+        return;
+    };
+    
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(&path) else {
+            continue;
+        };
+        if !content.contains("X-LXE-AppId=") {
+            continue;
+        }
+        
+        let mut changed = false;
+        let updated: Vec<String> = content.lines().map(|line| {
+            let Some(rest) = line.strip_prefix("Exec=") else {
+                return line.to_string();
+            };
+            let Some((binary, args)) = rest.split_once(" --uninstall-gui ") else {
+                return line.to_string();
+            };
+            if std::path::Path::new(binary).exists() {
+                return line.to_string();
+            }
+            changed = true;
+            format!("Exec={} --uninstall-gui {}", runtime_path.display(), args)
+        }).collect();
+        
+        if changed {
+            let mut output = updated.join("\n");
+            output.push('\n');
+            if fs::write(&path, output).is_ok() {
+                console.verbose(format!("Updated uninstall action: {}", path.display()));
+            }
+        }
+    }
+}
+
+/// Version of the installed runtime, if it can be determined
+fn installed_runtime_version() -> Option<String> {
+    let runtime_path = get_runtime_dir().ok()?.join("lxe-runtime");
+    if !runtime_path.exists() {
+        return None;
+    }
+    
+    // `lxe-runtime --version` prints "lxe-runtime X.Y.Z"
+    let output = Command::new(&runtime_path).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .split_whitespace()
+        .nth(1)
+        .map(|v| v.trim_start_matches('v').to_string())
+}
+
+/// Get the runtime installation directory
+fn get_runtime_dir() -> Result<PathBuf> {
+    let dir = dirs::data_local_dir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot find local data directory"))?
+        .join("lxe");
+    Ok(dir)
+}
+
+// Embedded templates for presets
+const TAURI_TEMPLATE: &str = r#"# LXE Configuration for Tauri 2.x Applications
+#
+# IMPORTANT: Tauri sidecars have specific requirements!
+# This template handles them correctly.
+
 [package]
 name = "My Tauri App"
 id = "com.example.myapp"
@@ -796,6 +3076,90 @@ cp icon.png dist/ 2>/dev/null || echo "No icon"
 compression = 19
 "#;
 
+const RUST_XTASK_TEMPLATE: &str = r#"# LXE Configuration for Rust Applications (cargo xtask dist)
+
+[package]
+name = "My Rust App"
+id = "com.example.myapp"
+version = "1.0.0"
+executable = "{executable}"
+icon = "icon.png"
+description = "A Rust application"
+categories = ["Utility"]
+terminal = false
+
+[build]
+input = "./target/lxe-dist"
+
+# `cargo xtask dist` builds the release binary first; this only stages it
+script = """
+rm -rf target/lxe-dist && mkdir -p target/lxe-dist && \
+cp target/release/{executable} target/lxe-dist/ && \
+{ cp icon.png target/lxe-dist/ 2>/dev/null || echo "No icon"; }
+"""
+
+compression = 19
+"#;
+
+const XTASK_CARGO_TEMPLATE: &str = r#"[package]
+name = "xtask"
+version = "0.1.0"
+edition = "2021"
+publish = false
+
+[dependencies]
+anyhow = "1"
+lxe-build-rs = { git = "https://github.com/lxe-core/lxe" }
+"#;
+
+const XTASK_MAIN_TEMPLATE: &str = r#"//! Project tasks: `cargo xtask <task>`
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() -> anyhow::Result<()> {
+    match std::env::args().nth(1).as_deref() {
+        Some("dist") => dist(),
+        _ => {
+            eprintln!("Tasks:\n  dist    Build a release binary and package it as .lxe");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Release build + LXE package
+fn dist() -> anyhow::Result<()> {
+    let root = project_root();
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    
+    let status = Command::new(cargo)
+        .args(["build", "--release"])
+        .current_dir(&root)
+        .status()?;
+    anyhow::ensure!(status.success(), "cargo build --release failed");
+    
+    let result = lxe_build_rs::build_lxe_package(root.join("lxe.toml"))?;
+    println!(
+        "Packaged {} ({} bytes) in {:.1}s",
+        result.output_path.display(),
+        result.size_bytes,
+        result.duration.as_secs_f64(),
+    );
+    Ok(())
+}
+
+fn project_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .expect("xtask lives in a subdirectory of the project")
+        .to_path_buf()
+}
+"#;
+
+const XTASK_ALIAS_TEMPLATE: &str = r#"[alias]
+xtask = "run --package xtask --"
+"#;
+
 const ELECTRON_TEMPLATE: &str = r#"# LXE Configuration for Electron Applications
 
 [package]
@@ -824,206 +3188,2873 @@ cp build/icon.png dist/ 2>/dev/null || echo "No icon"
 compression = 10
 "#;
 
-/// Generate signing keypair
-fn cmd_key_generate(output: &PathBuf, console: &Console) -> Result<()> {
+const FLUTTER_TEMPLATE: &str = r#"# LXE Configuration for Flutter Desktop Applications
+
+[package]
+name = "My Flutter App"
+id = "com.example.myapp"
+version = "1.0.0"
+executable = "{executable}"
+icon = "icon.png"
+description = "A Flutter desktop application"
+categories = ["Utility"]
+terminal = false
+
+# Flutter apps bundle their own rendering engine (libflutter_linux_gtk.so
+# ships inside the bundle), so no extra GTK dependencies are required.
+[installer]
+# Optional: License agreement
+# license = "LICENSE"
+# welcome_title = "Welcome to My Flutter App"
+# theme = "auto"
+# show_launch = true
+
+[build]
+input = "./dist"
+
+# Flutter emits a self-contained bundle (binary + lib/ + data/)
+script = """
+if [ ! -f assets/icon.png ]; then echo "assets/icon.png not found" >&2; exit 1; fi && \
+flutter build linux --release && \
+rm -rf dist && \
+cp -r build/linux/x64/release/bundle dist && \
+cp assets/icon.png dist/icon.png
+"""
+
+compression = 19
+"#;
+
+const GO_TEMPLATE: &str = r#"# LXE Configuration for Go Applications
+
+[package]
+name = "My Go App"
+id = "com.example.myapp"
+version = "1.0.0"
+executable = "{executable}"
+icon = "icon.png"
+description = "A Go desktop application"
+categories = ["Utility"]
+terminal = false
+
+[installer]
+# Optional: License agreement
+# license = "LICENSE"
+
+[build]
+input = "./dist"
+
+# A static binary runs on any distribution; drop CGO_ENABLED=0 if the app
+# links C libraries (GTK, Fyne, ...). If the Makefile has a build target,
+# `make build` can replace the go build command; set input to the directory
+# it writes to and copy the icon there.
+script = """
+if [ ! -f assets/icon.png ]; then echo "assets/icon.png not found" >&2; exit 1; fi && \
+rm -rf dist && \
+CGO_ENABLED=0 go build -ldflags="-s -w" -o dist/{executable} {package} && \
+cp assets/icon.png dist/icon.png
+"""
+
+compression = 19
+"#;
+
+const DOTNET_TEMPLATE: &str = r#"# LXE Configuration for .NET Applications
+
+[package]
+name = "My .NET App"
+id = "com.example.myapp"
+version = "1.0.0"
+executable = "{executable}"
+icon = "icon.png"
+description = "A .NET desktop application"
+categories = ["Utility"]
+terminal = false
+
+[installer]
+# Optional: License agreement
+# license = "LICENSE"
+
+[build]
+input = "./dist"
+
+# Self-contained: the .NET runtime ships in dist/, so users don't need it
+# installed. With several projects, name the app's .csproj after publish.
+script = """
+if [ ! -f Assets/icon.png ]; then echo "Assets/icon.png not found" >&2; exit 1; fi && \
+rm -rf dist && \
+dotnet publish -r linux-x64 --self-contained -c Release -o dist && \
+cp Assets/icon.png dist/icon.png
+"""
+
+compression = 19
+"#;
+
+/// Generate a signing key; on a token (`backend` is PKCS#11) `output` only
+/// receives a reference to it
+fn cmd_key_generate(
+    output: &std::path::Path,
+    algorithm: KeyAlgorithm,
+    backend: &lxe_common::signing::KeyBackend,
+    console: &Console,
+) -> Result<()> {
+    use lxe_common::signing::{KeyBackend, Pkcs11KeyReference};
+    
     if output.exists() {
         anyhow::bail!("Key file already exists: {}", output.display());
     }
     
-    console.log("🔑 Generating Ed25519 keypair...");
+    if let KeyBackend::Pkcs11 { module, slot, .. } = backend {
+        if !matches!(algorithm, KeyAlgorithm::Ed25519) {
+            anyhow::bail!("Token keys are always Ed25519 (CKM_EDDSA)");
+        }
+        console.log(format!("🔑 Generating Ed25519 keypair on the token in slot {}...", slot));
+        let public_key = backend.generate_token_key()?;
+        Pkcs11KeyReference { module: module.clone(), slot: *slot, public_key: public_key.clone() }.save(output)?;
+        
+        console.success("Keypair generated!");
+        console.log(format!("   🔐 Private key: on the token (label '{}')", lxe_common::signing::PKCS11_KEY_LABEL));
+        console.log(format!("   📎 Key reference: {}", output.display()));
+        console.log(format!("   🔓 Public key: {}", public_key));
+        console.log("   Use the reference like a key file; set LXE_PKCS11_PIN when signing");
+        return Ok(());
+    }
+    
+    let key = generate_private_key(algorithm, console)?;
+    
+    // Saved with owner-only permissions
+    key.save(output)?;
+    
+    console.success("Keypair generated!");
+    console.log(format!("   🔒 Private key: {}", output.display()));
+    console.log(format!("   🔓 Public key: {}", key.public_key_base64()?));
+    console.warn("Keep your private key secure and never commit it to git!");
+    
+    Ok(())
+}
+
+/// Generate a key of the given algorithm (RSA generation shows a spinner)
+fn generate_private_key(algorithm: KeyAlgorithm, console: &Console) -> Result<lxe_common::signing::PrivateKey> {
+    use lxe_common::signing::{LxeKeyPair, PrivateKey};
+    
+    match algorithm {
+        KeyAlgorithm::Ed25519 => {
+            console.log("🔑 Generating Ed25519 keypair...");
+            Ok(PrivateKey::Ed25519(Box::new(LxeKeyPair::generate())))
+        }
+        KeyAlgorithm::Rsa2048 | KeyAlgorithm::Rsa4096 => {
+            let bits = if matches!(algorithm, KeyAlgorithm::Rsa2048) { 2048 } else { 4096 };
+            let spinner = console.spinner(&format!("Generating RSA-{} keypair...", bits));
+            let key = PrivateKey::generate_rsa(bits);
+            if let Some(pb) = spinner {
+                pb.finish_and_clear();
+            }
+            key
+        }
+    }
+}
+
+/// File name of key `index` in a batch: `team.key` → `team-001.key`
+fn batch_key_path(output: &std::path::Path, index: u32) -> PathBuf {
+    let base = match output.extension() {
+        Some(ext) if ext == "key" => output.with_extension(""),
+        _ => output.to_path_buf(),
+    };
+    let mut name = base.into_os_string();
+    name.push(format!("-{:03}.key", index));
+    PathBuf::from(name)
+}
+
+/// Generate `count` keypairs, one per build agent
+fn cmd_key_generate_batch(
+    output: &std::path::Path,
+    algorithm: KeyAlgorithm,
+    count: u32,
+    format: KeyTableFormat,
+    console: &Console,
+) -> Result<()> {
+    use lxe_common::signing::public_key_fingerprint;
+    
+    let paths: Vec<PathBuf> = (1..=count).map(|i| batch_key_path(output, i)).collect();
+    // Refuse up front rather than leave a half-generated batch behind
+    if let Some(existing) = paths.iter().find(|p| p.exists()) {
+        anyhow::bail!("Key file already exists: {}", existing.display());
+    }
+    
+    // Per-key progress would interleave with CSV output
+    if format == KeyTableFormat::Table {
+        console.log(format!("🔑 Generating {} keypairs...", count));
+    }
+    let quiet = Console::new(true, false);
+    let mut rows = Vec::with_capacity(paths.len());
+    for path in paths {
+        let key = generate_private_key(algorithm, &quiet)?;
+        key.save(&path)?;
+        let public_key = key.public_key_base64()?;
+        let fingerprint = public_key_fingerprint(&public_key)?;
+        rows.push((path, public_key, fingerprint));
+    }
+    
+    if format == KeyTableFormat::Csv {
+        let mut csv = String::from("filename,public_key_b64,fingerprint\n");
+        for (path, public_key, fingerprint) in &rows {
+            csv.push_str(&format!("{},{},{}\n", path.display(), public_key, fingerprint));
+        }
+        console.print_raw("csv", &csv);
+        return Ok(());
+    }
+    console.report("keys", rows.iter()
+        .map(|(path, public_key, fingerprint)| serde_json::json!({
+            "file": path,
+            "public_key": public_key,
+            "fingerprint": fingerprint,
+        }))
+        .collect::<Vec<_>>());
+    
+    console.success(format!("Generated {} keypairs\n", rows.len()));
+    let width = rows.iter().map(|(p, _, _)| p.display().to_string().len()).max().unwrap_or(0).max(4);
+    console.log(format!("   {:<width$}  PUBLIC KEY", "FILE", width = width));
+    for (path, public_key, _) in &rows {
+        console.log(format!("   {:<width$}  {}", path.display(), public_key, width = width));
+    }
+    console.warn("Keep the private keys secure and never commit them to git!");
+    
+    Ok(())
+}
+
+/// Describe a key file (`lxe key inspect`)
+fn cmd_key_inspect(path: &std::path::Path, console: &Console) -> Result<()> {
+    use lxe_common::signing::{inspect_key_file, public_key_fingerprint};
+    
+    let info = inspect_key_file(path)?;
+    // Key files carry no creation date; the mtime is the best approximation
+    let created = fs::metadata(path)?.modified().ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| stats::format_install_date(&format!("unix:{}", d.as_secs())))
+        .unwrap_or_else(|| "unknown".to_string());
+    
+    console.log(format!("🔑 {}\n", path.display()));
+    console.log(format!("   Algorithm:   {}", info.algorithm));
+    console.log(format!("   Created:     {}", created));
+    console.log(format!("   Public key:  {}", info.public_key));
+    console.log(format!("   Fingerprint: {}", public_key_fingerprint(&info.public_key)?));
+    if info.has_private_key {
+        console.log("   Contains:    private key");
+        console.warn("This file is a private key: keep it in a secrets manager, never share it");
+    } else {
+        console.log("   Contains:    public key only (safe to share)");
+    }
+    
+    Ok(())
+}
+
+/// Import a public key into the trust store
+fn cmd_key_import(source: &KeySource, name: &str, issuer: Option<&str>, expected_fingerprint: Option<&str>, proxy: Option<&str>, console: &Console) -> Result<()> {
+    use lxe_common::keystore::TrustStore;
+    use lxe_common::signing::{parse_public_key, public_key_fingerprint};
+    
+    let content = match source {
+        KeySource::File(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read key file: {}", path.display()))?,
+        KeySource::Url(url) => {
+            console.log(format!("🌐 Fetching key from {}", url));
+            let client = network::client_builder(proxy)?
+                .timeout(std::time::Duration::from_secs(30))
+                .build()?;
+            let response = client.get(url).send()
+                .with_context(|| format!("Failed to fetch key: {}", url))?;
+            if !response.status().is_success() {
+                anyhow::bail!("Download failed: HTTP {}", response.status());
+            }
+            response.text()?
+        }
+        KeySource::Stdin => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)
+                .context("Failed to read key from stdin")?;
+            buf
+        }
+    };
+    
+    let public_key = parse_public_key(&content)
+        .context("Failed to import public key")?;
+    let fingerprint = public_key_fingerprint(&public_key)?;
+    
+    // Keys fetched over the network must be confirmed out-of-band
+    if let Some(expected) = expected_fingerprint {
+        if expected.trim() != fingerprint {
+            anyhow::bail!("Fingerprint mismatch: expected {}, got {}", expected.trim(), fingerprint);
+        }
+    } else if let KeySource::Url(_) = source {
+        if console.json {
+            anyhow::bail!("Confirmation required; pass --fingerprint with --output-format json");
+        }
+        console.log(format!("\n   Fingerprint: {}", fingerprint));
+        if !console.confirm("Does this fingerprint match the one published by the key owner?")? {
+            anyhow::bail!("Import cancelled: fingerprint not confirmed");
+        }
+    }
+    
+    let mut store = TrustStore::load()?;
+    store.add(name, &public_key)?;
+    if let Some(issuer) = issuer {
+        store.set_issuer(name, issuer)?;
+    }
+    store.save()?;
+    
+    console.success(format!("Trusted key '{}' imported", name));
+    console.log(format!("   Fingerprint: {}", fingerprint));
+    
+    Ok(())
+}
+
+/// Trust a public key, optionally only for some app IDs
+fn cmd_key_trust(public_key: &str, app_id: Option<&str>, name: Option<&str>, console: &Console) -> Result<()> {
+    use lxe_common::keystore::TrustStore;
+    use lxe_common::signing::{parse_public_key, public_key_fingerprint};
+    
+    let public_key = parse_public_key(public_key)
+        .context("Invalid public key")?;
+    let fingerprint = public_key_fingerprint(&public_key)?;
+    
+    // Default name: "key-" and the start of the fingerprint's hash
+    let default_name = format!("key-{}", fingerprint.trim_start_matches("SHA256:").chars().take(8).collect::<String>());
+    let mut store = TrustStore::load()?;
+    let key = store.trust(name.unwrap_or(&default_name), &public_key, app_id)?;
+    let scope = if key.app_ids.is_empty() { "all apps".to_string() } else { key.app_ids.join(", ") };
+    console.success(format!("Trusted key '{}' for {}", key.name, scope));
+    console.log(format!("   Fingerprint: {}", fingerprint));
+    store.save()?;
+    
+    Ok(())
+}
+
+/// List trusted public keys
+fn cmd_key_list(console: &Console) -> Result<()> {
+    use lxe_common::keystore::TrustStore;
+    use lxe_common::signing::public_key_fingerprint;
+    
+    let store = TrustStore::load()?;
+    
+    if store.keys.is_empty() {
+        console.log("No trusted keys. Add one with 'lxe key import <source> --name <name>'.");
+        return Ok(());
+    }
+    
+    console.log("🔑 Trusted Keys\n");
+    for key in &store.keys {
+        let fingerprint = public_key_fingerprint(&key.public_key)
+            .unwrap_or_else(|_| "<invalid key>".to_string());
+        console.log(format!("   {}  {}", key.name, fingerprint));
+        console.verbose(format!("  Public key: {}", key.public_key));
+        console.verbose(format!("  Added: {}", key.added_at));
+        if let Some(ref issuer) = key.issuer_name {
+            console.verbose(format!("  Issued by: {}", issuer));
+        }
+        if !key.app_ids.is_empty() {
+            console.log(format!("      only for: {}", key.app_ids.join(", ")));
+        }
+    }
+    
+    Ok(())
+}
+
+/// Remove a trusted public key
+fn cmd_key_remove(name: &str, console: &Console) -> Result<()> {
+    use lxe_common::keystore::TrustStore;
+    
+    let mut store = TrustStore::load()?;
+    if store.remove(name).is_none() {
+        anyhow::bail!("No trusted key named '{}'", name);
+    }
+    store.save()?;
+    
+    console.success(format!("Removed trusted key '{}'", name));
+    Ok(())
+}
+
+/// Default detached signature path: `<file>.sig`
+fn default_sig_path(file: &std::path::Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// Sign an arbitrary file with a package signing key
+fn cmd_key_sign_file(key_path: &std::path::Path, file: &std::path::Path, output: Option<PathBuf>, console: &Console) -> Result<()> {
+    use lxe_common::signing::{FileSignature, PrivateKey};
+    
+    let key = PrivateKey::load(key_path)?;
+    let signature = FileSignature::sign(&key, file)?;
+    let output = output.unwrap_or_else(|| default_sig_path(file));
+    signature.save(&output)?;
+    
+    console.success(format!("Signed {}", file.display()));
+    console.log(format!("   ✍️  Signature: {}", output.display()));
+    console.verbose(format!("SHA256: {}", signature.file_sha256));
+    Ok(())
+}
+
+/// Verify a detached file signature
+fn cmd_key_verify_file(public_key: &str, file: &std::path::Path, sig: Option<PathBuf>, console: &Console) -> Result<()> {
+    use lxe_common::keystore::TrustStore;
+    use lxe_common::signing::{parse_public_key, public_key_fingerprint, FileSignature};
+    
+    // A trusted key's name, else the key itself
+    let (public_key, key_name) = match TrustStore::load()?.find(public_key) {
+        Some(trusted) => (trusted.public_key.clone(), Some(trusted.name.clone())),
+        None => (parse_public_key(public_key).context("Not a trusted key name or public key")?, None),
+    };
+    
+    let sig_path = sig.unwrap_or_else(|| default_sig_path(file));
+    let signature = FileSignature::load(&sig_path)?;
+    signature.verify(file, &public_key)
+        .with_context(|| format!("Verification of {} failed", file.display()))?;
+    
+    console.success(format!("Valid {} signature", signature.algorithm));
+    let signer = key_name.unwrap_or_else(|| public_key_fingerprint(&public_key).unwrap_or_default());
+    console.log(format!("   Signed by: {}", signer));
+    Ok(())
+}
+
+/// Verify package signature
+fn cmd_verify(file: &PathBuf, show_chain: bool, console: &Console) -> Result<()> {
+    console.log(format!("🔍 Verifying: {}\n", file.display()));
+    
+    // Step 1: Read the package metadata
+    let payload_info = lxe_common::payload::read_payload_info(file)
+        .context("Failed to read package")?;
+    
+    let metadata = &payload_info.metadata;
+    console.report("app_id", &metadata.app_id);
+    console.report("version", &metadata.version);
+    
+    // Display package info
+    console.log("📦 Package Information");
+    console.log(format!("   Name: {}", metadata.name));
+    console.log(format!("   Version: {}", metadata.version));
+    console.log(format!("   App ID: {}", metadata.app_id));
+    console.log("");
+    
+    // Step 2: Check if package is signed
+    let (has_signature, has_public_key) = (
+        metadata.signature.is_some(),
+        metadata.public_key.is_some(),
+    );
+    
+    if !has_signature && !has_public_key {
+        console.report("signature", "unsigned");
+        console.warn("Package is UNSIGNED");
+        console.log("   This package was not signed by the publisher.");
+        console.log("   Only install if you trust the source.");
+        return Ok(());
+    }
+    
+    if has_signature != has_public_key {
+        anyhow::bail!(
+            "Invalid package: has {} but not {}",
+            if has_signature { "signature" } else { "public_key" },
+            if has_public_key { "signature" } else { "public_key" }
+        );
+    }
+    
+    // Step 3: Display signature info
+    let public_key = metadata.public_key.as_ref().unwrap();
+    let signature = metadata.signature.as_ref().unwrap();
+    
+    console.log("🔑 Signature Information");
+    console.log(format!("   Algorithm: {}", metadata.algorithm));
+    console.log(format!("   Public Key: {}...", &public_key[..20.min(public_key.len())]));
+    console.log(format!("   Signature: {}...", &signature[..20.min(signature.len())]));
+    console.log("");
+    
+    // Step 4: Verify the signature
+    console.log("🔐 Verifying Signature...");
+    
+    // Get the signable data (metadata without sig fields + checksum)
+    let signable_json = metadata.to_signable_json()
+        .context("Failed to create signable metadata")?;
+    
+    let signable_data = lxe_common::signing::create_signable_data(&signable_json, &metadata.payload_checksum)
+        .context("Failed to create signable data")?;
+    
+    let is_valid = lxe_common::signing::verify_signature(metadata.algorithm, &signable_data, signature, public_key)
+        .context("Failed to verify signature")?;
+    
+    console.report("signature", if is_valid { "valid" } else { "invalid" });
+    if is_valid {
+        console.log("   ✅ Signature is VALID");
+        console.log("");
+        console.log("📊 Payload Integrity");
+        console.log(format!("   Checksum: {}... ({})", &metadata.payload_checksum[..16.min(metadata.payload_checksum.len())], metadata.checksum_algorithm));
+        console.log("   Status: Verified by signature");
+        console.log("");
+        console.success("Package is authentic and signed by the publisher.");
+        console.log(format!("   Public key: {}", public_key));
+        let store = lxe_common::keystore::TrustStore::load()?;
+        let trusted = store.check_signer(public_key, &metadata.app_id);
+        console.report("trusted_key", trusted.as_ref().ok().and_then(|key| key.map(|key| &key.name)));
+        match trusted {
+            Ok(Some(key)) => console.log(format!("   Trusted key: {}", key.name)),
+            Ok(None) => console.log(format!("   Not in your trust store for {} (lxe key trust <public-key> --app-id {})", metadata.app_id, metadata.app_id)),
+            Err(ref e) => console.error(format!("{:#}", e)),
+        }
+        
+        if show_chain {
+            print_trust_chain(&store, public_key, &metadata.app_id, console);
+        }
+        trusted?;
+    } else {
+        console.error("Signature is INVALID");
+        console.log("");
+        console.error("SECURITY WARNING");
+        console.log("   This package may have been tampered with!");
+        console.log("   The signature does not match the package contents.");
+        console.log("   DO NOT INSTALL this package.");
+        anyhow::bail!("Signature verification failed");
+    }
+    
+    Ok(())
+}
+
+/// Show who vouches for `public_key` signing `app_id`, from the signing
+/// key up to its root
+fn print_trust_chain(store: &lxe_common::keystore::TrustStore, public_key: &str, app_id: &str, console: &Console) {
+    use lxe_common::keystore::ChainLink;
+    use lxe_common::signing::public_key_fingerprint;
+    
+    let fingerprint = |key: &str| public_key_fingerprint(key).unwrap_or_else(|_| "<invalid key>".to_string());
+    
+    console.log("");
+    console.log("🔗 Trust Chain");
+    for (depth, link) in store.chain(public_key).iter().enumerate() {
+        let indent = if depth == 0 { "   ".to_string() } else { format!("   {}└─ ", "   ".repeat(depth - 1)) };
+        match link {
+            ChainLink::Trusted(key) => {
+                let added = stats::format_install_date(&key.added_at);
+                let scope = if key.covers(app_id) { String::new() } else { format!(", not for {}", app_id) };
+                console.log(format!("{}{} ({}, trusted since {}{})", indent, key.name, fingerprint(&key.public_key), added, scope));
+            }
+            ChainLink::Unknown { public_key: key, name } => {
+                let label = name.as_deref().unwrap_or("Unknown Publisher");
+                console.log(format!("{}{} ({}, not trusted)", indent, label, fingerprint(key)));
+            }
+        }
+    }
+    
+    if store.trusted_for(public_key, app_id).is_none() {
+        console.log("");
+        console.log("   To trust this publisher:");
+        console.log(format!("   lxe key trust {} --name \"Publisher\" --app-id {}", public_key, app_id));
+    }
+}
+
+/// Fields that `lxe patch-meta` refuses to touch
+const PROTECTED_META_FIELDS: &[&str] = &["payload_checksum", "checksum_algorithm", "signature", "public_key", "format_version"];
+
+/// Check a package against the LXE format specification
+fn cmd_conformance(file: &std::path::Path, level: conformance::ConformanceLevel, console: &Console) -> Result<()> {
+    console.log(format!("🧪 Conformance ({:?}): {}\n", level, file.display()));
+    
+    let checks = conformance::run(file, level);
+    for check in &checks {
+        let mark = if check.passed { "✓" } else { "✗" };
+        console.log(format!("   {} {:<38} {}", mark, check.name, check.detail));
+    }
+    
+    let failed = checks.iter().filter(|c| !c.passed).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, checks.len());
+    }
+    
+    console.success(format!("All {} checks passed", checks.len()));
+    Ok(())
+}
+
+fn cmd_validate(file: &std::path::Path, console: &Console) -> Result<()> {
+    use validate::Status;
+    
+    console.log(format!("🔎 Validating {}\n", file.display()));
+    
+    let checks = validate::run(file);
+    for check in &checks {
+        console.log(format!("   {} {:<32} {}", check.status.mark(), check.name, check.detail));
+    }
+    
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let (failed, warned) = (count(Status::Fail), count(Status::Warn));
+    if failed > 0 {
+        anyhow::bail!("{} of {} checks failed", failed, checks.len());
+    }
+    
+    if warned > 0 {
+        console.warn(format!("No failures, {} warning(s)", warned));
+    } else {
+        console.success(format!("All {} checks passed", checks.len()));
+    }
+    Ok(())
+}
+
+/// Print a package's metadata and payload listing (`tar -tvf` style)
+fn cmd_inspect(file: &std::path::Path, json: bool, console: &Console) -> Result<()> {
+    use inspect::SignatureStatus;
+    
+    let info = lxe_common::payload::read_payload_info_unchecked(file)
+        .with_context(|| format!("Failed to read package: {}", file.display()))?;
+    let signature = inspect::signature_status(&info.metadata);
+    let stub = inspect::stub_status(file, &info);
+    let entries = lxe_common::payload::list_entries(&info)?;
+    
+    if json || console.json {
+        return console.print_json(serde_json::json!({
+            "metadata": info.metadata,
+            "signature": signature.as_str(),
+            "metadata_stub": stub.as_str(),
+            "files": entries,
+        }));
+    }
+    
+    console.log(format!("📦 {}\n", file.display()));
+    let rows = inspect::metadata_rows(&info.metadata);
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    for (name, value) in &rows {
+        console.log(format!("   {:<width$}  {}", name, value, width = width));
+    }
+    
+    console.log("");
+    match signature {
+        SignatureStatus::Unsigned => console.log("🔑 Signature: unsigned"),
+        SignatureStatus::Valid => console.log(format!("🔑 Signature: valid ({})", info.metadata.algorithm)),
+        SignatureStatus::Invalid(reason) => console.warn(format!("Signature: INVALID ({})", reason)),
+    }
+    match stub {
+        inspect::StubStatus::Stale => console.warn("Metadata stub: stale (it doesn't match the package layout)"),
+        status => console.log(format!("🧭 Metadata stub: {}", status.as_str())),
+    }
+    
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    console.log(format!(
+        "\n📂 Payload: {} entries, {} uncompressed ({} compressed)",
+        entries.len(),
+        stats::format_size(total),
+        stats::format_size(info.payload_size)
+    ));
+    for entry in &entries {
+        let target = entry.link_target.as_ref()
+            .map(|t| format!(" -> {}", t.display()))
+            .unwrap_or_default();
+        console.log(format!(
+            "   {} {:>10}  {}{}",
+            inspect::mode_string(entry.kind, entry.mode),
+            entry.size,
+            entry.path.display(),
+            target
+        ));
+    }
+    Ok(())
+}
+
+/// Unpack a package's payload into `output`
+fn cmd_extract(file: &std::path::Path, output: &std::path::Path, strip: usize, console: &Console) -> Result<()> {
+    let info = lxe_common::payload::read_payload_info_unchecked(file)
+        .with_context(|| format!("Failed to read package: {}", file.display()))?;
+    // Nothing from the package runs, so only tampering matters, not system requirements
+    if let inspect::SignatureStatus::Invalid(reason) = inspect::signature_status(&info.metadata) {
+        anyhow::bail!("Package signature is invalid ({}); refusing to extract it", reason);
+    }
+    
+    console.log(format!("📦 Extracting {} v{} to {}", info.metadata.name, info.metadata.version, output.display()));
+    let spinner = console.spinner("Extracting...");
+    let result = extract::extract(&info, output, strip, |path| {
+        if let Some(ref pb) = spinner {
+            pb.set_message(path.display().to_string());
+        }
+        console.verbose(path.display());
+    });
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    
+    let count = result?;
+    console.success(format!("Extracted {} entries to {}", count, output.display()));
+    Ok(())
+}
+
+/// Print changed metadata, then the file differences in `diff -u` style
+/// (`filters` are the --added, --removed and --changed flags)
+fn cmd_diff(
+    old: &std::path::Path,
+    new: &std::path::Path,
+    filters: (bool, bool, bool),
+    console: &Console,
+) -> Result<()> {
+    use diff::FileChange;
+    use lxe_common::payload::{list_entries, read_payload_info_unchecked};
+    
+    let read = |file: &std::path::Path| -> Result<_> {
+        let info = read_payload_info_unchecked(file)
+            .with_context(|| format!("Failed to read package: {}", file.display()))?;
+        let entries = list_entries(&info)
+            .with_context(|| format!("Failed to list {}", file.display()))?;
+        Ok((info.metadata, entries))
+    };
+    let (old_metadata, old_entries) = read(old)?;
+    let (new_metadata, new_entries) = read(new)?;
+    
+    console.log(format!("--- {}", old.display()));
+    console.log(format!("+++ {}", new.display()));
+    
+    let fields = diff::metadata_changes(&old_metadata, &new_metadata);
+    if !fields.is_empty() {
+        console.log("\n📝 Metadata");
+        let width = fields.iter().map(|(name, _, _)| name.len()).max().unwrap_or(0);
+        for (name, old_value, new_value) in &fields {
+            console.log(format!("   {:<width$}  {} → {}", name, old_value, new_value, width = width));
+        }
+    }
+    
+    let (added, removed, changed) = filters;
+    let show_all = !(added || removed || changed);
+    let changes: Vec<FileChange> = diff::diff_entries(&old_entries, &new_entries)
+        .into_iter()
+        .filter(|change| show_all || match change {
+            FileChange::Added(_) => added,
+            FileChange::Removed(_) => removed,
+            FileChange::Changed { .. } => changed,
+        })
+        .collect();
+    
+    if !changes.is_empty() {
+        console.log("\n📂 Files");
+    }
+    let line = |marker: char, entry: &lxe_common::payload::EntryInfo| {
+        format!(
+            "{}  {} {:>10}  {}",
+            marker,
+            inspect::mode_string(entry.kind, entry.mode),
+            entry.size,
+            entry.path.display()
+        )
+    };
+    for change in &changes {
+        match change {
+            FileChange::Added(entry) => console.log(line('+', entry)),
+            FileChange::Removed(entry) => console.log(line('-', entry)),
+            FileChange::Changed { old, new } => {
+                console.log(line('-', old));
+                console.log(line('+', new));
+            }
+        }
+    }
+    
+    let count = |f: fn(&FileChange) -> bool| changes.iter().filter(|c| f(c)).count();
+    console.log(format!(
+        "\n   {} added, {} removed, {} changed; {} metadata field(s) changed",
+        count(|c| matches!(c, FileChange::Added(_))),
+        count(|c| matches!(c, FileChange::Removed(_))),
+        count(|c| matches!(c, FileChange::Changed { .. })),
+        fields.len()
+    ));
+    Ok(())
+}
+
+/// Build a delta package upgrading `old` to `new`
+fn cmd_delta(
+    old: &std::path::Path,
+    new: &std::path::Path,
+    output: &std::path::Path,
+    level: i32,
+    console: &Console,
+) -> Result<()> {
+    use lxe_common::delta::DELTA_FORMAT_VERSION;
+    use lxe_common::payload::{self, read_payload_info};
+    
+    let read = |file: &std::path::Path| {
+        read_payload_info(file).with_context(|| format!("Failed to read package: {}", file.display()))
+    };
+    let old_info = read(old)?;
+    let new_info = read(new)?;
+    if old_info.metadata.app_id != new_info.metadata.app_id {
+        anyhow::bail!(
+            "Packages are different apps: {} and {}",
+            old_info.metadata.app_id,
+            new_info.metadata.app_id
+        );
+    }
+    if old_info.metadata.is_delta() || new_info.metadata.is_delta() {
+        anyhow::bail!("Deltas are built from full packages, not from other deltas");
+    }
+    if old_info.metadata.payload_checksum == new_info.metadata.payload_checksum {
+        anyhow::bail!("The packages have the same payload; there is nothing to update");
+    }
+    
+    console.log(format!(
+        "📦 Delta for {}: {} → {}\n",
+        new_info.metadata.app_id, old_info.metadata.version, new_info.metadata.version
+    ));
+    
+    let spinner = console.spinner("Comparing payloads...");
+    let new_digests = delta::entry_digests(&new_info)?;
+    let plan = delta::plan(&delta::entry_digests(&old_info)?, &new_digests);
+    
+    if let Some(ref pb) = spinner {
+        pb.set_message("Compressing changed files...");
+    }
+    let output_dir = output.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(std::path::Path::new("."));
+    let payload_file = tempfile::NamedTempFile::new_in(output_dir)
+        .context("Failed to create temporary payload file")?;
+    delta::write_payload(&new_info, &plan, payload_file.path(), level)?;
+    let checksum = payload::checksum_file(payload_file.path(), new_info.metadata.checksum_algorithm)?;
+    
+    // The new release's metadata, describing the delta payload
+    let mut metadata: serde_json::Value = serde_json::from_slice(&payload::read_raw_metadata(&new_info)?)
+        .context("Failed to parse package metadata")?;
+    if let Some(obj) = metadata.as_object_mut() {
+        obj.remove("signature");
+        obj.remove("public_key");
+        obj.remove("algorithm");
+        // Delta payloads are always zstd
+        obj.remove("compressor");
+    }
+    metadata["payload_checksum"] = serde_json::json!(checksum);
+    metadata["delta_base_checksum"] = serde_json::json!(old_info.metadata.payload_checksum);
+    metadata["delta_target_checksum"] = serde_json::json!(new_info.metadata.payload_checksum);
+    metadata["delta_target_tree"] = serde_json::json!(delta::tree_checksum(&new_digests));
+    metadata["delta_format_version"] = serde_json::json!(DELTA_FORMAT_VERSION);
+    
+    let mut runtime = Vec::new();
+    File::open(new)?.take(new_info.header_offset).read_to_end(&mut runtime)?;
+    let size = builder::write_package_file(output, &runtime, &serde_json::to_vec(&metadata)?, &checksum, payload_file.path())?;
+    if let Some(pb) = spinner {
+        pb.finish_and_clear();
+    }
+    
+    let full_size = fs::metadata(new)?.len();
+    console.success(format!("Delta package: {}", output.display()));
+    console.log(format!(
+        "   {} added or changed, {} removed",
+        plan.include.len(),
+        plan.removed.len()
+    ));
+    console.log(format!(
+        "   {} (full package: {})",
+        stats::format_size(size),
+        stats::format_size(full_size)
+    ));
+    if new_info.metadata.is_signed() {
+        console.warn("The delta is unsigned - sign it with lxe sign");
+    }
+    Ok(())
+}
+
+/// Convert a directory of AppImages, continuing past failures
+fn cmd_migrate_appimage(
+    input_dir: &std::path::Path,
+    output_dir: &std::path::Path,
+    key: Option<&std::path::Path>,
+    parallel: bool,
+    console: &Console,
+) -> Result<()> {
+    use rayon::prelude::*;
+    
+    let appimages = appimage::find_appimages(input_dir)?;
+    if appimages.is_empty() {
+        console.log(format!("No *.AppImage files in {}", input_dir.display()));
+        return Ok(());
+    }
+    fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+    // The builds run in temporary directories, so paths must be absolute
+    let output_dir = output_dir.canonicalize()?;
+    let key = key.map(|k| k.canonicalize().with_context(|| format!("Key file not found: {}", k.display())))
+        .transpose()?;
+    let lxe_exe = std::env::current_exe()?;
+    
+    console.log(format!("📦 Migrating {} AppImage(s) to {}\n", appimages.len(), output_dir.display()));
+    let convert = |path: &PathBuf| {
+        console.log(format!("🔨 Converting {}...", path.display()));
+        appimage::convert(path, &output_dir, key.as_deref(), &lxe_exe)
+    };
+    let results: Vec<Result<PathBuf>> = if parallel {
+        appimages.par_iter().map(convert).collect()
+    } else {
+        appimages.iter().map(convert).collect()
+    };
+    
+    console.log("");
+    let width = appimages.iter()
+        .map(|p| p.file_name().unwrap_or_default().to_string_lossy().len())
+        .max()
+        .unwrap_or(0);
+    for (path, result) in appimages.iter().zip(&results) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        match result {
+            Ok(package) => console.log(format!(
+                "   ✓ {:<width$}  {}",
+                name,
+                package.file_name().unwrap_or_default().to_string_lossy(),
+                width = width
+            )),
+            Err(e) => console.log(format!("   ✗ {:<width$}  {:#}", name, e, width = width)),
+        }
+    }
+    
+    let failed = results.iter().filter(|r| r.is_err()).count();
+    if failed > 0 {
+        anyhow::bail!("{} of {} AppImages failed to convert", failed, results.len());
+    }
+    console.success(format!("Converted {} AppImages", results.len()));
+    Ok(())
+}
+
+/// Report suspicious package content; fails if anything is high severity
+fn cmd_security_scan(file: &std::path::Path, level: security::ScanLevel, console: &Console) -> Result<()> {
+    use security::Severity;
+    
+    console.log(format!("🛡️  Security scan ({:?}): {}\n", level, file.display()));
+    
+    let findings = security::scan(file, level)?;
+    if findings.is_empty() {
+        console.success("No findings");
+        return Ok(());
+    }
+    
+    for finding in &findings {
+        let location = finding.path.as_ref()
+            .map(|p| format!(" {}:", p.display()))
+            .unwrap_or_default();
+        let mut lines = finding.detail.lines();
+        console.log(format!(
+            "   [{:<6}] {}{} {}",
+            finding.severity,
+            finding.check,
+            location,
+            lines.next().unwrap_or_default()
+        ));
+        // Hook scripts are shown verbatim below their finding
+        for line in lines {
+            console.log(format!("            | {}", line));
+        }
+    }
+    
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    console.log(format!(
+        "\n   {} high, {} medium, {} low",
+        count(Severity::High),
+        count(Severity::Medium),
+        count(Severity::Low)
+    ));
+    if count(Severity::High) > 0 {
+        anyhow::bail!("{} high-severity finding(s)", count(Severity::High));
+    }
+    Ok(())
+}
+
+/// Time each checksum algorithm on the same data
+fn cmd_benchmark(file: Option<&std::path::Path>, size_mb: usize, console: &Console) -> Result<()> {
+    use lxe_common::payload::{calculate_checksum, ChecksumAlgorithm};
+    
+    let data = match file {
+        Some(path) => fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+        // Hashing speed doesn't depend on content; avoid all-zero pages anyway
+        None => (0..size_mb * 1024 * 1024).map(|i| (i % 251) as u8).collect(),
+    };
+    let mb = data.len() as f64 / 1024.0 / 1024.0;
+    console.log(format!("⏱️  Checksum benchmark ({:.1} MB)\n", mb));
+    
+    let mut results = Vec::new();
+    for algo in ChecksumAlgorithm::ALL {
+        let start = std::time::Instant::now();
+        calculate_checksum(&data, algo);
+        let secs = start.elapsed().as_secs_f64().max(f64::EPSILON);
+        console.log(format!("   {:<8} {:>8.3}s  {:>8.0} MB/s", algo.to_string(), secs, mb / secs));
+        results.push((algo, secs));
+    }
+    
+    let time = |algo| results.iter().find(|(a, _)| *a == algo).map(|(_, s)| *s).unwrap_or(0.0);
+    console.log("");
+    console.log(format!(
+        "   BLAKE3 is {:.1}x the speed of SHA256",
+        time(ChecksumAlgorithm::Sha256) / time(ChecksumAlgorithm::Blake3)
+    ));
+    Ok(())
+}
+
+/// Rewrite package metadata in place without rebuilding the payload
+fn cmd_patch_meta(file: &PathBuf, set: &[String], console: &Console) -> Result<()> {
+    use lxe_common::payload;
+    
+    if set.is_empty() {
+        anyhow::bail!("Nothing to patch. Use --set key=value (e.g., --set version=1.0.1)");
+    }
+    
+    let info = payload::read_payload_info_unchecked(file)
+        .context("Failed to read package")?;
+    let old_json = payload::read_raw_metadata(&info)?;
+    let mut metadata: serde_json::Value = serde_json::from_slice(&old_json)
+        .context("Failed to parse package metadata")?;
+    
+    console.log(format!("🩹 Patching: {}\n", file.display()));
+    
+    for assignment in set {
+        let (key, value) = assignment.split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid --set '{}': expected key=value", assignment))?;
+        let old = apply_meta_patch(&mut metadata, key.trim(), value)?;
+        console.log(format!("   {}: {} → {}", key.trim(), old.unwrap_or_else(|| "(unset)".to_string()), value));
+    }
+    
+    // Any change invalidates the signature
+    let was_signed = metadata.get("signature").is_some();
+    if let Some(obj) = metadata.as_object_mut() {
+        obj.remove("signature");
+        obj.remove("public_key");
+    }
+    
+    // Make sure the result is still valid metadata
+    serde_json::from_value::<lxe_common::metadata::LxeMetadata>(metadata.clone())
+        .context("Patched metadata is invalid")?;
+    
+    let new_json = serde_json::to_vec(&metadata)?;
+    if new_json.len() > old_json.len() * 2 && new_json.len() - old_json.len() > 4096 {
+        console.warn(format!(
+            "Metadata grew from {} to {} bytes",
+            old_json.len(),
+            new_json.len()
+        ));
+    }
+    
+    rewrite_metadata(file, &info, old_json.len(), &new_json)?;
+    
+    console.success("Metadata updated");
+    if was_signed {
+        console.warn("Signature removed - re-sign the package to restore it");
+    }
+    
+    Ok(())
+}
+
+/// Sign a built package, replacing its metadata blob
+fn cmd_sign(file: &PathBuf, key_path: &std::path::Path, force: bool, console: &Console) -> Result<()> {
+    use lxe_common::payload;
+    
+    let info = payload::read_payload_info_unchecked(file)
+        .context("Failed to read package")?;
+    if info.metadata.signature.is_some() && !force {
+        anyhow::bail!("{} is already signed. Use --force to replace the signature.", file.display());
+    }
+    // The signature only covers the checksum; it must not vouch for a changed payload
+    conformance::verify_payload(file, &info.metadata)?;
+    
+    console.log(format!("🔏 Signing: {}\n", file.display()));
+    let key = lxe_common::signing::KeyBackend::from_key_file(key_path)?;
+    let signed = replace_signature(file, &info, &key)?;
+    
+    console.success("Package signed");
+    if let Some(public_key) = &signed.public_key {
+        let fingerprint = lxe_common::signing::public_key_fingerprint(public_key).unwrap_or_default();
+        console.log(format!("   Key: {}", fingerprint));
+    }
+    Ok(())
+}
+
+/// Sign the package `file` with `key`, replacing any signature it has;
+/// returns the new metadata
+fn replace_signature(
+    file: &PathBuf,
+    info: &lxe_common::payload::PayloadInfo,
+    key: &lxe_common::signing::KeyBackend,
+) -> Result<lxe_common::metadata::LxeMetadata> {
+    let old_json = lxe_common::payload::read_raw_metadata(info)?;
+    let mut metadata: serde_json::Value = serde_json::from_slice(&old_json)
+        .context("Failed to parse package metadata")?;
+    if let Some(obj) = metadata.as_object_mut() {
+        obj.remove("signature");
+        obj.remove("public_key");
+        obj.remove("algorithm");
+    }
+    builder::sign_metadata(&mut metadata, key, &info.metadata.payload_checksum)?;
+    
+    // Refuse to write a package the installer would reject
+    let signed: lxe_common::metadata::LxeMetadata = serde_json::from_value(metadata.clone())?;
+    if let inspect::SignatureStatus::Invalid(reason) = inspect::signature_status(&signed) {
+        anyhow::bail!("Signature doesn't verify: {}", reason);
+    }
+    
+    let new_json = serde_json::to_vec(&metadata)?;
+    rewrite_metadata(file, info, old_json.len(), &new_json)?;
+    Ok(signed)
+}
+
+/// Outcome of `lxe key rotate`
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RotationSummary {
+    resigned: usize,
+    unsigned: usize,
+    failed: usize,
+}
+
+/// Re-sign the packages in `dir` that `old_key` verifies with `new_key`
+fn cmd_key_rotate(old_key: &std::path::Path, new_key: &std::path::Path, dir: &std::path::Path, console: &Console) -> Result<()> {
+    let summary = rotate_keys(old_key, new_key, dir, console)?;
+    
+    console.report("resigned", summary.resigned);
+    console.report("unsigned", summary.unsigned);
+    console.report("failed", summary.failed);
+    console.log(format!(
+        "\n{} re-signed, {} unsigned (skipped), {} failed",
+        summary.resigned, summary.unsigned, summary.failed
+    ));
+    if summary.failed > 0 {
+        anyhow::bail!("{} package(s) could not be re-signed", summary.failed);
+    }
+    console.success("Key rotated");
+    Ok(())
+}
+
+fn rotate_keys(old_key: &std::path::Path, new_key: &std::path::Path, dir: &std::path::Path, console: &Console) -> Result<RotationSummary> {
+    use lxe_common::payload;
+    use lxe_common::signing::{inspect_key_file, public_key_fingerprint, KeyBackend};
+    
+    let old_public_key = inspect_key_file(old_key)?.public_key;
+    let new_key = KeyBackend::from_key_file(new_key)?;
+    
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "lxe"))
+        .collect();
+    files.sort();
+    
+    // Check everything first, so a wrong --old-key changes nothing
+    let mut summary = RotationSummary::default();
+    let mut to_sign = Vec::new();
+    for file in files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let info = match payload::read_payload_info_unchecked(&file) {
+            Ok(info) => info,
+            Err(e) => {
+                console.error(format!("{}: {:#}", name, e));
+                summary.failed += 1;
+                continue;
+            }
+        };
+        match inspect::signature_status(&info.metadata) {
+            inspect::SignatureStatus::Unsigned => {
+                console.verbose(format!("{}: unsigned, skipped", name));
+                summary.unsigned += 1;
+            }
+            inspect::SignatureStatus::Valid if info.metadata.public_key.as_deref() == Some(old_public_key.as_str()) => {
+                // The signature only covers the checksum; a new one must not vouch for a changed payload
+                match conformance::verify_payload(&file, &info.metadata) {
+                    Ok(()) => to_sign.push((file, info)),
+                    Err(e) => {
+                        console.error(format!("{}: {:#}", name, e));
+                        summary.failed += 1;
+                    }
+                }
+            }
+            inspect::SignatureStatus::Valid => {
+                let signer = info.metadata.public_key.as_deref()
+                    .and_then(|key| public_key_fingerprint(key).ok())
+                    .unwrap_or_default();
+                console.error(format!("{}: signed by another key ({})", name, signer));
+                summary.failed += 1;
+            }
+            inspect::SignatureStatus::Invalid(reason) => {
+                console.error(format!("{}: invalid signature: {}", name, reason));
+                summary.failed += 1;
+            }
+        }
+    }
+    if to_sign.is_empty() {
+        anyhow::bail!(
+            "The old key ({}) verifies no package in {} - wrong key?",
+            public_key_fingerprint(&old_public_key).unwrap_or_default(),
+            dir.display()
+        );
+    }
+    
+    for (file, info) in to_sign {
+        let name = file.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        match replace_signature(&file, &info, &new_key) {
+            Ok(_) => {
+                console.log(format!("   🔏 {}", name));
+                summary.resigned += 1;
+            }
+            Err(e) => {
+                console.error(format!("{}: {:#}", name, e));
+                summary.failed += 1;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Replace the metadata blob of `file` (`old_len` bytes) with `new_json`,
+/// keeping the runtime, checksum, payload, footer version and flags; the
+/// runtime's metadata stub is updated to the new layout
+fn rewrite_metadata(
+    file: &PathBuf,
+    info: &lxe_common::payload::PayloadInfo,
+    old_len: usize,
+    new_json: &[u8],
+) -> Result<()> {
+    use lxe_common::{payload, payload_format};
+    
+    // Rewrite: [runtime][magic][len][metadata][checksum][payload][footer]
+    let tmp_path = file.with_extension("lxe.tmp");
+    {
+        let mut src = File::open(file)?;
+        let stub_offset = payload_format::stub_offset_in(&mut src, info.header_offset);
+        src.seek(std::io::SeekFrom::Start(0))?;
+        let mut out = std::io::BufWriter::new(File::create(&tmp_path)?);
+        
+        std::io::copy(&mut (&mut src).take(info.header_offset), &mut out)?;
+        out.write_all(LXE_MAGIC)?;
+        out.write_all(&(new_json.len() as u32).to_le_bytes())?;
+        out.write_all(new_json)?;
+        
+        // Checksum and payload are copied byte-for-byte
+        let checksum_offset = info.header_offset + (LXE_MAGIC.len() + 4 + old_len) as u64;
+        src.seek(std::io::SeekFrom::Start(checksum_offset))?;
+        let copied = std::io::copy(&mut (&mut src).take(32 + info.payload_size), &mut out)?;
+        if copied != 32 + info.payload_size {
+            anyhow::bail!("Package is truncated");
+        }
+        
+        if info.footer_version >= payload::FOOTER_VERSION_3 {
+            let mut old_footer = [0u8; payload::FOOTER_V3_SIZE as usize];
+            src.seek(std::io::SeekFrom::End(-(payload::FOOTER_V3_SIZE as i64)))?;
+            src.read_exact(&mut old_footer)?;
+            let flags = payload::FooterV3::from_bytes(&old_footer)
+                .ok_or_else(|| anyhow::anyhow!("Package has no valid v3 footer"))?
+                .flags;
+            let metadata_offset = info.header_offset + (LXE_MAGIC.len() + 4) as u64;
+            payload::write_footer_v3(&mut out, &payload::FooterV3 {
+                flags,
+                metadata_offset,
+                metadata_len: new_json.len() as u32,
+                payload_offset: metadata_offset + new_json.len() as u64 + 32,
+                payload_len: info.payload_size,
+            })?;
+        } else if info.footer_version >= payload::FOOTER_VERSION_2 {
+            payload::write_footer_v2(&mut out, info.header_offset, &info.footer_ext)?;
+        } else {
+            payload::write_footer_v1(&mut out, info.header_offset)?;
+        }
+        
+        // The stub still points at the old metadata
+        if let Some(stub_offset) = stub_offset {
+            let metadata_offset = info.header_offset + (LXE_MAGIC.len() + 4) as u64;
+            let stub = payload_format::MetaStub {
+                header_offset: info.header_offset,
+                metadata_offset,
+                metadata_len: new_json.len() as u32,
+                payload_offset: metadata_offset + new_json.len() as u64 + 32,
+                payload_size: info.payload_size,
+            };
+            out.seek(std::io::SeekFrom::Start(stub_offset))?;
+            out.write_all(&stub.to_bytes())?;
+        }
+        out.flush()?;
+    }
+    
+    // Keep the executable bit
+    fs::set_permissions(&tmp_path, fs::metadata(file)?.permissions())?;
+    fs::rename(&tmp_path, file)
+        .context("Failed to replace package")
+}
+
+/// Set `key` (dotted paths allowed, e.g. installer.welcome_title) to `value`
+/// 
+/// Values that parse as JSON (numbers, booleans, arrays) are stored as such,
+/// anything else as a string. Returns the previous value, if any.
+fn apply_meta_patch(metadata: &mut serde_json::Value, key: &str, value: &str) -> Result<Option<String>> {
+    if PROTECTED_META_FIELDS.contains(&key) {
+        anyhow::bail!("Field '{}' cannot be patched", key);
+    }
+    
+    let new_value = serde_json::from_str::<serde_json::Value>(value)
+        .ok()
+        .filter(|v| !v.is_object())
+        .unwrap_or_else(|| serde_json::Value::String(value.to_string()));
+    
+    let mut target = metadata;
+    let mut parts = key.split('.').peekable();
+    while let Some(part) = parts.next() {
+        let obj = target.as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("Cannot set '{}': parent is not an object", key))?;
+        if parts.peek().is_none() {
+            let old = obj.insert(part.to_string(), new_value);
+            return Ok(old.filter(|v| !v.is_null()).map(|v| match v {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            }));
+        }
+        target = obj.entry(part.to_string()).or_insert_with(|| serde_json::json!({}));
+    }
+    
+    anyhow::bail!("Empty key in --set")
+}
+
+struct UninstallOptions {
+    yes: bool,
+    system: bool,
+    dry_run: bool,
+    purge: bool,
+    scan_leftovers: bool,
+    remove_leftovers: bool,
+}
+
+/// Uninstall an LXE application (SYNC - no tokio, no polkit)
+fn cmd_uninstall(app_id: &str, options: &UninstallOptions, console: &Console) -> Result<()> {
+    console.log(format!("🧹 Uninstalling: {}\n", app_id));
+    
+    // Determine base directory
+    let prefix = lxe_common::manifest::InstallManifest::load(app_id).ok().flatten()
+        .and_then(|manifest| manifest.prefix);
+    let base_dir = if let Some(prefix) = prefix {
+        console.log(format!("   Mode: Custom prefix ({})", prefix.display()));
+        prefix
+    } else if options.system {
+        console.log("   Mode: System-wide");
+        console.warn("System-wide uninstall requires sudo");
+        PathBuf::from("/usr")
+    } else {
+        console.log("   Mode: User-local");
+        dirs::data_local_dir()
+            .ok_or_else(|| anyhow::anyhow!("Cannot find local data directory"))?
+            .parent()
+            .ok_or_else(|| anyhow::anyhow!("Cannot find ~/.local"))?
+            .to_path_buf()
+    };
+    
+    // Check if installed
+    let app_dir = base_dir.join("share").join(app_id);
+    if !app_dir.exists() {
+        anyhow::bail!("Application not found: {}\n\nNo installation found at: {:?}", app_id, app_dir);
+    }
+    
+    console.log(format!("   Found: {:?}", app_dir));
+    console.report("app_id", app_id);
+    console.report("app_dir", &app_dir);
+    console.report("dry_run", options.dry_run);
+    
+    if options.dry_run {
+        console.log("\nWould remove:");
+        console.log(format!("   {:?}", app_dir));
+        for dir in purge_dirs(app_id, options.purge) {
+            console.log(format!("   {:?}", dir));
+        }
+        console.log("\nDry run - nothing was removed.");
+        return Ok(());
+    }
+    
+    // Confirmation prompt (unless --yes or --silent)
+    if !options.yes && !console.silent && !console.confirm(format!("Are you sure you want to uninstall {}?", app_id))? {
+        console.log("\nCancelled.");
+        return Ok(());
+    }
+    
+    console.log("\nRemoving files...");
+    remove_app(app_id, &base_dir, options.purge, console)?;
+    console.report("purged", options.purge);
+    
+    console.success(format!("{} has been uninstalled.", app_id));
+    
+    if options.scan_leftovers {
+        clean_leftovers(app_id, options, console)?;
+    }
+    Ok(())
+}
+
+/// List (and with --remove-leftovers, delete) directories the app left behind
+fn clean_leftovers(app_id: &str, options: &UninstallOptions, console: &Console) -> Result<()> {
+    let leftovers = lxe_common::leftovers::find_leftover_files(app_id)?;
+    if leftovers.is_empty() {
+        console.log("\n   No leftover files found");
+        return Ok(());
+    }
+    
+    console.log(format!("\n🔎 Leftover directories of {}:", app_id));
+    for entry in &leftovers {
+        console.log(format!(
+            "   {:<7} {:>10}  {}",
+            entry.kind.to_string(),
+            stats::format_size(entry.size_bytes),
+            entry.path.display()
+        ));
+    }
+    let total: u64 = leftovers.iter().map(|e| e.size_bytes).sum();
+    console.log(format!("   Total: {}", stats::format_size(total)));
+    
+    if !options.remove_leftovers {
+        console.log("\n   Run with --remove-leftovers to delete them");
+        return Ok(());
+    }
+    
+    if !options.yes && !console.silent && !console.confirm(format!("Delete these {} directories?", leftovers.len()))? {
+        console.log("\nLeftovers kept.");
+        return Ok(());
+    }
+    
+    for entry in &leftovers {
+        fs::remove_dir_all(&entry.path)
+            .with_context(|| format!("Failed to remove {}", entry.path.display()))?;
+        console.log(format!("   Removed: {:?}", entry.path));
+    }
+    console.report("leftovers_removed", leftovers.iter().map(|e| &e.path).collect::<Vec<_>>());
+    console.success(format!("Removed {} of leftovers", stats::format_size(total)));
+    Ok(())
+}
+
+/// Uninstall every LXE app, continuing past individual failures
+fn cmd_uninstall_all(options: &UninstallOptions, console: &Console) -> Result<()> {
+    use lxe_common::manifest::InstallManifest;
+    use lxe_common::paths;
+    
+    let manifests: Vec<InstallManifest> = InstallManifest::load_all()?
+        .into_iter()
+        .filter(|m| options.system || !m.is_system)
+        .collect();
+    if manifests.is_empty() {
+        console.log("No applications installed via LXE.");
+        return Ok(());
+    }
+    
+    let user_base = paths::user::base_dir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot find ~/.local"))?;
+    let base_of = |m: &InstallManifest| stats::install_base(m).unwrap_or_else(|| user_base.clone());
+    
+    console.log(format!("🧹 Uninstalling {} app(s):\n", manifests.len()));
+    console.log(format!("   {:<32} {:<12} {:>10} {}", "APP", "VERSION", "SIZE", "SCOPE"));
+    let mut total_size = 0;
+    for m in &manifests {
+        let size = stats::dir_size(&base_of(m).join("share").join(&m.app_id));
+        total_size += size;
+        console.log(format!(
+            "   {:<32} {:<12} {:>10} {}",
+            m.app_id,
+            m.version,
+            stats::format_size(size),
+            if m.is_system { "system" } else { "user" },
+        ));
+    }
+    console.log(format!("\n   Total: {}", stats::format_size(total_size)));
+    if options.purge {
+        console.log("   Config, cache and state directories will be deleted too (--purge)");
+    } else {
+        console.log("   User data directories are kept (use --purge to delete them)");
+    }
+    
+    if options.dry_run {
+        console.log("\nDry run - nothing was removed.");
+        return Ok(());
+    }
+    
+    // The CLI has no polkit agent; check once instead of failing halfway
+    if manifests.iter().any(|m| m.is_system) && !nix::unistd::geteuid().is_root() {
+        anyhow::bail!(
+            "Removing system-wide apps requires root.\n\
+             Re-run with: sudo lxe uninstall --all --system"
+        );
+    }
+    
+    if !options.yes && !console.silent && !console.confirm(format!("Uninstall all {} apps?", manifests.len()))? {
+        console.log("\nCancelled.");
+        return Ok(());
+    }
+    
+    let mut results = Vec::new();
+    let mut errors = Vec::new();
+    for m in &manifests {
+        console.log(format!("\nRemoving {}...", m.app_id));
+        match remove_app(&m.app_id, &base_of(m), options.purge, console) {
+            Ok(()) => results.push((m.app_id.as_str(), "removed")),
+            Err(e) => {
+                results.push((m.app_id.as_str(), "failed"));
+                errors.push((m.app_id.as_str(), e));
+            }
+        }
+    }
+    
+    refresh_application_menus(&[
+        paths::user::applications_dir(),
+        options.system.then(paths::system::applications_dir),
+    ]);
+    
+    console.report("results", results.iter()
+        .map(|(app_id, status)| serde_json::json!({ "app_id": app_id, "status": status }))
+        .collect::<Vec<_>>());
+    console.log(format!("\n{:<32} {}", "APP_ID", "STATUS"));
+    for (app_id, status) in &results {
+        console.log(format!("{:<32} {}", app_id, status));
+    }
+    
+    if !errors.is_empty() {
+        console.log("");
+        for (app_id, e) in &errors {
+            console.error(format!("{}: {:#}", app_id, e));
+        }
+        anyhow::bail!("{} of {} uninstalls failed", errors.len(), results.len());
+    }
+    
+    console.success(format!("Uninstalled {} app(s)", results.len()));
+    Ok(())
+}
+
+/// Remove an installed app's files, menu entry, icons, bin links and manifest
+fn remove_app(app_id: &str, base_dir: &std::path::Path, purge: bool, console: &Console) -> Result<()> {
+    let app_dir = base_dir.join("share").join(app_id);
+    
+    // Links are recorded in the manifest; read it before the files go
+    let manifest = lxe_common::manifest::InstallManifest::load(app_id).ok().flatten();
+    
+    // Stop the app's systemd service before its program goes away
+    let unit = lxe_common::systemd::unit_name(app_id);
+    let unit_files = manifest.iter()
+        .flat_map(|m| m.files.iter().map(PathBuf::from))
+        .filter(|f| f.file_name().is_some_and(|name| name == unit.as_str()) && f.exists());
+    for unit_file in unit_files {
+        let is_system = manifest.as_ref().is_some_and(|m| m.is_system);
+        if let Err(e) = lxe_common::systemd::disable(&unit, is_system) {
+            console.log(format!("   ⚠️  Could not disable {}: {:#}", unit, e));
+        }
+        fs::remove_file(&unit_file).ok();
+        if !is_system {
+            lxe_common::systemd::reload_user().ok();
+        }
+        console.log(format!("   Removed: {:?}", unit_file));
+    }
+    
+    // Remove app directory, keeping modified configuration files
+    if app_dir.exists() {
+        if let Some(ref manifest) = manifest {
+            let backup_dir = lxe_common::manifest::InstallManifest::backup_dir(app_id);
+            let backed_up = manifest.back_up_configs(&app_dir, &backup_dir)
+                .context("Failed to back up configuration files")?;
+            if !backed_up.is_empty() {
+                console.log(format!("   Kept {} modified configuration file(s) in {:?}", backed_up.len(), backup_dir));
+            }
+        }
+        fs::remove_dir_all(&app_dir)
+            .context("Failed to remove application directory")?;
+        console.log(format!("   Removed: {:?}", app_dir));
+    }
+    
+    // Remove .desktop file
+    let desktop_file = base_dir.join("share/applications").join(format!("{}.desktop", app_id));
+    if desktop_file.exists() {
+        fs::remove_file(&desktop_file)?;
+        console.log(format!("   Removed: {:?}", desktop_file));
+    }
+    let applications_dir = base_dir.join("share/applications");
+    for entry in manifest.iter().flat_map(|m| m.desktop_entries(&applications_dir)) {
+        if entry.exists() {
+            fs::remove_file(&entry).ok();
+            console.log(format!("   Removed: {:?}", entry));
+        }
+    }
+    
+    // Remove bin symlink
+    let bin_dir = base_dir.join("bin");
+    // Try to find the symlink - check common patterns
+    let exe_name = app_id.rsplit('.').next().unwrap_or(app_id);
+    let mut bin_links = vec![bin_dir.join(exe_name)];
+    if let Some(ref manifest) = manifest {
+        bin_links.extend(manifest.files.iter()
+            .map(PathBuf::from)
+            .filter(|f| fs::read_link(f).is_ok_and(|target| target.starts_with(&app_dir))));
+    }
+    for bin_link in bin_links {
+        if bin_link.exists() || bin_link.is_symlink() {
+            fs::remove_file(&bin_link).ok();
+            console.log(format!("   Removed: {:?}", bin_link));
+        }
+    }
+    
+    let metainfo = base_dir.join("share/metainfo").join(lxe_common::appstream::metainfo_filename(app_id));
+    if metainfo.exists() {
+        fs::remove_file(&metainfo).ok();
+        console.log(format!("   Removed: {:?}", metainfo));
+    }
+    
+    // Remove icons, including recorded ones in non-standard sizes
+    let icons_base = base_dir.join("share/icons/hicolor");
+    let recorded_icons = manifest.iter()
+        .flat_map(|m| m.files.iter().map(PathBuf::from))
+        .filter(|f| f.starts_with(&icons_base));
+    let standard_icons = lxe_common::paths::icons::SIZES.iter().chain(&["scalable"])
+        .flat_map(|size| ["png", "svg"].map(|ext| icons_base.join(size).join("apps").join(format!("{}.{}", app_id, ext))));
+    for icon_path in recorded_icons.chain(standard_icons) {
+        if icon_path.exists() {
+            fs::remove_file(&icon_path).ok();
+            console.log(format!("   Removed: {:?}", icon_path));
+        }
+    }
+    
+    for dir in purge_dirs(app_id, purge) {
+        fs::remove_dir_all(&dir)
+            .with_context(|| format!("Failed to remove {}", dir.display()))?;
+        console.log(format!("   Removed: {:?}", dir));
+    }
+    
+    let event = lxe_common::history::HistoryEvent::new(
+        lxe_common::history::HistoryEventKind::Uninstall,
+        app_id,
+        manifest.as_ref().map(|m| m.version.as_str()).unwrap_or("unknown"),
+        manifest.as_ref().is_some_and(|m| m.is_system),
+    );
+    if let Err(e) = lxe_common::history::append(&event) {
+        console.log(format!("   ⚠️  Could not record the uninstall in the history: {:#}", e));
+    }
+    
+    lxe_common::manifest::InstallManifest::delete(app_id)
+}
+
+/// Existing config/cache/state directories of `app_id`, if purging
+fn purge_dirs(app_id: &str, purge: bool) -> Vec<PathBuf> {
+    if !purge {
+        return Vec::new();
+    }
+    lxe_common::paths::user::app_data_dirs(app_id)
+        .into_iter()
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// Rebuild the desktop entry caches so removed apps leave the menus
+fn refresh_application_menus(dirs: &[Option<PathBuf>]) {
+    for dir in dirs.iter().flatten().filter(|d| d.exists()) {
+        let _ = Command::new("update-desktop-database")
+            .arg(dir)
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .status();
+    }
+}
+
+/// Build workspace members in dependency order
+fn cmd_workspace_build(
+    all: bool,
+    packages: &[String],
+    jobs: Option<usize>,
+    console: &Console,
+) -> Result<()> {
+    use workspace::{BuildOutcome, WorkspaceMember};
+    
+    if !all && packages.is_empty() {
+        anyhow::bail!("Specify --all or one or more member names to build");
+    }
+    
+    let workspace_dir = std::env::current_dir()?;
+    let mut members = WorkspaceMember::load_all(&workspace_dir)?;
+    
+    // Narrow to the requested members plus everything they depend on
+    if !all {
+        let mut wanted = std::collections::HashSet::new();
+        let mut queue: Vec<String> = packages.to_vec();
+        while let Some(name) = queue.pop() {
+            let member = members.iter().find(|m| m.name == name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown workspace member: {}", name))?;
+            queue.extend(member.depends_on.iter().cloned());
+            wanted.insert(name);
+        }
+        members.retain(|m| wanted.contains(&m.name));
+    }
+    
+    let levels = workspace::build_levels(&members)?;
+    let jobs = jobs.unwrap_or_else(|| {
+        std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+    });
+    
+    console.log(format!("🗂️  Workspace: {} members, {} levels, {} jobs\n", members.len(), levels.len(), jobs));
+    for (n, level) in levels.iter().enumerate() {
+        console.log(format!("   Level {}:", n + 1));
+        for &i in level {
+            let member = &members[i];
+            let label = format!("{} v{}", member.name, member.config.package.version);
+            if member.depends_on.is_empty() {
+                console.log(format!("     • {}", label));
+            } else {
+                console.log(format!("     • {} ← {}", label, member.depends_on.join(", ")));
+            }
+        }
+    }
+    console.log("");
+    
+    // Each member is built by a child `lxe build` so builds don't share a working directory
+    let lxe_exe = std::env::current_exe()?;
+    let outcomes = workspace::build_all(&members, &levels, jobs, |member| {
+        console.log(format!("🔨 Building {}...", member.name));
+        let output = Command::new(&lxe_exe)
+            .args(["build", "--silent"])
+            .current_dir(&member.dir)
+            .output()
+            .with_context(|| format!("Failed to run build for {}", member.name))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("{}", stderr.lines().last().unwrap_or("build failed"));
+        }
+        Ok(())
+    })?;
+    
+    // Summary table
+    console.log(format!("\n   {:<24} {:<10} TIME", "MEMBER", "STATUS"));
+    let mut failures = 0;
+    for (name, outcome) in &outcomes {
+        match outcome {
+            BuildOutcome::Built(time) => {
+                console.log(format!("   {:<24} {:<10} {:.1}s", name, "✅ ok", time.as_secs_f64()));
+            }
+            BuildOutcome::Failed(time, error) => {
+                failures += 1;
+                console.log(format!("   {:<24} {:<10} {:.1}s", name, "❌ failed", time.as_secs_f64()));
+                console.error(format!("{}: {}", name, error));
+            }
+            BuildOutcome::Skipped(dependency) => {
+                failures += 1;
+                console.log(format!("   {:<24} {:<10} (needs {})", name, "⏭️ skipped", dependency));
+            }
+        }
+    }
+    
+    if failures > 0 {
+        anyhow::bail!("{} of {} workspace members did not build", failures, outcomes.len());
+    }
+    
+    console.success(format!("Built {} packages", outcomes.len()));
+    Ok(())
+}
+
+/// Replace an installed app with a new package without a full reinstall
+fn cmd_swap(app_id: &str, file: &std::path::Path, keep_running: bool, console: &Console) -> Result<()> {
+    use lxe_common::manifest::InstallManifest;
+    use swap::sibling;
+    
+    let info = lxe_common::payload::read_payload_info(file)
+        .context("Failed to read package")?;
+    let meta = &info.metadata;
+    lxe_common::requirements::check_system_requirements(meta)?;
+    if meta.app_id != app_id {
+        anyhow::bail!("Package is {} but you asked to swap {}", meta.app_id, app_id);
+    }
+    
+    let mut manifest = InstallManifest::load(app_id)?
+        .ok_or_else(|| anyhow::anyhow!("{} is not installed via LXE (no manifest found)", app_id))?;
+    let base_dir = stats::install_base(&manifest)
+        .ok_or_else(|| anyhow::anyhow!("Cannot find ~/.local"))?;
+    let app_dir = base_dir.join("share").join(app_id);
+    if !app_dir.is_dir() {
+        anyhow::bail!("Installation directory missing: {}", app_dir.display());
+    }
+    
+    console.log(format!("🔄 Swapping {}: v{} → v{}\n", app_id, manifest.version, meta.version));
+    
+    // Phase 1: extract next to the current install
+    let new_dir = sibling(&app_dir, ".new");
+    let old_dir = sibling(&app_dir, ".old");
+    console.log("   Extracting new version...");
+    if let Err(e) = swap::extract_verified(&info, &new_dir) {
+        let _ = fs::remove_dir_all(&new_dir);
+        return Err(e);
+    }
+    
+    let backup_dir = InstallManifest::backup_dir(app_id);
+    manifest.back_up_configs(&app_dir, &backup_dir)
+        .context("Failed to back up configuration files")?;
+    
+    // Phase 2: swap directories, then point the desktop entry and symlink at them
+    let pids = swap::running_processes(&app_dir);
+    swap::swap_dirs(&app_dir, &new_dir, &old_dir)?;
+    
+    let exec_path = app_dir.join(&meta.exec);
+    let integrate = || -> Result<PathBuf> {
+        let exec_name = std::path::Path::new(&meta.exec)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| meta.exec.clone());
+        let link = base_dir.join("bin").join(exec_name);
+        swap::replace_symlink(&link, &exec_path)?;
+        
+        let desktop_path = base_dir.join("share/applications").join(meta.desktop_filename());
+        if let Ok(content) = fs::read_to_string(&desktop_path) {
+            let updated = swap::update_desktop_entry(&content, &[
+                ("Name", meta.name.clone()),
+                ("Comment", meta.description.clone().unwrap_or_else(|| meta.name.clone())),
+                ("Exec", exec_path.display().to_string()),
+                ("X-LXE-Version", meta.version.clone()),
+            ]);
+            swap::write_atomic(&desktop_path, &updated)?;
+        }
+        Ok(link)
+    };
+    
+    let new_link = match integrate() {
+        Ok(link) => link,
+        Err(e) => {
+            console.warn("Swap failed - restoring previous version");
+            swap::rollback(&app_dir, &old_dir)?;
+            return Err(e);
+        }
+    };
+    
+    // Old processes still run from the renamed directory
+    if !pids.is_empty() {
+        if keep_running {
+            let n = swap::signal_processes(&pids, libc::SIGHUP);
+            console.log(format!("   Sent SIGHUP to {} running process(es)", n));
+        } else {
+            let n = swap::signal_processes(&pids, libc::SIGTERM);
+            console.log(format!("   Stopped {} running process(es)", n));
+        }
+    }
+    
+    if let Err(e) = fs::remove_dir_all(&old_dir) {
+        console.warn(format!("Could not remove {}: {}", old_dir.display(), e));
+    }
+    
+    // Drop a stale symlink if the executable was renamed
+    let bin_dir = base_dir.join("bin");
+    for entry in manifest.files.iter_mut() {
+        let path = PathBuf::from(&*entry);
+        let points_into_app = fs::read_link(&path)
+            .map(|target| target.starts_with(&app_dir))
+            .unwrap_or(false);
+        if path.parent() == Some(bin_dir.as_path()) && path != new_link && points_into_app {
+            let _ = fs::remove_file(&path);
+            *entry = new_link.display().to_string();
+        }
+    }
+    
+    manifest.version = meta.version.clone();
+    manifest.name = Some(meta.name.clone());
+    manifest.exec = Some(meta.exec.clone());
+    manifest.deprecated = meta.deprecated;
+    manifest.deprecation_message = meta.deprecation_message.clone();
+    manifest.download_size = Some(info.payload_size);
+    manifest.signed = meta.signature.is_some();
+    manifest.size_cache = None;
+    manifest.file_records.clear();
+    if let Err(e) = manifest.record_files(&app_dir) {
+        console.warn(format!("Could not record file checksums: {:#}", e));
+    }
+    manifest.set_config_paths(&app_dir, &meta.config_paths);
+    manifest.save()?;
+    
+    match InstallManifest::restore_configs(&backup_dir, &app_dir, &meta.config_paths) {
+        Ok(restored) if !restored.is_empty() => {
+            console.log(format!("   Restored {} configuration file(s)", restored.len()));
+        }
+        Ok(_) => {}
+        Err(e) => console.warn(format!("Could not restore configuration files: {:#}", e)),
+    }
+    
+    console.success(format!("{} is now at v{}", app_id, meta.version));
+    Ok(())
+}
+
+/// Run every operation of an actions file as one transaction
+fn cmd_transaction_run(actions: &std::path::Path, console: &Console) -> Result<()> {
+    use lxe_common::transaction::{TransactionLog, TransactionSpec};
+    
+    let spec = TransactionSpec::from_file(actions)?;
+    let id = format!("run-{}", std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0));
+    
+    let log = TransactionLog::create(&id, spec)?;
+    execute_transaction(log, console)
+}
+
+/// Start a transaction for `lxe transaction add`
+fn cmd_transaction_begin(id: &str, console: &Console) -> Result<()> {
+    use lxe_common::transaction::{TransactionLog, TransactionSpec};
+    
+    TransactionLog::create(id, TransactionSpec::default())?;
+    TransactionLog::set_current(Some(id))?;
+    
+    console.success(format!("Started transaction '{}'", id));
+    console.log("   Add operations with: lxe transaction add install <file>");
+    Ok(())
+}
+
+/// Queue an operation in an open transaction
+fn cmd_transaction_add(kind: TransactionOp, target: &str, id: Option<&str>, console: &Console) -> Result<()> {
+    use lxe_common::transaction::{InstallOp, Phase, TransactionLog, UninstallOp};
+    
+    let id = resolve_transaction_id(id)?;
+    let mut log = TransactionLog::load(&id)?;
+    if log.phase != Phase::Open {
+        anyhow::bail!("Transaction '{}' no longer accepts operations", id);
+    }
+    
+    match kind {
+        TransactionOp::Install => {
+            let file = fs::canonicalize(target)
+                .with_context(|| format!("Package not found: {}", target))?;
+            log.spec.install.push(InstallOp { file });
+        }
+        TransactionOp::Uninstall => {
+            log.spec.uninstall.push(UninstallOp { id: target.to_string() });
+        }
+    }
+    log.save()?;
+    
+    console.success(format!(
+        "Queued {} of {} ({} operation(s) in '{}')",
+        if matches!(kind, TransactionOp::Install) { "install" } else { "uninstall" },
+        target,
+        log.spec.install.len() + log.spec.uninstall.len(),
+        id
+    ));
+    Ok(())
+}
+
+/// Prepare and commit a transaction started with `begin`
+fn cmd_transaction_commit(id: Option<&str>, console: &Console) -> Result<()> {
+    let id = resolve_transaction_id(id)?;
+    let log = lxe_common::transaction::TransactionLog::load(&id)?;
+    
+    let result = execute_transaction(log, console);
+    if lxe_common::transaction::TransactionLog::current().as_deref() == Some(id.as_str()) {
+        lxe_common::transaction::TransactionLog::set_current(None)?;
+    }
+    result
+}
+
+/// Undo a transaction (also recovers from an interrupted commit)
+fn cmd_transaction_rollback(id: Option<&str>, console: &Console) -> Result<()> {
+    use lxe_common::transaction::TransactionLog;
+    
+    let id = resolve_transaction_id(id)?;
+    let mut log = TransactionLog::load(&id)?;
+    let renames = log.renames.len();
+    log.rollback()?;
+    log.remove()?;
+    if TransactionLog::current().as_deref() == Some(id.as_str()) {
+        TransactionLog::set_current(None)?;
+    }
+    
+    console.success(format!("Rolled back '{}' ({} rename(s) reversed)", id, renames));
+    Ok(())
+}
+
+/// Explicit ID, or the one started with `begin`
+fn resolve_transaction_id(id: Option<&str>) -> Result<String> {
+    id.map(String::from)
+        .or_else(lxe_common::transaction::TransactionLog::current)
+        .ok_or_else(|| anyhow::anyhow!("No transaction given and none started with 'lxe transaction begin'"))
+}
+
+/// Prepare + commit, rolling back on any failure
+fn execute_transaction(mut log: lxe_common::transaction::TransactionLog, console: &Console) -> Result<()> {
+    console.log(format!(
+        "🔒 Transaction '{}': {} install(s), {} uninstall(s)\n",
+        log.id,
+        log.spec.install.len(),
+        log.spec.uninstall.len()
+    ));
+    
+    console.log("   Phase 1: preparing...");
+    let result = transaction::prepare(&mut log).and_then(|()| {
+        console.log("   Phase 2: committing...");
+        transaction::commit(&mut log)
+    });
+    
+    if let Err(e) = result {
+        console.warn("Transaction failed - rolling back");
+        if let Err(rollback_err) = log.rollback() {
+            console.error(format!(
+                "Rollback incomplete: {:#}\n   Retry with: lxe transaction rollback {}",
+                rollback_err, log.id
+            ));
+            return Err(e);
+        }
+        log.remove()?;
+        return Err(e);
+    }
+    
+    let runtime_source = get_runtime_dir().ok()
+        .map(|d| d.join("lxe-runtime"))
+        .filter(|p| p.exists());
+    let warnings = transaction::finalize(&log, runtime_source.as_deref());
+    for warning in &warnings {
+        console.warn(warning);
+    }
+    console.report("transaction", &log.id);
+    console.report("installed", log.staged.iter().map(|s| &s.app_id).collect::<Vec<_>>());
+    console.report("uninstalled", log.spec.uninstall.iter().map(|op| &op.id).collect::<Vec<_>>());
+    console.report("warnings", &warnings);
+    log.remove()?;
+    
+    console.success("Transaction committed");
+    Ok(())
+}
+
+/// Mark a published package version as deprecated in the registry
+fn cmd_deprecate(
+    id: &str,
+    version: Option<&str>,
+    message: &str,
+    registry: Option<&str>,
+    proxy: Option<&str>,
+    console: &Console,
+) -> Result<()> {
+    let registry = match registry {
+        Some(url) => url.to_string(),
+        None => lxe_common::config::GlobalConfig::load()?.registry.ok_or_else(|| {
+            anyhow::anyhow!(
+                "No registry configured.\n\
+                 Set registry = \"https://...\" in ~/.config/lxe/config.toml or pass --registry"
+            )
+        })?,
+    };
+    
+    // Without a version, every version of the package is deprecated
+    let url = match version {
+        Some(v) => format!("{}/packages/{}/{}", registry.trim_end_matches('/'), id, v),
+        None => format!("{}/packages/{}", registry.trim_end_matches('/'), id),
+    };
+    
+    console.log(format!("⚠️  Deprecating {} {}", id, version.unwrap_or("(all versions)")));
+    console.verbose(format!("PATCH {}", url));
+    
+    let client = network::client_builder(proxy)?.build()?;
+    let mut request = client.patch(&url)
+        .json(&serde_json::json!({
+            "deprecated": true,
+            "deprecation_message": message,
+        }));
+    if let Ok(token) = std::env::var("LXE_REGISTRY_TOKEN") {
+        request = request.bearer_auth(token);
+    }
+    
+    let response = request.send()
+        .with_context(|| format!("Failed to reach registry at {}", registry))?;
+    
+    match response.status() {
+        s if s.is_success() => {}
+        reqwest::StatusCode::NOT_FOUND => {
+            anyhow::bail!("{} {} not found in registry", id, version.unwrap_or(""))
+        }
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+            anyhow::bail!("Registry rejected the request - set LXE_REGISTRY_TOKEN to a publisher token")
+        }
+        s => anyhow::bail!("Registry request failed: HTTP {}", s),
+    }
+    
+    console.success("Package marked as deprecated");
+    Ok(())
+}
+
+/// Show install, upgrade and uninstall events, newest first
+fn cmd_history(app_id: Option<&str>, limit: Option<usize>, console: &Console) -> Result<()> {
+    let events: Vec<_> = lxe_common::history::load()?
+        .into_iter()
+        .rev()
+        .filter(|e| app_id.is_none_or(|id| e.app_id == id))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    
+    if events.is_empty() {
+        console.log("No install history recorded yet.");
+        return Ok(());
+    }
+    
+    console.log(format!(
+        "{:<20} {:<9} {:<32} {:<20} {}",
+        "DATE", "EVENT", "APP", "VERSION", "SCOPE"
+    ));
+    for event in &events {
+        let version = match event.previous_version {
+            Some(ref previous) => format!("{} → {}", previous, event.version),
+            None => event.version.clone(),
+        };
+        console.log(format!(
+            "{:<20} {:<9} {:<32} {:<20} {}",
+            event.timestamp.replace('T', " ").trim_end_matches('Z'),
+            event.event.as_str(),
+            event.app_id,
+            version,
+            if event.is_system { "system" } else { "user" },
+        ));
+    }
+    Ok(())
+}
+
+fn cmd_completion(action: CompletionAction, console: &Console) -> Result<()> {
+    use clap::CommandFactory;
+    use completion::CompletionShell;
+    
+    let shell = match action {
+        CompletionAction::Bash => CompletionShell::Bash,
+        CompletionAction::Zsh => CompletionShell::Zsh,
+        CompletionAction::Fish => CompletionShell::Fish,
+        CompletionAction::Install { shell } => {
+            let shell = match shell {
+                Some(shell) => shell,
+                None => {
+                    let env = std::env::var("SHELL").unwrap_or_default();
+                    CompletionShell::from_path(&env).with_context(|| format!(
+                        "Cannot detect a supported shell from $SHELL ('{}'); pass --shell bash, zsh or fish", env
+                    ))?
+                }
+            };
+            let path = completion::install(Cli::command(), shell)?;
+            console.report("installed_path", &path);
+            console.success(format!("Installed {} completions to {}", shell.name(), path.display()));
+            if let Some(hint) = shell.setup_hint(&path) {
+                console.log(format!("   {}", hint));
+            }
+            return Ok(());
+        }
+    };
+    let mut script = Vec::new();
+    completion::generate(Cli::command(), shell, &mut script);
+    console.print_raw("script", &String::from_utf8_lossy(&script));
+    Ok(())
+}
+
+/// Show installed size statistics
+fn cmd_stats(format: OutputFormat, top: Option<usize>, console: &Console) -> Result<()> {
+    use stats::format_size;
+    
+    let mut apps = stats::collect()?;
+    // Totals always cover every app, even with --top
+    let totals = stats::totals(&apps);
+    if let Some(n) = top {
+        apps.truncate(n);
+    }
+    
+    if format == OutputFormat::Json || console.json {
+        return console.print_json(serde_json::json!({ "apps": apps, "total": totals }));
+    }
+    
+    if apps.is_empty() {
+        console.log("No applications installed via LXE.");
+        return Ok(());
+    }
+    
+    console.log(format!(
+        "{:<32} {:<12} {:>10} {:<10} {:<7} {}",
+        "APP", "VERSION", "SIZE", "INSTALLED", "SCOPE", "SIGNED"
+    ));
+    for app in &apps {
+        console.log(format!(
+            "{:<32} {:<12} {:>10} {:<10} {:<7} {}",
+            app.app_id,
+            app.version,
+            format_size(app.installed_size_bytes),
+            app.install_date,
+            if app.is_system { "system" } else { "user" },
+            if app.signed { "yes" } else { "no" },
+        ));
+    }
+    
+    console.log(format!("\n📊 {} app(s)", totals.count));
+    console.log(format!("   Installed size: {}", format_size(totals.installed_size_bytes)));
+    console.log(format!("   Download size:  {}", format_size(totals.download_size_bytes)));
+    console.log(format!("   Signed: {}, unsigned: {}", totals.signed, totals.unsigned));
+    
+    Ok(())
+}
+
+/// Refresh interval of `lxe status --watch`
+const STATUS_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Summarize the LXE environment
+fn cmd_status(format: OutputFormat, watch: bool, proxy: Option<&str>, console: &Console) -> Result<()> {
+    let runtime_status = || {
+        let path = get_runtime_dir().ok()
+            .map(|dir| dir.join("lxe-runtime"))
+            .filter(|p| p.exists());
+        status::RuntimeStatus { version: path.as_ref().and_then(|_| installed_runtime_version()), path }
+    };
+    
+    if watch && console.json {
+        anyhow::bail!("--watch doesn't work with --output-format json");
+    }
+    
+    // Updates need the network; --watch checks them once
+    let mut report = status::collect(runtime_status(), true, None, proxy)?;
+    loop {
+        if format == OutputFormat::Json || console.json {
+            console.print_json(serde_json::to_value(&report)?)?;
+        } else {
+            if watch {
+                // Clear the screen and move the cursor home
+                print!("\x1b[2J\x1b[H");
+            }
+            console.log("🩺 LXE Status\n");
+            for (key, value) in report.lines() {
+                console.log(format!("   {:<22} {}", format!("{}:", key), value));
+            }
+            if watch {
+                console.log("\n   Refreshing every 5s (Ctrl+C to stop)");
+            }
+        }
+        
+        if !watch {
+            return Ok(());
+        }
+        std::thread::sleep(STATUS_WATCH_INTERVAL);
+        report = status::collect(runtime_status(), false, report.pending_updates.take(), proxy)?;
+    }
+}
+
+/// Delete what LXE left behind (see `clean`)
+fn cmd_clean(dry_run: bool, yes: bool, console: &Console) -> Result<()> {
+    let items = clean::find_items();
+    if items.is_empty() {
+        console.success("Nothing to clean up");
+        return Ok(());
+    }
+    
+    console.log("🧹 Found:\n");
+    for item in &items {
+        console.log(format!(
+            "   {:<8} {:>10}  {}",
+            item.kind.to_string(),
+            stats::format_size(item.size_bytes),
+            item.path.display()
+        ));
+    }
+    let total: u64 = items.iter().map(|i| i.size_bytes).sum();
+    console.log(format!("\n   Total: {}", stats::format_size(total)));
+    
+    if dry_run {
+        console.log("\nDry run - nothing was removed.");
+        return Ok(());
+    }
+    
+    if !yes && !console.silent && !console.confirm(format!("Delete these {} items?", items.len()))? {
+        console.log("\nCancelled.");
+        return Ok(());
+    }
+    
+    let mut freed = 0;
+    let mut failed = 0;
+    for item in &items {
+        match item.remove() {
+            Ok(()) => freed += item.size_bytes,
+            Err(e) => {
+                console.warn(format!("Could not remove {}: {}", item.path.display(), e));
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} items could not be removed ({} freed)", failed, items.len(), stats::format_size(freed));
+    }
+    console.success(format!("Freed {}", stats::format_size(freed)));
+    Ok(())
+}
+
+fn cmd_doctor(console: &Console) -> Result<()> {
+    use doctor::CheckStatus;
+    
+    console.log("🩺 LXE Doctor\n");
+    let checks = doctor::run_checks(&get_runtime_dir()?.join("lxe-runtime"));
+    for check in &checks {
+        let status = match check.status {
+            CheckStatus::Pass => "pass",
+            CheckStatus::Warn => "warn",
+            CheckStatus::Fail => "FAIL",
+        };
+        console.log(format!("   [{}] {}: {}", status, check.name, check.detail));
+        if let Some(ref hint) = check.hint {
+            console.log(format!("          → {}", hint));
+        }
+    }
+    
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    console.log(format!("\n   {} passed, {} warning(s), {} failed", count(CheckStatus::Pass), count(CheckStatus::Warn), count(CheckStatus::Fail)));
+    if count(CheckStatus::Fail) > 0 {
+        anyhow::bail!("{} check(s) failed", count(CheckStatus::Fail));
+    }
+    Ok(())
+}
+
+/// Rehash an installed app's files and report those that changed (exits 1 if any did)
+fn cmd_verify_install(id: &str, console: &Console) -> Result<()> {
+    use lxe_common::manifest::InstallManifest;
+    
+    let manifest = InstallManifest::load(id)?
+        .ok_or_else(|| anyhow::anyhow!("{} is not installed", id))?;
+    if manifest.file_records.is_empty() {
+        console.warn(format!("No file checksums recorded for {} (installed by an older LXE); reinstall it to record them", id));
+        return Ok(());
+    }
+    
+    console.log(format!("🔍 Verifying {} v{} ({} files)", id, manifest.version, manifest.file_records.len()));
+    let report = manifest.verify_files(true);
+    for path in &report.missing {
+        console.log(format!("   ✗ missing:  {}", path.display()));
+    }
+    for path in &report.modified {
+        console.log(format!("   ✗ modified: {}", path.display()));
+    }
+    if !report.is_intact() {
+        anyhow::bail!(
+            "{} of {} files are missing or modified - reinstall {} to repair it",
+            report.missing.len() + report.modified.len(),
+            report.checked,
+            id
+        );
+    }
+    console.success(format!("All {} files are intact", report.checked));
+    Ok(())
+}
+
+/// List (and with `delete`, remove) files in the app directory that the
+/// package didn't install
+fn cmd_check_orphans(id: &str, delete: bool, yes: bool, console: &Console) -> Result<()> {
+    use lxe_common::manifest::InstallManifest;
+    
+    let manifest = InstallManifest::load(id)?
+        .ok_or_else(|| anyhow::anyhow!("{} is not installed", id))?;
+    if manifest.file_records.is_empty() {
+        anyhow::bail!("No files recorded for {} (installed by an older LXE); reinstall it to record them", id);
+    }
+    let app_dir = stats::app_dir(&manifest)
+        .context("Could not determine the app directory")?;
+    
+    let orphans = manifest.orphaned_paths(&app_dir)?;
+    let total: u64 = orphans.iter().map(|o| o.size).sum();
+    console.report("orphans", orphans.iter().map(|o| &o.path).collect::<Vec<_>>());
+    console.report("size_bytes", total);
+    if orphans.is_empty() {
+        console.success(format!("No orphaned files in {}", app_dir.display()));
+        return Ok(());
+    }
+    
+    console.log(format!("🔍 Not installed by {} v{}:\n", id, manifest.version));
+    for orphan in &orphans {
+        let slash = if orphan.is_dir { "/" } else { "" };
+        console.log(format!("   {:>10}  {}{}", stats::format_size(orphan.size), orphan.path.display(), slash));
+    }
+    console.log(format!("\n   Total: {} ({} bytes)", stats::format_size(total), total));
+    
+    if !delete {
+        console.log(format!("\n💡 Remove them with: lxe check-orphans --delete {}", id));
+        return Ok(());
+    }
+    if !yes && !console.silent && !console.confirm(format!("Delete these {} items?", orphans.len()))? {
+        console.log("\nCancelled.");
+        return Ok(());
+    }
+    
+    let mut failed = 0;
+    for orphan in &orphans {
+        if let Err(e) = orphan.remove() {
+            console.warn(format!("Could not remove {}: {}", orphan.path.display(), e));
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} items could not be removed", failed, orphans.len());
+    }
+    console.success(format!("Removed {} items ({})", orphans.len(), stats::format_size(total)));
+    Ok(())
+}
+
+/// Default seconds between update checks (6 hours)
+const DEFAULT_UPDATE_INTERVAL: u64 = 6 * 60 * 60;
+
+/// Never poll the registry more often than this
+const MIN_UPDATE_INTERVAL: u64 = 5 * 60;
+
+/// Check installed apps for updates and show desktop notifications
+fn cmd_notify_updates(interval: Option<u64>, daemon: bool, stop: bool, proxy: Option<&str>, console: &Console) -> Result<()> {
+    let pid_file = get_runtime_dir()?.join("notify-updates.pid");
+    
+    if stop {
+        let pid: i32 = fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("notify-updates daemon is not running"))?;
+        // SAFETY: kill() has no memory-safety preconditions
+        if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+            console.warn(format!("Process {} was not running", pid));
+        }
+        fs::remove_file(&pid_file)?;
+        console.success("Stopped notify-updates daemon");
+        return Ok(());
+    }
+    
+    let config = lxe_common::config::GlobalConfig::load()?;
+    let interval = interval
+        .or(config.check_updates_interval)
+        .unwrap_or(DEFAULT_UPDATE_INTERVAL);
+    if interval < MIN_UPDATE_INTERVAL {
+        console.warn(format!("Interval raised to {}s to avoid hammering the registry", MIN_UPDATE_INTERVAL));
+    }
+    let interval = interval.max(MIN_UPDATE_INTERVAL);
+    
+    if daemon {
+        let running = fs::read_to_string(&pid_file)
+            .ok()
+            .and_then(|s| s.trim().parse::<i32>().ok())
+            // SAFETY: signal 0 only checks that the process exists
+            .is_some_and(|pid| unsafe { libc::kill(pid, 0) } == 0);
+        if running {
+            anyhow::bail!("notify-updates is already running (stop it with --stop)");
+        }
+        
+        use std::os::unix::process::CommandExt;
+        let child = Command::new(std::env::current_exe()?)
+            .args(proxy.map(|proxy| ["--proxy", proxy]).into_iter().flatten())
+            .args(["--silent", "notify-updates", "--interval", &interval.to_string()])
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0)
+            .spawn()
+            .context("Failed to start background process")?;
+        
+        fs::create_dir_all(pid_file.parent().unwrap_or(std::path::Path::new(".")))?;
+        fs::write(&pid_file, child.id().to_string())?;
+        console.success(format!("notify-updates running in the background (PID {})", child.id()));
+        return Ok(());
+    }
+    
+    console.log(format!("🔔 Checking for updates every {} minutes (Ctrl+C to stop)", interval / 60));
     
-    let signing_key = SigningKey::generate(&mut OsRng);
-    let verifying_key = signing_key.verifying_key();
+    let mut checker = updates::UpdateChecker::new(config.registry, proxy)?;
+    let mut notified = std::collections::HashSet::new();
+    loop {
+        let manifests = lxe_common::manifest::InstallManifest::load_all()?;
+        for update in checker.check(&manifests) {
+            // One notification per new version
+            if notified.insert((update.app_id.clone(), update.latest.clone())) {
+                console.log(format!("   {} {} → {}", update.app_id, update.installed, update.latest));
+                notify_update(update, proxy.map(str::to_string));
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+    }
+}
+
+/// Show a notification with an "Install" action for one update
+fn notify_update(update: updates::AvailableUpdate, proxy: Option<String>) {
+    std::thread::spawn(move || {
+        let handle = notify_rust::Notification::new()
+            .appname("LXE")
+            .summary(&format!("Update available: {}", update.name))
+            .body(&format!("Version {} is available (installed: {})", update.latest, update.installed))
+            .icon("software-update-available")
+            .action("install", "Install")
+            .show();
+        
+        match handle {
+            Ok(handle) => handle.wait_for_action(|action| {
+                if action == "install" {
+                    if let Err(e) = download_and_launch(&update, proxy.as_deref()) {
+                        eprintln!("⚠️  Failed to install update for {}: {:#}", update.app_id, e);
+                    }
+                }
+            }),
+            Err(e) => eprintln!("⚠️  Could not show notification: {}", e),
+        }
+    });
+}
+
+/// Download a package and open its installer once it is verified
+fn download_and_launch(update: &updates::AvailableUpdate, proxy: Option<&str>) -> Result<()> {
+    let url = &update.url;
+    let dir = dirs::cache_dir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot find cache directory"))?
+        .join("lxe/updates");
+    fs::create_dir_all(&dir)?;
     
-    // Encode: 32-byte seed + 32-byte public key
-    let mut key_bytes = [0u8; 64];
-    key_bytes[..32].copy_from_slice(signing_key.as_bytes());
-    key_bytes[32..].copy_from_slice(verifying_key.as_bytes());
+    let name = url.rsplit('/').next().filter(|n| !n.is_empty()).unwrap_or("update.lxe");
+    let path = dir.join(name);
     
-    let encoded = BASE64_STANDARD.encode(&key_bytes);
-    fs::write(output, &encoded)?;
+    let client = network::client_builder(proxy)?.build()?;
+    let mut response = client.get(url).send()?.error_for_status()?;
+    let mut file = File::create(&path)?;
+    std::io::copy(&mut response, &mut file)?;
+    drop(file);
     
-    // Set restrictive permissions
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        let perms = std::fs::Permissions::from_mode(0o600);
-        std::fs::set_permissions(output, perms)?;
+    if let Err(e) = updates::verify_download(&path, update) {
+        let _ = fs::remove_file(&path);
+        return Err(e.context(format!("Refusing to run {}", url)));
     }
     
-    console.success("Keypair generated!");
-    console.log(format!("   🔒 Private key: {}", output.display()));
-    console.log(format!("   🔓 Public key: {}", BASE64_STANDARD.encode(verifying_key.as_bytes())));
-    console.warn("Keep your private key secure and never commit it to git!");
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
     
+    Command::new(&path).spawn()
+        .with_context(|| format!("Failed to launch {}", path.display()))?;
     Ok(())
 }
 
-/// Verify package signature
-fn cmd_verify(file: &PathBuf, console: &Console) -> Result<()> {
-    console.log(format!("🔍 Verifying: {}\n", file.display()));
+/// List installed applications
+/// Flags of `lxe list`
+struct ListOptions {
+    show_deprecated: bool,
+    user: Option<String>,
+    format: list::ListFormat,
+    filter: Option<String>,
+    sort: list::SortField,
+    reverse: bool,
+    system_only: bool,
+    user_only: bool,
+    count: bool,
+}
+
+fn cmd_list(options: &ListOptions, console: &Console) -> Result<()> {
+    use lxe_common::manifest::InstallManifest;
+    use list::{ListFormat, ListRow};
     
-    // Step 1: Read the package metadata
-    let payload_info = lxe_common::payload::read_payload_info(file)
-        .context("Failed to read package")?;
+    let (all, base_dir) = match options.user {
+        Some(ref name) => {
+            let user = nix::unistd::User::from_name(name)
+                .with_context(|| format!("Failed to look up user '{}'", name))?
+                .ok_or_else(|| anyhow::anyhow!("No such user: {}", name))?;
+            let data_dir = user.dir.join(".local").join("share");
+            let manifests = InstallManifest::load_all_in(&InstallManifest::manifests_dir_in(&data_dir))
+                .with_context(|| format!("Cannot read {}'s installed apps", name))?;
+            (manifests, Some(user.dir.join(".local")))
+        }
+        None => (InstallManifest::load_all()?, lxe_common::paths::user::base_dir()),
+    };
     
-    let metadata = &payload_info.metadata;
+    let manifests: Vec<InstallManifest> = all
+        .into_iter()
+        .filter(|m| !options.show_deprecated || m.deprecated)
+        .filter(|m| !options.system_only || m.is_system)
+        .filter(|m| !options.user_only || !m.is_system)
+        .filter(|m| options.filter.as_deref().is_none_or(|p| lxe_common::keystore::glob_matches(p, &m.app_id)))
+        .collect();
     
-    // Display package info
-    console.log("📦 Package Information");
-    console.log(format!("   Name: {}", metadata.name));
-    console.log(format!("   Version: {}", metadata.version));
-    console.log(format!("   App ID: {}", metadata.app_id));
-    console.log("");
+    if options.count {
+        if console.json {
+            console.report("count", manifests.len());
+        } else {
+            println!("{}", manifests.len());
+        }
+        return Ok(());
+    }
     
-    // Step 2: Check if package is signed
-    let (has_signature, has_public_key) = (
-        metadata.signature.is_some(),
-        metadata.public_key.is_some(),
-    );
+    // Sizes are only cached in our own manifests
+    let cache_size = options.user.is_none();
+    let mut rows: Vec<ListRow> = manifests.into_iter()
+        .map(|m| ListRow::from_manifest(m, base_dir.as_deref(), cache_size))
+        .collect();
+    list::sort(&mut rows, options.sort, options.reverse);
     
-    if !has_signature && !has_public_key {
-        console.warn("Package is UNSIGNED");
-        console.log("   This package was not signed by the publisher.");
-        console.log("   Only install if you trust the source.");
+    if console.json {
+        console.report("apps", &rows);
         return Ok(());
     }
+    match options.format {
+        ListFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&rows)?);
+            return Ok(());
+        }
+        ListFormat::Csv => {
+            print!("{}", list::to_csv(&rows));
+            return Ok(());
+        }
+        ListFormat::Table => {}
+    }
     
-    if has_signature != has_public_key {
-        anyhow::bail!(
-            "Invalid package: has {} but not {}",
-            if has_signature { "signature" } else { "public_key" },
-            if has_public_key { "signature" } else { "public_key" }
-        );
+    if rows.is_empty() {
+        console.log(if options.show_deprecated {
+            "No deprecated applications installed."
+        } else {
+            "No applications installed via LXE."
+        });
+        return Ok(());
     }
     
-    // Step 3: Display signature info
-    let public_key = metadata.public_key.as_ref().unwrap();
-    let signature = metadata.signature.as_ref().unwrap();
+    console.log(format!(
+        "{:<32} {:<24} {:<12} {:>10} {:<10} {:<8} {}",
+        "APP ID", "NAME", "VERSION", "SIZE", "INSTALLED", "LOCATION", "SIGNED"
+    ));
+    for row in &rows {
+        let marker = if row.deprecated { "  ⚠️  deprecated" } else { "" };
+        let admin = if row.installed_for_user.is_some() { " (installed by admin)" } else { "" };
+        console.log(format!(
+            "{:<32} {:<24} {:<12} {:>10} {:<10} {:<8} {}{}{}",
+            row.app_id,
+            row.name,
+            row.version,
+            stats::format_size(row.size_bytes),
+            row.install_date,
+            row.location,
+            if row.signed { "yes" } else { "no" },
+            admin,
+            marker,
+        ));
+        
+        if options.show_deprecated {
+            if let Some(ref message) = row.deprecation_message {
+                console.log(format!("      {}", message));
+            }
+        }
+    }
     
-    console.log("🔑 Signature Information");
-    console.log(format!("   Public Key: {}...", &public_key[..20.min(public_key.len())]));
-    console.log(format!("   Signature: {}...", &signature[..20.min(signature.len())]));
-    console.log("");
+    Ok(())
+}
+
+/// Run an installed app, inside a bwrap sandbox when a profile is given
+fn cmd_run(id: &str, sandbox_preset: Option<&str>, args: &[String], console: &Console) -> Result<()> {
+    use lxe_common::manifest::InstallManifest;
+    use lxe_common::sandbox::{self, BwrapBuilder};
     
-    // Step 4: Verify the signature
-    console.log("🔐 Verifying Signature...");
+    let manifest = InstallManifest::load(id)?
+        .ok_or_else(|| anyhow::anyhow!("{} is not installed", id))?;
+    let app_dir = stats::app_dir(&manifest)
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the user data directory"))?;
     
-    // Get the signable data (metadata without sig fields + checksum)
-    let signable_json = metadata.to_signable_json()
-        .context("Failed to create signable metadata")?;
+    let exec = match manifest.exec {
+        Some(ref exec) => app_dir.join(exec),
+        // Manifests of older versions don't record `exec`; their bin symlink
+        // points at it (extra executables have their own links)
+        None => manifest.files.iter()
+            .filter_map(|f| fs::read_link(f).ok())
+            .find(|target| target.starts_with(&app_dir))
+            .ok_or_else(|| anyhow::anyhow!("Could not find the executable of {}", id))?,
+    };
     
-    let signable_data = lxe_common::signing::create_signable_data(&signable_json, &metadata.payload_checksum)
-        .context("Failed to create signable data")?;
+    let mut command = match sandbox_preset {
+        Some(name) => {
+            let profile = sandbox::find_profile(name)?;
+            if !sandbox::bwrap_available() {
+                anyhow::bail!("bwrap not found. Install bubblewrap to use --sandbox-preset.");
+            }
+            console.verbose(format!("Sandbox profile '{}': {}", name, profile.description));
+            
+            let builder = BwrapBuilder::new(&exec)
+                .args(args)
+                .bind_path(&app_dir, false);
+            profile.apply(builder).build()
+        }
+        None => {
+            let mut command = Command::new(&exec);
+            command.args(args);
+            command
+        }
+    };
     
-    let is_valid = lxe_common::signing::verify_signature(&signable_data, signature, public_key)
-        .context("Failed to verify signature")?;
+    let status = command.current_dir(&app_dir).status()
+        .with_context(|| format!("Failed to start {}", exec.display()))?;
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+    Ok(())
+}
+
+/// List the sandbox profiles available to `lxe run --sandbox-preset`
+fn cmd_sandbox_profiles_list(console: &Console) -> Result<()> {
+    let profiles = lxe_common::sandbox::load_profiles()?;
     
-    if is_valid {
-        console.log("   ✅ Signature is VALID");
-        console.log("");
-        console.log("📊 Payload Integrity");
-        console.log(format!("   Checksum: {}...", &metadata.payload_checksum[..16.min(metadata.payload_checksum.len())]));
-        console.log("   Status: Verified by signature");
-        console.log("");
-        console.success("Package is authentic and signed by the publisher.");
-        console.log(format!("   Public key: {}", public_key));
-    } else {
-        console.error("Signature is INVALID");
-        console.log("");
-        console.error("SECURITY WARNING");
-        console.log("   This package may have been tampered with!");
-        console.log("   The signature does not match the package contents.");
-        console.log("   DO NOT INSTALL this package.");
-        anyhow::bail!("Signature verification failed");
+    for (name, profile) in &profiles {
+        let source = if profile.builtin { "built-in" } else { "custom" };
+        console.log(format!("  • {} ({})", name, source));
+        if !profile.description.is_empty() {
+            console.log(format!("      {}", profile.description));
+        }
+        console.verbose(format!(
+            "    network={} home={} display={} dbus={}",
+            profile.network, profile.home, profile.display, profile.dbus
+        ));
+    }
+    
+    if let Some(path) = lxe_common::paths::lxe::sandbox_profiles_path() {
+        console.verbose(format!("Custom profiles: {}", path.display()));
     }
     
     Ok(())
 }
 
-/// Uninstall an LXE application (SYNC - no tokio, no polkit)
-fn cmd_uninstall(app_id: &str, yes: bool, system: bool, console: &Console) -> Result<()> {
-    console.log(format!("🧹 Uninstalling: {}\n", app_id));
+/// Create a new package repository
+fn cmd_repo_init(dir: &std::path::Path, name: Option<&str>, console: &Console) -> Result<()> {
+    let default_name = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf())
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "lxe-repo".to_string());
     
-    // Determine base directory
-    let base_dir = if system {
-        console.log("   Mode: System-wide");
-        console.warn("System-wide uninstall requires sudo");
-        PathBuf::from("/usr")
-    } else {
-        console.log("   Mode: User-local");
-        dirs::data_local_dir()
-            .ok_or_else(|| anyhow::anyhow!("Cannot find local data directory"))?
-            .parent()
-            .ok_or_else(|| anyhow::anyhow!("Cannot find ~/.local"))?
-            .to_path_buf()
-    };
+    repo::Repository::init(dir, name.unwrap_or(&default_name))?;
     
-    // Check if installed
-    let app_dir = base_dir.join("share").join(app_id);
-    if !app_dir.exists() {
-        anyhow::bail!("Application not found: {}\n\nNo installation found at: {:?}", app_id, app_dir);
-    }
+    console.success(format!("Created repository in {}", dir.display()));
+    console.log("\n📝 Next steps:");
+    console.log(format!("   lxe repo add my-app.lxe --repo {}", dir.display()));
+    console.log(format!("   lxe repo serve --repo {}", dir.display()));
     
-    console.log(format!("   Found: {:?}", app_dir));
+    Ok(())
+}
+
+/// Copy a package into a repository
+fn cmd_repo_add(repo_dir: &std::path::Path, file: &std::path::Path, generate_sha256: bool, console: &Console) -> Result<()> {
+    let repo = repo::Repository::open(repo_dir)?;
+    let (entry, replaced) = repo.add(file, generate_sha256)?;
     
-    // Confirmation prompt (unless --yes or --silent)
-    if !yes && !console.silent {
-        print!("\n⚠️  Are you sure you want to uninstall {}? [y/N] ", app_id);
-        std::io::Write::flush(&mut std::io::stdout())?;
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input)?;
-        if !input.trim().eq_ignore_ascii_case("y") {
-            console.log("\nCancelled.");
-            return Ok(());
-        }
+    if replaced {
+        console.warn(format!("Replaced existing {} {} ({})", entry.id, entry.version, entry.arch));
+    }
+    console.success(format!("Added {} {} → {}", entry.id, entry.version, entry.file));
+    if !entry.signed {
+        console.warn("Package is unsigned");
     }
     
-    console.log("\nRemoving files...");
+    Ok(())
+}
+
+/// Remove a package version from a repository
+fn cmd_repo_remove(repo_dir: &std::path::Path, id: &str, version: &str, console: &Console) -> Result<()> {
+    let repo = repo::Repository::open(repo_dir)?;
     
-    // Remove app directory
-    fs::remove_dir_all(&app_dir)
-        .context("Failed to remove application directory")?;
-    console.log(format!("   Removed: {:?}", app_dir));
+    for entry in repo.remove(id, version)? {
+        console.success(format!("Removed {}", entry.file));
+    }
     
-    // Remove .desktop file
-    let desktop_file = base_dir.join("share/applications").join(format!("{}.desktop", app_id));
-    if desktop_file.exists() {
-        fs::remove_file(&desktop_file)?;
-        console.log(format!("   Removed: {:?}", desktop_file));
+    Ok(())
+}
+
+/// Rebuild a repository's index.json
+fn cmd_repo_index(repo_dir: &std::path::Path, generate_sha256: bool, console: &Console) -> Result<()> {
+    let repo = repo::Repository::open(repo_dir)?;
+    let index = repo.reindex(generate_sha256)?;
+    
+    console.success(format!("Indexed {} package(s)", index.packages.len()));
+    let missing_hashes = index.packages.iter().filter(|e| e.sha256.is_none()).count();
+    if missing_hashes > 0 {
+        console.log(format!("{} package(s) without SHA256 - run with --generate-sha256", missing_hashes));
     }
     
-    // Remove bin symlink
-    let bin_dir = base_dir.join("bin");
-    // Try to find the symlink - check common patterns
-    let exe_name = app_id.rsplit('.').next().unwrap_or(app_id);
-    let bin_link = bin_dir.join(exe_name);
-    if bin_link.exists() || bin_link.is_symlink() {
-        fs::remove_file(&bin_link).ok();
-        console.log(format!("   Removed: {:?}", bin_link));
+    Ok(())
+}
+
+/// Search repositories for packages; unreachable ones are skipped with a warning
+fn cmd_repo_search(query: &str, url: Option<String>, proxy: Option<&str>, console: &Console) -> Result<()> {
+    let urls = match url {
+        Some(url) => vec![url],
+        None => {
+            let config = lxe_common::config::GlobalConfig::load()?;
+            let mut urls = config.repo.urls;
+            urls.extend(config.registry.filter(|r| !urls.contains(r)));
+            urls
+        }
+    };
+    if urls.is_empty() {
+        anyhow::bail!(
+            "No repository configured.\n\
+             Add one to ~/.config/lxe/config.toml:\n\n\
+             [repo]\n\
+             urls = [\"https://packages.example.com\"]\n\n\
+             or pass --url"
+        );
     }
     
-    // Remove icons
-    let icon_sizes = ["16x16", "24x24", "32x32", "48x48", "64x64", "128x128", "256x256", "512x512", "scalable"];
-    let icons_base = base_dir.join("share/icons/hicolor");
-    for size in icon_sizes {
-        for ext in ["png", "svg"] {
-            let icon_path = icons_base.join(size).join("apps").join(format!("{}.{}", app_id, ext));
-            if icon_path.exists() {
-                fs::remove_file(&icon_path).ok();
-                console.log(format!("   Removed: {:?}", icon_path));
+    let client = network::client_builder(proxy)?
+        .timeout(std::time::Duration::from_secs(30))
+        .build()?;
+    let mut found = 0;
+    for url in &urls {
+        let index = match repo::fetch_index(&client, url) {
+            Ok(index) => index,
+            Err(e) => {
+                console.warn(format!("{}: {:#}", url, e));
+                continue;
             }
+        };
+        let results = repo::search(&index, query);
+        if results.is_empty() {
+            continue;
+        }
+        console.log(format!("📦 {} ({})", index.name, url));
+        for entry in &results {
+            console.log(format!("   {:<32} {:<12} {}", entry.id, entry.version, entry.name));
         }
+        console.log("");
+        found += results.len();
     }
     
-    console.success(format!("{} has been uninstalled.", app_id));
+    if found == 0 {
+        console.log(format!("No packages matching '{}'", query));
+    }
     Ok(())
 }
 
+/// Serve a repository over HTTP
+fn cmd_repo_serve(repo_dir: &std::path::Path, port: u16, console: &Console) -> Result<()> {
+    let repo = repo::Repository::open(repo_dir)?;
+    let index = repo.load_index()?;
+    
+    console.log(format!("📡 Serving '{}' ({} packages)", index.name, index.packages.len()));
+    console.log(format!("   http://localhost:{}/{}", port, repo::INDEX_FILE));
+    console.log(format!("\n   Clients: set registry = \"http://<host>:{}\" in ~/.config/lxe/config.toml", port));
+    console.log("   Press Ctrl+C to stop");
+    
+    repo::serve(&repo, port)
+}
+
+/// `lxe help`: a help topic, the help of a command, or the overview
+fn cmd_help(topic: &[String]) -> Result<()> {
+    use clap::CommandFactory;
+    
+    let mut command = Cli::command();
+    command.build();
+    let topics = help_topics::TOPICS.iter().map(|t| t.name).collect::<Vec<_>>();
+    
+    let name = match topic {
+        [] => {
+            command.print_help()?;
+            println!("\nHelp topics (lxe help <topic>): {}", topics.join(", "));
+            return Ok(());
+        }
+        [name] => name.as_str(),
+        [..] => "",
+    };
+    if name == "list" {
+        println!("Help topics:\n");
+        for topic in help_topics::TOPICS {
+            println!("  {:<16} {}", topic.name, topic.summary);
+        }
+        println!("\nRun 'lxe help <topic>' to read one.");
+        return Ok(());
+    }
+    if let Some(topic) = help_topics::find(name) {
+        print!("{}", help_topics::wrap(topic.text, help_topics::WRAP_WIDTH));
+        return Ok(());
+    }
+    
+    // `lxe help build`, `lxe help key generate`
+    let mut subcommand = Some(&mut command);
+    for name in topic {
+        subcommand = subcommand.and_then(|c| c.find_subcommand_mut(name));
+    }
+    if let Some(subcommand) = subcommand {
+        subcommand.print_long_help()?;
+        return Ok(());
+    }
+    
+    let topic = topic.join(" ");
+    match help_topics::closest(&topic) {
+        Some(closest) => anyhow::bail!(
+            "Unknown help topic '{}'. Did you mean '{}'?\nRun 'lxe help list' for all topics", topic, closest
+        ),
+        None => anyhow::bail!("Unknown help topic '{}'\nRun 'lxe help list' for all topics", topic),
+    }
+}
+
 /// Self-update the LXE tool
-fn cmd_self_update(check_only: bool, console: &Console) -> Result<()> {
+fn cmd_self_update(check_only: bool, proxy: Option<&str>, console: &Console) -> Result<()> {
     use self_update::cargo_crate_version;
     
+    // self_update builds its own clients and can't be handed one, but they
+    // read the proxy from the environment. Set it here, while the process is
+    // still single-threaded and before any client exists.
+    if let Some(proxy) = proxy {
+        reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy URL: {}", proxy))?;
+        std::env::set_var("HTTP_PROXY", proxy);
+        std::env::set_var("HTTPS_PROXY", proxy);
+    }
+    
     console.log("🔄 Checking for updates...\n");
     console.log(format!("   Current version: v{}", cargo_crate_version!()));
     
@@ -1070,149 +6101,187 @@ fn cmd_self_update(check_only: bool, console: &Console) -> Result<()> {
 
 // === Helper Functions ===
 
-fn create_tar_archive(input_dir: &PathBuf) -> Result<Vec<u8>> {
-    let mut archive_data = Vec::new();
-    
-    {
-        let mut builder = tar::Builder::new(&mut archive_data);
-        builder.follow_symlinks(false);
-        builder.append_dir_all(".", input_dir)
-            .context("Failed to add directory to tar archive")?;
-        builder.finish()
-            .context("Failed to finish tar archive")?;
-    }
-    
-    Ok(archive_data)
-}
-
-fn compress_zstd(data: &[u8], level: i32) -> Result<Vec<u8>> {
-    zstd::encode_all(std::io::Cursor::new(data), level)
-        .context("Failed to compress with zstd")
-}
-
 fn calculate_sha256(data: &[u8]) -> String {
     let mut hasher = Sha256::new();
     hasher.update(data);
     hex::encode(hasher.finalize())
 }
 
-fn get_runtime_binary(custom_path: &Option<PathBuf>) -> Result<Vec<u8>> {
-    // Check custom path first
-    if let Some(path) = custom_path {
-        if path.exists() {
-            let mut file = File::open(path)?;
-            let mut data = Vec::new();
-            file.read_to_end(&mut data)?;
-            return Ok(data);
-        }
+/// Reader adaptor that hashes everything read through it
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> HashingReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, hasher: Sha256::new() }
     }
     
-    // Look for lxe-runtime in same directory as this binary
-    let current_exe = std::env::current_exe()?;
-    let runtime_path = current_exe.parent()
-        .map(|p| p.join("lxe-runtime"))
-        .filter(|p| p.exists());
-    
-    if let Some(path) = runtime_path {
-        let mut file = File::open(&path)?;
-        let mut data = Vec::new();
-        file.read_to_end(&mut data)?;
-        return Ok(data);
+    /// Hex-encoded SHA256 of all bytes read so far
+    fn finalize(self) -> String {
+        hex::encode(self.hasher.finalize())
+    }
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
     }
+}
+
+/// Fetch a `.sha256` sidecar file and return the hex digest it contains
+/// 
+/// Accepts both a bare digest and `sha256sum` output (`<digest>  <filename>`).
+fn fetch_sha256_sidecar(client: &reqwest::blocking::Client, url: &str) -> Result<String> {
+    let response = client.get(url).send()
+        .with_context(|| format!("Failed to download checksum: {}", url))?;
     
-    // Check downloaded runtime location (~/.local/share/lxe/lxe-runtime)
-    if let Ok(runtime_dir) = get_runtime_dir() {
-        let downloaded_path = runtime_dir.join("lxe-runtime");
-        if downloaded_path.exists() {
-            let mut file = File::open(&downloaded_path)?;
-            let mut data = Vec::new();
-            file.read_to_end(&mut data)?;
-            return Ok(data);
-        }
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "Checksum file not available: HTTP {}\n\
+             Use --skip-verify to download without verification (not recommended).",
+            response.status()
+        );
     }
     
-    anyhow::bail!(
-        "LXE runtime not found.\n\
-         Run 'lxe runtime download' to install it, or:\n\
-         - Place 'lxe-runtime' in the same directory as 'lxe'\n\
-         - Or specify [runtime] path in lxe.toml"
-    )
+    let text = response.text()?;
+    parse_sha256_sidecar(&text)
+        .ok_or_else(|| anyhow::anyhow!("Invalid checksum file: {}", url))
 }
 
-fn sign_metadata(
-    metadata: &mut serde_json::Value,
-    key_path: &PathBuf,
-    checksum: &str,
-) -> Result<()> {
-    // Load key
-    let contents = fs::read_to_string(key_path)
-        .with_context(|| format!("Failed to read key: {}", key_path.display()))?;
-    
-    let key_bytes = BASE64_STANDARD.decode(contents.trim())
-        .context("Invalid base64 in key file")?;
-    
-    if key_bytes.len() != 64 {
-        anyhow::bail!("Invalid key file format");
-    }
-    
-    let seed: [u8; 32] = key_bytes[..32].try_into()?;
-    let signing_key = SigningKey::from_bytes(&seed);
-    
-    // Create signable data using the EXACT same struct as verification
-    let app_id = metadata["app_id"].as_str().ok_or(anyhow::anyhow!("Missing app_id"))?;
-    let name = metadata["name"].as_str().ok_or(anyhow::anyhow!("Missing name"))?;
-    let version = metadata["version"].as_str().ok_or(anyhow::anyhow!("Missing version"))?;
-    let arch = metadata["arch"].as_str().ok_or(anyhow::anyhow!("Missing arch"))?;
-    let install_size = metadata["install_size"].as_u64().ok_or(anyhow::anyhow!("Missing install_size"))?;
-    let exec = metadata["exec"].as_str().ok_or(anyhow::anyhow!("Missing exec"))?;
-    let icon = metadata["icon"].as_str();
-    let description = metadata["description"].as_str();
-    let payload_checksum = metadata["payload_checksum"].as_str().ok_or(anyhow::anyhow!("Missing payload_checksum"))?;
-    let terminal = metadata["terminal"].as_bool().unwrap_or(false);
-    
-    // Convert categories array
-    let categories: Vec<String> = metadata["categories"]
-        .as_array()
-        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-        .unwrap_or_default();
+fn parse_sha256_sidecar(text: &str) -> Option<String> {
+    let digest = text.split_whitespace().next()?;
+    (digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()))
+        .then(|| digest.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_hashing_reader_matches_sidecar() {
+        let data = b"lxe runtime tarball";
+        let mut reader = HashingReader::new(&data[..]);
+        std::io::copy(&mut reader, &mut std::io::sink()).unwrap();
         
-    let signable = SignableMetadata {
-        format_version: 1,
-        app_id,
-        name,
-        version,
-        arch,
-        install_size,
-        exec,
-        icon,
-        categories: &categories,
-        description,
-        payload_checksum,
-        min_runtime_version: None,
-        license: None,
-        homepage: None,
-        exec_args: None,
-        terminal,
-    };
+        let digest = reader.finalize();
+        assert_eq!(digest, calculate_sha256(data));
+        
+        let sidecar = format!("{}  lxe-runtime-x86_64-linux.tar.gz\n", digest.to_uppercase());
+        assert_eq!(parse_sha256_sidecar(&sidecar), Some(digest));
+        assert_eq!(parse_sha256_sidecar("not found"), None);
+    }
     
-    let signable_json = serde_json::to_vec(&signable)?;
+    #[test]
+    fn test_json_result() {
+        let mut console = Console::new(false, false);
+        console.report("ignored", 1);
+        console.json = true;
+        console.report("size_bytes", 42u64);
+        let report = console.report.lock().unwrap().clone();
+        let result = json_result("build", report, None);
+        assert_eq!(result, serde_json::json!({"status": "ok", "command": "build", "size_bytes": 42, "error": null}));
+        
+        // Output of the command itself becomes result fields; prompts need --yes
+        console.print_json(serde_json::json!({"apps": []})).unwrap();
+        console.print_raw("csv", "a,b\n");
+        assert_eq!(console.report.lock().unwrap()["apps"], serde_json::json!([]));
+        assert_eq!(console.report.lock().unwrap()["csv"], "a,b\n");
+        assert!(console.confirm("Delete?").is_err());
+        
+        let error = anyhow::anyhow!("inner").context("outer");
+        let result = json_result("list", Default::default(), Some(&error));
+        assert_eq!(result, serde_json::json!({"status": "error", "command": "list", "error": "outer: inner"}));
+    }
     
-    // Create final blob to sign (metadata + checksum bytes)
-    let checksum_bytes = hex::decode(checksum)?;
-    let mut signable_data = signable_json;
-    signable_data.extend_from_slice(&checksum_bytes);
+    #[test]
+    fn test_rotate_keys() {
+        use lxe_common::signing::{KeyBackend, LxeKeyPair};
+        
+        let dir = tempfile::tempdir().unwrap();
+        let (old_key, new_key, other_key) = (dir.path().join("old.key"), dir.path().join("new.key"), dir.path().join("other.key"));
+        for key in [&old_key, &new_key, &other_key] {
+            LxeKeyPair::generate().save(key).unwrap();
+        }
+        let packages = dir.path().join("packages");
+        fs::create_dir(&packages).unwrap();
+        let payload = dir.path().join("payload");
+        fs::write(&payload, b"payload").unwrap();
+        let checksum = calculate_sha256(b"payload");
+        for (name, key) in [("a.lxe", Some(&old_key)), ("b.lxe", Some(&old_key)), ("c.lxe", None), ("d.lxe", Some(&old_key))] {
+            let metadata = lxe_common::metadata::LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 7, &checksum);
+            let mut metadata = serde_json::to_value(metadata).unwrap();
+            if let Some(key) = key {
+                builder::sign_metadata(&mut metadata, &KeyBackend::File(key.clone()), &checksum).unwrap();
+            }
+            let json = serde_json::to_vec(&metadata).unwrap();
+            builder::write_package_file(&packages.join(name), b"runtime", &json, &checksum, &payload).unwrap();
+        }
+        // A payload that no longer matches its checksum isn't re-signed
+        let tampered = packages.join("d.lxe");
+        let mut bytes = fs::read(&tampered).unwrap();
+        let at = bytes.len() - lxe_common::payload::FOOTER_V3_SIZE as usize - 1;
+        bytes[at] ^= 0xff;
+        fs::write(&tampered, &bytes).unwrap();
+        // Footer flags survive re-signing
+        let flagged = packages.join("a.lxe");
+        let mut bytes = fs::read(&flagged).unwrap();
+        let flags_at = bytes.len() - lxe_common::payload::FOOTER_V3_SIZE as usize + 9;
+        bytes[flags_at] = 0x80;
+        fs::write(&flagged, &bytes).unwrap();
+        let console = Console::new(true, false);
+        assert!(cmd_sign(&tampered, &new_key, true, &console).is_err());
+        
+        // A key that signed nothing is refused, without touching a package
+        assert!(rotate_keys(&other_key, &new_key, &packages, &console).is_err());
+        
+        let summary = rotate_keys(&old_key, &new_key, &packages, &console).unwrap();
+        assert_eq!(summary, RotationSummary { resigned: 2, unsigned: 1, failed: 1 });
+        let new_public_key = lxe_common::signing::inspect_key_file(&new_key).unwrap().public_key;
+        let info = lxe_common::payload::read_payload_info_unchecked(&packages.join("b.lxe")).unwrap();
+        assert_eq!(info.metadata.public_key.as_deref(), Some(new_public_key.as_str()));
+        assert!(matches!(inspect::signature_status(&info.metadata), inspect::SignatureStatus::Valid));
+        let bytes = fs::read(&flagged).unwrap();
+        assert_eq!(bytes[bytes.len() - lxe_common::payload::FOOTER_V3_SIZE as usize + 9], 0x80);
+    }
     
-    // Sign
-    use ed25519_dalek::Signer;
-    let signature = signing_key.sign(&signable_data);
+    #[test]
+    fn test_key_import_fingerprint_mismatch() {
+        use lxe_common::signing::LxeKeyPair;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("key.pub");
+        fs::write(&key, LxeKeyPair::generate().public_key_base64()).unwrap();
+        let source = KeySource::File(key);
+        let error = cmd_key_import(&source, "test", None, Some("SHA256:wrong"), None, &Console::new(true, false)).unwrap_err();
+        assert!(error.to_string().contains("Fingerprint mismatch"), "{:#}", error);
+    }
     
-    // Add to metadata
-    metadata["signature"] = serde_json::Value::String(
-        BASE64_STANDARD.encode(signature.to_bytes())
-    );
-    metadata["public_key"] = serde_json::Value::String(
-        BASE64_STANDARD.encode(signing_key.verifying_key().as_bytes())
-    );
+    #[test]
+    fn test_batch_key_path() {
+        assert_eq!(batch_key_path(std::path::Path::new("keys/agent.key"), 1), PathBuf::from("keys/agent-001.key"));
+        assert_eq!(batch_key_path(std::path::Path::new("agent"), 12), PathBuf::from("agent-012.key"));
+    }
     
-    Ok(())
+    #[test]
+    fn test_apply_meta_patch() {
+        let mut meta = serde_json::json!({
+            "name": "Old",
+            "version": "1.0.0",
+            "installer": { "show_launch": true }
+        });
+        
+        assert_eq!(apply_meta_patch(&mut meta, "name", "New Name").unwrap(), Some("Old".to_string()));
+        apply_meta_patch(&mut meta, "installer.show_launch", "false").unwrap();
+        apply_meta_patch(&mut meta, "version", "1.0.1").unwrap();
+        
+        assert_eq!(meta["name"], "New Name");
+        assert_eq!(meta["installer"]["show_launch"], false);
+        assert_eq!(meta["version"], "1.0.1");
+        assert!(apply_meta_patch(&mut meta, "payload_checksum", "00").is_err());
+    }
 }
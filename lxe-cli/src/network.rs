@@ -0,0 +1,346 @@
+//! Download Helpers
+//!
+//! Streams large downloads into a `.part` file next to their destination so
+//! an interrupted transfer can continue with an HTTP `Range` request (guarded
+//! by `If-Range`, so a file changed on the server is downloaded anew), and
+//! optionally caps the transfer rate for metered connections. Clients go
+//! through the proxy given with `--proxy`, or the one from `HTTP_PROXY` /
+//! `HTTPS_PROXY` (hosts in `NO_PROXY` are reached directly).
+
+use anyhow::{Context, Result};
+use reqwest::blocking::{Client, ClientBuilder};
+use reqwest::header::{HeaderMap, ACCEPT_RANGES, CONTENT_LENGTH, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::{NoProxy, Proxy, StatusCode};
+use indicatif::ProgressBar;
+use std::fs::{self, File, OpenOptions};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Partial downloads untouched for this long are deleted
+pub const STALE_PART_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Extension of in-progress downloads
+const PART_EXTENSION: &str = "part";
+
+/// Appended to a `.part` file's name for the file holding its validator
+const VALIDATOR_SUFFIX: &str = ".validator";
+
+/// Granularity of the rate limiter
+const THROTTLE_SLOT: Duration = Duration::from_millis(100);
+
+/// Reader that caps throughput at a fixed number of bytes per second
+/// 
+/// Time is split into 100 ms slots with a tenth of the per-second budget
+/// each; once a slot's budget is used up, reads sleep until the next slot.
+pub struct ThrottledReader<R> {
+    inner: R,
+    bytes_per_slot: u64,
+    slot_start: Instant,
+    bytes_this_slot: u64,
+}
+
+impl<R: Read> ThrottledReader<R> {
+    pub fn new(inner: R, bytes_per_second: u64) -> Self {
+        Self {
+            inner,
+            bytes_per_slot: (bytes_per_second / 10).max(1),
+            slot_start: Instant::now(),
+            bytes_this_slot: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for ThrottledReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let elapsed = self.slot_start.elapsed();
+        if elapsed >= THROTTLE_SLOT {
+            self.slot_start = Instant::now();
+            self.bytes_this_slot = 0;
+        } else if self.bytes_this_slot >= self.bytes_per_slot {
+            std::thread::sleep(THROTTLE_SLOT - elapsed);
+            self.slot_start = Instant::now();
+            self.bytes_this_slot = 0;
+        }
+        
+        let budget = (self.bytes_per_slot - self.bytes_this_slot) as usize;
+        let len = buf.len().min(budget);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.bytes_this_slot += n as u64;
+        Ok(n)
+    }
+}
+
+/// Proxy URLs for plain and TLS connections
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ProxySettings {
+    pub http: Option<String>,
+    pub https: Option<String>,
+}
+
+impl ProxySettings {
+    /// `--proxy` for both schemes, else the environment variables (upper
+    /// or lower case)
+    pub fn resolve(flag: Option<&str>, env: impl Fn(&str) -> Option<String>) -> Self {
+        if let Some(url) = flag {
+            return Self { http: Some(url.to_string()), https: Some(url.to_string()) };
+        }
+        let var = |name: &str| env(name)
+            .or_else(|| env(&name.to_lowercase()))
+            .filter(|value| !value.is_empty());
+        Self { http: var("HTTP_PROXY"), https: var("HTTPS_PROXY") }
+    }
+}
+
+/// Client builder with the LXE user agent and proxy settings
+pub fn client_builder(proxy: Option<&str>) -> Result<ClientBuilder> {
+    let settings = ProxySettings::resolve(proxy, |name| std::env::var(name).ok());
+    let mut builder = Client::builder()
+        .user_agent(format!("lxe-cli/{}", env!("CARGO_PKG_VERSION")));
+    if let Some(ref url) = settings.http {
+        let proxy = Proxy::http(url).with_context(|| format!("Invalid proxy URL: {}", url))?;
+        builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+    }
+    if let Some(ref url) = settings.https {
+        let proxy = Proxy::https(url).with_context(|| format!("Invalid proxy URL: {}", url))?;
+        builder = builder.proxy(proxy.no_proxy(NoProxy::from_env()));
+    }
+    Ok(builder)
+}
+
+/// Options for a single download
+#[derive(Default)]
+pub struct DownloadOptions<'a> {
+    /// Continue an existing `.part` file if the server accepts ranges
+    pub resume: bool,
+    
+    /// Speed cap in KiB/s
+    pub limit_kbps: Option<u64>,
+    
+    /// Progress bar to advance; its length is set once the size is known
+    pub progress: Option<&'a ProgressBar>,
+}
+
+/// Download `url` into `part_path`, continuing a previous partial download
+/// when `options.resume` is set and the server accepts byte ranges
+/// 
+/// The ETag (or Last-Modified date) of the response is kept next to the
+/// `.part` file and sent as `If-Range` when resuming, so bytes of an older
+/// version of the file are never combined with a newer one. A `.part` file
+/// without one is downloaded again.
+/// 
+/// Returns the number of bytes that were already on disk and reused.
+pub fn download_resumable(client: &Client, url: &str, part_path: &Path, options: &DownloadOptions) -> Result<u64> {
+    let validator_path = validator_path(part_path);
+    let validator = fs::read_to_string(&validator_path).ok();
+    let mut offset = match (fs::metadata(part_path), &validator) {
+        (Ok(meta), Some(_)) if options.resume => meta.len(),
+        _ => 0,
+    };
+    
+    if offset > 0 {
+        let head = client.head(url).send()
+            .and_then(|r| r.error_for_status())
+            .context("Failed to query download server")?;
+        
+        if !accepts_ranges(head.headers()) || range_validator(head.headers()) != validator {
+            offset = 0;
+        } else if content_length(head.headers()) == Some(offset) {
+            // Previous attempt finished but was never verified
+            return Ok(offset);
+        }
+    }
+    
+    let mut request = client.get(url);
+    if let (true, Some(validator)) = (offset > 0, &validator) {
+        request = request
+            .header(RANGE, format!("bytes={}-", offset))
+            .header(IF_RANGE, validator.as_str());
+    }
+    let mut response = request.send().context("Download failed")?;
+    
+    // A server may ignore the range (or the file changed) and send the whole body
+    if offset > 0 && response.status() != StatusCode::PARTIAL_CONTENT {
+        offset = 0;
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            response = client.get(url).send().context("Download failed")?;
+        }
+    }
+    if !response.status().is_success() {
+        anyhow::bail!("Download failed: HTTP {}", response.status());
+    }
+    
+    let mut file = if offset > 0 {
+        OpenOptions::new().append(true).open(part_path)?
+    } else {
+        match range_validator(response.headers()) {
+            Some(validator) => fs::write(&validator_path, validator)?,
+            None => remove_if_exists(&validator_path)?,
+        }
+        File::create(part_path)?
+    };
+    
+    let body = response_reader(response, offset, options.progress);
+    let mut reader: Box<dyn Read> = match options.limit_kbps {
+        Some(kbps) => Box::new(ThrottledReader::new(body, kbps * 1024)),
+        None => body,
+    };
+    std::io::copy(&mut reader, &mut file)
+        .context("Download interrupted (run the command again to resume)")?;
+    
+    if let Some(progress) = options.progress {
+        progress.finish();
+    }
+    
+    Ok(offset)
+}
+
+/// Remove the `.part` file of a download, along with its validator
+pub fn remove_part(part_path: &Path) -> Result<()> {
+    remove_if_exists(part_path)?;
+    remove_if_exists(&validator_path(part_path))?;
+    Ok(())
+}
+
+/// Remove `.part` files in `dir` that haven't been modified for `max_age`
+pub fn clean_stale_parts(dir: &Path, max_age: Duration) -> usize {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    
+    let now = SystemTime::now();
+    entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|e| e == PART_EXTENSION))
+        .filter(|path| {
+            fs::metadata(path)
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|modified| now.duration_since(modified).ok())
+                .is_some_and(|age| age >= max_age)
+        })
+        .filter(|path| remove_part(path).is_ok())
+        .count()
+}
+
+/// Where the validator of `part_path` is kept
+fn validator_path(part_path: &Path) -> PathBuf {
+    let mut name = part_path.as_os_str().to_owned();
+    name.push(VALIDATOR_SUFFIX);
+    PathBuf::from(name)
+}
+
+fn remove_if_exists(path: &Path) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Response body, reporting to `progress` if given
+fn response_reader(
+    response: reqwest::blocking::Response,
+    offset: u64,
+    progress: Option<&ProgressBar>,
+) -> Box<dyn Read> {
+    match progress {
+        Some(progress) => {
+            if let Some(len) = response.content_length() {
+                progress.set_length(offset + len);
+            }
+            progress.set_position(offset);
+            Box::new(progress.clone().wrap_read(response))
+        }
+        None => Box::new(response),
+    }
+}
+
+fn accepts_ranges(headers: &HeaderMap) -> bool {
+    headers.get(ACCEPT_RANGES)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|unit| unit.trim().eq_ignore_ascii_case("bytes")))
+}
+
+/// Value for `If-Range`: a strong ETag, else the Last-Modified date (weak
+/// ETags aren't allowed there)
+fn range_validator(headers: &HeaderMap) -> Option<String> {
+    let value = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    value(ETAG)
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| value(LAST_MODIFIED))
+        .map(str::to_string)
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_accepts_ranges() {
+        let mut headers = HeaderMap::new();
+        assert!(!accepts_ranges(&headers));
+        
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("none"));
+        assert!(!accepts_ranges(&headers));
+        
+        headers.insert(ACCEPT_RANGES, HeaderValue::from_static("Bytes"));
+        assert!(accepts_ranges(&headers));
+        
+        assert_eq!(range_validator(&headers), None);
+        headers.insert(LAST_MODIFIED, HeaderValue::from_static("Wed, 01 May 2024 12:00:00 GMT"));
+        headers.insert(ETAG, HeaderValue::from_static("W/\"abc\""));
+        assert_eq!(range_validator(&headers).as_deref(), Some("Wed, 01 May 2024 12:00:00 GMT"));
+        headers.insert(ETAG, HeaderValue::from_static("\"abc\""));
+        assert_eq!(range_validator(&headers).as_deref(), Some("\"abc\""));
+    }
+
+    #[test]
+    fn test_proxy_settings() {
+        let env = |name: &str| match name {
+            "https_proxy" => Some("http://proxy.corp:3128".to_string()),
+            "HTTP_PROXY" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(ProxySettings::resolve(None, env), ProxySettings {
+            http: None,
+            https: Some("http://proxy.corp:3128".into()),
+        });
+        let flag = ProxySettings::resolve(Some("http://other:8080"), env);
+        assert_eq!(flag.http.as_deref(), Some("http://other:8080"));
+        assert_eq!(flag.https, flag.http);
+    }
+
+    #[test]
+    fn test_throttled_reader() {
+        let data = vec![0u8; 3000];
+        let start = Instant::now();
+        let mut reader = ThrottledReader::new(&data[..], 10_000);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        
+        // 1000 bytes per slot: the third slot can't start before 200 ms
+        assert_eq!(out.len(), data.len());
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_clean_stale_parts() {
+        let dir = std::env::temp_dir().join(format!("lxe-parts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("runtime.tar.gz.part"), b"partial").unwrap();
+        fs::write(dir.join("runtime.tar.gz.part.validator"), b"\"abc\"").unwrap();
+        fs::write(dir.join("lxe-runtime"), b"binary").unwrap();
+        
+        assert_eq!(clean_stale_parts(&dir, STALE_PART_AGE), 0);
+        assert_eq!(clean_stale_parts(&dir, Duration::ZERO), 1);
+        assert!(dir.join("lxe-runtime").exists());
+        assert!(!dir.join("runtime.tar.gz.part.validator").exists());
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,197 @@
+//! GitHub Release Queries
+//!
+//! Lists published LXE runtime versions so users can pin a specific one.
+
+use anyhow::{Context, Result};
+
+/// GitHub API endpoint for LXE releases
+const RELEASES_API: &str = "https://api.github.com/repos/lxe-core/lxe/releases";
+
+/// Warn when fewer API requests than this remain in the rate-limit window
+const RATE_LIMIT_WARN_THRESHOLD: u32 = 10;
+
+/// A published runtime release
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeVersion {
+    /// Release tag (e.g., "v1.2.0")
+    pub tag: String,
+
+    /// ISO-8601 publish timestamp
+    pub published_at: String,
+
+    /// Whether this is a prerelease (or draft)
+    pub prerelease: bool,
+
+    /// Names of the release assets
+    pub assets: Vec<String>,
+}
+
+impl RuntimeVersion {
+    /// Parse one entry of the GitHub releases API response
+    pub fn from_release(json: &serde_json::Value) -> Option<RuntimeVersion> {
+        let tag = json.get("tag_name")?.as_str()?.to_string();
+        let published_at = json.get("published_at")
+            .and_then(|v| v.as_str())
+            .unwrap_or("unpublished")
+            .to_string();
+        let prerelease = json.get("prerelease").and_then(|v| v.as_bool()).unwrap_or(false)
+            || json.get("draft").and_then(|v| v.as_bool()).unwrap_or(false);
+        let assets = json.get("assets")
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter()
+                .filter_map(|a| a.get("name")?.as_str().map(String::from))
+                .collect())
+            .unwrap_or_default();
+
+        Some(RuntimeVersion { tag, published_at, prerelease, assets })
+    }
+
+    /// Version without the leading "v"
+    pub fn version(&self) -> &str {
+        self.tag.trim_start_matches('v')
+    }
+
+    /// Publish date only (YYYY-MM-DD)
+    pub fn date(&self) -> &str {
+        self.published_at.split('T').next().unwrap_or(&self.published_at)
+    }
+
+    /// Architectures with a runtime tarball in this release
+    pub fn architectures(&self) -> Vec<&str> {
+        ["x86_64", "aarch64"]
+            .into_iter()
+            .filter(|arch| self.assets.iter().any(|a| *a == runtime_asset_name(arch)))
+            .collect()
+    }
+}
+
+/// File name of the runtime tarball for an architecture
+pub fn runtime_asset_name(arch: &str) -> String {
+    format!("lxe-runtime-{}-linux.tar.gz", arch)
+}
+
+/// Releases per API request at most
+const MAX_PAGE_SIZE: usize = 100;
+
+/// API requests per listing at most, so a run of prereleases can't use up
+/// the rate limit
+const MAX_PAGES: usize = 5;
+
+/// Fetch up to `limit` releases, newest first; prereleases only count (and
+/// are only returned) with `include_prerelease`
+///
+/// Returns the releases and the remaining API rate limit (if reported).
+pub fn fetch_runtime_versions(limit: usize, include_prerelease: bool, proxy: Option<&str>) -> Result<(Vec<RuntimeVersion>, Option<u32>)> {
+    let client = crate::network::client_builder(proxy)?.build()?;
+    // Filtering needs more releases than it keeps
+    let per_page = if include_prerelease { limit.clamp(1, MAX_PAGE_SIZE) } else { MAX_PAGE_SIZE };
+    collect_versions(limit, include_prerelease, per_page, |page| fetch_page(&client, per_page, page))
+}
+
+/// Take releases from consecutive pages until `limit` are kept or the pages run out
+fn collect_versions(
+    limit: usize,
+    include_prerelease: bool,
+    per_page: usize,
+    mut fetch: impl FnMut(usize) -> Result<(Vec<serde_json::Value>, Option<u32>)>,
+) -> Result<(Vec<RuntimeVersion>, Option<u32>)> {
+    let mut versions = Vec::new();
+    let mut remaining = None;
+    for page in 1..=MAX_PAGES {
+        let (releases, left) = fetch(page)?;
+        remaining = left.or(remaining);
+        versions.extend(releases.iter()
+            .filter_map(RuntimeVersion::from_release)
+            .filter(|v| include_prerelease || !v.prerelease));
+        if versions.len() >= limit || releases.len() < per_page {
+            break;
+        }
+    }
+    versions.truncate(limit);
+    Ok((versions, remaining))
+}
+
+/// One page of the releases API, with the remaining rate limit
+fn fetch_page(client: &reqwest::blocking::Client, per_page: usize, page: usize) -> Result<(Vec<serde_json::Value>, Option<u32>)> {
+    let response = client.get(RELEASES_API)
+        .query(&[("per_page", per_page), ("page", page)])
+        .header("Accept", "application/vnd.github+json")
+        .send()
+        .context("Failed to query GitHub releases")?;
+
+    let remaining = response.headers()
+        .get("X-RateLimit-Remaining")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u32>().ok());
+
+    if response.status() == reqwest::StatusCode::FORBIDDEN && remaining == Some(0) {
+        anyhow::bail!(
+            "GitHub API rate limit exceeded.\n\
+             Unauthenticated requests are limited to 60 per hour - try again later."
+        );
+    }
+    if !response.status().is_success() {
+        anyhow::bail!("GitHub API request failed: HTTP {}", response.status());
+    }
+
+    let json: serde_json::Value = response.json()
+        .context("Invalid response from GitHub API")?;
+    let releases = match json {
+        serde_json::Value::Array(releases) => releases,
+        _ => Vec::new(),
+    };
+    Ok((releases, remaining))
+}
+
+/// Whether the remaining rate limit is low enough to warn about
+pub fn rate_limit_low(remaining: Option<u32>) -> bool {
+    remaining.is_some_and(|r| r < RATE_LIMIT_WARN_THRESHOLD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_release() {
+        let json = serde_json::json!({
+            "tag_name": "v1.2.0",
+            "published_at": "2025-03-01T12:00:00Z",
+            "prerelease": false,
+            "draft": false,
+            "assets": [
+                { "name": "lxe-runtime-x86_64-linux.tar.gz" },
+                { "name": "lxe-runtime-x86_64-linux.tar.gz.sha256" },
+                { "name": "lxe-x86_64-linux.tar.gz" }
+            ]
+        });
+
+        let version = RuntimeVersion::from_release(&json).unwrap();
+        assert_eq!(version.version(), "1.2.0");
+        assert_eq!(version.date(), "2025-03-01");
+        assert!(!version.prerelease);
+        assert_eq!(version.architectures(), vec!["x86_64"]);
+
+        assert!(RuntimeVersion::from_release(&serde_json::json!({})).is_none());
+    }
+
+    #[test]
+    fn test_collect_versions_skips_prereleases() {
+        let release = |n: usize| serde_json::json!({
+            "tag_name": format!("v1.{}.0", n),
+            "published_at": "2025-03-01T12:00:00Z",
+            "prerelease": !n.is_multiple_of(3),
+            "assets": []
+        });
+        let mut requested = Vec::new();
+        // 7 releases, 3 per page; only v1.0.0, v1.3.0 and v1.6.0 are stable
+        let (versions, remaining) = collect_versions(3, false, 3, |page| {
+            requested.push(page);
+            let releases: Vec<_> = ((page - 1) * 3..(page * 3).min(7)).map(release).collect();
+            Ok((releases, Some(50 - page as u32)))
+        }).unwrap();
+        assert_eq!(versions.iter().map(|v| v.version()).collect::<Vec<_>>(), ["1.0.0", "1.3.0", "1.6.0"]);
+        assert_eq!(requested, [1, 2, 3]);
+        assert_eq!(remaining, Some(47));
+    }
+}
@@ -0,0 +1,500 @@
+//! Local Package Repositories
+//!
+//! A repository is a plain directory that can be copied to an air-gapped
+//! network and served over HTTP:
+//!
+//! ```text
+//! my-repo/
+//! ├── repo.toml      # repository name and description
+//! ├── index.json     # package index (what clients download first)
+//! └── packages/      # the .lxe files
+//! ```
+//!
+//! Clients point `registry` in `~/.config/lxe/config.toml` at the served URL
+//! and resolve packages through `index.json`; package URLs in the index are
+//! relative to the repository root.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Repository settings file
+pub const REPO_FILE: &str = "repo.toml";
+
+/// Package index file
+pub const INDEX_FILE: &str = "index.json";
+
+/// Directory holding the packages
+pub const PACKAGES_DIR: &str = "packages";
+
+/// Current index format version
+pub const INDEX_VERSION: u32 = 1;
+
+/// Contents of `repo.toml`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoConfig {
+    pub repository: RepoSection,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoSection {
+    /// Display name of the repository
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Contents of `index.json`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RepoIndex {
+    /// Index format version
+    pub version: u32,
+
+    /// Repository name (copied from repo.toml)
+    pub name: String,
+
+    /// Unix timestamp of the last update
+    pub updated_at: u64,
+
+    pub packages: Vec<RepoEntry>,
+}
+
+/// One package in the index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoEntry {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub arch: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Path relative to the repository root (e.g., "packages/app_1.0.0_x86_64.lxe")
+    pub file: String,
+
+    /// File size in bytes
+    pub size: u64,
+
+    /// SHA256 of the whole .lxe file (only with --generate-sha256)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+
+    /// Whether the package carries a signature
+    pub signed: bool,
+}
+
+/// A repository directory on disk
+pub struct Repository {
+    root: PathBuf,
+    config: RepoConfig,
+}
+
+impl Repository {
+    /// Create a new repository in `dir`
+    pub fn init(dir: &Path, name: &str) -> Result<Repository> {
+        if dir.join(REPO_FILE).exists() {
+            anyhow::bail!("{} already contains a repository", dir.display());
+        }
+        fs::create_dir_all(dir.join(PACKAGES_DIR))
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let config = RepoConfig {
+            repository: RepoSection { name: name.to_string(), description: None },
+        };
+        fs::write(dir.join(REPO_FILE), toml::to_string_pretty(&config)?)?;
+
+        let repo = Repository { root: dir.to_path_buf(), config };
+        repo.save_index(&mut RepoIndex::default())?;
+        Ok(repo)
+    }
+
+    /// Open an existing repository
+    pub fn open(dir: &Path) -> Result<Repository> {
+        let config_path = dir.join(REPO_FILE);
+        let content = fs::read_to_string(&config_path).with_context(|| {
+            format!(
+                "No {} found in {}\nCreate a repository with: lxe repo init {}",
+                REPO_FILE,
+                dir.display(),
+                dir.display()
+            )
+        })?;
+        let config: RepoConfig = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", config_path.display()))?;
+
+        Ok(Repository { root: dir.to_path_buf(), config })
+    }
+
+    /// Repository root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Load `index.json` (empty if missing)
+    pub fn load_index(&self) -> Result<RepoIndex> {
+        let path = self.root.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(RepoIndex::default());
+        }
+        let content = fs::read(&path)?;
+        serde_json::from_slice(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write `index.json` atomically
+    pub fn save_index(&self, index: &mut RepoIndex) -> Result<()> {
+        index.version = INDEX_VERSION;
+        index.name = self.config.repository.name.clone();
+        index.updated_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        index.packages.sort_by(|a, b| (&a.id, &a.version, &a.arch).cmp(&(&b.id, &b.version, &b.arch)));
+
+        let path = self.root.join(INDEX_FILE);
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(index)?)?;
+        fs::rename(&tmp, &path).context("Failed to update index")
+    }
+
+    /// Copy a package into the repository and index it
+    ///
+    /// Returns the new entry and whether it replaced an existing one.
+    pub fn add(&self, file: &Path, generate_sha256: bool) -> Result<(RepoEntry, bool)> {
+        let info = lxe_common::payload::read_payload_info_unchecked(file)
+            .with_context(|| format!("{} is not a valid LXE package", file.display()))?;
+        let meta = &info.metadata;
+
+        let file_name = package_file_name(&meta.app_id, &meta.version, &meta.arch)?;
+        let packages = self.root.join(PACKAGES_DIR);
+        let dest = packages.join(&file_name);
+        if dest.parent() != Some(packages.as_path()) {
+            anyhow::bail!("Package file name {} leaves {}", file_name, packages.display());
+        }
+        fs::copy(file, &dest)
+            .with_context(|| format!("Failed to copy package to {}", dest.display()))?;
+
+        let entry = self.entry_for(&dest, generate_sha256)?;
+
+        let mut index = self.load_index()?;
+        let before = index.packages.len();
+        index.packages.retain(|e| e.file != entry.file);
+        let replaced = index.packages.len() != before;
+        index.packages.push(entry.clone());
+        self.save_index(&mut index)?;
+
+        Ok((entry, replaced))
+    }
+
+    /// Remove every architecture of `id` at `version`
+    pub fn remove(&self, id: &str, version: &str) -> Result<Vec<RepoEntry>> {
+        let mut index = self.load_index()?;
+        let (removed, kept): (Vec<RepoEntry>, Vec<RepoEntry>) = index.packages
+            .drain(..)
+            .partition(|e| e.id == id && e.version == version);
+
+        if removed.is_empty() {
+            anyhow::bail!("{} {} is not in the repository", id, version);
+        }
+
+        for entry in &removed {
+            let path = self.root.join(&entry.file);
+            if path.exists() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to delete {}", path.display()))?;
+            }
+        }
+
+        index.packages = kept;
+        self.save_index(&mut index)?;
+        Ok(removed)
+    }
+
+    /// Rebuild the index from the files in `packages/`
+    ///
+    /// Without `generate_sha256`, hashes of unchanged files are carried over.
+    pub fn reindex(&self, generate_sha256: bool) -> Result<RepoIndex> {
+        let old = self.load_index()?;
+        let mut index = RepoIndex::default();
+
+        let mut files: Vec<PathBuf> = fs::read_dir(self.root.join(PACKAGES_DIR))?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|ext| ext == "lxe"))
+            .collect();
+        files.sort();
+
+        for path in files {
+            let mut entry = match self.entry_for(&path, generate_sha256) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("⚠️  Skipping {}: {:#}", path.display(), e);
+                    continue;
+                }
+            };
+            if entry.sha256.is_none() {
+                entry.sha256 = old.packages.iter()
+                    .find(|o| o.file == entry.file && o.size == entry.size)
+                    .and_then(|o| o.sha256.clone());
+            }
+            index.packages.push(entry);
+        }
+
+        self.save_index(&mut index)?;
+        Ok(index)
+    }
+
+    /// Build an index entry for a package inside the repository
+    fn entry_for(&self, path: &Path, generate_sha256: bool) -> Result<RepoEntry> {
+        let info = lxe_common::payload::read_payload_info_unchecked(path)?;
+        let meta = info.metadata;
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+
+        Ok(RepoEntry {
+            id: meta.app_id,
+            name: meta.name,
+            version: meta.version,
+            arch: meta.arch,
+            description: meta.description,
+            file: relative.to_string_lossy().replace('\\', "/"),
+            size: fs::metadata(path)?.len(),
+            sha256: if generate_sha256 { Some(sha256_file(path)?) } else { None },
+            signed: meta.signature.is_some(),
+        })
+    }
+}
+
+/// `<app_id>_<version>_<arch>.lxe`, the name of a package in `packages/`
+///
+/// The parts come from the package's (unsigned) metadata, so they are
+/// checked before they end up in a path.
+fn package_file_name(app_id: &str, version: &str, arch: &str) -> Result<String> {
+    if !crate::conformance::is_reverse_dns(app_id) {
+        anyhow::bail!("Invalid app ID '{}': expected reverse-DNS like com.example.App", app_id);
+    }
+    let valid = |part: &str| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || "._+~-".contains(c))
+    };
+    if !valid(version) {
+        anyhow::bail!("Invalid version '{}': only letters, digits and . _ + ~ - are allowed", version);
+    }
+    if !valid(arch) {
+        anyhow::bail!("Invalid architecture '{}': only letters, digits and . _ + ~ - are allowed", arch);
+    }
+    Ok(format!("{}_{}_{}.lxe", app_id, version, arch))
+}
+
+/// Download a repository's `index.json`; `url` is the repository root
+pub fn fetch_index(client: &reqwest::blocking::Client, url: &str) -> Result<RepoIndex> {
+    let response = client.get(format!("{}/{}", url.trim_end_matches('/'), INDEX_FILE))
+        .send()
+        .context("Registry unreachable")?;
+    if !response.status().is_success() {
+        anyhow::bail!("HTTP {}", response.status());
+    }
+    response.json().context("Invalid package index")
+}
+
+/// Newest version of each package whose ID or name contains `query`
+/// (case-insensitive), sorted by ID
+pub fn search<'a>(index: &'a RepoIndex, query: &str) -> Vec<&'a RepoEntry> {
+    let query = query.to_lowercase();
+    let mut newest: Vec<&RepoEntry> = Vec::new();
+    let matches = index.packages.iter()
+        .filter(|e| e.id.to_lowercase().contains(&query) || e.name.to_lowercase().contains(&query));
+    for entry in matches {
+        match newest.iter_mut().find(|n| n.id == entry.id) {
+            Some(n) if crate::updates::is_newer(&entry.version, &n.version) => *n = entry,
+            Some(_) => {}
+            None => newest.push(entry),
+        }
+    }
+    newest.sort_by(|a, b| a.id.cmp(&b.id));
+    newest
+}
+
+/// SHA256 of a file, streamed
+pub(crate) fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Serve the repository over HTTP until interrupted
+///
+/// Only GET/HEAD of `index.json` and files under `packages/` are answered.
+pub fn serve(repo: &Repository, port: u16) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .context("Failed to start async runtime")?;
+
+    let root = repo.root().to_path_buf();
+    runtime.block_on(async move {
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", port))
+            .await
+            .with_context(|| format!("Failed to bind port {}", port))?;
+
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let root = root.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_connection(stream, &root).await {
+                    eprintln!("⚠️  Request failed: {}", e);
+                }
+            });
+        }
+    })
+}
+
+/// Answer a single HTTP/1.0-style request
+async fn handle_connection(stream: tokio::net::TcpStream, root: &Path) -> std::io::Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+
+    // Drain headers
+    let mut header = String::new();
+    while reader.read_line(&mut header).await? > 2 {
+        header.clear();
+    }
+
+    let mut stream = reader.into_inner();
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+
+    if method != "GET" && method != "HEAD" {
+        return write_status(&mut stream, "405 Method Not Allowed").await;
+    }
+
+    let Some(path) = resolve_request_path(root, target) else {
+        return write_status(&mut stream, "404 Not Found").await;
+    };
+    let Ok(mut file) = tokio::fs::File::open(&path).await else {
+        return write_status(&mut stream, "404 Not Found").await;
+    };
+
+    let size = file.metadata().await?.len();
+    let content_type = if path.ends_with(INDEX_FILE) {
+        "application/json"
+    } else {
+        "application/octet-stream"
+    };
+    let head = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        content_type, size
+    );
+    stream.write_all(head.as_bytes()).await?;
+    if method == "GET" {
+        tokio::io::copy(&mut file, &mut stream).await?;
+    }
+    stream.shutdown().await
+}
+
+async fn write_status(stream: &mut tokio::net::TcpStream, status: &str) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    let response = format!("HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+/// Map a request target to a file in the repository, refusing anything
+/// outside `index.json` and `packages/`
+fn resolve_request_path(root: &Path, target: &str) -> Option<PathBuf> {
+    let path = target.split(['?', '#']).next()?.trim_start_matches('/');
+
+    if path.is_empty() || path == INDEX_FILE {
+        return Some(root.join(INDEX_FILE));
+    }
+
+    let name = path.strip_prefix(PACKAGES_DIR)?.strip_prefix('/')?;
+    if name.is_empty() || name.contains('/') || name.contains("..") || name.contains('%') {
+        return None;
+    }
+    Some(root.join(PACKAGES_DIR).join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_request_path() {
+        let root = Path::new("/srv/repo");
+        assert_eq!(resolve_request_path(root, "/"), Some(root.join("index.json")));
+        assert_eq!(resolve_request_path(root, "/index.json?t=1"), Some(root.join("index.json")));
+        assert_eq!(
+            resolve_request_path(root, "/packages/app_1.0.0_x86_64.lxe"),
+            Some(root.join("packages/app_1.0.0_x86_64.lxe"))
+        );
+        assert_eq!(resolve_request_path(root, "/packages/../repo.toml"), None);
+        assert_eq!(resolve_request_path(root, "/repo.toml"), None);
+    }
+
+    #[test]
+    fn test_package_file_name() {
+        assert_eq!(package_file_name("com.example.App", "1.0.0-rc1+b2", "x86_64").unwrap(), "com.example.App_1.0.0-rc1+b2_x86_64.lxe");
+        assert!(package_file_name("../../etc", "1.0", "x86_64").is_err());
+        assert!(package_file_name("com.example.App", "../../../x", "x86_64").is_err());
+        assert!(package_file_name("com.example.App", "1.0", "a/b").is_err());
+        assert!(package_file_name("com.example.App", "..", "x86_64").is_err());
+        assert!(package_file_name("com.example.App", "1.0", "").is_err());
+    }
+
+    #[test]
+    fn test_init_and_open() {
+        let dir = std::env::temp_dir().join(format!("lxe-repo-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        Repository::init(&dir, "Test Repo").unwrap();
+        assert!(dir.join(PACKAGES_DIR).is_dir());
+        assert!(Repository::init(&dir, "Again").is_err());
+
+        let repo = Repository::open(&dir).unwrap();
+        let index = repo.load_index().unwrap();
+        assert_eq!(index.name, "Test Repo");
+        assert!(index.packages.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_search() {
+        let entry = |id: &str, name: &str, version: &str| RepoEntry {
+            id: id.into(),
+            name: name.into(),
+            version: version.into(),
+            arch: "x86_64".into(),
+            description: None,
+            file: format!("packages/{}_{}.lxe", id, version),
+            size: 0,
+            sha256: None,
+            signed: false,
+        };
+        let index = RepoIndex {
+            packages: vec![
+                entry("org.example.Editor", "Text Editor", "1.2.0"),
+                entry("org.example.Editor", "Text Editor", "1.10.0"),
+                entry("org.example.Viewer", "Image Viewer", "2.0.0"),
+            ],
+            ..Default::default()
+        };
+
+        let found = search(&index, "EDITOR");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].version, "1.10.0");
+        assert_eq!(search(&index, "image").len(), 1);
+        assert_eq!(search(&index, "org.example").len(), 2);
+        assert!(search(&index, "browser").is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! Build Report (`lxe build --report`)
+//!
+//! Numbers `lxe build` prints, as a JSON file CI can keep as an artifact to
+//! chart package size over time:
+//!
+//! ```text
+//! {"output_path":"MyApp.lxe","format_version":3,"input":{"file_count":42,...},...}
+//! ```
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Statistics of one build, collected while `lxe build` runs
+#[derive(Debug, Default, Serialize)]
+pub struct BuildReport {
+    pub output_path: PathBuf,
+    /// Package footer version
+    pub format_version: u8,
+    pub input: InputStats,
+    pub compression: CompressionStats,
+    pub signing: SigningStats,
+    pub sections: SectionSizes,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct InputStats {
+    /// Regular files in the input directory
+    pub file_count: u64,
+    /// Size of the tar archive
+    pub uncompressed_bytes: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompressionStats {
+    pub compressor: String,
+    pub compressed_bytes: u64,
+    /// Uncompressed / compressed
+    pub ratio: f64,
+    /// None when the payload of an interrupted build was reused
+    pub seconds: Option<f64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct SigningStats {
+    pub signed: bool,
+    pub public_key_fingerprint: Option<String>,
+}
+
+/// Bytes of each part of the package file
+#[derive(Debug, Default, Serialize)]
+pub struct SectionSizes {
+    pub runtime: u64,
+    pub metadata: u64,
+    pub payload: u64,
+    /// Whole file, including magic, checksum and footer
+    pub total: u64,
+}
+
+impl BuildReport {
+    pub fn set_compression(&mut self, compressed_bytes: u64, seconds: Option<f64>) {
+        self.compression.compressed_bytes = compressed_bytes;
+        self.compression.ratio = if compressed_bytes == 0 {
+            0.0
+        } else {
+            self.input.uncompressed_bytes as f64 / compressed_bytes as f64
+        };
+        self.compression.seconds = seconds;
+    }
+
+    /// Take the signing info from the package metadata
+    pub fn set_signing(&mut self, metadata: &serde_json::Value) {
+        self.signing.signed = metadata.get("signature").is_some();
+        self.signing.public_key_fingerprint = metadata.get("public_key")
+            .and_then(|key| key.as_str())
+            .and_then(|key| lxe_common::signing::public_key_fingerprint(key).ok());
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Regular files under `dir` (symlinks are not followed)
+pub fn count_files(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .count() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_report() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("lib")).unwrap();
+        std::fs::write(dir.path().join("app"), b"binary").unwrap();
+        std::fs::write(dir.path().join("lib/data"), b"data").unwrap();
+        std::os::unix::fs::symlink("app", dir.path().join("link")).unwrap();
+        assert_eq!(count_files(dir.path()), 2);
+
+        let mut report = BuildReport::default();
+        report.input.uncompressed_bytes = 1000;
+        report.set_compression(250, Some(0.5));
+        assert_eq!(report.compression.ratio, 4.0);
+        report.set_signing(&serde_json::json!({ "app_id": "com.example.App" }));
+        assert!(!report.signing.signed);
+
+        let path = dir.path().join("App.lxe.report.json");
+        report.write(&path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["compression"]["compressed_bytes"], 250);
+        assert_eq!(json["signing"]["public_key_fingerprint"], serde_json::Value::Null);
+    }
+}
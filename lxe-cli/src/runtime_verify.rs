@@ -0,0 +1,156 @@
+//! Runtime Verification (`lxe build --verify-runtime`)
+//!
+//! Whatever `[runtime] path` points to is prepended to the package verbatim,
+//! so a wrong file there produces a package that doesn't install. Every build
+//! runs the lightweight check (ELF header and the `lxe-runtime/<version>`
+//! marker compiled into the runtime) to report the runtime version;
+//! `--verify-runtime` also runs the binary with `--version` and enforces
+//! `[runtime] min_version`.
+
+use crate::validate::{elf_arch, elf_machine};
+use anyhow::{bail, Context, Result};
+use lxe_common::requirements::{normalize_version, version_satisfies};
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Prefix of the version marker in runtime binaries
+const VERSION_MARKER: &[u8] = b"lxe-runtime/";
+
+/// How long `<runtime> --version` may take
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a runtime binary says about itself
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeInfo {
+    /// "unknown" for runtimes built before the version marker
+    pub version: String,
+    pub arch: String,
+    /// Runtimes don't record one yet
+    pub build_date: Option<String>,
+}
+
+/// ELF header and embedded strings of a runtime, without running it
+pub fn inspect_runtime(data: &[u8]) -> Result<RuntimeInfo> {
+    if data.len() < 20 || data[0..4] != *b"\x7fELF" {
+        bail!("Runtime is not an ELF binary");
+    }
+    if find(data, b"lxe-runtime").is_none() {
+        bail!("Runtime doesn't contain the 'lxe-runtime' identifier - is it an LXE runtime?");
+    }
+
+    let version = find(data, VERSION_MARKER)
+        .map(|at| {
+            let rest = &data[at + VERSION_MARKER.len()..];
+            let end = rest.iter()
+                .position(|b| !(b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-' | b'+')))
+                .unwrap_or(rest.len());
+            String::from_utf8_lossy(&rest[..end]).into_owned()
+        })
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    Ok(RuntimeInfo {
+        version,
+        arch: elf_machine(data).and_then(elf_arch).unwrap_or("unknown").to_string(),
+        build_date: None,
+    })
+}
+
+/// Full check: the lightweight one, then `<runtime> --version` and the
+/// `min_version` requirement
+pub fn verify_runtime_binary(path: &Path, min_version: Option<&str>) -> Result<RuntimeInfo> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("Failed to read runtime: {}", path.display()))?;
+    let mut info = inspect_runtime(&data)
+        .with_context(|| format!("{} failed verification", path.display()))?;
+
+    let output = run_with_timeout(path, "--version", VERSION_TIMEOUT)?;
+    let reported = parse_version_output(&output).with_context(|| {
+        format!("Unexpected '{} --version' output: {}", path.display(), output.trim())
+    })?;
+    if info.version != "unknown" && info.version != reported {
+        bail!(
+            "Runtime reports version {} but was built as {} - the file may be corrupt",
+            reported, info.version
+        );
+    }
+    info.version = reported;
+
+    if let Some(min_version) = min_version {
+        if !version_satisfies(min_version, &info.version) {
+            bail!(
+                "Runtime {} is older than [runtime] min_version = \"{}\"\n\
+                 Run 'lxe runtime upgrade' to install a newer runtime",
+                info.version, min_version
+            );
+        }
+    }
+    Ok(info)
+}
+
+/// The version in clap's `lxe-runtime 1.2.0` output
+fn parse_version_output(output: &str) -> Option<String> {
+    let version = output.lines().next()?.trim().strip_prefix("lxe-runtime ")?.trim();
+    normalize_version(version)?;
+    Some(version.to_string())
+}
+
+/// Stdout of `program arg`, killing it after `timeout`
+fn run_with_timeout(program: &Path, arg: &str, timeout: Duration) -> Result<String> {
+    let mut child = Command::new(program)
+        .arg(arg)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() > timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("'{} {}' didn't finish within {}s", program.display(), arg, timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    if !status.success() {
+        bail!("'{} {}' failed with exit code: {:?}", program.display(), arg, status.code());
+    }
+
+    let mut output = String::new();
+    if let Some(mut stdout) = child.stdout.take() {
+        stdout.read_to_string(&mut output)?;
+    }
+    Ok(output)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_runtime() {
+        let mut runtime = vec![0u8; 64];
+        runtime[0..4].copy_from_slice(b"\x7fELF");
+        runtime[18..20].copy_from_slice(&0x3Eu16.to_le_bytes());
+        assert!(inspect_runtime(&runtime).is_err());
+
+        runtime.extend_from_slice(b"\0lxe-runtime/1.4.2\0");
+        let info = inspect_runtime(&runtime).unwrap();
+        assert_eq!((info.version.as_str(), info.arch.as_str()), ("1.4.2", "x86_64"));
+        assert!(inspect_runtime(b"#!/bin/sh\necho lxe-runtime").is_err());
+
+        assert_eq!(parse_version_output("lxe-runtime 1.4.2\n"), Some("1.4.2".to_string()));
+        assert_eq!(parse_version_output("bash 5.2"), None);
+    }
+}
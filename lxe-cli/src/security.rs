@@ -0,0 +1,300 @@
+//! Static Security Scan (`lxe security-scan`)
+//!
+//! Screens a package before deployment without installing or running it.
+//! The payload is decompressed as a stream and only the tar headers (and,
+//! at `--level full`, the contents of ELF files) are inspected; nothing is
+//! written to disk.
+//!
+//! Findings are heuristics: a `high` finding is not proof of malice, but
+//! should be explained by the publisher before the package is deployed.
+
+use crate::inspect::SignatureStatus;
+use anyhow::{Context, Result};
+use lxe_common::builder;
+use lxe_common::payload;
+use lxe_common::payload_format::find_elf_section;
+use std::fs::File;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// How much of the payload is inspected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ScanLevel {
+    /// Tar headers and metadata (permissions, hooks, desktop entries)
+    Basic,
+    /// Basic plus the RPATH/RUNPATH of every ELF file (reads their contents)
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // `pad` so callers can align the column
+        f.pad(match self {
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+        })
+    }
+}
+
+/// One suspicious property of the package
+#[derive(Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub check: &'static str,
+    /// Payload entry the finding is about, if any
+    pub path: Option<PathBuf>,
+    pub detail: String,
+}
+
+/// Extensions of files run by an interpreter
+const SCRIPT_EXTENSIONS: &[&str] = &["sh", "bash", "zsh", "py", "pl", "rb"];
+
+/// Library directories the dynamic loader searches anyway
+const SYSTEM_LIBRARY_DIRS: &[&str] = &["/lib", "/lib64", "/usr/lib", "/usr/lib64", "/usr/local/lib"];
+
+/// Anyone can write here, so libraries loaded from them can be planted
+const WRITABLE_DIRS: &[&str] = &["/tmp", "/var/tmp", "/dev/shm", "/home"];
+
+/// ELF files larger than this are not read for RPATH analysis
+const MAX_ELF_SCAN_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Scan `path`, most severe findings first
+pub fn scan(path: &Path, level: ScanLevel) -> Result<Vec<Finding>> {
+    let info = payload::read_payload_info_unchecked(path)
+        .with_context(|| format!("Failed to read package: {}", path.display()))?;
+    let mut findings = Vec::new();
+
+    findings.extend(signature_finding(&crate::inspect::signature_status(&info.metadata)));
+    let layout = payload::read_layout(path)?;
+    let metadata = serde_json::to_value(&info.metadata)?;
+    let checksum = crate::conformance::check_checksum(path, &layout, Some(&metadata));
+    if let Err(ref detail) = checksum {
+        findings.push(Finding {
+            severity: Severity::High,
+            check: "checksum",
+            path: None,
+            detail: format!("Payload doesn't match its checksum, it was modified or is corrupted: {}", detail),
+        });
+    }
+    if let Some(ref hooks) = info.metadata.hooks {
+        let scripts = [
+            ("pre_install", &hooks.pre_install),
+            ("post_install", &hooks.post_install),
+            ("pre_uninstall", &hooks.pre_uninstall),
+            ("post_uninstall", &hooks.post_uninstall),
+        ];
+        for (name, script) in scripts.into_iter().filter_map(|(name, s)| Some((name, s.as_ref()?))) {
+            findings.push(Finding {
+                severity: Severity::Medium,
+                check: "hook",
+                path: None,
+                detail: format!("{} runs a shell script:\n{}", name, script.trim_end()),
+            });
+        }
+    }
+
+    if let Err(e) = scan_payload(path, &info, level, &mut findings) {
+        // A modified payload may not even unpack; that's part of the finding
+        if checksum.is_ok() {
+            return Err(e);
+        }
+        findings.push(Finding {
+            severity: Severity::High,
+            check: "payload",
+            path: None,
+            detail: format!("Payload can't be read: {:#}", e),
+        });
+    }
+
+    findings.sort_by_key(|f| std::cmp::Reverse(f.severity));
+    Ok(findings)
+}
+
+/// Checks of the payload's files
+fn scan_payload(path: &Path, info: &payload::PayloadInfo, level: ScanLevel, findings: &mut Vec<Finding>) -> Result<()> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(info.payload_offset))?;
+    let decoder = builder::decoder(file.take(info.payload_size), &info.metadata.compressor)?;
+    let mut archive = tar::Archive::new(decoder);
+    for entry in archive.entries().context("Failed to read payload")? {
+        let mut entry = entry.context("Corrupt tar entry in payload")?;
+        let entry_path = entry.path()?.to_path_buf();
+        let header = entry.header();
+        let mode = header.mode()?;
+        let size = header.size()?;
+        if !header.entry_type().is_file() {
+            continue;
+        }
+
+        // The magic is enough for the header checks; full scans need the whole file
+        let mut contents = Vec::new();
+        entry.by_ref().take(4).read_to_end(&mut contents)?;
+        let is_elf = contents == b"\x7fELF";
+        findings.extend(check_entry(&entry_path, mode, is_elf));
+
+        if is_elf && level == ScanLevel::Full && size <= MAX_ELF_SCAN_SIZE {
+            entry.read_to_end(&mut contents)?;
+            for (tag, search_path) in read_library_paths(&contents) {
+                if let Some((severity, reason)) = classify_library_path(&search_path) {
+                    findings.push(Finding {
+                        severity,
+                        check: "rpath",
+                        path: Some(entry_path.clone()),
+                        detail: format!("{} '{}' {}", tag, search_path, reason),
+                    });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What the signature check says about the publisher
+fn signature_finding(status: &SignatureStatus) -> Option<Finding> {
+    let (severity, detail) = match status {
+        SignatureStatus::Valid => return None,
+        SignatureStatus::Unsigned => (Severity::Low, "Package is not signed; its publisher can't be verified".to_string()),
+        SignatureStatus::Invalid(reason) => (Severity::High, format!("Signature is invalid ({}); the package was tampered with", reason)),
+    };
+    Some(Finding { severity, check: "signature", path: None, detail })
+}
+
+/// Permission and location checks of one payload file
+fn check_entry(path: &Path, mode: u32, is_elf: bool) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let finding = |severity, check, detail: String| Finding {
+        severity,
+        check,
+        path: Some(path.to_path_buf()),
+        detail,
+    };
+
+    for (bit, name) in [(0o4000, "setuid"), (0o2000, "setgid")] {
+        if mode & bit != 0 {
+            let (severity, kind) = if is_elf { (Severity::High, "ELF binary") } else { (Severity::Medium, "file") };
+            findings.push(finding(severity, "permissions", format!("{} is {} (mode {:o})", kind, name, mode & 0o7777)));
+        }
+    }
+
+    let is_script = path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| SCRIPT_EXTENSIONS.contains(&ext));
+    if is_script && mode & 0o002 != 0 {
+        findings.push(finding(
+            Severity::Medium,
+            "permissions",
+            format!("Script is world-writable (mode {:o}); any user could change what it runs", mode & 0o7777),
+        ));
+    }
+
+    let is_desktop_entry = path.extension().is_some_and(|e| e == "desktop")
+        && path.parent().is_some_and(|p| p.ends_with("share/applications"));
+    if is_desktop_entry {
+        findings.push(finding(
+            Severity::Medium,
+            "desktop-entry",
+            "Desktop entry in share/applications could override a system entry".to_string(),
+        ));
+    }
+
+    findings
+}
+
+/// DT_RPATH and DT_RUNPATH entries of an ELF64 file, split at ':'
+fn read_library_paths(elf: &[u8]) -> Vec<(&'static str, String)> {
+    const DT_NULL: i64 = 0;
+    const DT_RPATH: i64 = 15;
+    const DT_RUNPATH: i64 = 29;
+
+    let mut cursor = Cursor::new(elf);
+    let (Ok(Some(dynamic)), Ok(Some(dynstr))) = (
+        find_elf_section(&mut cursor, ".dynamic"),
+        find_elf_section(&mut cursor, ".dynstr"),
+    ) else {
+        return Vec::new();
+    };
+    let section = |(offset, size): (u64, u64)| elf.get(offset as usize..offset.saturating_add(size) as usize);
+    let (Some(dynamic), Some(dynstr)) = (section(dynamic), section(dynstr)) else {
+        return Vec::new();
+    };
+
+    let mut paths = Vec::new();
+    for entry in dynamic.chunks_exact(16) {
+        let tag = i64::from_le_bytes(entry[0..8].try_into().unwrap());
+        let value = u64::from_le_bytes(entry[8..16].try_into().unwrap()) as usize;
+        let name = match tag {
+            DT_NULL => break,
+            DT_RPATH => "RPATH",
+            DT_RUNPATH => "RUNPATH",
+            _ => continue,
+        };
+        let Some(string) = dynstr.get(value..).and_then(|rest| rest.split(|&b| b == 0).next()) else {
+            continue;
+        };
+        for search_path in String::from_utf8_lossy(string).split(':') {
+            paths.push((name, search_path.to_string()));
+        }
+    }
+    paths
+}
+
+/// Severity of a library search path, `None` if it is unremarkable
+fn classify_library_path(search_path: &str) -> Option<(Severity, &'static str)> {
+    if search_path.is_empty() {
+        return Some((Severity::High, "is empty, so libraries are loaded from the working directory"));
+    }
+    if search_path.starts_with("$ORIGIN") || search_path.starts_with("${ORIGIN}") {
+        return None;
+    }
+    if !search_path.starts_with('/') {
+        return Some((Severity::High, "is relative to the working directory"));
+    }
+    let path = Path::new(search_path);
+    if WRITABLE_DIRS.iter().any(|dir| path.starts_with(dir)) {
+        return Some((Severity::High, "is in a world-writable location"));
+    }
+    if SYSTEM_LIBRARY_DIRS.iter().any(|dir| path.starts_with(dir)) {
+        return None;
+    }
+    Some((Severity::Low, "is outside the install directory and system library paths"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_and_rpath_checks() {
+        let severities = |path: &str, mode, is_elf| {
+            check_entry(Path::new(path), mode, is_elf).iter().map(|f| f.severity).collect::<Vec<_>>()
+        };
+        assert_eq!(severities("bin/app", 0o4755, true), [Severity::High]);
+        assert_eq!(severities("run.sh", 0o777, false), [Severity::Medium]);
+        assert_eq!(severities("./share/applications/firefox.desktop", 0o644, false), [Severity::Medium]);
+        assert!(severities("bin/app", 0o755, true).is_empty());
+
+        assert_eq!(classify_library_path("$ORIGIN/../lib"), None);
+        assert_eq!(classify_library_path("/usr/lib/x86_64-linux-gnu"), None);
+        assert_eq!(classify_library_path("").map(|c| c.0), Some(Severity::High));
+        assert_eq!(classify_library_path("lib").map(|c| c.0), Some(Severity::High));
+        assert_eq!(classify_library_path("/tmp/build/lib").map(|c| c.0), Some(Severity::High));
+        assert_eq!(classify_library_path("/opt/vendor/lib").map(|c| c.0), Some(Severity::Low));
+    }
+
+    #[test]
+    fn test_signature_finding() {
+        assert!(signature_finding(&SignatureStatus::Valid).is_none());
+        assert_eq!(signature_finding(&SignatureStatus::Unsigned).unwrap().severity, Severity::Low);
+        let invalid = signature_finding(&SignatureStatus::Invalid("bad".into())).unwrap();
+        assert_eq!(invalid.severity, Severity::High);
+        assert!(invalid.detail.contains("bad"));
+    }
+}
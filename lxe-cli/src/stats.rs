@@ -0,0 +1,193 @@
+//! Installation Statistics
+//!
+//! Aggregates disk usage of installed apps from their manifests. Directory
+//! sizes are cached in the manifest and only recomputed when the app
+//! directory's mtime changes.
+
+use anyhow::Result;
+use lxe_common::manifest::{InstallManifest, SizeCache};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Statistics for one installed app
+#[derive(Debug, Serialize)]
+pub struct AppStats {
+    pub app_id: String,
+    pub name: String,
+    pub version: String,
+    pub installed_size_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_size_bytes: Option<u64>,
+    pub install_date: String,
+    pub is_system: bool,
+    pub signed: bool,
+}
+
+/// Totals over all apps
+#[derive(Debug, Default, Serialize)]
+pub struct Totals {
+    pub count: usize,
+    pub installed_size_bytes: u64,
+    pub download_size_bytes: u64,
+    pub signed: usize,
+    pub unsigned: usize,
+}
+
+/// Prefix an app was installed under: its `--install-dir`, `/usr` or `~/.local`
+pub fn install_base(manifest: &InstallManifest) -> Option<PathBuf> {
+    match manifest.prefix {
+        Some(ref prefix) => Some(prefix.clone()),
+        None if manifest.is_system => Some(lxe_common::paths::system::base_dir()),
+        None => lxe_common::paths::user::base_dir(),
+    }
+}
+
+/// Installation directory of an app from its manifest
+pub fn app_dir(manifest: &InstallManifest) -> Option<PathBuf> {
+    Some(install_base(manifest)?.join("share").join(&manifest.app_id))
+}
+
+/// Collect stats for every installed app, largest first
+///
+/// Manifests whose size cache was refreshed are saved back.
+pub fn collect() -> Result<Vec<AppStats>> {
+    let mut stats = Vec::new();
+
+    for mut manifest in InstallManifest::load_all()? {
+        let size = match app_dir(&manifest) {
+            Some(dir) => cached_dir_size(&mut manifest, &dir),
+            None => 0,
+        };
+
+        stats.push(AppStats {
+            name: manifest.name.clone().unwrap_or_else(|| manifest.app_id.clone()),
+            install_date: format_install_date(&manifest.installed_at),
+            app_id: manifest.app_id,
+            version: manifest.version,
+            installed_size_bytes: size,
+            download_size_bytes: manifest.download_size,
+            is_system: manifest.is_system,
+            signed: manifest.signed,
+        });
+    }
+
+    stats.sort_by_key(|a| std::cmp::Reverse(a.installed_size_bytes));
+    Ok(stats)
+}
+
+/// Sum up a list of app stats
+pub fn totals(stats: &[AppStats]) -> Totals {
+    let mut totals = Totals::default();
+    for app in stats {
+        totals.count += 1;
+        totals.installed_size_bytes += app.installed_size_bytes;
+        totals.download_size_bytes += app.download_size_bytes.unwrap_or(0);
+        if app.signed {
+            totals.signed += 1;
+        } else {
+            totals.unsigned += 1;
+        }
+    }
+    totals
+}
+
+/// Directory size, reusing the manifest cache while the mtime is unchanged
+pub fn cached_dir_size(manifest: &mut InstallManifest, dir: &Path) -> u64 {
+    let Some(mtime) = mtime_secs(dir) else {
+        return 0;
+    };
+
+    if let Some(cache) = &manifest.size_cache {
+        if cache.mtime == mtime {
+            return cache.bytes;
+        }
+    }
+
+    let bytes = dir_size(dir);
+    manifest.size_cache = Some(SizeCache { bytes, mtime });
+    // Best effort - system manifests may not be writable
+    let _ = manifest.save();
+    bytes
+}
+
+fn mtime_secs(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).ok()?
+        .modified().ok()?
+        .duration_since(UNIX_EPOCH).ok()
+        .map(|d| d.as_secs())
+}
+
+/// Total size of regular files under `dir` (symlinks are not followed)
+pub fn dir_size(dir: &Path) -> u64 {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Turn a manifest timestamp ("unix:1700000000") into YYYY-MM-DD
+pub fn format_install_date(installed_at: &str) -> String {
+    let Some(secs) = installed_at.strip_prefix("unix:").and_then(|s| s.parse::<i64>().ok()) else {
+        return installed_at.to_string();
+    };
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Human-readable byte size
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_install_date() {
+        assert_eq!(format_install_date("unix:0"), "1970-01-01");
+        assert_eq!(format_install_date("unix:1709251200"), "2024-03-01");
+        assert_eq!(format_install_date("2024-01-01T00:00:00Z"), "2024-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn test_app_dir() {
+        let mut manifest = InstallManifest::new("com.example.App".into(), None, "1.0.0".into(), true);
+        assert_eq!(app_dir(&manifest), Some(PathBuf::from("/usr/share/com.example.App")));
+        manifest.prefix = Some(PathBuf::from("/opt/apps"));
+        assert_eq!(app_dir(&manifest), Some(PathBuf::from("/opt/apps/share/com.example.App")));
+    }
+}
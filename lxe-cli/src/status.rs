@@ -0,0 +1,185 @@
+//! Environment Health Report (`lxe status`)
+//!
+//! Gathers everything an admin or CI job wants to know about the local LXE
+//! setup into one serializable report: versions, installed apps, disk usage,
+//! PATH, config and trust store.
+
+use crate::{stats, updates};
+use anyhow::Result;
+use lxe_common::config::GlobalConfig;
+use lxe_common::keystore::TrustStore;
+use lxe_common::manifest::InstallManifest;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Debug, Serialize)]
+pub struct StatusReport {
+    pub cli_version: String,
+    pub runtime: RuntimeStatus,
+    pub user_apps: usize,
+    pub system_apps: usize,
+    pub disk_usage_bytes: u64,
+    pub signed_apps: usize,
+    pub unsigned_apps: usize,
+    pub bin_dir_in_path: bool,
+    pub config: ConfigStatus,
+    pub keystore: KeystoreStatus,
+    pub last_install: Option<LastInstall>,
+    /// `None` when no app has an update source (`source_url` or registry)
+    pub pending_updates: Option<Vec<PendingUpdate>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RuntimeStatus {
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ConfigStatus {
+    pub path: Option<PathBuf>,
+    pub exists: bool,
+    /// Parse error, if the file is invalid
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeystoreStatus {
+    pub path: Option<PathBuf>,
+    pub trusted_keys: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LastInstall {
+    pub app_id: String,
+    pub version: String,
+    pub date: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingUpdate {
+    pub app_id: String,
+    pub installed: String,
+    pub latest: String,
+}
+
+/// Collect the report; pending updates are only checked with `check_updates`
+/// (they need the network, through `proxy` if given) and are otherwise
+/// taken from `cached_updates`
+pub fn collect(
+    runtime: RuntimeStatus,
+    check_updates: bool,
+    cached_updates: Option<Vec<PendingUpdate>>,
+    proxy: Option<&str>,
+) -> Result<StatusReport> {
+    let apps = stats::collect()?;
+    let totals = stats::totals(&apps);
+    let manifests = InstallManifest::load_all()?;
+
+    let config_path = GlobalConfig::path().ok();
+    let config_exists = config_path.as_ref().is_some_and(|p| p.exists());
+    let (config, config_error) = match GlobalConfig::load() {
+        Ok(config) => (config, None),
+        Err(e) => (GlobalConfig::default(), Some(format!("{:#}", e))),
+    };
+
+    let pending_updates = if check_updates {
+        let has_source = config.registry.is_some() || manifests.iter().any(|m| m.source_url.is_some());
+        if has_source {
+            let mut checker = updates::UpdateChecker::new(config.registry.clone(), proxy)?;
+            Some(checker.check(&manifests).into_iter()
+                .map(|u| PendingUpdate { app_id: u.app_id, installed: u.installed, latest: u.latest })
+                .collect())
+        } else {
+            None
+        }
+    } else {
+        cached_updates
+    };
+
+    // Manifest timestamps are "unix:<secs>", so the latest sorts last numerically
+    let last_install = manifests.iter()
+        .max_by_key(|m| m.installed_at.strip_prefix("unix:").and_then(|s| s.parse::<u64>().ok()))
+        .map(|m| LastInstall {
+            app_id: m.app_id.clone(),
+            version: m.version.clone(),
+            date: stats::format_install_date(&m.installed_at),
+        });
+
+    Ok(StatusReport {
+        cli_version: env!("CARGO_PKG_VERSION").to_string(),
+        runtime,
+        user_apps: apps.iter().filter(|a| !a.is_system).count(),
+        system_apps: apps.iter().filter(|a| a.is_system).count(),
+        disk_usage_bytes: totals.installed_size_bytes,
+        signed_apps: totals.signed,
+        unsigned_apps: totals.unsigned,
+        bin_dir_in_path: bin_dir_in_path(),
+        config: ConfigStatus {
+            path: config_path,
+            exists: config_exists,
+            error: config_error,
+        },
+        keystore: KeystoreStatus {
+            path: TrustStore::default_path().ok(),
+            trusted_keys: TrustStore::load().map(|s| s.keys.len()).unwrap_or(0),
+        },
+        last_install,
+        pending_updates,
+    })
+}
+
+/// Whether ~/.local/bin is on `$PATH`
+pub fn bin_dir_in_path() -> bool {
+    let Some(bin_dir) = lxe_common::paths::user::bin_dir() else {
+        return false;
+    };
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|p| p == bin_dir))
+        .unwrap_or(false)
+}
+
+impl StatusReport {
+    /// Aligned "key: value" lines
+    pub fn lines(&self) -> Vec<(&'static str, String)> {
+        let path_or = |path: &Option<PathBuf>, missing: &str| {
+            path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| missing.to_string())
+        };
+
+        let runtime = match (&self.runtime.path, &self.runtime.version) {
+            (Some(path), Some(version)) => format!("{} ({})", version, path.display()),
+            (Some(path), None) => format!("unknown version ({})", path.display()),
+            _ => "not installed (run 'lxe runtime download')".to_string(),
+        };
+
+        let config = match (&self.config.error, self.config.exists) {
+            (Some(error), _) => format!("{} - invalid: {}", path_or(&self.config.path, "-"), error),
+            (None, true) => path_or(&self.config.path, "-"),
+            (None, false) => format!("{} (not created, using defaults)", path_or(&self.config.path, "-")),
+        };
+
+        let updates = match &self.pending_updates {
+            None => "not checked (no source_url or registry configured)".to_string(),
+            Some(updates) if updates.is_empty() => "none".to_string(),
+            Some(updates) => updates.iter()
+                .map(|u| format!("{} {} → {}", u.app_id, u.installed, u.latest))
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+
+        vec![
+            ("CLI version", self.cli_version.clone()),
+            ("Runtime", runtime),
+            ("Installed apps", format!("{} user, {} system", self.user_apps, self.system_apps)),
+            ("Disk usage", stats::format_size(self.disk_usage_bytes)),
+            ("Signatures", format!("{} signed, {} unsigned", self.signed_apps, self.unsigned_apps)),
+            ("~/.local/bin in PATH", if self.bin_dir_in_path { "yes" } else { "no" }.to_string()),
+            ("Config", config),
+            ("Trusted keys", format!("{} ({})", self.keystore.trusted_keys, path_or(&self.keystore.path, "-"))),
+            ("Last install", self.last_install.as_ref()
+                .map(|l| format!("{} v{} on {}", l.app_id, l.version, l.date))
+                .unwrap_or_else(|| "-".to_string())),
+            ("Pending updates", updates),
+        ]
+    }
+}
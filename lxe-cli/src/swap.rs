@@ -0,0 +1,183 @@
+//! Hot-Swapping Installed Applications
+//!
+//! Replaces an installed app directory with a new version using two
+//! renames (`app_dir` → `app_dir.old`, `app_dir.new` → `app_dir`), so the
+//! app is never half-extracted on disk. The `.new`/`.old` directories are
+//! siblings of `app_dir`, which keeps every rename on one filesystem.
+
+use anyhow::{Context, Result};
+use lxe_common::builder;
+use lxe_common::payload::{self, PayloadInfo};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Sibling path with a suffix appended (e.g., `com.example.app.new`)
+pub fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    dir.with_file_name(name)
+}
+
+/// Verify the payload checksum (with the package's algorithm) and extract
+/// it into `dest`
+pub fn extract_verified(info: &PayloadInfo, dest: &Path) -> Result<()> {
+    let computed = payload::checksum_reader(payload::open_payload_reader(info)?, info.metadata.checksum_algorithm)?;
+    if !computed.eq_ignore_ascii_case(&info.metadata.payload_checksum) {
+        anyhow::bail!("Payload checksum mismatch - the package is corrupted");
+    }
+
+    if dest.exists() {
+        fs::remove_dir_all(dest)
+            .with_context(|| format!("Failed to clear {}", dest.display()))?;
+    }
+    fs::create_dir_all(dest)?;
+
+    let decoder = builder::decoder(payload::open_payload_reader(info)?, &info.metadata.compressor)?;
+    tar::Archive::new(decoder)
+        .unpack(dest)
+        .with_context(|| format!("Failed to extract to {}", dest.display()))?;
+
+    let exec = dest.join(&info.metadata.exec);
+    if !exec.is_file() {
+        anyhow::bail!("Executable '{}' missing from the new package", info.metadata.exec);
+    }
+    Ok(())
+}
+
+/// Replace `app_dir` with `new_dir`, leaving the previous version at `old_dir`
+///
+/// If the second rename fails, the first one is reverted.
+pub fn swap_dirs(app_dir: &Path, new_dir: &Path, old_dir: &Path) -> Result<()> {
+    if old_dir.exists() {
+        fs::remove_dir_all(old_dir)
+            .with_context(|| format!("Failed to remove stale {}", old_dir.display()))?;
+    }
+
+    fs::rename(app_dir, old_dir)
+        .with_context(|| format!("Failed to move {} aside", app_dir.display()))?;
+
+    if let Err(e) = fs::rename(new_dir, app_dir) {
+        fs::rename(old_dir, app_dir)
+            .context("Swap failed and the previous version could not be restored")?;
+        return Err(e).context("Failed to move the new version into place");
+    }
+    Ok(())
+}
+
+/// Undo `swap_dirs`: put `old_dir` back at `app_dir`
+pub fn rollback(app_dir: &Path, old_dir: &Path) -> Result<()> {
+    if app_dir.exists() {
+        fs::remove_dir_all(app_dir)?;
+    }
+    fs::rename(old_dir, app_dir).context("Failed to restore the previous version")
+}
+
+/// PIDs of processes whose executable lives under `dir`
+pub fn running_processes(dir: &Path) -> Vec<i32> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str()?.parse::<i32>().ok())
+        .filter(|pid| {
+            fs::read_link(format!("/proc/{}/exe", pid))
+                .map(|exe| exe.starts_with(dir))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Send `signal` to each process, returning how many were signalled
+pub fn signal_processes(pids: &[i32], signal: i32) -> usize {
+    pids.iter()
+        // SAFETY: kill() has no memory-safety preconditions
+        .filter(|&&pid| unsafe { libc::kill(pid, signal) } == 0)
+        .count()
+}
+
+/// Point the bin symlink at the new executable, replacing it atomically
+pub fn replace_symlink(link: &Path, target: &Path) -> Result<()> {
+    let tmp = sibling(link, ".lxe-tmp");
+    let _ = fs::remove_file(&tmp);
+    std::os::unix::fs::symlink(target, &tmp)
+        .with_context(|| format!("Failed to create symlink {}", tmp.display()))?;
+    fs::rename(&tmp, link)
+        .with_context(|| format!("Failed to update {}", link.display()))
+}
+
+/// Update version-dependent keys of the main `[Desktop Entry]` section
+///
+/// Other sections (actions, user edits) are left untouched.
+pub fn update_desktop_entry(content: &str, updates: &[(&str, String)]) -> String {
+    let mut in_main = false;
+    let mut output: String = content
+        .lines()
+        .map(|line| {
+            if line.starts_with('[') {
+                in_main = line == "[Desktop Entry]";
+            } else if in_main {
+                if let Some((key, _)) = line.split_once('=') {
+                    if let Some((_, value)) = updates.iter().find(|(k, _)| *k == key) {
+                        return format!("{}={}", key, value);
+                    }
+                }
+            }
+            line.to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    output.push('\n');
+    output
+}
+
+/// Write `content` to `path` via a temporary file and rename
+pub fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    let tmp = sibling(path, ".lxe-tmp");
+    fs::write(&tmp, content)?;
+    if let Ok(meta) = fs::metadata(path) {
+        fs::set_permissions(&tmp, meta.permissions())?;
+    }
+    fs::rename(&tmp, path).with_context(|| format!("Failed to update {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_desktop_entry() {
+        let content = "[Desktop Entry]\nName=App\nX-LXE-Version=1.0.0\n\n[Desktop Action Uninstall]\nName=Uninstall App\n";
+        let updated = update_desktop_entry(content, &[
+            ("Name", "App 2".to_string()),
+            ("X-LXE-Version", "2.0.0".to_string()),
+        ]);
+        assert_eq!(
+            updated,
+            "[Desktop Entry]\nName=App 2\nX-LXE-Version=2.0.0\n\n[Desktop Action Uninstall]\nName=Uninstall App\n"
+        );
+    }
+
+    #[test]
+    fn test_swap_dirs() {
+        let base = std::env::temp_dir().join(format!("lxe-swap-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        let app = base.join("com.example.app");
+        let new = sibling(&app, ".new");
+        let old = sibling(&app, ".old");
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&new).unwrap();
+        fs::write(app.join("version"), "1").unwrap();
+        fs::write(new.join("version"), "2").unwrap();
+
+        swap_dirs(&app, &new, &old).unwrap();
+        assert_eq!(fs::read_to_string(app.join("version")).unwrap(), "2");
+        assert_eq!(fs::read_to_string(old.join("version")).unwrap(), "1");
+
+        rollback(&app, &old).unwrap();
+        assert_eq!(fs::read_to_string(app.join("version")).unwrap(), "1");
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
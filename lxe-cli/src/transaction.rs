@@ -0,0 +1,259 @@
+//! Transaction Execution
+//!
+//! Drives a `TransactionLog` through prepare and commit for user-local
+//! installs. The log format and rollback live in `lxe_common::transaction`.
+
+use crate::swap;
+use anyhow::{Context, Result};
+use lxe_common::manifest::InstallManifest;
+use lxe_common::transaction::{Phase, StagedInstall, TransactionLog};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Installation root for transactions (~/.local)
+fn base_dir() -> Result<PathBuf> {
+    lxe_common::paths::user::base_dir().ok_or_else(|| anyhow::anyhow!("Cannot find ~/.local"))
+}
+
+/// Phase 1: extract and verify every package next to its final location
+pub fn prepare(log: &mut TransactionLog) -> Result<()> {
+    if log.phase != Phase::Open {
+        anyhow::bail!("Transaction '{}' cannot be prepared in phase {:?}", log.id, log.phase);
+    }
+    if log.spec.is_empty() {
+        anyhow::bail!("Transaction '{}' has no operations", log.id);
+    }
+
+    let share_dir = base_dir()?.join("share");
+    fs::create_dir_all(&share_dir)?;
+
+    for op in &log.spec.uninstall {
+        match InstallManifest::load(&op.id)? {
+            Some(m) if m.is_system => {
+                anyhow::bail!("{} is installed system-wide; transactions only manage user installs", op.id)
+            }
+            Some(_) => {}
+            None => anyhow::bail!("{} is not installed via LXE", op.id),
+        }
+    }
+
+    for op in log.spec.install.clone() {
+        let info = lxe_common::payload::read_payload_info(&op.file)
+            .with_context(|| format!("Invalid package {}", op.file.display()))?;
+        lxe_common::requirements::check_system_requirements(&info.metadata)?;
+        let app_id = info.metadata.app_id.clone();
+        if log.staged.iter().any(|s| s.app_id == app_id) {
+            anyhow::bail!("{} is installed twice in this transaction", app_id);
+        }
+        if let Some(prefix) = InstallManifest::load(&app_id)?.and_then(|m| m.prefix) {
+            anyhow::bail!(
+                "{} is installed under {}; transactions only install to ~/.local",
+                app_id,
+                prefix.display()
+            );
+        }
+
+        let app_dir = share_dir.join(&app_id);
+        let staged_dir = swap::sibling(&app_dir, &format!(".txn-{}", log.id));
+
+        // Record before extracting so rollback cleans up partial extractions
+        log.staged.push(StagedInstall {
+            app_id,
+            file: op.file.clone(),
+            staged_dir: staged_dir.clone(),
+            app_dir,
+        });
+        log.save()?;
+
+        swap::extract_verified(&info, &staged_dir)
+            .with_context(|| format!("Failed to stage {}", op.file.display()))?;
+    }
+
+    log.set_phase(Phase::Prepared)
+}
+
+/// Phase 2: rename staged directories into place, recording each rename
+///
+/// Previous versions and uninstalled apps are moved aside (not deleted)
+/// until every rename has succeeded.
+pub fn commit(log: &mut TransactionLog) -> Result<()> {
+    if log.phase != Phase::Prepared {
+        anyhow::bail!("Transaction '{}' is not prepared (phase {:?})", log.id, log.phase);
+    }
+    log.set_phase(Phase::Committing)?;
+
+    let base = base_dir()?;
+    let aside_suffix = format!(".txn-{}-old", log.id);
+
+    for staged in log.staged.clone() {
+        if staged.app_dir.exists() {
+            log.rename(&staged.app_dir, &swap::sibling(&staged.app_dir, &aside_suffix))?;
+        }
+        log.rename(&staged.staged_dir, &staged.app_dir)?;
+    }
+
+    for op in log.spec.uninstall.clone() {
+        let app_dir = app_base(&op.id, &base)?.join("share").join(&op.id);
+        if app_dir.exists() {
+            log.rename(&app_dir, &swap::sibling(&app_dir, &aside_suffix))?;
+        }
+    }
+
+    log.set_phase(Phase::Committed)
+}
+
+/// After commit: write desktop entries, symlinks and manifests, and delete
+/// what was moved aside. Returns warnings for steps that failed.
+pub fn finalize(log: &TransactionLog, runtime_source: Option<&Path>) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let Ok(base) = base_dir() else {
+        return vec!["Cannot find ~/.local".to_string()];
+    };
+
+    let runtime_path = base.join("bin").join("lxe-runtime");
+    if !runtime_path.exists() {
+        if let Some(source) = runtime_source {
+            let _ = fs::create_dir_all(base.join("bin"));
+            if let Err(e) = fs::copy(source, &runtime_path) {
+                warnings.push(format!("Could not install lxe-runtime for uninstall support: {}", e));
+            }
+        }
+    }
+
+    for staged in &log.staged {
+        if let Err(e) = integrate_install(staged, &base, &runtime_path) {
+            warnings.push(format!("{}: {:#}", staged.app_id, e));
+        }
+    }
+    for op in &log.spec.uninstall {
+        if let Err(e) = remove_integration(&op.id, &base) {
+            warnings.push(format!("{}: {:#}", op.id, e));
+        }
+    }
+
+    // Drop the previous versions only now that everything is in place
+    let aside_suffix = format!(".txn-{}-old", log.id);
+    for rename in &log.renames {
+        if rename.to.to_string_lossy().ends_with(&aside_suffix) {
+            let _ = fs::remove_dir_all(&rename.to);
+        }
+    }
+
+    warnings
+}
+
+/// Desktop entry, bin symlink and manifest for a committed install
+fn integrate_install(staged: &StagedInstall, base: &Path, runtime_path: &Path) -> Result<()> {
+    let info = lxe_common::payload::read_payload_info_unchecked(&staged.file)?;
+    let meta = &info.metadata;
+
+    let applications = base.join("share/applications");
+    fs::create_dir_all(&applications)?;
+    let desktop_path = applications.join(meta.desktop_filename());
+    let existing = fs::read_to_string(&desktop_path).ok();
+    let content = lxe_common::desktop::render_desktop_entry(
+        meta,
+        &staged.app_dir,
+        runtime_path,
+        existing.as_deref(),
+    );
+    swap::write_atomic(&desktop_path, &content)?;
+
+    let exec_name = Path::new(&meta.exec)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| meta.exec.clone());
+    let link = base.join("bin").join(exec_name);
+    fs::create_dir_all(base.join("bin"))?;
+    swap::replace_symlink(&link, &staged.app_dir.join(&meta.exec))?;
+
+    let mut extra_paths = Vec::new();
+    for extra in &meta.extra_executables {
+        if extra.validate().is_err() {
+            continue;
+        }
+        let extra_link = base.join("bin").join(extra.command());
+        swap::replace_symlink(&extra_link, &staged.app_dir.join(&extra.exec))?;
+        extra_paths.push(extra_link);
+        if extra.desktop_entry {
+            let extra_path = applications.join(extra.desktop_filename(&meta.app_id));
+            let content = lxe_common::desktop::render_desktop_entry(
+                &meta.for_extra_executable(extra),
+                &staged.app_dir,
+                runtime_path,
+                fs::read_to_string(&extra_path).ok().as_deref(),
+            );
+            swap::write_atomic(&extra_path, &content)?;
+            extra_paths.push(extra_path);
+        }
+    }
+
+    let metainfo_dir = base.join("share/metainfo");
+    fs::create_dir_all(&metainfo_dir)?;
+    let metainfo_path = metainfo_dir.join(lxe_common::appstream::metainfo_filename(&meta.app_id));
+    swap::write_atomic(&metainfo_path, &lxe_common::appstream::generate_metainfo(meta))?;
+
+    // Registers the app's MIME types (best effort)
+    let _ = std::process::Command::new("update-desktop-database")
+        .arg(&applications)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status();
+
+    let mut manifest = InstallManifest::new(
+        meta.app_id.clone(),
+        Some(meta.name.clone()),
+        meta.version.clone(),
+        false,
+    );
+    manifest.exec = Some(meta.exec.clone());
+    manifest.deprecated = meta.deprecated;
+    manifest.deprecation_message = meta.deprecation_message.clone();
+    manifest.download_size = Some(info.payload_size);
+    manifest.signed = meta.signature.is_some();
+    manifest.add_file(&staged.app_dir);
+    manifest.add_file(&desktop_path);
+    manifest.add_file(&link);
+    manifest.add_file(&metainfo_path);
+    manifest.add_file(runtime_path);
+    for path in &extra_paths {
+        manifest.add_file(path);
+    }
+    // Checksums are optional, `lxe verify-install` reports their absence
+    let _ = manifest.record_files(&staged.app_dir);
+    manifest.set_config_paths(&staged.app_dir, &meta.config_paths);
+    manifest.save()?;
+    Ok(())
+}
+
+/// Prefix `app_id` is installed under: its `--install-dir`, or `base`
+fn app_base(app_id: &str, base: &Path) -> Result<PathBuf> {
+    Ok(InstallManifest::load(app_id)?
+        .and_then(|m| m.prefix)
+        .unwrap_or_else(|| base.to_path_buf()))
+}
+
+/// Remove the desktop entry, symlinks and manifest of an uninstalled app
+fn remove_integration(app_id: &str, base: &Path) -> Result<()> {
+    let base = &app_base(app_id, base)?;
+    let app_dir = base.join("share").join(app_id);
+    let desktop_path = base.join("share/applications").join(format!("{}.desktop", app_id));
+    let _ = fs::remove_file(&desktop_path);
+
+    if let Some(manifest) = InstallManifest::load(app_id)? {
+        for entry in manifest.desktop_entries(&base.join("share/applications")) {
+            let _ = fs::remove_file(entry);
+        }
+        for file in &manifest.files {
+            let path = Path::new(file);
+            let is_app_link = fs::read_link(path)
+                .map(|target| target.starts_with(&app_dir))
+                .unwrap_or(false);
+            if is_app_link {
+                let _ = fs::remove_file(path);
+            }
+        }
+    }
+
+    InstallManifest::delete(app_id)
+}
@@ -0,0 +1,155 @@
+//! Update Server Manifests (`lxe build --generate-update-metadata`)
+//!
+//! Describes the published versions of one app for an update server or a
+//! CDN metadata endpoint, newest first:
+//!
+//! ```json
+//! {
+//!   "app_id": "com.example.App",
+//!   "versions": [
+//!     { "version": "1.2.0", "arch": "x86_64", "download_url": "https://.../app-1.2.0.lxe", ... }
+//!   ]
+//! }
+//! ```
+//!
+//! An installed app whose `source_url` points at such a file (a URL ending
+//! in `.json`) is checked for updates against it, see `updates`.
+
+use crate::repo::{RepoEntry, RepoIndex};
+use anyhow::{Context, Result};
+use lxe_common::requirements::normalize_version;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateManifest {
+    pub app_id: String,
+    pub versions: Vec<UpdateEntry>,
+}
+
+/// One published build
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateEntry {
+    pub version: String,
+    pub arch: String,
+
+    /// `--download-base-url` joined with the file name, or just the file name
+    pub download_url: String,
+
+    /// Size of the .lxe file in bytes
+    pub file_size: u64,
+
+    pub payload_checksum: String,
+
+    #[serde(default)]
+    pub public_key: Option<String>,
+
+    #[serde(default)]
+    pub signature: Option<String>,
+
+    #[serde(default)]
+    pub min_runtime_version: Option<String>,
+
+    #[serde(default)]
+    pub changelog: Option<String>,
+}
+
+impl UpdateManifest {
+    pub fn new(app_id: &str) -> Self {
+        UpdateManifest { app_id: app_id.to_string(), versions: Vec::new() }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Invalid update manifest: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The versions as a package index, with `download_url` as each
+    /// entry's file
+    pub fn to_index(&self) -> RepoIndex {
+        let packages = self.versions.iter()
+            .map(|entry| RepoEntry {
+                id: self.app_id.clone(),
+                name: self.app_id.clone(),
+                version: entry.version.clone(),
+                arch: entry.arch.clone(),
+                description: None,
+                file: entry.download_url.clone(),
+                size: entry.file_size,
+                // payload_checksum covers the payload, not the whole file
+                sha256: None,
+                signed: entry.signature.is_some(),
+            })
+            .collect();
+        RepoIndex {
+            version: crate::repo::INDEX_VERSION,
+            name: self.app_id.clone(),
+            updated_at: 0,
+            packages,
+        }
+    }
+
+    /// Add `entry`, replacing an earlier build of the same version and arch
+    pub fn add(&mut self, entry: UpdateEntry) {
+        self.versions.retain(|e| !(e.version == entry.version && e.arch == entry.arch));
+        self.versions.push(entry);
+        self.versions.sort_by(|a, b| {
+            normalize_version(&b.version)
+                .cmp(&normalize_version(&a.version))
+                .then_with(|| a.arch.cmp(&b.arch))
+        });
+    }
+}
+
+/// `base_url` and `file_name` joined with exactly one slash
+pub fn download_url(base_url: Option<&str>, file_name: &str) -> String {
+    match base_url {
+        Some(base) => format!("{}/{}", base.trim_end_matches('/'), file_name),
+        None => file_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(version: &str) -> UpdateEntry {
+        UpdateEntry {
+            version: version.to_string(),
+            arch: "x86_64".to_string(),
+            download_url: download_url(Some("https://cdn.example.com/apps/"), &format!("app-{}.lxe", version)),
+            file_size: 1,
+            payload_checksum: String::new(),
+            public_key: None,
+            signature: None,
+            min_runtime_version: None,
+            changelog: None,
+        }
+    }
+
+    #[test]
+    fn test_add_versions() {
+        let mut manifest = UpdateManifest::new("com.example.App");
+        manifest.add(entry("1.9.0"));
+        manifest.add(entry("1.10.0"));
+        manifest.add(UpdateEntry { file_size: 2, ..entry("1.9.0") });
+
+        let versions: Vec<_> = manifest.versions.iter().map(|e| e.version.as_str()).collect();
+        assert_eq!(versions, ["1.10.0", "1.9.0"]);
+        assert_eq!(manifest.versions[1].file_size, 2);
+        assert_eq!(manifest.versions[0].download_url, "https://cdn.example.com/apps/app-1.10.0.lxe");
+
+        let index = manifest.to_index();
+        assert_eq!(index.packages.len(), 2);
+        assert_eq!(index.packages[0].id, "com.example.App");
+        assert_eq!(index.packages[0].file, "https://cdn.example.com/apps/app-1.10.0.lxe");
+    }
+}
@@ -0,0 +1,210 @@
+//! Update Checks
+//!
+//! Compares installed apps against the package index (`index.json`, as
+//! written by `lxe repo`) of their source: the manifest's `source_url` if
+//! set, otherwise the configured registry. A source ending in `.json` is an
+//! update manifest (`lxe build --generate-update-metadata`) instead.
+
+use crate::repo::{RepoEntry, RepoIndex};
+use crate::update_manifest::UpdateManifest;
+use anyhow::{Context, Result};
+use lxe_common::manifest::InstallManifest;
+use lxe_common::requirements::normalize_version;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Minimum gap between two requests to the same server
+const REQUEST_SPACING: Duration = Duration::from_secs(1);
+
+/// A newer version of an installed app
+#[derive(Debug, Clone)]
+pub struct AvailableUpdate {
+    pub app_id: String,
+    pub name: String,
+    pub installed: String,
+    pub latest: String,
+    /// Download URL of the newer package
+    pub url: String,
+    /// SHA256 of the package from the index, if it has one
+    pub sha256: Option<String>,
+}
+
+/// Fetches package indexes, at most once per source per check
+pub struct UpdateChecker {
+    client: reqwest::blocking::Client,
+    default_registry: Option<String>,
+    indexes: HashMap<String, Option<RepoIndex>>,
+    last_request: Option<Instant>,
+}
+
+impl UpdateChecker {
+    pub fn new(default_registry: Option<String>, proxy: Option<&str>) -> Result<Self> {
+        let client = crate::network::client_builder(proxy)?
+            .timeout(Duration::from_secs(30))
+            .build()?;
+        Ok(UpdateChecker { client, default_registry, indexes: HashMap::new(), last_request: None })
+    }
+
+    /// Check every installed app; apps without a reachable source are skipped
+    pub fn check(&mut self, manifests: &[InstallManifest]) -> Vec<AvailableUpdate> {
+        // Forget indexes from the previous round
+        self.indexes.clear();
+
+        manifests.iter()
+            .filter_map(|m| self.check_one(m))
+            .collect()
+    }
+
+    fn check_one(&mut self, manifest: &InstallManifest) -> Option<AvailableUpdate> {
+        let source = manifest.source_url.clone().or_else(|| self.default_registry.clone())?;
+        let source = source.trim_end_matches('/').to_string();
+        let index = self.index(&source)?;
+
+        let latest = newest_entry(&index.packages, &manifest.app_id)?;
+        if !is_newer(&latest.version, &manifest.version) {
+            return None;
+        }
+
+        Some(AvailableUpdate {
+            app_id: manifest.app_id.clone(),
+            name: manifest.name.clone().unwrap_or_else(|| manifest.app_id.clone()),
+            installed: manifest.version.clone(),
+            latest: latest.version.clone(),
+            url: entry_url(&source, &latest.file),
+            sha256: latest.sha256.clone(),
+        })
+    }
+
+    /// Cached index for a source, fetching it (rate-limited) on first use
+    fn index(&mut self, source: &str) -> Option<&RepoIndex> {
+        if !self.indexes.contains_key(source) {
+            if let Some(last) = self.last_request {
+                let elapsed = last.elapsed();
+                if elapsed < REQUEST_SPACING {
+                    std::thread::sleep(REQUEST_SPACING - elapsed);
+                }
+            }
+            self.last_request = Some(Instant::now());
+
+            let index = fetch_source(&self.client, source)
+                .map_err(|e| eprintln!("⚠️  {}: {:#}", source, e))
+                .ok();
+            self.indexes.insert(source.to_string(), index);
+        }
+        self.indexes.get(source)?.as_ref()
+    }
+}
+
+/// The packages of `source`: a repository, or an update manifest
+fn fetch_source(client: &reqwest::blocking::Client, source: &str) -> Result<RepoIndex> {
+    if !is_update_manifest(source) {
+        return crate::repo::fetch_index(client, source);
+    }
+    let response = client.get(source).send().context("Update server unreachable")?;
+    if !response.status().is_success() {
+        anyhow::bail!("HTTP {}", response.status());
+    }
+    let manifest: UpdateManifest = response.json().context("Invalid update manifest")?;
+    Ok(manifest.to_index())
+}
+
+fn is_update_manifest(source: &str) -> bool {
+    source.ends_with(".json")
+}
+
+/// Download URL of `file` from `source`; update manifests usually list
+/// absolute URLs, relative ones are next to the manifest
+fn entry_url(source: &str, file: &str) -> String {
+    if file.contains("://") {
+        return file.to_string();
+    }
+    let base = match source.rsplit_once('/') {
+        Some((dir, _)) if is_update_manifest(source) => dir,
+        _ => source,
+    };
+    format!("{}/{}", base, file)
+}
+
+/// Highest version of `app_id` for this machine's architecture
+fn newest_entry<'a>(entries: &'a [RepoEntry], app_id: &str) -> Option<&'a RepoEntry> {
+    entries.iter()
+        .filter(|e| e.id == app_id && e.arch == std::env::consts::ARCH)
+        .max_by_key(|e| normalize_version(&e.version))
+}
+
+/// Check a downloaded update before it is run
+/// 
+/// The file must match the index's SHA256 (when listed) and be a package of
+/// the same app, validly signed, whose payload matches its checksum.
+pub fn verify_download(path: &Path, update: &AvailableUpdate) -> Result<()> {
+    if let Some(ref expected) = update.sha256 {
+        let actual = crate::repo::sha256_file(path)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            anyhow::bail!("SHA256 mismatch: index lists {}, download is {}", expected, actual);
+        }
+    }
+    
+    // Verifies the signature of signed packages
+    let info = lxe_common::payload::read_payload_info(path)
+        .context("Download is not a valid package")?;
+    if !info.metadata.is_signed() {
+        anyhow::bail!("Package is not signed, install it manually if you trust it");
+    }
+    if info.metadata.app_id != update.app_id {
+        anyhow::bail!("Download is {}, not {}", info.metadata.app_id, update.app_id);
+    }
+    crate::conformance::verify_payload(path, &info.metadata)
+}
+
+/// Whether `candidate` is a strictly newer version than `installed`
+pub fn is_newer(candidate: &str, installed: &str) -> bool {
+    match (normalize_version(candidate), normalize_version(installed)) {
+        (Some(candidate), Some(installed)) => candidate > installed,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() {
+        assert!(is_newer("1.2.0", "1.1.9"));
+        assert!(is_newer("2.0", "1.9.9"));
+        assert!(!is_newer("1.0.0", "1.0.0"));
+        assert!(!is_newer("0.9.0", "1.0.0"));
+        assert!(!is_newer("garbage", "1.0.0"));
+    }
+
+    #[test]
+    fn test_entry_url() {
+        let repo = "https://repo.example.com";
+        assert_eq!(entry_url(repo, "packages/app.lxe"), "https://repo.example.com/packages/app.lxe");
+        let manifest = "https://cdn.example.com/apps/updates.json";
+        assert_eq!(entry_url(manifest, "app-1.1.lxe"), "https://cdn.example.com/apps/app-1.1.lxe");
+        assert_eq!(entry_url(manifest, "https://dl.example.com/app.lxe"), "https://dl.example.com/app.lxe");
+    }
+
+    #[test]
+    fn test_verify_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("App.lxe");
+        std::fs::write(&path, b"not a package").unwrap();
+        let mut update = AvailableUpdate {
+            app_id: "com.example.App".into(),
+            name: "App".into(),
+            installed: "1.0".into(),
+            latest: "1.1".into(),
+            url: "https://example.com/App.lxe".into(),
+            sha256: Some("00".repeat(32)),
+        };
+        let err = verify_download(&path, &update).unwrap_err();
+        assert!(err.to_string().contains("SHA256 mismatch"), "{err:#}");
+        
+        // A matching hash alone isn't enough
+        update.sha256 = Some(crate::repo::sha256_file(&path).unwrap());
+        assert!(verify_download(&path, &update).is_err());
+    }
+}
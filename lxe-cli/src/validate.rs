@@ -0,0 +1,271 @@
+//! Pre-Release Package Validation (`lxe validate`)
+//!
+//! Checks a built package for mistakes that would only show up on users'
+//! machines: an executable for the wrong architecture, an `exec` or icon
+//! missing from the payload, a damaged payload, an app ID or categories
+//! desktop environments reject. Warnings are worth a look; failures make
+//! `lxe validate` exit 1, so it can gate a CI release job.
+
+use crate::conformance;
+use lxe_common::desktop::is_xdg_category;
+use lxe_common::metadata::LxeMetadata;
+use lxe_common::payload::{self, EntryInfo, EntryKind, PayloadInfo, FOOTER_VERSION_3};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl Status {
+    pub fn mark(self) -> &'static str {
+        match self {
+            Self::Pass => "✓",
+            Self::Warn => "⚠",
+            Self::Fail => "✗",
+        }
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug)]
+pub struct Validation {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Validation {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into() }
+    }
+}
+
+/// Symlinks followed when resolving `exec`
+const MAX_LINK_DEPTH: usize = 8;
+
+/// Run every check on the package at `path`
+///
+/// Checks that need the metadata or payload are skipped when those can't
+/// be read (which is itself reported as a failure).
+pub fn run(path: &Path) -> Vec<Validation> {
+    let mut checks = Vec::new();
+
+    let layout = match payload::read_layout(path) {
+        Ok(layout) => layout,
+        Err(e) => {
+            checks.push(Validation::new("Footer format", Status::Fail, format!("{:#}", e)));
+            return checks;
+        }
+    };
+    checks.push(match (layout.footer_size, layout.footer_version) {
+        (0, _) => Validation::new("Footer format", Status::Warn, "no footer - header only found by scanning"),
+        (_, FOOTER_VERSION_3) => Validation::new("Footer format", Status::Pass, format!("v{}", FOOTER_VERSION_3)),
+        (_, version) => Validation::new(
+            "Footer format",
+            Status::Warn,
+            format!("v{} (still readable; rebuild for v{})", version, FOOTER_VERSION_3),
+        ),
+    });
+
+    let json = conformance::read_metadata(path, &layout).ok();
+    checks.push(match conformance::check_checksum(path, &layout, json.as_ref()) {
+        Ok(detail) => Validation::new("Payload checksum", Status::Pass, detail),
+        Err(detail) => Validation::new("Payload checksum", Status::Fail, detail),
+    });
+
+    let info = match payload::read_payload_info_unchecked(path) {
+        Ok(info) => info,
+        Err(e) => {
+            checks.push(Validation::new("Metadata", Status::Fail, format!("{:#}", e)));
+            return checks;
+        }
+    };
+    let metadata = &info.metadata;
+
+    checks.push(if conformance::is_reverse_dns(&metadata.app_id) {
+        Validation::new("App ID is reverse-DNS", Status::Pass, &metadata.app_id)
+    } else {
+        Validation::new("App ID is reverse-DNS", Status::Fail, format!("'{}' (e.g. com.example.App)", metadata.app_id))
+    });
+    checks.push(check_categories(&metadata.categories));
+
+    let entries = match payload::list_entries(&info) {
+        Ok(entries) => entries,
+        Err(e) => {
+            checks.push(Validation::new("Payload archive", Status::Fail, format!("{:#}", e)));
+            return checks;
+        }
+    };
+    let entries: HashMap<PathBuf, EntryInfo> = entries.into_iter()
+        .map(|entry| (normalize(&entry.path), entry))
+        .collect();
+
+    let exec = resolve(&entries, Path::new(&metadata.exec));
+    checks.push(match exec {
+        Some(ref target) => Validation::new("Executable in payload", Status::Pass, target.display().to_string()),
+        None => Validation::new("Executable in payload", Status::Fail, format!("'{}' not found", metadata.exec)),
+    });
+    if let Some(ref target) = exec {
+        checks.push(check_arch(&info, metadata, target));
+    }
+
+    checks.push(match metadata.icon {
+        None => Validation::new("Icon in payload", Status::Warn, "no icon set"),
+        Some(ref icon) if resolve(&entries, Path::new(icon)).is_some() => {
+            Validation::new("Icon in payload", Status::Pass, icon)
+        }
+        Some(ref icon) => Validation::new("Icon in payload", Status::Fail, format!("'{}' not found", icon)),
+    });
+
+    checks
+}
+
+fn check_categories(categories: &[String]) -> Validation {
+    const NAME: &str = "Categories are XDG categories";
+    if categories.is_empty() {
+        return Validation::new(NAME, Status::Warn, "none set - menus list the app under Other");
+    }
+    let unknown: Vec<String> = categories.iter()
+        .filter(|c| !is_xdg_category(c))
+        .map(|c| format!("'{}'", c))
+        .collect();
+    if unknown.is_empty() {
+        Validation::new(NAME, Status::Pass, categories.join(";"))
+    } else {
+        Validation::new(NAME, Status::Fail, format!("unknown: {} (custom ones need an X- prefix)", unknown.join(", ")))
+    }
+}
+
+/// The executable's ELF machine must match `metadata.arch`
+fn check_arch(info: &PayloadInfo, metadata: &LxeMetadata, exec: &Path) -> Validation {
+    const NAME: &str = "Executable matches arch";
+    let head = match read_entry_head(info, exec, 20) {
+        Ok(head) => head,
+        Err(e) => return Validation::new(NAME, Status::Fail, format!("{:#}", e)),
+    };
+    let Some(machine) = elf_machine(&head) else {
+        return Validation::new(NAME, Status::Warn, "not an ELF binary (a script?), architecture not checked");
+    };
+    match elf_arch(machine) {
+        Some(arch) if arch == metadata.arch => Validation::new(NAME, Status::Pass, arch),
+        Some(arch) => Validation::new(
+            NAME,
+            Status::Fail,
+            format!("metadata says {} but the executable is {}", metadata.arch, arch),
+        ),
+        None => Validation::new(NAME, Status::Warn, format!("unknown ELF machine {:#x}", machine)),
+    }
+}
+
+/// `e_machine` of an ELF header
+pub fn elf_machine(head: &[u8]) -> Option<u16> {
+    if head.len() < 20 || head[..4] != *b"\x7fELF" {
+        return None;
+    }
+    let bytes = [head[18], head[19]];
+    // EI_DATA: 1 = little endian, 2 = big endian
+    Some(if head[5] == 2 { u16::from_be_bytes(bytes) } else { u16::from_le_bytes(bytes) })
+}
+
+/// `std::env::consts::ARCH` name of an ELF machine (what `lxe build` records)
+pub fn elf_arch(machine: u16) -> Option<&'static str> {
+    Some(match machine {
+        0x03 => "x86",
+        0x28 => "arm",
+        0x3e => "x86_64",
+        0xb7 => "aarch64",
+        0xf3 => "riscv64",
+        _ => return None,
+    })
+}
+
+/// Payload path of the file `path` refers to, following symlinks inside
+/// the payload
+fn resolve(entries: &HashMap<PathBuf, EntryInfo>, path: &Path) -> Option<PathBuf> {
+    let mut path = normalize(path);
+    for _ in 0..MAX_LINK_DEPTH {
+        let entry = entries.get(&path)?;
+        match (entry.kind, &entry.link_target) {
+            (EntryKind::Symlink, Some(target)) if target.is_relative() => {
+                path = normalize(&path.parent().unwrap_or(Path::new("")).join(target));
+            }
+            (EntryKind::Symlink, _) => return None,
+            _ => return Some(path),
+        }
+    }
+    None
+}
+
+/// `path` without `.` and with `..` applied
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => normalized.push(part),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            _ => {}
+        }
+    }
+    normalized
+}
+
+/// First `len` bytes of the payload file at `path`
+fn read_entry_head(info: &PayloadInfo, path: &Path, len: usize) -> anyhow::Result<Vec<u8>> {
+    let decoder = payload::decoder(payload::open_payload_reader(info)?, &info.metadata.compressor)?;
+    let mut archive = tar::Archive::new(decoder);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        if normalize(&entry.path()?) == path {
+            let mut head = Vec::with_capacity(len);
+            entry.take(len as u64).read_to_end(&mut head)?;
+            return Ok(head);
+        }
+    }
+    anyhow::bail!("{} not found in the payload", path.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, kind: EntryKind, target: Option<&str>) -> (PathBuf, EntryInfo) {
+        let info = EntryInfo {
+            path: PathBuf::from(path),
+            size: 0,
+            mode: 0o755,
+            kind,
+            link_target: target.map(PathBuf::from),
+        };
+        (PathBuf::from(path), info)
+    }
+
+    #[test]
+    fn test_validation_helpers() {
+        let entries: HashMap<PathBuf, EntryInfo> = [
+            entry("lib/app/app", EntryKind::File, None),
+            entry("bin/app", EntryKind::Symlink, Some("../lib/app/app")),
+            entry("bin/loop", EntryKind::Symlink, Some("loop")),
+        ].into_iter().collect();
+        assert_eq!(resolve(&entries, Path::new("./bin/app")), Some(PathBuf::from("lib/app/app")));
+        assert_eq!(resolve(&entries, Path::new("bin/loop")), None);
+        assert_eq!(resolve(&entries, Path::new("bin/missing")), None);
+
+        let mut header = [0u8; 20];
+        header[..4].copy_from_slice(b"\x7fELF");
+        header[5] = 1;
+        header[18] = 0xb7;
+        assert_eq!(elf_machine(&header).and_then(elf_arch), Some("aarch64"));
+        assert_eq!(elf_machine(b"#!/bin/sh\n"), None);
+
+        assert_eq!(check_categories(&["Utility".into(), "X-Custom".into()]).status, Status::Pass);
+        assert_eq!(check_categories(&["Utilities".into()]).status, Status::Fail);
+        assert_eq!(check_categories(&[]).status, Status::Warn);
+    }
+}
@@ -0,0 +1,198 @@
+//! Rebuild on Changes (`lxe build --watch`)
+//!
+//! Watches the `[build] input` directory and `lxe.toml`. With a build script,
+//! the script's sources can be anywhere, so the whole project directory is
+//! watched instead. Events are debounced: a rebuild starts once nothing has
+//! changed for [`DEBOUNCE`].
+//!
+//! A build writes files itself, so what it writes is never watched: `target/`,
+//! the artifacts and output directories, packages, and with a build script
+//! the input directory (the script's output). Other events that arrive while
+//! a build runs are discarded.
+
+use anyhow::{Context, Result};
+use lxe_common::config::LxeConfig;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Quiet period before a rebuild
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Directories and files whose changes trigger a rebuild
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchTargets {
+    /// Watched recursively
+    pub dirs: Vec<PathBuf>,
+    /// Watched through their parent directory, so editors replacing the file
+    /// don't end the watch
+    pub files: Vec<PathBuf>,
+    /// Directories the build writes to, inside the watched ones
+    pub ignored: Vec<PathBuf>,
+}
+
+impl WatchTargets {
+    /// `build_dirs` are further directories the build writes to
+    /// (`--target-dir`, `--output-dir`)
+    pub fn new(config: &LxeConfig, config_file: &Path, base_dir: &Path, build_dirs: &[PathBuf]) -> Self {
+        let input = clean_path(&base_dir.join(&config.build.input));
+        let dir = if config.build.script.is_some() {
+            base_dir.to_path_buf()
+        } else {
+            input.clone()
+        };
+        
+        let output_dir = clean_path(&config.output_path(base_dir)).parent().map(Path::to_path_buf);
+        let script_output = config.build.script.as_ref().map(|_| input);
+        let ignored = [base_dir.join("target")].into_iter()
+            .chain(build_dirs.iter().map(|dir| clean_path(&base_dir.join(dir))))
+            .chain(output_dir)
+            .chain(script_output)
+            // Never the watched directory itself, or one containing it
+            .filter(|ignored| !dir.starts_with(ignored))
+            .collect();
+        Self { dirs: vec![dir], files: vec![config_file.to_path_buf()], ignored }
+    }
+
+    /// Whether a change to `path` should trigger a rebuild
+    pub fn matches(&self, path: &Path) -> bool {
+        if path.components().any(|c| c == Component::Normal(".git".as_ref())) {
+            return false;
+        }
+        if self.files.iter().any(|file| file == path) {
+            return true;
+        }
+        // Packages and their `.partial`/`.bak` siblings
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.ends_with(".lxe") || name.contains(".lxe.") {
+            return false;
+        }
+        self.dirs.iter().any(|dir| path.starts_with(dir)) && !self.ignored.iter().any(|dir| path.starts_with(dir))
+    }
+}
+
+/// `path` without `./` components, so event paths can be matched by prefix
+fn clean_path(path: &Path) -> PathBuf {
+    path.components().filter(|c| *c != Component::CurDir).collect()
+}
+
+/// Local time of day (HH:MM:SS) for the rebuild headers
+pub fn local_time() -> String {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return String::from("??:??:??");
+    }
+    format!("{:02}:{:02}:{:02}", tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+/// File system watcher for [`WatchTargets`]
+pub struct BuildWatcher {
+    targets: WatchTargets,
+    events: Receiver<notify::Result<Event>>,
+    // Stops watching when dropped
+    _watcher: notify::RecommendedWatcher,
+}
+
+impl BuildWatcher {
+    pub fn new(targets: WatchTargets) -> Result<Self> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)
+            .context("Failed to start the file watcher")?;
+        for dir in &targets.dirs {
+            watcher.watch(dir, RecursiveMode::Recursive)
+                .with_context(|| format!("Failed to watch {}", dir.display()))?;
+        }
+        for file in &targets.files {
+            let parent = file.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            watcher.watch(parent, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Failed to watch {}", file.display()))?;
+        }
+        Ok(Self { targets, events, _watcher: watcher })
+    }
+
+    /// Block until a watched path is created, modified or removed and no
+    /// watched path has changed for [`DEBOUNCE`]; returns the changed paths
+    pub fn wait_for_change(&self) -> Result<Vec<PathBuf>> {
+        let mut changed = Vec::new();
+        while changed.is_empty() {
+            let event = self.events.recv().context("File watcher stopped")?;
+            self.collect(event, &mut changed);
+        }
+        // Other events don't extend the quiet period
+        let mut quiet_until = Instant::now() + DEBOUNCE;
+        loop {
+            match self.events.recv_timeout(quiet_until.saturating_duration_since(Instant::now())) {
+                Ok(event) => {
+                    if self.collect(event, &mut changed) {
+                        quiet_until = Instant::now() + DEBOUNCE;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => return Ok(changed),
+                Err(RecvTimeoutError::Disconnected) => anyhow::bail!("File watcher stopped"),
+            }
+        }
+    }
+
+    /// Discard the events so far, and those arriving within [`DEBOUNCE`]
+    /// (a build's last writes)
+    pub fn discard_pending(&self) {
+        let until = Instant::now() + DEBOUNCE;
+        while self.events.recv_timeout(until.saturating_duration_since(Instant::now())).is_ok() {}
+    }
+
+    /// Add the watched paths of `event` to `changed`; returns whether it had any
+    fn collect(&self, event: notify::Result<Event>, changed: &mut Vec<PathBuf>) -> bool {
+        let Ok(event) = event else {
+            return false;
+        };
+        if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)) {
+            return false;
+        }
+        let mut any = false;
+        for path in event.paths {
+            if self.targets.matches(&path) {
+                any = true;
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+        any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch_targets() {
+        let base = Path::new("/project");
+        let mut config = LxeConfig::from_str(r#"
+            [package]
+            name = "App"
+            id = "com.example.app"
+            version = "1.0.0"
+            executable = "app"
+        "#).unwrap();
+        config.build.input = "./dist".into();
+        let targets = WatchTargets::new(&config, &base.join("lxe.toml"), base, &[]);
+        assert_eq!(targets.dirs, [PathBuf::from("/project/dist")]);
+        assert!(targets.matches(Path::new("/project/dist/app")));
+        assert!(targets.matches(Path::new("/project/lxe.toml")));
+        assert!(!targets.matches(Path::new("/project/App-1.0.0.lxe")));
+        assert!(!targets.matches(Path::new("/project/src/main.rs")));
+
+        config.build.script = Some("make".into());
+        config.build.output = Some("./out/App.lxe".into());
+        let targets = WatchTargets::new(&config, &base.join("lxe.toml"), base, &[PathBuf::from("build-tmp")]);
+        assert!(targets.matches(Path::new("/project/src/main.rs")));
+        assert!(!targets.matches(Path::new("/project/.git/index")));
+        // Nothing the build writes triggers another one
+        for written in ["target/debug/app", "dist/app", "out/App.lxe", "build-tmp/lxe-build-x/archive.tar", "App.lxe.partial"] {
+            assert!(!targets.matches(&base.join(written)), "{}", written);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Multi-Package Workspaces
+//!
+//! A workspace is a directory with an `lxe-workspace.toml` listing member
+//! directories, each containing its own `lxe.toml`:
+//!
+//! ```toml
+//! [workspace]
+//! members = ["core", "plugins/editor", "app"]
+//! ```
+//!
+//! Members declare build order with `[build] depends_on = ["core"]`, using
+//! member names (the last path segment of each member entry).
+
+use anyhow::{Context, Result};
+use lxe_common::config::LxeConfig;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Workspace manifest file name
+pub const WORKSPACE_FILE: &str = "lxe-workspace.toml";
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceFile {
+    workspace: WorkspaceSection,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkspaceSection {
+    members: Vec<String>,
+}
+
+/// One package in the workspace
+#[derive(Debug)]
+pub struct WorkspaceMember {
+    /// Member name (last segment of the member path)
+    pub name: String,
+
+    /// Member directory
+    pub dir: PathBuf,
+
+    /// Parsed lxe.toml of the member
+    pub config: LxeConfig,
+
+    /// Names of members that must be built first
+    pub depends_on: Vec<String>,
+}
+
+impl WorkspaceMember {
+    /// Load every member listed in `lxe-workspace.toml`
+    pub fn load_all(workspace_dir: &Path) -> Result<Vec<WorkspaceMember>> {
+        let manifest_path = workspace_dir.join(WORKSPACE_FILE);
+        let content = std::fs::read_to_string(&manifest_path).with_context(|| {
+            format!(
+                "No {} found in {}\n\
+                 Create one with:\n\n\
+                 [workspace]\n\
+                 members = [\"app-one\", \"app-two\"]",
+                WORKSPACE_FILE,
+                workspace_dir.display()
+            )
+        })?;
+        let manifest: WorkspaceFile = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+
+        let mut members = Vec::new();
+        for entry in &manifest.workspace.members {
+            let dir = workspace_dir.join(entry);
+            let name = Path::new(entry)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| entry.clone());
+            let config = LxeConfig::from_file(&dir.join("lxe.toml"))
+                .with_context(|| format!("Failed to load workspace member '{}'", name))?;
+            let depends_on = config.build.depends_on.clone();
+
+            members.push(WorkspaceMember { name, dir, config, depends_on });
+        }
+
+        Ok(members)
+    }
+}
+
+/// Group members into build levels using Kahn's algorithm
+///
+/// Every member in a level only depends on members in earlier levels,
+/// so a level can be built in parallel. Returns indices into `members`.
+pub fn build_levels(members: &[WorkspaceMember]) -> Result<Vec<Vec<usize>>> {
+    let index: HashMap<&str, usize> = members.iter()
+        .enumerate()
+        .map(|(i, m)| (m.name.as_str(), i))
+        .collect();
+
+    if index.len() != members.len() {
+        anyhow::bail!("Workspace has duplicate member names");
+    }
+
+    let mut in_degree = vec![0usize; members.len()];
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); members.len()];
+    for (i, member) in members.iter().enumerate() {
+        for dep in &member.depends_on {
+            let &d = index.get(dep.as_str()).ok_or_else(|| {
+                anyhow::anyhow!("Member '{}' depends on unknown member '{}'", member.name, dep)
+            })?;
+            in_degree[i] += 1;
+            dependents[d].push(i);
+        }
+    }
+
+    let mut levels = Vec::new();
+    let mut current: Vec<usize> = (0..members.len()).filter(|&i| in_degree[i] == 0).collect();
+    let mut placed = 0;
+
+    while !current.is_empty() {
+        placed += current.len();
+        let mut next = Vec::new();
+        for &i in &current {
+            for &dependent in &dependents[i] {
+                in_degree[dependent] -= 1;
+                if in_degree[dependent] == 0 {
+                    next.push(dependent);
+                }
+            }
+        }
+        levels.push(current);
+        current = next;
+    }
+
+    if placed != members.len() {
+        let cycle: Vec<&str> = (0..members.len())
+            .filter(|&i| in_degree[i] > 0)
+            .map(|i| members[i].name.as_str())
+            .collect();
+        anyhow::bail!("Dependency cycle between workspace members: {}", cycle.join(", "));
+    }
+
+    Ok(levels)
+}
+
+/// Outcome of one member build
+#[derive(Debug)]
+pub enum BuildOutcome {
+    Built(Duration),
+    Failed(Duration, String),
+    /// Not built because a dependency failed
+    Skipped(String),
+}
+
+/// Build all members level by level, at most `jobs` at a time
+///
+/// `build` is called once per member; members whose dependencies failed
+/// are skipped rather than built.
+pub fn build_all<F>(
+    members: &[WorkspaceMember],
+    levels: &[Vec<usize>],
+    jobs: usize,
+    build: F,
+) -> Result<BTreeMap<String, BuildOutcome>>
+where
+    F: Fn(&WorkspaceMember) -> Result<()> + Sync,
+{
+    use rayon::prelude::*;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs.max(1))
+        .build()
+        .context("Failed to create build thread pool")?;
+
+    let mut outcomes = BTreeMap::new();
+    let mut failed: HashSet<&str> = HashSet::new();
+
+    for level in levels {
+        let (runnable, blocked): (Vec<usize>, Vec<usize>) = level.iter().partition(|&&i| {
+            members[i].depends_on.iter().all(|d| !failed.contains(d.as_str()))
+        });
+
+        for i in blocked {
+            let member = &members[i];
+            let culprit = member.depends_on.iter()
+                .find(|d| failed.contains(d.as_str()))
+                .cloned()
+                .unwrap_or_default();
+            failed.insert(member.name.as_str());
+            outcomes.insert(member.name.clone(), BuildOutcome::Skipped(culprit));
+        }
+
+        let results: Vec<(usize, BuildOutcome)> = pool.install(|| {
+            runnable.par_iter().map(|&i| {
+                let start = std::time::Instant::now();
+                let outcome = match build(&members[i]) {
+                    Ok(()) => BuildOutcome::Built(start.elapsed()),
+                    Err(e) => BuildOutcome::Failed(start.elapsed(), format!("{:#}", e)),
+                };
+                (i, outcome)
+            }).collect()
+        });
+
+        for (i, outcome) in results {
+            if matches!(outcome, BuildOutcome::Failed(..)) {
+                failed.insert(members[i].name.as_str());
+            }
+            outcomes.insert(members[i].name.clone(), outcome);
+        }
+    }
+
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(name: &str, deps: &[&str]) -> WorkspaceMember {
+        let config = LxeConfig::from_str(&format!(
+            "[package]\nname = \"{0}\"\nid = \"com.example.{0}\"\nversion = \"1.0.0\"\nexecutable = \"{0}\"\n",
+            name
+        )).unwrap();
+        WorkspaceMember {
+            name: name.to_string(),
+            dir: PathBuf::from(name),
+            config,
+            depends_on: deps.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_build_levels() {
+        let members = vec![
+            member("app", &["core", "ui"]),
+            member("core", &[]),
+            member("ui", &["core"]),
+        ];
+        let levels = build_levels(&members).unwrap();
+        assert_eq!(levels, vec![vec![1], vec![2], vec![0]]);
+
+        let cyclic = vec![member("a", &["b"]), member("b", &["a"])];
+        assert!(build_levels(&cyclic).is_err());
+    }
+
+    #[test]
+    fn test_failed_dependency_skips_downstream() {
+        let members = vec![member("core", &[]), member("app", &["core"])];
+        let levels = build_levels(&members).unwrap();
+        let outcomes = build_all(&members, &levels, 2, |m| {
+            if m.name == "core" { anyhow::bail!("boom") } else { Ok(()) }
+        }).unwrap();
+
+        assert!(matches!(outcomes["core"], BuildOutcome::Failed(..)));
+        assert!(matches!(outcomes["app"], BuildOutcome::Skipped(ref d) if d == "core"));
+    }
+}
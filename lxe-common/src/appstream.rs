@@ -0,0 +1,164 @@
+//! AppStream Metadata
+//!
+//! Software centers (GNOME Software, KDE Discover) list apps from AppStream
+//! metainfo files. `lxe build` can write one next to the package
+//! (`[build] generate_metainfo`) and the installer puts one in
+//! `share/metainfo`, both generated from the package metadata.
+
+use crate::metadata::{LxeMetadata, ReleaseNote};
+use std::fmt::Write;
+
+/// File name of the metainfo file of `app_id`
+pub fn metainfo_filename(app_id: &str) -> String {
+    format!("{}.metainfo.xml", app_id)
+}
+
+/// Check that a release can be written to AppStream (version and date)
+pub fn validate_release(release: &ReleaseNote) -> anyhow::Result<()> {
+    if release.version.trim().is_empty() {
+        anyhow::bail!("Release needs a version");
+    }
+    if let Some(ref date) = release.date {
+        let valid = date.len() == 10
+            && date.char_indices().all(|(i, c)| if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() });
+        if !valid {
+            anyhow::bail!("Invalid date '{}' of release {} (expected YYYY-MM-DD)", date, release.version);
+        }
+    }
+    Ok(())
+}
+
+/// AppStream 0.14 `desktop-application` component for the package
+///
+/// Without declared releases, the package's own version is listed.
+pub fn generate_metainfo(metadata: &LxeMetadata) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<component type=\"desktop-application\">\n");
+    let _ = writeln!(xml, "  <id>{}</id>", escape(&metadata.app_id));
+    xml.push_str("  <metadata_license>CC0-1.0</metadata_license>\n");
+    if let Some(ref license) = metadata.license {
+        let _ = writeln!(xml, "  <project_license>{}</project_license>", escape(license));
+    }
+    let _ = writeln!(xml, "  <name>{}</name>", escape(&metadata.name));
+
+    let summary = metadata.description.as_deref()
+        .and_then(|d| d.lines().next())
+        .unwrap_or(&metadata.name);
+    let _ = writeln!(xml, "  <summary>{}</summary>", escape(summary));
+    if let Some(ref description) = metadata.description {
+        let _ = writeln!(xml, "  <description>\n{}  </description>", paragraphs(description, "    "));
+    }
+
+    let _ = writeln!(xml, "  <launchable type=\"desktop-id\">{}</launchable>", escape(&metadata.desktop_filename()));
+    if let Some(ref homepage) = metadata.homepage {
+        let _ = writeln!(xml, "  <url type=\"homepage\">{}</url>", escape(homepage));
+    }
+
+    let categories: Vec<&String> = metadata.categories.iter().filter(|c| *c != "Application").collect();
+    if !categories.is_empty() {
+        xml.push_str("  <categories>\n");
+        for category in categories {
+            let _ = writeln!(xml, "    <category>{}</category>", escape(category));
+        }
+        xml.push_str("  </categories>\n");
+    }
+
+    xml.push_str("  <provides>\n");
+    let command = std::path::Path::new(&metadata.exec)
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| metadata.exec.clone());
+    let _ = writeln!(xml, "    <binary>{}</binary>", escape(&command));
+    for mime_type in &metadata.mime_types {
+        let _ = writeln!(xml, "    <mediatype>{}</mediatype>", escape(mime_type));
+    }
+    xml.push_str("  </provides>\n");
+
+    if !metadata.screenshots.is_empty() {
+        xml.push_str("  <screenshots>\n");
+        for (i, url) in metadata.screenshots.iter().enumerate() {
+            let kind = if i == 0 { " type=\"default\"" } else { "" };
+            let _ = writeln!(xml, "    <screenshot{}>\n      <image>{}</image>\n    </screenshot>", kind, escape(url));
+        }
+        xml.push_str("  </screenshots>\n");
+    }
+
+    let current = [ReleaseNote { version: metadata.version.clone(), date: None, description: None }];
+    let releases = if metadata.releases.is_empty() { &current[..] } else { &metadata.releases[..] };
+    xml.push_str("  <releases>\n");
+    for release in releases {
+        let date = release.date.as_ref()
+            .map(|d| format!(" date=\"{}\"", escape(d)))
+            .unwrap_or_default();
+        match release.description {
+            Some(ref description) => {
+                let _ = writeln!(
+                    xml,
+                    "    <release version=\"{}\"{}>\n      <description>\n{}      </description>\n    </release>",
+                    escape(&release.version),
+                    date,
+                    paragraphs(description, "        ")
+                );
+            }
+            None => {
+                let _ = writeln!(xml, "    <release version=\"{}\"{}/>", escape(&release.version), date);
+            }
+        }
+    }
+    xml.push_str("  </releases>\n");
+
+    xml.push_str("  <content_rating type=\"oars-1.1\"/>\n");
+    xml.push_str("</component>\n");
+    xml
+}
+
+/// `<p>` elements for the blank-line separated paragraphs of `text`
+fn paragraphs(text: &str, indent: &str) -> String {
+    text.split("\n\n")
+        .map(|p| p.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|p| !p.is_empty())
+        .map(|p| format!("{}<p>{}</p>\n", indent, escape(&p)))
+        .collect()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_metainfo() {
+        let mut metadata = LxeMetadata::new("com.example.Editor", "Editor", "2.0.0", "bin/editor", 0, "");
+        metadata.description = Some("Edits <text> & more".into());
+        metadata.homepage = Some("https://example.com".into());
+        metadata.mime_types = vec!["text/markdown".into()];
+        metadata.screenshots = vec!["https://example.com/main.png".into()];
+        metadata.releases = vec![
+            ReleaseNote { version: "2.0.0".into(), date: Some("2024-05-01".into()), description: Some("New UI.\n\nFaster.".into()) },
+            ReleaseNote { version: "1.0.0".into(), date: None, description: None },
+        ];
+
+        let xml = generate_metainfo(&metadata);
+        assert!(xml.contains("<component type=\"desktop-application\">"));
+        assert!(xml.contains("<summary>Edits &lt;text&gt; &amp; more</summary>"));
+        assert!(xml.contains("<launchable type=\"desktop-id\">com.example.Editor.desktop</launchable>"));
+        assert!(xml.contains("<binary>editor</binary>"));
+        assert!(xml.contains("<mediatype>text/markdown</mediatype>"));
+        assert!(xml.contains("<screenshot type=\"default\">"));
+        assert!(xml.contains("<release version=\"2.0.0\" date=\"2024-05-01\">"));
+        assert!(xml.contains("        <p>New UI.</p>\n        <p>Faster.</p>\n"));
+        assert!(xml.contains("<release version=\"1.0.0\"/>"));
+        assert!(!xml.contains("<categories>"));
+
+        assert!(validate_release(&metadata.releases[0]).is_ok());
+        let bad = ReleaseNote { version: "1.0".into(), date: Some("May 1".into()), description: None };
+        assert!(validate_release(&bad).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+//! Assembling Packages from Separately Built Parts
+//!
+//! `lxe build --manifest-only` stops after compressing and signing, leaving
+//! the metadata JSON and compressed payload as separate files. `lxe assemble`
+//! later appends them to a runtime, possibly on another machine, producing
+//! the same package `lxe build` would have.
+//!
+//! Only available with the `build` feature.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::builder;
+use crate::metadata::LxeMetadata;
+
+/// File names `lxe build --manifest-only` writes next to the package output
+pub fn part_paths(output_dir: &Path, app_id: &str) -> (PathBuf, PathBuf) {
+    (
+        output_dir.join(format!("{}.lxe.json", app_id)),
+        output_dir.join(format!("{}.tar.zst", app_id)),
+    )
+}
+
+/// Build a package from metadata JSON, compressed payload and runtime binary
+///
+/// The metadata bytes are embedded unchanged, so a signature made at build
+/// time stays valid. The payload must match the metadata's checksum.
+pub fn assemble(
+    metadata_path: &Path,
+    payload_path: &Path,
+    runtime: &[u8],
+    output_path: &Path,
+) -> Result<(LxeMetadata, u64)> {
+    let metadata_json = fs::read(metadata_path)
+        .with_context(|| format!("Failed to read {}", metadata_path.display()))?;
+    let metadata: LxeMetadata = serde_json::from_slice(&metadata_json)
+        .with_context(|| format!("{} is not LXE metadata", metadata_path.display()))?;
+    
+    let compressed = fs::read(payload_path)
+        .with_context(|| format!("Failed to read {}", payload_path.display()))?;
+    let checksum = crate::payload::calculate_checksum(&compressed, metadata.checksum_algorithm);
+    if checksum != metadata.payload_checksum {
+        anyhow::bail!(
+            "Payload doesn't match the metadata ({})\n  expected: {}\n  payload:  {}",
+            metadata.checksum_algorithm,
+            metadata.payload_checksum,
+            checksum
+        );
+    }
+    
+    if metadata.is_signed() {
+        crate::payload::verify_package_signature(&metadata)?;
+    }
+    
+    if !runtime.starts_with(b"\x7fELF") {
+        anyhow::bail!("The runtime is not an ELF binary");
+    }
+    
+    let size = builder::write_package(output_path, runtime, &metadata_json, &checksum, &compressed)?;
+    Ok((metadata, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_assemble_checks_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let compressed = builder::compress_zstd(b"payload", 3).unwrap();
+        let metadata = json!({
+            "format_version": 1,
+            "app_id": "com.example.Test",
+            "name": "Test",
+            "version": "1.0.0",
+            "arch": "x86_64",
+            "install_size": 7,
+            "exec": "app",
+            "categories": [],
+            "payload_checksum": builder::sha256_hex(&compressed),
+        });
+        
+        let (metadata_path, payload_path) = part_paths(dir.path(), "com.example.Test");
+        fs::write(&metadata_path, serde_json::to_vec(&metadata).unwrap()).unwrap();
+        fs::write(&payload_path, &compressed).unwrap();
+        let runtime = b"\x7fELF fake runtime";
+        
+        let output = dir.path().join("test.lxe");
+        let (metadata, _) = assemble(&metadata_path, &payload_path, runtime, &output).unwrap();
+        assert_eq!(metadata.app_id, "com.example.Test");
+        assert!(crate::payload::read_payload_info(&output).is_ok());
+        
+        fs::write(&payload_path, b"tampered").unwrap();
+        assert!(assemble(&metadata_path, &payload_path, runtime, &output).is_err());
+    }
+}
@@ -0,0 +1,721 @@
+//! Package Builder
+//!
+//! The steps that turn a project directory into a `.lxe` file: archive the
+//! input, compress it, describe it in metadata, sign it and append it all to
+//! the runtime. `lxe build` drives these with progress output; `lxe-build-rs`
+//! drives them from Rust build tooling without the CLI installed.
+//!
+//! Only available with the `build` feature.
+
+use anyhow::{Context, Result};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::{Compressor, LxeConfig};
+use crate::metadata::{DesktopAction, ExtraExecutable, ServiceMetadata, SignableMetadata, LXE_MAGIC};
+use crate::payload::{self, ChecksumAlgorithm, FooterV3};
+use crate::payload_format::{self, MetaStub};
+use crate::signing::KeyBackend;
+
+/// Tar the contents of `input_dir` (symlinks are stored, not followed)
+pub fn create_tar_archive(input_dir: &Path) -> Result<Vec<u8>> {
+    create_tar_archive_with(input_dir, &BTreeMap::new(), false)
+}
+
+/// Like `create_tar_archive`, but files whose path relative to `input_dir`
+/// is a key of `overrides` are archived from the mapped file instead
+/// (e.g., stripped binaries from `strip::strip_elf_files`)
+/// 
+/// With `reproducible` (`[build] reproducible`), entries are sorted by path
+/// and carry no timestamps or ownership, so the same input files give the
+/// same archive on any machine.
+pub fn create_tar_archive_with(
+    input_dir: &Path,
+    overrides: &BTreeMap<PathBuf, PathBuf>,
+    reproducible: bool,
+) -> Result<Vec<u8>> {
+    let mut archive_data = Vec::new();
+    write_tar_archive(input_dir, overrides, reproducible, &mut archive_data)?;
+    Ok(archive_data)
+}
+
+/// Like `create_tar_archive_with`, streaming the archive into `writer`
+/// (e.g., a file, so large inputs are never held in memory)
+pub fn write_tar_archive<W: Write>(
+    input_dir: &Path,
+    overrides: &BTreeMap<PathBuf, PathBuf>,
+    reproducible: bool,
+    writer: W,
+) -> Result<()> {
+    let mut builder = tar::Builder::new(writer);
+    builder.follow_symlinks(false);
+    if overrides.is_empty() && !reproducible {
+        builder.append_dir_all(".", input_dir)
+            .context("Failed to add directory to tar archive")?;
+    } else {
+        append_tree(&mut builder, input_dir, input_dir, overrides, reproducible)
+            .context("Failed to add directory to tar archive")?;
+    }
+    builder.into_inner()
+        .context("Failed to finish tar archive")?
+        .flush()?;
+    Ok(())
+}
+
+/// `append_dir_all`, with overridden file contents and optionally
+/// reproducible entries
+fn append_tree<W: Write>(
+    builder: &mut tar::Builder<W>,
+    root: &Path,
+    dir: &Path,
+    overrides: &BTreeMap<PathBuf, PathBuf>,
+    reproducible: bool,
+) -> std::io::Result<()> {
+    let rel = dir.strip_prefix(root).unwrap_or(dir);
+    if reproducible {
+        append_reproducible(builder, dir, &Path::new(".").join(rel))?;
+    } else {
+        builder.append_dir(Path::new(".").join(rel), dir)?;
+    }
+    
+    let mut entries = fs::read_dir(dir)?.collect::<std::io::Result<Vec<_>>>()?;
+    if reproducible {
+        // Directory order depends on the filesystem
+        entries.sort_by_key(|entry| entry.file_name());
+    }
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            append_tree(builder, root, &path, overrides, reproducible)?;
+            continue;
+        }
+        
+        let rel = path.strip_prefix(root).unwrap_or(&path);
+        let source = overrides.get(rel).unwrap_or(&path);
+        if reproducible {
+            append_reproducible(builder, source, &Path::new(".").join(rel))?;
+        } else {
+            builder.append_path_with_name(source, Path::new(".").join(rel))?;
+        }
+    }
+    Ok(())
+}
+
+/// Append `source` as `name` with mtime, uid and gid 0 and no user or group
+/// names; permission bits are kept
+fn append_reproducible<W: Write>(
+    builder: &mut tar::Builder<W>,
+    source: &Path,
+    name: &Path,
+) -> std::io::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+    
+    let meta = fs::symlink_metadata(source)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_metadata_in_mode(&meta, tar::HeaderMode::Deterministic);
+    header.set_mtime(0);
+    header.set_mode(meta.mode() & 0o7777);
+    
+    let file_type = meta.file_type();
+    if file_type.is_symlink() {
+        header.set_size(0);
+        builder.append_link(&mut header, name, fs::read_link(source)?)
+    } else if file_type.is_file() {
+        builder.append_data(&mut header, name, File::open(source)?)
+    } else {
+        header.set_size(0);
+        builder.append_data(&mut header, name, std::io::empty())
+    }
+}
+
+/// Compress `data` with `compressor`
+pub fn compress(data: &[u8], compressor: &Compressor) -> Result<Vec<u8>> {
+    match *compressor {
+        Compressor::Zstd { level } => compress_zstd(data, level),
+        Compressor::Lz4 { level } => {
+            let mut encoder = lz4_encoder(Vec::new(), level)?;
+            encoder.write_all(data).context("Failed to compress with lz4")?;
+            let (compressed, result) = encoder.finish();
+            result.context("Failed to compress with lz4")?;
+            Ok(compressed)
+        }
+    }
+}
+
+pub fn compress_zstd(data: &[u8], level: i32) -> Result<Vec<u8>> {
+    zstd::encode_all(std::io::Cursor::new(data), level)
+        .context("Failed to compress with zstd")
+}
+
+/// Compress the file at `input` into `output`; returns the compressed size
+pub fn compress_zstd_file(input: &Path, output: &Path, level: i32) -> Result<u64> {
+    let source = File::open(input)
+        .with_context(|| format!("Failed to open {}", input.display()))?;
+    compress_zstd_stream(source, output, level, None)
+}
+
+/// Compress `source` into `output`; returns the compressed size
+///
+/// `window_log` limits the match window (and so the memory used) below
+/// what `level` would pick.
+pub fn compress_zstd_stream<R: Read>(source: R, output: &Path, level: i32, window_log: Option<u32>) -> Result<u64> {
+    let target = File::create(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut encoder = zstd::stream::Encoder::new(target, level)
+        .context("Failed to compress with zstd")?;
+    if let Some(window_log) = window_log {
+        encoder.window_log(window_log).context("Invalid zstd window size")?;
+    }
+    std::io::copy(&mut std::io::BufReader::new(source), &mut encoder)
+        .context("Failed to compress with zstd")?;
+    encoder.finish().context("Failed to compress with zstd")?.flush()?;
+    Ok(fs::metadata(output)?.len())
+}
+
+/// Compress `source` into `output` with `compressor`; returns the compressed size
+///
+/// `window_log` only applies to zstd (LZ4 uses a fixed 64 KB window).
+pub fn compress_stream<R: Read>(source: R, output: &Path, compressor: &Compressor, window_log: Option<u32>) -> Result<u64> {
+    let level = match *compressor {
+        Compressor::Zstd { level } => return compress_zstd_stream(source, output, level, window_log),
+        Compressor::Lz4 { level } => level,
+    };
+    let target = File::create(output)
+        .with_context(|| format!("Failed to create {}", output.display()))?;
+    let mut encoder = lz4_encoder(target, level)?;
+    std::io::copy(&mut std::io::BufReader::new(source), &mut encoder)
+        .context("Failed to compress with lz4")?;
+    let (mut target, result) = encoder.finish();
+    result.context("Failed to compress with lz4")?;
+    target.flush()?;
+    Ok(fs::metadata(output)?.len())
+}
+
+fn lz4_encoder<W: Write>(target: W, level: i32) -> Result<lz4::Encoder<W>> {
+    lz4::EncoderBuilder::new()
+        .level(level.max(0) as u32)
+        .build(target)
+        .context("Failed to compress with lz4")
+}
+
+/// Streaming decoder for a payload compressed with `compressor`
+///
+/// Like [`payload::decoder`], but with the (faster) zstd C library.
+pub fn decoder<'a, R: Read + Send + 'a>(reader: R, compressor: &str) -> Result<Box<dyn Read + Send + 'a>> {
+    match compressor {
+        "zstd" => Ok(Box::new(zstd::Decoder::new(reader).context("Payload is not zstd-compressed")?)),
+        other => payload::decoder(reader, other),
+    }
+}
+
+/// Hex-encoded SHA256 of `data`
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Window hints detected at build time (see `window_hints::detect`)
+#[derive(Debug, Clone, Default)]
+pub struct BuildHints {
+    /// Overrides `package.wm_class` when set
+    pub wm_class: Option<String>,
+    pub dbus_activatable: bool,
+}
+
+/// Metadata JSON for a package built from `config`
+/// 
+/// `install_size` is the uncompressed tar size, `checksum` the hex digest of
+/// the compressed payload in `config.build.checksum_algorithm`.
+pub fn package_metadata(
+    config: &LxeConfig,
+    base_dir: &Path,
+    install_size: u64,
+    checksum: &str,
+    hints: &BuildHints,
+) -> serde_json::Value {
+    let mut metadata = json!({
+        "format_version": 1,
+        "app_id": config.package.id,
+        "name": config.package.name,
+        "version": config.package.version,
+        "arch": std::env::consts::ARCH,
+        "install_size": install_size,
+        "exec": config.package.executable,
+        "icon": config.package.icon,
+        "description": config.package.description,
+        "categories": config.package.categories,
+        "terminal": config.package.terminal,
+        "wm_class": hints.wm_class.as_ref().or(config.package.wm_class.as_ref()),
+        "dbus_activatable": hints.dbus_activatable,
+        "desktop_actions": config.package.actions,
+        "extra_executables": config.package.extra_executables,
+        "min_glibc_version": config.package.min_glibc,
+        "min_kernel_version": config.package.min_kernel,
+        "payload_checksum": checksum,
+        // Installer customization
+        "installer": {
+            "welcome_title": config.installer.welcome_title,
+            "welcome_text": config.installer.welcome_text,
+            "finish_title": config.installer.finish_title,
+            "finish_text": config.installer.finish_text,
+            "accent_color": config.installer.accent_color,
+            "theme": config.installer.theme,
+            "show_launch": config.installer.show_launch.unwrap_or(true),
+            // Advanced branding - read license file if specified
+            "license_text": config.installer.license.as_ref().and_then(|p| {
+                fs::read_to_string(base_dir.join(p)).ok()
+            }),
+            "banner": config.installer.banner,
+            "logo": config.installer.logo,
+            "allow_custom_dir": config.installer.allow_custom_dir.unwrap_or(false),
+        },
+    });
+    
+    if let Some(ref homepage) = config.package.homepage {
+        metadata["homepage"] = json!(homepage);
+    }
+    if !config.package.screenshots.is_empty() {
+        metadata["screenshots"] = json!(config.package.screenshots);
+    }
+    if !config.package.releases.is_empty() {
+        metadata["releases"] = json!(config.package.releases);
+    }
+    if !config.package.config_paths.is_empty() {
+        metadata["config_paths"] = json!(config.package.config_paths);
+    }
+    if let Some(ref service) = config.service {
+        metadata["service"] = json!(service);
+    }
+    if !config.package.mime_types.is_empty() {
+        metadata["mime_types"] = json!(config.package.mime_types);
+    }
+    if !config.package.dependencies.is_empty() {
+        metadata["dependencies"] = json!(config.package.dependencies);
+    }
+    
+    // Omitted for SHA256 so older runtimes read the metadata unchanged
+    let algo = config.build.checksum_algorithm;
+    if !algo.is_default() {
+        metadata["checksum_algorithm"] = json!(algo);
+    }
+    if !config.build.compressor.is_default() {
+        metadata["compressor"] = json!(config.build.compressor);
+    }
+    metadata
+}
+
+/// One PNG rendered by [`render_svg_icon`], relative to the input directory
+#[derive(Debug, Clone)]
+pub struct RenderedIcon {
+    pub path: PathBuf,
+    pub size: u32,
+    /// `false` if the PNG was already up to date
+    pub updated: bool,
+}
+
+/// Outcome of [`render_svg_icon`]
+#[derive(Debug, Clone, Default)]
+pub struct SvgIcon {
+    /// The icon to put in the metadata: the PNG with `convert_icon_to_png`,
+    /// else `package.icon` unchanged
+    pub package_icon: Option<String>,
+    pub rendered: Vec<RenderedIcon>,
+}
+
+/// Render an SVG `package.icon` into the input directory as `[build]` asks
+/// (`convert_icon_to_png`, `generate_icon_sizes`); other icons are left alone
+///
+/// The installer only sizes PNG icons, so this saves it needing librsvg.
+pub fn render_svg_icon(config: &LxeConfig, input_dir: &Path) -> Result<SvgIcon> {
+    let mut result = SvgIcon { package_icon: config.package.icon.clone(), rendered: Vec::new() };
+    let Some(icon) = config.package.icon.as_deref().filter(|i| i.ends_with(".svg")) else {
+        return Ok(result);
+    };
+    let svg = input_dir.join(icon);
+    let mut renders = Vec::new();
+    if config.build.convert_icon_to_png {
+        let png = Path::new(icon).with_extension("png").to_string_lossy().into_owned();
+        renders.push((PathBuf::from(&png), config.build.icon_target_size));
+        result.package_icon = Some(png);
+    }
+    for &size in &config.build.generate_icon_sizes {
+        renders.push((crate::icon::hicolor_icon_path(icon, size), size));
+    }
+    for (path, size) in renders {
+        let updated = crate::icon::svg_to_png(&svg, size, &input_dir.join(&path))?;
+        result.rendered.push(RenderedIcon { path, size, updated });
+    }
+    Ok(result)
+}
+
+/// Write `<app_id>.metainfo.xml` next to `package_path` if
+/// `[build] generate_metainfo` is set; returns the file written
+pub fn write_metainfo(config: &LxeConfig, metadata: &serde_json::Value, package_path: &Path) -> Result<Option<PathBuf>> {
+    if !config.build.generate_metainfo {
+        return Ok(None);
+    }
+    let metadata: crate::metadata::LxeMetadata = serde_json::from_value(metadata.clone())?;
+    let path = package_path.with_file_name(crate::appstream::metainfo_filename(&metadata.app_id));
+    fs::write(&path, crate::appstream::generate_metainfo(&metadata))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(Some(path))
+}
+
+/// Sign `metadata` in place with `key`
+pub fn sign_metadata(
+    metadata: &mut serde_json::Value,
+    key: &KeyBackend,
+    checksum: &str,
+) -> Result<()> {
+    // Create signable data using the EXACT same struct as verification
+    let app_id = metadata["app_id"].as_str().ok_or(anyhow::anyhow!("Missing app_id"))?;
+    let name = metadata["name"].as_str().ok_or(anyhow::anyhow!("Missing name"))?;
+    let version = metadata["version"].as_str().ok_or(anyhow::anyhow!("Missing version"))?;
+    let arch = metadata["arch"].as_str().ok_or(anyhow::anyhow!("Missing arch"))?;
+    let install_size = metadata["install_size"].as_u64().ok_or(anyhow::anyhow!("Missing install_size"))?;
+    let exec = metadata["exec"].as_str().ok_or(anyhow::anyhow!("Missing exec"))?;
+    let icon = metadata["icon"].as_str();
+    let description = metadata["description"].as_str();
+    let payload_checksum = metadata["payload_checksum"].as_str().ok_or(anyhow::anyhow!("Missing payload_checksum"))?;
+    let terminal = metadata["terminal"].as_bool().unwrap_or(false);
+    
+    // Convert categories array
+    let categories: Vec<String> = metadata["categories"]
+        .as_array()
+        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    
+    let mime_types: Vec<String> =
+        serde_json::from_value(metadata["mime_types"].clone()).unwrap_or_default();
+    let desktop_actions: Vec<DesktopAction> =
+        serde_json::from_value(metadata["desktop_actions"].clone()).unwrap_or_default();
+    let extra_executables: Vec<ExtraExecutable> =
+        serde_json::from_value(metadata["extra_executables"].clone()).unwrap_or_default();
+    let checksum_algorithm: ChecksumAlgorithm =
+        serde_json::from_value(metadata["checksum_algorithm"].clone()).unwrap_or_default();
+    let service: Option<ServiceMetadata> =
+        serde_json::from_value(metadata["service"].clone()).unwrap_or_default();
+        
+    let signable = SignableMetadata {
+        format_version: 1,
+        app_id,
+        name,
+        version,
+        arch,
+        install_size,
+        exec,
+        icon,
+        categories: &categories,
+        mime_types: &mime_types,
+        description,
+        payload_checksum,
+        checksum_algorithm,
+        min_runtime_version: None,
+        license: None,
+        homepage: metadata["homepage"].as_str(),
+        exec_args: None,
+        terminal,
+        desktop_actions: &desktop_actions,
+        extra_executables: &extra_executables,
+        service: service.as_ref(),
+        min_glibc_version: metadata["min_glibc_version"].as_str(),
+        min_kernel_version: metadata["min_kernel_version"].as_str(),
+        delta_base_checksum: metadata["delta_base_checksum"].as_str(),
+        delta_target_checksum: metadata["delta_target_checksum"].as_str(),
+        delta_target_tree: metadata["delta_target_tree"].as_str(),
+        delta_format_version: metadata["delta_format_version"].as_u64().map(|v| v as u8),
+        deprecated: metadata["deprecated"].as_bool().unwrap_or(false),
+        deprecation_message: metadata["deprecation_message"].as_str(),
+    };
+    
+    let signable_json = serde_json::to_vec(&signable)?;
+    
+    // Create final blob to sign (metadata + checksum bytes)
+    let checksum_bytes = hex::decode(checksum)?;
+    let mut signable_data = signable_json;
+    signable_data.extend_from_slice(&checksum_bytes);
+    
+    // Sign and add to metadata (the algorithm field is omitted for Ed25519)
+    let signed = key.sign(&signable_data)?;
+    metadata["signature"] = serde_json::Value::String(signed.signature);
+    metadata["public_key"] = serde_json::Value::String(signed.public_key);
+    if !signed.algorithm.is_default() {
+        metadata["algorithm"] = serde_json::to_value(signed.algorithm)?;
+    }
+    
+    Ok(())
+}
+
+/// Locate and read the runtime binary
+/// 
+/// Search order: `custom_path` (from `[runtime] path`), `lxe-runtime` next to
+/// the running executable, then the runtime downloaded by `lxe runtime download`.
+pub fn find_runtime_binary(custom_path: Option<&Path>) -> Result<Vec<u8>> {
+    let path = find_runtime_path(custom_path)?;
+    fs::read(&path)
+        .with_context(|| format!("Failed to read runtime: {}", path.display()))
+}
+
+/// Path of the runtime `find_runtime_binary` reads
+pub fn find_runtime_path(custom_path: Option<&Path>) -> Result<PathBuf> {
+    let next_to_exe = std::env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(|p| p.join("lxe-runtime")));
+    let downloaded = dirs::data_local_dir()
+        .map(|dir| dir.join("lxe").join("lxe-runtime"));
+    
+    let candidates = [custom_path.map(Path::to_path_buf), next_to_exe, downloaded];
+    if let Some(path) = candidates.into_iter().flatten().find(|p| p.exists()) {
+        return Ok(path);
+    }
+    
+    anyhow::bail!(
+        "LXE runtime not found.\n\
+         Run 'lxe runtime download' to install it, or:\n\
+         - Place 'lxe-runtime' in the same directory as 'lxe'\n\
+         - Or specify [runtime] path in lxe.toml"
+    )
+}
+
+/// Write the final package and make it executable
+/// 
+/// Layout: `[runtime][magic][len][metadata][checksum][payload][footer]`.
+/// Returns the size of the written file.
+pub fn write_package(
+    output_path: &Path,
+    runtime: &[u8],
+    metadata_json: &[u8],
+    checksum: &str,
+    compressed: &[u8],
+) -> Result<u64> {
+    write_package_from(output_path, runtime, metadata_json, checksum, &mut &compressed[..])
+}
+
+/// Like `write_package`, copying the compressed payload from a file
+pub fn write_package_file(
+    output_path: &Path,
+    runtime: &[u8],
+    metadata_json: &[u8],
+    checksum: &str,
+    payload_path: &Path,
+) -> Result<u64> {
+    let mut payload = File::open(payload_path)
+        .with_context(|| format!("Failed to open {}", payload_path.display()))?;
+    write_package_from(output_path, runtime, metadata_json, checksum, &mut payload)
+}
+
+fn write_package_from(
+    output_path: &Path,
+    runtime: &[u8],
+    metadata_json: &[u8],
+    checksum: &str,
+    compressed: &mut impl std::io::Read,
+) -> Result<u64> {
+    let mut output_file = File::create(output_path)
+        .with_context(|| format!("Failed to create {}", output_path.display()))?;
+    
+    // [Runtime Binary]
+    output_file.write_all(runtime)?;
+    
+    // [Magic Bytes] - header marker
+    output_file.write_all(LXE_MAGIC)?;
+    
+    // [Metadata Length (u32 LE)]
+    let metadata_len = metadata_json.len() as u32;
+    output_file.write_all(&metadata_len.to_le_bytes())?;
+    
+    // [Metadata JSON]
+    output_file.write_all(metadata_json)?;
+    
+    // [Checksum (32 bytes)]
+    output_file.write_all(&payload::checksum_slot(checksum)?)?;
+    
+    // [Compressed Payload]
+    let payload_size = std::io::copy(compressed, &mut output_file)?;
+    
+    // [Footer: the layout above, see payload::FooterV3]
+    let header_offset = runtime.len() as u64;
+    let metadata_offset = header_offset + LXE_MAGIC.len() as u64 + 4;
+    let payload_offset = metadata_offset + metadata_len as u64 + payload::CHECKSUM_SLOT_SIZE as u64;
+    payload::write_footer_v3(&mut output_file, &FooterV3 {
+        flags: 0,
+        metadata_offset,
+        metadata_len,
+        payload_offset,
+        payload_len: payload_size,
+    })?;
+    
+    // Fill in the runtime's metadata stub, if it has one
+    if let Some(stub_offset) = payload_format::stub_offset(runtime) {
+        let stub = MetaStub {
+            header_offset,
+            metadata_offset,
+            metadata_len,
+            payload_offset,
+            payload_size,
+        };
+        output_file.seek(SeekFrom::Start(stub_offset))?;
+        output_file.write_all(&stub.to_bytes())?;
+    }
+    
+    output_file.flush()?;
+    
+    // Make executable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mut perms = fs::metadata(output_path)?.permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(output_path, perms)?;
+    }
+    
+    Ok(fs::metadata(output_path)?.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_package_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("lxe-builder-{}", std::process::id()));
+        let input = dir.join("input");
+        fs::create_dir_all(&input).unwrap();
+        fs::write(input.join("app"), b"#!/bin/sh\n").unwrap();
+        
+        let tar = create_tar_archive(&input).unwrap();
+        let compressed = compress_zstd(&tar, 3).unwrap();
+        let checksum = sha256_hex(&compressed);
+        let metadata = json!({
+            "format_version": 1,
+            "app_id": "com.example.Test",
+            "name": "Test",
+            "version": "1.0.0",
+            "arch": "x86_64",
+            "install_size": tar.len(),
+            "exec": "app",
+            "categories": [],
+            "payload_checksum": checksum,
+        });
+        
+        let output = dir.join("test.lxe");
+        let runtime = b"\x7fELF fake runtime";
+        let size = write_package(&output, runtime, &serde_json::to_vec(&metadata).unwrap(), &checksum, &compressed).unwrap();
+        assert_eq!(size, fs::metadata(&output).unwrap().len());
+        
+        let info = payload::read_payload_info(&output).unwrap();
+        assert_eq!(info.metadata.app_id, "com.example.Test");
+        assert_eq!(info.header_offset, runtime.len() as u64);
+        assert_eq!(info.footer_version, payload::FOOTER_VERSION_3);
+        assert_eq!(info.payload_size, compressed.len() as u64);
+        
+        // A runtime with a stub section gets it filled in
+        let runtime = payload_format::fake_elf(payload_format::META_STUB_SECTION, &payload_format::EMPTY_META_STUB);
+        write_package(&output, &runtime, &serde_json::to_vec(&metadata).unwrap(), &checksum, &compressed).unwrap();
+        let stub = payload::read_metadata_stub(&output).unwrap();
+        let info = payload::read_payload_info(&output).unwrap();
+        assert_eq!((stub.payload_offset, stub.payload_size), (info.payload_offset, info.payload_size));
+        assert_eq!(payload::read_metadata_via_stub(&output).unwrap().app_id, "com.example.Test");
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_lz4_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input");
+        fs::create_dir_all(&input).unwrap();
+        fs::write(input.join("app"), b"#!/bin/sh\n").unwrap();
+        
+        let tar = create_tar_archive(&input).unwrap();
+        let compressed = compress(&tar, &Compressor::Lz4 { level: 9 }).unwrap();
+        let checksum = sha256_hex(&compressed);
+        let metadata = json!({
+            "format_version": 1,
+            "app_id": "com.example.Test",
+            "name": "Test",
+            "version": "1.0.0",
+            "arch": "x86_64",
+            "install_size": tar.len(),
+            "exec": "app",
+            "categories": [],
+            "payload_checksum": checksum,
+            "compressor": "lz4",
+        });
+        let output = dir.path().join("test.lxe");
+        write_package(&output, b"\x7fELF fake runtime", &serde_json::to_vec(&metadata).unwrap(), &checksum, &compressed).unwrap();
+        
+        let info = payload::read_payload_info(&output).unwrap();
+        assert_eq!(info.metadata.compressor, "lz4");
+        let entries = payload::list_entries(&info).unwrap();
+        assert!(entries.iter().any(|entry| entry.path == Path::new("app")));
+        
+        // Same stream through the C zstd / lz4 dispatch of the CLI
+        let mut unpacked = Vec::new();
+        decoder(compressed.as_slice(), "lz4").unwrap().read_to_end(&mut unpacked).unwrap();
+        assert_eq!(unpacked, tar);
+        assert!(payload::decoder(compressed.as_slice(), "brotli").is_err());
+    }
+    
+    #[test]
+    fn test_tar_overrides() {
+        use std::io::Read;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("input");
+        fs::create_dir_all(input.join("bin")).unwrap();
+        fs::write(input.join("bin/app"), b"original").unwrap();
+        fs::write(input.join("readme"), b"kept").unwrap();
+        let replacement = dir.path().join("stripped");
+        fs::write(&replacement, b"stripped").unwrap();
+        
+        let overrides = BTreeMap::from([(PathBuf::from("bin/app"), replacement)]);
+        let tar_data = create_tar_archive_with(&input, &overrides, false).unwrap();
+        
+        let mut contents = BTreeMap::new();
+        for entry in tar::Archive::new(tar_data.as_slice()).entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().into_owned();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            contents.insert(path, data);
+        }
+        assert_eq!(contents["bin/app"], "stripped");
+        assert_eq!(contents["readme"], "kept");
+    }
+    
+    #[test]
+    fn test_reproducible_tar() {
+        use std::os::unix::fs::PermissionsExt;
+        
+        let dir = tempfile::tempdir().unwrap();
+        let build = |name: &str, mtime: u64| {
+            let input = dir.path().join(name);
+            fs::create_dir_all(input.join("lib")).unwrap();
+            fs::write(input.join("lib/b.so"), b"lib").unwrap();
+            fs::write(input.join("app"), b"#!/bin/sh\n").unwrap();
+            fs::set_permissions(input.join("app"), fs::Permissions::from_mode(0o755)).unwrap();
+            std::os::unix::fs::symlink("lib/b.so", input.join("a.so")).unwrap();
+            let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime);
+            File::options().write(true).open(input.join("app")).unwrap().set_modified(time).unwrap();
+            create_tar_archive_with(&input, &BTreeMap::new(), true).unwrap()
+        };
+        
+        let first = build("one", 1_000_000);
+        assert_eq!(first, build("two", 2_000_000));
+        
+        let mut archive = tar::Archive::new(first.as_slice());
+        let entries: Vec<(String, u64, u32)> = archive.entries().unwrap()
+            .map(|e| {
+                let e = e.unwrap();
+                (e.path().unwrap().display().to_string(), e.header().mtime().unwrap(), e.header().mode().unwrap())
+            })
+            .collect();
+        let paths: Vec<&str> = entries.iter().map(|(p, _, _)| p.as_str()).collect();
+        assert_eq!(paths, ["./", "a.so", "app", "lib", "lib/b.so"]);
+        assert!(entries.iter().all(|(_, mtime, _)| *mtime == 0));
+        assert_eq!(entries[2].2, 0o755);
+    }
+}
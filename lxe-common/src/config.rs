@@ -3,9 +3,12 @@
 //! Parses lxe.toml files for declarative package configuration.
 
 use anyhow::{Context, Result};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 
+use crate::metadata::{DesktopAction, ExtraExecutable, LxeDependency, ReleaseNote, ServiceMetadata};
+use crate::payload::{ChecksumAlgorithm, CompressorKind};
+
 /// The main configuration structure matching lxe.toml
 #[derive(Debug, Deserialize)]
 pub struct LxeConfig {
@@ -18,6 +21,9 @@ pub struct LxeConfig {
     pub security: SecurityConfig,
     #[serde(default)]
     pub installer: InstallerConfig,
+    /// Background service installed as a systemd user unit (optional)
+    #[serde(default)]
+    pub service: Option<ServiceMetadata>,
 }
 
 /// Installer UI customization options
@@ -101,6 +107,10 @@ pub struct PackageConfig {
     #[serde(default)]
     pub categories: Vec<String>,
     
+    /// MIME types the app opens, e.g. "text/markdown" (optional)
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+    
     /// Run in terminal (default: false)
     #[serde(default)]
     pub terminal: bool,
@@ -110,6 +120,14 @@ pub struct PackageConfig {
     #[serde(default)]
     pub wm_class: Option<String>,
 
+    /// Minimum glibc version required to run (e.g., "2.35")
+    #[serde(default)]
+    pub min_glibc: Option<String>,
+    
+    /// Minimum Linux kernel version required to run (e.g., "5.10")
+    #[serde(default)]
+    pub min_kernel: Option<String>,
+
     /// DEPRECATED: License file should be in [installer] section
     #[serde(default)]
     pub license: Option<String>,
@@ -117,6 +135,35 @@ pub struct PackageConfig {
     /// Authors (optional metadata)
     #[serde(default)]
     pub authors: Vec<String>,
+    
+    /// Custom desktop actions ([[package.actions]])
+    #[serde(default)]
+    pub actions: Vec<DesktopAction>,
+    
+    /// More commands shipped in the package ([[package.extra_executables]])
+    #[serde(default)]
+    pub extra_executables: Vec<ExtraExecutable>,
+    
+    /// What the app needs from the system ([[package.dependencies]])
+    #[serde(default)]
+    pub dependencies: Vec<LxeDependency>,
+    
+    /// Project homepage URL (optional)
+    #[serde(default)]
+    pub homepage: Option<String>,
+    
+    /// Screenshot URLs for software centers (optional)
+    #[serde(default)]
+    pub screenshots: Vec<String>,
+    
+    /// Release history for software centers, newest first ([[package.releases]])
+    #[serde(default)]
+    pub releases: Vec<ReleaseNote>,
+    
+    /// Files or directories in the app directory the user may edit; kept
+    /// across uninstalls and upgrades when modified (optional)
+    #[serde(default)]
+    pub config_paths: Vec<String>,
 }
 
 /// Build configuration
@@ -130,13 +177,96 @@ pub struct BuildConfig {
     #[serde(default)]
     pub script: Option<String>,
     
-    /// Zstd compression level (1-22, default: 19)
+    /// Directory the build script runs in, relative to lxe.toml (default: its directory)
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    
+    /// Compression level (1-22, default: 19; lz4 caps it at 12)
     #[serde(default = "default_compression")]
     pub compression: i32,
     
+    /// Payload compressor: "zstd" (default) or "lz4", which gives bigger
+    /// packages that install faster (see [`BuildConfig::compressor`])
+    #[serde(default)]
+    pub compressor: CompressorKind,
+    
+    /// Payload checksum: "sha256" (default), "blake3" or "sha512"
+    #[serde(default)]
+    pub checksum_algorithm: ChecksumAlgorithm,
+    
     /// Output file path (default: ./<name>.lxe)
     #[serde(default)]
     pub output: Option<String>,
+    
+    /// File name template for `lxe build --output-dir`
+    /// (`{id}`, `{version}`, `{arch}`, `{name}`, `{date}`)
+    #[serde(default)]
+    pub output_template: Option<String>,
+    
+    /// Workspace members that must be built before this one
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    
+    /// Strip debug symbols from ELF binaries before packaging
+    #[serde(default)]
+    pub strip_symbols: bool,
+    
+    /// Optimize the PNG icon (oxipng/optipng) before packaging
+    #[serde(default)]
+    pub compress_icons: bool,
+    
+    /// PNG optimization level (0-6, default: 2)
+    #[serde(default = "default_icon_compression")]
+    pub icon_compression_level: u8,
+    
+    /// Render an SVG `package.icon` to PNG and package that instead
+    #[serde(default)]
+    pub convert_icon_to_png: bool,
+    
+    /// Width and height of the PNG rendered from an SVG icon (default: 256)
+    #[serde(default = "default_icon_target_size")]
+    pub icon_target_size: u32,
+    
+    /// Extra PNG sizes rendered from an SVG icon for the hicolor theme
+    #[serde(default)]
+    pub generate_icon_sizes: Vec<u32>,
+    
+    /// Memory budget of `lxe build` in MB; compression uses a smaller
+    /// window when its estimated usage wouldn't fit
+    #[serde(default)]
+    pub max_memory_mb: Option<u64>,
+    
+    /// Archive without timestamps or file ownership, in a fixed order, so
+    /// rebuilding the same files gives the same payload checksum
+    #[serde(default)]
+    pub reproducible: bool,
+    
+    /// Write `<app_id>.metainfo.xml` (AppStream) next to the package
+    #[serde(default)]
+    pub generate_metainfo: bool,
+    
+    /// Scripts `lxe build` runs around the build (`[build.hooks]`)
+    #[serde(default)]
+    pub hooks: BuildHooks,
+}
+
+/// Build-tool level hooks, run with `sh -c` in the directory `lxe build` runs in
+/// 
+/// Unlike `build.script` these don't produce the input; they wrap the whole
+/// `lxe build` (e.g. to upload the package or notify a chat channel).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct BuildHooks {
+    /// Runs before any build step
+    #[serde(default)]
+    pub pre_build: Option<String>,
+    
+    /// Runs after the package was created (`LXE_OUTPUT_PATH` is the package)
+    #[serde(default)]
+    pub post_build: Option<String>,
+    
+    /// Runs if any step fails (`LXE_ERROR` is the error message)
+    #[serde(default)]
+    pub on_build_failed: Option<String>,
 }
 
 impl Default for BuildConfig {
@@ -144,18 +274,80 @@ impl Default for BuildConfig {
         Self {
             input: default_input(),
             script: None,
+            working_dir: None,
             compression: default_compression(),
+            compressor: CompressorKind::default(),
+            checksum_algorithm: ChecksumAlgorithm::default(),
             output: None,
+            output_template: None,
+            depends_on: Vec::new(),
+            strip_symbols: false,
+            compress_icons: false,
+            icon_compression_level: default_icon_compression(),
+            convert_icon_to_png: false,
+            icon_target_size: default_icon_target_size(),
+            generate_icon_sizes: Vec::new(),
+            max_memory_mb: None,
+            reproducible: false,
+            generate_metainfo: false,
+            hooks: BuildHooks::default(),
+        }
+    }
+}
+
+impl BuildConfig {
+    /// The configured compressor with its level
+    pub fn compressor(&self) -> Compressor {
+        match self.compressor {
+            CompressorKind::Zstd => Compressor::Zstd { level: self.compression },
+            CompressorKind::Lz4 => Compressor::Lz4 { level: self.compression.min(LZ4_MAX_LEVEL) },
+        }
+    }
+}
+
+/// Highest LZ4 level (LZ4 HC)
+const LZ4_MAX_LEVEL: i32 = 12;
+
+/// Payload compressor and level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+pub enum Compressor {
+    Zstd { level: i32 },
+    /// LZ4 frame format; levels from 3 on use the slower HC compressor
+    Lz4 { level: i32 },
+}
+
+impl Compressor {
+    pub fn kind(&self) -> CompressorKind {
+        match self {
+            Compressor::Zstd { .. } => CompressorKind::Zstd,
+            Compressor::Lz4 { .. } => CompressorKind::Lz4,
+        }
+    }
+    
+    pub fn level(&self) -> i32 {
+        match *self {
+            Compressor::Zstd { level } | Compressor::Lz4 { level } => level,
         }
     }
 }
 
+impl std::fmt::Display for Compressor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} level {}", self.kind(), self.level())
+    }
+}
+
 /// Runtime configuration
 #[derive(Debug, Deserialize, Default)]
 pub struct RuntimeConfig {
     /// Path to custom runtime binary (optional)
     #[serde(default)]
     pub path: Option<String>,
+    
+    /// Oldest runtime `lxe build --verify-runtime` accepts (e.g. "1.2.0")
+    #[serde(default)]
+    pub min_version: Option<String>,
 }
 
 /// Security/signing configuration
@@ -174,6 +366,14 @@ fn default_compression() -> i32 {
     19
 }
 
+fn default_icon_target_size() -> u32 {
+    256
+}
+
+fn default_icon_compression() -> u8 {
+    2
+}
+
 impl LxeConfig {
     /// Load configuration from a file path
     pub fn from_file(path: &Path) -> Result<Self> {
@@ -218,6 +418,50 @@ impl LxeConfig {
         }
     }
     
+    /// Directory the build script runs in
+    pub fn script_dir(&self, base_dir: &Path) -> PathBuf {
+        match self.build.working_dir {
+            Some(ref dir) => base_dir.join(dir),
+            None => base_dir.to_path_buf(),
+        }
+    }
+    
+    /// Check the build script before running it
+    /// 
+    /// A script that starts with a file path (`./build.sh --release`) must
+    /// point to an existing, executable file.
+    pub fn validate_script(&self, base_dir: &Path) -> Result<()> {
+        let Some(ref script) = self.build.script else {
+            return Ok(());
+        };
+        
+        let script_dir = self.script_dir(base_dir);
+        if !script_dir.is_dir() {
+            anyhow::bail!("Build working_dir does not exist: {}", script_dir.display());
+        }
+        
+        if let Some(file) = script_file(script) {
+            let path = script_dir.join(file);
+            if !path.is_file() {
+                anyhow::bail!("Build script not found: {}", path.display());
+            }
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                if path.metadata()?.permissions().mode() & 0o111 == 0 {
+                    anyhow::bail!(
+                        "Build script is not executable: {}\n\
+                         Fix with: chmod +x {}",
+                        path.display(),
+                        file
+                    );
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Get the resolved runtime path (if specified)
     pub fn runtime_path(&self, base_dir: &Path) -> Option<PathBuf> {
         self.runtime.path.as_ref().map(|p| base_dir.join(p))
@@ -241,6 +485,14 @@ impl LxeConfig {
             );
         }
         
+        // Packaging into the input directory itself can't work
+        if self.output_path(base_dir) == input {
+            anyhow::bail!(
+                "build.input and build.output both point to {}",
+                input.display()
+            );
+        }
+        
         // Validate compression level
         if !(1..=22).contains(&self.build.compression) {
             anyhow::bail!(
@@ -249,6 +501,12 @@ impl LxeConfig {
             );
         }
         
+        // Icons rendered from SVG
+        let mut icon_sizes = std::iter::once(&self.build.icon_target_size).chain(&self.build.generate_icon_sizes);
+        if let Some(size) = icon_sizes.find(|s| !(1..=1024).contains(*s)) {
+            anyhow::bail!("Icon sizes must be between 1 and 1024 pixels, got: {}", size);
+        }
+        
         // Validate app ID format (basic check)
         if !self.package.id.contains('.') {
             anyhow::bail!(
@@ -257,6 +515,50 @@ impl LxeConfig {
             );
         }
         
+        // Desktop actions end up in the .desktop Exec= line
+        for action in &self.package.actions {
+            action.validate()?;
+        }
+        
+        // Each extra executable gets a bin symlink, so command names must be unique
+        let main_command = Path::new(&self.package.executable)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string());
+        let mut commands = std::collections::HashSet::new();
+        commands.extend(main_command);
+        for extra in &self.package.extra_executables {
+            extra.validate()?;
+            if !commands.insert(extra.command()) {
+                anyhow::bail!(
+                    "Extra executable '{}' would share the command name '{}' with another executable",
+                    extra.exec,
+                    extra.command()
+                );
+            }
+        }
+        
+        for dependency in &self.package.dependencies {
+            dependency.validate()?;
+        }
+        
+        if let Some(bad) = self.package.mime_types.iter().find(|t| !crate::desktop::is_mime_type(t)) {
+            anyhow::bail!("Invalid MIME type '{}' in [package] mime_types (expected type/subtype)", bad);
+        }
+        
+        let is_url = |url: &str| url.starts_with("https://") || url.starts_with("http://");
+        if let Some(bad) = self.package.homepage.iter().chain(&self.package.screenshots).find(|url| !is_url(url)) {
+            anyhow::bail!("Invalid URL '{}' in [package] (expected http:// or https://)", bad);
+        }
+        for release in &self.package.releases {
+            crate::appstream::validate_release(release)?;
+        }
+        if let Some(bad) = self.package.config_paths.iter().find(|p| !crate::metadata::is_valid_config_path(p)) {
+            anyhow::bail!("Invalid config path '{}' in [package] (expected a path relative to the app directory)", bad);
+        }
+        if let Some(ref service) = self.service {
+            service.validate()?;
+        }
+        
         // ICON VALIDATION: Ensure packages always have working icons
         if input.exists() {
             if let Some(ref icon) = self.package.icon {
@@ -307,6 +609,175 @@ impl LxeConfig {
     }
 }
 
+/// How the runtime treats unsigned packages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SignaturePolicy {
+    /// Refuse to install unsigned packages
+    RequireSigned,
+    /// Install unsigned packages with a warning (default)
+    #[default]
+    WarnUnsigned,
+    /// Ask once per app ID, then remember the answer
+    Tofu,
+    /// Install unsigned packages silently
+    AllowAll,
+    /// Only install packages signed by a key trusted for their app ID
+    TrustedOnly,
+}
+
+/// Memory limits of the runtime's extractor (`[extractor]` in the global config)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractorConfig {
+    /// Memory for buffered file contents and read buffers while extracting;
+    /// the decompression window the package was built with comes on top
+    #[serde(default = "default_extractor_memory_mb")]
+    pub max_memory_mb: u64,
+}
+
+fn default_extractor_memory_mb() -> u64 {
+    64
+}
+
+impl Default for ExtractorConfig {
+    fn default() -> Self {
+        Self { max_memory_mb: default_extractor_memory_mb() }
+    }
+}
+
+impl ExtractorConfig {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Repositories the user trusts (`[repo]` in the global config)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RepoSettings {
+    /// Base URLs of repositories served by `lxe repo serve` (or any web
+    /// server hosting their `index.json`)
+    #[serde(default)]
+    pub urls: Vec<String>,
+}
+
+impl RepoSettings {
+    fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+}
+
+/// What `lxe build` does when the output file already exists
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictAction {
+    /// Replace the existing file (default)
+    #[default]
+    Overwrite,
+    /// Fail the build
+    Error,
+    /// Write to `<name>-1.lxe`, `<name>-2.lxe`, ... instead
+    Rename,
+    /// Move the existing file to `<name>.lxe.bak` once the new package is built
+    Backup,
+}
+
+impl std::str::FromStr for ConflictAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "overwrite" => Ok(Self::Overwrite),
+            "error" => Ok(Self::Error),
+            "rename" => Ok(Self::Rename),
+            "backup" => Ok(Self::Backup),
+            _ => anyhow::bail!("Unknown conflict action '{}' (overwrite, error, rename or backup)", s),
+        }
+    }
+}
+
+/// User-wide LXE settings (~/.config/lxe/config.toml)
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GlobalConfig {
+    /// Policy for unsigned packages
+    #[serde(default)]
+    pub signature_policy: SignaturePolicy,
+    
+    /// App IDs the user accepted as unsigned under the TOFU policy
+    #[serde(default)]
+    pub tofu_unsigned: Vec<String>,
+    
+    /// Package repository URL (e.g., one served by `lxe repo serve`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry: Option<String>,
+    
+    /// Seconds between update checks of `lxe notify-updates`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check_updates_interval: Option<u64>,
+    
+    /// Download speed cap in KiB/s (`--no-limit` overrides it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_download_speed_kbps: Option<u64>,
+    
+    /// Default for `lxe build --conflict`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub conflict: Option<ConflictAction>,
+    
+    /// Memory limits of package extraction
+    #[serde(default, skip_serializing_if = "ExtractorConfig::is_default")]
+    pub extractor: ExtractorConfig,
+    
+    /// Package repositories to search (`[repo]`)
+    #[serde(default, skip_serializing_if = "RepoSettings::is_empty")]
+    pub repo: RepoSettings,
+}
+
+impl GlobalConfig {
+    /// Path of the global config file
+    pub fn path() -> Result<PathBuf> {
+        crate::paths::lxe::config_dir()
+            .map(|d| d.join("config.toml"))
+            .context("Could not determine config directory")
+    }
+    
+    /// Load the global config (defaults if the file doesn't exist)
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config file: {}", path.display()))
+    }
+    
+    /// Save the global config
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        
+        let contents = toml::to_string_pretty(self)
+            .context("Failed to serialize global config")?;
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Failed to write config file: {}", path.display()))
+    }
+    
+    /// Whether an unsigned app was already accepted under TOFU
+    pub fn is_tofu_trusted(&self, app_id: &str) -> bool {
+        self.tofu_unsigned.iter().any(|id| id == app_id)
+    }
+    
+    /// Remember an unsigned app as accepted
+    pub fn add_tofu_trusted(&mut self, app_id: &str) {
+        if !self.is_tofu_trusted(app_id) {
+            self.tofu_unsigned.push(app_id.to_string());
+        }
+    }
+}
+
 /// Generate a template lxe.toml file
 pub fn generate_template(name: &str, executable: &str) -> String {
     format!(r#"# LXE Package Configuration
@@ -354,10 +825,49 @@ compression = 19
     )
 }
 
+/// The file a build script starts with, if its first word is a path
+/// (`./build.sh`, `scripts/dist.sh --release`) rather than a command
+pub fn script_file(script: &str) -> Option<&str> {
+    let first = script.split_whitespace().next()?;
+    (first.contains('/') || first.ends_with(".sh")).then_some(first)
+}
+
+/// Whether a build script seems to invoke the packager again
+/// 
+/// `lxe build` inside a build script would run the same script again and
+/// never finish. This is a heuristic over the script's words.
+pub fn detect_recursive_build(script: &str) -> bool {
+    let words: Vec<&str> = script
+        .split(|c: char| c.is_whitespace() || matches!(c, ';' | '&' | '|' | '(' | ')' | '`' | '$'))
+        .filter(|w| !w.is_empty())
+        .collect();
+    
+    words.iter().enumerate().any(|(i, word)| {
+        let command = word.rsplit('/').next().unwrap_or(word);
+        match command {
+            "lxe-pack" | "lxe-build" => true,
+            "lxe" => words.get(i + 1) == Some(&"build"),
+            _ => false,
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    #[test]
+    fn test_detect_recursive_build() {
+        assert!(detect_recursive_build("npm run build && lxe build"));
+        assert!(detect_recursive_build("cargo build; ~/.cargo/bin/lxe-pack --input dist"));
+        assert!(!detect_recursive_build("./lxe-build.sh"));
+        assert!(!detect_recursive_build("npm run build && lxe verify app.lxe"));
+        
+        assert_eq!(script_file("./lxe-build.sh"), Some("./lxe-build.sh"));
+        assert_eq!(script_file("scripts/dist.sh --release"), Some("scripts/dist.sh"));
+        assert_eq!(script_file("npm run build"), None);
+    }
+    
     #[test]
     fn test_parse_minimal_config() {
         let toml = r#"
@@ -391,8 +901,12 @@ mod tests {
             input = "./build"
             script = "make build"
             compression = 22
+            compressor = "lz4"
             output = "./out/app.lxe"
             
+            [build.hooks]
+            post_build = "./upload.sh \"$LXE_OUTPUT_PATH\""
+            
             [security]
             key = "./keys/private.pem"
         "#;
@@ -400,6 +914,89 @@ mod tests {
         let config = LxeConfig::from_str(toml).unwrap();
         assert_eq!(config.package.terminal, true);
         assert_eq!(config.build.compression, 22);
+        assert_eq!(config.build.compressor(), Compressor::Lz4 { level: 12 });
         assert!(config.security.key.is_some());
+        assert_eq!(config.build.hooks.post_build.as_deref(), Some("./upload.sh \"$LXE_OUTPUT_PATH\""));
+        assert!(config.build.hooks.pre_build.is_none());
+    }
+    
+    #[test]
+    fn test_global_config_signature_policy() {
+        let config: GlobalConfig = toml::from_str(r#"
+            signature_policy = "tofu"
+            tofu_unsigned = ["com.example.app"]
+            
+            [repo]
+            urls = ["https://repo.example.com"]
+        "#).unwrap();
+        assert_eq!(config.signature_policy, SignaturePolicy::Tofu);
+        assert!(config.is_tofu_trusted("com.example.app"));
+        assert_eq!(config.repo.urls, ["https://repo.example.com"]);
+        assert!(toml::to_string_pretty(&config).unwrap().contains("[repo]"));
+        
+        let default: GlobalConfig = toml::from_str("").unwrap();
+        assert_eq!(default.signature_policy, SignaturePolicy::WarnUnsigned);
+    }
+    
+    #[test]
+    fn test_parse_desktop_actions() {
+        let toml = r#"
+            [package]
+            name = "Test App"
+            id = "com.test.app"
+            version = "1.0.0"
+            executable = "app"
+            
+            [[package.actions]]
+            id = "new-window"
+            name = "New Window"
+            exec_args = "--new-window"
+        "#;
+        
+        let config = LxeConfig::from_str(toml).unwrap();
+        assert_eq!(config.package.actions.len(), 1);
+        assert_eq!(config.package.actions[0].id, "new-window");
+    }
+    
+    #[test]
+    fn test_extra_executables() {
+        let toml = r#"
+            [package]
+            name = "Test App"
+            id = "com.test.app"
+            version = "1.0.0"
+            executable = "bin/app"
+            
+            [build]
+            script = "true"
+            
+            [[package.extra_executables]]
+            exec = "bin/app-cli"
+            name = "Test App CLI"
+            terminal = true
+            
+            [[package.dependencies]]
+            kind = "shared-lib"
+            id = "libGL.so.1"
+            
+            [[package.dependencies]]
+            kind = "lxe-package"
+            id = "com.example.runtime"
+            version_req = ">=2.0"
+        "#;
+        
+        let mut config = LxeConfig::from_str(toml).unwrap();
+        assert_eq!(config.package.dependencies[1].kind, crate::metadata::DepKind::LxePackage);
+        assert_eq!(config.package.dependencies[1].to_string(), "com.example.runtime >=2.0");
+        let extra = &config.package.extra_executables[0];
+        assert_eq!(extra.command(), "app-cli");
+        assert_eq!(extra.desktop_filename("com.test.app"), "com.test.app.app-cli.desktop");
+        assert!(!extra.desktop_entry);
+        config.validate(Path::new("/nonexistent")).unwrap();
+        
+        config.package.extra_executables[0].exec = "other/app".to_string();
+        assert!(config.validate(Path::new("/nonexistent")).is_err());
+        config.package.extra_executables[0].exec = "../app-cli".to_string();
+        assert!(config.validate(Path::new("/nonexistent")).is_err());
     }
 }
@@ -0,0 +1,160 @@
+//! Delta Packages
+//!
+//! `lxe delta` packs only what changed between two releases of an app. The
+//! payload of a delta package holds the added and changed entries, preceded
+//! by [`DELTA_MANIFEST`] listing the paths the new release removed. Its
+//! metadata names the release it applies to by payload checksum
+//! (`delta_base_checksum`); the runtime checks that against the installed
+//! version's manifest, then applies the delta to a copy of the installed
+//! files. The result must match `delta_target_tree`, the [`tree_checksum`]
+//! of the full release.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Current delta payload format
+pub const DELTA_FORMAT_VERSION: u8 = 1;
+
+/// First entry of a delta payload
+pub const DELTA_MANIFEST: &str = ".lxe-delta.json";
+
+/// Paths removed by a delta, relative to the app directory
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaManifest {
+    pub removed: Vec<PathBuf>,
+}
+
+impl DeltaManifest {
+    /// Whether the payload entry at `path` is the delta manifest
+    pub fn is_manifest(path: &Path) -> bool {
+        path.strip_prefix(".").unwrap_or(path) == Path::new(DELTA_MANIFEST)
+    }
+
+    /// Remove the listed paths from `dir` (missing ones are skipped)
+    pub fn remove_from(&self, dir: &Path) -> Result<()> {
+        for path in &self.removed {
+            if !path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+                anyhow::bail!("Delta removes a path outside the app directory: {}", path.display());
+            }
+            let target = dir.join(path);
+            // Never remove anything behind a symlink of the app directory
+            let mut ancestor = dir.to_path_buf();
+            for component in path.parent().into_iter().flat_map(Path::components) {
+                ancestor.push(component);
+                if std::fs::symlink_metadata(&ancestor).is_ok_and(|m| m.file_type().is_symlink()) {
+                    anyhow::bail!("Delta removes {} through the symlink {}", path.display(), ancestor.display());
+                }
+            }
+            let Ok(file_type) = std::fs::symlink_metadata(&target).map(|m| m.file_type()) else {
+                continue;
+            };
+            if file_type.is_dir() {
+                std::fs::remove_dir_all(&target)
+            } else {
+                std::fs::remove_file(&target)
+            }
+            .with_context(|| format!("Failed to remove {}", target.display()))?;
+        }
+        Ok(())
+    }
+}
+
+/// What [`tree_checksum`] covers of one path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEntry {
+    /// A regular file (or hard link) with the SHA256 of its contents
+    File(String),
+    Symlink(PathBuf),
+}
+
+/// SHA256 over the paths, file contents and symlink targets of an app
+/// directory; directories and permissions are left out
+pub fn tree_checksum(entries: &BTreeMap<PathBuf, TreeEntry>) -> String {
+    let mut hasher = Sha256::new();
+    for (path, entry) in entries {
+        let line = match entry {
+            TreeEntry::File(sha256) => format!("F {} {}\n", sha256, path.display()),
+            TreeEntry::Symlink(target) => format!("L {} -> {}\n", path.display(), target.display()),
+        };
+        hasher.update(line.as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// The [`TreeEntry`] of every file and symlink under `dir`, by relative path
+pub fn tree_entries(dir: &Path) -> Result<BTreeMap<PathBuf, TreeEntry>> {
+    let mut entries = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in std::fs::read_dir(&current).with_context(|| format!("Failed to read {}", current.display()))? {
+            let path = entry?.path();
+            let file_type = std::fs::symlink_metadata(&path)?.file_type();
+            let relative = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_symlink() {
+                entries.insert(relative, TreeEntry::Symlink(std::fs::read_link(&path)?));
+            } else if file_type.is_file() {
+                let sha256 = crate::payload::checksum_file(&path, crate::payload::ChecksumAlgorithm::Sha256)?;
+                entries.insert(relative, TreeEntry::File(sha256));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_manifest() {
+        assert!(DeltaManifest::is_manifest(Path::new("./.lxe-delta.json")));
+        assert!(!DeltaManifest::is_manifest(Path::new("./lib/.lxe-delta.json")));
+
+        let dir = std::env::temp_dir().join(format!("lxe-delta-test-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib/old")).unwrap();
+        std::fs::write(dir.join("lib/old/a.so"), b"a").unwrap();
+        std::fs::write(dir.join("app"), b"app").unwrap();
+
+        let manifest = DeltaManifest { removed: vec!["lib/old".into(), "missing".into()] };
+        manifest.remove_from(&dir).unwrap();
+        assert!(!dir.join("lib/old").exists());
+        assert!(dir.join("app").exists());
+
+        let escape = DeltaManifest { removed: vec!["../app".into()] };
+        assert!(escape.remove_from(&dir.join("lib")).is_err());
+
+        // Removals don't follow symlinked directories
+        let outside = dir.join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        std::fs::write(outside.join("keep"), b"keep").unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("lib/link")).unwrap();
+        let through_link = DeltaManifest { removed: vec!["lib/link/keep".into()] };
+        assert!(through_link.remove_from(&dir).is_err());
+        assert!(outside.join("keep").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_tree_checksum() {
+        let dir = std::env::temp_dir().join(format!("lxe-delta-tree-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("lib")).unwrap();
+        std::fs::write(dir.join("app"), b"app").unwrap();
+        std::os::unix::fs::symlink("../app", dir.join("lib/app")).unwrap();
+
+        let entries = tree_entries(&dir).unwrap();
+        let expected = BTreeMap::from([
+            (PathBuf::from("app"), TreeEntry::File(crate::payload::calculate_checksum(b"app", Default::default()))),
+            (PathBuf::from("lib/app"), TreeEntry::Symlink("../app".into())),
+        ]);
+        assert_eq!(entries, expected);
+
+        std::fs::write(dir.join("app"), b"changed").unwrap();
+        assert_ne!(tree_checksum(&tree_entries(&dir).unwrap()), tree_checksum(&expected));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
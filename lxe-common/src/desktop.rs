@@ -0,0 +1,281 @@
+//! Desktop Entry Generation
+//!
+//! Renders the `.desktop` file for an installed app. Shared by the runtime
+//! installer and CLI commands that install without the runtime (e.g.,
+//! transactions).
+
+use crate::metadata::{DesktopAction, LxeMetadata};
+use std::path::Path;
+
+/// Render the `.desktop` file for an app installed in `app_dir`
+/// 
+/// `existing` is the current entry (if any); user-added actions in it are kept.
+pub fn render_desktop_entry(
+    metadata: &LxeMetadata,
+    app_dir: &Path,
+    runtime_path: &Path,
+    existing: Option<&str>,
+) -> String {
+    let exec_path = app_dir.join(&metadata.exec);
+    
+    // FIX: Use absolute path to icon instead of relying on icon theme lookup
+    // This is more reliable and doesn't require gtk-update-icon-cache
+    let icon_value = if let Some(ref icon_filename) = metadata.icon {
+        let icon_path = app_dir.join(icon_filename);
+        if icon_path.exists() {
+            // Use absolute path - most reliable method
+            icon_path.display().to_string()
+        } else {
+            // Fallback to app_id for theme lookup
+            metadata.app_id.clone()
+        }
+    } else {
+        metadata.app_id.clone()
+    };
+    
+    let terminal = if metadata.terminal { "true" } else { "false" };
+    
+    // Custom actions from the package (re-validated: metadata comes from the file)
+    let actions: Vec<_> = metadata.desktop_actions.iter()
+        .filter(|action| match action.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::warn!("Skipping desktop action: {}", e);
+                false
+            }
+        })
+        .collect();
+    
+    // Preserve actions the user added to an existing entry (e.g., on reinstall)
+    let user_sections = existing
+        .map(|existing| user_desktop_actions(existing, &metadata.desktop_actions))
+        .unwrap_or_default();
+    
+    let dbus_line = if metadata.dbus_activatable { "DBusActivatable=true\n" } else { "" };
+    let mime_line = if metadata.mime_types.is_empty() {
+        String::new()
+    } else {
+        format!("MimeType={};\n", metadata.mime_types.join(";"))
+    };
+    let exec = format!("{}{}", exec_path.display(), exec_field_code(&metadata.mime_types));
+    // Lets the next rewrite tell the package's actions from the user's
+    let package_actions_line = if actions.is_empty() {
+        String::new()
+    } else {
+        format!("{}{};\n", PACKAGE_ACTIONS_KEY, actions.iter().map(|a| a.id.as_str()).collect::<Vec<_>>().join(";"))
+    };
+    
+    // Uninstall is always listed last
+    let mut action_ids: String = actions.iter()
+        .map(|a| format!("{};", a.id))
+        .chain(user_sections.iter().map(|(id, _)| format!("{};", id)))
+        .collect();
+    action_ids.push_str("Uninstall;");
+    
+    let mut action_sections = String::new();
+    for action in &actions {
+        let exec = if action.exec_args.is_empty() {
+            exec_path.display().to_string()
+        } else {
+            format!("{} {}", exec_path.display(), action.exec_args)
+        };
+        action_sections.push_str(&format!(
+            "\n[Desktop Action {}]\nName={}\nExec={}\n",
+            action.id, action.name, exec
+        ));
+    }
+    for (_, section) in &user_sections {
+        action_sections.push('\n');
+        action_sections.push_str(section);
+    }
+    
+    format!(
+        r#"[Desktop Entry]
+Type=Application
+Name={name}
+Comment={comment}
+Exec={exec}
+Icon={icon}
+Terminal={terminal}
+Categories={categories}
+StartupWMClass={wm_class}
+{mime_line}{dbus_line}X-LXE-Version={version}
+X-LXE-AppId={app_id}
+{package_actions_line}Actions={actions}
+{action_sections}
+[Desktop Action Uninstall]
+Name=Uninstall {name}
+Exec={runtime_path} --uninstall-gui {app_id}
+"#,
+        name = metadata.name,
+        comment = metadata.description.as_deref().unwrap_or(&metadata.name),
+        exec = exec,
+        icon = icon_value,
+        terminal = terminal,
+        categories = metadata.categories_string(),
+        // Use custom wm_class if provided, otherwise derive from app_id
+        wm_class = metadata.wm_class.as_deref()
+            .unwrap_or_else(|| metadata.app_id.rsplit('.').next().unwrap_or(&metadata.name)),
+        version = metadata.version,
+        app_id = metadata.app_id,
+        mime_line = mime_line,
+        dbus_line = dbus_line,
+        package_actions_line = package_actions_line,
+        actions = action_ids,
+        action_sections = action_sections,
+        // Use the installed runtime path for uninstall action
+        runtime_path = runtime_path.display(),
+    )
+}
+
+/// Field code passing opened files to the app: `%U` if it handles a URL
+/// scheme (`x-scheme-handler/...`), `%F` for other MIME types
+fn exec_field_code(mime_types: &[String]) -> &'static str {
+    if mime_types.is_empty() {
+        ""
+    } else if mime_types.iter().any(|m| m.starts_with("x-scheme-handler/")) {
+        " %U"
+    } else {
+        " %F"
+    }
+}
+
+/// Key listing the package's own actions in a rendered entry
+const PACKAGE_ACTIONS_KEY: &str = "X-LXE-Actions=";
+
+/// Collect `[Desktop Action]` sections a user added to an existing entry
+/// 
+/// Returns (id, section text) for every action that is neither `Uninstall`,
+/// one of the package's own actions, nor one the previous version shipped
+/// (listed under `X-LXE-Actions`), so they survive a rewrite.
+pub fn user_desktop_actions(existing: &str, package_actions: &[DesktopAction]) -> Vec<(String, String)> {
+    let previous_actions: Vec<&str> = existing.lines()
+        .find_map(|line| line.strip_prefix(PACKAGE_ACTIONS_KEY))
+        .map(|ids| ids.split(';').filter(|id| !id.is_empty()).collect())
+        .unwrap_or_default();
+    let mut sections = Vec::new();
+    let mut current: Option<(String, String)> = None;
+    
+    for line in existing.lines() {
+        if line.starts_with('[') {
+            if let Some(section) = current.take() {
+                sections.push(section);
+            }
+            if let Some(id) = line.strip_prefix("[Desktop Action ").and_then(|l| l.strip_suffix(']')) {
+                let is_ours = id == "Uninstall"
+                    || package_actions.iter().any(|a| a.id == id)
+                    || previous_actions.contains(&id);
+                if !is_ours {
+                    current = Some((id.to_string(), String::new()));
+                }
+            }
+        }
+        if let Some((_, ref mut text)) = current {
+            if !line.trim().is_empty() {
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+    if let Some(section) = current {
+        sections.push(section);
+    }
+    
+    sections
+}
+
+/// Registered categories of the XDG menu specification (main, additional
+/// and reserved)
+const XDG_CATEGORIES: &[&str] = &[
+    // Main
+    "AudioVideo", "Audio", "Video", "Development", "Education", "Game", "Graphics",
+    "Network", "Office", "Science", "Settings", "System", "Utility",
+    // Additional
+    "Building", "Debugger", "IDE", "GUIDesigner", "Profiling", "RevisionControl", "Translation",
+    "Calendar", "ContactManagement", "Database", "Dictionary", "Chart", "Email", "Finance",
+    "FlowChart", "PDA", "ProjectManagement", "Presentation", "Spreadsheet", "WordProcessor",
+    "2DGraphics", "VectorGraphics", "RasterGraphics", "3DGraphics", "Scanning", "OCR",
+    "Photography", "Publishing", "Viewer", "TextTools", "DesktopSettings", "HardwareSettings",
+    "Printing", "PackageManager", "Dialup", "InstantMessaging", "Chat", "IRCClient", "Feed",
+    "FileTransfer", "HamRadio", "News", "P2P", "RemoteAccess", "Telephony", "TelephonyTools",
+    "VideoConference", "WebBrowser", "WebDevelopment", "Midi", "Mixer", "Sequencer", "Tuner",
+    "TV", "AudioVideoEditing", "Player", "Recorder", "DiscBurning", "ActionGame",
+    "AdventureGame", "ArcadeGame", "BoardGame", "BlocksGame", "CardGame", "KidsGame",
+    "LogicGame", "RolePlaying", "Shooter", "Simulation", "SportsGame", "StrategyGame", "Art",
+    "Construction", "Music", "Languages", "ArtificialIntelligence", "Astronomy", "Biology",
+    "Chemistry", "ComputerScience", "DataVisualization", "Economy", "Electricity", "Geography",
+    "Geology", "Geoscience", "History", "Humanities", "ImageProcessing", "Literature", "Maps",
+    "Math", "NumericalAnalysis", "MedicalSoftware", "Physics", "Robotics", "Spirituality",
+    "Sports", "ParallelComputing", "Amusement", "Archiving", "Compression", "Electronics",
+    "Emulator", "Engineering", "FileTools", "FileManager", "TerminalEmulator", "Filesystem",
+    "Monitor", "Security", "Accessibility", "Calculator", "Clock", "TextEditor",
+    "Documentation", "Adult", "Core", "KDE", "GNOME", "XFCE", "DDE", "GTK", "Qt", "Motif",
+    "Java", "ConsoleOnly",
+    // Reserved
+    "Screensaver", "TrayIcon", "Applet", "Shell",
+];
+
+/// A registered XDG menu category, or a vendor extension (`X-Foo`)
+pub fn is_xdg_category(s: &str) -> bool {
+    XDG_CATEGORIES.contains(&s) || s.strip_prefix("X-").is_some_and(|rest| !rest.is_empty())
+}
+
+/// `type/subtype` with RFC 6838 restricted-name characters
+pub fn is_mime_type(s: &str) -> bool {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part.len() <= 127
+            && part.starts_with(|c: char| c.is_ascii_alphanumeric())
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+    };
+    matches!(s.split_once('/'), Some((t, sub)) if valid(t) && valid(sub))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_desktop_actions_preserved() {
+        let existing = "[Desktop Entry]\nName=App\nActions=new-window;Mine;Uninstall;\n\n\
+                        [Desktop Action new-window]\nName=New Window\nExec=app --new-window\n\n\
+                        [Desktop Action Mine]\nName=My Action\nExec=app --mine\n\n\
+                        [Desktop Action Uninstall]\nName=Uninstall App\nExec=lxe-runtime --uninstall-gui a.b\n";
+        let package_actions = vec![DesktopAction {
+            id: "new-window".to_string(),
+            name: "New Window".to_string(),
+            exec_args: "--new-window".to_string(),
+        }];
+        
+        let user = user_desktop_actions(existing, &package_actions);
+        assert_eq!(user.len(), 1);
+        assert_eq!(user[0].0, "Mine");
+        assert!(user[0].1.contains("Exec=app --mine"));
+        
+        // An action the previous version shipped goes away with it
+        let metadata = LxeMetadata {
+            desktop_actions: package_actions,
+            ..LxeMetadata::new("a.b", "App", "1.0.0", "app", 0, "")
+        };
+        let rendered = render_desktop_entry(&metadata, Path::new("/opt/app"), Path::new("lxe-runtime"), Some(existing));
+        assert!(rendered.contains("\nX-LXE-Actions=new-window;\n"));
+        assert_eq!(user_desktop_actions(&rendered, &[]).iter().map(|(id, _)| id.as_str()).collect::<Vec<_>>(), ["Mine"]);
+    }
+    
+    #[test]
+    fn test_mime_types() {
+        let mut metadata = LxeMetadata::new("com.example.Editor", "Editor", "1.0.0", "editor", 0, "");
+        let entry = render_desktop_entry(&metadata, Path::new("/opt/editor"), Path::new("lxe-runtime"), None);
+        assert!(!entry.contains("MimeType="));
+        assert!(entry.contains("\nExec=/opt/editor/editor\n"));
+        
+        metadata.mime_types = vec!["text/markdown".into(), "image/svg+xml".into()];
+        let entry = render_desktop_entry(&metadata, Path::new("/opt/editor"), Path::new("lxe-runtime"), None);
+        assert!(entry.contains("\nMimeType=text/markdown;image/svg+xml;\n"));
+        assert!(entry.contains("\nExec=/opt/editor/editor %F\n"));
+        
+        metadata.mime_types.push("x-scheme-handler/editor".into());
+        let entry = render_desktop_entry(&metadata, Path::new("/opt/editor"), Path::new("lxe-runtime"), None);
+        assert!(entry.contains("\nExec=/opt/editor/editor %U\n"));
+    }
+}
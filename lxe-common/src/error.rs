@@ -0,0 +1,100 @@
+//! LXE Error Types
+//!
+//! Typed errors for failures that callers need to tell apart
+//! (e.g., to show a dedicated message in the GUI). Everything else
+//! stays `anyhow::Error`; these convert into it and can be recovered
+//! with `downcast_ref::<LxeError>()`.
+
+use thiserror::Error;
+
+const MB: u64 = 1024 * 1024;
+
+/// Errors with a specific meaning to the CLI and runtime
+#[derive(Debug, Error)]
+pub enum LxeError {
+    /// The running system is older than the package requires
+    #[error("System requirement not met: {requirement} (found {found})")]
+    SystemRequirementNotMet {
+        requirement: String,
+        found: String,
+    },
+    
+    /// The signature policy requires a signed package
+    #[error("Package {app_id} is unsigned, but the signature policy requires signed packages")]
+    SignatureRequired {
+        app_id: String,
+    },
+
+    /// The payload signature doesn't match the package's public key
+    #[error(
+        "SECURITY: Package signature verification FAILED!\n\n\
+         This package may have been tampered with.\n\
+         Do not install it unless you trust the source.\n\n\
+         If you're a developer, check that:\n\
+         1. The private key matches the public key in the package\n\
+         2. The metadata wasn't modified after signing"
+    )]
+    SignatureInvalid,
+
+    /// The signing key isn't trusted for the app
+    #[error("Package {app_id} is not signed by a trusted key: {reason}")]
+    UntrustedSigner {
+        app_id: String,
+        reason: String,
+    },
+
+    /// The payload doesn't hash to the checksum in the metadata
+    #[error("Payload checksum mismatch: expected {expected}, got {computed}")]
+    ChecksumMismatch {
+        expected: String,
+        computed: String,
+    },
+
+    /// The filesystem can't hold the app (sizes in bytes, `needed` includes headroom)
+    #[error(
+        "Not enough disk space: needs {} MB (including 20% headroom), but only {} MB are available",
+        .needed.div_ceil(MB),
+        .available / MB
+    )]
+    InsufficientDiskSpace {
+        needed: u64,
+        available: u64,
+    },
+
+    /// The payload can't be decompressed or unpacked
+    #[error("Package payload is corrupted: {0}")]
+    PayloadCorrupted(String),
+
+    /// The user declined (or couldn't give) polkit authorization
+    #[error("Authorization denied")]
+    AuthorizationDenied,
+
+    /// This version is already installed (silent mode without `--force`)
+    #[error("Version {version} is already installed")]
+    AlreadyInstalled {
+        version: String,
+    },
+
+    /// The user cancelled the installation
+    #[error("Installation cancelled")]
+    Cancelled,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_downcast_through_context() {
+        let err = LxeError::InsufficientDiskSpace { needed: 3 * MB + 1, available: 2 * MB };
+        assert_eq!(err.to_string(), "Not enough disk space: needs 4 MB (including 20% headroom), but only 2 MB are available");
+
+        let result: anyhow::Result<()> = Err(err.into());
+        let err = result.context("Cannot install Editor").unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<LxeError>(),
+            Some(LxeError::InsufficientDiskSpace { available, .. }) if *available == 2 * MB
+        ));
+    }
+}
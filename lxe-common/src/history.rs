@@ -0,0 +1,176 @@
+//! Install History
+//!
+//! Manifests only describe what is installed now; `history.jsonl` (next to
+//! the manifests in ~/.local/share/lxe) keeps what happened, one JSON event
+//! per line, appended on every install, upgrade and uninstall:
+//!
+//! ```text
+//! {"event":"install","app_id":"com.example.App","version":"1.0.0","timestamp":"2024-05-01T12:00:00Z","is_system":false}
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryEventKind {
+    Install,
+    /// Install over an earlier version
+    Upgrade,
+    Uninstall,
+}
+
+impl HistoryEventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Install => "install",
+            Self::Upgrade => "upgrade",
+            Self::Uninstall => "uninstall",
+        }
+    }
+}
+
+/// One line of the history
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEvent {
+    pub event: HistoryEventKind,
+    pub app_id: String,
+    pub version: String,
+    /// UTC, RFC 3339 (`2024-05-01T12:00:00Z`)
+    pub timestamp: String,
+    pub is_system: bool,
+    /// Version replaced by an upgrade
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_version: Option<String>,
+}
+
+impl HistoryEvent {
+    /// An event happening now
+    pub fn new(event: HistoryEventKind, app_id: &str, version: &str, is_system: bool) -> Self {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        Self {
+            event,
+            app_id: app_id.to_string(),
+            version: version.to_string(),
+            timestamp: format_timestamp(secs),
+            is_system,
+            previous_version: None,
+        }
+    }
+
+    /// Install event, or an upgrade of `previous_version`
+    pub fn installed(app_id: &str, version: &str, is_system: bool, previous_version: Option<&str>) -> Self {
+        let kind = if previous_version.is_some() { HistoryEventKind::Upgrade } else { HistoryEventKind::Install };
+        Self {
+            previous_version: previous_version.map(str::to_string),
+            ..Self::new(kind, app_id, version, is_system)
+        }
+    }
+}
+
+/// The user's history file (~/.local/share/lxe/history.jsonl)
+pub fn history_path() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("~/.local/share"))
+        .join("lxe")
+        .join("history.jsonl")
+}
+
+/// Append `event` to the user's history
+///
+/// Installs for another user run as that user by now (see the runtime's
+/// `target_user`), so the file never needs its ownership fixed afterwards.
+pub fn append(event: &HistoryEvent) -> Result<()> {
+    append_to(&history_path(), event)
+}
+
+/// Append `event` to the history at `path`
+pub fn append_to(path: &Path, event: &HistoryEvent) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).context("Failed to create history directory")?;
+    }
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    // One write per line, so concurrent installs don't interleave; never
+    // follow a symlink planted in place of the history file
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Events of the history at `path`, oldest first; unreadable lines are skipped
+pub fn load_from(path: &Path) -> Result<Vec<HistoryEvent>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    Ok(content.lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// The user's events, oldest first
+pub fn load() -> Result<Vec<HistoryEvent>> {
+    load_from(&history_path())
+}
+
+/// Seconds since the epoch as RFC 3339 UTC
+fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, rem / 3600, rem % 3600 / 60, rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_roundtrip() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_714_564_800), "2024-05-01T12:00:00Z");
+
+        let path = std::env::temp_dir().join(format!("lxe-history-test-{}/history.jsonl", std::process::id()));
+        let install = HistoryEvent::installed("com.example.App", "1.0.0", false, None);
+        let upgrade = HistoryEvent::installed("com.example.App", "1.1.0", false, Some("1.0.0"));
+        append_to(&path, &install).unwrap();
+        append_to(&path, &upgrade).unwrap();
+        fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"not json\n").unwrap();
+
+        let events = load_from(&path).unwrap();
+        assert_eq!(events, [install.clone(), upgrade]);
+        assert_eq!(events[1].event, HistoryEventKind::Upgrade);
+        let line = fs::read_to_string(&path).unwrap();
+        assert!(line.starts_with(r#"{"event":"install","app_id":"com.example.App","version":"1.0.0","timestamp":""#));
+
+        let link = path.with_file_name("link.jsonl");
+        std::os::unix::fs::symlink(&path, &link).unwrap();
+        assert!(append_to(&link, &install).is_err());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,122 @@
+//! Build Hooks (`[build.hooks]`)
+//!
+//! Scripts that wrap a package build (`lxe build` or `lxe-build-rs`), e.g.
+//! to upload the finished package:
+//!
+//! ```toml
+//! [build.hooks]
+//! pre_build = "git diff --quiet"
+//! post_build = "./upload.sh \"$LXE_OUTPUT_PATH\""
+//! on_build_failed = "./notify.sh \"$LXE_ERROR\""
+//! ```
+//!
+//! Every hook gets `LXE_APP_ID` and `LXE_VERSION`; `post_build` also gets
+//! `LXE_OUTPUT_PATH` and `on_build_failed` gets `LXE_ERROR`.
+//!
+//! When the caller keeps stdout for machine-readable output (`lxe build
+//! --output-format json`), a hook's stdout goes to stderr instead.
+//!
+//! Only available with the `build` feature.
+
+use anyhow::{Context, Result};
+use crate::config::LxeConfig;
+use std::os::fd::AsFd;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// When a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildHook {
+    PreBuild,
+    PostBuild,
+    OnBuildFailed,
+}
+
+impl BuildHook {
+    /// Key in `[build.hooks]`
+    pub fn name(self) -> &'static str {
+        match self {
+            BuildHook::PreBuild => "pre_build",
+            BuildHook::PostBuild => "post_build",
+            BuildHook::OnBuildFailed => "on_build_failed",
+        }
+    }
+
+    /// The configured script, if any
+    pub fn script(self, config: &LxeConfig) -> Option<&str> {
+        let hooks = &config.build.hooks;
+        match self {
+            BuildHook::PreBuild => hooks.pre_build.as_deref(),
+            BuildHook::PostBuild => hooks.post_build.as_deref(),
+            BuildHook::OnBuildFailed => hooks.on_build_failed.as_deref(),
+        }
+    }
+}
+
+/// Run `hook` if it is configured; returns whether it ran
+///
+/// `env` is added to the package variables. With `stdout_to_stderr` the
+/// hook's stdout is written to stderr. A failing hook is an error.
+pub fn run(hook: BuildHook, config: &LxeConfig, dir: &Path, env: &[(&str, &str)], stdout_to_stderr: bool) -> Result<bool> {
+    let Some(script) = hook.script(config) else {
+        return Ok(false);
+    };
+
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(script)
+        .current_dir(dir)
+        .env("LXE_APP_ID", &config.package.id)
+        .env("LXE_VERSION", &config.package.version)
+        .envs(env.iter().copied());
+    if stdout_to_stderr {
+        redirect_stdout_to_stderr(&mut command)?;
+    }
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run {} hook", hook.name()))?;
+    if !status.success() {
+        anyhow::bail!("{} hook failed with exit code: {:?}", hook.name(), status.code());
+    }
+    Ok(true)
+}
+
+/// Let a child process write its stdout to our stderr
+pub fn redirect_stdout_to_stderr(command: &mut Command) -> Result<()> {
+    let stderr = std::io::stderr().as_fd().try_clone_to_owned()
+        .context("Failed to duplicate stderr")?;
+    command.stdout(Stdio::from(stderr));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_hook_env() {
+        let dir = std::env::temp_dir().join(format!("lxe-hooks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let config = LxeConfig::from_str(
+            r#"
+            [package]
+            name = "App"
+            id = "com.example.App"
+            version = "1.2.0"
+            executable = "app"
+
+            [build.hooks]
+            post_build = "echo \"$LXE_APP_ID $LXE_VERSION $LXE_OUTPUT_PATH\" > hook.out"
+            on_build_failed = "exit 7"
+            "#,
+        ).unwrap();
+
+        assert!(!run(BuildHook::PreBuild, &config, &dir, &[], false).unwrap());
+        assert!(run(BuildHook::PostBuild, &config, &dir, &[("LXE_OUTPUT_PATH", "app.lxe")], false).unwrap());
+        assert_eq!(std::fs::read_to_string(dir.join("hook.out")).unwrap(), "com.example.App 1.2.0 app.lxe\n");
+        assert!(run(BuildHook::OnBuildFailed, &config, &dir, &[], true).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+//! Icon Optimization for `lxe build --compress-icons`
+//!
+//! The package icon is recompressed with `oxipng` (or `optipng`) and, if
+//! larger than 512x512, scaled down with ImageMagick. Like stripped binaries,
+//! the result lives in a staging directory and replaces the original only
+//! inside the archive.
+//!
+//! SVG icons can also be rendered to PNG (`[build] convert_icon_to_png`,
+//! `generate_icon_sizes`) with resvg, so neither the build machine nor the
+//! user's machine needs librsvg. The PNGs are written into the input
+//! directory.
+//!
+//! Only available with the `build` feature.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::paths::icons::png_dimensions;
+
+/// Largest standard hicolor size; bigger icons are only wasted bytes
+pub const MAX_ICON_SIZE: u32 = 512;
+
+/// An optimized copy of the package icon
+pub struct OptimizedIcon {
+    /// The optimized file, to be archived in place of the original
+    pub path: PathBuf,
+    pub original_size: u64,
+    pub optimized_size: u64,
+
+    /// Dimensions of the original icon
+    pub dimensions: (u32, u32),
+
+    /// Whether it was scaled down to `MAX_ICON_SIZE` (needs ImageMagick)
+    pub resized: bool,
+
+    /// The PNG optimizer that ran, if any was installed
+    pub optimizer: Option<&'static str>,
+
+    // Holds the optimized copy until the archive is written
+    _staging: tempfile::TempDir,
+}
+
+/// Optimize the PNG at `icon`
+///
+/// Returns `Ok(None)` for icons that aren't PNGs.
+pub fn optimize_icon(icon: &Path, level: u8) -> Result<Option<OptimizedIcon>> {
+    let data = fs::read(icon)
+        .with_context(|| format!("Failed to read icon {}", icon.display()))?;
+    let Some((width, height)) = png_dimensions(&data) else {
+        return Ok(None);
+    };
+
+    let staging = tempfile::Builder::new()
+        .prefix("lxe-icon-")
+        .tempdir()
+        .context("Failed to create staging directory")?;
+    let path = staging.path().join("icon.png");
+    fs::write(&path, &data)?;
+
+    let mut resized = false;
+    if (width > MAX_ICON_SIZE || height > MAX_ICON_SIZE) && tool_available("convert") {
+        let geometry = format!("{0}x{0}", MAX_ICON_SIZE);
+        let status = Command::new("convert")
+            .arg(icon)
+            .args(["-resize", &geometry])
+            .arg(&path)
+            .status()
+            .context("Failed to run convert")?;
+        resized = status.success();
+    }
+
+    let level = level.min(6).to_string();
+    let optimizer = if tool_available("oxipng") {
+        run_quiet(Command::new("oxipng").args(["-o", &level, "--strip", "safe", "-q"]).arg(&path))?;
+        Some("oxipng")
+    } else if tool_available("optipng") {
+        run_quiet(Command::new("optipng").args(["-o", &level, "-strip", "all", "-quiet"]).arg(&path))?;
+        Some("optipng")
+    } else {
+        None
+    };
+
+    let original_size = data.len() as u64;
+    let mut optimized_size = fs::metadata(&path)?.len();
+    if optimized_size > original_size && !resized {
+        // Already optimal; keep the original bytes
+        fs::write(&path, &data)?;
+        optimized_size = original_size;
+    }
+
+    Ok(Some(OptimizedIcon {
+        path,
+        original_size,
+        optimized_size,
+        dimensions: (width, height),
+        resized,
+        optimizer,
+        _staging: staging,
+    }))
+}
+
+/// Render the SVG at `svg` as a `size`x`size` PNG at `png`
+///
+/// Drawings that aren't square are scaled to fit and centered. Returns
+/// `false` without rendering if `png` is newer than `svg` and already has
+/// that size (so rebuilds don't touch the input).
+pub fn svg_to_png(svg: &Path, size: u32, png: &Path) -> Result<bool> {
+    let up_to_date = || -> Option<bool> {
+        let newer = fs::metadata(png).ok()?.modified().ok()? >= fs::metadata(svg).ok()?.modified().ok()?;
+        Some(newer && png_dimensions(&fs::read(png).ok()?)? == (size, size))
+    };
+    if up_to_date().unwrap_or(false) {
+        return Ok(false);
+    }
+
+    let data = fs::read(svg)
+        .with_context(|| format!("Failed to read icon {}", svg.display()))?;
+    let tree = resvg::usvg::Tree::from_data(&data, &resvg::usvg::Options::default())
+        .with_context(|| format!("Failed to parse SVG icon {}", svg.display()))?;
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size)
+        .context("Icon size must be at least 1 pixel")?;
+
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = size as f32 / width.max(height);
+    let transform = resvg::tiny_skia::Transform::from_scale(scale, scale).post_translate(
+        (size as f32 - width * scale) / 2.0,
+        (size as f32 - height * scale) / 2.0,
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    if let Some(parent) = png.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    pixmap.save_png(png)
+        .with_context(|| format!("Failed to write {}", png.display()))?;
+    Ok(true)
+}
+
+/// Where `generate_icon_sizes` renders `icon` at `size`, relative to the
+/// input directory (the runtime installs these into hicolor)
+pub fn hicolor_icon_path(icon: &str, size: u32) -> PathBuf {
+    let stem = Path::new(icon).file_stem().unwrap_or_default().to_string_lossy();
+    PathBuf::from(format!("icons/hicolor/{0}x{0}/apps/{1}.png", size, stem))
+}
+
+fn run_quiet(command: &mut Command) -> Result<()> {
+    let output = command.output().context("Failed to run PNG optimizer")?;
+    if !output.status.success() {
+        anyhow::bail!("PNG optimizer failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+fn tool_available(tool: &str) -> bool {
+    Command::new("which")
+        .arg(tool)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_to_png() {
+        let dir = tempfile::tempdir().unwrap();
+        let svg = dir.path().join("app.svg");
+        fs::write(&svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="48" height="24"><rect width="48" height="24" fill="red"/></svg>"#).unwrap();
+
+        let png = dir.path().join(hicolor_icon_path("app.svg", 64));
+        assert!(svg_to_png(&svg, 64, &png).unwrap());
+        assert_eq!(png_dimensions(&fs::read(&png).unwrap()), Some((64, 64)));
+        assert!(!svg_to_png(&svg, 64, &png).unwrap());
+        assert!(svg_to_png(&svg, 32, &png).unwrap());
+    }
+}
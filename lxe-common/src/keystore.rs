@@ -0,0 +1,382 @@
+//! Trusted Public Key Store
+//!
+//! Keeps the list of publisher keys the user has explicitly chosen to trust.
+//! Stored as TOML at `~/.config/lxe/trusted-keys.toml`:
+//!
+//! ```toml
+//! [[keys]]
+//! name = "acme"
+//! public_key = "base64..."
+//! added_at = "unix:1700000000"
+//! # Optional: the key that vouches for this one (e.g. a company CA)
+//! issuer_key = "base64..."
+//! issuer_name = "Acme Root"
+//! # Optional: only trust it for these app IDs (globs; default: all apps)
+//! app_ids = ["com.acme.*"]
+//! ```
+//!
+//! Several keys may be trusted for the same apps, so publishers can rotate
+//! keys or have more than one signing authority. Once a key is trusted for
+//! an app ID, only keys trusted for that app ID may sign it.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::error::LxeError;
+use crate::paths;
+
+/// A single trusted publisher key
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TrustedKey {
+    /// User-chosen label for the key
+    pub name: String,
+
+    /// Ed25519 public key (base64, 32 bytes)
+    pub public_key: String,
+
+    /// When the key was imported
+    pub added_at: String,
+
+    /// Public key of the issuer that certified this key, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer_key: Option<String>,
+
+    /// Display name of the issuer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer_name: Option<String>,
+
+    /// App ID globs this key is trusted for (empty: every app)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub app_ids: Vec<String>,
+}
+
+impl TrustedKey {
+    /// Whether the key is trusted to sign `app_id`
+    pub fn covers(&self, app_id: &str) -> bool {
+        self.app_ids.is_empty() || self.app_ids.iter().any(|pattern| glob_matches(pattern, app_id))
+    }
+}
+
+/// One step in a key's chain of issuers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainLink<'a> {
+    /// A key in the trust store
+    Trusted(&'a TrustedKey),
+    /// A key that is not in the trust store
+    Unknown {
+        public_key: String,
+        name: Option<String>,
+    },
+}
+
+/// The set of keys the user trusts
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TrustStore {
+    #[serde(default)]
+    pub keys: Vec<TrustedKey>,
+}
+
+impl TrustStore {
+    /// Default location of the trust store
+    pub fn default_path() -> Result<PathBuf> {
+        paths::lxe::trusted_keys_path()
+            .context("Could not determine config directory")
+    }
+
+    /// Load the trust store from the default location (empty if missing)
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::default_path()?)
+    }
+
+    /// Load the trust store from a specific file (empty if missing)
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read trust store: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse trust store: {}", path.display()))
+    }
+
+    /// Save the trust store to the default location
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::default_path()?)
+    }
+
+    /// Save the trust store to a specific file
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+
+        let content = toml::to_string_pretty(self)
+            .context("Failed to serialize trust store")?;
+        fs::write(path, content)
+            .with_context(|| format!("Failed to write trust store: {}", path.display()))
+    }
+
+    /// Add a key under a new name
+    pub fn add(&mut self, name: &str, public_key: &str) -> Result<()> {
+        if self.find(name).is_some() {
+            bail!("A trusted key named '{}' already exists", name);
+        }
+        if let Some(existing) = self.keys.iter().find(|k| k.public_key == public_key) {
+            bail!("This key is already trusted as '{}'", existing.name);
+        }
+
+        self.keys.push(TrustedKey {
+            name: name.to_string(),
+            public_key: public_key.to_string(),
+            added_at: unix_now(),
+            issuer_key: None,
+            issuer_name: None,
+            app_ids: Vec::new(),
+        });
+        Ok(())
+    }
+
+    /// Trust `public_key` for apps matching `app_id` (every app if `None`)
+    ///
+    /// A key that is already trusted gets the pattern added to its scope;
+    /// a new key is stored as `name`.
+    pub fn trust(&mut self, name: &str, public_key: &str, app_id: Option<&str>) -> Result<&TrustedKey> {
+        let existing = self.keys.iter().position(|k| k.public_key == public_key);
+        let index = match existing {
+            Some(index) => index,
+            None => {
+                self.add(name, public_key)?;
+                self.keys.len() - 1
+            }
+        };
+
+        let key = &mut self.keys[index];
+        match app_id {
+            None => key.app_ids.clear(),
+            // An existing unrestricted key already covers every app
+            Some(_) if existing.is_some() && key.app_ids.is_empty() => {}
+            Some(pattern) => {
+                if !key.app_ids.iter().any(|p| p == pattern) {
+                    key.app_ids.push(pattern.to_string());
+                }
+            }
+        }
+        Ok(&self.keys[index])
+    }
+
+    /// Record that the trusted key `issuer` certified the key named `name`
+    pub fn set_issuer(&mut self, name: &str, issuer: &str) -> Result<()> {
+        let issuer = self.find(issuer)
+            .with_context(|| format!("No trusted key named '{}'", issuer))?
+            .clone();
+        let key = self.keys.iter_mut()
+            .find(|k| k.name == name)
+            .with_context(|| format!("No trusted key named '{}'", name))?;
+        if key.public_key == issuer.public_key {
+            bail!("A key cannot be its own issuer");
+        }
+        key.issuer_key = Some(issuer.public_key);
+        key.issuer_name = Some(issuer.name);
+        Ok(())
+    }
+
+    /// Remove a key by name, returning it if present
+    pub fn remove(&mut self, name: &str) -> Option<TrustedKey> {
+        let index = self.keys.iter().position(|k| k.name == name)?;
+        Some(self.keys.remove(index))
+    }
+
+    /// Look up a key by name
+    pub fn find(&self, name: &str) -> Option<&TrustedKey> {
+        self.keys.iter().find(|k| k.name == name)
+    }
+
+    /// Check whether a public key (base64) is trusted
+    pub fn is_trusted(&self, public_key: &str) -> bool {
+        self.keys.iter().any(|k| k.public_key == public_key)
+    }
+
+    /// The trusted key `public_key` if it may sign `app_id`
+    pub fn trusted_for(&self, public_key: &str, app_id: &str) -> Option<&TrustedKey> {
+        self.find_by_public_key(public_key).filter(|k| k.covers(app_id))
+    }
+
+    /// Check that `public_key` may sign `app_id`
+    ///
+    /// Returns the trusted key, or `None` if the store has no say about
+    /// the app. Fails when keys are trusted for the app (by an `app_ids`
+    /// pattern) and `public_key` is not one of them.
+    pub fn check_signer(&self, public_key: &str, app_id: &str) -> Result<Option<&TrustedKey>> {
+        if let Some(key) = self.trusted_for(public_key, app_id) {
+            return Ok(Some(key));
+        }
+        let owners: Vec<&str> = self.keys.iter()
+            .filter(|k| !k.app_ids.is_empty() && k.covers(app_id))
+            .map(|k| k.name.as_str())
+            .collect();
+        if !owners.is_empty() {
+            return Err(LxeError::UntrustedSigner {
+                app_id: app_id.to_string(),
+                reason: format!("it may only be signed by {}", owners.join(", ")),
+            }.into());
+        }
+        Ok(None)
+    }
+
+    /// Look up a key by its public key
+    pub fn find_by_public_key(&self, public_key: &str) -> Option<&TrustedKey> {
+        self.keys.iter().find(|k| k.public_key == public_key)
+    }
+
+    /// The chain from `public_key` up to its root issuer
+    ///
+    /// Ends at a key without issuer, at an issuer missing from the store,
+    /// or where the chain would loop.
+    pub fn chain(&self, public_key: &str) -> Vec<ChainLink<'_>> {
+        let mut chain = Vec::new();
+        let mut next = Some((public_key.to_string(), None));
+
+        while let Some((key, name)) = next.take() {
+            let Some(trusted) = self.find_by_public_key(&key) else {
+                chain.push(ChainLink::Unknown { public_key: key, name });
+                break;
+            };
+            let seen = chain.iter().any(|link| link == &ChainLink::Trusted(trusted));
+            if seen {
+                break;
+            }
+            chain.push(ChainLink::Trusted(trusted));
+            next = trusted.issuer_key.clone().map(|k| (k, trusted.issuer_name.clone()));
+        }
+        chain
+    }
+}
+
+/// Shell-style glob with `*` and `?`
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    // Iterative matcher with backtracking to the last `*`
+    let (mut p, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Current time as "unix:<secs>" (same format as install manifests)
+fn unix_now() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("unix:{}", duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_remove_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("lxe-keystore-{}", std::process::id()));
+        let path = dir.join("trusted-keys.toml");
+
+        let mut store = TrustStore::default();
+        store.add("acme", "AAAA").unwrap();
+        assert!(store.add("acme", "BBBB").is_err());
+        assert!(store.add("other", "AAAA").is_err());
+        store.save_to(&path).unwrap();
+
+        let mut loaded = TrustStore::load_from(&path).unwrap();
+        assert!(loaded.is_trusted("AAAA"));
+        assert_eq!(loaded.remove("acme").unwrap().public_key, "AAAA");
+        assert!(loaded.keys.is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_trust_app_ids() {
+        let mut store = TrustStore::default();
+        store.trust("old", "OLD", Some("com.acme.*")).unwrap();
+        store.trust("new", "NEW", Some("com.acme.*")).unwrap();
+        store.trust("ignored", "NEW", Some("org.acme.Tool")).unwrap();
+        assert_eq!(store.keys.len(), 2);
+        assert_eq!(store.keys[1].app_ids, ["com.acme.*", "org.acme.Tool"]);
+
+        // Either key may sign the apps it covers
+        assert_eq!(store.trusted_for("OLD", "com.acme.App").unwrap().name, "old");
+        assert_eq!(store.trusted_for("NEW", "com.acme.App").unwrap().name, "new");
+        assert!(store.trusted_for("OLD", "org.acme.Tool").is_none());
+        assert!(store.trusted_for("OTHER", "com.acme.App").is_none());
+
+        // Without a pattern the key covers every app, and stays that way
+        store.trust("old", "OLD", None).unwrap();
+        store.trust("old", "OLD", Some("com.other.*")).unwrap();
+        assert!(store.keys[0].app_ids.is_empty());
+        assert!(store.trusted_for("OLD", "org.anything").is_some());
+    }
+
+    #[test]
+    fn test_check_signer() {
+        let mut store = TrustStore::default();
+        store.trust("acme", "ACME", Some("com.acme.*")).unwrap();
+        store.trust("anyone", "ANY", None).unwrap();
+
+        assert_eq!(store.check_signer("ACME", "com.acme.App").unwrap().unwrap().name, "acme");
+        // Scoped apps need a key trusted for them, even one trusted elsewhere
+        assert!(store.check_signer("OTHER", "com.acme.App").is_err());
+        assert!(store.check_signer("ANY", "com.acme.App").is_ok());
+        store.trust("other", "OTHER", Some("org.other.*")).unwrap();
+        assert!(store.check_signer("OTHER", "com.acme.App").is_err());
+        // Apps nobody is scoped to are left to the signature policy
+        assert!(store.check_signer("OTHER", "org.unknown.App").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("com.example.*", "com.example.App"));
+        assert!(glob_matches("*.App", "com.example.App"));
+        assert!(glob_matches("com.?xample.*", "com.example.App"));
+        assert!(!glob_matches("org.*", "com.example.App"));
+        assert!(glob_matches("*", ""));
+    }
+
+    #[test]
+    fn test_issuer_chain() {
+        let mut store = TrustStore::default();
+        store.add("root", "ROOT").unwrap();
+        store.add("team", "TEAM").unwrap();
+        store.set_issuer("team", "root").unwrap();
+        store.keys[0].issuer_key = Some("OFFLINE".into());
+
+        let chain = store.chain("TEAM");
+        assert_eq!(chain.len(), 3);
+        assert!(matches!(chain[1], ChainLink::Trusted(k) if k.name == "root"));
+        assert!(matches!(&chain[2], ChainLink::Unknown { public_key, .. } if public_key == "OFFLINE"));
+
+        // Loops end the chain
+        store.keys[0].issuer_key = Some("TEAM".into());
+        assert_eq!(store.chain("TEAM").len(), 2);
+    }
+}
@@ -0,0 +1,151 @@
+//! Leftover Detection (`lxe uninstall --scan-leftovers`)
+//!
+//! Apps write config and caches the install manifest doesn't know about.
+//! After an uninstall, these directories are found by name:
+//!
+//! - `~/.config/<fragment>/`, `~/.cache/<fragment>/` where a fragment is the
+//!   app ID or its last segment (`com.example.MyApp` → `MyApp`, `myapp`)
+//! - `~/.local/share/<app_id>/`
+//! - `/tmp/<app_id>-*/`
+
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where a leftover was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeftoverKind {
+    Config,
+    Cache,
+    Data,
+    Temp,
+}
+
+impl std::fmt::Display for LeftoverKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LeftoverKind::Config => write!(f, "config"),
+            LeftoverKind::Cache => write!(f, "cache"),
+            LeftoverKind::Data => write!(f, "data"),
+            LeftoverKind::Temp => write!(f, "temp"),
+        }
+    }
+}
+
+/// A directory probably left behind by an uninstalled app
+#[derive(Debug, Clone)]
+pub struct LeftoverEntry {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub kind: LeftoverKind,
+}
+
+/// Shorter fragments (e.g. `app`) would match other programs' directories
+const MIN_FRAGMENT_LEN: usize = 3;
+
+/// Directories leftovers must never be, even if a fragment matches
+const PROTECTED_DIRS: &[&str] = &[
+    "/", "/bin", "/boot", "/dev", "/etc", "/home", "/lib", "/lib64", "/opt", "/proc",
+    "/root", "/run", "/sbin", "/srv", "/sys", "/tmp", "/usr", "/var",
+];
+
+/// Find directories in the user's config, cache, data and temp locations
+/// that are named after `app_id`
+pub fn find_leftover_files(app_id: &str) -> Result<Vec<LeftoverEntry>> {
+    if app_id.is_empty() || app_id.contains(['/', '\0']) || app_id.starts_with('.') {
+        anyhow::bail!("Invalid app ID: {:?}", app_id);
+    }
+
+    let fragments = app_id_fragments(app_id);
+    let mut candidates: Vec<(PathBuf, LeftoverKind)> = Vec::new();
+    for (root, kind) in [(dirs::config_dir(), LeftoverKind::Config), (dirs::cache_dir(), LeftoverKind::Cache)] {
+        if let Some(root) = root {
+            candidates.extend(fragments.iter().map(|f| (root.join(f), kind)));
+        }
+    }
+    if let Some(data) = dirs::data_local_dir() {
+        candidates.push((data.join(app_id), LeftoverKind::Data));
+    }
+
+    let temp = std::env::temp_dir();
+    let temp_prefix = format!("{}-", app_id);
+    if let Ok(entries) = fs::read_dir(&temp) {
+        candidates.extend(entries.flatten()
+            .filter(|e| e.file_name().to_string_lossy().starts_with(&temp_prefix))
+            .map(|e| (e.path(), LeftoverKind::Temp)));
+    }
+
+    let mut found: Vec<LeftoverEntry> = Vec::new();
+    for (path, kind) in candidates {
+        // Real directories only: a symlink could point anywhere
+        let is_dir = fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir());
+        if !is_dir || !is_safe_leftover(&path) || found.iter().any(|e| e.path == path) {
+            continue;
+        }
+        found.push(LeftoverEntry { size_bytes: dir_size(&path), path, kind });
+    }
+    Ok(found)
+}
+
+/// The app ID and its last segment, as-is and lowercased
+fn app_id_fragments(app_id: &str) -> Vec<String> {
+    let last = app_id.rsplit('.').next().unwrap_or(app_id);
+    let mut fragments = vec![app_id.to_string()];
+    for fragment in [last.to_string(), last.to_lowercase()] {
+        if fragment.len() >= MIN_FRAGMENT_LEN && !fragments.contains(&fragment) {
+            fragments.push(fragment);
+        }
+    }
+    fragments
+}
+
+/// A leftover must be below a directory, never a system directory itself
+pub fn is_safe_leftover(path: &Path) -> bool {
+    if !path.is_absolute() || path.components().count() < 3 {
+        return false;
+    }
+    let protected = PROTECTED_DIRS.iter().any(|dir| path == Path::new(dir));
+    let is_home = dirs::home_dir().is_some_and(|home| path == home);
+    let is_xdg_root = [dirs::config_dir(), dirs::cache_dir(), dirs::data_local_dir()]
+        .into_iter()
+        .flatten()
+        .any(|root| path == root);
+    !(protected || is_home || is_xdg_root)
+}
+
+/// Total size of regular files under `dir` (symlinks are not followed)
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return 0;
+    };
+    entries.flatten()
+        .filter_map(|e| Some((e.path(), e.metadata().ok()?)))
+        .map(|(path, meta)| if meta.is_dir() { dir_size(&path) } else if meta.is_file() { meta.len() } else { 0 })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fragments_and_safety() {
+        assert_eq!(app_id_fragments("com.example.MyApp"), ["com.example.MyApp", "MyApp", "myapp"]);
+        assert_eq!(app_id_fragments("org.x.ab"), ["org.x.ab"]);
+
+        assert!(!is_safe_leftover(Path::new("/etc")));
+        assert!(!is_safe_leftover(Path::new("/tmp")));
+        assert!(!is_safe_leftover(Path::new("relative/dir")));
+        assert!(is_safe_leftover(Path::new("/tmp/com.example.MyApp-1234")));
+
+        let temp = std::env::temp_dir().join(format!("com.example.LeftoverTest{}-run", std::process::id()));
+        fs::create_dir_all(temp.join("sub")).unwrap();
+        fs::write(temp.join("sub/file"), b"12345").unwrap();
+        let app_id = format!("com.example.LeftoverTest{}", std::process::id());
+        let found = find_leftover_files(&app_id).unwrap();
+        let entry = found.iter().find(|e| e.path == temp).unwrap();
+        assert_eq!((entry.kind, entry.size_bytes), (LeftoverKind::Temp, 5));
+
+        fs::remove_dir_all(&temp).unwrap();
+    }
+}
@@ -3,8 +3,35 @@
 //! Shared modules for both CLI packer and runtime installer.
 //! This crate has NO GTK4 or async dependencies.
 
+pub mod appstream;
+#[cfg(feature = "build")]
+pub mod assembly;
+#[cfg(feature = "build")]
+pub mod builder;
 pub mod config;
+pub mod delta;
+pub mod desktop;
+pub mod error;
+pub mod history;
+#[cfg(feature = "build")]
+pub mod hooks;
+#[cfg(feature = "build")]
+pub mod icon;
+pub mod keystore;
+pub mod leftovers;
+pub mod manifest;
 pub mod metadata;
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
+pub mod progress;
 pub mod signing;
+#[cfg(feature = "build")]
+pub mod strip;
+pub mod systemd;
+pub mod transaction;
 pub mod paths;
 pub mod payload;
+pub mod payload_format;
+pub mod requirements;
+pub mod sandbox;
+pub mod window_hints;
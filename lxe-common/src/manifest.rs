@@ -0,0 +1,634 @@
+//! Manifest System for Tracking Installed Applications
+//!
+//! This module provides manifest file management for tracking what files
+//! LXE has installed, enabling clean uninstallation.
+//!
+//! The files of the app directory are also recorded with their SHA256, so
+//! `lxe verify-install` can tell when they were modified or damaged later.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::time::SystemTime;
+
+use crate::payload::{checksum_file, ChecksumAlgorithm};
+
+/// Manifest data for an installed application
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallManifest {
+    /// Application ID (e.g., "com.example.app")
+    pub app_id: String,
+    
+    /// Application Display Name (e.g., "Visual Studio Code")
+    #[serde(default)]
+    pub name: Option<String>,
+    
+    /// Version that was installed
+    pub version: String,
+    
+    /// Timestamp of installation (ISO 8601)
+    pub installed_at: String,
+    
+    /// Whether this is a system-wide installation
+    pub is_system: bool,
+    
+    /// List of all installed files and directories
+    pub files: Vec<String>,
+    
+    /// Whether the installed version was marked deprecated by its publisher
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    
+    /// Publisher's reason for the deprecation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<String>,
+    
+    /// Compressed payload size of the installed package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_size: Option<u64>,
+    
+    /// Whether the installed package carried a signature
+    #[serde(default)]
+    pub signed: bool,
+    
+    /// Where to look for updates (package index URL); falls back to the registry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    
+    /// User an administrator installed this app for (`--for-user`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_for_user: Option<String>,
+    
+    /// Payload checksum of the installed release (the base `lxe delta`
+    /// packages are checked against)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_checksum: Option<String>,
+    
+    /// Custom installation prefix (`--install-dir`) instead of `~/.local`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<PathBuf>,
+    
+    /// Main executable, relative to the app directory (metadata `exec`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec: Option<String>,
+    
+    /// Cached size of the app directory (see `lxe stats`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_cache: Option<SizeCache>,
+    
+    /// Every regular file of the app directory as installed, by absolute
+    /// path (empty for apps installed before files were recorded)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub file_records: HashMap<PathBuf, FileRecord>,
+    
+    /// Configuration files or directories of the package (`config_paths`),
+    /// by absolute path; modified ones are backed up instead of deleted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config_paths: Vec<PathBuf>,
+}
+
+/// An installed file's contents at install time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub sha256: String,
+    pub size: u64,
+    pub installed_at: SystemTime,
+}
+
+/// Result of [`InstallManifest::verify_files`]
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct IntegrityReport {
+    /// Number of recorded files checked
+    pub checked: usize,
+    pub missing: Vec<PathBuf>,
+    /// Files whose size or contents differ from the record
+    pub modified: Vec<PathBuf>,
+}
+
+impl IntegrityReport {
+    pub fn is_intact(&self) -> bool {
+        self.missing.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// A file or directory in the app directory the manifest doesn't know
+/// (see [`InstallManifest::orphaned_paths`])
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrphanedPath {
+    pub path: PathBuf,
+    /// Size of the file, or of all files in the directory
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+impl OrphanedPath {
+    pub fn remove(&self) -> std::io::Result<()> {
+        if self.is_dir {
+            fs::remove_dir_all(&self.path)
+        } else {
+            fs::remove_file(&self.path)
+        }
+    }
+}
+
+/// Installed size, valid while the app directory's mtime is unchanged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeCache {
+    pub bytes: u64,
+    
+    /// App directory mtime (Unix seconds) when `bytes` was computed
+    pub mtime: u64,
+}
+
+impl InstallManifest {
+    /// Create a new manifest for an app
+    pub fn new(app_id: String, name: Option<String>, version: String, is_system: bool) -> Self {
+        Self {
+            app_id,
+            name,
+            version,
+            installed_at: chrono_lite_now(),
+            is_system,
+            files: Vec::new(),
+            deprecated: false,
+            deprecation_message: None,
+            download_size: None,
+            signed: false,
+            source_url: None,
+            installed_for_user: None,
+            payload_checksum: None,
+            prefix: None,
+            exec: None,
+            size_cache: None,
+            file_records: HashMap::new(),
+            config_paths: Vec::new(),
+        }
+    }
+    
+    /// Add a file path to the manifest (once)
+    pub fn add_file(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().display().to_string();
+        if !self.files.contains(&path) {
+            self.files.push(path);
+        }
+    }
+    
+    /// Record the size and SHA256 of every regular file under `app_dir`
+    /// (symlinks are not followed)
+    pub fn record_files(&mut self, app_dir: &Path) -> Result<()> {
+        let installed_at = SystemTime::now();
+        for (path, metadata) in regular_files(app_dir)? {
+            let sha256 = checksum_file(&path, ChecksumAlgorithm::Sha256)?;
+            self.file_records.insert(path, FileRecord { sha256, size: metadata.len(), installed_at });
+        }
+        Ok(())
+    }
+    
+    /// Compare the recorded files with the disk; without `hash` only
+    /// existence and sizes are checked, which is much faster
+    pub fn verify_files(&self, hash: bool) -> IntegrityReport {
+        let mut report = IntegrityReport::default();
+        // Configuration files are expected to change
+        let records = self.file_records.iter()
+            .filter(|(path, _)| !self.config_paths.iter().any(|c| path.starts_with(c)));
+        for (path, record) in records {
+            report.checked += 1;
+            let Ok(metadata) = fs::symlink_metadata(path) else {
+                report.missing.push(path.clone());
+                continue;
+            };
+            let modified = !metadata.is_file()
+                || metadata.len() != record.size
+                || (hash && !checksum_file(path, ChecksumAlgorithm::Sha256)
+                    .is_ok_and(|sha256| sha256.eq_ignore_ascii_case(&record.sha256)));
+            if modified {
+                report.modified.push(path.clone());
+            }
+        }
+        report.missing.sort();
+        report.modified.sort();
+        report
+    }
+    
+    /// Files under `app_dir` that were not installed by the package, sorted
+    /// by path
+    /// 
+    /// A directory without any installed file in it is reported as a whole.
+    /// Symlinks are never recorded and so are not reported, neither is
+    /// anything under a configuration path (the app writes those).
+    pub fn orphaned_paths(&self, app_dir: &Path) -> Result<Vec<OrphanedPath>> {
+        let mut orphans = Vec::new();
+        self.collect_orphans(app_dir, &mut orphans)?;
+        orphans.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(orphans)
+    }
+    
+    /// Add the orphans in `dir` to `orphans`; returns whether everything in
+    /// `dir` is orphaned, with the total size
+    fn collect_orphans(&self, dir: &Path, orphans: &mut Vec<OrphanedPath>) -> Result<(bool, u64)> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read {}", dir.display()))?;
+        let mut found = Vec::new();
+        let (mut all_orphaned, mut size) = (true, 0);
+        for entry in entries {
+            let path = entry?.path();
+            let metadata = fs::symlink_metadata(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let is_known = self.file_records.contains_key(&path)
+                || self.files.iter().any(|f| Path::new(f) == path)
+                || self.config_paths.iter().any(|c| path.starts_with(c));
+            if is_known || metadata.file_type().is_symlink() {
+                all_orphaned = false;
+            } else if metadata.is_dir() {
+                let mut inner = Vec::new();
+                let (dir_orphaned, dir_size) = self.collect_orphans(&path, &mut inner)?;
+                if dir_orphaned && dir_size > 0 {
+                    found.push(OrphanedPath { path, size: dir_size, is_dir: true });
+                    size += dir_size;
+                } else {
+                    // Empty directories may come from the package
+                    all_orphaned &= dir_orphaned;
+                    size += inner.iter().map(|o| o.size).sum::<u64>();
+                    found.extend(inner);
+                }
+            } else {
+                size += metadata.len();
+                found.push(OrphanedPath { path, size: metadata.len(), is_dir: false });
+            }
+        }
+        orphans.extend(found);
+        Ok((all_orphaned, size))
+    }
+    
+    /// Set the configuration paths of a package installed to `app_dir`
+    /// (invalid ones are ignored)
+    pub fn set_config_paths(&mut self, app_dir: &Path, config_paths: &[String]) {
+        self.config_paths = config_paths.iter()
+            .filter(|p| crate::metadata::is_valid_config_path(p))
+            .map(|p| app_dir.join(p))
+            .collect();
+    }
+    
+    /// Copy the configuration files in `app_dir` that differ from their
+    /// record (or have none, like files the app created) to `backup_dir`
+    /// (usually [`Self::backup_dir`]), before the app directory is removed
+    /// 
+    /// Returns the backed up files.
+    pub fn back_up_configs(&self, app_dir: &Path, backup_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut backed_up = Vec::new();
+        for config in &self.config_paths {
+            let Ok(relative) = config.strip_prefix(app_dir) else {
+                continue;
+            };
+            if fs::symlink_metadata(config).is_err() {
+                continue;
+            }
+            for (path, _) in regular_files(config)? {
+                let path = path.as_path();
+                let unchanged = self.file_records.get(path).is_some_and(|record| {
+                    checksum_file(path, ChecksumAlgorithm::Sha256)
+                        .is_ok_and(|sha256| sha256.eq_ignore_ascii_case(&record.sha256))
+                });
+                if unchanged {
+                    continue;
+                }
+                let inner = path.strip_prefix(config).unwrap_or(Path::new(""));
+                let target = backup_dir.join(relative).join(inner);
+                if let Some(parent) = target.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                fs::copy(path, &target)
+                    .with_context(|| format!("Failed to back up {}", path.display()))?;
+                backed_up.push(target);
+            }
+        }
+        Ok(backed_up)
+    }
+    
+    /// Copy the configuration files backed up to `backup_dir` into a newly
+    /// installed `app_dir`, for the paths still listed in `config_paths`
+    /// 
+    /// Restored files are removed from the backup; the others stay there.
+    /// Returns the restored files.
+    pub fn restore_configs(backup_dir: &Path, app_dir: &Path, config_paths: &[String]) -> Result<Vec<PathBuf>> {
+        if !backup_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let configs: Vec<&Path> = config_paths.iter()
+            .filter(|p| crate::metadata::is_valid_config_path(p))
+            .map(Path::new)
+            .collect();
+        let mut restored = Vec::new();
+        for (backup, _) in regular_files(backup_dir)? {
+            let Ok(relative) = backup.strip_prefix(backup_dir) else {
+                continue;
+            };
+            if !configs.iter().any(|c| relative.starts_with(c)) {
+                continue;
+            }
+            let target = app_dir.join(relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::copy(&backup, &target)
+                .with_context(|| format!("Failed to restore {}", target.display()))?;
+            fs::remove_file(&backup).ok();
+            restored.push(target);
+        }
+        remove_empty_dirs(backup_dir);
+        Ok(restored)
+    }
+    
+    /// Recorded .desktop files in `applications_dir` (the app's own entry
+    /// and those of its extra executables)
+    pub fn desktop_entries(&self, applications_dir: &Path) -> Vec<PathBuf> {
+        self.files.iter()
+            .map(PathBuf::from)
+            .filter(|f| f.parent() == Some(applications_dir))
+            .filter(|f| f.extension().is_some_and(|ext| ext == "desktop"))
+            .collect()
+    }
+    
+    /// Get the manifest directory for user installs
+    pub fn manifests_dir() -> PathBuf {
+        Self::manifests_dir_in(&dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("~/.local/share")))
+    }
+    
+    /// Manifest directory under a data directory (e.g., another user's ~/.local/share)
+    pub fn manifests_dir_in(data_dir: &Path) -> PathBuf {
+        data_dir.join("lxe").join("manifests")
+    }
+    
+    /// Where the modified configuration files of an uninstalled or upgraded
+    /// app are kept (~/.local/share/lxe/backups/<app_id>)
+    pub fn backup_dir(app_id: &str) -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("~/.local/share"))
+            .join("lxe").join("backups").join(app_id)
+    }
+    
+    /// Get the manifest file path for an app
+    pub fn manifest_path(app_id: &str) -> PathBuf {
+        Self::manifests_dir().join(format!("{}.json", app_id))
+    }
+    
+    /// Save the manifest to disk
+    pub fn save(&self) -> Result<PathBuf> {
+        let dir = Self::manifests_dir();
+        fs::create_dir_all(&dir)
+            .context("Failed to create manifests directory")?;
+        
+        let path = Self::manifest_path(&self.app_id);
+        let json = serde_json::to_string_pretty(self)
+            .context("Failed to serialize manifest")?;
+        
+        fs::write(&path, json)
+            .context("Failed to write manifest file")?;
+        
+        tracing::info!("Saved manifest to {:?}", path);
+        Ok(path)
+    }
+    
+    /// Load a manifest from disk
+    pub fn load(app_id: &str) -> Result<Option<Self>> {
+        let path = Self::manifest_path(app_id);
+        
+        if !path.exists() {
+            return Ok(None);
+        }
+        
+        let json = fs::read_to_string(&path)
+            .context("Failed to read manifest file")?;
+        
+        let manifest: Self = serde_json::from_str(&json)
+            .context("Failed to parse manifest JSON")?;
+        
+        Ok(Some(manifest))
+    }
+    
+    /// Delete the manifest file
+    pub fn delete(app_id: &str) -> Result<()> {
+        let path = Self::manifest_path(app_id);
+        
+        if path.exists() {
+            fs::remove_file(&path)
+                .context("Failed to delete manifest file")?;
+            tracing::info!("Deleted manifest: {:?}", path);
+        }
+        
+        Ok(())
+    }
+    
+    /// Load the manifests of all installed apps, sorted by app ID
+    /// 
+    /// Unreadable manifests are skipped.
+    pub fn load_all() -> Result<Vec<Self>> {
+        Self::load_all_in(&Self::manifests_dir())
+    }
+    
+    /// Load all manifests in `dir`, sorted by app ID
+    pub fn load_all_in(dir: &Path) -> Result<Vec<Self>> {
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut manifests: Vec<Self> = fs::read_dir(dir)?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .filter_map(|json| serde_json::from_str(&json).ok())
+            .collect();
+        manifests.sort_by(|a, b| a.app_id.cmp(&b.app_id));
+        
+        Ok(manifests)
+    }
+    
+    /// List all installed app IDs
+    pub fn list_installed() -> Result<Vec<String>> {
+        let dir = Self::manifests_dir();
+        
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        
+        let mut apps = Vec::new();
+        let entries = fs::read_dir(&dir)?;
+        
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension().map(|e| e == "json").unwrap_or(false) {
+                if let Some(stem) = path.file_stem() {
+                    apps.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+        
+        Ok(apps)
+    }
+}
+
+/// Regular files under `root` (or `root` itself), without following symlinks
+fn regular_files(root: &Path) -> Result<Vec<(PathBuf, fs::Metadata)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(path) = pending.pop() {
+        let metadata = fs::symlink_metadata(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        if metadata.is_dir() {
+            let entries = fs::read_dir(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            for entry in entries {
+                pending.push(entry?.path());
+            }
+        } else if metadata.is_file() {
+            files.push((path, metadata));
+        }
+    }
+    Ok(files)
+}
+
+/// Remove `dir` and its subdirectories if they contain no files
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    fs::remove_dir(dir).ok();
+}
+
+/// Simple ISO 8601-like timestamp without external crate
+fn chrono_lite_now() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    
+    let duration = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    
+    // Simple Unix timestamp - good enough for our purposes
+    format!("unix:{}", duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_manifest_creation() {
+        let mut manifest = InstallManifest::new(
+            "com.example.app".to_string(),
+            Some("Example App".to_string()),
+            "1.0.0".to_string(),
+            false,
+        );
+        
+        manifest.add_file("/home/user/.local/share/com.example.app/bin/app");
+        manifest.add_file("/home/user/.local/share/applications/com.example.app.desktop");
+        manifest.add_file("/home/user/.local/share/com.example.app/bin/app");
+        
+        assert_eq!(manifest.files.len(), 2);
+        assert!(manifest.installed_at.starts_with("unix:"));
+    }
+    
+    #[test]
+    fn test_verify_files() {
+        let dir = std::env::temp_dir().join(format!("lxe-manifest-verify-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("app"), b"#!/bin/sh\n").unwrap();
+        fs::write(dir.join("lib/data"), b"data").unwrap();
+        std::os::unix::fs::symlink("app", dir.join("link")).unwrap();
+        
+        let mut manifest = InstallManifest::new("com.example.app".into(), None, "1.0.0".into(), false);
+        manifest.record_files(&dir).unwrap();
+        assert_eq!(manifest.file_records.len(), 2);
+        assert!(manifest.verify_files(true).is_intact());
+        
+        // Same size, different contents: only hashing notices
+        fs::write(dir.join("lib/data"), b"DATA").unwrap();
+        fs::remove_file(dir.join("app")).unwrap();
+        assert_eq!(manifest.verify_files(false).modified, Vec::<PathBuf>::new());
+        let report = manifest.verify_files(true);
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.missing, [dir.join("app")]);
+        assert_eq!(report.modified, [dir.join("lib/data")]);
+        
+        // Records survive a save/load round trip
+        let json = serde_json::to_string(&manifest).unwrap();
+        let loaded: InstallManifest = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.file_records, manifest.file_records);
+        
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_orphaned_paths() {
+        let dir = std::env::temp_dir().join(format!("lxe-manifest-orphans-{}", std::process::id()));
+        fs::create_dir_all(dir.join("lib/empty")).unwrap();
+        fs::create_dir_all(dir.join("etc")).unwrap();
+        fs::write(dir.join("app"), b"binary").unwrap();
+        fs::write(dir.join("lib/data"), b"data").unwrap();
+        std::os::unix::fs::symlink("app", dir.join("link")).unwrap();
+        
+        let mut manifest = InstallManifest::new("com.example.app".into(), None, "1.0.0".into(), false);
+        manifest.record_files(&dir).unwrap();
+        manifest.set_config_paths(&dir, &["etc".into()]);
+        assert_eq!(manifest.orphaned_paths(&dir).unwrap(), []);
+        
+        fs::write(dir.join("lib/stale.so"), b"old library").unwrap();
+        fs::create_dir_all(dir.join("plugins/extra")).unwrap();
+        fs::write(dir.join("plugins/a"), b"12").unwrap();
+        fs::write(dir.join("plugins/extra/b"), b"345").unwrap();
+        fs::write(dir.join("etc/settings.ini"), b"written by the app").unwrap();
+        let orphans = manifest.orphaned_paths(&dir).unwrap();
+        assert_eq!(orphans, [
+            OrphanedPath { path: dir.join("lib/stale.so"), size: 11, is_dir: false },
+            OrphanedPath { path: dir.join("plugins"), size: 5, is_dir: true },
+        ]);
+        
+        orphans[1].remove().unwrap();
+        assert!(!dir.join("plugins").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_config_backup() {
+        let root = std::env::temp_dir().join(format!("lxe-manifest-configs-{}", std::process::id()));
+        let (app_dir, backup_dir) = (root.join("app"), root.join("backup"));
+        fs::create_dir_all(app_dir.join("etc")).unwrap();
+        fs::write(app_dir.join("app"), b"binary").unwrap();
+        fs::write(app_dir.join("etc/settings.ini"), b"theme=light").unwrap();
+        fs::write(app_dir.join("etc/unchanged.ini"), b"a=b").unwrap();
+        
+        let mut manifest = InstallManifest::new("com.example.app".into(), None, "1.0.0".into(), false);
+        manifest.record_files(&app_dir).unwrap();
+        manifest.set_config_paths(&app_dir, &["etc".into(), "../escape".into()]);
+        assert_eq!(manifest.config_paths, [app_dir.join("etc")]);
+        
+        // Edited and created files are kept, files as installed are not
+        fs::write(app_dir.join("etc/settings.ini"), b"theme=dark").unwrap();
+        fs::write(app_dir.join("etc/recent"), b"file.txt").unwrap();
+        fs::write(app_dir.join("app"), b"patched").unwrap();
+        assert_eq!(manifest.verify_files(true).modified, [app_dir.join("app")]);
+        let mut backed_up = manifest.back_up_configs(&app_dir, &backup_dir).unwrap();
+        backed_up.sort();
+        assert_eq!(backed_up, [backup_dir.join("etc/recent"), backup_dir.join("etc/settings.ini")]);
+        
+        fs::remove_dir_all(&app_dir).unwrap();
+        fs::create_dir_all(app_dir.join("etc")).unwrap();
+        fs::write(app_dir.join("etc/settings.ini"), b"theme=light").unwrap();
+        let restored = InstallManifest::restore_configs(&backup_dir, &app_dir, &["etc".into()]).unwrap();
+        assert_eq!(restored.len(), 2);
+        assert_eq!(fs::read(app_dir.join("etc/settings.ini")).unwrap(), b"theme=dark");
+        assert!(!backup_dir.exists());
+        
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -3,6 +3,10 @@
 //! Defines the structure of LXE package metadata embedded in the binary.
 
 use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::payload::{ChecksumAlgorithm, CompressorKind};
+use crate::signing::SigningAlgorithm;
 
 /// Magic bytes identifying an LXE payload
 /// 
@@ -46,17 +50,39 @@ pub struct LxeMetadata {
     #[serde(default)]
     pub categories: Vec<String>,
 
+    /// MIME types the app can open (`MimeType=` in the .desktop file)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mime_types: Vec<String>,
+
     /// Short description
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
 
-    /// SHA256 checksum of the compressed payload
+    /// Checksum of the compressed payload (hex, see `checksum_algorithm`)
     pub payload_checksum: String,
 
+    /// Algorithm of `payload_checksum` (omitted for SHA256, the original format)
+    #[serde(default, skip_serializing_if = "ChecksumAlgorithm::is_default")]
+    pub checksum_algorithm: ChecksumAlgorithm,
+
+    /// Compression of the payload: "zstd" (omitted, the original format) or
+    /// "lz4". Kept as a string so packages with a compressor this version
+    /// doesn't know still parse and fail only when extracted.
+    #[serde(default = "default_compressor", skip_serializing_if = "is_default_compressor")]
+    pub compressor: String,
+
     /// Optional: Minimum required LXE runtime version
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_runtime_version: Option<String>,
 
+    /// Optional: Minimum glibc version required on the target system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_glibc_version: Option<String>,
+
+    /// Optional: Minimum Linux kernel version required on the target system
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_kernel_version: Option<String>,
+
     /// Optional: License identifier (SPDX)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub license: Option<String>,
@@ -76,23 +102,87 @@ pub struct LxeMetadata {
     /// Optional: StartupWMClass for GNOME dock (default: derived from app ID)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub wm_class: Option<String>,
+    
+    /// Optional: App is D-Bus activatable (adds DBusActivatable=true)
+    #[serde(default)]
+    pub dbus_activatable: bool,
 
     /// Optional: Custom installation hooks
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hooks: Option<InstallHooks>,
     
+    /// Optional: Extra desktop actions (right-click menu entries)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub desktop_actions: Vec<DesktopAction>,
+    
+    /// Optional: More programs to link into bin (and the launcher menu)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_executables: Vec<ExtraExecutable>,
+    
     /// Optional: Installer UI customization
     #[serde(default)]
     pub installer: InstallerMetadata,
     
+    /// Optional: Publisher has marked this version as deprecated
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    
+    /// Optional: Why the version is deprecated (shown to the user)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<String>,
+    
+    /// Optional: What the app needs besides its own files (warned about
+    /// before installing, see `requirements::check_dependencies`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<LxeDependency>,
+    
+    /// Optional: Screenshot URLs for software centers (see `appstream`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub screenshots: Vec<String>,
+    
+    /// Optional: Release history for software centers, newest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub releases: Vec<ReleaseNote>,
+    
+    /// Optional: Configuration files (or directories) relative to the app
+    /// directory; modified ones are kept across uninstalls and upgrades
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config_paths: Vec<String>,
+    
+    /// Optional: Background service run by the user's systemd instance
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service: Option<ServiceMetadata>,
+    
+    // ========== Delta Package Fields (see `delta`) ==========
+    
+    /// Payload checksum of the release this delta applies to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_base_checksum: Option<String>,
+    
+    /// Payload checksum of the full release the delta upgrades to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_target_checksum: Option<String>,
+    
+    /// `delta::tree_checksum` of the full release, checked after patching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_target_tree: Option<String>,
+    
+    /// Delta payload format (`delta::DELTA_FORMAT_VERSION`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delta_format_version: Option<u8>,
+    
     // ========== Digital Signature Fields ==========
     
-    /// Ed25519 public key (base64-encoded, 32 bytes)
+    /// Signature algorithm (omitted for Ed25519, the original format)
+    #[serde(default, skip_serializing_if = "SigningAlgorithm::is_default")]
+    pub algorithm: SigningAlgorithm,
+    
+    /// Public key (base64-encoded; 32 bytes for Ed25519, SPKI DER for RSA)
     /// Used to verify the package signature
     #[serde(skip_serializing_if = "Option::is_none")]
     pub public_key: Option<String>,
     
-    /// Ed25519 signature (base64-encoded, 64 bytes)
+    /// Signature (base64-encoded; 64 bytes for Ed25519)
     /// Signs: signable_metadata_json + payload_checksum_bytes
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
@@ -118,6 +208,273 @@ pub struct InstallHooks {
     pub post_uninstall: Option<String>,
 }
 
+/// A custom `[Desktop Action]` entry (e.g., "New Window")
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DesktopAction {
+    /// Action identifier (alphanumeric and hyphens only)
+    pub id: String,
+    
+    /// Label shown in the launcher menu
+    pub name: String,
+    
+    /// Arguments appended to the main executable
+    #[serde(default)]
+    pub exec_args: String,
+}
+
+/// Characters that would let `exec_args` escape into a shell
+const SHELL_METACHARACTERS: &[char] = &[
+    ';', '&', '|', '$', '`', '<', '>', '(', ')', '{', '}', '\\', '"', '\'', '*', '?', '!', '\n', '\r',
+];
+
+impl DesktopAction {
+    /// Check that the action is safe to write into a .desktop file
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.id.is_empty() || !self.id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            anyhow::bail!(
+                "Invalid desktop action id '{}': only letters, digits and '-' are allowed",
+                self.id
+            );
+        }
+        
+        if self.id == "Uninstall" {
+            anyhow::bail!("Desktop action id 'Uninstall' is reserved by LXE");
+        }
+        
+        if self.name.trim().is_empty() || self.name.contains('\n') {
+            anyhow::bail!("Desktop action '{}' needs a single-line name", self.id);
+        }
+        
+        if let Some(c) = self.exec_args.chars().find(|c| SHELL_METACHARACTERS.contains(c)) {
+            anyhow::bail!(
+                "Desktop action '{}' has a shell metacharacter '{}' in exec_args",
+                self.id,
+                c.escape_default()
+            );
+        }
+        
+        Ok(())
+    }
+}
+
+/// Kind of a runtime dependency
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DepKind {
+    /// A command in `PATH`, e.g. `xdg-open`
+    System,
+    
+    /// Another LXE app, by app ID
+    LxePackage,
+    
+    /// A shared library known to `ldconfig`, e.g. `libGL.so.1`
+    SharedLib,
+}
+
+/// A runtime dependency ([[package.dependencies]])
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LxeDependency {
+    pub kind: DepKind,
+    
+    /// Command name, app ID or library name
+    pub id: String,
+    
+    /// Semver range the installed version must match (LXE packages only),
+    /// e.g. ">=2.0"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_req: Option<String>,
+}
+
+impl LxeDependency {
+    /// Check that the version range parses
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.id.trim().is_empty() {
+            anyhow::bail!("Dependency needs an id");
+        }
+        if let Some(ref req) = self.version_req {
+            semver::VersionReq::parse(req)
+                .map_err(|e| anyhow::anyhow!("Invalid version_req '{}' of dependency '{}': {}", req, self.id, e))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for LxeDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.version_req {
+            Some(ref req) => write!(f, "{} {}", self.id, req),
+            None => write!(f, "{}", self.id),
+        }
+    }
+}
+
+/// Whether `path` is usable in `config_paths`: relative, and without `..`
+/// so it can't leave the app directory
+pub fn is_valid_config_path(path: &str) -> bool {
+    let path = Path::new(path);
+    path.components().next().is_some()
+        && path.components().all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+/// A release in the AppStream metadata ([[package.releases]])
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReleaseNote {
+    pub version: String,
+    
+    /// Release date, `YYYY-MM-DD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    
+    /// What changed, as plain text (one paragraph per blank-line block)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Another program in the package, e.g. `myapp-cli` next to `myapp`
+/// ([[package.extra_executables]])
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ExtraExecutable {
+    /// Path relative to the install directory
+    pub exec: String,
+    
+    /// Launcher name
+    pub name: String,
+    
+    #[serde(default)]
+    pub terminal: bool,
+    
+    /// Also add a launcher entry (the bin symlink is always created)
+    #[serde(default)]
+    pub desktop_entry: bool,
+}
+
+impl ExtraExecutable {
+    /// Check that the entry is safe to link and write into a .desktop file
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let path = std::path::Path::new(&self.exec);
+        let escapes = path.components().any(|c| !matches!(c, std::path::Component::Normal(_)));
+        if self.exec.is_empty() || escapes {
+            anyhow::bail!(
+                "Invalid extra executable '{}': must be a path inside the package",
+                self.exec
+            );
+        }
+        
+        if let Some(c) = self.exec.chars().find(|c| c.is_whitespace() || SHELL_METACHARACTERS.contains(c)) {
+            anyhow::bail!(
+                "Extra executable '{}' has an unsupported character '{}'",
+                self.exec,
+                c.escape_default()
+            );
+        }
+        
+        if self.name.trim().is_empty() || self.name.contains('\n') {
+            anyhow::bail!("Extra executable '{}' needs a single-line name", self.exec);
+        }
+        
+        Ok(())
+    }
+    
+    /// Command name, i.e. the name of its bin symlink
+    pub fn command(&self) -> String {
+        std::path::Path::new(&self.exec)
+            .file_name()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.exec.clone())
+    }
+    
+    /// File name of its launcher entry, next to the app's own
+    pub fn desktop_filename(&self, app_id: &str) -> String {
+        format!("{}.{}.desktop", app_id, self.command())
+    }
+}
+
+/// A background service shipped with the app, e.g. a sync daemon
+/// (`[service]`); installed as a systemd user unit (see `systemd`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ServiceMetadata {
+    /// Program relative to the install directory, optionally followed by
+    /// arguments
+    pub exec: String,
+    
+    /// One-line `Description=` of the unit
+    pub description: String,
+    
+    #[serde(default)]
+    pub restart: ServiceRestart,
+    
+    /// Units to start after (`After=`), e.g. `network-online.target`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after: Vec<String>,
+    
+    /// Socket unit the service wants (`Wants=`), e.g. `myapp.socket`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wants_socket: Option<String>,
+}
+
+/// When systemd restarts the service (`Restart=`)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServiceRestart {
+    #[serde(alias = "Always")]
+    Always,
+    #[default]
+    #[serde(alias = "OnFailure")]
+    OnFailure,
+    #[serde(alias = "No")]
+    No,
+}
+
+impl ServiceRestart {
+    /// Value of `Restart=`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Always => "always",
+            Self::OnFailure => "on-failure",
+            Self::No => "no",
+        }
+    }
+}
+
+impl ServiceMetadata {
+    /// Check that the service is safe to write into a unit file
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let program = self.program();
+        let path = std::path::Path::new(program);
+        if program.is_empty() || path.components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
+            anyhow::bail!("Invalid service exec '{}': must start with a path inside the package", self.exec);
+        }
+        if self.exec.contains(['\n', '\r']) {
+            anyhow::bail!("Service exec '{}' must be a single line", self.exec.escape_default());
+        }
+        if self.description.trim().is_empty() || self.description.contains(['\n', '\r']) {
+            anyhow::bail!("The service needs a single-line description");
+        }
+        
+        let is_unit_name = |unit: &str| !unit.is_empty()
+            && unit.chars().all(|c| c.is_ascii_alphanumeric() || ":-_.@\\".contains(c));
+        if let Some(bad) = self.after.iter().find(|unit| !is_unit_name(unit)) {
+            anyhow::bail!("Invalid unit name '{}' in [service] after", bad);
+        }
+        if let Some(ref socket) = self.wants_socket {
+            if !is_unit_name(socket) || !socket.ends_with(".socket") {
+                anyhow::bail!("Invalid socket unit '{}' in [service] wants_socket (expected name.socket)", socket);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Program part of `exec`
+    pub fn program(&self) -> &str {
+        self.exec.split_whitespace().next().unwrap_or_default()
+    }
+    
+    /// Arguments part of `exec`
+    pub fn args(&self) -> &str {
+        self.exec.trim_start()[self.program().len()..].trim()
+    }
+}
+
 /// Installer UI customization embedded in the package
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct InstallerMetadata {
@@ -171,6 +528,14 @@ fn default_show_launch() -> bool {
     true
 }
 
+fn default_compressor() -> String {
+    CompressorKind::Zstd.as_str().to_string()
+}
+
+fn is_default_compressor(compressor: &str) -> bool {
+    compressor == CompressorKind::default().as_str()
+}
+
 impl LxeMetadata {
     /// Create a new metadata instance with required fields
     pub fn new(
@@ -191,25 +556,92 @@ impl LxeMetadata {
             exec: exec.into(),
             icon: None,
             categories: vec!["Application".to_string()],
+            mime_types: Vec::new(),
             description: None,
             payload_checksum: payload_checksum.into(),
+            checksum_algorithm: ChecksumAlgorithm::default(),
+            compressor: default_compressor(),
             min_runtime_version: None,
+            min_glibc_version: None,
+            min_kernel_version: None,
             license: None,
             homepage: None,
             exec_args: None,
             terminal: false,
             wm_class: None,
+            dbus_activatable: false,
             hooks: None,
+            desktop_actions: Vec::new(),
+            extra_executables: Vec::new(),
+            deprecated: false,
+            deprecation_message: None,
+            dependencies: Vec::new(),
+            screenshots: Vec::new(),
+            releases: Vec::new(),
+            config_paths: Vec::new(),
+            service: None,
+            delta_base_checksum: None,
+            delta_target_checksum: None,
+            delta_target_tree: None,
+            delta_format_version: None,
             installer: InstallerMetadata::default(),
+            algorithm: SigningAlgorithm::default(),
             public_key: None,
             signature: None,
         }
     }
 
+    /// Start building metadata from the required fields
+    /// 
+    /// ```
+    /// # use lxe_common::metadata::LxeMetadata;
+    /// let metadata = LxeMetadata::builder("com.example.App", "App", "1.0.0", "bin/app", 0, "")
+    ///     .description("An example")
+    ///     .categories(["Utility"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(metadata.categories, ["Utility"]);
+    /// ```
+    pub fn builder(
+        app_id: impl Into<String>,
+        name: impl Into<String>,
+        version: impl Into<String>,
+        exec: impl Into<String>,
+        install_size: u64,
+        payload_checksum: impl Into<String>,
+    ) -> LxeMetadataBuilder {
+        LxeMetadataBuilder {
+            app_id: app_id.into(),
+            name: name.into(),
+            version: version.into(),
+            exec: exec.into(),
+            install_size,
+            payload_checksum: payload_checksum.into(),
+            ..Default::default()
+        }
+    }
+
     /// Get the .desktop file name
     pub fn desktop_filename(&self) -> String {
         format!("{}.desktop", self.app_id)
     }
+    
+    /// Metadata describing `extra` as if it were the main executable
+    /// 
+    /// Keeps the app ID and icon, so the launcher entry and bin symlink
+    /// point into this app's directory.
+    pub fn for_extra_executable(&self, extra: &ExtraExecutable) -> LxeMetadata {
+        LxeMetadata {
+            name: extra.name.clone(),
+            exec: extra.exec.clone(),
+            exec_args: None,
+            terminal: extra.terminal,
+            dbus_activatable: false,
+            desktop_actions: Vec::new(),
+            extra_executables: Vec::new(),
+            ..self.clone()
+        }
+    }
 
     /// Get XDG categories as semicolon-separated string
     pub fn categories_string(&self) -> String {
@@ -220,6 +652,12 @@ impl LxeMetadata {
         cats
     }
     
+    /// Whether this is a delta package (`lxe delta`), applied on top of
+    /// an installed release
+    pub fn is_delta(&self) -> bool {
+        self.delta_base_checksum.is_some()
+    }
+    
     /// Check if this package is signed
     pub fn is_signed(&self) -> bool {
         self.public_key.is_some() && self.signature.is_some()
@@ -241,14 +679,32 @@ impl LxeMetadata {
             exec: &self.exec,
             icon: self.icon.as_deref(),
             categories: &self.categories,
+            mime_types: &self.mime_types,
             description: self.description.as_deref(),
             payload_checksum: &self.payload_checksum,
+            checksum_algorithm: self.checksum_algorithm,
             min_runtime_version: self.min_runtime_version.as_deref(),
             license: self.license.as_deref(),
             homepage: self.homepage.as_deref(),
             exec_args: self.exec_args.as_deref(),
             terminal: self.terminal,
+            desktop_actions: &self.desktop_actions,
+            extra_executables: &self.extra_executables,
+            service: self.service.as_ref(),
+            min_glibc_version: self.min_glibc_version.as_deref(),
+            min_kernel_version: self.min_kernel_version.as_deref(),
+            delta_base_checksum: self.delta_base_checksum.as_deref(),
+            delta_target_checksum: self.delta_target_checksum.as_deref(),
+            delta_target_tree: self.delta_target_tree.as_deref(),
+            delta_format_version: self.delta_format_version,
+            deprecated: self.deprecated,
+            deprecation_message: self.deprecation_message.as_deref(),
             // NOTE: hooks excluded from signing for simplicity
+            // NOTE: dependencies excluded - they only cause warnings
+            // NOTE: screenshots/releases excluded - only shown in software centers
+            // NOTE: algorithm excluded - a mismatched key fails verification anyway
+            // NOTE: compressor excluded - the signed checksum covers the payload bytes
+            // NOTE: config_paths excluded - only paths inside the app directory are kept
         };
         
         let json = serde_json::to_vec(&signable)?;
@@ -256,6 +712,133 @@ impl LxeMetadata {
     }
 }
 
+/// Builder for [`LxeMetadata`] (see [`LxeMetadata::builder`])
+/// 
+/// Options left unset keep the defaults of [`LxeMetadata::new`].
+#[derive(Debug, Clone, Default)]
+pub struct LxeMetadataBuilder {
+    app_id: String,
+    name: String,
+    version: String,
+    exec: String,
+    install_size: u64,
+    payload_checksum: String,
+    icon: Option<String>,
+    description: Option<String>,
+    categories: Option<Vec<String>>,
+    terminal: Option<bool>,
+    wm_class: Option<String>,
+    license: Option<String>,
+    homepage: Option<String>,
+    exec_args: Option<String>,
+    min_runtime_version: Option<String>,
+    installer: Option<InstallerMetadata>,
+    hooks: Option<InstallHooks>,
+}
+
+impl LxeMetadataBuilder {
+    pub fn icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+    
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+    
+    pub fn categories<I>(mut self, categories: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.categories = Some(categories.into_iter().map(Into::into).collect());
+        self
+    }
+    
+    pub fn terminal(mut self, terminal: impl Into<bool>) -> Self {
+        self.terminal = Some(terminal.into());
+        self
+    }
+    
+    pub fn wm_class(mut self, wm_class: impl Into<String>) -> Self {
+        self.wm_class = Some(wm_class.into());
+        self
+    }
+    
+    pub fn license(mut self, license: impl Into<String>) -> Self {
+        self.license = Some(license.into());
+        self
+    }
+    
+    pub fn homepage(mut self, homepage: impl Into<String>) -> Self {
+        self.homepage = Some(homepage.into());
+        self
+    }
+    
+    pub fn exec_args(mut self, exec_args: impl Into<String>) -> Self {
+        self.exec_args = Some(exec_args.into());
+        self
+    }
+    
+    pub fn min_runtime_version(mut self, version: impl Into<String>) -> Self {
+        self.min_runtime_version = Some(version.into());
+        self
+    }
+    
+    pub fn installer(mut self, installer: impl Into<InstallerMetadata>) -> Self {
+        self.installer = Some(installer.into());
+        self
+    }
+    
+    pub fn hooks(mut self, hooks: impl Into<InstallHooks>) -> Self {
+        self.hooks = Some(hooks.into());
+        self
+    }
+    
+    /// Check the required fields and create the metadata
+    pub fn build(self) -> anyhow::Result<LxeMetadata> {
+        for (field, value) in [
+            ("app_id", &self.app_id),
+            ("name", &self.name),
+            ("version", &self.version),
+            ("exec", &self.exec),
+        ] {
+            if value.trim().is_empty() {
+                anyhow::bail!("Package metadata is missing '{}'", field);
+            }
+        }
+        if let Some(c) = self.exec_args.as_deref()
+            .and_then(|args| args.chars().find(|c| SHELL_METACHARACTERS.contains(c)))
+        {
+            anyhow::bail!("exec_args contains a shell metacharacter '{}'", c.escape_default());
+        }
+        
+        let mut metadata = LxeMetadata::new(
+            self.app_id,
+            self.name,
+            self.version,
+            self.exec,
+            self.install_size,
+            self.payload_checksum,
+        );
+        metadata.icon = self.icon;
+        metadata.description = self.description;
+        if let Some(categories) = self.categories {
+            metadata.categories = categories;
+        }
+        metadata.terminal = self.terminal.unwrap_or(false);
+        metadata.wm_class = self.wm_class;
+        metadata.license = self.license;
+        metadata.homepage = self.homepage;
+        metadata.exec_args = self.exec_args;
+        metadata.min_runtime_version = self.min_runtime_version;
+        metadata.installer = self.installer.unwrap_or_default();
+        metadata.hooks = self.hooks;
+        Ok(metadata)
+    }
+}
+
 /// A version of metadata without signature fields, used for signing
 /// 
 /// This struct is PUBLIC so that both the packer (lxe-pack) and runtime (lxe-runtime)
@@ -273,9 +856,15 @@ pub struct SignableMetadata<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<&'a str>,
     pub categories: &'a [String],
+    /// Signed: they make the app open the user's files
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub mime_types: &'a [String],
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<&'a str>,
     pub payload_checksum: &'a str,
+    /// Omitted for SHA256 so signatures from before the field still verify
+    #[serde(skip_serializing_if = "ChecksumAlgorithm::is_default")]
+    pub checksum_algorithm: ChecksumAlgorithm,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub min_runtime_version: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -285,6 +874,33 @@ pub struct SignableMetadata<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exec_args: Option<&'a str>,
     pub terminal: bool,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub desktop_actions: &'a [DesktopAction],
+    /// Signed: they become commands in the user's PATH
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    pub extra_executables: &'a [ExtraExecutable],
+    /// Signed: it runs in the background whenever the user logs in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service: Option<&'a ServiceMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_glibc_version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_kernel_version: Option<&'a str>,
+    /// Signed: a delta applied to the wrong base breaks the installation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_base_checksum: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_target_checksum: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_target_tree: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delta_format_version: Option<u8>,
+    /// Signed: the message is shown to the user; deprecating a released
+    /// package means re-signing it
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub deprecated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deprecation_message: Option<&'a str>,
 }
 
 #[cfg(test)]
@@ -307,6 +923,31 @@ mod tests {
 
         assert_eq!(parsed.app_id, "com.example.Test");
         assert_eq!(parsed.name, "Test App");
+
+        // Not deprecated: signatures from before the fields still verify
+        let mut meta = parsed;
+        let signable = meta.to_signable_json().unwrap();
+        assert!(!String::from_utf8(signable.clone()).unwrap().contains("deprecat"));
+        meta.deprecated = true;
+        meta.deprecation_message = Some("Data loss on save".to_string());
+        assert_ne!(meta.to_signable_json().unwrap(), signable);
+    }
+
+    #[test]
+    fn test_builder() {
+        let meta = LxeMetadata::builder("com.example.App", "App", "1.0.0", "bin/app", 0, "")
+            .terminal(true)
+            .exec_args("--verbose")
+            .build()
+            .unwrap();
+        assert!(meta.terminal);
+        assert_eq!(meta.categories, ["Application"]);
+        
+        assert!(LxeMetadata::builder("com.example.App", "", "1.0.0", "app", 0, "").build().is_err());
+        assert!(LxeMetadata::builder("com.example.App", "App", "1.0.0", "app", 0, "")
+            .exec_args("$(reboot)")
+            .build()
+            .is_err());
     }
 
     #[test]
@@ -321,4 +962,21 @@ mod tests {
         );
         assert_eq!(meta.desktop_filename(), "com.discord.Discord.desktop");
     }
+
+    #[test]
+    fn test_desktop_action_validation() {
+        let mut action = DesktopAction {
+            id: "new-window".to_string(),
+            name: "New Window".to_string(),
+            exec_args: "--new-window %U".to_string(),
+        };
+        assert!(action.validate().is_ok());
+        
+        action.exec_args = "--foo; rm -rf ~".to_string();
+        assert!(action.validate().is_err());
+        
+        action.exec_args = String::new();
+        action.id = "new window".to_string();
+        assert!(action.validate().is_err());
+    }
 }
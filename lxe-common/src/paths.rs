@@ -64,6 +64,16 @@ pub mod user {
     pub fn base_dir() -> Option<PathBuf> {
         dirs::data_local_dir().and_then(|p| p.parent().map(|pp| pp.to_path_buf()))
     }
+    
+    /// Directories an app keeps its own data in, named after its ID
+    /// (~/.config/<id>, ~/.cache/<id>, ~/.local/state/<id>)
+    pub fn app_data_dirs(app_id: &str) -> Vec<PathBuf> {
+        [dirs::config_dir(), dirs::cache_dir(), dirs::state_dir()]
+            .into_iter()
+            .flatten()
+            .map(|dir| dir.join(app_id))
+            .collect()
+    }
 }
 
 /// Icon theme paths (hicolor)
@@ -87,7 +97,36 @@ pub mod icons {
     }
     
     /// Standard icon sizes in hicolor theme
-    pub const SIZES: &[&str] = &["16x16", "24x24", "32x32", "48x48", "64x64", "128x128", "256x256", "512x512"];
+    pub const SIZES: &[&str] = &["16x16", "22x22", "24x24", "32x32", "48x48", "64x64", "128x128", "256x256", "512x512"];
+    
+    /// Size directory for icons that aren't PNGs
+    pub const FALLBACK_SIZE: &str = "48x48";
+    
+    /// hicolor directory for a PNG icon, from the dimensions in its IHDR
+    /// chunk (the first 24 bytes): the smallest standard size that fits the
+    /// image, `scalable` above 512x512, and [`FALLBACK_SIZE`] if `data`
+    /// isn't a PNG
+    pub fn size_dir_for_png(data: &[u8]) -> &'static str {
+        let Some((width, height)) = png_dimensions(data) else {
+            return FALLBACK_SIZE;
+        };
+        let side = width.max(height);
+        SIZES.iter()
+            .find(|size| size.split('x').next().and_then(|s| s.parse::<u32>().ok()).is_some_and(|s| s >= side))
+            .copied()
+            .unwrap_or("scalable")
+    }
+    
+    /// Width and height from a PNG's IHDR chunk
+    pub fn png_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+        const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+        if !data.starts_with(SIGNATURE) || data.get(12..16) != Some(b"IHDR") {
+            return None;
+        }
+        let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+        Some((width, height))
+    }
 }
 
 /// LXE-specific paths and naming conventions
@@ -97,6 +136,12 @@ pub mod lxe {
     /// The polkit policy file path
     pub const POLKIT_POLICY_PATH: &str = "/usr/share/polkit-1/actions/org.lxe.policy";
     
+    /// Suffix of the directory a new version is extracted to
+    pub const STAGING_SUFFIX: &str = ".lxe-staging";
+    
+    /// Suffix of the previous version's directory while the new one is set up
+    pub const BACKUP_SUFFIX: &str = ".lxe-old";
+    
     /// Get the installation directory for an app
     pub fn app_install_dir(base_dir: &PathBuf, app_id: &str) -> PathBuf {
         base_dir.join("share").join(app_id)
@@ -112,6 +157,21 @@ pub mod lxe {
         
         apps_dir.map(|dir| dir.join(format!("{}.desktop", app_id)))
     }
+    
+    /// Get the LXE configuration directory (~/.config/lxe)
+    pub fn config_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|p| p.join("lxe"))
+    }
+    
+    /// Get the trusted public keys store (~/.config/lxe/trusted-keys.toml)
+    pub fn trusted_keys_path() -> Option<PathBuf> {
+        config_dir().map(|p| p.join("trusted-keys.toml"))
+    }
+    
+    /// Get the user's sandbox profiles (~/.config/lxe/sandbox-profiles.toml)
+    pub fn sandbox_profiles_path() -> Option<PathBuf> {
+        config_dir().map(|p| p.join("sandbox-profiles.toml"))
+    }
 }
 
 /// Safety validation for paths before deletion
@@ -174,6 +234,23 @@ mod tests {
         assert!(!safety::is_safe_to_delete(std::path::Path::new("/usr"), "com.test.App"));
     }
     
+    #[test]
+    fn test_icon_size_dir_for_png() {
+        let png = |width: u32, height: u32| {
+            let mut data = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+            data.extend(width.to_be_bytes());
+            data.extend(height.to_be_bytes());
+            data
+        };
+        assert_eq!(icons::size_dir_for_png(&png(22, 22)), "22x22");
+        assert_eq!(icons::size_dir_for_png(&png(256, 256)), "256x256");
+        assert_eq!(icons::size_dir_for_png(&png(100, 90)), "128x128");
+        assert_eq!(icons::size_dir_for_png(&png(1024, 1024)), "scalable");
+        assert_eq!(icons::size_dir_for_png(b"<svg/>"), icons::FALLBACK_SIZE);
+        assert_eq!(icons::png_dimensions(&png(1024, 768)), Some((1024, 768)));
+        assert_eq!(icons::png_dimensions(b"<svg/>"), None);
+    }
+    
     #[test]
     fn test_safety_requires_app_id() {
         let path = std::path::Path::new("/home/user/.local/share/some-app");
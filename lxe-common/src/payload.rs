@@ -2,12 +2,37 @@
 //!
 //! Reads the embedded payload from the running binary's tail.
 //! The binary structure is:
-//! [ELF executable][LXE_MAGIC][metadata_len:u32][metadata:JSON][checksum:32bytes][zstd_payload]
+//! [ELF executable][LXE_MAGIC][metadata_len:u32][metadata:JSON][checksum:32bytes][payload][footer]
+//!
+//! The payload is a tar archive compressed with zstd, or with LZ4 when the
+//! metadata's `compressor` says so.
+//!
+//! The checksum slot holds the first 32 bytes of the payload digest (all of
+//! it for SHA256 and BLAKE3); the full hex digest is `payload_checksum` in
+//! the metadata.
+//!
+//! Footer v1 (16 bytes):
+//! [header_offset:u64][LXE_MAGIC]
+//!
+//! Footer v2 (17 + 4 + N bytes) - adds extension fields without breaking v1 readers,
+//! which only look at the last 16 bytes:
+//! [extended_fields:N][extended_size:u32][format_version:u8=2][header_offset:u64][LXE_MAGIC]
+//!
+//! Footer v3 (40 bytes, written by `lxe build`) - records the whole layout, so
+//! readers jump straight to the metadata and payload:
+//! [LXE_MAGIC][format_version:u8=3][flags:u8][metadata_offset:u64][metadata_len:u32]
+//! [payload_offset:u64][payload_len:u64][reserved:2]
+//!
+//! v1 and v2 footers are still read; packages without any footer are found
+//! by a linear scan for the header.
 
 use crate::metadata::{LxeMetadata, LXE_MAGIC};
+use crate::payload_format::{self, MetaStub};
 use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
 use std::fs::File;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{BufReader, Read, Seek, SeekFrom, Write};
 use std::path::Path;
 
 /// Information about the embedded payload
@@ -24,10 +49,332 @@ pub struct PayloadInfo {
     
     /// Path to the executable (for reopening during extraction)
     pub exe_path: std::path::PathBuf,
+    
+    /// Offset of the LXE_MAGIC header (= size of the runtime binary)
+    pub header_offset: u64,
+    
+    /// Footer format version (1 or 2)
+    pub footer_version: u8,
+    
+    /// Extension fields from a v2 footer (defaults for v1)
+    pub footer_ext: FooterExtensions,
+}
+
+/// Size of the v1 footer: header offset + magic
+pub const FOOTER_V1_SIZE: u64 = 16;
+
+/// Footer format version written by `write_footer_v2`
+pub const FOOTER_VERSION_2: u8 = 2;
+
+/// Footer format version written by `write_footer_v3`
+pub const FOOTER_VERSION_3: u8 = 3;
+
+/// Size of the v3 footer
+pub const FOOTER_V3_SIZE: u64 = 40;
+
+/// Size of the extension block written by this version
+const FOOTER_EXT_SIZE: u32 = 16;
+
+/// Upper bound on extension block size (guards against misdetected v1 footers)
+const FOOTER_EXT_MAX: u32 = 4096;
+
+/// Extension fields carried by the v2 footer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FooterExtensions {
+    /// Payload encryption algorithm (0 = none)
+    pub payload_encryption_algo: u8,
+    
+    /// Offset of an additional metadata block (0 = none)
+    pub extra_metadata_offset: u64,
+}
+
+impl FooterExtensions {
+    /// Whether any extension is in use (a v1 footer is enough otherwise)
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+    
+    /// Serialize to the on-disk extension block
+    fn to_bytes(self) -> [u8; FOOTER_EXT_SIZE as usize] {
+        let mut bytes = [0u8; FOOTER_EXT_SIZE as usize];
+        bytes[0] = self.payload_encryption_algo;
+        bytes[1..9].copy_from_slice(&self.extra_metadata_offset.to_le_bytes());
+        // bytes[9..16] reserved (zero)
+        bytes
+    }
+    
+    /// Parse an extension block (unknown trailing bytes are ignored)
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < 9 {
+            return None;
+        }
+        Some(Self {
+            payload_encryption_algo: bytes[0],
+            extra_metadata_offset: u64::from_le_bytes(bytes[1..9].try_into().ok()?),
+        })
+    }
+}
+
+/// Write a v1 footer: [header_offset:u64][LXE_MAGIC]
+pub fn write_footer_v1<W: Write>(writer: &mut W, header_offset: u64) -> std::io::Result<()> {
+    writer.write_all(&header_offset.to_le_bytes())?;
+    writer.write_all(LXE_MAGIC)
+}
+
+/// Write a v2 footer carrying extension fields
+/// 
+/// The last 16 bytes are identical to a v1 footer, so older runtimes can
+/// still locate the header.
+pub fn write_footer_v2<W: Write>(
+    writer: &mut W,
+    header_offset: u64,
+    ext: &FooterExtensions,
+) -> std::io::Result<()> {
+    writer.write_all(&ext.to_bytes())?;
+    writer.write_all(&FOOTER_EXT_SIZE.to_le_bytes())?;
+    writer.write_all(&[FOOTER_VERSION_2])?;
+    write_footer_v1(writer, header_offset)
+}
+
+/// Layout recorded in a v3 footer (offsets from the start of the file)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FooterV3 {
+    /// No flags are defined yet; readers ignore unknown ones
+    pub flags: u8,
+    pub metadata_offset: u64,
+    pub metadata_len: u32,
+    pub payload_offset: u64,
+    pub payload_len: u64,
+}
+
+impl FooterV3 {
+    pub fn to_bytes(&self) -> [u8; FOOTER_V3_SIZE as usize] {
+        let mut bytes = [0u8; FOOTER_V3_SIZE as usize];
+        bytes[0..8].copy_from_slice(LXE_MAGIC);
+        bytes[8] = FOOTER_VERSION_3;
+        bytes[9] = self.flags;
+        bytes[10..18].copy_from_slice(&self.metadata_offset.to_le_bytes());
+        bytes[18..22].copy_from_slice(&self.metadata_len.to_le_bytes());
+        bytes[22..30].copy_from_slice(&self.payload_offset.to_le_bytes());
+        bytes[30..38].copy_from_slice(&self.payload_len.to_le_bytes());
+        // bytes[38..40] reserved (zero)
+        bytes
+    }
+    
+    /// Parse the last `FOOTER_V3_SIZE` bytes of a file; None if they are no v3 footer
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != FOOTER_V3_SIZE as usize || bytes[0..8] != LXE_MAGIC[..] || bytes[8] != FOOTER_VERSION_3 {
+            return None;
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        Some(Self {
+            flags: bytes[9],
+            metadata_offset: u64_at(10),
+            metadata_len: u32::from_le_bytes(bytes[18..22].try_into().unwrap()),
+            payload_offset: u64_at(22),
+            payload_len: u64_at(30),
+        })
+    }
+    
+    /// Offset of the LXE_MAGIC header in front of the metadata
+    pub fn header_offset(&self) -> u64 {
+        self.metadata_offset.saturating_sub(LXE_MAGIC.len() as u64 + 4)
+    }
+}
+
+/// Write a v3 footer
+pub fn write_footer_v3<W: Write>(writer: &mut W, footer: &FooterV3) -> std::io::Result<()> {
+    writer.write_all(&footer.to_bytes())
+}
+
+/// Footer found at the end of a package
+#[derive(Debug, Clone, Copy)]
+struct Footer {
+    header_offset: u64,
+    /// Total footer size in bytes
+    size: u64,
+    version: u8,
+    ext: FooterExtensions,
+}
+
+/// Size of the checksum slot between metadata and payload
+pub const CHECKSUM_SLOT_SIZE: usize = 32;
+
+/// Hash algorithm of `payload_checksum`, recorded in the metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    /// SHA256 (packages from before algorithm selection have no field)
+    #[default]
+    Sha256,
+    
+    /// BLAKE3, much faster on large payloads
+    Blake3,
+    
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    pub const ALL: [ChecksumAlgorithm; 3] = [Self::Sha256, Self::Blake3, Self::Sha512];
+    
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::fmt::Display for ChecksumAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChecksumAlgorithm::Sha256 => write!(f, "SHA256"),
+            ChecksumAlgorithm::Blake3 => write!(f, "BLAKE3"),
+            ChecksumAlgorithm::Sha512 => write!(f, "SHA512"),
+        }
+    }
+}
+
+/// Compression format of the payload, recorded as `compressor` in the metadata
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressorKind {
+    /// Packages from before compressor selection have no field
+    #[default]
+    Zstd,
+    
+    /// Lower ratio, but decompresses several times faster
+    Lz4,
+}
+
+impl CompressorKind {
+    /// Name in the metadata and lxe.toml
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompressorKind::Zstd => "zstd",
+            CompressorKind::Lz4 => "lz4",
+        }
+    }
+    
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl std::fmt::Display for CompressorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Streaming decoder for a payload compressed with `compressor` (the
+/// metadata's `compressor` field)
+pub fn decoder<'a, R: Read + Send + 'a>(reader: R, compressor: &str) -> Result<Box<dyn Read + Send + 'a>> {
+    match compressor {
+        "zstd" => Ok(Box::new(ruzstd::StreamingDecoder::new(reader)
+            .map_err(|e| anyhow::anyhow!("Failed to initialize zstd decoder: {}", e))?)),
+        "lz4" => Ok(Box::new(lz4::Decoder::new(reader)
+            .context("Failed to initialize lz4 decoder")?)),
+        other => bail!("Unsupported payload compressor '{}' (expected zstd or lz4)", other),
+    }
+}
+
+/// Incremental hasher for any [`ChecksumAlgorithm`]
+pub enum ChecksumHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Sha512(Sha512),
+}
+
+impl ChecksumHasher {
+    pub fn new(algo: ChecksumAlgorithm) -> Self {
+        match algo {
+            ChecksumAlgorithm::Sha256 => ChecksumHasher::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Blake3 => ChecksumHasher::Blake3(Box::new(blake3::Hasher::new())),
+            ChecksumAlgorithm::Sha512 => ChecksumHasher::Sha512(Sha512::new()),
+        }
+    }
+    
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            ChecksumHasher::Sha256(h) => h.update(data),
+            ChecksumHasher::Blake3(h) => {
+                h.update(data);
+            }
+            ChecksumHasher::Sha512(h) => h.update(data),
+        }
+    }
+    
+    /// Hex-encoded digest
+    pub fn finalize_hex(self) -> String {
+        match self {
+            ChecksumHasher::Sha256(h) => hex::encode(h.finalize()),
+            ChecksumHasher::Blake3(h) => h.finalize().to_hex().to_string(),
+            ChecksumHasher::Sha512(h) => hex::encode(h.finalize()),
+        }
+    }
+}
+
+/// Hex-encoded checksum of `data`
+pub fn calculate_checksum(data: &[u8], algo: ChecksumAlgorithm) -> String {
+    let mut hasher = ChecksumHasher::new(algo);
+    hasher.update(data);
+    hasher.finalize_hex()
+}
+
+/// Hex-encoded checksum of the file at `path`, read in chunks
+pub fn checksum_file(path: &Path, algo: ChecksumAlgorithm) -> Result<String> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    checksum_reader(file, algo)
+}
+
+/// Hex-encoded checksum of everything `reader` yields, read in chunks
+pub fn checksum_reader(mut reader: impl Read, algo: ChecksumAlgorithm) -> Result<String> {
+    let mut hasher = ChecksumHasher::new(algo);
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finalize_hex())
+}
+
+/// Bytes stored in the checksum slot for a hex digest (its first 32 bytes)
+pub fn checksum_slot(checksum_hex: &str) -> Result<Vec<u8>> {
+    let mut bytes = hex::decode(checksum_hex)
+        .context("Invalid hex in payload checksum")?;
+    if bytes.len() < CHECKSUM_SLOT_SIZE {
+        bail!("Payload checksum is shorter than {} bytes", CHECKSUM_SLOT_SIZE);
+    }
+    bytes.truncate(CHECKSUM_SLOT_SIZE);
+    Ok(bytes)
 }
 
 /// Read payload information from an LXE binary
+/// 
+/// Verifies the signature (if signed). System requirements are only checked
+/// by the install paths (`requirements::check_system_requirements`), so
+/// packages can still be inspected on systems that can't run them.
 pub fn read_payload_info(exe_path: &Path) -> Result<PayloadInfo> {
+    let info = read_payload_info_unchecked(exe_path)?;
+    
+    // ========== Ed25519 Signature Verification ==========
+    // If the package is signed, verify the signature BEFORE returning.
+    // This happens before the GUI opens, so a tampered package never shows the wizard.
+    
+    if info.metadata.is_signed() {
+        verify_package_signature(&info.metadata)?;
+    }
+    
+    Ok(info)
+}
+
+/// Parse the package layout without the signature check
+/// 
+/// For tooling that inspects or rewrites packages; installers must use
+/// `read_payload_info`.
+pub fn read_payload_info_unchecked(exe_path: &Path) -> Result<PayloadInfo> {
     let file = File::open(exe_path)
         .with_context(|| format!("Failed to open executable: {:?}", exe_path))?;
     
@@ -53,8 +400,9 @@ pub fn read_payload_info(exe_path: &Path) -> Result<PayloadInfo> {
         1024 * 1024
     };
     
-    let magic_offset = find_magic_offset(&mut reader, scan_start, file_size)?
+    let footer = locate_footer(&mut reader, scan_start, file_size)?
         .ok_or_else(|| anyhow::anyhow!("LXE magic bytes not found in binary"))?;
+    let magic_offset = footer.header_offset;
     
     // Read metadata length (4 bytes, little-endian)
     reader.seek(SeekFrom::Start(magic_offset + LXE_MAGIC.len() as u64))?;
@@ -73,32 +421,148 @@ pub fn read_payload_info(exe_path: &Path) -> Result<PayloadInfo> {
     let metadata: LxeMetadata = serde_json::from_slice(&metadata_bytes)
         .context("Failed to parse LXE metadata")?;
     
-    // Skip checksum (32 bytes SHA256)
-    let checksum_size = 32;
+    // Skip the checksum slot (same size for every algorithm)
     let current_pos = reader.stream_position()?;
     
-    // Calculate payload offset and size
-    let payload_offset = current_pos + checksum_size;
-    let payload_size = file_size - payload_offset;
-    
-    // ========== Ed25519 Signature Verification ==========
-    // If the package is signed, verify the signature BEFORE returning.
-    // This happens before the GUI opens, so a tampered package never shows the wizard.
-    
-    if metadata.is_signed() {
-        verify_package_signature(&metadata)?;
-    }
+    // Calculate payload offset and size (the footer is not part of the payload)
+    let payload_offset = current_pos + CHECKSUM_SLOT_SIZE as u64;
+    let payload_size = (file_size - footer.size).saturating_sub(payload_offset);
     
     Ok(PayloadInfo {
         metadata,
         payload_offset,
         payload_size,
         exe_path: exe_path.to_path_buf(),
+        header_offset: magic_offset,
+        footer_version: footer.version,
+        footer_ext: footer.ext,
+    })
+}
+
+/// Byte layout of a package, located without parsing the metadata
+#[derive(Debug, Clone, Copy)]
+pub struct PackageLayout {
+    pub file_size: u64,
+    
+    /// Offset of LXE_MAGIC (end of the runtime)
+    pub header_offset: u64,
+    
+    /// Value of the metadata length field
+    pub metadata_len: u32,
+    
+    /// Footer size in bytes (0 if the header was found by scanning)
+    pub footer_size: u64,
+    
+    pub footer_version: u8,
+}
+
+impl PackageLayout {
+    /// Offset of the metadata JSON
+    pub fn metadata_offset(&self) -> u64 {
+        self.header_offset + LXE_MAGIC.len() as u64 + 4
+    }
+    
+    /// Offset of the payload checksum slot
+    pub fn checksum_offset(&self) -> u64 {
+        self.metadata_offset() + self.metadata_len as u64
+    }
+    
+    /// Offset of the compressed payload
+    pub fn payload_offset(&self) -> u64 {
+        self.checksum_offset() + CHECKSUM_SLOT_SIZE as u64
+    }
+    
+    /// Size of the compressed payload
+    pub fn payload_size(&self) -> u64 {
+        (self.file_size - self.footer_size).saturating_sub(self.payload_offset())
+    }
+}
+
+/// Locate the header and footer of a package
+pub fn read_layout(exe_path: &Path) -> Result<PackageLayout> {
+    let file = File::open(exe_path)
+        .with_context(|| format!("Failed to open {:?}", exe_path))?;
+    let file_size = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    
+    let footer = locate_footer(&mut reader, 0, file_size)?
+        .ok_or_else(|| anyhow::anyhow!("LXE magic bytes not found in binary"))?;
+    
+    reader.seek(SeekFrom::Start(footer.header_offset + LXE_MAGIC.len() as u64))?;
+    let mut len_bytes = [0u8; 4];
+    reader.read_exact(&mut len_bytes)?;
+    
+    Ok(PackageLayout {
+        file_size,
+        header_offset: footer.header_offset,
+        metadata_len: u32::from_le_bytes(len_bytes),
+        footer_size: footer.size,
+        footer_version: footer.version,
     })
 }
 
-/// Verify the Ed25519 signature on a signed package
-fn verify_package_signature(metadata: &LxeMetadata) -> Result<()> {
+/// Read the layout recorded in the runtime's metadata stub
+/// 
+/// Only section headers and the stub are read; nothing is executed or
+/// scanned. Fails for packages whose runtime has no stub.
+pub fn read_metadata_stub(file: &Path) -> Result<MetaStub> {
+    let mut reader = BufReader::new(File::open(file)
+        .with_context(|| format!("Failed to open {:?}", file))?);
+    let (offset, size) = payload_format::find_elf_section(&mut reader, payload_format::META_STUB_SECTION)?
+        .ok_or_else(|| anyhow::anyhow!("No metadata stub in {:?} (runtime without stub support)", file))?;
+    if size < payload_format::META_STUB_SIZE as u64 {
+        bail!("Metadata stub section is too small ({} bytes)", size);
+    }
+    
+    let mut bytes = [0u8; payload_format::META_STUB_SIZE];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut bytes)?;
+    MetaStub::from_bytes(&bytes)
+}
+
+/// Parse the metadata located by the metadata stub (see `read_metadata_stub`)
+pub fn read_metadata_via_stub(file: &Path) -> Result<LxeMetadata> {
+    let stub = read_metadata_stub(file)?;
+    if stub.metadata_len > 1024 * 1024 {
+        bail!("Metadata length {} exceeds maximum (1MB)", stub.metadata_len);
+    }
+    
+    let mut reader = File::open(file)?;
+    let mut json = vec![0u8; stub.metadata_len as usize];
+    reader.seek(SeekFrom::Start(stub.metadata_offset))?;
+    reader.read_exact(&mut json)?;
+    serde_json::from_slice(&json).context("Failed to parse LXE metadata")
+}
+
+/// Verify the signature on a signed package, then check its key against
+/// the trust store and signature policy
+pub(crate) fn verify_package_signature(metadata: &LxeMetadata) -> Result<()> {
+    check_package_signature(metadata)?;
+    tracing::info!("✓ Package signature verified successfully");
+    
+    // The signature only proves the package matches its own key; the trust
+    // store says whether that key may sign this app
+    let public_key = metadata.public_key.as_deref().unwrap_or_default();
+    let store = crate::keystore::TrustStore::load()?;
+    match store.check_signer(public_key, &metadata.app_id)? {
+        Some(key) => tracing::info!("✓ Signed by trusted key '{}'", key.name),
+        None => {
+            let policy = crate::config::GlobalConfig::load()?.signature_policy;
+            if policy == crate::config::SignaturePolicy::TrustedOnly {
+                return Err(crate::error::LxeError::UntrustedSigner {
+                    app_id: metadata.app_id.clone(),
+                    reason: "the key is not in the trust store (see `lxe key trust`)".to_string(),
+                }.into());
+            }
+            tracing::info!("Signing key is not trusted for {} (see `lxe key trust`)", metadata.app_id);
+        }
+    }
+    Ok(())
+}
+
+/// Check that a signed package's signature matches its metadata and
+/// payload checksum, without consulting the trust store
+pub fn check_package_signature(metadata: &LxeMetadata) -> Result<()> {
     use crate::signing;
     
     let public_key = metadata.public_key.as_ref()
@@ -115,39 +579,82 @@ fn verify_package_signature(metadata: &LxeMetadata) -> Result<()> {
         .context("Failed to create signable data")?;
     
     // Verify the signature
-    let is_valid = signing::verify_signature(&signable_data, signature, public_key)
+    let is_valid = signing::verify_signature(metadata.algorithm, &signable_data, signature, public_key)
         .context("Failed to verify signature")?;
     
     if !is_valid {
-        bail!(
-            "SECURITY: Package signature verification FAILED!\n\n\
-             This package may have been tampered with.\n\
-             Do not install it unless you trust the source.\n\n\
-             If you're a developer, check that:\n\
-             1. The private key matches the public key in the package\n\
-             2. The metadata wasn't modified after signing"
-        );
+        return Err(crate::error::LxeError::SignatureInvalid.into());
     }
-    
-    tracing::info!("✓ Package signature verified successfully");
     Ok(())
 }
 
+/// Find the footer: a v3 footer is read directly, older packages go
+/// through `find_magic_offset`
+fn locate_footer(
+    reader: &mut BufReader<File>,
+    start_offset: u64,
+    file_size: u64,
+) -> Result<Option<Footer>> {
+    if let Some(footer) = parse_v3_footer(reader, file_size)? {
+        return Ok(Some(footer));
+    }
+    find_magic_offset(reader, start_offset, file_size)
+}
+
+/// Read the last `FOOTER_V3_SIZE` bytes as a v3 footer
+/// 
+/// The recorded layout must match the file: the header in front of the
+/// metadata, the metadata length in it, and a payload ending at the footer.
+/// Anything else is left to the v1/v2 readers.
+fn parse_v3_footer(reader: &mut BufReader<File>, file_size: u64) -> Result<Option<Footer>> {
+    if file_size < FOOTER_V3_SIZE {
+        return Ok(None);
+    }
+    let footer_start = file_size - FOOTER_V3_SIZE;
+    reader.seek(SeekFrom::Start(footer_start))?;
+    let mut bytes = [0u8; FOOTER_V3_SIZE as usize];
+    reader.read_exact(&mut bytes)?;
+    let Some(footer) = FooterV3::from_bytes(&bytes) else {
+        return Ok(None);
+    };
+    
+    let consistent = footer.metadata_offset >= (LXE_MAGIC.len() + 4) as u64
+        && footer.metadata_offset + footer.metadata_len as u64 + CHECKSUM_SLOT_SIZE as u64 == footer.payload_offset
+        && footer.payload_offset.checked_add(footer.payload_len) == Some(footer_start);
+    if !consistent {
+        return Ok(None);
+    }
+    let mut header = [0u8; 12];
+    reader.seek(SeekFrom::Start(footer.header_offset()))?;
+    reader.read_exact(&mut header)?;
+    if header[..8] != LXE_MAGIC[..] || header[8..] != footer.metadata_len.to_le_bytes() {
+        return Ok(None);
+    }
+    
+    tracing::info!("Found LXE v3 footer. Jumping to metadata at offset {}", footer.metadata_offset);
+    Ok(Some(Footer {
+        header_offset: footer.header_offset(),
+        size: FOOTER_V3_SIZE,
+        version: FOOTER_VERSION_3,
+        ext: FooterExtensions::default(),
+    }))
+}
+
 /// Scan the file for LXE magic bytes - finds the LAST occurrence
 /// Scan for LXE magic bytes using the Footer (O(1)) approach
 /// 
-/// New format: [Runtime] ... [Payload] [HeaderOffset(u64)] [Magic(8)]
+/// Footer: [Runtime] ... [Payload] [v2 fields?] [HeaderOffset(u64)] [Magic(8)]
 fn find_magic_offset(
     reader: &mut BufReader<File>,
     _start_offset: u64,
     file_size: u64,
-) -> Result<Option<u64>> {
+) -> Result<Option<Footer>> {
     // 1. Check for Footer (last 16 bytes)
-    if file_size < 16 {
+    if file_size < FOOTER_V1_SIZE {
         return Ok(None);
     }
     
-    let footer_start = file_size - 16;
+    let footer_start = file_size - FOOTER_V1_SIZE;
     reader.seek(SeekFrom::Start(footer_start))?;
     
     let mut footer = [0u8; 16];
@@ -159,9 +666,20 @@ fn find_magic_offset(
         let offset = u64::from_le_bytes(offset_bytes.try_into().unwrap());
         
         // Sanity check offset
-        if offset < file_size - 16 {
+        if offset < footer_start {
             tracing::info!("Found LXE Footer. Jumping to payload at offset {}", offset);
-            return Ok(Some(offset));
+            
+            // 2. A version byte of 2 just before the v1 part marks a v2 footer
+            if let Some(v2) = read_footer_v2(reader, offset, footer_start)? {
+                return Ok(Some(v2));
+            }
+            
+            return Ok(Some(Footer {
+                header_offset: offset,
+                size: FOOTER_V1_SIZE,
+                version: 1,
+                ext: FooterExtensions::default(),
+            }));
         }
     }
     
@@ -178,17 +696,87 @@ fn find_magic_offset(
     // Find LAST occurrence in the scan window to avoid false positives in runtime self
     // (Though with 10MB scan, we might hit runtime data. The footer is safer.)
     if let Some(pos) = buffer.windows(LXE_MAGIC.len()).rposition(|w| w == LXE_MAGIC) {
-         return Ok(Some(pos as u64));
+         return Ok(Some(Footer {
+             header_offset: pos as u64,
+             size: 0,
+             version: 1,
+             ext: FooterExtensions::default(),
+         }));
     }
 
     Ok(None)
 }
 
+/// Try to parse the v2 part of a footer ending at `v1_start`
+/// 
+/// Returns None for v1 footers. Because the byte before a v1 footer is
+/// payload data, the extension block is validated before it is trusted.
+fn read_footer_v2(
+    reader: &mut BufReader<File>,
+    header_offset: u64,
+    v1_start: u64,
+) -> Result<Option<Footer>> {
+    // [ext_size:u32][version:u8]
+    if v1_start < 5 {
+        return Ok(None);
+    }
+    reader.seek(SeekFrom::Start(v1_start - 5))?;
+    let mut fixed = [0u8; 5];
+    reader.read_exact(&mut fixed)?;
+    
+    if fixed[4] != FOOTER_VERSION_2 {
+        return Ok(None);
+    }
+    
+    let ext_size = u32::from_le_bytes(fixed[..4].try_into().unwrap());
+    let ext_start = match (v1_start - 5).checked_sub(ext_size as u64) {
+        Some(start) if ext_size <= FOOTER_EXT_MAX && start > header_offset => start,
+        _ => return Ok(None),
+    };
+    
+    reader.seek(SeekFrom::Start(ext_start))?;
+    let mut ext_bytes = vec![0u8; ext_size as usize];
+    reader.read_exact(&mut ext_bytes)?;
+    
+    // Reserved bytes of the current layout must be zero
+    if ext_bytes.len() >= FOOTER_EXT_SIZE as usize && ext_bytes[9..FOOTER_EXT_SIZE as usize].iter().any(|&b| b != 0) {
+        return Ok(None);
+    }
+    
+    let Some(ext) = FooterExtensions::from_bytes(&ext_bytes) else {
+        return Ok(None);
+    };
+    
+    tracing::info!("Found LXE v2 footer ({} extension bytes)", ext_size);
+    Ok(Some(Footer {
+        header_offset,
+        size: FOOTER_V1_SIZE + 5 + ext_size as u64,
+        version: FOOTER_VERSION_2,
+        ext,
+    }))
+}
+
 /// Find a subsequence within a slice
 fn find_subsequence(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack.windows(needle.len()).position(|window| window == needle)
 }
 
+/// Read the raw metadata JSON bytes of a package
+/// 
+/// Unlike `PayloadInfo::metadata`, this keeps fields unknown to this version.
+pub fn read_raw_metadata(info: &PayloadInfo) -> Result<Vec<u8>> {
+    let mut file = File::open(&info.exe_path)?;
+    file.seek(SeekFrom::Start(info.header_offset + LXE_MAGIC.len() as u64))?;
+    
+    let mut len_bytes = [0u8; 4];
+    file.read_exact(&mut len_bytes)?;
+    let metadata_len = u32::from_le_bytes(len_bytes) as usize;
+    
+    let mut metadata_bytes = vec![0u8; metadata_len];
+    file.read_exact(&mut metadata_bytes)?;
+    Ok(metadata_bytes)
+}
+
 /// Create a reader positioned at the payload start
 pub fn open_payload_reader(info: &PayloadInfo) -> Result<impl Read> {
     let file = File::open(&info.exe_path)?;
@@ -197,6 +785,86 @@ pub fn open_payload_reader(info: &PayloadInfo) -> Result<impl Read> {
     Ok(reader.take(info.payload_size))
 }
 
+/// Type of a payload archive entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    HardLink,
+    Other,
+}
+
+/// One entry of the payload archive, as listed by `list_entries`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntryInfo {
+    /// Path relative to the install directory (without the leading `./`)
+    pub path: std::path::PathBuf,
+    
+    /// Uncompressed size (0 for anything but files)
+    pub size: u64,
+    
+    /// Permission bits
+    pub mode: u32,
+    
+    pub kind: EntryKind,
+    
+    /// Target of symlinks and hard links
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link_target: Option<std::path::PathBuf>,
+}
+
+/// List the payload archive's entries without extracting anything
+pub fn list_entries(info: &PayloadInfo) -> Result<Vec<EntryInfo>> {
+    let decoder = decoder(open_payload_reader(info)?, &info.metadata.compressor)?;
+    let mut archive = tar::Archive::new(decoder);
+    
+    let mut entries = Vec::new();
+    for entry in archive.entries().context("Failed to read payload")? {
+        let entry = entry.context("Corrupt tar entry in payload")?;
+        entries.extend(entry_info(&entry)?);
+    }
+    Ok(entries)
+}
+
+/// Describe one payload archive entry; `None` for the archive root
+pub fn entry_info<R: Read>(entry: &tar::Entry<'_, R>) -> Result<Option<EntryInfo>> {
+    let path = entry.path()?;
+    let path = path.strip_prefix(".").unwrap_or(&path).to_path_buf();
+    // The archive root itself
+    if path.as_os_str().is_empty() {
+        return Ok(None);
+    }
+    
+    let header = entry.header();
+    let entry_type = header.entry_type();
+    // Files with holes are stored as GNU sparse entries
+    let kind = if entry_type.is_file() || entry_type.is_gnu_sparse() {
+        EntryKind::File
+    } else if entry_type.is_dir() {
+        EntryKind::Directory
+    } else if entry_type.is_symlink() {
+        EntryKind::Symlink
+    } else if entry_type.is_hard_link() {
+        EntryKind::HardLink
+    } else {
+        EntryKind::Other
+    };
+    let size = match header.as_gnu() {
+        Some(gnu) if entry_type.is_gnu_sparse() => gnu.real_size()?,
+        _ if kind == EntryKind::File => header.size()?,
+        _ => 0,
+    };
+    Ok(Some(EntryInfo {
+        path,
+        size,
+        mode: header.mode()? & 0o7777,
+        kind,
+        link_target: entry.link_name()?.map(|target| target.into_owned()),
+    }))
+}
+
 /// Extract the icon from the payload to a temporary file
 /// Returns the path to the extracted icon, or None if no icon exists
 pub fn extract_icon_to_temp(info: &PayloadInfo) -> Result<Option<std::path::PathBuf>> {
@@ -210,9 +878,7 @@ pub fn extract_icon_to_temp(info: &PayloadInfo) -> Result<Option<std::path::Path
     let temp_icon_path = temp_dir.join(format!("lxe-icon-{}.png", info.metadata.app_id));
     
     // Open payload and decompress
-    let mut reader = open_payload_reader(info)?;
-    let decoder = ruzstd::StreamingDecoder::new(&mut reader)
-        .map_err(|e| anyhow::anyhow!("Failed to initialize zstd decoder: {}", e))?;
+    let decoder = decoder(open_payload_reader(info)?, &info.metadata.compressor)?;
     let mut archive = tar::Archive::new(decoder);
     
     // Find and extract just the icon file
@@ -265,4 +931,154 @@ mod tests {
         let scan_start = if large_file_size < 2 * 1024 * 1024 { 0 } else { 1024 * 1024 };
         assert_eq!(scan_start, 1024 * 1024);
     }
+    
+    #[test]
+    fn test_calculate_checksum() {
+        assert_eq!(
+            calculate_checksum(b"abc", ChecksumAlgorithm::Sha256),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            calculate_checksum(b"abc", ChecksumAlgorithm::Blake3),
+            "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85"
+        );
+        
+        let sha512 = calculate_checksum(b"abc", ChecksumAlgorithm::Sha512);
+        assert!(sha512.starts_with("ddaf35a193617aba"));
+        assert_eq!(checksum_slot(&sha512).unwrap(), hex::decode(&sha512[..64]).unwrap());
+        assert_eq!(checksum_reader(&b"abc"[..], ChecksumAlgorithm::Sha512).unwrap(), sha512);
+    }
+    
+    fn write_test_package(path: &Path, payload: &[u8], ext: Option<FooterExtensions>) {
+        let meta = LxeMetadata::new("com.example.Test", "Test", "1.0.0", "test", 0, "00");
+        let json = serde_json::to_vec(&meta).unwrap();
+        
+        let mut data = b"fake runtime".to_vec();
+        let header_offset = data.len() as u64;
+        data.extend_from_slice(LXE_MAGIC);
+        data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        data.extend_from_slice(&json);
+        data.extend_from_slice(&[0u8; 32]);
+        data.extend_from_slice(payload);
+        match ext {
+            Some(ext) => write_footer_v2(&mut data, header_offset, &ext).unwrap(),
+            None => write_footer_v1(&mut data, header_offset).unwrap(),
+        }
+        std::fs::write(path, data).unwrap();
+    }
+    
+    #[test]
+    fn test_footer_v1_and_v2() {
+        let dir = std::env::temp_dir();
+        let v1_path = dir.join(format!("lxe-footer-v1-{}.lxe", std::process::id()));
+        let v2_path = dir.join(format!("lxe-footer-v2-{}.lxe", std::process::id()));
+        
+        // Payload ending in 0x02 must not be mistaken for a v2 footer
+        write_test_package(&v1_path, b"payload\x02", None);
+        let info = read_payload_info(&v1_path).unwrap();
+        assert_eq!(info.footer_version, 1);
+        assert_eq!(info.payload_size, 8);
+        
+        let ext = FooterExtensions { payload_encryption_algo: 1, extra_metadata_offset: 42 };
+        write_test_package(&v2_path, b"payload", Some(ext));
+        let info = read_payload_info(&v2_path).unwrap();
+        assert_eq!(info.footer_version, 2);
+        assert_eq!(info.footer_ext, ext);
+        assert_eq!(info.payload_size, 7);
+        
+        let _ = std::fs::remove_file(&v1_path);
+        let _ = std::fs::remove_file(&v2_path);
+    }
+    
+    #[test]
+    fn test_read_ignores_requirements() {
+        let path = std::env::temp_dir().join(format!("lxe-requirements-{}.lxe", std::process::id()));
+        let mut meta = LxeMetadata::new("com.example.Test", "Test", "1.0.0", "test", 0, "00");
+        meta.min_glibc_version = Some("999.0".to_string());
+        let json = serde_json::to_vec(&meta).unwrap();
+        let mut data = b"fake runtime".to_vec();
+        let header_offset = data.len() as u64;
+        data.extend_from_slice(LXE_MAGIC);
+        data.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        data.extend_from_slice(&json);
+        data.extend_from_slice(&[0u8; 32]);
+        write_footer_v1(&mut data, header_offset).unwrap();
+        std::fs::write(&path, data).unwrap();
+        
+        // Inspecting works anywhere; installing is refused
+        let info = read_payload_info(&path).unwrap();
+        assert!(crate::requirements::check_system_requirements(&info.metadata).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+    
+    #[test]
+    fn test_footer_v3() {
+        let path = std::env::temp_dir().join(format!("lxe-footer-v3-{}.lxe", std::process::id()));
+        
+        // The runtime contains the magic too; the footer must win without a scan
+        let mut runtime = b"fake runtime ".to_vec();
+        runtime.extend_from_slice(LXE_MAGIC);
+        write_test_package(&path, b"payload", None);
+        let v1 = std::fs::read(&path).unwrap();
+        let info = read_payload_info(&path).unwrap();
+        let json_len = info.payload_offset - info.header_offset - 12 - 32;
+        
+        let mut data = runtime.clone();
+        data.extend_from_slice(&v1[info.header_offset as usize..v1.len() - FOOTER_V1_SIZE as usize]);
+        let metadata_offset = runtime.len() as u64 + 12;
+        let footer = FooterV3 {
+            flags: 0,
+            metadata_offset,
+            metadata_len: json_len as u32,
+            payload_offset: metadata_offset + json_len + 32,
+            payload_len: 7,
+        };
+        assert_eq!(FooterV3::from_bytes(&footer.to_bytes()), Some(footer));
+        write_footer_v3(&mut data, &footer).unwrap();
+        std::fs::write(&path, &data).unwrap();
+        
+        let info = read_payload_info(&path).unwrap();
+        assert_eq!(info.footer_version, FOOTER_VERSION_3);
+        assert_eq!(info.header_offset, runtime.len() as u64);
+        assert_eq!((info.payload_offset, info.payload_size), (footer.payload_offset, 7));
+        let layout = read_layout(&path).unwrap();
+        assert_eq!((layout.footer_size, layout.payload_size()), (FOOTER_V3_SIZE, 7));
+        
+        // A footer that doesn't match the file is not trusted
+        let mut bad = footer;
+        bad.payload_len = 8;
+        data.truncate(data.len() - FOOTER_V3_SIZE as usize);
+        write_footer_v3(&mut data, &bad).unwrap();
+        std::fs::write(&path, &data).unwrap();
+        assert_ne!(read_layout(&path).map(|l| l.footer_version).ok(), Some(FOOTER_VERSION_3));
+        
+        let _ = std::fs::remove_file(&path);
+    }
+    
+    #[cfg(feature = "build")]
+    #[test]
+    fn test_list_entries() {
+        let dir = std::env::temp_dir().join(format!("lxe-list-entries-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("input/lib")).unwrap();
+        std::fs::write(dir.join("input/app"), b"#!/bin/sh\n").unwrap();
+        std::fs::write(dir.join("input/lib/libfoo.so.1"), b"elf").unwrap();
+        std::os::unix::fs::symlink("libfoo.so.1", dir.join("input/lib/libfoo.so")).unwrap();
+        
+        let tar = crate::builder::create_tar_archive(&dir.join("input")).unwrap();
+        let package = dir.join("test.lxe");
+        write_test_package(&package, &crate::builder::compress_zstd(&tar, 1).unwrap(), None);
+        
+        let mut entries = list_entries(&read_payload_info(&package).unwrap()).unwrap();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        let listed: Vec<_> = entries.iter().map(|e| (e.path.to_str().unwrap(), e.kind, e.size)).collect();
+        assert_eq!(listed, [
+            ("app", EntryKind::File, 10),
+            ("lib", EntryKind::Directory, 0),
+            ("lib/libfoo.so", EntryKind::Symlink, 0),
+            ("lib/libfoo.so.1", EntryKind::File, 3),
+        ]);
+        assert_eq!(entries[2].link_target.as_deref(), Some(Path::new("libfoo.so.1")));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,222 @@
+//! Metadata Stub - package layout readable without parsing the footer
+//!
+//! The runtime reserves a 256-byte stub in an ELF section named
+//! `__lxe_meta_stub`. `lxe build` fills it in with where the metadata and
+//! payload are, so tools find a package's metadata from the section
+//! headers alone, without executing anything:
+//!
+//! ```text
+//! [LXE_MAGIC:8][version:u8][reserved:7][header_offset:u64][metadata_offset:u64]
+//! [metadata_len:u32][reserved:4][payload_offset:u64][payload_size:u64][zero padding]
+//! ```
+//!
+//! Until patched, the stub holds `EMPTY_META_STUB` (no magic). Runtimes
+//! without the section still build working packages, just without a stub.
+
+use crate::metadata::LXE_MAGIC;
+use anyhow::{bail, Context, Result};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Size of the stub section
+pub const META_STUB_SIZE: usize = 256;
+
+/// ELF section holding the stub
+pub const META_STUB_SECTION: &str = "__lxe_meta_stub";
+
+/// Stub layout version written by this version
+pub const META_STUB_VERSION: u8 = 1;
+
+/// Placeholder compiled into the runtime
+pub const EMPTY_META_STUB: [u8; META_STUB_SIZE] = {
+    let mut stub = [0u8; META_STUB_SIZE];
+    let marker = b"LXE-META-STUB-UNSET";
+    let mut i = 0;
+    while i < marker.len() {
+        stub[i] = marker[i];
+        i += 1;
+    }
+    stub
+};
+
+/// Upper bound on section header table size (guards against corrupt headers)
+const MAX_SECTION_HEADERS: u64 = 4096;
+
+/// Package layout recorded in the stub (all offsets from the start of the file)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetaStub {
+    pub header_offset: u64,
+    pub metadata_offset: u64,
+    pub metadata_len: u32,
+    pub payload_offset: u64,
+    pub payload_size: u64,
+}
+
+impl MetaStub {
+    pub fn to_bytes(&self) -> [u8; META_STUB_SIZE] {
+        let mut bytes = [0u8; META_STUB_SIZE];
+        bytes[0..8].copy_from_slice(LXE_MAGIC);
+        bytes[8] = META_STUB_VERSION;
+        bytes[16..24].copy_from_slice(&self.header_offset.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.metadata_offset.to_le_bytes());
+        bytes[32..36].copy_from_slice(&self.metadata_len.to_le_bytes());
+        bytes[40..48].copy_from_slice(&self.payload_offset.to_le_bytes());
+        bytes[48..56].copy_from_slice(&self.payload_size.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < META_STUB_SIZE {
+            bail!("Metadata stub is truncated");
+        }
+        if bytes[0..8] != LXE_MAGIC[..] {
+            bail!("Metadata stub was not filled in (package built without stub support)");
+        }
+        if bytes[8] != META_STUB_VERSION {
+            bail!("Unsupported metadata stub version {}", bytes[8]);
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        Ok(Self {
+            header_offset: u64_at(16),
+            metadata_offset: u64_at(24),
+            metadata_len: u32::from_le_bytes(bytes[32..36].try_into().unwrap()),
+            payload_offset: u64_at(40),
+            payload_size: u64_at(48),
+        })
+    }
+}
+
+/// File offset and size of the ELF section `name`, if present
+///
+/// Only 64-bit little-endian ELF files are supported (the runtime targets).
+pub fn find_elf_section<R: Read + Seek>(reader: &mut R, name: &str) -> Result<Option<(u64, u64)>> {
+    let mut header = [0u8; 64];
+    reader.seek(SeekFrom::Start(0))?;
+    if reader.read_exact(&mut header).is_err() || header[0..4] != *b"\x7fELF" {
+        bail!("Not an ELF binary");
+    }
+    if header[4] != 2 || header[5] != 1 {
+        bail!("Only 64-bit little-endian ELF binaries are supported");
+    }
+
+    let sh_offset = u64::from_le_bytes(header[0x28..0x30].try_into()?);
+    let sh_entsize = u16::from_le_bytes(header[0x3A..0x3C].try_into()?) as u64;
+    let sh_num = u16::from_le_bytes(header[0x3C..0x3E].try_into()?) as u64;
+    let sh_strndx = u16::from_le_bytes(header[0x3E..0x40].try_into()?) as u64;
+    if sh_num == 0 || sh_entsize < 64 || sh_num > MAX_SECTION_HEADERS || sh_strndx >= sh_num {
+        return Ok(None);
+    }
+
+    let mut table = vec![0u8; (sh_num * sh_entsize) as usize];
+    reader.seek(SeekFrom::Start(sh_offset))?;
+    reader.read_exact(&mut table).context("Truncated ELF section headers")?;
+    let section = |index: u64| {
+        let entry = &table[(index * sh_entsize) as usize..][..64];
+        let field = |at: usize| u64::from_le_bytes(entry[at..at + 8].try_into().unwrap());
+        let name = u32::from_le_bytes(entry[0..4].try_into().unwrap()) as usize;
+        let kind = u32::from_le_bytes(entry[4..8].try_into().unwrap());
+        (name, kind, field(0x18), field(0x20))
+    };
+
+    // Section names live in the string table section
+    let (_, _, strtab_offset, strtab_size) = section(sh_strndx);
+    if strtab_size > 1024 * 1024 {
+        return Ok(None);
+    }
+    let mut strtab = vec![0u8; strtab_size as usize];
+    reader.seek(SeekFrom::Start(strtab_offset))?;
+    reader.read_exact(&mut strtab).context("Truncated ELF string table")?;
+
+    const SHT_NOBITS: u32 = 8;
+    for index in 0..sh_num {
+        let (name_offset, kind, offset, size) = section(index);
+        let section_name = strtab.get(name_offset..)
+            .and_then(|rest| rest.split(|&b| b == 0).next())
+            .unwrap_or_default();
+        if section_name == name.as_bytes() && kind != SHT_NOBITS {
+            return Ok(Some((offset, size)));
+        }
+    }
+    Ok(None)
+}
+
+/// Offset of the stub in `runtime`, if it has a stub section
+pub fn stub_offset(runtime: &[u8]) -> Option<u64> {
+    stub_offset_in(&mut std::io::Cursor::new(runtime), runtime.len() as u64)
+}
+
+/// Offset of the stub in a package read from `reader` whose runtime is
+/// `runtime_len` bytes, if the stub section lies entirely inside the runtime
+pub fn stub_offset_in<R: Read + Seek>(reader: &mut R, runtime_len: u64) -> Option<u64> {
+    match find_elf_section(reader, META_STUB_SECTION) {
+        Ok(Some((offset, size)))
+            if size >= META_STUB_SIZE as u64
+                && offset.checked_add(META_STUB_SIZE as u64).is_some_and(|end| end <= runtime_len) =>
+        {
+            Some(offset)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+/// Minimal ELF64 with a string table and one named section
+pub(crate) fn fake_elf(section_name: &str, contents: &[u8]) -> Vec<u8> {
+    let strtab = format!("\0.shstrtab\0{}\0", section_name).into_bytes();
+    let strtab_offset = 64u64;
+    let contents_offset = strtab_offset + strtab.len() as u64;
+    let sh_offset = contents_offset + contents.len() as u64;
+
+    let mut elf = vec![0u8; 64];
+    elf[0..4].copy_from_slice(b"\x7fELF");
+    elf[4] = 2;
+    elf[5] = 1;
+    elf[0x28..0x30].copy_from_slice(&sh_offset.to_le_bytes());
+    elf[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+    elf[0x3C..0x3E].copy_from_slice(&3u16.to_le_bytes());
+    elf[0x3E..0x40].copy_from_slice(&1u16.to_le_bytes());
+    elf.extend_from_slice(&strtab);
+    elf.extend_from_slice(contents);
+
+    let mut header = |name: u32, kind: u32, offset: u64, size: u64| {
+        let mut entry = [0u8; 64];
+        entry[0..4].copy_from_slice(&name.to_le_bytes());
+        entry[4..8].copy_from_slice(&kind.to_le_bytes());
+        entry[0x18..0x20].copy_from_slice(&offset.to_le_bytes());
+        entry[0x20..0x28].copy_from_slice(&size.to_le_bytes());
+        elf.extend_from_slice(&entry);
+    };
+    header(0, 0, 0, 0);
+    header(1, 3, strtab_offset, strtab.len() as u64);
+    header(11, 1, contents_offset, contents.len() as u64);
+    elf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stub_section_roundtrip() {
+        let elf = fake_elf(META_STUB_SECTION, &EMPTY_META_STUB);
+        let offset = stub_offset(&elf).unwrap();
+        assert_eq!(elf[offset as usize..][..META_STUB_SIZE], EMPTY_META_STUB);
+        assert!(MetaStub::from_bytes(&EMPTY_META_STUB).is_err());
+        assert_eq!(stub_offset(&fake_elf(".data", &EMPTY_META_STUB)), None);
+
+        // A section pointing past the end of the runtime is ignored
+        let mut elf = fake_elf(META_STUB_SECTION, &EMPTY_META_STUB);
+        let sh_offset = u64::from_le_bytes(elf[0x28..0x30].try_into().unwrap()) as usize;
+        let end = elf.len() as u64;
+        elf[sh_offset + 2 * 64 + 0x18..][..8].copy_from_slice(&end.to_le_bytes());
+        assert_eq!(stub_offset(&elf), None);
+
+        let stub = MetaStub {
+            header_offset: 1000,
+            metadata_offset: 1012,
+            metadata_len: 300,
+            payload_offset: 1344,
+            payload_size: 5000,
+        };
+        assert_eq!(MetaStub::from_bytes(&stub.to_bytes()).unwrap(), stub);
+    }
+}
@@ -0,0 +1,123 @@
+//! Hardware Token Keys (PKCS#11)
+//!
+//! Ed25519 keys that never leave a PKCS#11 device (YubiKey, smart cards, or
+//! SoftHSM for testing). `lxe key generate --backend pkcs11` creates the key
+//! pair on the token, labeled [`crate::signing::PKCS11_KEY_LABEL`]; signing
+//! calls `C_Sign` with `CKM_EDDSA`, so the signatures verify exactly like
+//! those of file keys.
+//!
+//! Only compiled with the `pkcs11` feature.
+
+use anyhow::{bail, Context, Result};
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::mechanism::eddsa::{EddsaParams, EddsaSignatureScheme};
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::slot::Slot;
+use cryptoki::types::AuthPin;
+use crate::signing::PKCS11_KEY_LABEL as KEY_LABEL;
+use std::path::Path;
+
+/// DER-encoded OID 1.3.101.112 (Ed25519), the `CKA_EC_PARAMS` of the key
+const ED25519_OID: [u8; 5] = [0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// Generate an Ed25519 key pair on the token in `slot`; returns the raw
+/// 32-byte public key
+pub fn generate(module: &Path, slot: u64, pin: Option<&str>) -> Result<Vec<u8>> {
+    let session = open_session(module, slot, pin)?;
+    if find_key(&session, ObjectClass::PRIVATE_KEY)?.is_some() {
+        bail!("Slot {} already holds an LXE signing key (label '{}')", slot, KEY_LABEL);
+    }
+
+    let label = Attribute::Label(KEY_LABEL.as_bytes().to_vec());
+    let public_template = [
+        Attribute::Token(true),
+        Attribute::Verify(true),
+        Attribute::EcParams(ED25519_OID.to_vec()),
+        label.clone(),
+    ];
+    let private_template = [
+        Attribute::Token(true),
+        Attribute::Private(true),
+        Attribute::Sensitive(true),
+        Attribute::Sign(true),
+        label,
+    ];
+    let (public, _) = session
+        .generate_key_pair(&Mechanism::EccEdwardsKeyPairGen, &public_template, &private_template)
+        .context("The token could not generate an Ed25519 key pair")?;
+    read_public_key(&session, public)
+}
+
+/// Sign `data` on the token (`CKM_EDDSA`, pure Ed25519); returns the raw
+/// 64-byte signature and the raw public key of the pair, read in the same
+/// session
+pub fn sign(module: &Path, slot: u64, pin: Option<&str>, data: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let session = open_session(module, slot, pin)?;
+    let public = find_key(&session, ObjectClass::PUBLIC_KEY)?
+        .with_context(|| format!("No LXE public key (label '{}') in slot {}", KEY_LABEL, slot))?;
+    let public_key = read_public_key(&session, public)?;
+    let private = find_key(&session, ObjectClass::PRIVATE_KEY)?
+        .with_context(|| format!("No LXE private key (label '{}') in slot {} - is the PIN set?", KEY_LABEL, slot))?;
+    let mechanism = Mechanism::Eddsa(EddsaParams::new(EddsaSignatureScheme::Pure));
+    let signature = session.sign(&mechanism, private, data)
+        .context("The token refused to sign")?;
+    Ok((signature, public_key))
+}
+
+fn open_session(module: &Path, slot: u64, pin: Option<&str>) -> Result<Session> {
+    let pkcs11 = Pkcs11::new(module)
+        .with_context(|| format!("Failed to load PKCS#11 module {}", module.display()))?;
+    pkcs11.initialize(CInitializeArgs::OsThreads)
+        .context("Failed to initialize the PKCS#11 module")?;
+    let session = pkcs11.open_rw_session(Slot::try_from(slot)?)
+        .with_context(|| format!("Failed to open PKCS#11 slot {} (is the token plugged in?)", slot))?;
+    if let Some(pin) = pin {
+        session.login(UserType::User, Some(&AuthPin::new(pin.to_string())))
+            .context("PKCS#11 login failed (wrong PIN?)")?;
+    }
+    Ok(session)
+}
+
+fn find_key(session: &Session, class: ObjectClass) -> Result<Option<ObjectHandle>> {
+    let template = [
+        Attribute::Class(class),
+        Attribute::KeyType(KeyType::EC_EDWARDS),
+        Attribute::Label(KEY_LABEL.as_bytes().to_vec()),
+    ];
+    Ok(session.find_objects(&template)?.into_iter().next())
+}
+
+fn read_public_key(session: &Session, public: ObjectHandle) -> Result<Vec<u8>> {
+    let attributes = session.get_attributes(public, &[AttributeType::EcPoint])?;
+    let Some(Attribute::EcPoint(point)) = attributes.into_iter().next() else {
+        bail!("The token did not return the public key");
+    };
+    parse_ec_point(&point)
+}
+
+/// `CKA_EC_POINT` of an Ed25519 key: a DER OCTET STRING holding the 32-byte
+/// key, or (on some tokens) the bare key
+fn parse_ec_point(point: &[u8]) -> Result<Vec<u8>> {
+    match point {
+        [0x04, 0x20, key @ ..] if key.len() == 32 => Ok(key.to_vec()),
+        key if key.len() == 32 => Ok(key.to_vec()),
+        _ => bail!("Unexpected Ed25519 public key encoding ({} bytes)", point.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ec_point() {
+        let key = [7u8; 32];
+        let mut der = vec![0x04, 0x20];
+        der.extend_from_slice(&key);
+        assert_eq!(parse_ec_point(&der).unwrap(), key);
+        assert_eq!(parse_ec_point(&key).unwrap(), key);
+        assert!(parse_ec_point(&der[..20]).is_err());
+    }
+}
@@ -0,0 +1,99 @@
+//! Install Progress Events
+//!
+//! `lxe-runtime --silent --progress-socket <path>` reports its progress as
+//! newline-delimited JSON over a Unix domain socket, so scripts and custom
+//! installer frameworks can follow a headless install:
+//!
+//! ```text
+//! {"type":"start","name":"MyApp","total_bytes":104857600}
+//! {"type":"progress","files":42,"bytes":10485760}
+//! {"type":"complete"}
+//! ```
+//!
+//! The reader (e.g. `lxe-progress-reader`) listens on the socket; the runtime
+//! connects to it, so no event is lost to a reader that attaches late.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// How long the runtime waits for the reader to create the socket
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One line on the progress socket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ProgressEvent {
+    /// Installation started; `total_bytes` is the uncompressed payload size
+    Start { name: String, total_bytes: u64 },
+    /// Files and bytes extracted so far
+    Progress { files: u32, bytes: u64 },
+    Complete,
+    Error { message: String },
+}
+
+/// Writing end of a progress socket
+pub struct ProgressSocket {
+    stream: UnixStream,
+}
+
+impl ProgressSocket {
+    /// Connect to the reader listening at `path`, waiting up to
+    /// [`CONNECT_TIMEOUT`] for it to start
+    pub fn connect(path: &Path) -> Result<Self> {
+        let started = Instant::now();
+        loop {
+            match UnixStream::connect(path) {
+                Ok(stream) => return Ok(Self { stream }),
+                Err(_) if started.elapsed() < CONNECT_TIMEOUT => std::thread::sleep(Duration::from_millis(100)),
+                Err(e) => return Err(e).with_context(|| format!(
+                    "Failed to connect to progress socket {} (is a reader listening?)", path.display()
+                )),
+            }
+        }
+    }
+
+    /// Send one event; a reader that went away doesn't fail the install
+    pub fn send(&mut self, event: &ProgressEvent) {
+        let Ok(mut line) = serde_json::to_string(event) else {
+            return;
+        };
+        line.push('\n');
+        if let Err(e) = self.stream.write_all(line.as_bytes()) {
+            tracing::debug!("Progress socket write failed: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::os::unix::net::UnixListener;
+
+    #[test]
+    fn test_progress_socket() {
+        let path = std::env::temp_dir().join(format!("lxe-progress-test-{}.sock", std::process::id()));
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let mut socket = ProgressSocket::connect(&path).unwrap();
+        socket.send(&ProgressEvent::Start { name: "MyApp".into(), total_bytes: 100 });
+        socket.send(&ProgressEvent::Progress { files: 42, bytes: 10 });
+        socket.send(&ProgressEvent::Complete);
+        drop(socket);
+
+        let (stream, _) = listener.accept().unwrap();
+        let lines: Vec<String> = BufReader::new(stream).lines().map(Result::unwrap).collect();
+        assert_eq!(lines, [
+            r#"{"type":"start","name":"MyApp","total_bytes":100}"#,
+            r#"{"type":"progress","files":42,"bytes":10}"#,
+            r#"{"type":"complete"}"#,
+        ]);
+        let error: ProgressEvent = serde_json::from_str(r#"{"type":"error","message":"disk full"}"#).unwrap();
+        assert_eq!(error, ProgressEvent::Error { message: "disk full".into() });
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+//! System Requirement Checks
+//!
+//! Packages can declare a minimum glibc and kernel version. These are
+//! checked before installation so users on older distros get a clear
+//! message instead of a binary that fails to start.
+//!
+//! Declared dependencies (commands, shared libraries, other LXE apps) are
+//! only warned about, since most packages bundle their own libraries.
+
+use crate::error::LxeError;
+use crate::manifest::InstallManifest;
+use crate::metadata::{DepKind, LxeDependency, LxeMetadata};
+use semver::{Version, VersionReq};
+
+/// Check the running system against the package's declared minimums
+pub fn check_system_requirements(metadata: &LxeMetadata) -> Result<(), LxeError> {
+    if let Some(ref required) = metadata.min_glibc_version {
+        // Non-glibc systems (musl) can't run glibc binaries anyway
+        let found = glibc_version().unwrap_or_else(|| "none".to_string());
+        if !version_satisfies(required, &found) {
+            return Err(LxeError::SystemRequirementNotMet {
+                requirement: format!("glibc >= {}", required),
+                found: format!("glibc {}", found),
+            });
+        }
+    }
+    
+    if let Some(ref required) = metadata.min_kernel_version {
+        // If the kernel version can't be read, don't block the install
+        if let Some(found) = kernel_version() {
+            if !version_satisfies(required, &found) {
+                return Err(LxeError::SystemRequirementNotMet {
+                    requirement: format!("Linux kernel >= {}", required),
+                    found: format!("Linux {}", found),
+                });
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// A declared dependency that isn't satisfied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyWarning {
+    pub dependency: LxeDependency,
+    
+    /// What is wrong, e.g. "not installed"
+    pub problem: String,
+}
+
+impl std::fmt::Display for DependencyWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.dependency.kind {
+            DepKind::System => "command",
+            DepKind::LxePackage => "LXE app",
+            DepKind::SharedLib => "shared library",
+        };
+        write!(f, "{} ({}): {}", self.dependency, kind, self.problem)
+    }
+}
+
+/// Check the package's dependencies against this system
+/// 
+/// Shared libraries are looked up in `ldconfig -p`; if ldconfig can't be
+/// run, they are not checked.
+pub fn check_dependencies(metadata: &LxeMetadata) -> Vec<DependencyWarning> {
+    let mut libraries: Option<Option<Vec<String>>> = None;
+    let mut warnings = Vec::new();
+    
+    for dependency in &metadata.dependencies {
+        let problem = match dependency.kind {
+            DepKind::System => {
+                let found = std::env::var_os("PATH")
+                    .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(&dependency.id).is_file()));
+                (!found).then(|| "not found in PATH".to_string())
+            }
+            DepKind::SharedLib => {
+                libraries.get_or_insert_with(ldconfig_libraries)
+                    .as_ref()
+                    .filter(|libs| !libs.iter().any(|lib| library_matches(lib, &dependency.id)))
+                    .map(|_| "not found by ldconfig".to_string())
+            }
+            DepKind::LxePackage => match InstallManifest::load(&dependency.id).ok().flatten() {
+                None => Some("not installed".to_string()),
+                Some(installed) => dependency.version_req.as_deref()
+                    .and_then(|req| VersionReq::parse(req).ok())
+                    .filter(|req| !normalize_version(&installed.version).is_some_and(|v| req.matches(&v)))
+                    .map(|_| format!("version {} is installed", installed.version)),
+            },
+        };
+        if let Some(problem) = problem {
+            warnings.push(DependencyWarning { dependency: dependency.clone(), problem });
+        }
+    }
+    warnings
+}
+
+/// Library names in the linker cache (`ldconfig -p`)
+fn ldconfig_libraries() -> Option<Vec<String>> {
+    let output = ["ldconfig", "/sbin/ldconfig", "/usr/sbin/ldconfig"].iter()
+        .find_map(|cmd| std::process::Command::new(cmd).arg("-p").output().ok())
+        .filter(|output| output.status.success())?;
+    Some(parse_ldconfig(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// `libGL.so.1 (libc6,x86-64) => /usr/lib/libGL.so.1` lines to names
+fn parse_ldconfig(output: &str) -> Vec<String> {
+    output.lines()
+        .filter(|line| line.contains("=>"))
+        .filter_map(|line| line.split_whitespace().next())
+        .map(String::from)
+        .collect()
+}
+
+/// Whether the library `name` is `id`, with or without (part of) the
+/// version suffix: `libGL.so.1` matches `libGL`, `libGL.so` and `libGL.so.1`
+fn library_matches(name: &str, id: &str) -> bool {
+    name.strip_prefix(id).is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Get the running glibc version (e.g., "2.35")
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+pub fn glibc_version() -> Option<String> {
+    // SAFETY: gnu_get_libc_version returns a pointer to a static,
+    // NUL-terminated string owned by glibc
+    let ptr = unsafe { libc::gnu_get_libc_version() };
+    if ptr.is_null() {
+        return None;
+    }
+    let version = unsafe { std::ffi::CStr::from_ptr(ptr) };
+    version.to_str().ok().map(String::from)
+}
+
+/// Get the running glibc version (not available on this target)
+#[cfg(not(all(target_os = "linux", target_env = "gnu")))]
+pub fn glibc_version() -> Option<String> {
+    None
+}
+
+/// Get the running kernel release via uname(2) (e.g., "6.5.0-14-generic")
+pub fn kernel_version() -> Option<String> {
+    // SAFETY: utsname is plain old data; uname fills it on success
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return None;
+    }
+    let release = unsafe { std::ffi::CStr::from_ptr(uts.release.as_ptr()) };
+    release.to_str().ok().map(String::from)
+}
+
+/// Check whether `found` is at least `required`
+/// 
+/// Both sides are loose versions ("2.35", "6.5.0-14-generic"); they are
+/// normalized to MAJOR.MINOR.PATCH before comparing with semver.
+pub fn version_satisfies(required: &str, found: &str) -> bool {
+    let (Some(required), Some(found)) = (normalize_version(required), normalize_version(found)) else {
+        return false;
+    };
+    
+    VersionReq::parse(&format!(">={}", required))
+        .map(|req| req.matches(&found))
+        .unwrap_or(false)
+}
+
+/// Turn "2.35" or "6.5.0-14-generic" into a semver Version
+pub fn normalize_version(version: &str) -> Option<Version> {
+    let mut parts = version.trim()
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?
+        .split('.')
+        .filter(|p| !p.is_empty())
+        .map(|p| p.parse::<u64>().ok());
+    
+    let major = parts.next()??;
+    let minor = parts.next().unwrap_or(Some(0))?;
+    let patch = parts.next().unwrap_or(Some(0))?;
+    Some(Version::new(major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_version_satisfies() {
+        assert!(version_satisfies("2.35", "2.35"));
+        assert!(version_satisfies("2.35", "2.39"));
+        assert!(!version_satisfies("2.35", "2.31"));
+        assert!(version_satisfies("5.10", "6.5.0-14-generic"));
+        assert!(!version_satisfies("6.1", "5.15.0-91-generic"));
+        assert!(!version_satisfies("2.35", "none"));
+    }
+    
+    #[test]
+    fn test_no_requirements_passes() {
+        let meta = LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 0, "");
+        assert!(check_system_requirements(&meta).is_ok());
+        assert!(check_dependencies(&meta).is_empty());
+    }
+    
+    #[test]
+    fn test_dependencies() {
+        let libs = parse_ldconfig("1234 libs found in cache `/etc/ld.so.cache'\n\
+            \tlibGL.so.1 (libc6,x86-64) => /usr/lib/x86_64-linux-gnu/libGL.so.1\n");
+        assert_eq!(libs, ["libGL.so.1"]);
+        assert!(library_matches("libGL.so.1", "libGL"));
+        assert!(library_matches("libGL.so.1", "libGL.so"));
+        assert!(!library_matches("libGLX.so.0", "libGL"));
+        
+        let mut meta = LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 0, "");
+        meta.dependencies = vec![
+            LxeDependency { kind: DepKind::System, id: "sh".into(), version_req: None },
+            LxeDependency { kind: DepKind::System, id: "lxe-no-such-command".into(), version_req: None },
+            LxeDependency { kind: DepKind::LxePackage, id: "com.example.NotInstalled".into(), version_req: Some(">=2.0".into()) },
+        ];
+        let warnings = check_dependencies(&meta);
+        let ids: Vec<&str> = warnings.iter().map(|w| w.dependency.id.as_str()).collect();
+        assert_eq!(ids, ["lxe-no-such-command", "com.example.NotInstalled"]);
+        assert_eq!(warnings[1].to_string(), "com.example.NotInstalled >=2.0 (LXE app): not installed");
+    }
+}
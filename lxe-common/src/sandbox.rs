@@ -0,0 +1,475 @@
+//! Sandbox Profiles for `lxe run`
+//!
+//! Apps are confined with bubblewrap (`bwrap`). Rather than spelling out
+//! bwrap arguments, users pick a named profile: the built-in `minimal`,
+//! `desktop` and `trusted`, or their own from
+//! `~/.config/lxe/sandbox-profiles.toml`:
+//!
+//! ```toml
+//! [browser]
+//! description = "Network and a writable Downloads folder"
+//! network = true
+//! display = true
+//! home = "none"
+//! bind = ["~/Downloads"]
+//! ```
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// How much of `$HOME` the sandboxed app can see
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HomeAccess {
+    /// An empty, throwaway home directory
+    #[default]
+    None,
+    ReadOnly,
+    ReadWrite,
+}
+
+impl std::fmt::Display for HomeAccess {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HomeAccess::None => "none",
+            HomeAccess::ReadOnly => "read-only",
+            HomeAccess::ReadWrite => "read-write",
+        })
+    }
+}
+
+/// A named set of sandbox permissions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SandboxProfile {
+    #[serde(default)]
+    pub description: String,
+
+    /// Share the host network
+    #[serde(default)]
+    pub network: bool,
+
+    #[serde(default)]
+    pub home: HomeAccess,
+
+    /// X11 and Wayland display sockets
+    #[serde(default)]
+    pub display: bool,
+
+    /// Session and system D-Bus
+    #[serde(default)]
+    pub dbus: bool,
+
+    /// Extra paths mounted read-write (`~/` is expanded)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bind: Vec<String>,
+
+    /// Extra paths mounted read-only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bind_ro: Vec<String>,
+
+    /// Environment variables set inside the sandbox
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+
+    /// Shipped with LXE rather than read from the profiles file
+    #[serde(skip)]
+    pub builtin: bool,
+}
+
+impl SandboxProfile {
+    /// Add this profile's permissions to `builder`
+    pub fn apply(&self, mut builder: BwrapBuilder) -> BwrapBuilder {
+        if self.network {
+            builder = builder.allow_network();
+        }
+        builder = match self.home {
+            HomeAccess::None => builder,
+            HomeAccess::ReadOnly => builder.allow_home_read(),
+            HomeAccess::ReadWrite => builder.allow_home_write(),
+        };
+        if self.display {
+            builder = builder.allow_display();
+        }
+        if self.dbus {
+            builder = builder.allow_dbus();
+        }
+        for path in &self.bind {
+            builder = builder.bind_path(expand_home(path), true);
+        }
+        for path in &self.bind_ro {
+            builder = builder.bind_path(expand_home(path), false);
+        }
+        for (key, value) in &self.env {
+            builder = builder.add_env(key, value);
+        }
+        builder
+    }
+}
+
+/// The profiles shipped with LXE
+pub fn builtin_profiles() -> BTreeMap<String, SandboxProfile> {
+    let minimal = SandboxProfile {
+        description: "No network, no home directory".into(),
+        ..Default::default()
+    };
+    let desktop = SandboxProfile {
+        description: "Display, network, read-only home".into(),
+        network: true,
+        home: HomeAccess::ReadOnly,
+        display: true,
+        ..Default::default()
+    };
+    let trusted = SandboxProfile {
+        description: "Display, network, D-Bus, full home access".into(),
+        network: true,
+        home: HomeAccess::ReadWrite,
+        display: true,
+        dbus: true,
+        ..Default::default()
+    };
+
+    [("minimal", minimal), ("desktop", desktop), ("trusted", trusted)]
+        .into_iter()
+        .map(|(name, profile)| (name.to_string(), SandboxProfile { builtin: true, ..profile }))
+        .collect()
+}
+
+/// Built-in profiles plus the user's, which override built-ins of the same name
+pub fn load_profiles() -> Result<BTreeMap<String, SandboxProfile>> {
+    let mut profiles = builtin_profiles();
+
+    if let Some(path) = crate::paths::lxe::sandbox_profiles_path() {
+        if path.exists() {
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let custom: BTreeMap<String, SandboxProfile> = toml::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            profiles.extend(custom);
+        }
+    }
+
+    Ok(profiles)
+}
+
+/// Look up a profile by name
+pub fn find_profile(name: &str) -> Result<SandboxProfile> {
+    let mut profiles = load_profiles()?;
+    profiles.remove(name).ok_or_else(|| {
+        let names: Vec<&str> = profiles.keys().map(|k| k.as_str()).collect();
+        anyhow::anyhow!("Unknown sandbox profile '{}' (available: {})", name, names.join(", "))
+    })
+}
+
+/// Whether `bwrap` is installed
+pub fn bwrap_available() -> bool {
+    Command::new("which")
+        .arg("bwrap")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Host directories every sandbox sees read-only (besides `/usr`); on
+/// merged-`/usr` systems these are symlinks and are recreated as such
+const SYSTEM_DIRS: &[&str] = &["/bin", "/sbin", "/lib", "/lib32", "/lib64"];
+
+/// The parts of `/etc` programs need to load libraries, fonts and certificates
+const ETC_PATHS: &[&str] = &[
+    "/etc/ld.so.cache",
+    "/etc/ld.so.conf",
+    "/etc/ld.so.conf.d",
+    "/etc/alternatives",
+    "/etc/passwd",
+    "/etc/group",
+    "/etc/nsswitch.conf",
+    "/etc/localtime",
+    "/etc/locale.conf",
+    "/etc/machine-id",
+    "/etc/fonts",
+    "/etc/xdg",
+    "/etc/gtk-3.0",
+    "/etc/ssl",
+    "/etc/pki",
+    "/etc/ca-certificates",
+];
+
+/// Name resolution, only mounted with network access
+const ETC_NETWORK_PATHS: &[&str] = &["/etc/resolv.conf", "/etc/hosts", "/etc/host.conf", "/etc/gai.conf"];
+
+/// The system bus socket
+const SYSTEM_BUS: &str = "/run/dbus";
+
+/// Builds a `bwrap` command line
+///
+/// The sandbox starts from an empty root with a read-only `/usr` and a
+/// few files of `/etc`, private `/tmp`, `/dev`, `/proc` and runtime
+/// directory, an empty home, no D-Bus and no network; each `allow_*` call
+/// opens one of those back up, and `bind_path` adds host paths (the app
+/// directory among them).
+#[derive(Debug, Clone)]
+pub struct BwrapBuilder {
+    program: PathBuf,
+    args: Vec<OsString>,
+    network: bool,
+    home: HomeAccess,
+    display: bool,
+    dbus: bool,
+    binds: Vec<(PathBuf, bool)>,
+    env: Vec<(String, String)>,
+}
+
+impl BwrapBuilder {
+    /// Sandbox for running `program`
+    pub fn new(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            network: false,
+            home: HomeAccess::None,
+            display: false,
+            dbus: false,
+            binds: Vec::new(),
+            env: Vec::new(),
+        }
+    }
+
+    /// Arguments passed to the program
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<OsString>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn allow_network(mut self) -> Self {
+        self.network = true;
+        self
+    }
+
+    pub fn allow_home_read(mut self) -> Self {
+        if self.home == HomeAccess::None {
+            self.home = HomeAccess::ReadOnly;
+        }
+        self
+    }
+
+    pub fn allow_home_write(mut self) -> Self {
+        self.home = HomeAccess::ReadWrite;
+        self
+    }
+
+    pub fn allow_display(mut self) -> Self {
+        self.display = true;
+        self
+    }
+
+    pub fn allow_dbus(mut self) -> Self {
+        self.dbus = true;
+        self
+    }
+
+    /// Mount a host path at the same location; missing paths are skipped
+    pub fn bind_path(mut self, path: impl Into<PathBuf>, writable: bool) -> Self {
+        self.binds.push((path.into(), writable));
+        self
+    }
+
+    pub fn add_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// The full `bwrap` argument list, ending with the program and its arguments
+    pub fn build_args(&self) -> Vec<OsString> {
+        let mut out = Args::default();
+
+        out.push(["--unshare-user-try", "--unshare-ipc", "--unshare-pid", "--unshare-uts", "--unshare-cgroup-try"]);
+        if !self.network {
+            out.push(["--unshare-net"]);
+        }
+        out.push(["--die-with-parent", "--new-session"]);
+
+        out.bind("--ro-bind", Path::new("/usr"));
+        for dir in SYSTEM_DIRS.iter().map(Path::new) {
+            match std::fs::read_link(dir) {
+                Ok(target) => {
+                    out.0.push("--symlink".into());
+                    out.0.push(target.into());
+                    out.0.push(dir.into());
+                }
+                Err(_) => out.bind("--ro-bind-try", dir),
+            }
+        }
+        for path in ETC_PATHS {
+            out.bind("--ro-bind-try", Path::new(path));
+        }
+        if self.network {
+            for path in ETC_NETWORK_PATHS {
+                out.bind("--ro-bind-try", Path::new(path));
+            }
+        }
+        out.push(["--dev", "/dev", "--proc", "/proc", "--tmpfs", "/tmp"]);
+
+        // Later mounts are layered on top of earlier ones, so hide the
+        // runtime directory and home before binding anything inside them
+        let runtime_dir = std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from);
+        if let Some(ref dir) = runtime_dir {
+            out.mount("--tmpfs", dir);
+        }
+
+        if let Some(home) = dirs::home_dir() {
+            match self.home {
+                HomeAccess::None => out.mount("--tmpfs", &home),
+                HomeAccess::ReadOnly => out.bind("--ro-bind", &home),
+                HomeAccess::ReadWrite => out.bind("--bind", &home),
+            }
+        }
+
+        if self.display {
+            out.bind("--ro-bind-try", Path::new("/tmp/.X11-unix"));
+            if let Some(xauthority) = std::env::var_os("XAUTHORITY") {
+                out.bind("--ro-bind-try", Path::new(&xauthority));
+            }
+            if let (Some(dir), Some(socket)) = (&runtime_dir, std::env::var_os("WAYLAND_DISPLAY")) {
+                out.bind("--bind-try", &dir.join(socket));
+            }
+        } else {
+            out.push(["--unsetenv", "DISPLAY", "--unsetenv", "WAYLAND_DISPLAY", "--unsetenv", "XAUTHORITY"]);
+        }
+
+        let session_bus = runtime_dir.as_ref().map(|dir| dir.join("bus"));
+        if self.dbus {
+            if let Some(ref bus) = session_bus {
+                out.bind("--bind-try", bus);
+            }
+            out.bind("--bind-try", Path::new(SYSTEM_BUS));
+        } else {
+            out.push(["--unsetenv", "DBUS_SESSION_BUS_ADDRESS"]);
+        }
+
+        for (path, writable) in &self.binds {
+            out.bind(if *writable { "--bind-try" } else { "--ro-bind-try" }, path);
+        }
+
+        // A bound path (say `/run` or the runtime directory) must not bring
+        // the buses back
+        if !self.dbus {
+            let exposed = |socket: &Path| socket.exists() && self.binds.iter().any(|(path, _)| socket.starts_with(path));
+            if let Some(bus) = session_bus.filter(|bus| exposed(bus)) {
+                out.0.push("--ro-bind".into());
+                out.0.push("/dev/null".into());
+                out.0.push(bus.into());
+            }
+            if exposed(Path::new(SYSTEM_BUS)) {
+                out.mount("--tmpfs", Path::new(SYSTEM_BUS));
+            }
+        }
+
+        for (key, value) in &self.env {
+            out.push(["--setenv", key.as_str(), value.as_str()]);
+        }
+
+        out.push(["--"]);
+        out.0.push(self.program.clone().into_os_string());
+        out.0.extend(self.args.iter().cloned());
+        out.0
+    }
+
+    /// The `bwrap` command, ready to spawn
+    pub fn build(&self) -> Command {
+        let mut command = Command::new("bwrap");
+        command.args(self.build_args());
+        command
+    }
+}
+
+/// Argument accumulator for `BwrapBuilder::build_args`
+#[derive(Default)]
+struct Args(Vec<OsString>);
+
+impl Args {
+    fn push<const N: usize>(&mut self, args: [&str; N]) {
+        self.0.extend(args.iter().map(OsString::from));
+    }
+
+    fn mount(&mut self, flag: &str, path: &Path) {
+        self.0.push(flag.into());
+        self.0.push(path.into());
+    }
+
+    fn bind(&mut self, flag: &str, path: &Path) {
+        self.0.push(flag.into());
+        self.0.push(path.into());
+        self.0.push(path.into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn has_pair(args: &[OsString], flag: &str, value: &str) -> bool {
+        args.windows(2).any(|w| w[0] == flag && w[1] == value)
+    }
+
+    #[test]
+    fn test_builtin_profiles() {
+        let profiles = builtin_profiles();
+        let minimal = BwrapBuilder::new("/opt/app/run");
+        let minimal = profiles["minimal"].apply(minimal).build_args();
+        assert!(minimal.contains(&"--unshare-net".into()));
+        assert!(has_pair(&minimal, "--ro-bind", "/usr"));
+        assert!(!has_pair(&minimal, "--ro-bind", "/"));
+        assert!(!has_pair(&minimal, "--ro-bind-try", "/etc/resolv.conf"));
+        assert!(!has_pair(&minimal, "--bind-try", SYSTEM_BUS));
+        assert_eq!(minimal.last().unwrap(), "/opt/app/run");
+
+        let desktop = profiles["desktop"].apply(BwrapBuilder::new("app")).build_args();
+        assert!(!desktop.contains(&"--unshare-net".into()));
+        assert!(has_pair(&desktop, "--ro-bind-try", "/etc/resolv.conf"));
+        if let Some(home) = dirs::home_dir() {
+            assert!(has_pair(&desktop, "--ro-bind", &home.to_string_lossy()));
+        }
+    }
+
+    #[test]
+    fn test_custom_profile_parsing() {
+        let profiles: BTreeMap<String, SandboxProfile> = toml::from_str(r#"
+            [browser]
+            network = true
+            home = "read-only"
+            bind = ["/srv/downloads"]
+            env = { MOZ_ENABLE_WAYLAND = "1" }
+        "#).unwrap();
+
+        let args = profiles["browser"].apply(BwrapBuilder::new("app")).build_args();
+        assert!(has_pair(&args, "--bind-try", "/srv/downloads"));
+        assert!(args.windows(3).any(|w| w[0] == "--setenv" && w[1] == "MOZ_ENABLE_WAYLAND" && w[2] == "1"));
+    }
+
+    #[test]
+    fn test_binds_dont_expose_dbus() {
+        let args = BwrapBuilder::new("app").bind_path("/run", false).build_args();
+        if Path::new(SYSTEM_BUS).exists() {
+            assert!(has_pair(&args, "--tmpfs", SYSTEM_BUS));
+        }
+        let args = BwrapBuilder::new("app").bind_path("/run", false).allow_dbus().build_args();
+        assert!(!has_pair(&args, "--tmpfs", SYSTEM_BUS));
+        assert!(has_pair(&args, "--bind-try", SYSTEM_BUS));
+    }
+}
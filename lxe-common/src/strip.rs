@@ -0,0 +1,241 @@
+//! Symbol Stripping for `lxe build --strip-symbols`
+//!
+//! ELF files in the input directory are stripped into a staging directory
+//! and swapped in while the payload is archived, so the project's own build
+//! output is never modified.
+//!
+//! Only available with the `build` feature.
+
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// How to strip
+#[derive(Debug, Clone)]
+pub struct StripOptions {
+    /// `strip` binary, e.g. `aarch64-linux-gnu-strip` when cross-compiling
+    pub tool: PathBuf,
+
+    /// Also strip shared libraries (can break apps that `dlsym` their own symbols)
+    pub include_shared: bool,
+}
+
+impl Default for StripOptions {
+    fn default() -> Self {
+        Self {
+            tool: PathBuf::from("strip"),
+            include_shared: false,
+        }
+    }
+}
+
+/// Stripped copies of the input's ELF files
+pub struct StrippedFiles {
+    /// Archive path (relative to the input dir) → stripped copy
+    pub overrides: BTreeMap<PathBuf, PathBuf>,
+
+    /// Total size of the stripped files before stripping
+    pub original_size: u64,
+
+    /// Total size after stripping
+    pub stripped_size: u64,
+
+    // Holds the stripped copies until the archive is written
+    _staging: tempfile::TempDir,
+}
+
+impl StrippedFiles {
+    pub fn saved_bytes(&self) -> u64 {
+        self.original_size.saturating_sub(self.stripped_size)
+    }
+}
+
+/// Strip every unstripped ELF file under `input_dir`
+pub fn strip_elf_files(input_dir: &Path, options: &StripOptions) -> Result<StrippedFiles> {
+    let staging = tempfile::Builder::new()
+        .prefix("lxe-strip-")
+        .tempdir()
+        .context("Failed to create staging directory")?;
+
+    let mut stripped = StrippedFiles {
+        overrides: BTreeMap::new(),
+        original_size: 0,
+        stripped_size: 0,
+        _staging: staging,
+    };
+
+    let mut files = Vec::new();
+    collect_files(input_dir, &mut files)?;
+
+    for path in files {
+        if !options.include_shared && is_shared_library(&path) {
+            continue;
+        }
+        if !matches!(has_symbols(&path), Ok(true)) {
+            continue;
+        }
+
+        let rel = path.strip_prefix(input_dir)?.to_path_buf();
+        let dest = stripped._staging.path().join(&rel);
+        fs::create_dir_all(dest.parent().unwrap_or(stripped._staging.path()))?;
+
+        let output = Command::new(&options.tool)
+            .arg("--strip-all")
+            .arg("-o")
+            .arg(&dest)
+            .arg(&path)
+            .output()
+            .with_context(|| format!("Failed to run {}", options.tool.display()))?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "{} failed on {}: {}",
+                options.tool.display(),
+                rel.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        // strip -o doesn't reliably keep the mode
+        fs::set_permissions(&dest, fs::metadata(&path)?.permissions())?;
+
+        stripped.original_size += fs::metadata(&path)?.len();
+        stripped.stripped_size += fs::metadata(&dest)?.len();
+        stripped.overrides.insert(rel, dest);
+    }
+
+    Ok(stripped)
+}
+
+/// Regular files under `dir`, not following symlinks
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), out)?;
+        } else if file_type.is_file() {
+            out.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// `libfoo.so`, `libfoo.so.1.2`
+fn is_shared_library(path: &Path) -> bool {
+    path.file_name()
+        .map(|n| n.to_string_lossy().split('.').skip(1).any(|part| part == "so"))
+        .unwrap_or(false)
+}
+
+/// Whether `path` is an ELF file that still has a symbol table or debug sections
+///
+/// Non-ELF files return `Ok(false)`.
+pub fn has_symbols(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file = fs::File::open(path)?;
+    if file.read_exact(&mut magic).is_err() || magic != *b"\x7fELF" {
+        return Ok(false);
+    }
+
+    let data = fs::read(path)?;
+    Ok(section_names(&data)?
+        .iter()
+        .any(|name| name == ".symtab" || name.starts_with(".debug")))
+}
+
+/// Section names from the ELF section header table
+fn section_names(data: &[u8]) -> Result<Vec<String>> {
+    let malformed = || anyhow::anyhow!("Malformed ELF file");
+
+    let is_64 = match data.get(4) {
+        Some(1) => false,
+        Some(2) => true,
+        _ => return Err(malformed()),
+    };
+    let little_endian = data.get(5) == Some(&1);
+
+    // Every offset comes from the file, so all arithmetic is checked
+    let read = |offset: u64, size: usize| -> Result<u64> {
+        let start = usize::try_from(offset).map_err(|_| malformed())?;
+        let bytes = data.get(start..start.checked_add(size).ok_or_else(malformed)?).ok_or_else(malformed)?;
+        let mut buf = [0u8; 8];
+        if little_endian {
+            buf[..size].copy_from_slice(bytes);
+            Ok(u64::from_le_bytes(buf))
+        } else {
+            buf[8 - size..].copy_from_slice(bytes);
+            Ok(u64::from_be_bytes(buf))
+        }
+    };
+
+    let (shoff, shentsize, shnum, shstrndx) = if is_64 {
+        (read(0x28, 8)?, read(0x3A, 2)?, read(0x3C, 2)?, read(0x3E, 2)?)
+    } else {
+        (read(0x20, 4)?, read(0x2E, 2)?, read(0x30, 2)?, read(0x32, 2)?)
+    };
+    if shoff == 0 || shnum == 0 {
+        return Ok(Vec::new());
+    }
+
+    // sh_name (u32) is first; sh_offset sits at 0x18 (64-bit) or 0x10 (32-bit)
+    let section = |index: u64| -> Result<u64> {
+        index.checked_mul(shentsize)
+            .and_then(|offset| offset.checked_add(shoff))
+            .ok_or_else(malformed)
+    };
+    let strtab_offset = if is_64 {
+        read(section(shstrndx)?.checked_add(0x18).ok_or_else(malformed)?, 8)?
+    } else {
+        read(section(shstrndx)?.checked_add(0x10).ok_or_else(malformed)?, 4)?
+    };
+
+    (0..shnum)
+        .map(|i| {
+            let name_offset = strtab_offset.checked_add(read(section(i)?, 4)?).ok_or_else(malformed)?;
+            let rest = usize::try_from(name_offset).ok()
+                .and_then(|offset| data.get(offset..))
+                .ok_or_else(malformed)?;
+            let end = rest.iter().position(|&b| b == 0).ok_or_else(malformed)?;
+            Ok(String::from_utf8_lossy(&rest[..end]).into_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_library_names() {
+        assert!(is_shared_library(Path::new("lib/libfoo.so")));
+        assert!(is_shared_library(Path::new("lib/libfoo.so.1.2")));
+        assert!(!is_shared_library(Path::new("bin/solver")));
+        assert!(!is_shared_library(Path::new("share/notes.sol")));
+    }
+
+    #[test]
+    fn test_has_symbols() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("readme.txt");
+        fs::write(&text, "not an elf").unwrap();
+        assert!(!has_symbols(&text).unwrap());
+
+        // Test binaries are built with symbols
+        let exe = std::env::current_exe().unwrap();
+        assert!(has_symbols(&exe).unwrap());
+    }
+
+    #[test]
+    fn test_malformed_section_table() {
+        let mut header = vec![0u8; 64];
+        header[..6].copy_from_slice(b"\x7fELF\x02\x01");
+        header[0x28..0x30].copy_from_slice(&(u64::MAX - 8).to_le_bytes());
+        header[0x3A..0x3C].copy_from_slice(&64u16.to_le_bytes());
+        header[0x3C..0x3E].copy_from_slice(&2u16.to_le_bytes());
+        header[0x3E..0x40].copy_from_slice(&1u16.to_le_bytes());
+        assert!(section_names(&header).is_err());
+    }
+}
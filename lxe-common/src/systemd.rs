@@ -0,0 +1,138 @@
+//! Systemd User Services
+//!
+//! A package's `[service]` becomes `<app_id>.service`, a unit for the user's
+//! systemd instance: in `~/.config/systemd/user` for user installs, or in
+//! `<prefix>/lib/systemd/user` (enabled for every user with `--global`) for
+//! system installs. Shared by the runtime installer and `lxe uninstall`.
+
+use crate::metadata::{LxeMetadata, ServiceMetadata};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// File name of the app's unit
+pub fn unit_name(app_id: &str) -> String {
+    format!("{}.service", app_id)
+}
+
+/// Unit directory for user installs (~/.config/systemd/user)
+pub fn user_unit_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("systemd/user"))
+}
+
+/// Unit directory for system installs under `base_dir` (e.g. /usr)
+pub fn system_unit_dir(base_dir: &Path) -> PathBuf {
+    base_dir.join("lib/systemd/user")
+}
+
+/// Render the unit of `service` for the app installed in `app_dir`
+pub fn render_unit(metadata: &LxeMetadata, service: &ServiceMetadata, app_dir: &Path) -> String {
+    // `%` starts a specifier in unit files; the quotes keep paths with spaces together
+    let program = app_dir.join(service.program()).display().to_string().replace('%', "%%");
+    let exec_start = match service.args() {
+        "" => format!("\"{}\"", program),
+        args => format!("\"{}\" {}", program, args.replace('%', "%%")),
+    };
+
+    let mut unit = String::from("[Unit]\n");
+    unit.push_str(&format!("Description={}\n", service.description.trim().replace('%', "%%")));
+    let after: Vec<&str> = service.after.iter().map(String::as_str).chain(service.wants_socket.as_deref()).collect();
+    if !after.is_empty() {
+        unit.push_str(&format!("After={}\n", after.join(" ")));
+    }
+    if let Some(ref socket) = service.wants_socket {
+        unit.push_str(&format!("Wants={}\n", socket));
+    }
+    unit.push_str(&format!("X-LXE-AppId={}\n", metadata.app_id));
+    unit.push_str("\n[Service]\n");
+    unit.push_str(&format!("ExecStart={}\n", exec_start));
+    unit.push_str(&format!("Restart={}\n", service.restart.as_str()));
+    unit.push_str("\n[Install]\nWantedBy=default.target\n");
+    unit
+}
+
+/// Enable the unit, so it starts at the next login; user units are also
+/// reloaded first
+pub fn enable(unit: &str, is_system: bool) -> Result<()> {
+    if is_system {
+        systemctl(&["--global", "enable", unit])
+    } else {
+        systemctl(&["--user", "daemon-reload"])?;
+        systemctl(&["--user", "enable", unit])
+    }
+}
+
+/// Disable the unit, stopping a running user service
+pub fn disable(unit: &str, is_system: bool) -> Result<()> {
+    if is_system {
+        systemctl(&["--global", "disable", unit])
+    } else {
+        systemctl(&["--user", "disable", "--now", unit])
+    }
+}
+
+/// Reload the user's units after one was removed
+pub fn reload_user() -> Result<()> {
+    systemctl(&["--user", "daemon-reload"])
+}
+
+fn systemctl(args: &[&str]) -> Result<()> {
+    let output = Command::new("systemctl")
+        .args(args)
+        .output()
+        .context("Failed to run systemctl (is systemd installed?)")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "systemctl {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::ServiceRestart;
+
+    #[test]
+    fn test_render_unit() {
+        let metadata = LxeMetadata::new("com.example.Sync", "Sync", "1.0.0", "sync", 0, "");
+        let mut service = ServiceMetadata {
+            exec: "bin/syncd --daemon".to_string(),
+            description: "Example sync daemon".to_string(),
+            restart: ServiceRestart::Always,
+            after: vec!["network-online.target".to_string()],
+            wants_socket: Some("com.example.Sync.socket".to_string()),
+        };
+        service.validate().unwrap();
+
+        let unit = render_unit(&metadata, &service, Path::new("/home/me/.local/share/com.example.Sync"));
+        assert_eq!(unit, "[Unit]\n\
+            Description=Example sync daemon\n\
+            After=network-online.target com.example.Sync.socket\n\
+            Wants=com.example.Sync.socket\n\
+            X-LXE-AppId=com.example.Sync\n\
+            \n[Service]\n\
+            ExecStart=\"/home/me/.local/share/com.example.Sync/bin/syncd\" --daemon\n\
+            Restart=always\n\
+            \n[Install]\n\
+            WantedBy=default.target\n");
+
+        service.exec = "../../bin/sh -c evil".to_string();
+        assert!(service.validate().is_err());
+        service.exec = "bin/syncd --progress 50%".to_string();
+        service.description = "100% synced".to_string();
+        let unit = render_unit(&metadata, &service, Path::new("/opt/%h"));
+        assert!(unit.contains("Description=100%% synced\n"));
+        assert!(unit.contains("ExecStart=\"/opt/%%h/bin/syncd\" --progress 50%%\n"));
+        service.exec = "bin/syncd".to_string();
+        service.wants_socket = Some("sync.service".to_string());
+        assert!(service.validate().is_err());
+
+        let restart: ServiceMetadata = toml::from_str("exec = \"syncd\"\ndescription = \"d\"\nrestart = \"OnFailure\"").unwrap();
+        assert_eq!(restart.restart, ServiceRestart::OnFailure);
+        assert_eq!(restart.args(), "");
+    }
+}
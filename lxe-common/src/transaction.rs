@@ -0,0 +1,294 @@
+//! Install Transactions
+//!
+//! Groups several installs/uninstalls so they either all take effect or
+//! none do. A transaction goes through two phases:
+//!
+//! 1. **Prepare** - every package is extracted and verified in a staging
+//!    directory next to its final location. Nothing visible changes.
+//! 2. **Commit** - staged directories are renamed into place. Every rename
+//!    is recorded in the log *after* it happens, so a failed or interrupted
+//!    commit can be undone by replaying the log backwards.
+//!
+//! Logs live in `~/.local/share/lxe/transactions/<id>.json`.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Operations of a transaction, as written in an actions file:
+///
+/// ```toml
+/// [[install]]
+/// file = "core.lxe"
+///
+/// [[uninstall]]
+/// id = "com.example.legacy"
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TransactionSpec {
+    #[serde(default)]
+    pub install: Vec<InstallOp>,
+
+    #[serde(default)]
+    pub uninstall: Vec<UninstallOp>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstallOp {
+    /// Path to the .lxe file
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UninstallOp {
+    /// Application ID to remove
+    pub id: String,
+}
+
+impl TransactionSpec {
+    /// Load an actions file; relative package paths are resolved against its directory
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let mut spec: TransactionSpec = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new("."));
+        for op in &mut spec.install {
+            if op.file.is_relative() {
+                op.file = base.join(&op.file);
+            }
+        }
+        Ok(spec)
+    }
+
+    /// Whether the transaction has nothing to do
+    pub fn is_empty(&self) -> bool {
+        self.install.is_empty() && self.uninstall.is_empty()
+    }
+}
+
+/// Where a transaction is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Accepting operations (`lxe transaction add`)
+    Open,
+    /// All packages staged and verified
+    Prepared,
+    /// Renaming into place; the log lists completed renames
+    Committing,
+    /// Every rename succeeded
+    Committed,
+    /// Completed renames were reversed
+    RolledBack,
+}
+
+/// A package extracted during the prepare phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StagedInstall {
+    pub app_id: String,
+    pub file: PathBuf,
+    /// Staging directory (sibling of `app_dir`)
+    pub staged_dir: PathBuf,
+    /// Final installation directory
+    pub app_dir: PathBuf,
+}
+
+/// One completed rename
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rename {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+/// Persistent record of a transaction
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransactionLog {
+    pub id: String,
+    pub phase: Phase,
+    pub spec: TransactionSpec,
+
+    #[serde(default)]
+    pub staged: Vec<StagedInstall>,
+
+    /// Renames done during commit, in order
+    #[serde(default)]
+    pub renames: Vec<Rename>,
+
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl TransactionLog {
+    /// Directory holding transaction logs
+    pub fn dir() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("~/.local/share"))
+            .join("lxe")
+            .join("transactions")
+    }
+
+    /// Start a new transaction
+    pub fn create(id: &str, spec: TransactionSpec) -> Result<Self> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            anyhow::bail!("Invalid transaction ID '{}': use letters, digits, '-' and '_'", id);
+        }
+
+        let path = Self::dir().join(format!("{}.json", id));
+        if path.exists() {
+            anyhow::bail!("Transaction '{}' already exists", id);
+        }
+
+        let log = TransactionLog {
+            id: id.to_string(),
+            phase: Phase::Open,
+            spec,
+            staged: Vec::new(),
+            renames: Vec::new(),
+            path,
+        };
+        log.save()?;
+        Ok(log)
+    }
+
+    /// Load an existing transaction
+    pub fn load(id: &str) -> Result<Self> {
+        let path = Self::dir().join(format!("{}.json", id));
+        let content = fs::read(&path)
+            .with_context(|| format!("No transaction named '{}'", id))?;
+        let mut log: TransactionLog = serde_json::from_slice(&content)
+            .with_context(|| format!("Corrupt transaction log: {}", path.display()))?;
+        log.path = path;
+        Ok(log)
+    }
+
+    /// Write the log atomically
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        fs::rename(&tmp, &self.path).context("Failed to write transaction log")
+    }
+
+    /// Record a phase transition
+    pub fn set_phase(&mut self, phase: Phase) -> Result<()> {
+        tracing::info!("Transaction {}: {:?} -> {:?}", self.id, self.phase, phase);
+        self.phase = phase;
+        self.save()
+    }
+
+    /// Rename `from` to `to` and record it
+    pub fn rename(&mut self, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(from, to)
+            .with_context(|| format!("Failed to rename {} -> {}", from.display(), to.display()))?;
+        self.renames.push(Rename { from: from.to_path_buf(), to: to.to_path_buf() });
+        self.save()
+    }
+
+    /// Reverse every recorded rename (newest first) and discard staged files
+    pub fn rollback(&mut self) -> Result<()> {
+        if self.phase == Phase::Committed {
+            anyhow::bail!("Transaction '{}' is already committed", self.id);
+        }
+
+        while let Some(rename) = self.renames.last().cloned() {
+            fs::rename(&rename.to, &rename.from).with_context(|| {
+                format!("Rollback failed: could not move {} back", rename.to.display())
+            })?;
+            self.renames.pop();
+            self.save()?;
+        }
+
+        for staged in &self.staged {
+            if staged.staged_dir.exists() {
+                let _ = fs::remove_dir_all(&staged.staged_dir);
+            }
+        }
+        self.set_phase(Phase::RolledBack)
+    }
+
+    /// Delete the log file
+    pub fn remove(self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path)?;
+        }
+        Ok(())
+    }
+
+    /// ID of the transaction started with `begin`, if any
+    pub fn current() -> Option<String> {
+        fs::read_to_string(Self::dir().join("current"))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    }
+
+    /// Set or clear the current transaction
+    pub fn set_current(id: Option<&str>) -> Result<()> {
+        let path = Self::dir().join("current");
+        match id {
+            Some(id) => {
+                fs::create_dir_all(Self::dir())?;
+                fs::write(path, id)?;
+            }
+            None => {
+                let _ = fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_spec() {
+        let spec: TransactionSpec = toml::from_str(r#"
+            [[install]]
+            file = "core.lxe"
+
+            [[install]]
+            file = "/opt/pkgs/app.lxe"
+
+            [[uninstall]]
+            id = "com.example.legacy"
+        "#).unwrap();
+
+        assert_eq!(spec.install.len(), 2);
+        assert_eq!(spec.uninstall[0].id, "com.example.legacy");
+        assert!(TransactionSpec::default().is_empty());
+    }
+
+    #[test]
+    fn test_rollback_reverses_renames() {
+        let base = std::env::temp_dir().join(format!("lxe-txn-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("app")).unwrap();
+        fs::create_dir_all(base.join("app.staged")).unwrap();
+
+        let mut log = TransactionLog {
+            id: "test".to_string(),
+            phase: Phase::Committing,
+            spec: TransactionSpec::default(),
+            staged: Vec::new(),
+            renames: Vec::new(),
+            path: base.join("log.json"),
+        };
+        log.rename(&base.join("app"), &base.join("app.old")).unwrap();
+        log.rename(&base.join("app.staged"), &base.join("app")).unwrap();
+
+        log.rollback().unwrap();
+        assert!(base.join("app").is_dir());
+        assert!(base.join("app.staged").is_dir());
+        assert!(!base.join("app.old").exists());
+        assert_eq!(log.phase, Phase::RolledBack);
+
+        let _ = fs::remove_dir_all(&base);
+    }
+}
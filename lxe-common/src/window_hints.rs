@@ -0,0 +1,350 @@
+//! Dynamic Window Hint Detection
+//!
+//! GNOME groups dock icons by `StartupWMClass`. When an app sets a WM_CLASS
+//! that doesn't match its desktop entry, it shows up twice in the dock.
+//! Static guesses (from the app ID) are often wrong for Electron/Java apps,
+//! so this module launches the app on a throwaway Xvfb display and reads
+//! the real WM_CLASS from its first window.
+//!
+//! The app gets a free display picked by Xvfb and its own session bus, so
+//! it can't reach the user's desktop. Run as root (system installs), it is
+//! started as the user who ran sudo, or as `nobody`, with an empty home.
+//!
+//! Requires `Xvfb`, `xwininfo` and `xprop` (x11-utils). D-Bus activation
+//! detection additionally uses `dbus-daemon` and `gdbus`.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// uid/gid of `nobody`, for apps detected as root without `SUDO_UID`
+const NOBODY: u32 = 65534;
+
+/// Hints discovered by running the application
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WindowHints {
+    /// The class part of the first WM_CLASS seen
+    pub wm_class: Option<String>,
+
+    /// Whether the app exports `org.freedesktop.Application` on the session bus
+    pub dbus_activatable: bool,
+}
+
+/// Kills the child's process group when dropped
+///
+/// The child must be spawned with `process_group(0)`, so whatever it
+/// started dies with it.
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        // SAFETY: plain syscall; the group id is the child's pid
+        unsafe { libc::kill(-(self.0.id() as i32), libc::SIGKILL) };
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Removes a directory tree when dropped
+struct RemoveOnDrop(PathBuf);
+
+impl Drop for RemoveOnDrop {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Who the app runs as
+struct AppUser {
+    /// `None`: the current user
+    ids: Option<(u32, u32)>,
+    home: PathBuf,
+}
+
+impl AppUser {
+    /// The current user, or the sudo caller (else `nobody`) when root
+    fn pick() -> Result<(Self, RemoveOnDrop)> {
+        let home = std::env::temp_dir().join(format!("lxe-hints-{}", std::process::id()));
+        std::fs::create_dir(&home)
+            .with_context(|| format!("Failed to create {}", home.display()))?;
+        let guard = RemoveOnDrop(home.clone());
+
+        // SAFETY: plain syscall
+        if unsafe { libc::geteuid() } != 0 {
+            return Ok((AppUser { ids: None, home }, guard));
+        }
+        let id = |var: &str| std::env::var(var).ok().and_then(|v| v.parse::<u32>().ok()).filter(|&id| id != 0);
+        let ids = match (id("SUDO_UID"), id("SUDO_GID")) {
+            (Some(uid), Some(gid)) => (uid, gid),
+            _ => (NOBODY, NOBODY),
+        };
+        std::os::unix::fs::chown(&home, Some(ids.0), Some(ids.1))?;
+        Ok((AppUser { ids: Some(ids), home }, guard))
+    }
+
+    /// Run `command` as this user, in its own process group
+    fn apply<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        if let Some((uid, gid)) = self.ids {
+            command.uid(uid).gid(gid);
+        }
+        command.env("HOME", &self.home).process_group(0)
+    }
+}
+
+/// Check whether the tools needed for detection are installed
+pub fn tools_available() -> bool {
+    ["Xvfb", "xwininfo", "xprop"].iter().all(|tool| {
+        Command::new("which")
+            .arg(tool)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    })
+}
+
+/// Launch `exec` under Xvfb and capture its window hints
+///
+/// The app is killed after the first WM_CLASS is seen or when `timeout`
+/// expires, whichever comes first.
+pub fn detect(exec: &Path, app_id: &str, timeout: Duration) -> Result<WindowHints> {
+    if !tools_available() {
+        anyhow::bail!(
+            "WM_CLASS detection requires Xvfb, xwininfo and xprop.\n\
+             Install them with: sudo apt install xvfb x11-utils"
+        );
+    }
+
+    let deadline = Instant::now() + timeout;
+    let (user, _home) = AppUser::pick()?;
+
+    // Xvfb picks a free display and writes its number to the pipe
+    let (display_reader, display_writer) = std::io::pipe()?;
+    let display_fd = std::os::fd::AsRawFd::as_raw_fd(&display_writer);
+    let mut xvfb = Command::new("Xvfb");
+    xvfb.args(["-displayfd", &display_fd.to_string(), "-ac", "-nolisten", "tcp"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    // SAFETY: fcntl is async-signal-safe; it keeps the pipe open across exec
+    unsafe {
+        xvfb.pre_exec(move || {
+            if libc::fcntl(display_fd, libc::F_SETFD, 0) == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let _xvfb = KillOnDrop(xvfb.spawn().context("Failed to start Xvfb")?);
+    drop(display_writer);
+    let display = read_line_until(display_reader, deadline)
+        .context("Xvfb did not report a display")?;
+    let display = format!(":{}", display.trim());
+
+    // A private session bus, so the app can't talk to the user's
+    let mut daemon = Command::new("dbus-daemon");
+    daemon.args(["--session", "--nofork", "--nopidfile", "--print-address=1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    let mut bus = user.apply(&mut daemon).spawn().ok().map(KillOnDrop);
+    let bus_address = bus.as_mut()
+        .and_then(|bus| bus.0.stdout.take())
+        .and_then(|out| read_line_until(out, deadline).ok())
+        .map(|address| address.trim().to_string());
+
+    let mut app = Command::new(exec);
+    app.current_dir(exec.parent().unwrap_or(Path::new(".")))
+        .env("DISPLAY", &display)
+        .env_remove("WAYLAND_DISPLAY")
+        .env_remove("XAUTHORITY")
+        .env_remove("XDG_RUNTIME_DIR")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    match bus_address {
+        Some(ref address) => app.env("DBUS_SESSION_BUS_ADDRESS", address),
+        None => app.env_remove("DBUS_SESSION_BUS_ADDRESS"),
+    };
+    let _app = KillOnDrop(
+        user.apply(&mut app)
+            .spawn()
+            .with_context(|| format!("Failed to launch {}", exec.display()))?,
+    );
+
+    let mut hints = WindowHints::default();
+
+    while Instant::now() < deadline {
+        if hints.wm_class.is_none() {
+            hints.wm_class = first_wm_class(&display);
+        }
+        if let (false, Some(address)) = (hints.dbus_activatable, &bus_address) {
+            hints.dbus_activatable = exports_application_interface(app_id, address);
+        }
+        if hints.wm_class.is_some() && (hints.dbus_activatable || bus_address.is_none()) {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(200));
+    }
+
+    Ok(hints)
+}
+
+/// First line `reader` yields, read on a thread so a stuck child can't
+/// block past `deadline`
+fn read_line_until(reader: impl Read + Send + 'static, deadline: Instant) -> Result<String> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let result = BufReader::new(reader).read_line(&mut line).map(|_| line);
+        let _ = sender.send(result);
+    });
+    let line = receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .context("Timed out")??;
+    if line.trim().is_empty() {
+        anyhow::bail!("Exited without output");
+    }
+    Ok(line)
+}
+
+/// Find the first window on `display` that has a WM_CLASS
+fn first_wm_class(display: &str) -> Option<String> {
+    let tree = x_command(display, "xwininfo", &["-root", "-tree"])?;
+
+    // Lines look like: `     0x400001 "title": ("name" "Class")  800x600+0+0  +0+0`
+    tree.lines()
+        .filter_map(|line| line.split_whitespace().next())
+        .filter(|id| id.starts_with("0x"))
+        .find_map(|id| {
+            let output = x_command(display, "xprop", &["-id", id, "WM_CLASS"])?;
+            parse_wm_class(&output)
+        })
+}
+
+/// Parse `WM_CLASS(STRING) = "instance", "Class"` into the class name
+pub fn parse_wm_class(xprop_output: &str) -> Option<String> {
+    let values = xprop_output.split_once('=')?.1;
+    let mut parts = values.split(',').map(|p| p.trim().trim_matches('"'));
+    let instance = parts.next().filter(|s| !s.is_empty());
+    let class = parts.next().filter(|s| !s.is_empty());
+    class.or(instance).map(String::from)
+}
+
+/// Check whether `app_id` exports the `org.freedesktop.Application`
+/// interface on the bus at `address`
+fn exports_application_interface(app_id: &str, address: &str) -> bool {
+    let object_path = format!("/{}", app_id.replace(['.', '-'], "/"));
+    Command::new("gdbus")
+        .args(["introspect", "--address", address, "--dest", app_id, "--object-path", &object_path])
+        .stderr(Stdio::null())
+        .output()
+        .map(|o| o.status.success()
+            && String::from_utf8_lossy(&o.stdout).contains("org.freedesktop.Application"))
+        .unwrap_or(false)
+}
+
+/// Run an X11 tool against the Xvfb display and return its stdout
+fn x_command(display: &str, tool: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(tool)
+        .args(["-display", display])
+        .args(args)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Write detected hints into an existing .desktop file
+pub fn apply_to_desktop_file(path: &Path, hints: &WindowHints) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_main_section = false;
+    let mut dbus_written = false;
+
+    for line in content.lines() {
+        if line.starts_with('[') {
+            // Append DBusActivatable at the end of [Desktop Entry]
+            if in_main_section && hints.dbus_activatable && !dbus_written {
+                insert_before_blank(&mut lines, "DBusActivatable=true");
+                dbus_written = true;
+            }
+            in_main_section = line == "[Desktop Entry]";
+        }
+
+        if in_main_section {
+            if let (Some(class), true) = (&hints.wm_class, line.starts_with("StartupWMClass=")) {
+                lines.push(format!("StartupWMClass={}", class));
+                continue;
+            }
+            if line.starts_with("DBusActivatable=") {
+                continue;
+            }
+        }
+        lines.push(line.to_string());
+    }
+
+    if in_main_section && hints.dbus_activatable && !dbus_written {
+        lines.push("DBusActivatable=true".to_string());
+    }
+
+    let mut output = lines.join("\n");
+    output.push('\n');
+    std::fs::write(path, output)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Insert a key before trailing blank lines of the current section
+fn insert_before_blank(lines: &mut Vec<String>, entry: &str) {
+    let pos = lines.iter().rposition(|l| !l.trim().is_empty()).map_or(0, |p| p + 1);
+    lines.insert(pos, entry.to_string());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wm_class() {
+        assert_eq!(
+            parse_wm_class("WM_CLASS(STRING) = \"code\", \"Code\"\n"),
+            Some("Code".to_string())
+        );
+        assert_eq!(parse_wm_class("WM_CLASS:  not found.\n"), None);
+    }
+
+    #[test]
+    fn test_read_line_until() {
+        let deadline = || Instant::now() + Duration::from_secs(5);
+        let mut child = Command::new("sh").args(["-c", "echo 42; sleep 1"]).stdout(Stdio::piped()).spawn().unwrap();
+        assert_eq!(read_line_until(child.stdout.take().unwrap(), deadline()).unwrap(), "42\n");
+        let _ = child.wait();
+
+        // A child that never writes can't hold detection up
+        let child = Command::new("sleep").arg("5").stdout(Stdio::piped()).process_group(0).spawn().unwrap();
+        let mut child = KillOnDrop(child);
+        let out = child.0.stdout.take().unwrap();
+        assert!(read_line_until(out, Instant::now() + Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn test_apply_to_desktop_file() {
+        let path = std::env::temp_dir().join(format!("lxe-hints-{}.desktop", std::process::id()));
+        std::fs::write(
+            &path,
+            "[Desktop Entry]\nName=App\nStartupWMClass=app\n\n[Desktop Action Uninstall]\nName=Uninstall\n",
+        ).unwrap();
+
+        let hints = WindowHints { wm_class: Some("RealApp".to_string()), dbus_activatable: true };
+        apply_to_desktop_file(&path, &hints).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("StartupWMClass=RealApp\nDBusActivatable=true\n\n[Desktop Action"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -2,15 +2,104 @@
 //!
 //! Handles decompression and extraction of the zstd-compressed payload
 //! using async I/O to prevent UI blocking.
+//!
+//! Progress can be observed through a watch channel ([`extract_async`],
+//! optionally as a [`ProgressStream`]), a callback ([`extract_with_callback`]),
+//! or not at all ([`extract_blocking`]). [`extract_async`] can be cancelled
+//! with a [`CancellationToken`]: the extraction stops before the next file is
+//! written, fails with [`LxeError::Cancelled`], and removes what it wrote.
+//!
+//! Decompression is inherently sequential, but writing thousands of small
+//! files is not: files up to [`PARALLEL_MAX_FILE_SIZE`] are read into memory
+//! and written by a rayon pool while the stream is decoded further. Hard
+//! links and then symlinks are created once everything else is written, so
+//! their targets exist. `--sequential-extract` ([`set_sequential`]) unpacks
+//! one entry at a time instead, for debugging.
+//!
+//! Either way nothing is written through a symlink: a path may appear only
+//! once in the archive, no entry may lie below a symlink, and hard links may
+//! only point to regular files the archive itself contains.
+//!
+//! A delta package (`lxe delta`) is applied to a copy of the installed app
+//! directory instead: its manifest entry removes what the new release
+//! dropped, then the changed files are unpacked over the copy, and the
+//! result is compared to the full release (`delta_target_tree`).
+//!
+//! The payload is decompressed as a stream, so memory use doesn't grow with
+//! the package: besides the zstd window, it is bounded by `[extractor]
+//! max_memory_mb` ([`configure`]), which sizes the read buffer and how much
+//! file content may wait for the pool.
 
-use lxe_common::metadata::LxeMetadata;
-use lxe_common::payload::PayloadInfo;
+use lxe_common::config::ExtractorConfig;
+use lxe_common::delta::DeltaManifest;
+use lxe_common::error::LxeError;
+use lxe_common::payload::{self, ChecksumHasher, PayloadInfo};
 use anyhow::{Context, Result};
-use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
-use tokio::fs::{self, File};
-use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, OnceLock};
+use std::task::{Context as TaskContext, Poll};
+use tokio::fs;
 use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_stream::Stream;
+use tokio_stream::wrappers::WatchStream;
+use tokio_util::sync::CancellationToken;
+
+/// Largest file written by the worker pool; bigger ones are written while
+/// reading, as buffering them costs memory without saving time
+pub const PARALLEL_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Set by `--sequential-extract`
+static SEQUENTIAL: AtomicBool = AtomicBool::new(false);
+
+/// Set from the global config at startup
+static CONFIG: OnceLock<ExtractorConfig> = OnceLock::new();
+
+/// Unpack one entry at a time instead of writing files in parallel
+pub fn set_sequential(sequential: bool) {
+    SEQUENTIAL.store(sequential, Ordering::Relaxed);
+}
+
+/// Use `config`'s limits for all extractions (only the first call counts)
+pub fn configure(config: &ExtractorConfig) {
+    let _ = CONFIG.set(*config);
+}
+
+/// Buffer sizes within `[extractor] max_memory_mb`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MemoryBudget {
+    /// Buffer for reading the compressed payload
+    read_buffer: usize,
+    
+    /// File contents waiting for the worker pool
+    max_buffered: u64,
+    
+    /// Largest file handed to the worker pool
+    max_parallel_file: u64,
+}
+
+impl MemoryBudget {
+    fn current() -> Self {
+        let config = CONFIG.get().copied().unwrap_or_default();
+        Self::new(config.max_memory_mb.saturating_mul(1024 * 1024))
+    }
+    
+    fn new(budget: u64) -> Self {
+        // 1/64 of the budget for reading, between 8 KiB and 1 MiB
+        let read_buffer = (budget / 64).clamp(8 * 1024, 1024 * 1024);
+        let max_buffered = budget.saturating_sub(read_buffer);
+        Self {
+            read_buffer: read_buffer as usize,
+            max_buffered,
+            // No single file may take more than a quarter of the buffer
+            max_parallel_file: (max_buffered / 4).min(PARALLEL_MAX_FILE_SIZE),
+        }
+    }
+}
 
 /// Progress information sent to the UI
 #[derive(Debug, Clone)]
@@ -18,7 +107,7 @@ pub struct ExtractProgress {
     /// Total bytes to extract (uncompressed)
     pub total_bytes: u64,
     
-    /// Bytes extracted so far
+    /// Bytes written to disk so far
     pub extracted_bytes: u64,
     
     /// Number of files extracted
@@ -58,96 +147,540 @@ impl ExtractProgress {
 
 /// Extract the payload to a target directory
 /// Returns a watch receiver for progress updates
+/// 
+/// Once `cancel` is cancelled, the extraction fails with
+/// [`LxeError::Cancelled`] and leaves `target_dir` as it was.
 pub fn extract_async(
     payload_info: PayloadInfo,
     target_dir: PathBuf,
-) -> (watch::Receiver<ExtractProgress>, tokio::task::JoinHandle<Result<()>>) {
+    cancel: CancellationToken,
+) -> (watch::Receiver<ExtractProgress>, JoinHandle<Result<()>>) {
+    let app_dir = target_dir.join(&payload_info.metadata.app_id);
+    extract_async_to(payload_info, app_dir.clone(), Some(app_dir), cancel)
+}
+
+/// Like [`extract_async`], but into `app_dir` itself instead of
+/// `<target_dir>/<app_id>` (e.g., a staging directory); a delta package is
+/// applied to a copy of the installed `base`
+pub fn extract_async_to(
+    payload_info: PayloadInfo,
+    app_dir: PathBuf,
+    base: Option<PathBuf>,
+    cancel: CancellationToken,
+) -> (watch::Receiver<ExtractProgress>, JoinHandle<Result<()>>) {
     let (tx, rx) = watch::channel(ExtractProgress::new(payload_info.metadata.install_size));
     
     let handle = tokio::spawn(async move {
-        extract_inner(payload_info, target_dir, tx).await
+        extract_inner(&payload_info, &app_dir, base.as_deref(), &cancel, |progress| {
+            let _ = tx.send(progress.clone());
+        }).await
     });
     
     (rx, handle)
 }
 
-async fn extract_inner(
+/// Extract the payload, calling `on_progress` after each extracted file
+/// 
+/// Unlike the watch channel of [`extract_async`], no update is skipped.
+/// The last call has `complete` set. Must be called within a tokio runtime.
+pub fn extract_with_callback(
     payload_info: PayloadInfo,
     target_dir: PathBuf,
-    progress_tx: watch::Sender<ExtractProgress>,
+    on_progress: impl Fn(ExtractProgress) + Send + 'static,
+) -> JoinHandle<Result<()>> {
+    tokio::spawn(async move {
+        // The extractor also reports when a file starts; only pass on finished ones
+        let mut reported = 0;
+        let app_dir = target_dir.join(&payload_info.metadata.app_id);
+        extract_inner(&payload_info, &app_dir, Some(&app_dir), &CancellationToken::new(), move |progress| {
+            if progress.files_extracted != reported || progress.complete {
+                reported = progress.files_extracted;
+                on_progress(progress.clone());
+            }
+        }).await
+    })
+}
+
+/// Extract the payload, blocking until done
+/// 
+/// For callers without an async runtime; panics if called from within one.
+pub fn extract_blocking(payload_info: &PayloadInfo, target_dir: &Path) -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("Failed to start tokio runtime")?;
+    let app_dir = target_dir.join(&payload_info.metadata.app_id);
+    runtime.block_on(extract_inner(payload_info, &app_dir, Some(&app_dir), &CancellationToken::new(), |_| {}))
+}
+
+/// Progress updates of [`extract_async`] as a [`Stream`]
+/// 
+/// Yields the current state first, then every change. Intermediate states
+/// may be skipped if the consumer is slower than the extractor.
+pub struct ProgressStream(WatchStream<ExtractProgress>);
+
+impl From<watch::Receiver<ExtractProgress>> for ProgressStream {
+    fn from(rx: watch::Receiver<ExtractProgress>) -> Self {
+        ProgressStream(WatchStream::new(rx))
+    }
+}
+
+impl Stream for ProgressStream {
+    type Item = ExtractProgress;
+    
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<Option<ExtractProgress>> {
+        Pin::new(&mut self.0).poll_next(cx)
+    }
+}
+
+/// Extract into a temporary directory next to `app_dir`, then move it there
+/// 
+/// The temporary directory is removed on any error, including cancellation.
+async fn extract_inner(
+    payload_info: &PayloadInfo,
+    app_dir: &Path,
+    base: Option<&Path>,
+    cancel: &CancellationToken,
+    mut report: impl FnMut(&ExtractProgress) + Send,
 ) -> Result<()> {
     let mut progress = ExtractProgress::new(payload_info.metadata.install_size);
+    let target_dir = app_dir.parent()
+        .context("App directory has no parent directory")?;
     
     // Ensure target directory exists
-    fs::create_dir_all(&target_dir).await
+    fs::create_dir_all(target_dir).await
         .context("Failed to create target directory")?;
     
     // Create a secure temporary directory for extraction
     // Uses tempfile crate to prevent TOCTOU attacks with cryptographically random names
     let temp_dir = tempfile::Builder::new()
         .prefix("lxe-extracting-")
-        .tempdir_in(&target_dir)
+        .tempdir_in(target_dir)
         .context("Failed to create secure temporary directory")?;
     let temp_path = temp_dir.path().to_path_buf();
     
+    let delta = payload_info.metadata.is_delta();
+    if delta {
+        let base = base.filter(|base| base.is_dir())
+            .context("This update package needs the app to be installed already")?;
+        copy_tree(base, &temp_path, cancel, &mut progress, &mut report)
+            .context("Failed to copy the installed version")?;
+    }
+    
     // Open the payload for reading
+    let budget = MemoryBudget::current();
     let file = std::fs::File::open(&payload_info.exe_path)?;
-    let mut reader = std::io::BufReader::new(file);
+    let mut reader = std::io::BufReader::with_capacity(budget.read_buffer, file);
     std::io::Seek::seek(&mut reader, std::io::SeekFrom::Start(payload_info.payload_offset))?;
     
-    // Create a streaming zstd decoder using ruzstd (pure Rust)
-    let decoder = ruzstd::StreamingDecoder::new(&mut reader)
-        .context("Failed to initialize zstd decoder")?;
+    // The payload is hashed as it is decompressed, so a damaged or modified
+    // one is caught without reading it twice
+    let mut hashing = HashingReader {
+        inner: (&mut reader).take(payload_info.payload_size),
+        hasher: ChecksumHasher::new(payload_info.metadata.checksum_algorithm),
+    };
+    
+    // Streaming decoder for the package's compressor (ruzstd for zstd, pure Rust)
+    let decoder = payload::decoder(&mut hashing, &payload_info.metadata.compressor)
+        .map_err(|e| LxeError::PayloadCorrupted(format!("{:#}", e)))?;
     
     // Wrap in a tar archive reader
     let mut archive = tar::Archive::new(decoder);
     
-    // Extract entries
+    if SEQUENTIAL.load(Ordering::Relaxed) {
+        unpack_sequential(&mut archive, &temp_path, delta, cancel, &mut progress, &mut report)?;
+    } else {
+        unpack_parallel(&mut archive, &temp_path, delta, budget, cancel, &mut progress, &mut report)?;
+    }
+    check_cancelled(cancel)?;
+    drop(archive);
+    
+    // The tar reader stops at the end-of-archive marker; hash what follows it
+    std::io::copy(&mut hashing, &mut std::io::sink())?;
+    let expected = &payload_info.metadata.payload_checksum;
+    let computed = hashing.hasher.finalize_hex();
+    if !computed.eq_ignore_ascii_case(expected) {
+        return Err(LxeError::ChecksumMismatch { expected: expected.clone(), computed }.into());
+    }
+    if delta {
+        check_delta_result(&payload_info.metadata, &temp_path)?;
+    }
+    
+    // Atomic move from temp to final location
+    // First, remove any existing installation
+    if app_dir.exists() {
+        fs::remove_dir_all(app_dir).await?;
+    }
+    
+    // Move temp to final
+    fs::rename(&temp_path, app_dir).await
+        .context("Failed to move extracted files to final location")?;
+    
+    // Mark complete
+    progress.complete = true;
+    progress.current_file = "Complete!".to_string();
+    report(&progress);
+    
+    Ok(())
+}
+
+/// A patched copy must hold exactly the files of the full release
+fn check_delta_result(metadata: &lxe_common::metadata::LxeMetadata, dir: &Path) -> Result<()> {
+    use lxe_common::delta::{tree_checksum, tree_entries};
+    
+    let expected = metadata.delta_target_tree.as_ref()
+        .context("This update package was made by an older lxe and can't be checked; install the full package instead")?;
+    let computed = tree_checksum(&tree_entries(dir)?);
+    if !computed.eq_ignore_ascii_case(expected) {
+        anyhow::bail!(
+            "Applying the update didn't produce v{} (files {} instead of {}); install the full package instead",
+            metadata.version,
+            computed,
+            expected
+        );
+    }
+    Ok(())
+}
+
+/// Unpack every entry in archive order
+fn unpack_sequential<R: Read>(
+    archive: &mut tar::Archive<R>,
+    dest: &Path,
+    delta: bool,
+    cancel: &CancellationToken,
+    progress: &mut ExtractProgress,
+    report: &mut impl FnMut(&ExtractProgress),
+) -> Result<()> {
+    let mut paths = ArchivePaths::default();
     for entry in archive.entries()? {
-        let mut entry = entry.context("Failed to read tar entry")?;
+        check_cancelled(cancel)?;
+        let mut entry = entry.map_err(|e| LxeError::PayloadCorrupted(e.to_string()))?;
         let path = entry.path()?.to_path_buf();
-        let path_str = path.to_string_lossy().to_string();
-        
-        progress.current_file = path_str.clone();
-        let _ = progress_tx.send(progress.clone());
+        if delta && DeltaManifest::is_manifest(&path) {
+            apply_removals(&mut entry, dest)?;
+            continue;
+        }
         
-        // Determine target path
-        let target_path = temp_path.join(&path);
+        progress.current_file = path.to_string_lossy().to_string();
+        report(progress);
         
-        // Create parent directories
-        if let Some(parent) = target_path.parent() {
-            std::fs::create_dir_all(parent)?;
+        let entry_type = entry.header().entry_type();
+        let relative = paths.claim(&path, entry_type)?;
+        if entry_type.is_hard_link() {
+            let target = entry.link_name()?
+                .with_context(|| format!("Link without a target: {}", path.display()))?;
+            paths.hard_link_target(&relative, &target)?;
         }
+        check_no_symlink_ancestor(dest, &relative)?;
+        unpack_in(&mut entry, dest, &path)?;
         
-        // Extract the file
-        entry.unpack(&target_path)?;
-        
-        // Update progress
         progress.extracted_bytes += entry.size();
         progress.files_extracted += 1;
-        let _ = progress_tx.send(progress.clone());
+        report(progress);
     }
+    Ok(())
+}
+
+/// A link whose target may not be written yet
+struct DeferredLink {
+    /// Relative to the destination
+    path: PathBuf,
+    target: PathBuf,
+}
+
+/// Unpack with small files written by the rayon pool, links last
+fn unpack_parallel<R: Read + Send>(
+    archive: &mut tar::Archive<R>,
+    dest: &Path,
+    delta: bool,
+    budget: MemoryBudget,
+    cancel: &CancellationToken,
+    progress: &mut ExtractProgress,
+    report: &mut (impl FnMut(&ExtractProgress) + Send),
+) -> Result<()> {
+    let mut paths = ArchivePaths::default();
+    let mut hard_links = Vec::new();
+    let mut symlinks = Vec::new();
     
-    // Atomic move from temp to final location
-    // First, remove any existing installation
-    let final_app_dir = target_dir.join(&payload_info.metadata.app_id);
-    if final_app_dir.exists() {
-        fs::remove_dir_all(&final_app_dir).await?;
+    // Reading stays on this thread, so waiting for workers can't starve the pool
+    rayon::in_place_scope(|scope| -> Result<()> {
+        // Workers send the size of each written file
+        let (done_tx, done_rx) = mpsc::channel::<(u64, Result<()>)>();
+        let mut buffered = 0u64;
+        let mut pending = 0usize;
+        
+        for entry in archive.entries()? {
+            // Files already handed to the pool are still written; the
+            // scope waits for them before the temporary directory goes
+            check_cancelled(cancel)?;
+            let mut entry = entry.map_err(|e| LxeError::PayloadCorrupted(e.to_string()))?;
+            let path = entry.path()?.to_path_buf();
+            if delta && DeltaManifest::is_manifest(&path) {
+                apply_removals(&mut entry, dest)?;
+                continue;
+            }
+            let header = entry.header();
+            let entry_type = header.entry_type();
+            let relative = paths.claim(&path, entry_type)?;
+            
+            if entry_type.is_symlink() || entry_type.is_hard_link() {
+                let target = entry.link_name()?
+                    .with_context(|| format!("Link without a target: {}", path.display()))?
+                    .into_owned();
+                let link = DeferredLink { path: relative, target };
+                if entry_type.is_hard_link() {
+                    hard_links.push(link);
+                } else {
+                    symlinks.push(link);
+                }
+                continue;
+            }
+            
+            // Only the base of a delta has symlinks at this point
+            check_no_symlink_ancestor(dest, &relative)?;
+            let target_path = dest.join(&relative);
+            
+            progress.current_file = path.to_string_lossy().to_string();
+            let size = entry.size();
+            if entry_type.is_file() && size <= budget.max_parallel_file {
+                let mode = header.mode()?;
+                let mtime = header.mtime().ok();
+                let mut data = Vec::with_capacity(size as usize);
+                entry.read_to_end(&mut data)?;
+                
+                while pending > 0 && buffered + size > budget.max_buffered {
+                    buffered -= record(done_rx.recv()?, progress, report)?;
+                    pending -= 1;
+                }
+                buffered += size;
+                pending += 1;
+                let done_tx = done_tx.clone();
+                scope.spawn(move |_| {
+                    let result = write_file(&target_path, &data, mode, mtime);
+                    let _ = done_tx.send((size, result));
+                });
+            } else {
+                // Directories, large files and special files
+                report(progress);
+                unpack_in(&mut entry, dest, &path)?;
+                record((size, Ok(())), progress, report)?;
+            }
+            
+            while let Ok(done) = done_rx.try_recv() {
+                buffered -= record(done, progress, report)?;
+                pending -= 1;
+            }
+        }
+        
+        for _ in 0..pending {
+            record(done_rx.recv()?, progress, report)?;
+        }
+        Ok(())
+    })?;
+    
+    // Hard links before symlinks: a symlink could otherwise redirect them
+    let links = hard_links.into_iter().map(|link| (link, true))
+        .chain(symlinks.into_iter().map(|link| (link, false)));
+    for (link, hard) in links {
+        check_cancelled(cancel)?;
+        progress.current_file = link.path.to_string_lossy().to_string();
+        report(progress);
+        check_no_symlink_ancestor(dest, &link.path)?;
+        let path = dest.join(&link.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if hard {
+            let target = paths.hard_link_target(&link.path, &link.target)?;
+            std::fs::hard_link(dest.join(target), &path)
+        } else {
+            std::os::unix::fs::symlink(&link.target, &path)
+        }
+        .with_context(|| format!("Failed to create link {}", path.display()))?;
+        progress.files_extracted += 1;
+        report(progress);
+    }
+    Ok(())
+}
+
+/// Paths of the archive unpacked so far
+#[derive(Default)]
+struct ArchivePaths {
+    /// Every non-directory entry
+    seen: HashSet<PathBuf>,
+    /// Regular files, which hard links may point to
+    files: HashSet<PathBuf>,
+}
+
+impl ArchivePaths {
+    /// Record an entry; returns its path relative to the destination
+    /// 
+    /// Fails for paths leaving the destination and for paths the archive
+    /// already contained (workers would race writing them).
+    fn claim(&mut self, path: &Path, entry_type: tar::EntryType) -> Result<PathBuf> {
+        let relative = relative_path(path)?;
+        if !entry_type.is_dir() && relative.as_os_str().is_empty() {
+            return Err(LxeError::PayloadCorrupted(format!("{} is not a valid entry path", path.display())).into());
+        }
+        if !entry_type.is_dir() && !self.seen.insert(relative.clone()) {
+            return Err(LxeError::PayloadCorrupted(format!("{} appears twice in the payload", path.display())).into());
+        }
+        if entry_type.is_file() {
+            self.files.insert(relative.clone());
+        }
+        Ok(relative)
     }
     
-    // Move temp to final
-    fs::rename(&temp_path, &final_app_dir).await
-        .context("Failed to move extracted files to final location")?;
+    /// Path a hard link at `link` may point to: a regular file of the archive
+    fn hard_link_target(&self, link: &Path, target: &Path) -> Result<PathBuf> {
+        let target = relative_path(target)?;
+        if !self.files.contains(&target) {
+            anyhow::bail!(
+                "Hard link {} points to {}, which is not a file of the package",
+                link.display(),
+                target.display()
+            );
+        }
+        Ok(target)
+    }
+}
+
+/// Unpack `entry` inside `dest`; `tar` checks that its parent directory
+/// resolves to a path inside `dest`
+fn unpack_in<R: Read>(entry: &mut tar::Entry<R>, dest: &Path, path: &Path) -> Result<()> {
+    if !entry.unpack_in(dest).with_context(|| format!("Failed to extract {}", path.display()))? {
+        anyhow::bail!("Refusing to extract {} outside the install directory", path.display());
+    }
+    Ok(())
+}
+
+/// Fail if a directory `relative` lies in (below `dest`) is a symlink
+fn check_no_symlink_ancestor(dest: &Path, relative: &Path) -> Result<()> {
+    let mut ancestor = dest.to_path_buf();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        if components.peek().is_none() {
+            break;
+        }
+        ancestor.push(component);
+        if std::fs::symlink_metadata(&ancestor).is_ok_and(|m| m.file_type().is_symlink()) {
+            anyhow::bail!("Refusing to extract {} through the symlink {}", relative.display(), ancestor.display());
+        }
+    }
+    Ok(())
+}
+
+/// Remove the paths listed in a delta's manifest entry from `dest`
+fn apply_removals(manifest: &mut impl Read, dest: &Path) -> Result<()> {
+    let manifest: DeltaManifest = serde_json::from_reader(manifest)
+        .context("Corrupt delta manifest")?;
+    manifest.remove_from(dest)
+}
+
+/// Copy the installed app directory `base` into `dest`, counting copied
+/// bytes as extracted
+fn copy_tree(
+    base: &Path,
+    dest: &Path,
+    cancel: &CancellationToken,
+    progress: &mut ExtractProgress,
+    report: &mut impl FnMut(&ExtractProgress),
+) -> Result<()> {
+    for entry in std::fs::read_dir(base)? {
+        check_cancelled(cancel)?;
+        let entry = entry?;
+        let source = entry.path();
+        let target = dest.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        
+        if file_type.is_dir() {
+            std::fs::create_dir(&target)?;
+            copy_tree(&source, &target, cancel, progress, report)?;
+            std::fs::set_permissions(&target, entry.metadata()?.permissions())?;
+        } else if file_type.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(&source)?, &target)?;
+        } else {
+            progress.current_file = entry.file_name().to_string_lossy().to_string();
+            progress.extracted_bytes += std::fs::copy(&source, &target)
+                .with_context(|| format!("Failed to copy {}", source.display()))?;
+            report(progress);
+        }
+    }
+    Ok(())
+}
+
+fn check_cancelled(cancel: &CancellationToken) -> Result<()> {
+    if cancel.is_cancelled() {
+        return Err(LxeError::Cancelled.into());
+    }
+    Ok(())
+}
+
+/// Count a written entry, returning its size
+fn record(
+    (size, result): (u64, Result<()>),
+    progress: &mut ExtractProgress,
+    report: &mut impl FnMut(&ExtractProgress),
+) -> Result<u64> {
+    result?;
+    progress.extracted_bytes += size;
+    progress.files_extracted += 1;
+    report(progress);
+    Ok(size)
+}
+
+/// An archive path without `.` components, empty for the root (`./`);
+/// paths leaving the destination are refused
+fn relative_path(path: &Path) -> Result<PathBuf> {
+    use std::path::Component;
     
-    // Mark complete
-    progress.complete = true;
-    progress.current_file = "Complete!".to_string();
-    let _ = progress_tx.send(progress);
+    if path.components().any(|c| !matches!(c, Component::Normal(_) | Component::CurDir)) {
+        anyhow::bail!("Refusing to extract {} outside the install directory", path.display());
+    }
+    Ok(path.components().filter(|c| matches!(c, Component::Normal(_))).collect())
+}
+
+/// Write a buffered file with the entry's permissions and modification time
+fn write_file(path: &Path, data: &[u8], mode: u32, mtime: Option<u64>) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
     
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // A delta's base may have a symlink here; replace it, don't write through it
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => {}
+    }
+    let mut file = std::fs::File::create(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(data)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    // Like tar's unpack: no setuid/setgid/sticky bits
+    file.set_permissions(std::fs::Permissions::from_mode(mode & 0o777))?;
+    if let Some(mtime) = mtime {
+        file.set_modified(std::time::UNIX_EPOCH + std::time::Duration::from_secs(mtime))?;
+    }
     Ok(())
 }
 
+/// Passes reads through to `inner`, hashing everything read
+struct HashingReader<R> {
+    inner: R,
+    hasher: ChecksumHasher,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+}
+
 /// Verify the payload checksum before extraction
+/// 
+/// Uses the metadata's `checksum_algorithm` (SHA256 for older packages).
 pub async fn verify_checksum(payload_info: &PayloadInfo) -> Result<bool> {
     let expected = &payload_info.metadata.payload_checksum;
     
@@ -155,7 +688,7 @@ pub async fn verify_checksum(payload_info: &PayloadInfo) -> Result<bool> {
     let mut reader = std::io::BufReader::new(file);
     std::io::Seek::seek(&mut reader, std::io::SeekFrom::Start(payload_info.payload_offset))?;
     
-    let mut hasher = Sha256::new();
+    let mut hasher = ChecksumHasher::new(payload_info.metadata.checksum_algorithm);
     let mut buffer = vec![0u8; 64 * 1024]; // 64KB buffer
     let mut remaining = payload_info.payload_size;
     
@@ -169,8 +702,7 @@ pub async fn verify_checksum(payload_info: &PayloadInfo) -> Result<bool> {
         remaining -= bytes_read as u64;
     }
     
-    let result = hasher.finalize();
-    let computed = hex::encode(result);
+    let computed = hasher.finalize_hex();
     
     Ok(computed == *expected)
 }
@@ -196,4 +728,107 @@ mod tests {
         let progress = ExtractProgress::new(0);
         assert_eq!(progress.fraction(), 0.0);
     }
+
+    #[test]
+    fn test_unpack_parallel() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut append = |path: &str, entry_type: tar::EntryType, link: Option<&str>, data: &[u8]| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_mode(0o755);
+            header.set_size(data.len() as u64);
+            if let Some(link) = link {
+                header.set_link_name(link).unwrap();
+            }
+            builder.append_data(&mut header, path, data).unwrap();
+        };
+        // Links come before their targets
+        append("lib/libfoo.so", tar::EntryType::Symlink, Some("libfoo.so.1"), b"");
+        append("app-link", tar::EntryType::Link, Some("app"), b"");
+        append("lib/libfoo.so.1", tar::EntryType::Regular, None, b"elf");
+        append("app", tar::EntryType::Regular, None, &vec![7u8; PARALLEL_MAX_FILE_SIZE as usize + 1]);
+        let data = builder.into_inner().unwrap();
+        
+        let dest = tempfile::tempdir().unwrap();
+        let mut progress = ExtractProgress::new(0);
+        let mut archive = tar::Archive::new(&data[..]);
+        let budget = MemoryBudget::new(ExtractorConfig::default().max_memory_mb * 1024 * 1024);
+        assert_eq!(budget.max_parallel_file, PARALLEL_MAX_FILE_SIZE);
+        let cancel = CancellationToken::new();
+        unpack_parallel(&mut archive, dest.path(), false, budget, &cancel, &mut progress, &mut |_| {}).unwrap();
+        
+        assert_eq!(progress.files_extracted, 4);
+        assert_eq!(progress.extracted_bytes, PARALLEL_MAX_FILE_SIZE + 4);
+        assert_eq!(std::fs::read(dest.path().join("lib/libfoo.so")).unwrap(), b"elf");
+        assert_eq!(std::fs::metadata(dest.path().join("app-link")).unwrap().len(), PARALLEL_MAX_FILE_SIZE + 1);
+        
+        assert!(relative_path(Path::new("../escape")).is_err());
+        assert_eq!(relative_path(Path::new("./lib/./x")).unwrap(), Path::new("lib/x"));
+        assert_eq!(relative_path(Path::new("./")).unwrap(), Path::new(""));
+        
+        // Small budgets buffer less and hand smaller files to the pool
+        let small = MemoryBudget::new(1024 * 1024);
+        assert_eq!((small.read_buffer, small.max_buffered), (16 * 1024, 1008 * 1024));
+        assert_eq!(small.max_parallel_file, 252 * 1024);
+        
+        // Cancelled before the first entry: nothing is written
+        cancel.cancel();
+        let dest = tempfile::tempdir().unwrap();
+        let mut archive = tar::Archive::new(&data[..]);
+        let err = unpack_sequential(&mut archive, dest.path(), false, &cancel, &mut progress, &mut |_| {}).unwrap_err();
+        assert!(matches!(err.downcast_ref::<LxeError>(), Some(LxeError::Cancelled)));
+        assert_eq!(std::fs::read_dir(dest.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_unpack_rejects_unsafe_links() {
+        fn archive(entries: &[(&str, tar::EntryType, Option<&str>)]) -> Vec<u8> {
+            let mut builder = tar::Builder::new(Vec::new());
+            for (path, entry_type, link) in entries {
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(*entry_type);
+                header.set_mode(0o644);
+                let data: &[u8] = if entry_type.is_file() { b"data" } else { b"" };
+                header.set_size(data.len() as u64);
+                if let Some(link) = link {
+                    header.set_link_name(link).unwrap();
+                }
+                builder.append_data(&mut header, path, data).unwrap();
+            }
+            builder.into_inner().unwrap()
+        }
+        let outside = tempfile::tempdir().unwrap();
+        let outside_path = outside.path().to_str().unwrap();
+        let unpack = |data: &[u8], parallel: bool| {
+            let dest = tempfile::tempdir().unwrap();
+            let mut archive = tar::Archive::new(data);
+            let mut progress = ExtractProgress::new(0);
+            let cancel = CancellationToken::new();
+            let result = if parallel {
+                let budget = MemoryBudget::new(ExtractorConfig::default().max_memory_mb * 1024 * 1024);
+                unpack_parallel(&mut archive, dest.path(), false, budget, &cancel, &mut progress, &mut |_| {})
+            } else {
+                unpack_sequential(&mut archive, dest.path(), false, &cancel, &mut progress, &mut |_| {})
+            };
+            (dest, result)
+        };
+        
+        let cases = [
+            // The same path twice
+            archive(&[("app", tar::EntryType::Regular, None), ("app", tar::EntryType::Regular, None)]),
+            // A hard link to a symlink leaving the package
+            archive(&[("s", tar::EntryType::Symlink, Some(outside_path)), ("h", tar::EntryType::Link, Some("s"))]),
+            // A hard link to a file the package doesn't contain
+            archive(&[("h", tar::EntryType::Link, Some("missing"))]),
+            // A file below a symlinked directory
+            archive(&[("d", tar::EntryType::Symlink, Some(outside_path)), ("d/f", tar::EntryType::Regular, None)]),
+        ];
+        for data in &cases {
+            for parallel in [false, true] {
+                let (_dest, result) = unpack(data, parallel);
+                assert!(result.is_err(), "parallel: {parallel}");
+            }
+        }
+        assert_eq!(std::fs::read_dir(outside.path()).unwrap().count(), 0);
+    }
 }
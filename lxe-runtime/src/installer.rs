@@ -5,11 +5,19 @@
 
 use crate::extractor;
 use crate::polkit;
+use crate::target_user::TargetUser;
+use lxe_common::desktop;
+use lxe_common::error::LxeError;
 use lxe_common::metadata::LxeMetadata;
 use lxe_common::payload::PayloadInfo;
+use lxe_common::paths::lxe::{BACKUP_SUFFIX, STAGING_SUFFIX};
 use anyhow::{Context, Result};
 use std::path::{Path, PathBuf};
 use tokio::fs;
+use tokio_util::sync::CancellationToken;
+
+// Shared with `lxe uninstall --scan-leftovers`
+pub use lxe_common::leftovers::{find_leftover_files, LeftoverEntry, LeftoverKind};
 
 /// Installation target configuration
 #[derive(Debug, Clone)]
@@ -25,6 +33,11 @@ pub struct InstallConfig {
     
     /// Whether to update the icon cache
     pub update_icon_cache: bool,
+    
+    /// Whether `base_dir` is a custom prefix (`--install-dir`); it is not in
+    /// the XDG lookup paths, so neither the desktop database nor `PATH` are
+    /// updated for it
+    pub custom_prefix: bool,
 }
 
 impl InstallConfig {
@@ -38,6 +51,7 @@ impl InstallConfig {
             is_system: false,
             create_desktop_entry: true,
             update_icon_cache: true,
+            custom_prefix: false,
         }
     }
     
@@ -48,6 +62,19 @@ impl InstallConfig {
             is_system: true,
             create_desktop_entry: true,
             update_icon_cache: true,
+            custom_prefix: false,
+        }
+    }
+    
+    /// Create config for installing under `prefix` (e.g. `/opt/myapps`),
+    /// with the same layout as `~/.local`
+    pub fn with_prefix(prefix: PathBuf) -> Self {
+        Self {
+            base_dir: prefix,
+            is_system: false,
+            create_desktop_entry: true,
+            update_icon_cache: false,
+            custom_prefix: true,
         }
     }
     
@@ -66,19 +93,127 @@ impl InstallConfig {
         self.base_dir.join("share").join("icons").join("hicolor")
     }
     
+    /// Get the AppStream metainfo directory path
+    pub fn metainfo_dir(&self) -> PathBuf {
+        self.base_dir.join("share").join("metainfo")
+    }
+    
     /// Get where the app files are installed
     pub fn app_dir(&self, app_id: &str) -> PathBuf {
         self.base_dir.join("share").join(app_id)
     }
+    
+    /// Get the systemd user unit directory; a custom prefix has none
+    pub fn systemd_unit_dir(&self) -> Option<PathBuf> {
+        if self.custom_prefix {
+            None
+        } else if self.is_system {
+            Some(lxe_common::systemd::system_unit_dir(&self.base_dir))
+        } else {
+            lxe_common::systemd::user_unit_dir()
+        }
+    }
+}
+
+/// Installation progress, reported by [`install_with_progress`]
+#[derive(Debug, Clone)]
+pub enum InstallProgress {
+    /// Payload extraction, `fraction` is 0.0 - 1.0
+    Extracting { fraction: f64, current_file: String, files: u32, bytes: u64 },
+    
+    /// Creating the .desktop file
+    InstallingDesktopEntry,
+    
+    /// Installing the app icon (skipped for packages without one)
+    InstallingIcon,
+    
+    /// Adding the bin directory to the user's PATH
+    ConfiguringPath,
+    
+    /// Something that doesn't stop the installation, e.g. an unbundled library
+    Warning { message: String },
+    
+    /// Installation finished; `installed_path` is the app directory
+    Complete { installed_path: PathBuf },
+    
+    /// Installation failed
+    Error { message: String },
 }
 
 /// Perform silent installation (no GUI)
 /// V5 FIX: Now checks polkit authorization before system installs
+/// 
+/// With `for_user`, the caller has already switched to the target user
+/// (see [`TargetUser::switch_to`]); it is recorded in the manifest.
+/// `report` receives the same progress as with [`install_with_progress`],
+/// except for errors.
 pub async fn install_silent(
     payload: &PayloadInfo,
     install_path: &Path,
     is_system: bool,
+    assume_yes: bool,
+    for_user: Option<&TargetUser>,
+    report: &(impl Fn(InstallProgress) + Sync),
+) -> Result<()> {
+    // Deprecated versions need explicit confirmation
+    if payload.metadata.deprecated {
+        eprintln!("⚠️  {} v{} is deprecated by its publisher", payload.metadata.name, payload.metadata.version);
+        if let Some(ref message) = payload.metadata.deprecation_message {
+            eprintln!("   {}", message);
+        }
+        if !assume_yes {
+            anyhow::bail!("Refusing to install a deprecated version. Re-run with --yes to install anyway.");
+        }
+    }
+    
+    // Missing dependencies don't block installing; the app may bundle them
+    for warning in lxe_common::requirements::check_dependencies(&payload.metadata) {
+        eprintln!("⚠️  Missing dependency {}", warning);
+    }
+    
+    let config = install_config(install_path, is_system).await?;
+    install_files(payload, &config, for_user, report).await
+}
+
+/// Install with progress reporting, for applications embedding the
+/// installer without the GTK wizard
+///
+/// Extraction progress is forwarded from the extractor's watch channel, so
+/// intermediate states may be skipped. Failures are reported as
+/// [`InstallProgress::Error`] before the error is returned. Unlike
+/// [`install_silent`], deprecated packages are not refused; check
+/// `payload.metadata.deprecated` before calling if that matters.
+pub async fn install_with_progress<F: Fn(InstallProgress) + Send + 'static>(
+    payload: &PayloadInfo,
+    install_path: &Path,
+    is_system: bool,
+    on_progress: F,
 ) -> Result<()> {
+    if payload.metadata.deprecated {
+        tracing::warn!("{} v{} is deprecated by its publisher", payload.metadata.name, payload.metadata.version);
+    }
+    for warning in lxe_common::requirements::check_dependencies(&payload.metadata) {
+        tracing::warn!("Missing dependency {}", warning);
+    }
+    
+    // `F` is only `Send`; the mutex keeps the returned future `Send` too
+    let on_progress = std::sync::Mutex::new(on_progress);
+    let report = |progress| (on_progress.lock().unwrap())(progress);
+    
+    let result = async {
+        let config = install_config(install_path, is_system).await?;
+        install_files(payload, &config, None, &report).await
+    }.await;
+    
+    if let Err(ref e) = result {
+        report(InstallProgress::Error { message: format!("{:#}", e) });
+    }
+    result
+}
+
+/// Resolve the install target, requesting polkit authorization for
+/// system installs
+async fn install_config(install_path: &Path, is_system: bool) -> Result<InstallConfig> {
     let config = if is_system {
         // V5 FIX: Check/request polkit authorization for system installs
         if !polkit::is_root() {
@@ -89,11 +224,11 @@ pub async fn install_silent(
                     tracing::info!("Polkit authorization granted");
                 }
                 Ok(false) => {
-                    anyhow::bail!(
-                        "Authorization denied. System-wide installation requires administrator privileges.\n\
+                    return Err(anyhow::Error::from(LxeError::AuthorizationDenied)).with_context(|| format!(
+                        "System-wide installation requires administrator privileges.\n\
                          Try running with: pkexec {} --silent --system",
-                        std::env::current_exe()?.display()
-                    );
+                        std::env::current_exe().unwrap_or_default().display()
+                    ));
                 }
                 Err(e) => {
                     // Polkit not available or other error - give helpful message
@@ -110,71 +245,467 @@ pub async fn install_silent(
         }
         InstallConfig::system()
     } else {
-        InstallConfig {
-            base_dir: install_path.to_path_buf(),
-            ..InstallConfig::user_local()
+        let user_local = InstallConfig::user_local();
+        if install_path == user_local.base_dir {
+            user_local
+        } else {
+            InstallConfig::with_prefix(install_path.to_path_buf())
         }
     };
     
+    Ok(config)
+}
+
+/// Fail early if the filesystem of `target` can't hold the app plus 20%
+/// headroom (`metadata.install_size` is the uncompressed payload size)
+pub fn check_disk_space(metadata: &LxeMetadata, target: &Path) -> Result<()> {
+    // The directory may not exist yet on a first install
+    let dir = target.ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(target);
+    let stat = nix::sys::statvfs::statvfs(dir)
+        .with_context(|| format!("Failed to check free space in {}", dir.display()))?;
+    let available = stat.blocks_available() as u64 * stat.fragment_size() as u64;
+    let needed = metadata.install_size.saturating_add(metadata.install_size / 5);
+    
+    if available < needed {
+        return Err(anyhow::Error::from(LxeError::InsufficientDiskSpace { needed, available }))
+            .with_context(|| format!("Cannot install {} to {}", metadata.name, dir.display()));
+    }
+    Ok(())
+}
+
+/// Libraries of the dynamic linker and glibc, which every system has
+const GLIBC_LIBRARIES: &[&str] = &[
+    "ld-linux", "ld64.so", "linux-vdso.so", "linux-gate.so",
+    "libc.so", "libm.so", "libdl.so", "libpthread.so", "librt.so",
+    "libresolv.so", "libutil.so", "libanl.so", "libnsl.so", "libmvec.so",
+];
+
+/// Libraries `exe_path` links against (`DT_NEEDED`) that are neither bundled
+/// anywhere in `app_dir` nor part of glibc
+/// 
+/// Those must come from the host, so the app may not start on systems
+/// without them. Scripts and unreadable files have none.
+pub fn check_linked_libs(exe_path: &Path, app_dir: &Path) -> Vec<String> {
+    let Ok(data) = std::fs::read(exe_path) else {
+        return Vec::new();
+    };
+    let Ok(elf) = goblin::elf::Elf::parse(&data) else {
+        return Vec::new();
+    };
+    
+    let mut bundled = std::collections::HashSet::new();
+    collect_file_names(app_dir, &mut bundled);
+    let mut missing: Vec<String> = elf.libraries.iter()
+        .filter(|lib| !GLIBC_LIBRARIES.iter().any(|prefix| lib.starts_with(prefix)))
+        .filter(|lib| !bundled.contains(std::ffi::OsStr::new(lib)))
+        .map(|lib| lib.to_string())
+        .collect();
+    missing.dedup();
+    missing
+}
+
+/// Names of the files and symlinks below `dir`
+fn collect_file_names(dir: &Path, names: &mut std::collections::HashSet<std::ffi::OsString>) {
+    for entry in std::fs::read_dir(dir).into_iter().flatten().flatten() {
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => collect_file_names(&entry.path(), names),
+            Ok(_) => {
+                names.insert(entry.file_name());
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Warning shown for a library from [`check_linked_libs`]
+pub fn unbundled_lib_warning(lib: &str) -> String {
+    format!("Warning: app links against {} which is not bundled. Installation may fail on some systems.", lib)
+}
+
+/// Refuse a delta package unless the installed version is the release it
+/// was built against, unmodified
+pub fn check_delta_base(metadata: &LxeMetadata) -> Result<()> {
+    use lxe_common::delta::DELTA_FORMAT_VERSION;
+    
+    let Some(ref base) = metadata.delta_base_checksum else {
+        return Ok(());
+    };
+    if metadata.delta_format_version.unwrap_or(DELTA_FORMAT_VERSION) > DELTA_FORMAT_VERSION {
+        anyhow::bail!("This update package needs a newer LXE runtime; install the full package instead");
+    }
+    
+    let installed = crate::manifest::InstallManifest::load(&metadata.app_id).ok().flatten();
+    // The delta is applied to a copy of the installed files, so they must be
+    // exactly the release's
+    if let Some(ref manifest) = installed {
+        let report = manifest.verify_files(true);
+        if !report.is_intact() {
+            anyhow::bail!(
+                "The installed {} was modified ({} file(s) changed, {} missing); install the full v{} package instead",
+                metadata.name,
+                report.modified.len(),
+                report.missing.len(),
+                metadata.version
+            );
+        }
+    }
+    match installed.and_then(|m| m.payload_checksum) {
+        Some(ref checksum) if checksum == base => Ok(()),
+        Some(_) => anyhow::bail!(
+            "This package updates {} from another version than the installed one; install the full v{} package instead",
+            metadata.name,
+            metadata.version
+        ),
+        None => anyhow::bail!(
+            "This package only updates an installed {}; install the full v{} package instead",
+            metadata.name,
+            metadata.version
+        ),
+    }
+}
+
+/// Extract the payload and create desktop integration for `config`
+/// 
+/// Two-phase: the payload is extracted to `<app_dir>.lxe-staging` and only
+/// then moved into place, keeping the previous version aside. If desktop
+/// integration fails, the previous version is restored (a fresh install's
+/// integration files are removed), and the manifest is written last.
+async fn install_files(
+    payload: &PayloadInfo,
+    config: &InstallConfig,
+    for_user: Option<&TargetUser>,
+    report: &(impl Fn(InstallProgress) + Sync),
+) -> Result<()> {
     // Ensure target directory exists
     let target_dir = config.base_dir.join("share");
     fs::create_dir_all(&target_dir).await
         .context("Failed to create installation directory")?;
     
-    // Extract files
-    let (_rx, handle) = extractor::extract_async(payload.clone(), target_dir);
+    check_disk_space(&payload.metadata, &target_dir)?;
+    check_delta_base(&payload.metadata)?;
+    
+    let app_dir = config.app_dir(&payload.metadata.app_id);
+    let staging = with_suffix(&app_dir, STAGING_SUFFIX);
+    
+    // The previous version's directory goes away with the upgrade
+    let backup_dir = crate::manifest::InstallManifest::backup_dir(&payload.metadata.app_id);
+    let previous = crate::manifest::InstallManifest::load(&payload.metadata.app_id).ok().flatten();
+    if let Some(ref previous) = previous {
+        if app_dir.exists() {
+            previous.back_up_configs(&app_dir, &backup_dir)
+                .context("Failed to back up configuration files")?;
+        }
+    }
+    
+    // Extract files, forwarding progress until the extractor finishes
+    let (mut rx, handle) = extractor::extract_async_to(
+        payload.clone(),
+        staging.clone(),
+        Some(app_dir.clone()),
+        CancellationToken::new(),
+    );
+    while rx.changed().await.is_ok() {
+        let progress = rx.borrow_and_update().clone();
+        report(InstallProgress::Extracting {
+            fraction: progress.fraction(),
+            current_file: progress.current_file,
+            files: progress.files_extracted,
+            bytes: progress.extracted_bytes,
+        });
+        if progress.complete || progress.error.is_some() {
+            break;
+        }
+    }
     
     // Wait for extraction to complete
-    handle.await
-        .context("Extraction task failed")??;
+    let extracted = handle.await
+        .context("Extraction task failed")
+        .and_then(|result| result);
+    if let Err(e) = extracted {
+        fs::remove_dir_all(&staging).await.ok();
+        return Err(e);
+    }
+    
+    let staged = StagedInstall::activate(&staging, &app_dir).await?;
+    
+    for lib in check_linked_libs(&app_dir.join(&payload.metadata.exec), &app_dir) {
+        let message = unbundled_lib_warning(&lib);
+        tracing::warn!("{}", message);
+        report(InstallProgress::Warning { message });
+    }
+    let integration_backup = match IntegrationBackup::create(previous.as_ref(), &app_dir, &config.bin_dir()) {
+        Ok(backup) => backup,
+        Err(e) => {
+            staged.rollback().await;
+            return Err(e);
+        }
+    };
+    let mut created = Vec::new();
+    let integrated = integrate(payload, config, for_user, report, &mut created).await
+        // Written last, once everything it lists is in place
+        .and_then(|manifest| manifest.save().context("Failed to save installation manifest"));
+    if let Err(e) = integrated {
+        tracing::warn!("Installation failed, rolling back: {:#}", e);
+        integration_backup.restore(&created);
+        staged.rollback().await;
+        return Err(e);
+    }
+    staged.commit().await;
+    
+    // Only once the install can no longer be rolled back
+    match payload.metadata.service {
+        Some(_) => enable_systemd_service(&payload.metadata, config),
+        None => remove_systemd_service(&payload.metadata.app_id, config).await,
+    }
+    
+    let event = lxe_common::history::HistoryEvent::installed(
+        &payload.metadata.app_id,
+        &payload.metadata.version,
+        config.is_system,
+        previous.as_ref().map(|m| m.version.as_str()),
+    );
+    if let Err(e) = lxe_common::history::append(&event) {
+        tracing::warn!("Could not record the install in the history: {:#}", e);
+    }
+    
+    // After the checksums were recorded, so they are backed up again on uninstall
+    match crate::manifest::InstallManifest::restore_configs(&backup_dir, &app_dir, &payload.metadata.config_paths) {
+        Ok(restored) if !restored.is_empty() => {
+            tracing::info!("Restored {} configuration file(s)", restored.len());
+        }
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Could not restore configuration files from {:?}: {:#}", backup_dir, e),
+    }
+    
+    tracing::info!(
+        "Successfully installed {} v{} to {:?}",
+        payload.metadata.name,
+        payload.metadata.version,
+        config.base_dir
+    );
+    
+    report(InstallProgress::Complete {
+        installed_path: app_dir,
+    });
     
-    // Install lxe-runtime to bin directory for uninstall support
-    let runtime_path = install_runtime_binary(&config).await?;
+    Ok(())
+}
+
+/// Desktop integration of an extracted app; every file written is added
+/// to `created`. Returns the manifest to save.
+async fn integrate(
+    payload: &PayloadInfo,
+    config: &InstallConfig,
+    for_user: Option<&TargetUser>,
+    report: &(impl Fn(InstallProgress) + Sync),
+    created: &mut Vec<PathBuf>,
+) -> Result<crate::manifest::InstallManifest> {
+    // Install lxe-runtime to bin directory for uninstall support; other apps
+    // may use an existing one, which is only ever upgraded
+    let runtime_existed = config.bin_dir().join("lxe-runtime").exists();
+    let runtime_path = install_runtime_binary(config).await?;
+    if !runtime_existed {
+        created.push(runtime_path.clone());
+    }
     
     // Ensure ~/.local/bin is in user's PATH (first install only)
-    if let Err(e) = ensure_path_configured(&config).await {
+    report(InstallProgress::ConfiguringPath);
+    if let Err(e) = ensure_path_configured(config).await {
         tracing::warn!("Could not configure PATH: {}", e);
         // Non-fatal - continue with installation
     }
     
     // Create .desktop file (needs runtime_path for uninstall action)
-    let desktop_path = create_desktop_entry(&payload.metadata, &config, &runtime_path).await?;
+    report(InstallProgress::InstallingDesktopEntry);
+    let desktop_path = create_desktop_entry(&payload.metadata, config, &runtime_path).await?;
+    created.push(desktop_path.clone());
     
     // Create symlink in bin directory
-    let symlink_path = create_bin_symlink(&payload.metadata, &config).await?;
+    let symlink_path = create_bin_symlink(&payload.metadata, config).await?;
+    created.push(symlink_path.clone());
+    
+    // Symlinks and launcher entries for extra executables
+    let extra_paths = install_extra_executables(&payload.metadata, config, &runtime_path).await?;
+    created.extend(extra_paths.iter().cloned());
+    
+    if !config.custom_prefix {
+        update_desktop_database(&config.applications_dir()).await;
+    }
     
-    // Install icon
+    let metainfo_path = install_metainfo(&payload.metadata, config).await?;
+    created.push(metainfo_path.clone());
+    
+    let service_path = create_systemd_service(&payload.metadata, config).await?;
+    created.extend(service_path.iter().cloned());
+    
+    // Install icon (rendered sizes first, so the icon cache update sees them)
+    let icon_sizes = install_icon_sizes(&payload.metadata, config).await?;
+    created.extend(icon_sizes.iter().cloned());
     let icon_path = if payload.metadata.icon.is_some() {
-        install_icon(&payload.metadata, &config).await?
+        report(InstallProgress::InstallingIcon);
+        install_icon(&payload.metadata, config).await?
     } else {
         None
     };
+    created.extend(icon_path.iter().cloned());
     
     // Save manifest for tracking (enables clean uninstall)
     let mut manifest = crate::manifest::InstallManifest::new(
         payload.metadata.app_id.clone(),
         Some(payload.metadata.name.clone()),
         payload.metadata.version.clone(),
-        is_system,
+        config.is_system,
     );
+    manifest.deprecated = payload.metadata.deprecated;
+    manifest.deprecation_message = payload.metadata.deprecation_message.clone();
+    manifest.download_size = Some(payload.payload_size);
+    manifest.signed = payload.metadata.signature.is_some();
+    // A delta leaves the full release installed
+    manifest.payload_checksum = Some(payload.metadata.delta_target_checksum.clone()
+        .unwrap_or_else(|| payload.metadata.payload_checksum.clone()));
     manifest.add_file(&config.app_dir(&payload.metadata.app_id));
     manifest.add_file(&desktop_path);
     manifest.add_file(&symlink_path);
+    manifest.add_file(&metainfo_path);
     manifest.add_file(&runtime_path);
-    if let Some(ref icon) = icon_path {
-        manifest.add_file(icon);
+    for path in extra_paths.iter().chain(&icon_sizes).chain(&icon_path) {
+        manifest.add_file(path);
+    }
+    if let Some(ref service) = service_path {
+        manifest.add_file(service);
+    }
+    manifest.installed_for_user = for_user.map(|u| u.name.clone());
+    manifest.prefix = config.custom_prefix.then(|| config.base_dir.clone());
+    manifest.exec = Some(payload.metadata.exec.clone());
+    // Checksums for `lxe verify-install`; an install without them still works
+    if let Err(e) = manifest.record_files(&config.app_dir(&payload.metadata.app_id)) {
+        tracing::warn!("Could not record file checksums: {:#}", e);
+    }
+    manifest.set_config_paths(&config.app_dir(&payload.metadata.app_id), &payload.metadata.config_paths);
+    Ok(manifest)
+}
+
+/// Copies of the previous version's integration files (desktop entries,
+/// icons, links, ...), so a failed update can put them back
+struct IntegrationBackup {
+    /// Keeps the copies until the install is over
+    _dir: Option<tempfile::TempDir>,
+    /// Original path and its copy
+    files: Vec<(PathBuf, PathBuf)>,
+}
+
+impl IntegrationBackup {
+    /// Copy the files `previous` recorded outside `app_dir`, except for the
+    /// shared lxe-runtime in `bin_dir`
+    fn create(previous: Option<&crate::manifest::InstallManifest>, app_dir: &Path, bin_dir: &Path) -> Result<Self> {
+        let Some(previous) = previous else {
+            return Ok(Self { _dir: None, files: Vec::new() });
+        };
+        let dir = tempfile::Builder::new().prefix("lxe-integration-").tempdir()
+            .context("Failed to back up the current desktop integration")?;
+        let runtime = bin_dir.join("lxe-runtime");
+        let mut files = Vec::new();
+        for (i, path) in previous.files.iter().map(PathBuf::from).enumerate() {
+            if path.starts_with(app_dir) || path == runtime {
+                continue;
+            }
+            let Ok(metadata) = std::fs::symlink_metadata(&path) else {
+                continue;
+            };
+            let copy = dir.path().join(i.to_string());
+            if metadata.is_symlink() {
+                std::os::unix::fs::symlink(std::fs::read_link(&path)?, &copy)?;
+            } else if metadata.is_file() {
+                std::fs::copy(&path, &copy)
+                    .with_context(|| format!("Failed to back up {}", path.display()))?;
+            } else {
+                continue;
+            }
+            files.push((path, copy));
+        }
+        Ok(Self { _dir: Some(dir), files })
     }
-    manifest.save().await
-        .context("Failed to save installation manifest")?;
     
-    tracing::info!(
-        "Successfully installed {} v{} to {:?}",
-        payload.metadata.name,
-        payload.metadata.version,
-        config.base_dir
-    );
+    /// Remove the files in `created` and put the copied ones back
+    fn restore(self, created: &[PathBuf]) {
+        for path in created {
+            std::fs::remove_file(path).ok();
+        }
+        for (path, copy) in &self.files {
+            std::fs::remove_file(path).ok();
+            // The copies may be on another file system, so they aren't renamed
+            let restored = match std::fs::read_link(copy) {
+                Ok(target) => std::os::unix::fs::symlink(target, path),
+                Err(_) => std::fs::copy(copy, path).map(|_| ()),
+            };
+            if let Err(e) = restored {
+                tracing::warn!("Could not restore {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// An extracted app directory moved into place, with the previous version
+/// kept aside until the installation is committed or rolled back
+struct StagedInstall {
+    app_dir: PathBuf,
+    backup: Option<PathBuf>,
+}
+
+impl StagedInstall {
+    /// Move `staging` to `app_dir`, keeping an existing `app_dir` as backup
+    async fn activate(staging: &Path, app_dir: &Path) -> Result<Self> {
+        let backup = if app_dir.exists() {
+            let backup = with_suffix(app_dir, BACKUP_SUFFIX);
+            if backup.exists() {
+                fs::remove_dir_all(&backup).await?;
+            }
+            fs::rename(app_dir, &backup).await
+                .with_context(|| format!("Failed to move the previous version aside: {}", app_dir.display()))?;
+            Some(backup)
+        } else {
+            None
+        };
+        
+        if let Err(e) = fs::rename(staging, app_dir).await {
+            if let Some(ref backup) = backup {
+                fs::rename(backup, app_dir).await.ok();
+            }
+            fs::remove_dir_all(staging).await.ok();
+            return Err(e).context("Failed to move extracted files to final location");
+        }
+        Ok(Self { app_dir: app_dir.to_path_buf(), backup })
+    }
     
-    Ok(())
+    /// Drop the previous version
+    async fn commit(self) {
+        if let Some(backup) = self.backup {
+            if let Err(e) = fs::remove_dir_all(&backup).await {
+                tracing::warn!("Could not remove the previous version at {}: {}", backup.display(), e);
+            }
+        }
+    }
+    
+    /// Remove the new version and restore the previous one
+    async fn rollback(self) {
+        if let Err(e) = fs::remove_dir_all(&self.app_dir).await {
+            tracing::warn!("Could not remove {}: {}", self.app_dir.display(), e);
+        }
+        if let Some(backup) = self.backup {
+            if let Err(e) = fs::rename(&backup, &self.app_dir).await {
+                tracing::warn!("Could not restore the previous version from {}: {}", backup.display(), e);
+            }
+        }
+    }
+}
+
+/// `path` with `suffix` appended to its file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
 }
 
 /// Install the runtime binary to the bin directory for persistent uninstall support
@@ -220,8 +751,9 @@ pub async fn install_runtime_to_bin(config: &InstallConfig) -> Result<PathBuf> {
 /// 
 /// Returns true if shell config was modified (user needs to restart terminal)
 pub async fn ensure_path_configured(config: &InstallConfig) -> Result<bool> {
-    // Skip for system installs (system bins are already in PATH)
-    if config.is_system {
+    // Skip for system installs (system bins are already in PATH) and custom
+    // prefixes (the user manages their own PATH)
+    if config.is_system || config.custom_prefix {
         return Ok(false);
     }
     
@@ -295,62 +827,53 @@ pub async fn create_desktop_entry(
     metadata: &LxeMetadata,
     config: &InstallConfig,
     runtime_path: &PathBuf,
+) -> Result<PathBuf> {
+    write_desktop_entry(metadata, &metadata.desktop_filename(), config, runtime_path).await
+}
+
+/// Create the bin symlinks and launcher entries of the package's extra
+/// executables; returns the created paths
+pub async fn install_extra_executables(
+    metadata: &LxeMetadata,
+    config: &InstallConfig,
+    runtime_path: &PathBuf,
+) -> Result<Vec<PathBuf>> {
+    let mut created = Vec::new();
+    for extra in &metadata.extra_executables {
+        // Re-validated: metadata comes from the file
+        if let Err(e) = extra.validate() {
+            tracing::warn!("Skipping extra executable: {}", e);
+            continue;
+        }
+        
+        let extra_metadata = metadata.for_extra_executable(extra);
+        created.push(create_bin_symlink(&extra_metadata, config).await?);
+        if extra.desktop_entry {
+            let file_name = extra.desktop_filename(&metadata.app_id);
+            created.push(write_desktop_entry(&extra_metadata, &file_name, config, runtime_path).await?);
+        }
+    }
+    Ok(created)
+}
+
+/// Write `metadata`'s launcher entry to `file_name` in the applications dir
+async fn write_desktop_entry(
+    metadata: &LxeMetadata,
+    file_name: &str,
+    config: &InstallConfig,
+    runtime_path: &PathBuf,
 ) -> Result<PathBuf> {
     let desktop_dir = config.applications_dir();
     fs::create_dir_all(&desktop_dir).await?;
     
-    let desktop_path = desktop_dir.join(metadata.desktop_filename());
-    
-    let exec_path = config.app_dir(&metadata.app_id).join(&metadata.exec);
+    let desktop_path = desktop_dir.join(file_name);
     
-    // FIX: Use absolute path to icon instead of relying on icon theme lookup
-    // This is more reliable and doesn't require gtk-update-icon-cache
-    let icon_value = if let Some(ref icon_filename) = metadata.icon {
-        let icon_path = config.app_dir(&metadata.app_id).join(icon_filename);
-        if icon_path.exists() {
-            // Use absolute path - most reliable method
-            icon_path.display().to_string()
-        } else {
-            // Fallback to app_id for theme lookup
-            metadata.app_id.clone()
-        }
-    } else {
-        metadata.app_id.clone()
-    };
-    
-    let terminal = if metadata.terminal { "true" } else { "false" };
-    
-    let content = format!(
-        r#"[Desktop Entry]
-Type=Application
-Name={name}
-Comment={comment}
-Exec={exec}
-Icon={icon}
-Terminal={terminal}
-Categories={categories}
-StartupWMClass={wm_class}
-X-LXE-Version={version}
-X-LXE-AppId={app_id}
-Actions=Uninstall;
-
-[Desktop Action Uninstall]
-Name=Uninstall {name}
-Exec={runtime_path} --uninstall-gui {app_id}
-"#,
-        name = metadata.name,
-        comment = metadata.description.as_deref().unwrap_or(&metadata.name),
-        exec = exec_path.display(),
-        icon = icon_value,
-        terminal = terminal,
-        categories = metadata.categories_string(),
-        // Use custom wm_class if provided, otherwise derive from app_id
-        wm_class = metadata.wm_class.as_deref()
-            .unwrap_or_else(|| metadata.app_id.split('.').last().unwrap_or(&metadata.name)),
-        version = metadata.version,
-        app_id = metadata.app_id,
-        // Use the installed runtime path for uninstall action
-        runtime_path = runtime_path.display(),
+    let existing = fs::read_to_string(&desktop_path).await.ok();
+    let content = desktop::render_desktop_entry(
+        metadata,
+        &config.app_dir(&metadata.app_id),
+        runtime_path,
+        existing.as_deref(),
     );
     
     fs::write(&desktop_path, content).await
@@ -414,14 +937,20 @@ pub async fn install_icon(
         return Ok(None);
     }
     
-    // Determine icon size from filename or default to scalable
+    // SVGs are scalable; PNGs go to the size in their header
     let (size_dir, extension) = if icon_relative.ends_with(".svg") {
         ("scalable", "svg")
-    } else if icon_relative.ends_with(".png") {
-        // Try to detect size from filename like "icon-48x48.png"
-        ("48x48", "png")
     } else {
-        ("48x48", "png")
+        let mut header = [0u8; 24];
+        let header = match fs::File::open(&source_icon).await {
+            Ok(mut file) => {
+                use tokio::io::AsyncReadExt;
+                let len = file.read(&mut header).await.unwrap_or(0);
+                &header[..len]
+            }
+            Err(_) => &header[..0],
+        };
+        (lxe_common::paths::icons::size_dir_for_png(header), "png")
     };
     
     let icon_dir = config.icons_dir()
@@ -443,6 +972,128 @@ pub async fn install_icon(
     Ok(Some(target_icon))
 }
 
+/// Install the PNGs `lxe build` rendered for `[build] generate_icon_sizes`
+/// (`icons/hicolor/<size>/apps/` in the app directory), whatever their sizes
+pub async fn install_icon_sizes(
+    metadata: &LxeMetadata,
+    config: &InstallConfig,
+) -> Result<Vec<PathBuf>> {
+    let Some(stem) = metadata.icon.as_deref().and_then(|i| Path::new(i).file_stem()) else {
+        return Ok(Vec::new());
+    };
+    let rendered = config.app_dir(&metadata.app_id).join("icons/hicolor");
+    let mut sizes = Vec::new();
+    if let Ok(mut entries) = fs::read_dir(&rendered).await {
+        while let Some(entry) = entries.next_entry().await? {
+            sizes.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    sizes.sort();
+    
+    let mut installed = Vec::new();
+    for size in sizes {
+        let source = rendered.join(&size).join("apps").join(format!("{}.png", stem.to_string_lossy()));
+        if !source.exists() {
+            continue;
+        }
+        let icon_dir = config.icons_dir().join(size).join("apps");
+        fs::create_dir_all(&icon_dir).await?;
+        let target = icon_dir.join(format!("{}.png", metadata.app_id));
+        fs::copy(&source, &target).await
+            .context("Failed to install icon")?;
+        installed.push(target);
+    }
+    Ok(installed)
+}
+
+/// Write the AppStream metainfo file, so software centers list the app
+pub async fn install_metainfo(metadata: &LxeMetadata, config: &InstallConfig) -> Result<PathBuf> {
+    let dir = config.metainfo_dir();
+    fs::create_dir_all(&dir).await
+        .context("Failed to create metainfo directory")?;
+    let path = dir.join(lxe_common::appstream::metainfo_filename(&metadata.app_id));
+    fs::write(&path, lxe_common::appstream::generate_metainfo(metadata)).await
+        .context("Failed to write AppStream metainfo")?;
+    
+    if !config.custom_prefix {
+        refresh_appstream_cache().await;
+    }
+    Ok(path)
+}
+
+/// Write the `[service]` of the package as a systemd user unit; returns the
+/// unit file, if the package has a service. It is enabled by
+/// [`enable_systemd_service`] once the install succeeded.
+pub async fn create_systemd_service(metadata: &LxeMetadata, config: &InstallConfig) -> Result<Option<PathBuf>> {
+    let Some(ref service) = metadata.service else {
+        return Ok(None);
+    };
+    let Some(dir) = config.systemd_unit_dir() else {
+        tracing::info!("Not installing the systemd service of {} under a custom prefix", metadata.app_id);
+        return Ok(None);
+    };
+    // Re-validated: metadata comes from the file
+    service.validate().context("Invalid [service] in package metadata")?;
+    
+    fs::create_dir_all(&dir).await
+        .context("Failed to create systemd unit directory")?;
+    let unit = lxe_common::systemd::unit_name(&metadata.app_id);
+    let path = dir.join(&unit);
+    let content = lxe_common::systemd::render_unit(metadata, service, &config.app_dir(&metadata.app_id));
+    fs::write(&path, content).await
+        .context("Failed to write systemd unit")?;
+    Ok(Some(path))
+}
+
+/// Enable the unit written by [`create_systemd_service`]
+/// 
+/// Failing to enable the unit (no systemd, no user session) only warns: the
+/// unit is picked up at the next login's daemon-reload.
+fn enable_systemd_service(metadata: &LxeMetadata, config: &InstallConfig) {
+    if config.systemd_unit_dir().is_none() {
+        return;
+    }
+    let unit = lxe_common::systemd::unit_name(&metadata.app_id);
+    // Root can't reach a user's systemd instance
+    if !config.is_system && polkit::is_root() {
+        tracing::info!("Installed {}; it is enabled with `systemctl --user enable {}`", unit, unit);
+    } else if let Err(e) = lxe_common::systemd::enable(&unit, config.is_system) {
+        tracing::warn!("Could not enable {}: {:#}", unit, e);
+    }
+}
+
+/// Disable and remove the app's unit, if it has one; on uninstall, and when
+/// an update drops the `[service]`
+async fn remove_systemd_service(app_id: &str, config: &InstallConfig) {
+    let unit = lxe_common::systemd::unit_name(app_id);
+    let Some(unit_path) = config.systemd_unit_dir().map(|dir| dir.join(&unit)) else {
+        return;
+    };
+    if !unit_path.exists() {
+        return;
+    }
+    if let Err(e) = lxe_common::systemd::disable(&unit, config.is_system) {
+        tracing::warn!("Could not disable {}: {:#}", unit, e);
+    }
+    tracing::info!("Removing systemd unit: {:?}", unit_path);
+    fs::remove_file(&unit_path).await.ok();
+    if !config.is_system {
+        lxe_common::systemd::reload_user().ok();
+    }
+}
+
+/// Refresh the AppStream cache (best effort; appstreamcli is optional)
+async fn refresh_appstream_cache() {
+    let output = tokio::process::Command::new("appstreamcli")
+        .arg("refresh-cache")
+        .output()
+        .await;
+    
+    if let Err(e) = output {
+        tracing::debug!("Could not run appstreamcli: {}", e);
+    }
+}
+
 /// Update the GTK icon cache
 async fn update_icon_cache(icons_dir: &Path) -> Result<()> {
     let output = tokio::process::Command::new("gtk-update-icon-cache")
@@ -468,6 +1119,19 @@ async fn update_icon_cache(icons_dir: &Path) -> Result<()> {
     }
 }
 
+/// Refresh the MIME cache of the desktop entries in `applications_dir`
+/// (best effort, like the icon cache)
+async fn update_desktop_database(applications_dir: &Path) {
+    let output = tokio::process::Command::new("update-desktop-database")
+        .arg(applications_dir)
+        .output()
+        .await;
+    
+    if let Err(e) = output {
+        tracing::debug!("Could not run update-desktop-database: {}", e);
+    }
+}
+
 /// Uninstall an application
 /// 
 /// SAFETY: This function validates paths before deletion to prevent
@@ -487,7 +1151,8 @@ pub async fn uninstall(
                 tracing::info!("Polkit authorization granted for uninstall");
             }
             Ok(false) => {
-                anyhow::bail!("Authorization denied for system uninstall");
+                return Err(anyhow::Error::from(LxeError::AuthorizationDenied))
+                    .context("System-wide uninstall requires administrator privileges");
             }
             Err(e) => {
                 anyhow::bail!("Could not request uninstall authorization: {}", e);
@@ -495,6 +1160,12 @@ pub async fn uninstall(
         }
     }
     
+    // Extra launcher entries are recorded in the manifest; read it first
+    let manifest = crate::manifest::InstallManifest::load(app_id).ok().flatten();
+    
+    // Stop the service before its program goes away
+    remove_systemd_service(app_id, config).await;
+    
     // Remove app directory with SAFETY CHECK
     let app_dir = config.app_dir(app_id);
     if app_dir.exists() {
@@ -506,6 +1177,15 @@ pub async fn uninstall(
             );
         }
         
+        // Modified configuration files are kept for a later reinstall
+        if let Some(ref manifest) = manifest {
+            let backed_up = manifest.back_up_configs(&app_dir, &crate::manifest::InstallManifest::backup_dir(app_id))
+                .context("Failed to back up configuration files")?;
+            if !backed_up.is_empty() {
+                tracing::info!("Backed up {} configuration file(s)", backed_up.len());
+            }
+        }
+        
         tracing::info!("Removing app directory: {:?}", app_dir);
         fs::remove_dir_all(&app_dir).await
             .context("Failed to remove application directory")?;
@@ -518,6 +1198,12 @@ pub async fn uninstall(
         fs::remove_file(&desktop_file).await
             .context("Failed to remove .desktop file")?;
     }
+    for entry in manifest.iter().flat_map(|m| m.desktop_entries(&config.applications_dir())) {
+        if entry.exists() {
+            tracing::info!("Removing desktop entry: {:?}", entry);
+            fs::remove_file(&entry).await.ok();
+        }
+    }
     
     // Remove bin symlinks - find any symlinks pointing to this app's directory
     let bin_dir = config.bin_dir();
@@ -539,27 +1225,33 @@ pub async fn uninstall(
         }
     }
     
-    // Remove icon (all sizes) - using paths module
-    for size in lxe_common::paths::icons::SIZES {
-        let icon_dir = config.icons_dir().join(size).join("apps");
-        for ext in ["svg", "png"] {
-            let icon_path = icon_dir.join(format!("{}.{}", app_id, ext));
-            if icon_path.exists() {
-                tracing::info!("Removing icon: {:?}", icon_path);
-                fs::remove_file(&icon_path).await.ok();
-            }
-        }
+    let metainfo = config.metainfo_dir().join(lxe_common::appstream::metainfo_filename(app_id));
+    if metainfo.exists() {
+        tracing::info!("Removing AppStream metainfo: {:?}", metainfo);
+        fs::remove_file(&metainfo).await.ok();
     }
     
-    // Also check scalable
-    let scalable_dir = config.icons_dir().join("scalable").join("apps");
-    for ext in ["svg", "png"] {
-        let icon_path = scalable_dir.join(format!("{}.{}", app_id, ext));
+    // Remove icon (all sizes and scalable, and other recorded sizes) - using paths module
+    let recorded_icons = manifest.iter()
+        .flat_map(|m| m.files.iter().map(PathBuf::from))
+        .filter(|f| f.starts_with(config.icons_dir()));
+    let standard_icons = lxe_common::paths::icons::SIZES.iter().chain(&["scalable"])
+        .flat_map(|size| ["svg", "png"].map(|ext| config.icons_dir().join(size).join("apps").join(format!("{}.{}", app_id, ext))));
+    for icon_path in recorded_icons.chain(standard_icons) {
         if icon_path.exists() {
+            tracing::info!("Removing icon: {:?}", icon_path);
             fs::remove_file(&icon_path).await.ok();
         }
     }
     
+    let version = manifest.as_ref().map(|m| m.version.as_str()).unwrap_or("unknown");
+    let event = lxe_common::history::HistoryEvent::new(
+        lxe_common::history::HistoryEventKind::Uninstall, app_id, version, config.is_system,
+    );
+    if let Err(e) = lxe_common::history::append(&event) {
+        tracing::warn!("Could not record the uninstall in the history: {:#}", e);
+    }
+    
     tracing::info!("Uninstallation complete for {}", app_id);
     
     Ok(())
@@ -576,6 +1268,7 @@ mod tests {
             is_system: false,
             create_desktop_entry: true,
             update_icon_cache: true,
+            custom_prefix: false,
         };
         
         assert_eq!(
@@ -590,5 +1283,143 @@ mod tests {
             config.app_dir("com.example.App"),
             PathBuf::from("/home/user/.local/share/com.example.App")
         );
+        
+        let prefix = InstallConfig::with_prefix(PathBuf::from("/opt/myapps"));
+        assert!(!prefix.is_system && prefix.custom_prefix);
+        assert_eq!(prefix.applications_dir(), PathBuf::from("/opt/myapps/share/applications"));
+        assert_eq!(prefix.icons_dir(), PathBuf::from("/opt/myapps/share/icons/hicolor"));
+        assert_eq!(prefix.bin_dir(), PathBuf::from("/opt/myapps/bin"));
+    }
+    
+    #[test]
+    fn test_staged_install() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("com.example.App");
+        let staging = with_suffix(&app_dir, STAGING_SUFFIX);
+        let stage = |version: &str| {
+            std::fs::create_dir_all(&staging).unwrap();
+            std::fs::write(staging.join("version"), version).unwrap();
+        };
+        let installed = || std::fs::read_to_string(app_dir.join("version")).unwrap();
+        
+        runtime.block_on(async {
+            stage("1");
+            let staged = StagedInstall::activate(&staging, &app_dir).await.unwrap();
+            assert!(staged.backup.is_none());
+            staged.commit().await;
+            assert_eq!(installed(), "1");
+            
+            // A failed update restores the previous version
+            stage("2");
+            let staged = StagedInstall::activate(&staging, &app_dir).await.unwrap();
+            assert_eq!(installed(), "2");
+            staged.rollback().await;
+            assert_eq!(installed(), "1");
+            
+            stage("2");
+            StagedInstall::activate(&staging, &app_dir).await.unwrap().commit().await;
+            assert_eq!(installed(), "2");
+            assert!(!staging.exists() && !with_suffix(&app_dir, BACKUP_SUFFIX).exists());
+        });
+    }
+    
+    #[test]
+    fn test_check_linked_libs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("run.sh"), "#!/bin/sh\n").unwrap();
+        assert!(check_linked_libs(&dir.path().join("run.sh"), dir.path()).is_empty());
+        assert!(check_linked_libs(&dir.path().join("missing"), dir.path()).is_empty());
+        
+        // The test binary itself links against glibc at least, which is never reported
+        let exe = std::env::current_exe().unwrap();
+        let missing = check_linked_libs(&exe, dir.path());
+        assert!(missing.iter().all(|lib| !lib.starts_with("libc.so") && !lib.starts_with("ld-linux")), "{:?}", missing);
+        
+        // Bundling a library (anywhere in the app dir) silences it
+        if let Some(lib) = missing.first() {
+            std::fs::create_dir_all(dir.path().join("lib")).unwrap();
+            std::fs::write(dir.path().join("lib").join(lib), b"").unwrap();
+            assert!(!check_linked_libs(&exe, dir.path()).contains(lib));
+        }
+        assert!(unbundled_lib_warning("libfoo.so.3").starts_with("Warning: app links against libfoo.so.3 which is not bundled."));
+    }
+    
+    #[test]
+    fn test_check_disk_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut metadata = LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 1024, "");
+        assert!(check_disk_space(&metadata, &dir.path().join("not/created/yet")).is_ok());
+        
+        metadata.install_size = u64::MAX / 2;
+        let message = check_disk_space(&metadata, dir.path()).unwrap_err().to_string();
+        assert!(message.contains(&dir.path().display().to_string()) && message.contains("MB"), "{}", message);
+    }
+    
+    #[test]
+    fn test_integrate_records_files_once() {
+        let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let config = InstallConfig::with_prefix(dir.path().to_path_buf());
+        let mut metadata = LxeMetadata::new("com.example.App", "App", "1.0.0", "app", 0, "");
+        metadata.icon = Some("icon.png".into());
+        
+        // Not a real PNG, so the main icon lands in the fallback size, which
+        // was also rendered
+        let app_dir = config.app_dir(&metadata.app_id);
+        std::fs::create_dir_all(&app_dir).unwrap();
+        std::fs::write(app_dir.join("icon.png"), b"png").unwrap();
+        for size in [lxe_common::paths::icons::FALLBACK_SIZE, "128x128"] {
+            let rendered = app_dir.join("icons/hicolor").join(size).join("apps");
+            std::fs::create_dir_all(&rendered).unwrap();
+            std::fs::write(rendered.join("icon.png"), b"png").unwrap();
+        }
+        
+        let payload = PayloadInfo {
+            metadata,
+            payload_offset: 0,
+            payload_size: 0,
+            exe_path: PathBuf::new(),
+            header_offset: 0,
+            footer_version: 1,
+            footer_ext: Default::default(),
+        };
+        let mut created = Vec::new();
+        let manifest = runtime.block_on(integrate(&payload, &config, None, &|_: InstallProgress| {}, &mut created)).unwrap();
+        
+        let unique: std::collections::HashSet<_> = manifest.files.iter().collect();
+        assert_eq!(unique.len(), manifest.files.len(), "{:?}", manifest.files);
+        let icon = config.icons_dir().join("128x128/apps/com.example.App.png");
+        assert!(manifest.files.contains(&icon.display().to_string()), "{:?}", manifest.files);
+    }
+    
+    #[test]
+    fn test_integration_backup_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let app_dir = dir.path().join("share/com.example.App");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        std::fs::write(app_dir.join("app"), b"v1").unwrap();
+        let desktop = dir.path().join("com.example.App.desktop");
+        std::fs::write(&desktop, b"Name=v1").unwrap();
+        let link = dir.path().join("app");
+        std::os::unix::fs::symlink(app_dir.join("app"), &link).unwrap();
+        
+        let mut previous = crate::manifest::InstallManifest::new("com.example.App".into(), None, "1.0.0".into(), false);
+        previous.add_file(&app_dir);
+        previous.add_file(&desktop);
+        previous.add_file(&link);
+        let backup = IntegrationBackup::create(Some(&previous), &app_dir, &dir.path().join("bin")).unwrap();
+        assert_eq!(backup.files.len(), 2);
+        
+        // The failed update rewrote the entry and added an icon
+        std::fs::write(&desktop, b"Name=v2").unwrap();
+        let icon = dir.path().join("com.example.App.png");
+        std::fs::write(&icon, b"png").unwrap();
+        std::fs::remove_file(&link).unwrap();
+        backup.restore(&[desktop.clone(), icon.clone(), link.clone()]);
+        
+        assert_eq!(std::fs::read(&desktop).unwrap(), b"Name=v1");
+        assert!(!icon.exists());
+        assert_eq!(std::fs::read_link(&link).unwrap(), app_dir.join("app"));
     }
 }
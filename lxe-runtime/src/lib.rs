@@ -5,16 +5,78 @@
 //! - Async extraction
 //! - GUI wizard
 //! - Installation state detection
+//!
+//! # Embedding the extractor
+//!
+//! [`extractor`] unpacks a package's payload without the wizard. Pick the
+//! API that fits the caller:
+//!
+//! ```no_run
+//! use lxe_runtime::extractor::{self, ProgressStream};
+//! use lxe_runtime::payload::read_payload_info;
+//! use std::path::Path;
+//! use tokio_stream::StreamExt;
+//! use tokio_util::sync::CancellationToken;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let payload = read_payload_info(Path::new("app.lxe"))?;
+//! let target = Path::new("/tmp/apps").to_path_buf();
+//!
+//! // Callback after every extracted file
+//! extractor::extract_with_callback(payload.clone(), target.clone(), |progress| {
+//!     println!("{} files, {:.0}%", progress.files_extracted, progress.fraction() * 100.0);
+//! }).await??;
+//!
+//! // Watch channel, consumed as a stream (may skip intermediate states)
+//! let cancel = CancellationToken::new();
+//! let (rx, handle) = extractor::extract_async(payload.clone(), target.clone(), cancel.clone());
+//! let mut updates = ProgressStream::from(rx);
+//! while let Some(progress) = updates.next().await {
+//!     if progress.complete {
+//!         break;
+//!     }
+//! }
+//! handle.await??;
+//! # Ok(())
+//! # }
+//!
+//! // Outside of async code
+//! # fn blocking() -> anyhow::Result<()> {
+//! # let payload = read_payload_info(Path::new("app.lxe"))?;
+//! extractor::extract_blocking(&payload, Path::new("/tmp/apps"))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! For a full install (desktop entry, icon, PATH) with progress reporting,
+//! use [`installer::install_with_progress`]:
+//!
+//! ```no_run
+//! use lxe_runtime::installer::{self, InstallProgress};
+//! # use lxe_runtime::payload::read_payload_info;
+//! # use std::path::Path;
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! # let payload = read_payload_info(Path::new("app.lxe"))?;
+//! let home = dirs::home_dir().unwrap().join(".local");
+//! installer::install_with_progress(&payload, &home, false, |progress| match progress {
+//!     InstallProgress::Extracting { fraction, .. } => println!("{:.0}%", fraction * 100.0),
+//!     InstallProgress::Complete { installed_path } => println!("Installed to {}", installed_path.display()),
+//!     other => println!("{:?}", other),
+//! }).await?;
+//! # Ok(())
+//! # }
+//! ```
 
 // Re-export from lxe-common for convenience
-pub use lxe_common::{config, metadata, signing, paths, payload};
+pub use lxe_common::{config, manifest, metadata, signing, paths, payload, transaction};
 
 // Runtime-specific modules
 pub mod installer;
 pub mod extractor;
 pub mod polkit;
 pub mod state;
+pub mod target_user;
 pub mod ui;
-pub mod manifest;
 pub mod libloader;
 
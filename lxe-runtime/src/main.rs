@@ -13,8 +13,24 @@ use tracing::{info, Level};
 use tracing_subscriber::FmtSubscriber;
 
 // Import from the runtime library crate
-use lxe_runtime::{installer, libloader, manifest, polkit, state, ui};
+use lxe_runtime::{extractor, installer, libloader, manifest, polkit, state, ui};
+use lxe_runtime::target_user::TargetUser;
 use lxe_common::{paths, payload};
+use lxe_common::config::{GlobalConfig, SignaturePolicy};
+use lxe_common::error::LxeError;
+use lxe_common::metadata::LxeMetadata;
+use lxe_common::payload_format::{EMPTY_META_STUB, META_STUB_SIZE};
+use lxe_common::progress::{ProgressEvent, ProgressSocket};
+use std::sync::Mutex;
+
+/// Package layout stub, filled in by `lxe build` (see `payload_format`)
+#[used]
+#[link_section = "__lxe_meta_stub"]
+static META_STUB: [u8; META_STUB_SIZE] = EMPTY_META_STUB;
+
+/// Version marker `lxe build` reads without running the runtime
+#[used]
+static VERSION_MARKER: &str = concat!("lxe-runtime/", env!("CARGO_PKG_VERSION"), "\0");
 
 /// LXE Runtime - Linux Executable Environment Installer
 #[derive(Parser, Debug)]
@@ -26,22 +42,34 @@ struct Args {
     #[arg(long)]
     silent: bool,
 
-    /// Custom installation directory
-    #[arg(long, value_name = "DIR")]
+    /// Install under a custom prefix (e.g. /opt/myapps) instead of ~/.local
+    #[arg(long, value_name = "DIR", conflicts_with = "system")]
     install_dir: Option<PathBuf>,
 
     /// Install system-wide (requires root/polkit)
     #[arg(long)]
     system: bool,
 
+    /// Install into another user's home (requires root)
+    #[arg(long, value_name = "USERNAME", requires = "silent", conflicts_with = "system")]
+    for_user: Option<String>,
+
     /// Measure and display startup time (for benchmarking)
     #[arg(long, hide = true)]
     measure_startup: bool,
 
+    /// Extract one file at a time (for debugging extraction problems)
+    #[arg(long, hide = true)]
+    sequential_extract: bool,
+
     /// Force reinstall even if already installed
     #[arg(long)]
     force: bool,
     
+    /// Confirm prompts in silent mode (e.g., installing a deprecated version)
+    #[arg(short, long)]
+    yes: bool,
+    
     /// Install the polkit policy file (requires root)
     /// Run this once before using --system flag
     #[arg(long)]
@@ -58,6 +86,15 @@ struct Args {
     /// List all installed LXE applications
     #[arg(long)]
     list: bool,
+    
+    /// After a silent install, run the app under Xvfb to detect its WM_CLASS
+    #[arg(long)]
+    detect_wmclass: bool,
+
+    /// Report silent install progress as JSON lines to the Unix socket a
+    /// reader (e.g. lxe-progress-reader) listens on
+    #[arg(long, value_name = "PATH", requires = "silent")]
+    progress_socket: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -79,6 +116,13 @@ fn main() -> Result<()> {
             .try_init();
     }
 
+    if args.sequential_extract {
+        extractor::set_sequential(true);
+    }
+    if let Ok(config) = GlobalConfig::load() {
+        extractor::configure(&config.extractor);
+    }
+
     // Benchmark mode
     if args.measure_startup {
         let elapsed = startup_time.elapsed();
@@ -119,6 +163,10 @@ fn main() -> Result<()> {
     let payload_info = match payload::read_payload_info(&exe_path) {
         Ok(info) => {
             info!("Package: {} v{}", info.metadata.name, info.metadata.version);
+            // Package found but this system is too old for it - tell the user
+            if let Err(err) = lxe_common::requirements::check_system_requirements(&info.metadata) {
+                return report_requirement_error(&err, args.silent);
+            }
             Some(info)
         }
         Err(e) => {
@@ -129,8 +177,13 @@ fn main() -> Result<()> {
     };
 
     if args.silent {
-        // Silent installation mode
-        run_silent_install(args, payload_info)
+        // Silent installation mode; an install that's already done is not a failure
+        let result = run_silent_install(args, payload_info);
+        if let Some(err @ LxeError::AlreadyInstalled { .. }) = result.as_ref().err().and_then(|e| e.downcast_ref::<LxeError>()) {
+            println!("ℹ️  {} (use --force to reinstall)", err);
+            return Ok(());
+        }
+        result
     } else {
         // Launch GTK4 GUI
         run_gui(args, payload_info, startup_time)
@@ -174,9 +227,42 @@ fn run_silent_install(
     args: Args,
     payload_info: Option<payload::PayloadInfo>,
 ) -> Result<()> {
-    let payload = payload_info.ok_or_else(|| {
-        anyhow::anyhow!("No payload embedded. Cannot run silent install on packer binary.")
-    })?;
+    let progress = args.progress_socket.as_deref()
+        .map(ProgressSocket::connect)
+        .transpose()?
+        .map(Mutex::new);
+    let send = |event: ProgressEvent| {
+        if let Some(ref socket) = progress {
+            socket.lock().unwrap().send(&event);
+        }
+    };
+
+    let result = payload_info
+        .ok_or_else(|| anyhow::anyhow!("No payload embedded. Cannot run silent install on packer binary."))
+        .and_then(|payload| {
+            send(ProgressEvent::Start {
+                name: payload.metadata.name.clone(),
+                total_bytes: payload.metadata.install_size,
+            });
+            silent_install(&args, &payload, &send)
+        });
+
+    // Already installed counts as success (see `main`)
+    match result {
+        Ok(()) => send(ProgressEvent::Complete),
+        Err(ref e) if matches!(e.downcast_ref::<LxeError>(), Some(LxeError::AlreadyInstalled { .. })) => {
+            send(ProgressEvent::Complete);
+        }
+        Err(ref e) => send(ProgressEvent::Error { message: format!("{:#}", e) }),
+    }
+    result
+}
+
+fn silent_install(
+    args: &Args,
+    payload: &payload::PayloadInfo,
+    send: &(impl Fn(ProgressEvent) + Sync),
+) -> Result<()> {
 
     // Print installation banner
     println!("📦 LXE Silent Installer");
@@ -184,10 +270,32 @@ fn run_silent_install(
     println!("   Package: {} v{}", payload.metadata.name, payload.metadata.version);
     println!("   App ID:  {}", payload.metadata.app_id);
     println!();
+    
+    enforce_signature_policy(&payload.metadata, false)?;
+
+    let target_user = args.for_user.as_deref()
+        .map(TargetUser::lookup)
+        .transpose()?;
+    if let Some(ref user) = target_user {
+        // Before the runtime starts any thread; every path below now
+        // resolves inside the target user's home
+        user.switch_to()?;
+        println!("👤 Installing for user: {}", user.name);
+        println!();
+    }
 
     let rt = tokio::runtime::Runtime::new()?;
+    
+    if !args.force && args.install_dir.is_none() {
+        let installed = manifest::InstallManifest::load(&payload.metadata.app_id).ok().flatten();
+        if let Some(installed) = installed.filter(|m| m.version == payload.metadata.version && m.is_system == args.system) {
+            return Err(LxeError::AlreadyInstalled { version: installed.version }.into());
+        }
+    }
+    
     rt.block_on(async {
-        let install_path = args.install_dir.unwrap_or_else(|| {
+        let custom_prefix = args.install_dir.is_some();
+        let install_path = args.install_dir.clone().unwrap_or_else(|| {
             if args.system {
                 paths::system::base_dir()
             } else {
@@ -212,7 +320,20 @@ fn run_silent_install(
             }).unwrap_or(false)
         };
         
-        let result = installer::install_silent(&payload, &install_path, args.system).await;
+        let result = installer::install_silent(
+            payload,
+            &install_path,
+            args.system,
+            args.yes,
+            target_user.as_ref(),
+            &|progress| match progress {
+                installer::InstallProgress::Extracting { files, bytes, .. } => {
+                    send(ProgressEvent::Progress { files, bytes });
+                }
+                installer::InstallProgress::Warning { message } => eprintln!("⚠️  {}", message),
+                _ => {}
+            },
+        ).await;
         
         match &result {
             Ok(()) => {
@@ -221,8 +342,12 @@ fn run_silent_install(
                 println!();
                 println!("   Find '{}' in your application menu.", payload.metadata.name);
                 
+                if args.detect_wmclass {
+                    detect_window_hints(&payload.metadata, &install_path);
+                }
+                
                 // Only show terminal restart note if we configured PATH this session
-                if !path_already_configured && !args.system {
+                if !path_already_configured && !args.system && !custom_prefix {
                     println!();
                     println!("   💡 To run '{}' from terminal:", payload.metadata.exec);
                     println!("      Restart your terminal (or run: source ~/.zshrc)");
@@ -238,6 +363,37 @@ fn run_silent_install(
     })
 }
 
+/// Detect WM_CLASS / D-Bus activation of an installed app and patch its .desktop file
+fn detect_window_hints(metadata: &LxeMetadata, install_path: &std::path::Path) {
+    use lxe_common::window_hints;
+    
+    let exec = install_path.join("share").join(&metadata.app_id).join(&metadata.exec);
+    let desktop_file = install_path.join("share/applications").join(metadata.desktop_filename());
+    
+    println!();
+    println!("🪟 Detecting WM_CLASS (up to 5s)...");
+    
+    let result = window_hints::detect(&exec, &metadata.app_id, std::time::Duration::from_secs(5))
+        .and_then(|hints| {
+            window_hints::apply_to_desktop_file(&desktop_file, &hints)?;
+            Ok(hints)
+        });
+    
+    match result {
+        Ok(hints) => {
+            if let Some(class) = hints.wm_class {
+                println!("   StartupWMClass={}", class);
+            } else {
+                println!("   No window appeared - keeping StartupWMClass from package");
+            }
+            if hints.dbus_activatable {
+                println!("   DBusActivatable=true");
+            }
+        }
+        Err(e) => eprintln!("   ⚠️  Detection skipped: {}", e),
+    }
+}
+
 fn run_gui(
     args: Args,
     payload_info: Option<payload::PayloadInfo>,
@@ -270,6 +426,13 @@ fn run_gui(
     }
 
     info!("GTK4/Libadwaita initialized in {:?}", startup_time.elapsed());
+    
+    if let Some(ref payload) = payload_info {
+        if let Err(e) = enforce_signature_policy(&payload.metadata, true) {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    }
 
     // Create and run the application
     let app = ui::app::LxeApplication::new(payload_info, args.force);
@@ -280,6 +443,139 @@ fn run_gui(
     std::process::exit(exit_code.into());
 }
 
+/// Explain an unmet system requirement and exit
+fn report_requirement_error(err: &LxeError, silent: bool) -> Result<()> {
+    let details = "This package was built for a newer Linux system.\n\
+                   Please update your distribution or ask the publisher for a compatible build.";
+    
+    let has_display = std::env::var("DISPLAY").is_ok() || std::env::var("WAYLAND_DISPLAY").is_ok();
+    
+    if silent || !has_display || gtk::init().is_err() {
+        eprintln!("❌ {}", err);
+        eprintln!();
+        eprintln!("{}", details);
+        std::process::exit(1);
+    }
+    
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(gtk::MessageType::Error)
+        .buttons(gtk::ButtonsType::Close)
+        .title("Cannot Install")
+        .text(err.to_string())
+        .secondary_text(details)
+        .modal(true)
+        .build();
+    
+    run_dialog(&dialog);
+    std::process::exit(1);
+}
+
+/// Show a dialog and block until the user responds
+fn run_dialog(dialog: &gtk::MessageDialog) -> gtk::ResponseType {
+    use gtk::prelude::*;
+    
+    let response = std::rc::Rc::new(std::cell::Cell::new(None));
+    let response_clone = response.clone();
+    dialog.connect_response(move |dialog, r| {
+        dialog.close();
+        response_clone.set(Some(r));
+    });
+    
+    dialog.show();
+    
+    let main_context = glib::MainContext::default();
+    loop {
+        if let Some(r) = response.get() {
+            return r;
+        }
+        main_context.iteration(true);
+    }
+}
+
+/// Apply the user's signature policy to an unsigned package
+/// 
+/// `gui` selects a dialog instead of a terminal prompt (GTK must be initialized).
+fn enforce_signature_policy(metadata: &LxeMetadata, gui: bool) -> Result<()> {
+    if metadata.is_signed() {
+        return Ok(());
+    }
+    
+    // An unreadable config must not loosen the policy: fail closed
+    let mut config = GlobalConfig::load().unwrap_or_else(|e| {
+        tracing::warn!("Invalid global config, requiring signed packages: {:#}", e);
+        GlobalConfig { signature_policy: SignaturePolicy::RequireSigned, ..GlobalConfig::default() }
+    });
+    
+    match config.signature_policy {
+        SignaturePolicy::AllowAll => Ok(()),
+        SignaturePolicy::WarnUnsigned => {
+            tracing::warn!("Package {} is UNSIGNED - only install if you trust the source", metadata.app_id);
+            if !gui {
+                eprintln!("⚠️  Package is UNSIGNED");
+                eprintln!("   This package was not signed by the publisher.");
+                eprintln!("   Only install if you trust the source.");
+                eprintln!();
+            }
+            Ok(())
+        }
+        SignaturePolicy::RequireSigned | SignaturePolicy::TrustedOnly => {
+            let err = LxeError::SignatureRequired { app_id: metadata.app_id.clone() };
+            if gui {
+                let dialog = gtk::MessageDialog::builder()
+                    .message_type(gtk::MessageType::Error)
+                    .buttons(gtk::ButtonsType::Close)
+                    .title("Cannot Install")
+                    .text("Unsigned package")
+                    .secondary_text(err.to_string())
+                    .modal(true)
+                    .build();
+                run_dialog(&dialog);
+            }
+            Err(err.into())
+        }
+        SignaturePolicy::Tofu => {
+            if config.is_tofu_trusted(&metadata.app_id) {
+                return Ok(());
+            }
+            
+            let question = format!(
+                "This package is unsigned. Add {} to trusted unsigned packages?",
+                metadata.app_id
+            );
+            let accepted = if gui {
+                let dialog = gtk::MessageDialog::builder()
+                    .message_type(gtk::MessageType::Warning)
+                    .buttons(gtk::ButtonsType::YesNo)
+                    .title("Unsigned Package")
+                    .text(&question)
+                    .secondary_text("The publisher did not sign this package. You will not be asked again for this app.")
+                    .modal(true)
+                    .build();
+                run_dialog(&dialog) == gtk::ResponseType::Yes
+            } else {
+                use std::io::{BufRead, IsTerminal, Write};
+                if !std::io::stdin().is_terminal() {
+                    false
+                } else {
+                    print!("⚠️  {} [y/N] ", question);
+                    std::io::stdout().flush()?;
+                    let mut answer = String::new();
+                    std::io::stdin().lock().read_line(&mut answer)?;
+                    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+                }
+            };
+            
+            if !accepted {
+                anyhow::bail!("Installation cancelled: unsigned package was not trusted");
+            }
+            
+            config.add_tofu_trusted(&metadata.app_id);
+            config.save()?;
+            Ok(())
+        }
+    }
+}
+
 /// Uninstall an application by its app ID
 fn run_uninstall(app_id: &str, is_system: bool) -> Result<()> {
     println!("🗑️  LXE Uninstaller");
@@ -288,13 +584,9 @@ fn run_uninstall(app_id: &str, is_system: bool) -> Result<()> {
     // Check if app is installed via manifest
     let rt = tokio::runtime::Runtime::new()?;
     
-    let manifest = rt.block_on(manifest::InstallManifest::load(app_id))?;
+    let manifest = manifest::InstallManifest::load(app_id)?;
     
-    let config = if is_system {
-        installer::InstallConfig::system()
-    } else {
-        installer::InstallConfig::user_local()
-    };
+    let config = uninstall_config(manifest.as_ref(), is_system);
     
     match manifest {
         Some(m) => {
@@ -313,7 +605,7 @@ fn run_uninstall(app_id: &str, is_system: bool) -> Result<()> {
     rt.block_on(installer::uninstall(app_id, &config))?;
     
     // Remove manifest
-    rt.block_on(manifest::InstallManifest::delete(app_id))?;
+    manifest::InstallManifest::delete(app_id)?;
     
     println!();
     println!("✅ {} has been uninstalled.", app_id);
@@ -321,6 +613,15 @@ fn run_uninstall(app_id: &str, is_system: bool) -> Result<()> {
     Ok(())
 }
 
+/// Install target an app was installed to, from its manifest's prefix
+fn uninstall_config(manifest: Option<&manifest::InstallManifest>, is_system: bool) -> installer::InstallConfig {
+    match manifest.and_then(|m| m.prefix.clone()) {
+        Some(prefix) => installer::InstallConfig::with_prefix(prefix),
+        None if is_system => installer::InstallConfig::system(),
+        None => installer::InstallConfig::user_local(),
+    }
+}
+
 /// Uninstall with GUI - shows confirmation dialog then uninstalls
 /// Uninstall with GUI - shows confirmation dialog then uninstalls
 fn run_uninstall_gui(app_id: &str, is_system: bool) -> Result<()> {
@@ -329,8 +630,7 @@ fn run_uninstall_gui(app_id: &str, is_system: bool) -> Result<()> {
     info!("LXE Uninstaller GUI for {}", app_id);
     
     // Check if app is installed via manifest
-    let rt = tokio::runtime::Runtime::new()?;
-    let manifest = rt.block_on(manifest::InstallManifest::load(app_id))?;
+    let manifest = manifest::InstallManifest::load(app_id)?;
     
     // Use stored name from manifest, or fallback to app_id
     let app_name = manifest
@@ -400,11 +700,8 @@ fn run_uninstall_gui(app_id: &str, is_system: bool) -> Result<()> {
         if response == gtk::ResponseType::Accept {
             println!("🗑️  Uninstalling {}...", app_id_owned);
             
-            let config = if is_system_owned {
-                installer::InstallConfig::system()
-            } else {
-                installer::InstallConfig::user_local()
-            };
+            let manifest = manifest::InstallManifest::load(&app_id_owned).ok().flatten();
+            let config = uninstall_config(manifest.as_ref(), is_system_owned);
             
             let rt = tokio::runtime::Runtime::new().unwrap();
             
@@ -413,7 +710,7 @@ fn run_uninstall_gui(app_id: &str, is_system: bool) -> Result<()> {
                 eprintln!("Error uninstalling: {}", e);
             }
             // Remove manifest
-            let _ = rt.block_on(manifest::InstallManifest::delete(&app_id_owned));
+            let _ = manifest::InstallManifest::delete(&app_id_owned);
             
             println!("✅ {} has been uninstalled.", app_id_owned);
         }
@@ -437,8 +734,7 @@ fn list_installed_apps() -> Result<()> {
     println!("📦 Installed LXE Applications");
     println!();
     
-    let rt = tokio::runtime::Runtime::new()?;
-    let apps = rt.block_on(manifest::InstallManifest::list_installed())?;
+    let apps = manifest::InstallManifest::list_installed()?;
     
     if apps.is_empty() {
         println!("  (no applications installed via LXE)");
@@ -446,9 +742,14 @@ fn list_installed_apps() -> Result<()> {
     }
     
     for app_id in apps {
-        if let Some(manifest) = rt.block_on(manifest::InstallManifest::load(&app_id))? {
+        if let Some(manifest) = manifest::InstallManifest::load(&app_id)? {
             let location = if manifest.is_system { "system" } else { "user" };
             println!("  • {} v{} ({})", app_id, manifest.version, location);
+            if manifest.deprecated {
+                println!("    ⚠️  deprecated{}", manifest.deprecation_message
+                    .map(|m| format!(": {}", m))
+                    .unwrap_or_default());
+            }
         } else {
             println!("  • {} (manifest corrupted)", app_id);
         }
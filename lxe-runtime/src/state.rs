@@ -3,6 +3,7 @@
 //! Detects the current installation state and determines the wizard mode.
 //! This enables the "Maintenance Mode" for already-installed applications.
 
+use lxe_common::manifest::InstallManifest;
 use lxe_common::metadata::LxeMetadata;
 use anyhow::Result;
 use std::path::{Path, PathBuf};
@@ -173,6 +174,15 @@ fn check_installation(
     // Compare versions
     let install_path = exec_path.parent()?.parent()?.to_path_buf();
     
+    // Recorded files that are gone or changed size (sizes only, so the
+    // wizard starts quickly; `lxe verify-install` compares contents)
+    let damaged = InstallManifest::load(&metadata.app_id).ok().flatten()
+        .filter(|manifest| manifest.is_system == is_system)
+        .is_some_and(|manifest| !manifest.verify_files(false).is_intact());
+    if damaged {
+        return Some(InstallState::Corrupted { install_path, is_system });
+    }
+    
     match compare_versions(&desktop_info.version, &metadata.version) {
         std::cmp::Ordering::Equal => {
             Some(InstallState::Installed {
@@ -0,0 +1,95 @@
+//! Installing for Another User (`--for-user`)
+//!
+//! An administrator (root) installs into another user's home by becoming
+//! that user before anything is written: `$HOME` points at their home, so
+//! every XDG path resolves as if they ran the installer, and the process
+//! continues with their uid and gid.
+
+use anyhow::{Context, Result};
+use nix::unistd::{geteuid, initgroups, setgid, setuid, Gid, Uid, User};
+use std::ffi::CString;
+use std::path::PathBuf;
+
+/// Variables that would otherwise still point at the caller's directories
+/// or session bus
+const CALLER_VARS: &[&str] = &[
+    "XDG_DATA_HOME",
+    "XDG_CONFIG_HOME",
+    "XDG_CACHE_HOME",
+    "XDG_STATE_HOME",
+    "XDG_RUNTIME_DIR",
+    "DBUS_SESSION_BUS_ADDRESS",
+];
+
+/// The user an installation is performed for
+#[derive(Debug, Clone)]
+pub struct TargetUser {
+    pub name: String,
+    pub uid: Uid,
+    pub gid: Gid,
+    pub home: PathBuf,
+}
+
+impl TargetUser {
+    /// Look up `username` in the user database
+    pub fn lookup(username: &str) -> Result<Self> {
+        let user = User::from_name(username)
+            .with_context(|| format!("Failed to look up user '{}'", username))?
+            .ok_or_else(|| anyhow::anyhow!("No such user: {}", username))?;
+        
+        Ok(Self {
+            name: user.name,
+            uid: user.uid,
+            gid: user.gid,
+            home: user.dir,
+        })
+    }
+    
+    /// Become this user for the rest of the process: `$HOME` points at
+    /// their home, so every XDG path resolves as if they ran the installer,
+    /// and root privileges are dropped for good
+    /// 
+    /// Files are then created with the user's permissions, so a symlink the
+    /// user planted can't redirect a write to a file only root may change.
+    /// Requires root. Call before any thread is started (the tokio runtime
+    /// included): changing the environment isn't thread-safe.
+    pub fn switch_to(&self) -> Result<()> {
+        if !geteuid().is_root() {
+            anyhow::bail!(
+                "Installing for another user requires root.\n\
+                 Try running with: sudo {} --silent --for-user {}",
+                std::env::current_exe().unwrap_or_default().display(),
+                self.name
+            );
+        }
+        
+        std::env::set_var("HOME", &self.home);
+        std::env::set_var("USER", &self.name);
+        std::env::set_var("LOGNAME", &self.name);
+        for var in CALLER_VARS {
+            std::env::remove_var(var);
+        }
+        
+        // Groups first: only root may change them
+        let name = CString::new(self.name.as_str())?;
+        initgroups(&name, self.gid).context("Failed to set supplementary groups")?;
+        setgid(self.gid).context("Failed to switch group")?;
+        setuid(self.uid).context("Failed to switch user")?;
+        if setuid(Uid::from_raw(0)).is_ok() {
+            anyhow::bail!("Root privileges could not be dropped");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let root = TargetUser::lookup("root").unwrap();
+        assert!(root.uid.is_root());
+        assert!(TargetUser::lookup("no-such-user-lxe").is_err());
+    }
+}
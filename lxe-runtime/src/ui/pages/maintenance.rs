@@ -2,6 +2,7 @@
 //!
 //! Provides options to Uninstall, Repair, or Upgrade the application.
 
+use crate::installer::{check_linked_libs, unbundled_lib_warning, InstallConfig};
 use crate::payload::PayloadInfo;
 use crate::state::WizardMode;
 use adw::prelude::*;
@@ -90,14 +91,15 @@ impl MaintenancePage {
             .map(|p| p.metadata.name.clone())
             .unwrap_or_else(|| "Application".to_string());
         
-        let (current_version, can_upgrade, can_repair) = match mode {
+        let (current_version, can_upgrade, can_repair, is_system) = match mode {
             WizardMode::Maintenance {
                 current_version,
                 can_upgrade,
                 can_repair,
+                is_system,
                 ..
-            } => (current_version, can_upgrade, can_repair),
-            _ => ("unknown".to_string(), false, false),
+            } => (current_version, can_upgrade, can_repair, is_system),
+            _ => ("unknown".to_string(), false, false, false),
         };
         
         let new_version = payload
@@ -135,6 +137,27 @@ impl MaintenancePage {
         self.append(&title);
         self.append(&version_label);
         
+        // Libraries the installed app expects from the host
+        if let Some(ref payload) = *payload {
+            let config = if is_system { InstallConfig::system() } else { InstallConfig::user_local() };
+            let app_dir = config.app_dir(&payload.metadata.app_id);
+            let warnings: Vec<String> = check_linked_libs(&app_dir.join(&payload.metadata.exec), &app_dir)
+                .iter()
+                .map(|lib| unbundled_lib_warning(lib))
+                .collect();
+            if !warnings.is_empty() {
+                let warning_label = gtk::Label::builder()
+                    .label(warnings.join("\n"))
+                    .css_classes(["caption", "warning"])
+                    .wrap(true)
+                    .max_width_chars(60)
+                    .justify(gtk::Justification::Center)
+                    .margin_bottom(8)
+                    .build();
+                self.append(&warning_label);
+            }
+        }
+        
         // Action buttons in a preferences group style
         let actions_box = gtk::Box::builder()
             .orientation(gtk::Orientation::Vertical)
@@ -3,6 +3,10 @@
 //! ARCHITECTURE: Uses a separate OS thread for the Tokio runtime to avoid
 //! blocking the GTK main thread. Communication happens via std::sync::mpsc
 //! and glib::idle_add for thread-safe UI updates.
+//!
+//! The Cancel button cancels the worker's `CancellationToken`; extraction
+//! stops before its next file and removes what it wrote. Once extraction is
+//! done, the remaining steps are quick and the button is hidden.
 
 use crate::extractor::{self, ExtractProgress};
 use crate::installer::{self, InstallConfig};
@@ -11,10 +15,12 @@ use crate::polkit;
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 use gtk::glib;
+use lxe_common::error::LxeError;
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::mpsc;
 use std::thread;
+use tokio_util::sync::CancellationToken;
 
 /// Messages sent from worker thread to GTK main thread
 #[derive(Debug, Clone)]
@@ -25,8 +31,46 @@ pub enum ProgressMessage {
     InstallingDesktopEntry,
     /// All operations complete
     Complete,
+    /// Extraction was cancelled and its files removed
+    Cancelled,
     /// An error occurred
-    Error(String),
+    Error(ErrorReport),
+}
+
+/// An error as shown to the user: what went wrong and what to do about it
+#[derive(Debug, Clone)]
+pub struct ErrorReport {
+    pub title: String,
+    pub message: String,
+    /// Full error chain, for bug reports
+    pub details: String,
+}
+
+impl ErrorReport {
+    /// Tailored text for the failures users can do something about; anything
+    /// else shows the error chain
+    pub fn new(err: &anyhow::Error) -> Self {
+        const MB: u64 = 1024 * 1024;
+        let (title, message) = match err.downcast_ref::<LxeError>() {
+            Some(LxeError::InsufficientDiskSpace { needed, available }) => ("Not Enough Disk Space", format!(
+                "The app needs {} MB, but only {} MB are free. Free up some space and try again.",
+                needed.div_ceil(MB),
+                available / MB
+            )),
+            Some(LxeError::SignatureInvalid) => ("Package Signature Is Invalid",
+                "This package was changed after it was signed and may have been tampered with. \
+                 Only install it again from a source you trust.".to_string()),
+            Some(LxeError::ChecksumMismatch { .. }) | Some(LxeError::PayloadCorrupted(_)) => ("Package Is Damaged",
+                "The package is incomplete or damaged, probably from an interrupted download. \
+                 Download it again and retry.".to_string()),
+            Some(LxeError::AuthorizationDenied) => ("Authorization Denied",
+                "Installing for all users needs administrator rights.".to_string()),
+            Some(LxeError::AlreadyInstalled { version }) => ("Already Installed",
+                format!("Version {} is already installed.", version)),
+            _ => ("Installation Failed", format!("{:#}", err)),
+        };
+        Self { title: title.to_string(), message, details: format!("{:#}", err) }
+    }
 }
 
 mod imp {
@@ -39,6 +83,9 @@ mod imp {
         pub status_label: RefCell<Option<gtk::Label>>,
         pub file_label: RefCell<Option<gtk::Label>>,
         pub percent_label: RefCell<Option<gtk::Label>>,
+        pub cancel_button: RefCell<Option<gtk::Button>>,
+        /// Token of the running extraction
+        pub cancel_token: RefCell<Option<CancellationToken>>,
         pub is_uninstall: RefCell<bool>,
     }
 
@@ -63,8 +110,11 @@ mod imp {
                 vec![
                     glib::subclass::Signal::builder("extraction-complete")
                         .build(),
+                    // Title, message and full error chain of an `ErrorReport`
                     glib::subclass::Signal::builder("extraction-failed")
-                        .param_types([String::static_type()])
+                        .param_types([String::static_type(), String::static_type(), String::static_type()])
+                        .build(),
+                    glib::subclass::Signal::builder("extraction-cancelled")
                         .build(),
                 ]
             })
@@ -146,12 +196,35 @@ impl ProgressPage {
             .margin_top(4)
             .build();
         
+        // Cancel button, shown while files are extracted
+        let cancel_button = gtk::Button::builder()
+            .label("Cancel")
+            .css_classes(["pill"])
+            .halign(gtk::Align::Center)
+            .width_request(100)
+            .height_request(40)
+            .margin_top(16)
+            .visible(false)
+            .build();
+        cancel_button.connect_clicked(glib::clone!(
+            @weak self as page =>
+            move |button| {
+                if let Some(ref token) = *page.imp().cancel_token.borrow() {
+                    token.cancel();
+                }
+                button.set_sensitive(false);
+                page.set_status("Cancelling...");
+            }
+        ));
+        
         self.append(&spinner);
         self.append(&status_label);
         self.append(&progress_bar);
         self.append(&percent_label);
         self.append(&file_label);
+        self.append(&cancel_button);
         
+        *imp.cancel_button.borrow_mut() = Some(cancel_button);
         *imp.progress_bar.borrow_mut() = Some(progress_bar);
         *imp.status_label.borrow_mut() = Some(status_label);
         *imp.file_label.borrow_mut() = Some(file_label);
@@ -197,6 +270,10 @@ impl ProgressPage {
         // Create an std::sync::mpsc channel for cross-thread communication
         let (sender, receiver) = mpsc::channel::<ProgressMessage>();
         
+        let cancel = CancellationToken::new();
+        *self.imp().cancel_token.borrow_mut() = Some(cancel.clone());
+        self.show_cancel_button(true);
+        
         // Get installation config
         let config = if is_system {
             InstallConfig::system()
@@ -213,9 +290,8 @@ impl ProgressPage {
             let rt = match tokio::runtime::Runtime::new() {
                 Ok(rt) => rt,
                 Err(e) => {
-                    let _ = sender.send(ProgressMessage::Error(
-                        format!("Failed to initialize async runtime: {}", e)
-                    ));
+                    let err = anyhow::Error::from(e).context("Failed to initialize async runtime");
+                    let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&err)));
                     return;
                 }
             };
@@ -230,23 +306,28 @@ impl ProgressPage {
                                 tracing::info!("Polkit authorization granted");
                             }
                             Ok(false) => {
-                                let _ = sender.send(ProgressMessage::Error(
-                                    "Authorization denied. Cannot install system-wide.".to_string()
-                                ));
+                                let err = LxeError::AuthorizationDenied.into();
+                                let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&err)));
                                 return;
                             }
                             Err(e) => {
-                                let _ = sender.send(ProgressMessage::Error(
-                                    format!("Authorization failed: {}", e)
-                                ));
+                                let err = e.context("Authorization failed");
+                                let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&err)));
                                 return;
                             }
                         }
                     }
                 }
                 
+                let checked = installer::check_disk_space(&payload.metadata, &target_dir)
+                    .and_then(|()| installer::check_delta_base(&payload.metadata));
+                if let Err(e) = checked {
+                    let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&e)));
+                    return;
+                }
+                
                 // Start extraction
-                let (mut rx, handle) = extractor::extract_async(payload.clone(), target_dir.clone());
+                let (mut rx, handle) = extractor::extract_async(payload.clone(), target_dir.clone(), cancel);
                 
                 // Forward progress updates to GTK thread via channel
                 let sender_clone = sender.clone();
@@ -289,7 +370,7 @@ impl ProgressPage {
                         
                         // Create .desktop file
                         if let Err(e) = installer::create_desktop_entry(&payload.metadata, &config, &runtime_path).await {
-                            let _ = sender.send(ProgressMessage::Error(e.to_string()));
+                            let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&e)));
                             return;
                         }
                         
@@ -298,6 +379,9 @@ impl ProgressPage {
                             // Non-fatal - log and continue
                             tracing::warn!("Could not create bin symlink: {}", e);
                         }
+                        if let Err(e) = installer::install_extra_executables(&payload.metadata, &config, &runtime_path).await {
+                            tracing::warn!("Could not install extra executables: {}", e);
+                        }
                         
                         // Install icon
                         if payload.metadata.icon.is_some() {
@@ -308,11 +392,16 @@ impl ProgressPage {
                         
                         let _ = sender.send(ProgressMessage::Complete);
                     }
+                    // The extractor has already removed its temporary directory
+                    Ok(Err(e)) if matches!(e.downcast_ref::<LxeError>(), Some(LxeError::Cancelled)) => {
+                        let _ = sender.send(ProgressMessage::Cancelled);
+                    }
                     Ok(Err(e)) => {
-                        let _ = sender.send(ProgressMessage::Error(e.to_string()));
+                        let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&e)));
                     }
                     Err(e) => {
-                        let _ = sender.send(ProgressMessage::Error(format!("Task panicked: {}", e)));
+                        let err = anyhow::anyhow!("Task panicked: {}", e);
+                        let _ = sender.send(ProgressMessage::Error(ErrorReport::new(&err)));
                     }
                 }
             });
@@ -334,15 +423,26 @@ impl ProgressPage {
                             page.update_progress(&progress);
                         }
                         ProgressMessage::InstallingDesktopEntry => {
+                            page.show_cancel_button(false);
                             page.set_status("Installing shortcuts...");
                         }
                         ProgressMessage::Complete => {
+                            page.show_cancel_button(false);
                             page.emit_by_name::<()>("extraction-complete", &[]);
                             should_continue = false;
                             break;
                         }
-                        ProgressMessage::Error(err) => {
-                            page.emit_by_name::<()>("extraction-failed", &[&err]);
+                        ProgressMessage::Cancelled => {
+                            page.show_cancel_button(false);
+                            page.update_progress(&ExtractProgress::new(0));
+                            page.set_status("Installing...");
+                            page.emit_by_name::<()>("extraction-cancelled", &[]);
+                            should_continue = false;
+                            break;
+                        }
+                        ProgressMessage::Error(report) => {
+                            page.show_cancel_button(false);
+                            page.fail(&report);
                             should_continue = false;
                             break;
                         }
@@ -360,6 +460,41 @@ impl ProgressPage {
         });
     }
     
+    /// Show `report`'s title and emit `extraction-failed`; the wizard
+    /// shows the error dialog
+    fn fail(&self, report: &ErrorReport) {
+        self.set_status(&report.title);
+        self.emit_by_name::<()>("extraction-failed", &[&report.title, &report.message, &report.details]);
+    }
+    
+    /// Start over after a failed installation, re-reading the package from
+    /// disk in case it was replaced (e.g., downloaded again)
+    pub fn retry_installation(&self) {
+        let imp = self.imp();
+        let exe_path = imp.payload_info.borrow().as_ref().map(|p| p.exe_path.clone());
+        if let Some(exe_path) = exe_path {
+            match crate::payload::read_payload_info(&exe_path) {
+                Ok(payload) => *imp.payload_info.borrow_mut() = Some(payload),
+                Err(e) => {
+                    self.fail(&ErrorReport::new(&e.context("Failed to read the package again")));
+                    return;
+                }
+            }
+        }
+        self.update_progress(&ExtractProgress::new(0));
+        self.start_installation();
+    }
+    
+    fn show_cancel_button(&self, visible: bool) {
+        if let Some(ref button) = *self.imp().cancel_button.borrow() {
+            button.set_visible(visible);
+            button.set_sensitive(visible);
+        }
+        if !visible {
+            *self.imp().cancel_token.borrow_mut() = None;
+        }
+    }
+    
     fn set_status(&self, status: &str) {
         if let Some(ref label) = *self.imp().status_label.borrow() {
             label.set_label(status);
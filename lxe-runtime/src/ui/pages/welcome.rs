@@ -189,6 +189,20 @@ impl WelcomePage {
             }
         ));
         
+        // Deprecation and dependency warnings go above everything else
+        if let Some(ref info) = *payload {
+            if info.metadata.deprecated {
+                let text = match info.metadata.deprecation_message {
+                    Some(ref message) => format!("This version is deprecated: {}", message),
+                    None => "This version is deprecated by its publisher".to_string(),
+                };
+                self.append(&warning_banner(&text));
+            }
+            for warning in lxe_common::requirements::check_dependencies(&info.metadata) {
+                self.append(&warning_banner(&format!("Missing dependency {}", warning)));
+            }
+        }
+        
         // Add all widgets
         self.append(&icon);
         self.append(&title);
@@ -211,6 +225,30 @@ impl WelcomePage {
     }
 }
 
+/// Amber warning banner, e.g. that this version is deprecated
+/// 
+/// Styled to look like `adw::Banner`, which needs libadwaita 1.3
+/// (we only require 1.1).
+fn warning_banner(text: &str) -> gtk::Box {
+    let banner = gtk::Box::builder()
+        .orientation(gtk::Orientation::Horizontal)
+        .spacing(8)
+        .margin_bottom(8)
+        .css_classes(["deprecation-banner"])
+        .build();
+    
+    banner.append(&gtk::Image::from_icon_name("dialog-warning-symbolic"));
+    
+    banner.append(&gtk::Label::builder()
+        .label(text)
+        .wrap(true)
+        .max_width_chars(50)
+        .xalign(0.0)
+        .build());
+    
+    banner
+}
+
 impl Default for WelcomePage {
     fn default() -> Self {
         Self::new(None)
@@ -17,6 +17,7 @@ mod imp {
     #[derive(Default)]
     pub struct WizardStack {
         pub stack: RefCell<Option<gtk::Stack>>,
+        pub toast_overlay: RefCell<Option<adw::ToastOverlay>>,
         pub payload_info: RefCell<Option<PayloadInfo>>,
         pub wizard_mode: RefCell<WizardMode>,
         
@@ -97,6 +98,12 @@ impl WizardStack {
             .hexpand(true)
             .build();
         
+        // Toasts show over every page
+        let toast_overlay = adw::ToastOverlay::new();
+        toast_overlay.set_child(Some(&stack));
+        toast_overlay.set_vexpand(true);
+        *imp.toast_overlay.borrow_mut() = Some(toast_overlay.clone());
+        
         match wizard_mode {
             WizardMode::Install => {
                 self.setup_install_flow(&stack, payload_info);
@@ -106,7 +113,7 @@ impl WizardStack {
             }
         }
         
-        self.append(&stack);
+        self.append(&toast_overlay);
         
         *imp.stack.borrow_mut() = Some(stack);
     }
@@ -214,6 +221,28 @@ impl WizardStack {
             }),
         );
         
+        // Failed: offer to retry
+        progress_page.connect_local(
+            "extraction-failed",
+            false,
+            glib::clone!(@weak self as wizard, @weak progress_page => @default-return None, move |values: &[glib::Value]| {
+                let text = |i: usize| values[i].get::<String>().unwrap_or_default();
+                wizard.show_failure_dialog(&progress_page, &text(1), &text(2), &text(3));
+                None
+            }),
+        );
+        
+        // Cancelled: back to the start
+        progress_page.connect_local(
+            "extraction-cancelled",
+            false,
+            glib::clone!(@weak self as wizard, @weak stack, @weak welcome_page => @default-return None, move |_| {
+                stack.set_visible_child(&welcome_page);
+                wizard.show_toast("Installation cancelled.");
+                None
+            }),
+        );
+        
         *imp.license_page.borrow_mut() = license_page;
         
         // Store page references
@@ -276,6 +305,55 @@ impl WizardStack {
         *imp.complete_page.borrow_mut() = Some(complete_page);
     }
     
+    /// Error dialog for a failed installation: Retry starts over, Cancel
+    /// closes the window, Copy Error puts `details` on the clipboard
+    fn show_failure_dialog(&self, progress_page: &ProgressPage, title: &str, message: &str, details: &str) {
+        const RESPONSE_COPY: gtk::ResponseType = gtk::ResponseType::Other(1);
+        
+        let dialog = gtk::MessageDialog::builder()
+            .message_type(gtk::MessageType::Error)
+            .text(title)
+            .secondary_text(message)
+            .modal(true)
+            .build();
+        dialog.add_button("Copy Error", RESPONSE_COPY);
+        dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+        dialog.add_button("Retry", gtk::ResponseType::Accept);
+        if let Some(retry) = dialog.widget_for_response(gtk::ResponseType::Accept) {
+            retry.add_css_class("suggested-action");
+        }
+        dialog.set_default_response(gtk::ResponseType::Accept);
+        let window = self.root().and_downcast::<gtk::Window>();
+        dialog.set_transient_for(window.as_ref());
+        
+        let details = details.to_string();
+        dialog.connect_response(glib::clone!(@weak self as wizard, @weak progress_page => move |dialog, response| {
+            match response {
+                // Keep the dialog open to retry or cancel afterwards
+                RESPONSE_COPY => dialog.clipboard().set_text(&details),
+                gtk::ResponseType::Accept => {
+                    dialog.close();
+                    progress_page.retry_installation();
+                }
+                _ => {
+                    dialog.close();
+                    wizard.emit_by_name::<()>("installation-cancelled", &[]);
+                    if let Some(window) = wizard.root().and_downcast::<gtk::Window>() {
+                        window.close();
+                    }
+                }
+            }
+        }));
+        dialog.present();
+    }
+    
+    /// Show a short notification over the current page
+    pub fn show_toast(&self, message: &str) {
+        if let Some(ref overlay) = *self.imp().toast_overlay.borrow() {
+            overlay.add_toast(adw::Toast::new(message));
+        }
+    }
+    
     /// Navigate to a specific page by name
     pub fn go_to_page(&self, name: &str) {
         if let Some(ref stack) = *self.imp().stack.borrow() {